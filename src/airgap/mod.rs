@@ -0,0 +1,33 @@
+/// Air-gapped operation
+///
+/// When enabled (via `--air-gapped` or `PROMPTGUARD_AIR_GAPPED=1`), every
+/// command that needs the `PromptGuard` API or the internet is refused with
+/// guidance instead of timing out against an unreachable network. Local
+/// commands (scan, init, apply, revert, status, shims) keep working.
+use crate::error::{PromptGuardError, Result};
+use std::sync::OnceLock;
+
+/// Environment variable that enables air-gapped mode
+pub const AIR_GAPPED_ENV: &str = "PROMPTGUARD_AIR_GAPPED";
+
+static AIR_GAPPED: OnceLock<bool> = OnceLock::new();
+
+/// Initialize air-gapped mode (call once at startup)
+pub fn init(flag: bool) {
+    let from_env = std::env::var(AIR_GAPPED_ENV)
+        .is_ok_and(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes" | "on"));
+    let _ = AIR_GAPPED.set(flag || from_env);
+}
+
+/// Whether the CLI is running in air-gapped mode
+pub fn is_enabled() -> bool {
+    *AIR_GAPPED.get_or_init(|| false)
+}
+
+/// Fail with guidance if `feature` needs network access in air-gapped mode
+pub fn ensure_online(feature: &str) -> Result<()> {
+    if is_enabled() {
+        return Err(PromptGuardError::AirGapped(feature.to_string()));
+    }
+    Ok(())
+}
@@ -4,7 +4,7 @@
 /// Helps users understand what environment variables need to be configured.
 use crate::error::Result;
 use crate::scanner::is_skip_dir;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fmt::Write;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -325,7 +325,7 @@ impl EnvScanner {
         let typescript_usage = self.scan_typescript_env_usage()?;
 
         // Group by variable name
-        let mut var_map: HashMap<String, Vec<String>> = HashMap::new();
+        let mut var_map: BTreeMap<String, Vec<String>> = BTreeMap::new();
 
         for var in &env_vars {
             let entry = var_map.entry(var.name.clone()).or_default();
@@ -1,7 +1,10 @@
 /// Code analysis utilities
 ///
 /// This module provides analyzers for understanding how LLM SDKs are used
-/// in a codebase, including environment variable usage and data flow.
+/// in a codebase, including environment variable usage, data flow and
+/// hardcoded credentials.
 pub mod envscanner;
+pub mod secrets;
 
 pub use envscanner::EnvScanner;
+pub use secrets::{find_secrets, SecretKind};
@@ -0,0 +1,135 @@
+/// Hardcoded credential detection
+///
+/// Finds `PromptGuard` and LLM provider keys written inline in files, so they
+/// can be reported before they are committed or moved to an env file.
+use crate::detector::is_ignored;
+use regex::Regex;
+use serde::Serialize;
+use std::sync::OnceLock;
+
+/// Whose key a match is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretKind {
+    PromptGuard,
+    OpenAI,
+    Anthropic,
+    Groq,
+    HuggingFace,
+    Google,
+    Aws,
+}
+
+impl SecretKind {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::PromptGuard => "PromptGuard API key",
+            Self::OpenAI => "OpenAI API key",
+            Self::Anthropic => "Anthropic API key",
+            Self::Groq => "Groq API key",
+            Self::HuggingFace => "Hugging Face token",
+            Self::Google => "Google API key",
+            Self::Aws => "AWS access key ID",
+        }
+    }
+
+    /// Environment variable the provider's SDK reads this key from
+    pub fn env_var(self) -> &'static str {
+        match self {
+            Self::PromptGuard => "PROMPTGUARD_API_KEY",
+            Self::OpenAI => "OPENAI_API_KEY",
+            Self::Anthropic => "ANTHROPIC_API_KEY",
+            Self::Groq => "GROQ_API_KEY",
+            Self::HuggingFace => "HF_TOKEN",
+            Self::Google => "GOOGLE_API_KEY",
+            Self::Aws => "AWS_ACCESS_KEY_ID",
+        }
+    }
+}
+
+/// A key found in a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretMatch {
+    pub kind: SecretKind,
+    /// 1-based line and column
+    pub line: usize,
+    pub column: usize,
+    pub value: String,
+}
+
+/// Patterns by kind; a key must not continue a longer token, so each
+/// pattern is anchored on a non-key character or the start of the line
+fn patterns() -> &'static [(SecretKind, Regex)] {
+    static PATTERNS: OnceLock<Vec<(SecretKind, Regex)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            (
+                SecretKind::PromptGuard,
+                r"pg_sk_(?:test|prod)_[A-Za-z0-9]{16,}",
+            ),
+            (SecretKind::Anthropic, r"sk-ant-[A-Za-z0-9_-]{20,}"),
+            (
+                SecretKind::OpenAI,
+                r"sk-(?:proj-|svcacct-)?[A-Za-z0-9_-]{20,}",
+            ),
+            (SecretKind::Groq, r"gsk_[A-Za-z0-9]{20,}"),
+            (SecretKind::HuggingFace, r"hf_[A-Za-z0-9]{30,}"),
+            (SecretKind::Google, r"AIza[0-9A-Za-z_-]{35}"),
+            (SecretKind::Aws, r"AKIA[0-9A-Z]{16}"),
+        ]
+        .into_iter()
+        .filter_map(|(kind, pattern)| {
+            let anchored = format!(r"(?:^|[^A-Za-z0-9_-])({pattern})");
+            Some((kind, Regex::new(&anchored).ok()?))
+        })
+        .collect()
+    })
+}
+
+/// Every key in `content`, in order. Lines suppressed with
+/// `# promptguard: ignore` are skipped.
+pub fn find_secrets(content: &str) -> Vec<SecretMatch> {
+    let mut found = Vec::new();
+    for (row, line) in content.lines().enumerate() {
+        let mut on_line: Vec<SecretMatch> = Vec::new();
+        for (kind, regex) in patterns() {
+            for m in regex.captures_iter(line).filter_map(|c| c.get(1)) {
+                // `sk-ant-...` also matches the OpenAI pattern
+                if on_line.iter().any(|s| s.column == m.start() + 1) {
+                    continue;
+                }
+                on_line.push(SecretMatch {
+                    kind: *kind,
+                    line: row + 1,
+                    column: m.start() + 1,
+                    value: m.as_str().to_string(),
+                });
+            }
+        }
+        if on_line.is_empty() || is_ignored(content, row) {
+            continue;
+        }
+        on_line.sort_by_key(|s| s.column);
+        found.extend(on_line);
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_secrets() {
+        let content = "client = OpenAI(api_key=\"sk-proj-abcdefghijklmnopqrstuvwx\")\n\
+                       anthropic = Anthropic(api_key='sk-ant-REDACTED')\n\
+                       task-abcdefghijklmnopqrstuvwxyz = 1\n\
+                       PG = \"pg_sk_test_abcdefghijklmnop1234\"  # promptguard: ignore\n";
+        let found = find_secrets(content);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].kind, SecretKind::OpenAI);
+        assert_eq!((found[0].line, found[0].column), (1, 26));
+        assert_eq!(found[1].kind, SecretKind::Anthropic);
+        assert_eq!(found[1].value, "sk-ant-REDACTED");
+    }
+}
@@ -1,21 +1,35 @@
+use crate::config::{ApiSettings, ConfigManager};
 use crate::error::{PromptGuardError, QuotaExceededInfo, Result};
-use reqwest::blocking::Client;
+use crate::logging;
+use crate::output::Output;
+use aws_lc_rs::rand::{SecureRandom, SystemRandom};
+use futures_util::stream::{self, StreamExt};
+use reqwest::{Certificate, Client, ClientBuilder, Method};
 use serde::{Deserialize, Serialize};
-use std::thread;
-use std::time::Duration;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Once;
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
 
-/// Maximum number of retry attempts for transient failures
+/// Retries of a failed request, unless `api.max_retries` is set
 const MAX_RETRIES: u32 = 3;
 
 /// Base delay for exponential backoff (100ms)
 const RETRY_BASE_DELAY_MS: u64 = 100;
 
+/// Longest wait between two attempts
+const RETRY_MAX_DELAY_MS: u64 = 5_000;
+
 /// Connection timeout in seconds
 const CONNECT_TIMEOUT_SECS: u64 = 10;
 
-/// Request timeout in seconds
+/// Request timeout in seconds, unless `api.timeout_secs` is set
 const REQUEST_TIMEOUT_SECS: u64 = 30;
 
+/// Most requests a bulk operation keeps in flight at once
+const MAX_CONCURRENT_REQUESTS: usize = 8;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ErrorResponse {
     error: ErrorDetail,
@@ -33,85 +47,198 @@ struct ErrorDetail {
     requests_limit: Option<u64>,
 }
 
+/// Client for the `PromptGuard` API.
+///
+/// Requests are async and run on a runtime owned by the client. `get`, `post`
+/// and `put` block on a single request; bulk operations build futures with
+/// the `*_async` methods and drive them with `run_concurrent`.
 pub struct PromptGuardClient {
     client: Client,
+    runtime: Runtime,
+    max_retries: u32,
     base_url: String,
+    /// A `pg_sk_` API key, or an access token from `promptguard login`
     api_key: String,
 }
 
+/// Builder shared by every HTTP client of the CLI. Requests go through the
+/// proxy in `HTTPS_PROXY`/`HTTP_PROXY` (or `ALL_PROXY`) except for hosts in
+/// `NO_PROXY`, and trust the CAs in the project's `api.ca_bundle` besides the
+/// built-in roots.
+fn client_builder(settings: &ApiSettings, timeout: Duration) -> Result<ClientBuilder> {
+    static INSECURE_WARNING: Once = Once::new();
+
+    let mut builder = Client::builder()
+        .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS).min(timeout))
+        .timeout(timeout)
+        .user_agent(format!("promptguard-cli/{}", env!("CARGO_PKG_VERSION")));
+    if let Some(ref ca_bundle) = settings.ca_bundle {
+        builder = builder.tls_certs_merge(ca_certificates(ca_bundle)?);
+    }
+    if settings.tls_insecure == Some(true) {
+        INSECURE_WARNING.call_once(|| {
+            Output::warning(
+                "TLS certificate verification is off (api.tls_insecure); use it only in lab environments",
+            );
+        });
+        builder = builder.tls_danger_accept_invalid_certs(true);
+    }
+    Ok(builder)
+}
+
+/// A blocking client for requests outside `PromptGuardClient` (provider
+/// probes, the login device flow, release downloads), with the same proxy
+/// and TLS settings
+pub fn blocking_client(timeout: Duration) -> Result<reqwest::blocking::Client> {
+    reqwest::blocking::ClientBuilder::from(client_builder(&settings(), timeout)?)
+        .timeout(timeout)
+        .build()
+        .map_err(|e| PromptGuardError::Api(format!("Failed to build HTTP client: {e}")))
+}
+
+/// The project's `api` settings, or the defaults outside a project
+fn settings() -> ApiSettings {
+    ConfigManager::new(None)
+        .and_then(|manager| manager.load())
+        .map(|config| config.api)
+        .unwrap_or_default()
+}
+
+/// The certificates in the PEM file `ca_bundle`, relative to the project root
+fn ca_certificates(ca_bundle: &str) -> Result<Vec<Certificate>> {
+    let path = crate::config::project_root()?.join(Path::new(ca_bundle));
+    let pem = std::fs::read(&path).map_err(|e| {
+        PromptGuardError::Config(format!(
+            "Cannot read CA bundle {} (api.ca_bundle): {e}",
+            path.display()
+        ))
+    })?;
+    let certificates = Certificate::from_pem_bundle(&pem).unwrap_or_default();
+    if certificates.is_empty() {
+        return Err(PromptGuardError::Config(format!(
+            "CA bundle {} (api.ca_bundle) holds no PEM certificates",
+            path.display()
+        )));
+    }
+    logging::debug(format_args!(
+        "Trusting {} certificate(s) from {}",
+        certificates.len(),
+        path.display()
+    ));
+    Ok(certificates)
+}
+
 impl PromptGuardClient {
+    /// Requests use the proxy and TLS settings of `client_builder`
     pub fn new(api_key: String, base_url: Option<String>) -> Result<Self> {
-        let client = Client::builder()
-            .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
-            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        crate::airgap::ensure_online("The PromptGuard API")?;
+
+        let settings = settings();
+        let timeout_secs = settings.timeout_secs.unwrap_or(REQUEST_TIMEOUT_SECS);
+        let client = client_builder(&settings, Duration::from_secs(timeout_secs))?
             .build()
             .map_err(|e| PromptGuardError::Api(format!("Failed to build HTTP client: {e}")))?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| PromptGuardError::Api(format!("Failed to start HTTP runtime: {e}")))?;
 
         let base_url = base_url.unwrap_or_else(|| "https://api.promptguard.co/api/v1".to_string());
 
         Ok(Self {
             client,
+            runtime,
+            max_retries: settings.max_retries.unwrap_or(MAX_RETRIES),
             base_url: base_url.trim_end_matches('/').to_string(),
             api_key,
         })
     }
 
-    /// Check if an error is retryable (transient network issues, server errors)
-    fn is_retryable_error(error: &reqwest::Error) -> bool {
-        error.is_timeout() || error.is_connect() || error.is_request()
+    /// Check if an error is worth retrying. A request that never connected
+    /// can always be sent again; one that may have reached the server (a
+    /// timeout) only if repeating it is harmless.
+    fn is_retryable_error(method: &Method, error: &reqwest::Error) -> bool {
+        error.is_connect() || (method.is_idempotent() && (error.is_timeout() || error.is_request()))
     }
 
-    /// Check if an HTTP status code is retryable.
+    /// Check if an HTTP status code is retryable: server errors, for requests
+    /// that are safe to repeat.
     /// 429 is NOT retried here; quota-exceeded responses are handled
     /// separately to avoid wasting attempts on a hard limit.
-    fn is_retryable_status(status: reqwest::StatusCode) -> bool {
-        status == reqwest::StatusCode::BAD_GATEWAY
-            || status == reqwest::StatusCode::SERVICE_UNAVAILABLE
-            || status == reqwest::StatusCode::GATEWAY_TIMEOUT
+    fn is_retryable_status(method: &Method, status: reqwest::StatusCode) -> bool {
+        method.is_idempotent() && status.is_server_error()
+    }
+
+    /// Exponential backoff with jitter before retry `attempt` (1-based): a
+    /// random delay between half and all of 100ms, 200ms, 400ms, ... capped
+    /// at 5s, so clients retrying together don't hit the server in step
+    fn retry_delay(attempt: u32) -> Duration {
+        let ceiling = RETRY_BASE_DELAY_MS
+            .saturating_mul(1 << (attempt - 1).min(16))
+            .min(RETRY_MAX_DELAY_MS);
+        let mut random = [0u8; 8];
+        let jitter = SystemRandom::new()
+            .fill(&mut random)
+            .map_or(0, |()| u64::from_le_bytes(random) % (ceiling / 2 + 1));
+        Duration::from_millis(ceiling / 2 + jitter)
     }
 
-    #[allow(clippy::needless_pass_by_value)]
-    fn request<T: serde::de::DeserializeOwned>(
+    async fn request<T: serde::de::DeserializeOwned>(
         &self,
-        method: &reqwest::Method,
+        method: &Method,
         endpoint: &str,
         body: Option<serde_json::Value>,
     ) -> Result<T> {
         let url = format!("{}{}", self.base_url, endpoint);
         let mut last_error: Option<PromptGuardError> = None;
 
-        for attempt in 0..=MAX_RETRIES {
+        for attempt in 0..=self.max_retries {
             if attempt > 0 {
-                // Exponential backoff: 100ms, 200ms, 400ms
-                let delay_ms = RETRY_BASE_DELAY_MS * (1 << (attempt - 1));
-                thread::sleep(Duration::from_millis(delay_ms));
+                let delay = Self::retry_delay(attempt);
+                logging::debug(format_args!(
+                    "Retrying {method} {url} in {} ms (attempt {} of {})",
+                    delay.as_millis(),
+                    attempt + 1,
+                    self.max_retries + 1
+                ));
+                tokio::time::sleep(delay).await;
             }
 
-            let mut request = self
-                .client
-                .request(method.clone(), &url)
-                .header("X-API-Key", &self.api_key)
-                .header(
-                    "User-Agent",
-                    format!("promptguard-cli/{}", env!("CARGO_PKG_VERSION")),
-                );
+            let request = self.client.request(method.clone(), &url);
+            // Device-flow logins store an OAuth token instead of a key
+            let request = if self.api_key.is_empty() || self.api_key.starts_with("pg_sk_") {
+                request.header("X-API-Key", &self.api_key)
+            } else {
+                request.bearer_auth(&self.api_key)
+            };
+            let mut request = request.header(
+                "User-Agent",
+                format!("promptguard-cli/{}", env!("CARGO_PKG_VERSION")),
+            );
 
             if let Some(ref body) = body {
                 request = request.json(body);
             }
 
-            match request.send() {
+            logging::debug(format_args!("{method} {url}"));
+            let started = Instant::now();
+            let sent = request.send().await;
+            let elapsed_ms = started.elapsed().as_millis();
+            match sent {
                 Ok(response) => {
                     let status = response.status();
+                    logging::debug(format_args!(
+                        "{method} {url} -> {status} in {elapsed_ms} ms"
+                    ));
 
                     if status.is_success() {
-                        return response.json().map_err(|e| {
+                        return response.json().await.map_err(|e| {
                             PromptGuardError::Api(format!("Failed to parse response: {e}"))
                         });
                     }
 
                     // Check if we should retry this status code
-                    if Self::is_retryable_status(status) && attempt < MAX_RETRIES {
+                    if Self::is_retryable_status(method, status) && attempt < self.max_retries {
                         last_error = Some(PromptGuardError::Api(format!(
                             "Server returned {status}, retrying..."
                         )));
@@ -121,6 +248,7 @@ impl PromptGuardClient {
                     // Non-retryable error or out of retries
                     let error_text = response
                         .text()
+                        .await
                         .unwrap_or_else(|_| "Unknown error".to_string());
 
                     if let Ok(error_response) = serde_json::from_str::<ErrorResponse>(&error_text) {
@@ -154,7 +282,10 @@ impl PromptGuardClient {
                     )));
                 },
                 Err(e) => {
-                    if Self::is_retryable_error(&e) && attempt < MAX_RETRIES {
+                    logging::debug(format_args!(
+                        "{method} {url} failed after {elapsed_ms} ms: {e}"
+                    ));
+                    if Self::is_retryable_error(method, &e) && attempt < self.max_retries {
                         last_error = Some(PromptGuardError::Api(format!(
                             "Request failed: {e}, retrying..."
                         )));
@@ -171,16 +302,33 @@ impl PromptGuardClient {
         }))
     }
 
+    /// Run `tasks` on the client's runtime with at most
+    /// `MAX_CONCURRENT_REQUESTS` in flight, passing each output to
+    /// `on_result` in the order the tasks were given
+    pub fn run_concurrent<F: Future>(
+        &self,
+        tasks: impl IntoIterator<Item = F>,
+        mut on_result: impl FnMut(F::Output),
+    ) {
+        self.runtime.block_on(async {
+            let mut results = stream::iter(tasks).buffered(MAX_CONCURRENT_REQUESTS);
+            while let Some(result) = results.next().await {
+                on_result(result);
+            }
+        });
+    }
+
     // Health Check
 
     pub fn health_check(&self) -> Result<()> {
-        let _: serde_json::Value = self.request(&reqwest::Method::GET, "/health", None)?;
+        let _: serde_json::Value = self.get("/health")?;
 
         Ok(())
     }
 
     pub fn get<T: serde::de::DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
-        self.request(&reqwest::Method::GET, endpoint, None)
+        self.runtime
+            .block_on(self.request(&Method::GET, endpoint, None))
     }
 
     pub fn post<T: serde::de::DeserializeOwned, B: serde::Serialize>(
@@ -188,14 +336,7 @@ impl PromptGuardClient {
         endpoint: &str,
         body: &B,
     ) -> Result<T> {
-        self.request(
-            &reqwest::Method::POST,
-            endpoint,
-            Some(
-                serde_json::to_value(body)
-                    .map_err(|e| PromptGuardError::Api(format!("Failed to serialize body: {e}")))?,
-            ),
-        )
+        self.runtime.block_on(self.post_async(endpoint, body))
     }
 
     pub fn put<T: serde::de::DeserializeOwned, B: serde::Serialize>(
@@ -203,13 +344,22 @@ impl PromptGuardClient {
         endpoint: &str,
         body: &B,
     ) -> Result<T> {
-        self.request(
-            &reqwest::Method::PUT,
-            endpoint,
-            Some(
-                serde_json::to_value(body)
-                    .map_err(|e| PromptGuardError::Api(format!("Failed to serialize body: {e}")))?,
-            ),
-        )
+        self.runtime
+            .block_on(self.request(&Method::PUT, endpoint, Some(serialize(body)?)))
     }
+
+    /// `post` as a future, for `run_concurrent`
+    pub async fn post_async<T: serde::de::DeserializeOwned, B: serde::Serialize>(
+        &self,
+        endpoint: &str,
+        body: &B,
+    ) -> Result<T> {
+        self.request(&Method::POST, endpoint, Some(serialize(body)?))
+            .await
+    }
+}
+
+fn serialize<B: serde::Serialize>(body: &B) -> Result<serde_json::Value> {
+    serde_json::to_value(body)
+        .map_err(|e| PromptGuardError::Api(format!("Failed to serialize body: {e}")))
 }
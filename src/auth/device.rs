@@ -0,0 +1,150 @@
+//! OAuth 2.0 device authorization grant (RFC 8628) for `promptguard login`
+//!
+//! The CLI asks the API for a device code, the user approves it in the
+//! browser, and polling the token endpoint then yields an access token.
+
+use crate::error::{PromptGuardError, Result};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const CLIENT_ID: &str = "promptguard-cli";
+const DEVICE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Extra wait the server asks for with `slow_down`
+const SLOW_DOWN: Duration = Duration::from_secs(5);
+
+/// Answer to the device authorization request
+#[derive(Debug, Deserialize)]
+pub struct DeviceAuthorization {
+    pub device_code: String,
+    /// Code the user enters at `verification_uri`
+    pub user_code: String,
+    pub verification_uri: String,
+    /// `verification_uri` with the code filled in
+    #[serde(default)]
+    pub verification_uri_complete: Option<String>,
+    #[serde(default = "DeviceAuthorization::default_expires_in")]
+    pub expires_in: u64,
+    /// Seconds to wait between polls
+    #[serde(default = "DeviceAuthorization::default_interval")]
+    pub interval: u64,
+}
+
+impl DeviceAuthorization {
+    fn default_expires_in() -> u64 {
+        900
+    }
+
+    fn default_interval() -> u64 {
+        5
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccessToken {
+    pub access_token: String,
+    /// Lifetime in seconds; tokens without one don't expire
+    #[serde(default)]
+    pub expires_in: Option<u64>,
+}
+
+/// Error body of the token endpoint, e.g. `{"error": "authorization_pending"}`
+#[derive(Debug, Deserialize)]
+struct TokenError {
+    error: String,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+pub struct DeviceFlow {
+    client: Client,
+    base_url: String,
+}
+
+impl DeviceFlow {
+    pub fn new(base_url: &str) -> Result<Self> {
+        crate::airgap::ensure_online("promptguard login")?;
+        let client = crate::api::blocking_client(Duration::from_secs(30))?;
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Request a device code and the URL to approve it at
+    pub fn start(&self) -> Result<DeviceAuthorization> {
+        let response = self
+            .client
+            .post(format!("{}/auth/device/code", self.base_url))
+            .json(&serde_json::json!({ "client_id": CLIENT_ID }))
+            .send()
+            .map_err(|e| PromptGuardError::Api(format!("Request failed: {e}")))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(PromptGuardError::Api(format!(
+                "Device login is not available ({status}). Log in with 'promptguard login --api-key <key>' instead"
+            )));
+        }
+        response
+            .json()
+            .map_err(|e| PromptGuardError::Api(format!("Failed to parse device code: {e}")))
+    }
+
+    /// Poll the token endpoint until the code is approved, denied or expires
+    pub fn wait(&self, code: &DeviceAuthorization) -> Result<AccessToken> {
+        let deadline = Instant::now() + Duration::from_secs(code.expires_in);
+        let mut interval = Duration::from_secs(code.interval);
+        loop {
+            thread::sleep(interval);
+            if Instant::now() > deadline {
+                return Err(Self::expired());
+            }
+
+            let response = self
+                .client
+                .post(format!("{}/auth/device/token", self.base_url))
+                .json(&serde_json::json!({
+                    "client_id": CLIENT_ID,
+                    "device_code": code.device_code,
+                    "grant_type": DEVICE_GRANT,
+                }))
+                .send()
+                .map_err(|e| PromptGuardError::Api(format!("Request failed: {e}")))?;
+            let status = response.status();
+            let body = response.text().unwrap_or_default();
+            if status.is_success() {
+                return serde_json::from_str(&body).map_err(|e| {
+                    PromptGuardError::Api(format!("Failed to parse access token: {e}"))
+                });
+            }
+
+            let Ok(error) = serde_json::from_str::<TokenError>(&body) else {
+                return Err(PromptGuardError::Api(format!(
+                    "API error ({status}): {body}"
+                )));
+            };
+            match error.error.as_str() {
+                "authorization_pending" => {},
+                "slow_down" => interval += SLOW_DOWN,
+                "access_denied" => {
+                    return Err(PromptGuardError::Api(
+                        "Login was denied in the browser".to_string(),
+                    ));
+                },
+                "expired_token" => return Err(Self::expired()),
+                other => {
+                    return Err(PromptGuardError::Api(format!(
+                        "Login failed: {}",
+                        error.error_description.as_deref().unwrap_or(other)
+                    )));
+                },
+            }
+        }
+    }
+
+    fn expired() -> PromptGuardError {
+        PromptGuardError::Api("The login code expired. Run 'promptguard login' again".to_string())
+    }
+}
@@ -1,22 +1,46 @@
 use crate::error::{PromptGuardError, Result};
+use crate::state;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub mod device;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct GlobalCredentials {
+    /// Saved by `login --api-key`; empty after a device-flow login
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub api_key: String,
+    /// OAuth access token from `login`'s device flow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_expires_at: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub base_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub active_project: Option<String>,
 }
 
+impl GlobalCredentials {
+    /// The API key, else the access token while it hasn't expired
+    pub fn secret(&self) -> Option<&str> {
+        if !self.api_key.is_empty() {
+            return Some(&self.api_key);
+        }
+        self.access_token
+            .as_deref()
+            .filter(|_| !self.token_expired())
+    }
+
+    pub fn token_expired(&self) -> bool {
+        self.token_expires_at.is_some_and(|at| at <= Utc::now())
+    }
+}
+
 fn credentials_dir() -> Result<PathBuf> {
-    let home = std::env::var("HOME")
-        .or_else(|_| std::env::var("USERPROFILE"))
-        .map_err(|_| PromptGuardError::Config("Cannot determine home directory".to_string()))?;
-    Ok(PathBuf::from(home).join(".promptguard"))
+    state::global_state_dir()
 }
 
 fn credentials_path() -> Result<PathBuf> {
@@ -29,7 +53,7 @@ pub fn load_credentials() -> Result<Option<GlobalCredentials>> {
         return Ok(None);
     }
 
-    let content = fs::read_to_string(&path)?;
+    let content = state::read_state(&path)?;
     let creds: GlobalCredentials = serde_json::from_str(&content)
         .map_err(|e| PromptGuardError::Config(format!("Failed to parse credentials: {e}")))?;
     Ok(Some(creds))
@@ -43,7 +67,7 @@ pub fn save_credentials(creds: &GlobalCredentials) -> Result<()> {
     let content = serde_json::to_string_pretty(creds)
         .map_err(|e| PromptGuardError::Config(format!("Failed to serialize credentials: {e}")))?;
 
-    fs::write(&path, &content)?;
+    state::write_state(&path, &content)?;
 
     // Restrict permissions on Unix
     #[cfg(unix)]
@@ -64,7 +88,7 @@ pub fn delete_credentials() -> Result<()> {
     Ok(())
 }
 
-/// Resolve API key with precedence: env var > project-local > global credentials
+/// Resolve API key with precedence: env var > project-local > global credentials > user config
 pub fn resolve_api_key() -> Result<String> {
     // 1. Environment variable (highest priority)
     if let Ok(key) = std::env::var("PROMPTGUARD_API_KEY") {
@@ -77,23 +101,34 @@ pub fn resolve_api_key() -> Result<String> {
     let local_config = crate::config::ConfigManager::new(None);
     if let Ok(mgr) = local_config {
         if let Ok(cfg) = mgr.load() {
-            if !cfg.api_key.is_empty() {
-                return Ok(cfg.api_key);
+            if !cfg.api_key().is_empty() {
+                return Ok(cfg.api_key().to_string());
             }
         }
     }
 
     // 3. Global credentials (~/.promptguard/credentials.json)
-    if let Ok(Some(creds)) = load_credentials() {
-        return Ok(creds.api_key);
+    let creds = load_credentials().ok().flatten();
+    if let Some(secret) = creds.as_ref().and_then(GlobalCredentials::secret) {
+        return Ok(secret.to_string());
     }
 
+    // 4. User config (~/.config/promptguard/config.json)
+    if let Some(key) = crate::config::user::UserConfig::load_or_default().api_key {
+        return Ok(key);
+    }
+
+    if creds.is_some_and(|c| c.token_expired()) {
+        return Err(PromptGuardError::Config(
+            "Your login has expired. Run 'promptguard login' again".to_string(),
+        ));
+    }
     Err(PromptGuardError::Config(
         "No API key found. Run 'promptguard login' or set PROMPTGUARD_API_KEY".to_string(),
     ))
 }
 
-/// Resolve base URL with precedence: env var > project-local > global > default
+/// Resolve base URL with precedence: env var > project-local > global > user config > default
 pub fn resolve_base_url() -> String {
     if let Ok(url) = std::env::var("PROMPTGUARD_BASE_URL") {
         if !url.is_empty() {
@@ -103,7 +138,7 @@ pub fn resolve_base_url() -> String {
 
     if let Ok(mgr) = crate::config::ConfigManager::new(None) {
         if let Ok(cfg) = mgr.load() {
-            return cfg.proxy_url;
+            return cfg.proxy_url.default_url().to_string();
         }
     }
 
@@ -113,5 +148,5 @@ pub fn resolve_base_url() -> String {
         }
     }
 
-    "https://api.promptguard.co/api/v1".to_string()
+    crate::config::user::default_base_url()
 }
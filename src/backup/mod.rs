@@ -1,8 +1,232 @@
-use crate::error::Result;
+/// Backups of source files taken before `PromptGuard` rewrites them
+///
+/// Snapshots live under `.promptguard/backups/`, one timestamped copy per
+/// file and operation, described by `.promptguard/backups/manifest.json`
+/// (original path, SHA-256, timestamp, operation). Older versions wrote
+/// `*.bak` files next to each source instead; `BackupManager` still finds
+/// and restores those.
+use crate::error::{PromptGuardError, Result};
+use crate::state::{
+    decrypt_bytes, ensure_key, is_encrypted, project_state_dir, read_state, write_state,
+};
+use aws_lc_rs::digest::{digest, SHA256};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
 use std::fs;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+pub const BACKUPS_DIR_NAME: &str = "backups";
+
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupEntry {
+    /// Short id for `backup restore` and `backup diff`
+    pub id: String,
+    /// Original path relative to the project root
+    pub file: String,
+    /// Snapshot file name inside `.promptguard/backups/`
+    pub snapshot: String,
+    /// SHA-256 of the snapshot contents
+    pub sha256: String,
+    pub timestamp: DateTime<Utc>,
+    /// Command that took the snapshot, e.g. `apply`
+    pub operation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub version: u32,
+    /// Oldest first
+    pub entries: Vec<BackupEntry>,
+}
+
+impl Default for BackupManifest {
+    fn default() -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl BackupManifest {
+    /// Snapshots of `file` (relative to the root), oldest first
+    pub fn for_file<'a>(&'a self, file: &'a str) -> impl Iterator<Item = &'a BackupEntry> + 'a {
+        self.entries.iter().filter(move |e| e.file == file)
+    }
+
+    /// Files with at least one snapshot, sorted
+    pub fn files(&self) -> Vec<String> {
+        let mut files: Vec<String> = self.entries.iter().map(|e| e.file.clone()).collect();
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// A snapshot by id, or the newest snapshot of a file path
+    pub fn find(&self, target: &str) -> Option<&BackupEntry> {
+        let target = target.trim_start_matches("./").replace('\\', "/");
+        if let Some(entry) = self.entries.iter().find(|e| e.id == target) {
+            return Some(entry);
+        }
+        self.entries.iter().rev().find(|e| e.file == target)
+    }
+}
+
+/// The project's snapshot store
+pub struct BackupStore {
+    root: PathBuf,
+}
+
+impl BackupStore {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+        }
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        project_state_dir(&self.root).join(BACKUPS_DIR_NAME)
+    }
+
+    fn manifest_path(&self) -> PathBuf {
+        self.dir().join(MANIFEST_FILE_NAME)
+    }
+
+    /// Load the manifest, or an empty one if nothing was backed up yet
+    pub fn manifest(&self) -> Result<BackupManifest> {
+        let path = self.manifest_path();
+        if !path.exists() {
+            return Ok(BackupManifest::default());
+        }
+        serde_json::from_str(&read_state(&path)?).map_err(|e| {
+            PromptGuardError::Parse(format!("Invalid backup manifest {}: {e}", path.display()))
+        })
+    }
+
+    /// Persist the manifest. An empty manifest removes the store.
+    pub fn save(&self, manifest: &BackupManifest) -> Result<()> {
+        if manifest.entries.is_empty() {
+            if self.dir().exists() {
+                fs::remove_dir_all(self.dir())?;
+            }
+            return Ok(());
+        }
+        write_state(
+            &self.manifest_path(),
+            &serde_json::to_string_pretty(manifest)?,
+        )
+    }
+
+    /// Snapshot `files` before `operation` changes them. A file whose newest
+    /// snapshot already matches its contents isn't copied again.
+    pub fn snapshot(&self, files: &[PathBuf], operation: &str) -> Result<Vec<BackupEntry>> {
+        let mut manifest = self.manifest()?;
+        let timestamp = Utc::now();
+        let mut taken = Vec::new();
+        for path in files {
+            let data = fs::read(path)?;
+            let file = self.relative(path);
+            let sha256 = sha256_hex(&data);
+            if manifest
+                .for_file(&file)
+                .last()
+                .is_some_and(|e| e.sha256 == sha256 && self.dir().join(&e.snapshot).is_file())
+            {
+                continue;
+            }
+
+            let id = sha256_hex(format!("{file}\n{}", timestamp.to_rfc3339()).as_bytes())[..8]
+                .to_string();
+            let snapshot = format!(
+                "{}-{id}-{}",
+                timestamp.format("%Y%m%dT%H%M%SZ"),
+                file.replace('/', "__")
+            );
+            fs::create_dir_all(self.dir())?;
+            fs::write(self.dir().join(&snapshot), &data)?;
+            let entry = BackupEntry {
+                id,
+                file,
+                snapshot,
+                sha256,
+                timestamp,
+                operation: operation.to_string(),
+            };
+            manifest.entries.push(entry.clone());
+            taken.push(entry);
+        }
+        self.save(&manifest)?;
+        Ok(taken)
+    }
+
+    /// Contents of a snapshot, checked against the manifest hash
+    pub fn read(&self, entry: &BackupEntry) -> Result<Vec<u8>> {
+        let mut data = fs::read(self.dir().join(&entry.snapshot))?;
+        // `state encrypt` seals snapshots without a source extension
+        if is_encrypted(&data) {
+            data = decrypt_bytes(&ensure_key()?, &data)?;
+        }
+        if sha256_hex(&data) != entry.sha256 {
+            return Err(PromptGuardError::Custom(format!(
+                "Backup {} of {} is corrupt (checksum mismatch)",
+                entry.id, entry.file
+            )));
+        }
+        Ok(data)
+    }
+
+    /// Write a snapshot back over its original file
+    pub fn restore(&self, entry: &BackupEntry) -> Result<PathBuf> {
+        let data = self.read(entry)?;
+        let path = self.root.join(&entry.file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, data)?;
+        Ok(path)
+    }
+
+    /// Delete the snapshots `remove` picks. Returns the removed entries.
+    pub fn prune(&self, remove: impl Fn(&BackupEntry) -> bool) -> Result<Vec<BackupEntry>> {
+        let mut manifest = self.manifest()?;
+        let (removed, kept): (Vec<BackupEntry>, Vec<BackupEntry>) =
+            manifest.entries.into_iter().partition(|e| remove(e));
+        for entry in &removed {
+            let path = self.dir().join(&entry.snapshot);
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        manifest.entries = kept;
+        self.save(&manifest)?;
+        Ok(removed)
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    digest(&SHA256, data)
+        .as_ref()
+        .iter()
+        .fold(String::new(), |mut hex, b| {
+            let _ = write!(hex, "{b:02x}");
+            hex
+        })
+}
+
+/// Legacy `*.bak` files written next to sources by older versions
 pub struct BackupManager {
     backup_extension: String,
 }
@@ -32,15 +256,6 @@ impl BackupManager {
         backup
     }
 
-    pub fn create_backup(&self, file_path: &Path) -> Result<PathBuf> {
-        let backup_path = self.backup_path(file_path);
-        // CRITICAL: Never overwrite existing backups - they contain the original state
-        if !backup_path.exists() {
-            fs::copy(file_path, &backup_path)?;
-        }
-        Ok(backup_path)
-    }
-
     pub fn restore_backup(&self, file_path: &Path) -> Result<()> {
         let backup_path = self.backup_path(file_path);
         if backup_path.exists() {
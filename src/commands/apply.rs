@@ -1,18 +1,26 @@
-use crate::backup::BackupManager;
-use crate::config::ConfigManager;
+use crate::backup::BackupStore;
+use crate::config::{ConfigManager, PromptGuardConfig};
 use crate::detector::detect_all_providers;
 use crate::error::{PromptGuardError, Result};
-use crate::output::Output;
+use crate::output::{Output, Progress};
 use crate::scanner::FileScanner;
-use crate::transformer;
-use crate::types::Provider;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use crate::transformer::{self, Journal, StagedChanges};
+use crate::types::{Provider, StaleBaseUrl};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 pub struct ApplyCommand {
     pub yes: bool,
+    /// Print a diff of the planned changes without writing anything
+    pub dry_run: bool,
+    /// Globs limiting which files are transformed (all files when empty)
+    pub files: Vec<String>,
+    pub json: bool,
 }
 
+/// A constructor whose literal base URL isn't the configured proxy URL
+type Stale = (Provider, PathBuf, StaleBaseUrl);
+
 impl ApplyCommand {
     pub fn execute(&self) -> Result<()> {
         Output::header("Apply Configuration");
@@ -23,19 +31,25 @@ impl ApplyCommand {
         }
 
         let config = config_manager.load()?;
+        let root_path = crate::config::project_root()?;
 
-        println!("\nThis will re-apply PromptGuard transformations to:");
-        println!("  • Proxy URL: {}", config.proxy_url);
-        println!("  • Providers: {}", config.providers.join(", "));
+        if !self.json {
+            Output::line("\nThis will re-apply PromptGuard transformations to:");
+            Output::line(&format!("  • Proxy URL: {}", config.proxy_url));
+            Output::line(&format!("  • Providers: {}", config.providers.join(", ")));
+            if !self.files.is_empty() {
+                Output::line(&format!("  • Files: {}", self.files.join(", ")));
+            }
+        }
 
-        if !self.yes && !Output::confirm("Proceed?", true)? {
+        if !self.yes && !self.dry_run && !Output::confirm("Proceed?", true)? {
             return Ok(());
         }
 
         Output::section("Scanning files...", "📁");
 
-        let root_path = std::env::current_dir()?;
-        let scanner = FileScanner::new(&root_path, Some(config.exclude_patterns.clone()))?;
+        let scanner = FileScanner::new(&root_path, Some(config.exclude_patterns.clone()))?
+            .with_include_patterns(&self.files)?;
         let files = scanner.scan_files(None)?;
 
         Output::step(&format!("Scanning {} files...", files.len()));
@@ -47,12 +61,15 @@ impl ApplyCommand {
             .filter_map(|p| Provider::parse(p))
             .collect();
 
-        let mut detection_results: HashMap<Provider, Vec<PathBuf>> = HashMap::new();
+        let mut detection_results: BTreeMap<Provider, Vec<PathBuf>> = BTreeMap::new();
 
+        let mut progress = Progress::new("Scanning", files.len(), self.json);
         for file_path in &files {
+            progress.inc_file(file_path.strip_prefix(&root_path).unwrap_or(file_path));
             if let Ok(results) = detect_all_providers(file_path) {
                 for (provider, result) in results {
                     if providers_to_check.contains(&provider) && !result.instances.is_empty() {
+                        progress.found(result.instances.len());
                         detection_results
                             .entry(provider)
                             .or_default()
@@ -61,21 +78,23 @@ impl ApplyCommand {
                 }
             }
         }
+        progress.finish();
 
         if detection_results.is_empty() {
             Output::warning("No SDK instances found to transform.");
-            return Ok(());
+            return self.finish(&root_path, &[], &[], &[], &[]);
         }
 
-        Output::section("Applying transformations...", "🔧");
+        if self.dry_run {
+            return self.preview(&detection_results, &root_path, &config);
+        }
 
-        let backup_manager = if config.backup_enabled {
-            Some(BackupManager::new(Some(config.backup_extension.clone())))
-        } else {
-            None
-        };
+        Output::section("Applying transformations...", "🔧");
 
-        let mut files_modified = 0;
+        // Every edit is staged in memory and written together at the end, so
+        // a failure part-way through leaves the project untouched
+        let mut staged = StagedChanges::new();
+        let mut dynamic_calls = Vec::new();
 
         for (provider, files) in &detection_results {
             let mut unique_files = files.clone();
@@ -83,24 +102,242 @@ impl ApplyCommand {
             unique_files.dedup();
 
             for file_path in unique_files {
-                // Create backup BEFORE transformation
-                if let Some(ref bm) = backup_manager {
-                    let _ = bm.create_backup(&file_path);
+                dynamic_calls.extend(Self::dynamic_calls(&file_path, &root_path, *provider));
+                staged
+                    .transform(
+                        &file_path,
+                        *provider,
+                        config.proxy_url.for_provider(*provider),
+                        config.env_var_for(*provider),
+                    )
+                    .map_err(|e| {
+                        let rel_path = file_path.strip_prefix(&root_path).unwrap_or(&file_path);
+                        PromptGuardError::Custom(format!(
+                            "Failed to transform {}: {e}. No files were changed",
+                            rel_path.display()
+                        ))
+                    })?;
+            }
+        }
+
+        let mut stale = Self::stale_base_urls(&mut staged, &detection_results, &root_path, &config);
+        if !stale.is_empty() {
+            if !self.json {
+                Output::line("");
+            }
+            Self::print_stale(&stale, &root_path, &config);
+            if self.yes || Output::confirm("Update them to the configured proxy URL?", true)? {
+                let mut stale_files: Vec<(Provider, &PathBuf)> =
+                    stale.iter().map(|(p, f, _)| (*p, f)).collect();
+                stale_files.dedup();
+                for (provider, file_path) in stale_files {
+                    staged.retarget(
+                        file_path,
+                        provider,
+                        config.proxy_url.for_provider(provider),
+                    )?;
+                }
+                stale.clear();
+            }
+        }
+
+        let changed: Vec<PathBuf> = staged
+            .changed_files()
+            .into_iter()
+            .map(Path::to_path_buf)
+            .collect();
+        if config.backup_enabled {
+            BackupStore::new(&root_path).snapshot(&changed, "apply")?;
+        }
+
+        let committed = staged.commit()?;
+        let mut journal = Journal::load(&root_path)?;
+        for (file_path, provider, edits) in &committed {
+            journal.record(&root_path, file_path, *provider, edits);
+        }
+        journal.save(&root_path)?;
+
+        for file_path in &changed {
+            let rel_path = file_path.strip_prefix(&root_path).unwrap_or(file_path);
+            Output::step(&format!("✓ {}", rel_path.display()));
+        }
+        Self::warn_dynamic_config(&dynamic_calls);
+
+        if !self.json {
+            Output::line("");
+            Output::success("Configuration applied!");
+            Output::line(&format!("\n  • {} files modified", changed.len()));
+        }
+
+        self.finish(&root_path, &changed, &[], &dynamic_calls, &stale)
+    }
+
+    /// Print the `--json` result: the files changed (or that would change
+    /// with --dry-run, with their diffs)
+    fn finish(
+        &self,
+        root_path: &Path,
+        files: &[PathBuf],
+        diffs: &[(Provider, String, String)],
+        dynamic_calls: &[String],
+        stale: &[Stale],
+    ) -> Result<()> {
+        if !self.json {
+            return Ok(());
+        }
+        let relative = |path: &PathBuf| {
+            path.strip_prefix(root_path)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+        Output::json(serde_json::json!({
+            "dry_run": self.dry_run,
+            "files_modified": files.iter().map(relative).collect::<Vec<_>>(),
+            "diffs": diffs.iter().map(|(provider, file, diff)| serde_json::json!({
+                "file": file,
+                "provider": provider.as_str(),
+                "diff": diff,
+            })).collect::<Vec<_>>(),
+            "dynamic_config": dynamic_calls,
+            "stale_base_urls": stale.iter().map(|(provider, file, s)| serde_json::json!({
+                "file": relative(file),
+                "line": s.line,
+                "provider": provider.as_str(),
+                "url": s.url,
+            })).collect::<Vec<_>>(),
+        }))
+    }
+
+    /// `file:line (Provider)` for each constructor in `file_path` configured
+    /// from `**kwargs` or spread options, which transforms leave untouched
+    pub fn dynamic_calls(file_path: &Path, root_path: &Path, provider: Provider) -> Vec<String> {
+        let rel_path = file_path.strip_prefix(root_path).unwrap_or(file_path);
+        transformer::dynamic_config_lines(file_path, provider)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|line| {
+                format!(
+                    "{}:{line} ({})",
+                    rel_path.display(),
+                    provider.display_name()
+                )
+            })
+            .collect()
+    }
+
+    /// Constructors in the detected files whose literal base URL differs
+    /// from the configured proxy URL, e.g. after `proxy_url` was changed
+    fn stale_base_urls(
+        staged: &mut StagedChanges,
+        detection_results: &BTreeMap<Provider, Vec<PathBuf>>,
+        root_path: &Path,
+        config: &PromptGuardConfig,
+    ) -> Vec<Stale> {
+        let mut stale = Vec::new();
+        for (provider, files) in detection_results {
+            let mut unique_files = files.clone();
+            unique_files.sort();
+            unique_files.dedup();
+            for file_path in unique_files {
+                let proxy_url = config.proxy_url.for_provider(*provider);
+                match staged.stale_base_urls(&file_path, *provider, proxy_url) {
+                    Ok(found) => {
+                        stale.extend(found.into_iter().map(|s| (*provider, file_path.clone(), s)));
+                    },
+                    Err(e) => Output::warning(&format!(
+                        "Failed to check base URLs in {}: {}",
+                        file_path
+                            .strip_prefix(root_path)
+                            .unwrap_or(&file_path)
+                            .display(),
+                        e
+                    )),
                 }
+            }
+        }
+        stale
+    }
+
+    fn print_stale(stale: &[Stale], root_path: &Path, config: &PromptGuardConfig) {
+        Output::warning(&format!(
+            "{} client constructor(s) use a different base URL than the configured proxy:",
+            stale.len()
+        ));
+        if Output::is_json() {
+            return;
+        }
+        for (provider, file_path, s) in stale {
+            let rel_path = file_path.strip_prefix(root_path).unwrap_or(file_path);
+            Output::line(&format!(
+                "  • {}:{} ({}): {} → {}",
+                rel_path.display(),
+                s.line,
+                provider.display_name(),
+                s.url,
+                config.proxy_url.for_provider(*provider)
+            ));
+        }
+    }
 
-                match transformer::transform_file(
+    /// Point constructors that static mode skipped at runtime mode
+    pub fn warn_dynamic_config(calls: &[String]) {
+        if calls.is_empty() {
+            return;
+        }
+        if !Output::is_json() {
+            Output::line("");
+        }
+        Output::warning(&format!(
+            "{} client constructor(s) use **kwargs, spread, or non-literal options and were left unchanged:",
+            calls.len()
+        ));
+        if Output::is_json() {
+            return;
+        }
+        for call in calls {
+            Output::line(&format!("  • {call}"));
+        }
+        Output::line("  Those options may already set a base URL or live in another file, so they can't be rewritten safely.");
+        Output::line(
+            "  Route them through PromptGuard with runtime mode: promptguard enable --runtime",
+        );
+    }
+
+    fn preview(
+        &self,
+        detection_results: &BTreeMap<Provider, Vec<PathBuf>>,
+        root_path: &Path,
+        config: &PromptGuardConfig,
+    ) -> Result<()> {
+        Output::section("Preview (dry run - no changes will be made):", "🔧");
+
+        let mut files_changed = Vec::new();
+        let mut diffs = Vec::new();
+        let mut dynamic_calls = Vec::new();
+        for (provider, files) in detection_results {
+            let mut unique_files = files.clone();
+            unique_files.sort();
+            unique_files.dedup();
+
+            for file_path in unique_files {
+                dynamic_calls.extend(Self::dynamic_calls(&file_path, root_path, *provider));
+                let rel_path = file_path.strip_prefix(root_path).unwrap_or(&file_path);
+                match transformer::preview_diff(
                     &file_path,
+                    &rel_path.display().to_string(),
                     *provider,
-                    &config.proxy_url,
-                    &config.env_var_name,
+                    config.proxy_url.for_provider(*provider),
+                    config.env_var_for(*provider),
                 ) {
-                    Ok(result) => {
-                        if result.modified {
-                            files_modified += 1;
-                            let rel_path = file_path.strip_prefix(&root_path).unwrap_or(&file_path);
-                            Output::step(&format!("✓ {}", rel_path.display()));
+                    Ok(Some(diff)) => {
+                        if !self.json {
+                            Output::diff(&diff);
                         }
+                        diffs.push((*provider, rel_path.display().to_string(), diff));
+                        files_changed.push(file_path.clone());
                     },
+                    Ok(None) => {},
                     Err(e) => {
                         Output::warning(&format!(
                             "Failed to transform {}: {}",
@@ -112,10 +349,29 @@ impl ApplyCommand {
             }
         }
 
-        println!();
-        Output::success("Configuration applied!");
-        println!("\n  • {files_modified} files modified");
+        Self::warn_dynamic_config(&dynamic_calls);
+
+        let stale = Self::stale_base_urls(
+            &mut StagedChanges::new(),
+            detection_results,
+            root_path,
+            config,
+        );
+        if !self.json {
+            if !stale.is_empty() {
+                Output::line("");
+                Self::print_stale(&stale, root_path, config);
+                Output::line("  `promptguard apply` will offer to update them");
+            }
+
+            Output::line("");
+            Output::line(&format!(
+                "✓ {} files would be modified",
+                files_changed.len()
+            ));
+            Output::line("\nTo apply: promptguard apply");
+        }
 
-        Ok(())
+        self.finish(root_path, &files_changed, &diffs, &dynamic_calls, &stale)
     }
 }
@@ -0,0 +1,386 @@
+//! Audit Command - LLM usage inventory
+//!
+//! Lists every SDK client in the project with its provider, location,
+//! client class, the model names referenced near it, and whether it is
+//! routed through `PromptGuard` (see `coverage`). Exported as JSON or CSV,
+//! it serves as an AI bill of materials for compliance reviews.
+
+use crate::commands::coverage::{self, CallSite, Guard};
+use crate::config::ConfigManager;
+use crate::detector::registry::ProviderInfo;
+use crate::error::{PromptGuardError, Result};
+use crate::output::Output;
+use crate::types::{Language, Provider};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// `model=`/`model:` style arguments and assignments
+const MODEL_ARG_PATTERN: &str =
+    r#"\b(?:model|model_id|modelId|model_name|modelName)\s*[=:]\s*["'`]([^"'`\s]{1,100})["'`]"#;
+
+/// String literals shaped like a provider's model ids
+const MODEL_LITERAL_PATTERN: &str = r#"["'`]((?:gpt-|chatgpt-|o[134](?:-|["'`])|text-embedding-|dall-e-|whisper-|claude-|command(?:-|["'`])|embed-|rerank-|gemini-|text-bison|llama|meta-llama/|mixtral-|mistral|gemma|anthropic\.|amazon\.|meta\.|cohere\.|ai21\.)[A-Za-z0-9._:/-]*)"#;
+
+fn model_patterns() -> &'static [Regex] {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [MODEL_ARG_PATTERN, MODEL_LITERAL_PATTERN]
+            .iter()
+            .filter_map(|p| Regex::new(p).ok())
+            .collect()
+    })
+}
+
+/// Model names referenced on each 1-based line of `source`
+fn models_by_line(source: &str) -> BTreeMap<usize, BTreeSet<String>> {
+    let mut models: BTreeMap<usize, BTreeSet<String>> = BTreeMap::new();
+    for (i, line) in source.lines().enumerate() {
+        for pattern in model_patterns() {
+            for caps in pattern.captures_iter(line) {
+                if let Some(m) = caps.get(1) {
+                    let name = m.as_str().trim_end_matches(['"', '\'', '`']);
+                    if !name.is_empty() {
+                        models.entry(i + 1).or_default().insert(name.to_string());
+                    }
+                }
+            }
+        }
+    }
+    models
+}
+
+/// Constructor named at `column` of `line`: the dotted identifier called
+/// there (`OpenAI`, `anthropic.Anthropic`, `boto3.client`), or the registry
+/// class for module-level configuration
+fn client_class(line: &str, column: usize, provider: Provider, language: Language) -> String {
+    let rest: String = line
+        .chars()
+        .skip(column.saturating_sub(1))
+        .collect::<String>();
+    let rest = rest.strip_prefix("new ").unwrap_or(&rest);
+    let callee: String = rest
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '.')
+        .collect();
+    if !callee.is_empty() && rest[callee.len()..].trim_start().starts_with('(') {
+        return callee;
+    }
+    let info = ProviderInfo::get(provider);
+    let class = match language {
+        Language::Python => info.py_class_name,
+        Language::TypeScript | Language::JavaScript => info.ts_class_name,
+    };
+    if class.is_empty() {
+        provider.as_str().to_string()
+    } else {
+        class.to_string()
+    }
+}
+
+/// One inventory entry
+#[derive(Debug, Serialize)]
+pub struct AuditEntry {
+    pub provider: Provider,
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub language: &'static str,
+    pub client: String,
+    pub models: Vec<String>,
+    pub guarded: bool,
+    pub guard: Option<Guard>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gap: Option<String>,
+}
+
+/// Names assigned a model literal (`MODEL = "gpt-4o"`, `const model = ...`),
+/// with the models they hold
+fn model_constants<'a>(
+    lines: &[&str],
+    models: &'a BTreeMap<usize, BTreeSet<String>>,
+) -> Vec<(Regex, &'a BTreeSet<String>)> {
+    static ASSIGNMENT: OnceLock<Option<Regex>> = OnceLock::new();
+    let Some(assignment) = ASSIGNMENT
+        .get_or_init(|| {
+            Regex::new(
+                r"^\s*(?:export\s+)?(?:const\s+|let\s+|var\s+)?([A-Za-z_]\w*)\s*(?::[^=]+)?=[^=]",
+            )
+            .ok()
+        })
+        .as_ref()
+    else {
+        return Vec::new();
+    };
+    models
+        .iter()
+        .filter_map(|(&line, names)| {
+            let name = assignment.captures(lines.get(line - 1)?)?.get(1)?.as_str();
+            let usage = Regex::new(&format!(r"\b{}\b", regex::escape(name))).ok()?;
+            Some((usage, names))
+        })
+        .collect()
+}
+
+/// Inventory entries for `sites`, which are sorted by file and line. Each
+/// call site owns the lines up to the next one in its file, and the models
+/// of the constants used there.
+pub fn inventory(root_path: &Path, sites: Vec<CallSite>) -> Vec<AuditEntry> {
+    let mut by_file: BTreeMap<String, Vec<CallSite>> = BTreeMap::new();
+    for site in sites {
+        by_file.entry(site.file.clone()).or_default().push(site);
+    }
+
+    let mut entries = Vec::new();
+    for (file, sites) in by_file {
+        let source = std::fs::read_to_string(root_path.join(&file)).unwrap_or_default();
+        let lines: Vec<&str> = source.lines().collect();
+        let models = models_by_line(&source);
+        let constants = model_constants(&lines, &models);
+        let language = Path::new(&file)
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(Language::from_extension)
+            .unwrap_or(Language::Python);
+
+        for (i, site) in sites.iter().enumerate() {
+            let next_line = sites
+                .iter()
+                .skip(i + 1)
+                .map(|s| s.line)
+                .find(|&l| l > site.line)
+                .unwrap_or(usize::MAX);
+            let region = lines
+                .get(site.line.saturating_sub(1)..next_line.saturating_sub(1).min(lines.len()))
+                .unwrap_or_default()
+                .join("\n");
+            let mut site_models: BTreeSet<&String> = models
+                .range(site.line..next_line)
+                .flat_map(|(_, names)| names)
+                .collect();
+            for (usage, names) in &constants {
+                if usage.is_match(&region) {
+                    site_models.extend(names.iter());
+                }
+            }
+            let line = lines
+                .get(site.line.saturating_sub(1))
+                .copied()
+                .unwrap_or("");
+
+            entries.push(AuditEntry {
+                provider: site.provider,
+                file: site.file.clone(),
+                line: site.line,
+                column: site.column,
+                language: language.as_str(),
+                client: client_class(line, site.column, site.provider, language),
+                models: site_models.into_iter().cloned().collect(),
+                guarded: site.guard.is_some(),
+                guard: site.guard,
+                gap: site.gap.clone(),
+            });
+        }
+    }
+    entries
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_csv(entries: &[AuditEntry]) -> String {
+    let mut csv =
+        String::from("provider,file,line,column,language,client,models,guarded,guard,gap\n");
+    for entry in entries {
+        let row = [
+            entry.provider.as_str().to_string(),
+            entry.file.clone(),
+            entry.line.to_string(),
+            entry.column.to_string(),
+            entry.language.to_string(),
+            entry.client.clone(),
+            entry.models.join(";"),
+            entry.guarded.to_string(),
+            entry
+                .guard
+                .map(Guard::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            entry.gap.clone().unwrap_or_default(),
+        ];
+        csv.push_str(
+            &row.iter()
+                .map(|f| csv_field(f))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        csv.push('\n');
+    }
+    csv
+}
+
+#[derive(Debug, Serialize)]
+struct ProviderSummary {
+    provider: &'static str,
+    call_sites: usize,
+    guarded: usize,
+    files: BTreeSet<String>,
+    models: BTreeSet<String>,
+}
+
+pub struct AuditCommand {
+    /// human, json or csv
+    pub format: String,
+    /// File to write; stdout when unset
+    pub output: Option<PathBuf>,
+}
+
+impl AuditCommand {
+    pub fn execute(&self) -> Result<()> {
+        if !matches!(self.format.as_str(), "human" | "json" | "csv") {
+            return Err(PromptGuardError::Custom(format!(
+                "Unknown audit format '{}'. Supported: human, json, csv",
+                self.format
+            )));
+        }
+        let config_manager = ConfigManager::new(None)?;
+        if !config_manager.exists() {
+            return Err(PromptGuardError::NotInitialized);
+        }
+        let config = config_manager.load()?;
+        let root_path = crate::config::project_root()?;
+
+        let show_progress = self.format == "human" && self.output.is_none();
+        if show_progress {
+            Output::header("PromptGuard LLM Usage Audit");
+        }
+        let (sites, ignored) = coverage::call_sites(&root_path, &config, show_progress)?;
+        let percent = coverage::percent(&sites);
+        let entries = inventory(&root_path, sites);
+
+        let mut providers: BTreeMap<Provider, ProviderSummary> = BTreeMap::new();
+        for entry in &entries {
+            let summary = providers
+                .entry(entry.provider)
+                .or_insert_with(|| ProviderSummary {
+                    provider: entry.provider.as_str(),
+                    call_sites: 0,
+                    guarded: 0,
+                    files: BTreeSet::new(),
+                    models: BTreeSet::new(),
+                });
+            summary.call_sites += 1;
+            summary.guarded += usize::from(entry.guarded);
+            summary.files.insert(entry.file.clone());
+            summary.models.extend(entry.models.iter().cloned());
+        }
+
+        let content = match self.format.as_str() {
+            "csv" => to_csv(&entries),
+            "json" => {
+                let guarded = entries.iter().filter(|e| e.guarded).count();
+                let report = serde_json::json!({
+                    "generated_at": chrono::Utc::now().to_rfc3339(),
+                    "project": root_path.file_name().map(|n| n.to_string_lossy().to_string()),
+                    "cli_version": env!("CARGO_PKG_VERSION"),
+                    "summary": {
+                        "call_sites": entries.len(),
+                        "guarded": guarded,
+                        "unguarded": entries.len() - guarded,
+                        "ignored": ignored,
+                        "coverage_percent": (percent * 10.0).round() / 10.0,
+                    },
+                    "providers": providers.values().collect::<Vec<_>>(),
+                    "call_sites": entries,
+                });
+                format!("{}\n", serde_json::to_string_pretty(&report)?)
+            },
+            _ => {
+                Self::print_human(&entries, &providers, ignored, percent);
+                return Ok(());
+            },
+        };
+
+        if let Some(path) = &self.output {
+            std::fs::write(path, content)?;
+            Output::success(&format!(
+                "Wrote {} call sites to {}",
+                entries.len(),
+                path.display()
+            ));
+        } else {
+            print!("{content}");
+        }
+        Ok(())
+    }
+
+    fn print_human(
+        entries: &[AuditEntry],
+        providers: &BTreeMap<Provider, ProviderSummary>,
+        ignored: usize,
+        percent: f64,
+    ) {
+        Output::line("");
+        if entries.is_empty() {
+            Output::info("No LLM SDK call sites found");
+            return;
+        }
+        let guarded = entries.iter().filter(|e| e.guarded).count();
+        Output::line(&format!(
+            "  {} call sites across {} providers, {guarded} guarded ({percent:.1}%)",
+            entries.len(),
+            providers.len()
+        ));
+        if ignored > 0 {
+            Output::line(&format!("  Ignored: {ignored} (promptguard: ignore)"));
+        }
+
+        for summary in providers.values() {
+            Output::section(
+                &format!(
+                    "{} ({} of {} guarded)",
+                    summary.provider, summary.guarded, summary.call_sites
+                ),
+                "🤖",
+            );
+            if !summary.models.is_empty() {
+                Output::line(&format!(
+                    "  Models: {}",
+                    summary
+                        .models
+                        .iter()
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+            for entry in entries
+                .iter()
+                .filter(|e| e.provider.as_str() == summary.provider)
+            {
+                let status = entry.guard.map_or_else(
+                    || format!("unguarded: {}", entry.gap.as_deref().unwrap_or_default()),
+                    |g| g.as_str().to_string(),
+                );
+                let models = if entry.models.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", entry.models.join(", "))
+                };
+                Output::line(&format!(
+                    "  • {}:{} {}{models} - {status}",
+                    entry.file, entry.line, entry.client
+                ));
+            }
+        }
+        Output::line("\n  Export with --format json or --format csv (-o FILE)");
+    }
+}
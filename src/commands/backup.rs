@@ -0,0 +1,175 @@
+use crate::backup::{BackupEntry, BackupStore};
+use crate::commands::logs::parse_since;
+use crate::error::{PromptGuardError, Result};
+use crate::output::Output;
+use crate::transformer::unified_diff;
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+pub enum BackupAction {
+    List,
+    /// Restore a snapshot by id, or the newest snapshot of a file
+    Restore {
+        target: String,
+        yes: bool,
+    },
+    /// Delete snapshots beyond the newest `keep` per file, or older than
+    /// `older_than`
+    Prune {
+        keep: usize,
+        older_than: Option<String>,
+    },
+    /// Show what changed in a file since a snapshot
+    Diff {
+        target: String,
+    },
+}
+
+pub struct BackupCommand {
+    pub action: BackupAction,
+    pub json: bool,
+}
+
+impl BackupCommand {
+    pub fn execute(&self) -> Result<()> {
+        let root_path = crate::config::project_root()?;
+        let store = BackupStore::new(&root_path);
+        match &self.action {
+            BackupAction::List => self.list(&store),
+            BackupAction::Restore { target, yes } => {
+                Self::restore(&store, &root_path, target, *yes)
+            },
+            BackupAction::Prune { keep, older_than } => {
+                self.prune(&store, *keep, older_than.as_deref())
+            },
+            BackupAction::Diff { target } => Self::diff(&store, &root_path, target),
+        }
+    }
+
+    fn find(store: &BackupStore, target: &str) -> Result<BackupEntry> {
+        store.manifest()?.find(target).cloned().ok_or_else(|| {
+            PromptGuardError::Config(format!(
+                "No backup matches '{target}'. Run 'promptguard backup list' to see them"
+            ))
+        })
+    }
+
+    fn print_entries(entries: &[BackupEntry]) {
+        let width = entries
+            .iter()
+            .map(|e| e.operation.chars().count())
+            .max()
+            .unwrap_or(0);
+        for entry in entries {
+            Output::line(&format!(
+                "  {}  {}  {:<width$}  {}",
+                entry.id,
+                entry.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                entry.operation,
+                entry.file
+            ));
+        }
+    }
+
+    fn list(&self, store: &BackupStore) -> Result<()> {
+        let manifest = store.manifest()?;
+        if self.json {
+            Output::json(serde_json::to_value(&manifest.entries)?)?;
+            return Ok(());
+        }
+
+        Output::header("Backups");
+        if manifest.entries.is_empty() {
+            Output::info(
+                "No backups yet. 'promptguard apply' snapshots files before changing them",
+            );
+            return Ok(());
+        }
+        Self::print_entries(&manifest.entries);
+        Output::line("");
+        Output::info(&format!(
+            "{} snapshots of {} files in .promptguard/backups/",
+            manifest.entries.len(),
+            manifest.files().len()
+        ));
+        Ok(())
+    }
+
+    fn restore(store: &BackupStore, root_path: &Path, target: &str, yes: bool) -> Result<()> {
+        let entry = Self::find(store, target)?;
+        Output::header("Restore Backup");
+        Output::line(&format!(
+            "\nThis will overwrite {} with backup {} ({}, before '{}').",
+            entry.file,
+            entry.id,
+            entry.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+            entry.operation
+        ));
+        if !yes && !Output::confirm("Restore this backup?", true)? {
+            Output::info("Restore cancelled");
+            return Ok(());
+        }
+
+        // The overwritten contents get a snapshot too, so a restore can be undone
+        let path = root_path.join(&entry.file);
+        if path.is_file() {
+            store.snapshot(std::slice::from_ref(&path), "restore")?;
+        }
+        store.restore(&entry)?;
+        Output::success(&format!("Restored {} from backup {}", entry.file, entry.id));
+        Ok(())
+    }
+
+    fn prune(&self, store: &BackupStore, keep: usize, older_than: Option<&str>) -> Result<()> {
+        let cutoff = older_than
+            .map(|since| parse_since(since, Utc::now()))
+            .transpose()?;
+        let manifest = store.manifest()?;
+
+        // Rank each file's snapshots newest first
+        let mut seen: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut expired: Vec<String> = Vec::new();
+        for entry in manifest.entries.iter().rev() {
+            let rank = seen.entry(&entry.file).or_default();
+            *rank += 1;
+            if *rank > keep || cutoff.is_some_and(|cutoff| entry.timestamp < cutoff) {
+                expired.push(entry.id.clone());
+            }
+        }
+        let removed = store.prune(|e| expired.contains(&e.id))?;
+
+        if self.json {
+            Output::json(serde_json::to_value(&removed)?)?;
+            return Ok(());
+        }
+        Output::header("Prune Backups");
+        if removed.is_empty() {
+            Output::info("Nothing to prune");
+            return Ok(());
+        }
+        Self::print_entries(&removed);
+        Output::line("");
+        Output::success(&format!("Removed {} snapshots", removed.len()));
+        Ok(())
+    }
+
+    fn diff(store: &BackupStore, root_path: &Path, target: &str) -> Result<()> {
+        let entry = Self::find(store, target)?;
+        let backup = String::from_utf8_lossy(&store.read(&entry)?).into_owned();
+        let current = fs::read_to_string(root_path.join(&entry.file)).unwrap_or_default();
+        let diff = unified_diff(
+            &backup,
+            &current,
+            &format!("a/{} (backup {})", entry.file, entry.id),
+            &format!("b/{}", entry.file),
+        );
+        if diff.is_empty() {
+            Output::info(&format!("{} matches backup {}", entry.file, entry.id));
+        } else {
+            print!("{diff}");
+        }
+        Ok(())
+    }
+}
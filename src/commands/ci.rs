@@ -0,0 +1,115 @@
+//! CI Command - Routing check for pipelines
+//!
+//! Runs the coverage check without prompts or colors and prints each
+//! unguarded call site as a GitHub Actions `::error` workflow command, so
+//! findings show up inline on the pull request. Exit codes follow the CI
+//! contract in [`PromptGuardError::ci_exit_code`]: 0 clean, 2 findings,
+//! 3 configuration error.
+
+use crate::commands::coverage;
+use crate::config::ConfigManager;
+use crate::error::{PromptGuardError, Result};
+use crate::output::sarif::{self, Rule};
+use crate::output::Output;
+
+pub struct CiCommand;
+
+/// Escape workflow command data: `%` and line breaks
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a workflow command property, which also ends at `,` or `:`
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// `::error file=...,line=...,col=...,title=...::message`
+fn annotation(
+    rule: Rule,
+    file: &str,
+    line: Option<usize>,
+    column: Option<usize>,
+    message: &str,
+) -> String {
+    let mut properties = vec![format!("file={}", escape_property(file))];
+    if let Some(line) = line {
+        properties.push(format!("line={line}"));
+        if let Some(column) = column {
+            properties.push(format!("col={column}"));
+        }
+    }
+    properties.push(format!(
+        "title={}",
+        escape_property(&format!("{} {}", rule.id(), rule.description()))
+    ));
+    format!("::error {}::{}", properties.join(","), escape_data(message))
+}
+
+impl CiCommand {
+    pub fn execute(&self) -> Result<()> {
+        let root_path = crate::config::project_root()?;
+        let config_manager = ConfigManager::new(None)?;
+        let config = config_manager.load().map_err(|e| match e {
+            PromptGuardError::Config(_) | PromptGuardError::NotInitialized => e,
+            other => PromptGuardError::Config(other.to_string()),
+        })?;
+
+        // Annotations need paths from the repository root
+        let prefix = sarif::repo_prefix(&root_path);
+        let (sites, ignored) = coverage::call_sites(&root_path, &config, false)?;
+        let mut findings = 0;
+        for site in &sites {
+            let Some(gap) = &site.gap else {
+                continue;
+            };
+            let rule = if site.gateway.is_some() {
+                Rule::ConflictingGateway
+            } else {
+                Rule::UnguardedSdkCall
+            };
+            println!(
+                "{}",
+                annotation(
+                    rule,
+                    &format!("{prefix}{}", site.file),
+                    Some(site.line),
+                    Some(site.column),
+                    &format!("{} client: {gap}", site.provider.display_name()),
+                )
+            );
+            findings += 1;
+        }
+        for file in &config.metadata.files_managed {
+            if !root_path.join(file).is_file() {
+                println!(
+                    "{}",
+                    annotation(
+                        Rule::MissingManagedFile,
+                        &format!("{prefix}{file}"),
+                        None,
+                        None,
+                        "Managed file no longer exists; run 'promptguard apply' again",
+                    )
+                );
+                findings += 1;
+            }
+        }
+
+        let guarded = sites.iter().filter(|s| s.gap.is_none()).count();
+        Output::line(&format!(
+            "PromptGuard: {guarded}/{} call sites routed, {ignored} ignored, {findings} finding{}",
+            sites.len(),
+            if findings == 1 { "" } else { "s" }
+        ));
+        if findings > 0 {
+            return Err(PromptGuardError::Findings(format!(
+                "{findings} PromptGuard finding{} (see the annotations above)",
+                if findings == 1 { "" } else { "s" }
+            )));
+        }
+        Ok(())
+    }
+}
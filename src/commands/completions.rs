@@ -0,0 +1,441 @@
+//! Completions Command - Shell completion scripts
+//!
+//! Walks the clap command tree, so every subcommand and flag is completed
+//! without a hand-maintained list. Arguments with a fixed set of values
+//! complete those values, and path arguments complete file names.
+
+use crate::error::Result;
+use std::fmt::Write;
+
+/// What an argument's value completes to
+enum Values {
+    Fixed(Vec<String>),
+    Files,
+    /// Free-form text; nothing to offer
+    Any,
+}
+
+impl Values {
+    fn of(arg: &clap::Arg) -> Self {
+        let fixed: Vec<String> = arg
+            .get_possible_values()
+            .iter()
+            .filter(|v| !v.is_hide_set())
+            .map(|v| v.get_name().to_string())
+            .collect();
+        if !fixed.is_empty() {
+            return Self::Fixed(fixed);
+        }
+        match arg.get_value_hint() {
+            clap::ValueHint::AnyPath | clap::ValueHint::FilePath | clap::ValueHint::DirPath => {
+                Self::Files
+            },
+            _ => Self::Any,
+        }
+    }
+
+    fn fixed(&self) -> &[String] {
+        match self {
+            Self::Fixed(values) => values,
+            Self::Files | Self::Any => &[],
+        }
+    }
+}
+
+/// A flag and the values it completes
+struct Flag {
+    longs: Vec<String>,
+    short: Option<char>,
+    help: String,
+    /// `None` for switches
+    values: Option<Values>,
+}
+
+impl Flag {
+    fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.longs.iter().map(|l| format!("--{l}")).collect();
+        names.extend(self.short.map(|s| format!("-{s}")));
+        names
+    }
+}
+
+/// One command in the tree with what can follow it
+struct Node {
+    /// Subcommand names from the root, not including the binary
+    path: Vec<String>,
+    subcommands: Vec<(String, String)>,
+    flags: Vec<Flag>,
+    /// Values of the first positional argument, if there is one
+    positionals: Option<Values>,
+}
+
+impl Node {
+    fn id(&self, bin: &str, separator: &str) -> String {
+        std::iter::once(bin)
+            .chain(self.path.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    /// Every word offered after this command: subcommands, then flags
+    fn words(&self) -> Vec<String> {
+        let mut words: Vec<String> = self.subcommands.iter().map(|(n, _)| n.clone()).collect();
+        words.extend(self.positionals.iter().flat_map(Values::fixed).cloned());
+        words.extend(self.flags.iter().flat_map(Flag::names));
+        words
+    }
+}
+
+fn help_text(styled: Option<&clap::builder::StyledStr>) -> String {
+    styled
+        .map(|s| s.to_string().lines().next().unwrap_or_default().to_string())
+        .unwrap_or_default()
+}
+
+fn collect(command: &clap::Command, path: &[String], nodes: &mut Vec<Node>) {
+    let mut flags = Vec::new();
+    let mut positionals = None;
+    for arg in command.get_arguments().filter(|a| !a.is_hide_set()) {
+        if arg.is_positional() {
+            positionals.get_or_insert_with(|| Values::of(arg));
+            continue;
+        }
+        let values = arg.get_action().takes_values().then(|| Values::of(arg));
+        let mut longs: Vec<String> = arg.get_long().map(str::to_string).into_iter().collect();
+        longs.extend(
+            arg.get_visible_aliases()
+                .unwrap_or_default()
+                .into_iter()
+                .map(str::to_string),
+        );
+        flags.push(Flag {
+            longs,
+            short: arg.get_short(),
+            help: help_text(arg.get_help()),
+            values,
+        });
+    }
+
+    let subcommands: Vec<&clap::Command> = command
+        .get_subcommands()
+        .filter(|c| !c.is_hide_set())
+        .collect();
+    nodes.push(Node {
+        path: path.to_vec(),
+        subcommands: subcommands
+            .iter()
+            .map(|c| (c.get_name().to_string(), help_text(c.get_about())))
+            .collect(),
+        flags,
+        positionals,
+    });
+    for sub in subcommands {
+        let mut sub_path = path.to_vec();
+        sub_path.push(sub.get_name().to_string());
+        collect(sub, &sub_path, nodes);
+    }
+}
+
+fn bash(bin: &str, nodes: &[Node]) -> String {
+    let function = format!("_{}", bin.replace('-', "_"));
+    let mut out = format!(
+        "{function}() {{\n    local cur prev path i\n    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"\n    \
+         prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"\n    path=\"{bin}\"\n\n    \
+         for ((i = 1; i < COMP_CWORD; i++)); do\n        case \"${{path}}__${{COMP_WORDS[i]}}\" in\n"
+    );
+    let subpaths: Vec<String> = nodes
+        .iter()
+        .filter(|n| !n.path.is_empty())
+        .map(|n| n.id(bin, "__"))
+        .collect();
+    if !subpaths.is_empty() {
+        let _ = writeln!(out,
+            "            {})\n                path=\"${{path}}__${{COMP_WORDS[i]}}\"\n                ;;",
+            subpaths.join("|")
+        );
+    }
+    out.push_str("        esac\n    done\n\n    case \"$path\" in\n");
+    for node in nodes {
+        let _ = writeln!(
+            out,
+            "        {})\n            case \"$prev\" in",
+            node.id(bin, "__")
+        );
+        for flag in &node.flags {
+            let reply = match &flag.values {
+                None => continue,
+                Some(Values::Fixed(values)) => {
+                    format!(
+                        "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+                        values.join(" ")
+                    )
+                },
+                Some(Values::Files) => "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string(),
+                Some(Values::Any) => "COMPREPLY=()".to_string(),
+            };
+            let _ = writeln!(out,
+                "                {})\n                    {reply}\n                    return\n                    ;;",
+                flag.names().join("|")
+            );
+        }
+        let _ = writeln!(out,
+            "            esac\n            COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n            ;;",
+            node.words().join(" ")
+        );
+    }
+    let _ = writeln!(
+        out,
+        "    esac\n}}\n\ncomplete -F {function} -o bashdefault -o default {bin}"
+    );
+    out
+}
+
+/// `s` inside a single-quoted `_arguments` description
+fn zsh_quote(s: &str) -> String {
+    s.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+}
+
+/// `s` inside a single-quoted `_describe` item
+fn zsh_describe_quote(s: &str) -> String {
+    s.replace('\'', "'\\''").replace(':', "\\:")
+}
+
+/// `_arguments` action completing `values`
+fn zsh_action(values: &Values) -> String {
+    match values {
+        Values::Fixed(values) => format!("({})", values.join(" ")),
+        Values::Files => "_files".to_string(),
+        Values::Any => " ".to_string(),
+    }
+}
+
+fn zsh_function(bin: &str, path: &[String]) -> String {
+    let id = std::iter::once(bin.to_string())
+        .chain(path.iter().cloned())
+        .collect::<Vec<_>>()
+        .join("__");
+    format!("_{}", id.replace('-', "_"))
+}
+
+fn zsh(bin: &str, nodes: &[Node]) -> String {
+    let mut out = format!("#compdef {bin}\n");
+    for node in nodes {
+        let function = zsh_function(bin, &node.path);
+        let _ = writeln!(
+            out,
+            "\n{function}() {{\n    local line state\n    _arguments -C -s \\"
+        );
+        for flag in &node.flags {
+            let names = flag.names();
+            let action = flag
+                .values
+                .as_ref()
+                .map(|v| format!(":value:{}", zsh_action(v)))
+                .unwrap_or_default();
+            let spec = if names.len() == 1 {
+                format!("'{}[{}]{action}'", names[0], zsh_quote(&flag.help))
+            } else {
+                format!(
+                    "'({})'{{{}}}'[{}]{action}'",
+                    names.join(" "),
+                    names.join(","),
+                    zsh_quote(&flag.help)
+                )
+            };
+            let _ = writeln!(out, "        {spec} \\");
+        }
+        if node.subcommands.is_empty() {
+            let rest = node
+                .positionals
+                .as_ref()
+                .map(|v| format!("'*:value:{}'", zsh_action(v)))
+                .unwrap_or_default();
+            let _ = writeln!(out, "        {rest}\n}}");
+            continue;
+        }
+        out.push_str("        '1: :->cmds' \\\n        '*:: :->args'\n\n    case $state in\n");
+        out.push_str("        cmds)\n            local -a commands\n            commands=(\n");
+        for (name, about) in &node.subcommands {
+            let _ = writeln!(
+                out,
+                "                '{name}:{}'",
+                zsh_describe_quote(about)
+            );
+        }
+        out.push_str(
+            "            )\n            _describe -t commands 'command' commands\n            ;;\n        \
+             args)\n            case $line[1] in\n",
+        );
+        for (name, _) in &node.subcommands {
+            let mut path = node.path.clone();
+            path.push(name.clone());
+            let _ = writeln!(
+                out,
+                "                {name}) {} ;;",
+                zsh_function(bin, &path)
+            );
+        }
+        out.push_str("            esac\n            ;;\n    esac\n}\n");
+    }
+    let _ = writeln!(out, "\n{} \"$@\"", zsh_function(bin, &[]));
+    out
+}
+
+fn fish_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// `complete` options completing `values`
+fn fish_options(values: &Values) -> String {
+    match values {
+        Values::Fixed(values) => format!(" -x -a {}", fish_quote(&values.join(" "))),
+        Values::Files => " -r -F".to_string(),
+        Values::Any => " -x".to_string(),
+    }
+}
+
+fn fish(bin: &str, nodes: &[Node]) -> String {
+    let helper = format!("__fish_{}_path", bin.replace('-', "_"));
+    let subpaths: Vec<String> = nodes
+        .iter()
+        .filter(|n| !n.path.is_empty())
+        .map(|n| fish_quote(&n.path.join(" ")))
+        .collect();
+    let mut out = format!(
+        "# Succeeds when the subcommands typed so far are exactly $argv\n\
+         function {helper}\n    set -l paths {}\n    set -l path\n    \
+         for token in (commandline -opc)[2..-1]\n        \
+         set -l candidate (string trim -- \"$path $token\")\n        \
+         if contains -- $candidate $paths\n            set path $candidate\n        end\n    end\n    \
+         test \"$path\" = \"$argv\"\nend\n\ncomplete -c {bin} -f\n",
+        subpaths.join(" ")
+    );
+    for node in nodes {
+        let condition = format!("{helper} {}", node.path.join(" "));
+        let condition = fish_quote(condition.trim_end());
+        for (name, about) in &node.subcommands {
+            let _ = writeln!(
+                out,
+                "complete -c {bin} -n {condition} -a {name} -d {}",
+                fish_quote(about)
+            );
+        }
+        for flag in &node.flags {
+            let mut line = format!("complete -c {bin} -n {condition}");
+            for long in &flag.longs {
+                let _ = write!(line, " -l {long}");
+            }
+            if let Some(short) = flag.short {
+                let _ = write!(line, " -s {short}");
+            }
+            if let Some(values) = &flag.values {
+                line.push_str(&fish_options(values));
+            }
+            let _ = writeln!(line, " -d {}", fish_quote(&flag.help));
+            out.push_str(&line);
+        }
+        match &node.positionals {
+            Some(Values::Fixed(values)) => {
+                let _ = writeln!(
+                    out,
+                    "complete -c {bin} -n {condition} -a {}",
+                    fish_quote(&values.join(" "))
+                );
+            },
+            Some(Values::Files) if node.subcommands.is_empty() => {
+                let _ = writeln!(out, "complete -c {bin} -n {condition} -F");
+            },
+            _ => {},
+        }
+    }
+    out
+}
+
+fn powershell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+fn powershell(bin: &str, nodes: &[Node]) -> String {
+    let mut out = format!(
+        "Register-ArgumentCompleter -Native -CommandName {} -ScriptBlock {{\n    \
+         param($wordToComplete, $commandAst, $cursorPosition)\n\n    \
+         # Completions after each subcommand path, with their tooltips\n    \
+         $completions = @{{\n",
+        powershell_quote(bin)
+    );
+    for node in nodes {
+        let mut entries: Vec<(String, String)> = node.subcommands.clone();
+        entries.extend(
+            node.positionals
+                .iter()
+                .flat_map(Values::fixed)
+                .map(|value| (value.clone(), value.clone())),
+        );
+        for flag in &node.flags {
+            entries.extend(flag.names().into_iter().map(|n| (n, flag.help.clone())));
+        }
+        let mut entries: Vec<String> = entries
+            .iter()
+            .map(|(word, tip)| format!("@({}, {})", powershell_quote(word), powershell_quote(tip)))
+            .collect();
+        // Keep a lone pair from being flattened into the outer array
+        if let [entry] = entries.as_mut_slice() {
+            entry.insert(0, ',');
+        }
+        let _ = writeln!(
+            out,
+            "        {} = @({})",
+            powershell_quote(&node.id(bin, ";")),
+            entries.join(", ")
+        );
+    }
+    let _ = writeln!(out,
+        "    }}\n\n    $path = {}\n    \
+         $start = $cursorPosition - $wordToComplete.Length\n    \
+         foreach ($element in $commandAst.CommandElements | Select-Object -Skip 1) {{\n        \
+         if ($element.Extent.StartOffset -ge $start) {{ break }}\n        \
+         $candidate = \"$path;$($element.ToString())\"\n        \
+         if ($completions.ContainsKey($candidate)) {{ $path = $candidate }}\n    }}\n\n    \
+         $completions[$path] | Where-Object {{ $_[0] -clike \"$wordToComplete*\" }} | ForEach-Object {{\n        \
+         [System.Management.Automation.CompletionResult]::new($_[0], $_[0], 'ParameterValue', $_[1])\n    \
+         }}\n}}",
+        powershell_quote(bin)
+    );
+    out
+}
+
+/// Completion script for `shell` covering `command` and all its subcommands
+fn script(shell: &str, command: &mut clap::Command) -> Option<String> {
+    command.build();
+    let bin = command.get_name().to_string();
+    let mut nodes = Vec::new();
+    collect(command, &[], &mut nodes);
+    match shell {
+        "bash" => Some(bash(&bin, &nodes)),
+        "zsh" => Some(zsh(&bin, &nodes)),
+        "fish" => Some(fish(&bin, &nodes)),
+        "powershell" => Some(powershell(&bin, &nodes)),
+        _ => None,
+    }
+}
+
+pub struct CompletionsCommand {
+    pub shell: String,
+    pub command: clap::Command,
+}
+
+impl CompletionsCommand {
+    pub fn execute(&self) -> Result<()> {
+        let mut command = self.command.clone();
+        let Some(script) = script(&self.shell, &mut command) else {
+            return Err(crate::error::PromptGuardError::Custom(format!(
+                "Unsupported shell '{}'. Use bash, zsh, fish or powershell.",
+                self.shell
+            )));
+        };
+        print!("{script}");
+        Ok(())
+    }
+}
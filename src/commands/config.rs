@@ -1,109 +1,551 @@
-use crate::config::ConfigManager;
+use crate::config::migrate;
+use crate::config::schema::{self, SchemaError};
+use crate::config::user::{user_config_path, UserConfig};
+use crate::config::{ConfigManager, PromptGuardConfig};
 use crate::error::{PromptGuardError, Result};
 use crate::output::Output;
+use std::path::{Path, PathBuf};
+
+pub enum ConfigAction {
+    /// Show every setting
+    Show,
+    Get {
+        key: String,
+    },
+    Set {
+        key: String,
+        value: String,
+    },
+    Unset {
+        key: String,
+    },
+    /// Check the config against the schema and the settings' own rules
+    Validate,
+    /// Rewrite the config in the current version's layout now
+    Migrate,
+    /// Print the embedded JSON Schema
+    Schema,
+    /// Write the settings to reuse in other repositories
+    Export {
+        /// Leave the API key out
+        no_secrets: bool,
+        /// File to write; stdout when unset
+        output: Option<PathBuf>,
+    },
+    /// Replace the settings with an exported config's (`-` reads stdin)
+    Import {
+        file: PathBuf,
+    },
+}
 
 pub struct ConfigCommand {
+    pub action: ConfigAction,
     pub json: bool,
+    /// Operate on the user config instead of the project's
+    pub global: bool,
 }
 
 impl ConfigCommand {
     pub fn execute(&self) -> Result<()> {
+        if let ConfigAction::Schema = self.action {
+            println!("{}", schema::CONFIG_SCHEMA.trim_end());
+            return Ok(());
+        }
+        if self.global {
+            return self.execute_user();
+        }
+
         let config_manager = ConfigManager::new(None)?;
+        if let ConfigAction::Import { file } = &self.action {
+            return self.import(&config_manager, file);
+        }
         if !config_manager.exists() {
             return Err(PromptGuardError::NotInitialized);
         }
+        if let ConfigAction::Validate = self.action {
+            return self.validate(&config_manager);
+        }
+        if let ConfigAction::Migrate = self.action {
+            return self.migrate(&config_manager);
+        }
+
+        let mut config = config_manager.load()?;
+
+        match &self.action {
+            ConfigAction::Show => self.show(&config_manager, &config),
+            ConfigAction::Get { key } => {
+                let value = config
+                    .get_value(key)?
+                    .ok_or_else(|| PromptGuardError::Config(format!("'{key}' is not set")))?;
+                self.print_value(key, value)
+            },
+            ConfigAction::Set { key, value } => {
+                config.set_value(key, value)?;
+                config_manager.save(&config)?;
+                self.report(&config, key, "Set")
+            },
+            ConfigAction::Unset { key } => {
+                config.unset_value(key)?;
+                config_manager.save(&config)?;
+                self.report(&config, key, "Unset")
+            },
+            ConfigAction::Export { no_secrets, output } => {
+                let content = serde_json::to_string_pretty(&config.export(!no_secrets)?)?;
+                if let Some(path) = output {
+                    std::fs::write(path, format!("{content}\n"))?;
+                    Output::success(&format!("Exported configuration to {}", path.display()));
+                    if !no_secrets && !config.api_key().is_empty() {
+                        Output::warning(
+                            "The export contains the API key; use --no-secrets to share it",
+                        );
+                    }
+                } else {
+                    println!("{content}");
+                }
+                Ok(())
+            },
+            ConfigAction::Validate
+            | ConfigAction::Migrate
+            | ConfigAction::Schema
+            | ConfigAction::Import { .. } => Ok(()),
+        }
+    }
+
+    /// `config import`: stamp an exported config onto this project
+    fn import(&self, config_manager: &ConfigManager, file: &Path) -> Result<()> {
+        let content = if file == Path::new("-") {
+            std::io::read_to_string(std::io::stdin())?
+        } else {
+            std::fs::read_to_string(file).map_err(|e| {
+                PromptGuardError::Config(format!("Failed to read {}: {e}", file.display()))
+            })?
+        };
+        let value: serde_json::Value = serde_json::from_str(&content).map_err(|e| {
+            PromptGuardError::Config(format!("Failed to parse {}: {e}", file.display()))
+        })?;
+        let replaced = config_manager.exists();
+        let config = config_manager.import(value)?;
+
+        if self.json {
+            return Output::json(serde_json::json!({
+                "config_path": config_manager.config_path().display().to_string(),
+                "replaced": replaced,
+                "providers": config.providers,
+                "proxy_url": config.proxy_url,
+            }));
+        }
+        Output::success(&format!(
+            "Imported configuration from {} into {}",
+            file.display(),
+            config_manager.config_path().display()
+        ));
+        Output::line(&format!("  Providers: {}", config.providers.join(", ")));
+        Output::line(&format!("  Proxy URL: {}", config.proxy_url));
+        if !replaced {
+            Output::line("\nApply it with: promptguard apply");
+        }
+        Ok(())
+    }
+
+    /// `config migrate`: save an older config in the current layout, moving a
+    /// stored API key to the env files
+    fn migrate(&self, config_manager: &ConfigManager) -> Result<()> {
+        let (from, applied) = config_manager.migrate()?;
+        let path = config_manager.config_path();
+        if self.json {
+            return Output::json(serde_json::json!({
+                "config_path": path.display().to_string(),
+                "migrated": !applied.is_empty(),
+                "from": from,
+                "to": migrate::CURRENT_VERSION,
+                "applied": applied,
+            }));
+        }
+        if applied.is_empty() {
+            Output::success(&format!(
+                "{} is already at config version {}",
+                path.display(),
+                migrate::CURRENT_VERSION
+            ));
+            return Ok(());
+        }
+        Output::success(&format!(
+            "Migrated {} from config version {from} to {}",
+            path.display(),
+            migrate::CURRENT_VERSION
+        ));
+        for step in &applied {
+            Output::line(&format!("  • {step}"));
+        }
+        Ok(())
+    }
+
+    /// `config validate`: report every problem, failing if there is one
+    fn validate(&self, config_manager: &ConfigManager) -> Result<()> {
+        let path = config_manager.config_path();
+        let content = std::fs::read_to_string(path)?;
+        let mut warnings = Vec::new();
+        let errors: Vec<SchemaError> = match serde_json::from_str::<serde_json::Value>(&content) {
+            Err(e) => vec![SchemaError {
+                path: "$".to_string(),
+                message: format!("not valid JSON: {e}"),
+            }],
+            Ok(value) => {
+                let mut errors = schema::validate_config(&value);
+                if let Some(version) = value.get("version").and_then(serde_json::Value::as_str) {
+                    if let Err(e) = migrate::check_version(version) {
+                        errors.push(SchemaError {
+                            path: "$.version".to_string(),
+                            message: e.to_string(),
+                        });
+                    } else if version != migrate::CURRENT_VERSION {
+                        warnings.push(format!(
+                            "config version {version} is upgraded to {} the next time it is \
+                             saved; run `promptguard config migrate` to save it now",
+                            migrate::CURRENT_VERSION
+                        ));
+                    }
+                }
+                if value.get("api_key").is_some() {
+                    warnings.push(
+                        "api_key is moved to the env file the next time the config is saved \
+                         (`promptguard config migrate`); don't commit it"
+                            .to_string(),
+                    );
+                }
+                if errors.is_empty() {
+                    let checked = serde_json::from_value::<PromptGuardConfig>(value)
+                        .map_err(|e| e.to_string())
+                        .and_then(|config| {
+                            config.validate().map_err(|e| e.to_string())?;
+                            Ok(config)
+                        });
+                    match checked {
+                        Ok(config) => warnings.extend(
+                            config
+                                .extra
+                                .keys()
+                                .filter(|k| !["$schema", "api_key"].contains(&k.as_str()))
+                                .map(|k| format!("unknown setting '{k}' is ignored")),
+                        ),
+                        Err(message) => errors.push(SchemaError {
+                            path: "$".to_string(),
+                            message,
+                        }),
+                    }
+                }
+                errors
+            },
+        };
+
+        if self.json {
+            Output::json(serde_json::json!({
+                "valid": errors.is_empty(),
+                "config_path": path.display().to_string(),
+                "errors": errors
+                    .iter()
+                    .map(|e| serde_json::json!({ "path": e.path, "message": e.message }))
+                    .collect::<Vec<_>>(),
+                "warnings": warnings,
+            }))?;
+        } else if errors.is_empty() {
+            Output::success(&format!("{} is valid", path.display()));
+            for warning in &warnings {
+                Output::warning(warning);
+            }
+        } else {
+            Output::error(&format!("{} is invalid:", path.display()));
+            for error in &errors {
+                Output::line(&format!("  • {error}"));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(PromptGuardError::Config(format!(
+                "{} problem(s) in {}",
+                errors.len(),
+                path.display()
+            )))
+        }
+    }
+
+    /// `config --global`: the user-level defaults every project inherits
+    fn execute_user(&self) -> Result<()> {
+        let mut user = UserConfig::load()?;
+        match &self.action {
+            ConfigAction::Show => self.show_user(&user),
+            ConfigAction::Get { key } => {
+                let value = user
+                    .get_value(key)?
+                    .ok_or_else(|| PromptGuardError::Config(format!("'{key}' is not set")))?;
+                self.print_value(key, value)
+            },
+            ConfigAction::Set { key, value } => {
+                user.set_value(key, value)?;
+                user.save()?;
+                let value = user.get_value(key)?;
+                self.print_change(key, value.as_ref(), "Set")
+            },
+            ConfigAction::Unset { key } => {
+                user.unset_value(key)?;
+                user.save()?;
+                self.print_change(key, None, "Unset")
+            },
+            ConfigAction::Validate => {
+                let path = user_config_path()?;
+                if self.json {
+                    return Output::json(serde_json::json!({
+                        "valid": true,
+                        "config_path": path.display().to_string(),
+                        "errors": [],
+                        "warnings": [],
+                    }));
+                }
+                Output::success(&format!("{} is valid", path.display()));
+                Ok(())
+            },
+            ConfigAction::Schema => Ok(()),
+            ConfigAction::Export { .. } | ConfigAction::Import { .. } | ConfigAction::Migrate => {
+                Err(PromptGuardError::Config(
+                    "config export, import and migrate work on the project config, not --global"
+                        .to_string(),
+                ))
+            },
+        }
+    }
+
+    fn show_user(&self, user: &UserConfig) -> Result<()> {
+        let path = user_config_path()?;
+        let api_key = user.api_key.as_deref().map(Output::mask_api_key);
+        if self.json {
+            return Output::json(serde_json::json!({
+                "api_key": api_key,
+                "base_url": user.base_url,
+                "exclude_patterns": user.exclude_patterns,
+                "telemetry": user.telemetry_enabled(),
+                "config_path": path.display().to_string(),
+            }));
+        }
 
-        let config = config_manager.load()?;
+        Output::header("PromptGuard User Configuration");
+        Output::line("\nDefaults for every project (project settings take precedence):");
+        Output::line(&format!(
+            "  API key: {}",
+            api_key.as_deref().unwrap_or("(not set)")
+        ));
+        Output::line(&format!(
+            "  Base URL: {}",
+            user.base_url.as_deref().unwrap_or("(not set)")
+        ));
+        Output::line(&format!(
+            "  Telemetry: {}",
+            if user.telemetry_enabled() {
+                "Enabled"
+            } else {
+                "Disabled"
+            }
+        ));
+        if !user.exclude_patterns.is_empty() {
+            Output::line("\nExtra exclude patterns:");
+            for pattern in &user.exclude_patterns {
+                Output::line(&format!("  • {pattern}"));
+            }
+        }
+        Output::line(&format!("\nConfiguration file: {}", path.display()));
+        Output::line("\nChange a setting: promptguard config --global set <key> <value>");
+        Ok(())
+    }
 
+    /// `config get`: strings print bare unless `--json` asks for an object
+    fn print_value(&self, key: &str, value: serde_json::Value) -> Result<()> {
         if self.json {
-            let result = serde_json::json!({
+            return Output::json(serde_json::json!({ "key": key, "value": value }));
+        }
+        match value {
+            serde_json::Value::String(s) => println!("{s}"),
+            value => println!("{}", serde_json::to_string_pretty(&value)?),
+        }
+        Ok(())
+    }
+
+    /// Print a setting after it changed
+    fn report(&self, config: &PromptGuardConfig, key: &str, verb: &str) -> Result<()> {
+        let value = config.get_value(key)?;
+        self.print_change(key, value.as_ref(), verb)
+    }
+
+    fn print_change(&self, key: &str, value: Option<&serde_json::Value>, verb: &str) -> Result<()> {
+        let value = match value {
+            Some(serde_json::Value::String(s)) if key == "api_key" => {
+                Some(serde_json::Value::String(Output::mask_api_key(s)))
+            },
+            other => other.cloned(),
+        };
+        if self.json {
+            Output::json(serde_json::json!({ "key": key, "value": value }))?;
+        } else {
+            match value {
+                Some(value) => Output::success(&format!("{verb} {key} = {value}")),
+                None => Output::success(&format!("{verb} {key}")),
+            }
+        }
+        Ok(())
+    }
+
+    fn show(&self, config_manager: &ConfigManager, config: &PromptGuardConfig) -> Result<()> {
+        if self.json {
+            return Output::json(serde_json::json!({
                 "version": config.version,
                 "enabled": config.enabled,
                 "proxy_url": config.proxy_url,
                 "providers": config.providers,
                 "env_file": config.env_file,
                 "env_var_name": config.env_var_name,
+                "env_var_overrides": config.env_var_overrides,
                 "backup_enabled": config.backup_enabled,
                 "backup_extension": config.backup_extension,
                 "framework": config.framework,
                 "project_id": config.project_id,
                 "runtime_mode": config.runtime_mode,
+                "env_only": config.env_only,
+                "shim_fail_mode": config.shim_fail_mode,
+                "shim_log": config.shim_log,
                 "exclude_patterns": config.exclude_patterns,
+                "profile": config.profile,
+                "env_overrides": config.env_overrides().collect::<Vec<_>>(),
+                "profiles": config.profiles.keys().collect::<Vec<_>>(),
                 "config_path": config_manager.config_path().display().to_string(),
                 "metadata": {
                     "cli_version": config.metadata.cli_version,
                     "files_managed": config.metadata.files_managed.len(),
                 },
-            });
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&result).unwrap_or_default()
-            );
-            return Ok(());
+            }));
         }
 
         Output::header("PromptGuard Configuration");
 
-        println!("\nConfiguration:");
-        println!("  Version: {}", config.version);
-        println!(
+        Output::line("\nConfiguration:");
+        Output::line(&format!("  Version: {}", config.version));
+        if let Some(ref profile) = config.profile {
+            Output::line(&format!("  Profile: {profile}"));
+        }
+        let overrides: Vec<&str> = config.env_overrides().collect();
+        if !overrides.is_empty() {
+            Output::line(&format!("  Overridden by: {}", overrides.join(", ")));
+        }
+        Output::line(&format!(
             "  Status: {}",
             if config.enabled {
                 "Enabled ✓"
             } else {
                 "Disabled"
             }
-        );
-        println!("  Proxy URL: {}", config.proxy_url);
-        println!("  Providers: {}", config.providers.join(", "));
-        println!("  Environment file: {}", config.env_file);
-        println!("  API key variable: {}", config.env_var_name);
-        println!(
+        ));
+        Output::line(&format!("  Proxy URL: {}", config.proxy_url));
+        Output::line(&format!("  Providers: {}", config.providers.join(", ")));
+        Output::line(&format!("  Environment file: {}", config.env_file));
+        Output::line(&format!("  API key variable: {}", config.env_var_name));
+        for (provider, var) in &config.env_var_overrides {
+            Output::line(&format!("    {provider}: {var}"));
+        }
+        Output::line(&format!(
             "  Backups: {}",
             if config.backup_enabled {
                 "Enabled"
             } else {
                 "Disabled"
             }
-        );
+        ));
         if config.backup_enabled {
-            println!("  Backup extension: {}", config.backup_extension);
+            Output::line(&format!("  Backup extension: {}", config.backup_extension));
         }
 
         if let Some(ref framework) = config.framework {
-            println!("  Framework: {framework}");
+            Output::line(&format!("  Framework: {framework}"));
+        }
+        if config.env_only {
+            Output::line(&format!(
+                "  Mode: environment variables only ({})",
+                config
+                    .metadata
+                    .env_only_vars
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        if config.runtime_mode {
+            Output::line(&format!(
+                "  Shim fail mode: {} (when the proxy is unreachable)",
+                config.shim_fail_mode.as_str()
+            ));
+            Output::line(&format!(
+                "  Shim log: {}",
+                if config.shim_log {
+                    "Enabled (.promptguard/shim.log)"
+                } else {
+                    "Disabled"
+                }
+            ));
         }
 
         if let Some(ref project_id) = config.project_id {
-            println!("  Project ID: {project_id}");
+            Output::line(&format!("  Project ID: {project_id}"));
+        }
+
+        if !config.profiles.is_empty() {
+            Output::line("\nProfiles (select with --profile):");
+            for (name, profile) in &config.profiles {
+                let mut overrides = Vec::new();
+                if let Some(ref url) = profile.proxy_url {
+                    overrides.push(format!("proxy_url={url}"));
+                }
+                if let Some(ref env_file) = profile.env_file {
+                    overrides.push(format!("env_file={env_file}"));
+                }
+                if let Some(ref var) = profile.env_var_name {
+                    overrides.push(format!("env_var_name={var}"));
+                }
+                if let Some(ref vars) = profile.env_var_overrides {
+                    overrides.push(format!("{} env_var_overrides", vars.len()));
+                }
+                Output::line(&format!("  • {name}: {}", overrides.join(", ")));
+            }
         }
 
-        println!("\nExclude patterns:");
+        Output::line("\nExclude patterns:");
         for pattern in &config.exclude_patterns {
-            println!("  • {pattern}");
+            Output::line(&format!("  • {pattern}"));
         }
 
-        println!("\nMetadata:");
-        println!("  CLI version: {}", config.metadata.cli_version);
+        Output::line("\nMetadata:");
+        Output::line(&format!("  CLI version: {}", config.metadata.cli_version));
         if let Some(last_applied) = config.metadata.last_applied {
-            println!(
+            Output::line(&format!(
                 "  Last applied: {}",
                 last_applied.format("%Y-%m-%d %H:%M:%S UTC")
-            );
+            ));
         }
         if !config.metadata.files_managed.is_empty() {
-            println!("  Files managed: {}", config.metadata.files_managed.len());
+            Output::line(&format!(
+                "  Files managed: {}",
+                config.metadata.files_managed.len()
+            ));
         }
 
-        println!(
+        Output::line(&format!(
             "\nConfiguration file: {}",
             config_manager.config_path().display()
-        );
+        ));
 
-        println!("\nCommands:");
-        println!("  promptguard disable  - Temporarily disable PromptGuard");
-        println!("  promptguard enable   - Re-enable PromptGuard");
-        println!("  promptguard revert   - Completely remove PromptGuard");
+        Output::line("\nCommands:");
+        Output::line("  promptguard config set <key> <value>  - Change a setting");
+        Output::line("  promptguard disable  - Temporarily disable PromptGuard");
+        Output::line("  promptguard enable   - Re-enable PromptGuard");
+        Output::line("  promptguard revert   - Completely remove PromptGuard");
 
         Ok(())
     }
@@ -0,0 +1,374 @@
+//! Coverage Command - Guarded vs unguarded call sites
+//!
+//! Scans the project and classifies every detected SDK call site by what
+//! routes it through `PromptGuard`: an injected base URL pointing at the
+//! proxy, a runtime shim loaded by the process, or env-only base URL
+//! variables. Everything else is a gap, listed by file and line.
+
+use crate::commands::ShimCommand;
+use crate::config::{ConfigManager, PromptGuardConfig};
+use crate::detector::detect_all_providers;
+use crate::detector::registry::ProviderInfo;
+use crate::env::EnvManager;
+use crate::error::{PromptGuardError, Result};
+use crate::output::{Output, Progress};
+use crate::scanner::FileScanner;
+use crate::transformer;
+use crate::types::{Language, Provider};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// What routes a call site through `PromptGuard`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Guard {
+    /// Base URL injected by the transform (or set by hand) to the proxy
+    Transform,
+    /// Runtime shim loaded by the process
+    Runtime,
+    /// Base URL environment variable from `enable --env-only`
+    EnvOnly,
+}
+
+impl Guard {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Transform => "transform",
+            Self::Runtime => "runtime",
+            Self::EnvOnly => "env_only",
+        }
+    }
+}
+
+/// One detected client construction and whether it is guarded
+#[derive(Debug, Clone, Serialize)]
+pub struct CallSite {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub provider: Provider,
+    pub guard: Option<Guard>,
+    /// Why an unguarded call site is not routed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gap: Option<String>,
+    /// Other endpoint a string-literal base URL sends unguarded calls to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<String>,
+}
+
+/// Every call site in the project not marked `promptguard: ignore`, sorted
+/// by file and line, and the number of ignored ones
+pub fn call_sites(
+    root_path: &Path,
+    config: &PromptGuardConfig,
+    show_progress: bool,
+) -> Result<(Vec<CallSite>, usize)> {
+    let scanner = FileScanner::new(root_path, Some(config.exclude_patterns.clone()))?;
+    let files = scanner.scan_files(None)?;
+    let runtime = ShimCommand::loaded_languages(root_path, config)?;
+    let env_only_vars = env_only_vars(root_path, config);
+    // The generated shims construct clients themselves
+    let state_dir = crate::state::project_state_dir(root_path);
+
+    let mut sites = Vec::new();
+    let mut ignored = 0;
+    let mut progress = Progress::new("Scanning", files.len(), !show_progress);
+    for file_path in &files {
+        progress.inc_file(file_path.strip_prefix(root_path).unwrap_or(file_path));
+        if file_path.starts_with(&state_dir) {
+            continue;
+        }
+        let Ok((file_sites, file_ignored)) =
+            classify_file(root_path, config, file_path, &runtime, &env_only_vars)
+        else {
+            continue;
+        };
+        progress.found(file_sites.len());
+        sites.extend(file_sites);
+        ignored += file_ignored;
+    }
+    progress.finish();
+    sites.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+    Ok((sites, ignored))
+}
+
+/// Call sites in one file, sorted by line
+pub fn file_call_sites(
+    root_path: &Path,
+    config: &PromptGuardConfig,
+    file_path: &Path,
+) -> Result<Vec<CallSite>> {
+    let runtime = ShimCommand::loaded_languages(root_path, config)?;
+    let env_only_vars = env_only_vars(root_path, config);
+    let (mut sites, _) = classify_file(root_path, config, file_path, &runtime, &env_only_vars)?;
+    sites.sort_by_key(|s| (s.line, s.column));
+    Ok(sites)
+}
+
+fn classify_file(
+    root_path: &Path,
+    config: &PromptGuardConfig,
+    file_path: &Path,
+    runtime: &BTreeSet<Language>,
+    env_only_vars: &BTreeSet<&str>,
+) -> Result<(Vec<CallSite>, usize)> {
+    let results = detect_all_providers(file_path)?;
+    let rel_path = file_path
+        .strip_prefix(root_path)
+        .unwrap_or(file_path)
+        .to_string_lossy()
+        .to_string();
+    let language = file_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(Language::from_extension);
+    let managed = config.metadata.files_managed.contains(&rel_path);
+    let source = std::fs::read_to_string(file_path).unwrap_or_default();
+
+    let mut sites = Vec::new();
+    let mut ignored = 0;
+    for (provider, result) in results {
+        ignored += result.ignored.len();
+        if result.instances.is_empty() {
+            continue;
+        }
+        let proxy_url = config.proxy_url.for_provider(provider);
+        let stale = transformer::stale_base_urls(file_path, provider, proxy_url)?;
+        let dynamic = transformer::dynamic_config_lines(file_path, provider)?;
+        let env_var = language.and_then(|l| base_url_env_var(provider, l));
+
+        // A literal belongs to the last client constructed at or above it
+        let mut instances = result.instances;
+        instances.sort_by_key(|i| (i.line, i.column));
+        let lines: Vec<usize> = instances.iter().map(|i| i.line).collect();
+        for (i, instance) in instances.into_iter().enumerate() {
+            let next_line = lines.get(i + 1).copied().unwrap_or(usize::MAX);
+            let stale_url = stale
+                .iter()
+                .find(|s| s.line >= instance.line && s.line < next_line);
+            let (guard, gap) = if !config.enabled {
+                (None, Some("PromptGuard is disabled".to_string()))
+            } else if language.is_some_and(|l| runtime.contains(&l)) {
+                (Some(Guard::Runtime), None)
+            } else if config.env_only && env_var.is_some_and(|v| env_only_vars.contains(v)) {
+                (Some(Guard::EnvOnly), None)
+            } else if config.runtime_mode && !instance.has_base_url {
+                (
+                    None,
+                    Some(
+                        "runtime shim isn't loaded; see 'promptguard shim inject --help'"
+                            .to_string(),
+                    ),
+                )
+            } else if instance.has_base_url {
+                match stale_url {
+                    Some(s) => (None, Some(format!("base URL points at {}", s.url))),
+                    None if managed || source.contains(proxy_url) => (Some(Guard::Transform), None),
+                    None => (
+                        None,
+                        Some("base URL set from an expression, not the proxy".to_string()),
+                    ),
+                }
+            } else if dynamic.contains(&instance.line) {
+                (
+                    None,
+                    Some(
+                        "built from options the transform can't rewrite; use runtime mode"
+                            .to_string(),
+                    ),
+                )
+            } else {
+                (
+                    None,
+                    Some("no base URL; calls go to the provider".to_string()),
+                )
+            };
+            let gateway = stale_url.filter(|_| guard.is_none()).map(|s| s.url.clone());
+            sites.push(CallSite {
+                file: rel_path.clone(),
+                line: instance.line,
+                column: instance.column,
+                provider,
+                guard,
+                gap,
+                gateway,
+            });
+        }
+    }
+    Ok((sites, ignored))
+}
+
+/// Base URL variables `enable --env-only` set that still hold its value
+fn env_only_vars<'a>(root_path: &Path, config: &'a PromptGuardConfig) -> BTreeSet<&'a str> {
+    let env_path = root_path.join(config.env_file.primary());
+    config
+        .metadata
+        .env_only_vars
+        .iter()
+        .filter(|(var, value)| {
+            EnvManager::get_key(&env_path, var).as_deref() == Some(value.as_str())
+        })
+        .map(|(var, _)| var.as_str())
+        .collect()
+}
+
+/// Variable `provider`'s SDK for `language` reads its base URL from
+fn base_url_env_var(provider: Provider, language: Language) -> Option<&'static str> {
+    let info = ProviderInfo::get(provider);
+    let var = match language {
+        Language::Python => info.py_base_url_env_var,
+        Language::TypeScript | Language::JavaScript => info.npm_base_url_env_var,
+    };
+    (!var.is_empty()).then_some(var)
+}
+
+/// Guarded share of `sites`, as a percentage; 100 when there are none
+#[allow(clippy::cast_precision_loss)]
+pub fn percent(sites: &[CallSite]) -> f64 {
+    if sites.is_empty() {
+        return 100.0;
+    }
+    let guarded = sites.iter().filter(|s| s.guard.is_some()).count();
+    guarded as f64 * 100.0 / sites.len() as f64
+}
+
+#[derive(Debug, Serialize)]
+struct ProviderCoverage {
+    provider: &'static str,
+    total: usize,
+    guarded: usize,
+}
+
+pub struct CoverageCommand {
+    /// Fail when coverage is below this percentage
+    pub min: Option<f64>,
+    pub json: bool,
+}
+
+impl CoverageCommand {
+    pub fn execute(&self) -> Result<()> {
+        let config_manager = ConfigManager::new(None)?;
+        if !config_manager.exists() {
+            return Err(PromptGuardError::NotInitialized);
+        }
+        let config = config_manager.load()?;
+        let root_path = crate::config::project_root()?;
+
+        if let Some(min) = self.min {
+            if !(0.0..=100.0).contains(&min) {
+                return Err(PromptGuardError::Custom(format!(
+                    "--min must be a percentage between 0 and 100, got {min}"
+                )));
+            }
+        }
+
+        if !self.json {
+            Output::header("PromptGuard Coverage");
+        }
+        let (sites, ignored) = call_sites(&root_path, &config, !self.json)?;
+        let percent = percent(&sites);
+        let guarded = sites.iter().filter(|s| s.guard.is_some()).count();
+
+        let mut by_provider: BTreeMap<Provider, ProviderCoverage> = BTreeMap::new();
+        let mut by_guard: BTreeMap<&'static str, usize> = BTreeMap::new();
+        for site in &sites {
+            let entry = by_provider
+                .entry(site.provider)
+                .or_insert_with(|| ProviderCoverage {
+                    provider: site.provider.as_str(),
+                    total: 0,
+                    guarded: 0,
+                });
+            entry.total += 1;
+            if let Some(guard) = site.guard {
+                entry.guarded += 1;
+                *by_guard.entry(guard.as_str()).or_default() += 1;
+            }
+        }
+
+        if self.json {
+            let result = serde_json::json!({
+                "coverage_percent": (percent * 10.0).round() / 10.0,
+                "total": sites.len(),
+                "guarded": guarded,
+                "unguarded": sites.len() - guarded,
+                "ignored": ignored,
+                "by_guard": by_guard,
+                "by_provider": by_provider.values().collect::<Vec<_>>(),
+                "call_sites": sites,
+            });
+            Output::json(result)?;
+        } else {
+            let hint = if !config.enabled {
+                "Run 'promptguard enable' to route them again"
+            } else if config.runtime_mode {
+                "Run 'promptguard shim inject' to load the shims from entry points"
+            } else {
+                "Run 'promptguard apply' to transform them, or 'promptguard enable --runtime'"
+            };
+            Self::print_human(&sites, &by_provider, &by_guard, ignored, percent, hint);
+        }
+
+        if let Some(min) = self.min {
+            if percent < min {
+                return Err(PromptGuardError::Custom(format!(
+                    "Coverage {percent:.1}% is below --min {min}%"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn print_human(
+        sites: &[CallSite],
+        by_provider: &BTreeMap<Provider, ProviderCoverage>,
+        by_guard: &BTreeMap<&'static str, usize>,
+        ignored: usize,
+        percent: f64,
+        hint: &str,
+    ) {
+        let guarded = sites.iter().filter(|s| s.guard.is_some()).count();
+        Output::line("");
+        if sites.is_empty() {
+            Output::info("No LLM SDK call sites found");
+            return;
+        }
+        Output::line(&format!(
+            "  Coverage: {percent:.1}% ({guarded} of {} call sites guarded)",
+            sites.len()
+        ));
+        for (guard, count) in by_guard {
+            Output::line(&format!("    {guard}: {count}"));
+        }
+        if ignored > 0 {
+            Output::line(&format!("  Ignored: {ignored} (promptguard: ignore)"));
+        }
+
+        Output::section("By provider", "📊");
+        for coverage in by_provider.values() {
+            Output::line(&format!(
+                "  • {}: {} of {}",
+                coverage.provider, coverage.guarded, coverage.total
+            ));
+        }
+
+        let gaps: Vec<&CallSite> = sites.iter().filter(|s| s.guard.is_none()).collect();
+        if gaps.is_empty() {
+            Output::line("");
+            Output::success("Every call site is routed through PromptGuard");
+        } else {
+            Output::section("Gaps", "⚠️");
+            for site in &gaps {
+                Output::line(&format!(
+                    "  • {}:{} {} - {}",
+                    site.file,
+                    site.line,
+                    site.provider.as_str(),
+                    site.gap.as_deref().unwrap_or_default()
+                ));
+            }
+            Output::line(&format!("\n  {hint}"));
+        }
+    }
+}
@@ -14,10 +14,7 @@ impl DashboardCommand {
                 "url": url,
                 "action": "open_browser",
             });
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&result).unwrap_or_default()
-            );
+            Output::json(result)?;
         } else {
             Output::info(&format!("Opening dashboard: {url}"));
             if let Err(e) = open::that(url) {
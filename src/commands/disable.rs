@@ -1,13 +1,17 @@
-use crate::backup::BackupManager;
-use crate::config::ConfigManager;
+use crate::commands::{RevertCommand, ShimCommand};
+use crate::config::{ConfigManager, PromptGuardConfig};
+use crate::env::EnvManager;
 use crate::error::{PromptGuardError, Result};
 use crate::output::Output;
-use crate::shim::{ShimGenerator, ShimInjector};
+use std::path::Path;
 
-pub struct DisableCommand;
+pub struct DisableCommand {
+    pub yes: bool,
+    pub json: bool,
+}
 
 impl DisableCommand {
-    pub fn execute() -> Result<()> {
+    pub fn execute(&self) -> Result<()> {
         Output::header("Disable PromptGuard");
 
         let config_manager = ConfigManager::new(None)?;
@@ -17,95 +21,81 @@ impl DisableCommand {
 
         let mut config = config_manager.load()?;
 
+        let mode = if config.runtime_mode {
+            "runtime"
+        } else if config.env_only {
+            "env_only"
+        } else {
+            "static"
+        };
+        let result = |changed: bool, restored: &[String], shims: Option<serde_json::Value>| {
+            serde_json::json!({
+                "enabled": false,
+                "changed": changed,
+                "mode": mode,
+                "restored_files": restored,
+                "shims_removed": shims,
+            })
+        };
+
         if !config.enabled {
             Output::warning("PromptGuard is already disabled");
-            return Ok(());
+            return self.finish(result(false, &[], None));
         }
 
         let mode_description = if config.runtime_mode {
             "runtime shim mode"
+        } else if config.env_only {
+            "environment variables only"
         } else {
             "static transform mode"
         };
 
-        println!("\nThis will temporarily disable PromptGuard ({mode_description}) by:");
-
-        if config.runtime_mode {
-            println!("  • Removing shim imports from entry points");
-            println!("  • Cleaning up generated shim files");
-        } else {
-            println!("  • Restoring all backup files");
+        if !self.json {
+            Self::print_plan(&config, mode_description);
         }
-
-        println!("  • Keeping configuration");
-        println!(
-            "\nYou can re-enable with: promptguard enable{}",
-            if config.runtime_mode {
-                " --runtime"
-            } else {
-                ""
-            }
-        );
-
-        if !Output::confirm("Continue?", true)? {
+        if !self.yes && !Output::confirm("Continue?", true)? {
             return Ok(());
         }
 
-        let root_path = std::env::current_dir()?;
+        let root_path = crate::config::project_root()?;
+        let mut restored_files = Vec::new();
+        let mut shims_removed = None;
 
         if config.runtime_mode {
             // Remove runtime shims
             Output::section("Removing shim injections...", "🧹");
 
-            let injector = ShimInjector::new(&root_path);
-            let removed_count = injector.remove_all_injections()?;
-
-            if removed_count > 0 {
-                Output::step(&format!("✓ Removed imports from {removed_count} files"));
-            }
-
-            // Clean up shim files
-            Output::section("Cleaning up shim files...", "🗑️");
-
-            let generator = ShimGenerator::new(
-                &root_path,
-                String::new(), // Unused for cleanup
-                String::new(), // Unused for cleanup
-                vec![],        // Unused for cleanup
-            );
+            let removal = ShimCommand::remove(&root_path, &config, false)?;
+            removal.print(&root_path, config.env_file.primary());
+            shims_removed = Some(removal.to_json(&root_path));
+            config.metadata.shim_checksums.clear();
+        } else if config.env_only {
+            Output::section("Removing base URL variables...", "🌍");
 
-            if generator.shims_installed() {
-                generator.clean_shims()?;
-                Output::step("✓ Removed .promptguard/ directory");
-            }
+            Self::remove_env_only_vars(&config, &root_path)?;
+            config.metadata.env_only_vars.clear();
         } else {
-            // Restore backups (static mode)
-            let backup_manager = BackupManager::new(Some(config.backup_extension.clone()));
-            let backups = backup_manager.list_backups(&root_path);
-            let mut restored_count = 0;
-
             Output::section("Restoring original files...", "📦");
 
-            for backup_path in &backups {
-                if let Some(original_path_str) = backup_path.to_str() {
-                    if let Some(original_str) =
-                        original_path_str.strip_suffix(&config.backup_extension)
-                    {
-                        let original_path = std::path::PathBuf::from(original_str);
-                        if backup_manager.restore_backup(&original_path).is_ok() {
-                            let rel_path = original_path
-                                .strip_prefix(&root_path)
-                                .unwrap_or(&original_path);
-                            Output::step(&format!("✓ {}", rel_path.display()));
-                            restored_count += 1;
-                        }
-                    }
-                }
-            }
+            // Remove only what PromptGuard added, keeping edits made since
+            let mut restored = RevertCommand::restore_sources(&config, &root_path)?.files;
 
-            if restored_count > 0 {
-                Output::step(&format!("Restored {restored_count} files"));
+            // Fall back to backups for files that still reference the proxy
+            RevertCommand::restore_from_backups(&config, &root_path, &mut restored);
+
+            if !restored.is_empty() {
+                Output::step(&format!("Restored {} files", restored.len()));
             }
+            restored_files = restored
+                .iter()
+                .map(|p| {
+                    p.strip_prefix(&root_path)
+                        .unwrap_or(p)
+                        .display()
+                        .to_string()
+                })
+                .collect();
         }
 
         // Update config to mark as disabled
@@ -113,18 +103,74 @@ impl DisableCommand {
         config_manager.save(&config)?;
         Output::step("Updated configuration");
 
-        println!();
-        Output::success("PromptGuard is now disabled");
-        println!("\n  • Configuration preserved");
-        println!(
-            "  • To re-enable: promptguard enable{}",
-            if config.runtime_mode {
-                " --runtime"
-            } else {
-                ""
-            }
-        );
+        if !self.json {
+            Output::line("");
+            Output::success("PromptGuard is now disabled");
+            Output::line("\n  • Configuration preserved");
+            Output::line(&format!(
+                "  • To re-enable: promptguard enable{}",
+                Self::enable_flag(&config)
+            ));
+        }
+
+        self.finish(result(true, &restored_files, shims_removed))
+    }
+
+    fn finish(&self, result: serde_json::Value) -> Result<()> {
+        if self.json {
+            Output::json(result)?;
+        }
+        Ok(())
+    }
+
+    /// The `enable` flag that restores the current mode
+    fn enable_flag(config: &PromptGuardConfig) -> &'static str {
+        if config.runtime_mode {
+            " --runtime"
+        } else if config.env_only {
+            " --env-only"
+        } else {
+            ""
+        }
+    }
+
+    fn print_plan(config: &PromptGuardConfig, mode_description: &str) {
+        Output::line(&format!(
+            "\nThis will temporarily disable PromptGuard ({mode_description}) by:"
+        ));
+
+        if config.runtime_mode {
+            Output::line("  • Removing shim imports from entry points");
+            Output::line("  • Cleaning up generated shim files");
+        } else if config.env_only {
+            Output::line(&format!(
+                "  • Removing the base URL variables from {}",
+                config.env_file.primary()
+            ));
+        } else {
+            Output::line("  • Removing the base URL overrides PromptGuard added");
+        }
 
+        Output::line("  • Keeping configuration");
+        Output::line(&format!(
+            "\nYou can re-enable with: promptguard enable{}",
+            Self::enable_flag(config)
+        ));
+    }
+
+    /// Remove the variables `enable --env-only` set, leaving any changed since
+    pub fn remove_env_only_vars(config: &PromptGuardConfig, root_path: &Path) -> Result<()> {
+        let env_path = root_path.join(config.env_file.primary());
+        for (var, value) in &config.metadata.env_only_vars {
+            if EnvManager::get_key(&env_path, var).as_deref() == Some(value.as_str())
+                && EnvManager::remove_key(&env_path, var)?
+            {
+                Output::step(&format!(
+                    "✓ Removed {var} from {}",
+                    config.env_file.primary()
+                ));
+            }
+        }
         Ok(())
     }
 }
@@ -1,10 +1,44 @@
+use crate::analyzer::{find_secrets, SecretKind};
 use crate::backup::BackupManager;
-use crate::config::ConfigManager;
-use crate::env::EnvManager;
+use crate::commands::ShimCommand;
+use crate::config::{ConfigManager, EnvFile, EnvFileState, PromptGuardConfig};
+use crate::detector::{detect_declared_providers, DeclaredDependency};
 use crate::error::Result;
 use crate::output::Output;
+use crate::shim::{ShimIntegrity, SHIM_VERSION};
 use std::fs;
 use std::path::Path;
+use std::process::Command;
+
+/// Tracked files larger than this aren't scanned for keys
+const MAX_SCAN_BYTES: u64 = 1024 * 1024;
+
+/// A key found in a file git tracks or would commit
+#[derive(Debug, serde::Serialize)]
+struct KeyLeak {
+    file: String,
+    line: usize,
+    kind: SecretKind,
+}
+
+/// Problems found so far, printed as they are recorded
+#[derive(Debug, Default, serde::Serialize)]
+struct Diagnosis {
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Diagnosis {
+    fn error(&mut self, message: String) {
+        Output::warning(&message);
+        self.errors.push(message);
+    }
+
+    fn warning(&mut self, message: String) {
+        Output::warning(&message);
+        self.warnings.push(message);
+    }
+}
 
 pub struct DoctorCommand {
     pub json: bool,
@@ -14,12 +48,13 @@ impl DoctorCommand {
     pub fn execute(&self) -> Result<()> {
         Output::header("Running diagnostics...");
 
-        println!("\n🩺 Running diagnostics...\n");
+        if !self.json {
+            Output::line("\n🩺 Running diagnostics...\n");
+        }
 
-        let mut warnings_count = 0;
-        let mut errors_count = 0;
+        let mut diagnosis = Diagnosis::default();
 
-        let root_path = std::env::current_dir()?;
+        let root_path = crate::config::project_root()?;
 
         // Check CLI version
         Output::step(&format!(
@@ -29,87 +64,223 @@ impl DoctorCommand {
 
         // Check config file
         let config_manager = ConfigManager::new(None)?;
+        let mut loaded_config = None;
+        let mut leaks = Vec::new();
         if config_manager.exists() {
+            // The raw file: loading takes a stored key out of the config in memory
+            let raw = fs::read_to_string(config_manager.config_path()).unwrap_or_default();
+            for secret in find_secrets(&raw) {
+                diagnosis.error(format!(
+                    "Security: .promptguard.json:{} contains a raw {} \
+                     (`promptguard config migrate` moves a stored api_key to the env file)",
+                    secret.line,
+                    secret.kind.display_name()
+                ));
+                leaks.push(KeyLeak {
+                    file: ".promptguard.json".to_string(),
+                    line: secret.line,
+                    kind: secret.kind,
+                });
+            }
             match config_manager.load() {
                 Ok(config) => {
                     Output::step("Configuration file: .promptguard.json (valid)");
 
-                    if config.api_key.starts_with("pg_sk_test_")
-                        || config.api_key.starts_with("pg_sk_prod_")
-                    {
-                        Output::step("API key: valid format");
+                    // The key is never stored in .promptguard.json, only referenced
+                    if let Some((key, source)) = crate::config::find_api_key(&root_path, &config) {
+                        Output::step(&format!(
+                            "API key: read from {} ({})",
+                            source.as_str(),
+                            config.env_var_name
+                        ));
+                        if key.starts_with("pg_sk_test_") || key.starts_with("pg_sk_prod_") {
+                            Output::step("API key: valid format");
+                        } else {
+                            diagnosis.error("API key: invalid format".to_string());
+                        }
                     } else {
-                        Output::warning("API key: invalid format");
-                        errors_count += 1;
+                        diagnosis.error(format!(
+                            "API key: {} not set in the environment, {} or the OS keychain",
+                            config.env_var_name, config.env_file
+                        ));
                     }
 
-                    // Security check: warn if config contains API key and is not gitignored
-                    if Self::check_config_in_gitignore(&root_path) {
-                        Output::step("Security: .promptguard.json is in .gitignore");
-                    } else {
-                        Output::warning(
-                            "Security: .promptguard.json contains API key but is NOT in .gitignore",
-                        );
-                        println!(
-                            "  ⚠️  Your API key may be exposed if committed to version control!"
-                        );
-                        println!(
-                            "  Recommendation: Add '.promptguard.json' to your .gitignore file"
-                        );
-                        println!("  Or use environment variables only (PROMPTGUARD_API_KEY)");
-                        warnings_count += 1;
-                    }
+                    loaded_config = Some(config);
                 },
                 Err(e) => {
-                    Output::warning(&format!("Configuration file: invalid ({e})"));
-                    errors_count += 1;
+                    diagnosis.error(format!("Configuration file: invalid ({e})"));
                 },
             }
         } else {
-            Output::warning("Configuration file: not found (run 'promptguard init')");
-            warnings_count += 1;
+            diagnosis.warning("Configuration file: not found (run 'promptguard init')".to_string());
         }
 
-        // Check .env file
-        let env_path = root_path.join(".env");
-        if env_path.exists() {
-            if EnvManager::has_key(&env_path, "PROMPTGUARD_API_KEY") {
-                Output::step("Environment file: .env (found, contains PROMPTGUARD_API_KEY)");
-
-                // Check if .env is gitignored
-                if Self::check_env_in_gitignore(&root_path) {
-                    Output::step("Security: .env is in .gitignore");
-                } else {
-                    Output::warning("Security: .env is NOT in .gitignore");
-                    println!("  ⚠️  Your secrets may be exposed if committed!");
-                    println!("  Recommendation: Add '.env' to your .gitignore file");
-                    warnings_count += 1;
-                }
+        // Check each env file holds the key, the same key, and stays out of git
+        let (env_file, env_var) = loaded_config.as_ref().map_or_else(
+            || (EnvFile::default(), "PROMPTGUARD_API_KEY".to_string()),
+            |config| (config.env_file.clone(), config.env_var_name.clone()),
+        );
+        let states = env_file.states(&root_path, &env_var);
+        let source = states
+            .iter()
+            .find(|(_, state)| *state == EnvFileState::InSync)
+            .map_or(env_file.primary(), |(file, _)| *file);
+        for &(file, state) in &states {
+            match state {
+                EnvFileState::Missing => {
+                    diagnosis.warning(format!("Environment file: {file} (not found)"));
+                    continue;
+                },
+                EnvFileState::NoKey => {
+                    diagnosis.warning(format!(
+                        "Environment file: {file} (found, but missing {env_var})"
+                    ));
+                    continue;
+                },
+                EnvFileState::InSync => {
+                    Output::step(&format!(
+                        "Environment file: {file} (found, contains {env_var})"
+                    ));
+                },
+                EnvFileState::OutOfSync => {
+                    diagnosis.warning(format!(
+                        "Environment file: {file} ({env_var} differs from {source}; run 'promptguard key' to update every env file)"
+                    ));
+                },
+            }
+
+            // Check if the env file is gitignored
+            if Self::check_env_in_gitignore(&root_path, file) {
+                Output::step(&format!("Security: {file} is in .gitignore"));
             } else {
-                Output::warning("Environment file: .env (found, but missing PROMPTGUARD_API_KEY)");
-                warnings_count += 1;
+                diagnosis.warning(format!("Security: {file} is NOT in .gitignore"));
+                if !self.json {
+                    Output::line("  ⚠️  Your secrets may be exposed if committed!");
+                    Output::line(&format!(
+                        "  Recommendation: Add '{file}' to your .gitignore file"
+                    ));
+                }
             }
-        } else {
-            Output::warning("Environment file: .env (not found)");
-            warnings_count += 1;
+        }
+
+        // Check no keys are hardcoded in files git tracks
+        match Self::tracked_files(&root_path) {
+            Some(files) => {
+                let before = leaks.len();
+                for file in &files {
+                    let path = root_path.join(file);
+                    if fs::metadata(&path).map_or(true, |m| m.len() > MAX_SCAN_BYTES) {
+                        continue;
+                    }
+                    let Ok(content) = fs::read_to_string(&path) else {
+                        continue;
+                    };
+                    for secret in find_secrets(&content) {
+                        diagnosis.error(format!(
+                            "Security: {file}:{} contains a {}",
+                            secret.line,
+                            secret.kind.display_name()
+                        ));
+                        leaks.push(KeyLeak {
+                            file: file.clone(),
+                            line: secret.line,
+                            kind: secret.kind,
+                        });
+                    }
+                }
+                if leaks.len() == before {
+                    Output::step(&format!(
+                        "Security: no API keys in {} tracked files",
+                        files.len()
+                    ));
+                } else if !self.json {
+                    Output::line(
+                        "  Move source keys to the env file: 'promptguard secrets migrate'",
+                    );
+                    Output::line(
+                        "  Then rotate them: 'promptguard key --rotate' for PromptGuard keys",
+                    );
+                    Output::line("  Mark intentional fixtures with '# promptguard: ignore'");
+                }
+            },
+            None => {
+                Output::step("Security: not a git repository; skipped the tracked-file key scan");
+            },
         }
 
         // Check for backups
         let backup_manager = BackupManager::new(None);
         let backups = backup_manager.list_backups(&root_path);
         if backups.is_empty() {
-            Output::step("No backup files found");
+            Output::step("No legacy *.bak backup files found");
         } else {
-            Output::warning(&format!(
-                "Backup files: {} *.bak files found",
+            diagnosis.warning(format!(
+                "Legacy backup files: {} *.bak files found",
                 backups.len()
             ));
-            println!("\n  Recommendations:");
-            println!("    1. Review and commit or remove *.bak backup files");
-            println!("    2. Or add '*.bak' to .gitignore");
-            warnings_count += 1;
+            if !self.json {
+                Output::line("\n  Recommendations:");
+                Output::line(
+                    "    1. Review and remove them; backups now live in .promptguard/backups/",
+                );
+                Output::line("       (see 'promptguard backup list')");
+                Output::line("    2. Or add '*.bak' to .gitignore");
+            }
+        }
+
+        // Check locked SDK versions support base URL overrides
+        let outdated: Vec<DeclaredDependency> = detect_declared_providers(&root_path)
+            .into_iter()
+            .filter(DeclaredDependency::is_outdated)
+            .collect();
+        if outdated.is_empty() {
+            Output::step("SDK versions: compatible with base URL overrides");
+        } else {
+            for dep in &outdated {
+                diagnosis.warning(format!(
+                    "SDK version: {} {} is too old for base URL overrides (requires >= {})",
+                    dep.package,
+                    dep.version.as_deref().unwrap_or_default(),
+                    dep.min_base_url_version().unwrap_or_default()
+                ));
+            }
+            if !self.json {
+                Output::line(
+                    "  Recommendation: Upgrade these SDKs so PromptGuard can route their traffic",
+                );
+            }
+        }
+
+        // Check runtime shims were generated by this CLI version
+        if let Some(config) = loaded_config.as_ref().filter(|c| c.runtime_mode) {
+            let generator = ShimCommand::generator(&root_path, config);
+            if generator.is_outdated() {
+                diagnosis.warning(format!(
+                    "Runtime shims: generated by {} (current v{SHIM_VERSION})",
+                    generator
+                        .installed_version()
+                        .map_or_else(|| "an older CLI".to_string(), |v| format!("v{v}"))
+                ));
+                if !self.json {
+                    Output::line("  Recommendation: Run 'promptguard shim upgrade'");
+                }
+            }
+        }
+
+        // Check runtime shims still match what `enable --runtime` generated
+        if let Some(mut config) = loaded_config {
+            if config.runtime_mode && !config.metadata.shim_checksums.is_empty() {
+                self.check_shim_integrity(
+                    &root_path,
+                    &mut config,
+                    &config_manager,
+                    &mut diagnosis,
+                )?;
+            }
         }
 
+        let errors_count = diagnosis.errors.len();
+        let warnings_count = diagnosis.warnings.len();
         if self.json {
             let health = if errors_count > 0 {
                 "error"
@@ -118,21 +289,18 @@ impl DoctorCommand {
             } else {
                 "healthy"
             };
-            let result = serde_json::json!({
+            return Output::json(serde_json::json!({
                 "health": health,
                 "errors": errors_count,
                 "warnings": warnings_count,
+                "issues": diagnosis,
+                "key_leaks": leaks,
                 "cli_version": env!("CARGO_PKG_VERSION"),
-            });
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&result).unwrap_or_default()
-            );
-            return Ok(());
+            }));
         }
 
         // Report overall health based on actual findings
-        println!();
+        Output::line("");
         if errors_count > 0 {
             Output::error(&format!(
                 "Overall health: ✗ {errors_count} error(s), {warnings_count} warning(s)"
@@ -148,14 +316,73 @@ impl DoctorCommand {
         Ok(())
     }
 
-    /// Check if .promptguard.json is listed in .gitignore
-    fn check_config_in_gitignore(root_path: &Path) -> bool {
-        Self::is_pattern_in_gitignore(root_path, ".promptguard.json")
+    /// Warn about shim files edited or deleted since they were generated and
+    /// offer to regenerate them. Warnings stay in `diagnosis` unless they do.
+    fn check_shim_integrity(
+        &self,
+        root_path: &Path,
+        config: &mut PromptGuardConfig,
+        config_manager: &ConfigManager,
+        diagnosis: &mut Diagnosis,
+    ) -> Result<()> {
+        let generator = ShimCommand::generator(root_path, config);
+        let problems = generator.verify(&config.metadata.shim_checksums);
+        if problems.is_empty() {
+            Output::step("Runtime shims: unmodified since generation");
+            return Ok(());
+        }
+
+        let messages: Vec<String> = problems
+            .iter()
+            .map(|problem| match problem {
+                ShimIntegrity::Modified(name) => {
+                    format!("Runtime shim: .promptguard/{name} was modified or truncated")
+                },
+                ShimIntegrity::Missing(name) => {
+                    format!("Runtime shim: .promptguard/{name} is missing")
+                },
+            })
+            .collect();
+        for message in &messages {
+            Output::warning(message);
+        }
+
+        if !self.json {
+            Output::line("  Edited shims may stop routing SDK calls through PromptGuard");
+            if Output::can_prompt() && Output::confirm("Regenerate the shims now?", true)? {
+                generator.regenerate(config.metadata.shim_checksums.keys())?;
+                config.metadata.shim_checksums = generator.checksums()?;
+                config_manager.save(config)?;
+                Output::step("✓ Regenerated runtime shims");
+                return Ok(());
+            }
+            Output::line("  Regenerate later with: promptguard doctor");
+        }
+        diagnosis.warnings.extend(messages);
+        Ok(())
+    }
+
+    /// Files git tracks under `root_path`, relative to it; `None` outside a repository
+    fn tracked_files(root_path: &Path) -> Option<Vec<String>> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(root_path)
+            .args(["ls-files", "-z"])
+            .output()
+            .ok()
+            .filter(|o| o.status.success())?;
+        Some(
+            String::from_utf8_lossy(&output.stdout)
+                .split('\0')
+                .filter(|f| !f.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
     }
 
     /// Check if .env is listed in .gitignore
-    fn check_env_in_gitignore(root_path: &Path) -> bool {
-        Self::is_pattern_in_gitignore(root_path, ".env")
+    pub fn check_env_in_gitignore(root_path: &Path, env_file: &str) -> bool {
+        Self::is_pattern_in_gitignore(root_path, env_file)
     }
 
     /// Check if a pattern exists in .gitignore
@@ -1,82 +1,184 @@
 use crate::analyzer::EnvScanner;
-use crate::config::ConfigManager;
-use crate::detector::detect_all_providers;
+use crate::commands::shim::ShimPlan;
+use crate::commands::{ApplyCommand, ShimCommand};
+use crate::config::{ConfigManager, PromptGuardConfig, ShimFailMode};
+use crate::detector::{detect_all_providers, detect_declared_providers, ProviderInfo};
+use crate::env::EnvManager;
 use crate::error::{PromptGuardError, Result};
 use crate::output::Output;
 use crate::scanner::FileScanner;
-use crate::shim::{ShimGenerator, ShimInjector};
-use crate::transformer;
+use crate::shim::{ShimInjection, NODE_PRELOAD_REQUIRE};
+use crate::transformer::{self, Journal};
 use crate::types::{Language, Provider};
-use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
+#[allow(clippy::struct_excessive_bools)]
 pub struct EnableCommand {
+    pub yes: bool,
     pub runtime: bool,
+    /// Set the SDKs' base URL variables in the env file instead of editing code
+    pub env_only: bool,
+    /// How runtime mode loads the shims: `entry-points` (default), `preload`
+    /// for Node, and/or `site` for Python. Languages without a mode use entry points.
+    pub inject: Vec<String>,
+    /// What the shims do when the proxy is unreachable (`open` or `closed`);
+    /// keeps the configured mode when unset
+    pub fail_mode: Option<String>,
+    /// Turn the shims' local `.promptguard/shim.log` on or off; keeps the
+    /// configured setting when unset
+    pub shim_log: Option<bool>,
+    /// Inject into Python test modules and runners too
+    pub include_tests: bool,
+    pub json: bool,
 }
 
 impl EnableCommand {
     pub fn execute(&self) -> Result<()> {
         Output::header("Enable PromptGuard");
 
+        let injections = ShimCommand::injections(&self.inject)?;
+        let fail_mode = self
+            .fail_mode
+            .as_deref()
+            .map(|mode| {
+                ShimFailMode::parse(mode).ok_or_else(|| {
+                    PromptGuardError::Custom(format!(
+                        "Unknown fail mode '{mode}'. Use open or closed"
+                    ))
+                })
+            })
+            .transpose()?;
+
         let config_manager = ConfigManager::new(None)?;
         if !config_manager.exists() {
             return Err(PromptGuardError::NotInitialized);
         }
 
         let mut config = config_manager.load()?;
+        let mode = if self.runtime {
+            "runtime"
+        } else if self.env_only {
+            "env_only"
+        } else {
+            "static"
+        };
 
-        if config.enabled && config.runtime_mode == self.runtime {
+        if config.enabled
+            && config.runtime_mode == self.runtime
+            && config.env_only == self.env_only
+            && self.inject.is_empty()
+            && fail_mode.is_none()
+            && self.shim_log.is_none()
+            && !self.include_tests
+        {
             if self.runtime {
                 Output::warning("PromptGuard runtime mode is already enabled");
+                let root_path = crate::config::project_root()?;
+                if !self.json && ShimCommand::generator(&root_path, &config).is_outdated() {
+                    Output::line(
+                        "  Its shims are from another CLI version. Run: promptguard shim upgrade",
+                    );
+                }
             } else {
                 Output::warning("PromptGuard is already enabled");
             }
+            return self.finish(mode, false, serde_json::json!({}));
+        }
+        if let Some(fail_mode) = fail_mode {
+            config.shim_fail_mode = fail_mode;
+        }
+        if let Some(shim_log) = self.shim_log {
+            config.shim_log = shim_log;
+        }
+
+        if !self.json {
+            self.print_plan(&config, &injections);
+        }
+        if !self.yes && !Output::confirm("Continue?", true)? {
             return Ok(());
         }
 
-        // Determine mode
+        let root_path = crate::config::project_root()?;
+
+        let details = if self.runtime {
+            // Runtime shim mode
+            self.enable_runtime_mode(&root_path, &mut config, &config_manager, &injections)?
+        } else if self.env_only {
+            self.enable_env_only_mode(&root_path, &mut config, &config_manager)?
+        } else {
+            // Static transformation mode
+            self.enable_static_mode(&root_path, &mut config, &config_manager)?
+        };
+
+        self.finish(mode, config.enabled, details)
+    }
+
+    /// Print the `--json` result: `details` are the mode's own fields
+    fn finish(&self, mode: &str, enabled: bool, details: serde_json::Value) -> Result<()> {
+        if !self.json {
+            return Ok(());
+        }
+        let mut result = serde_json::json!({
+            "enabled": enabled,
+            "mode": mode,
+        });
+        if let (Some(result), serde_json::Value::Object(details)) =
+            (result.as_object_mut(), details)
+        {
+            result.extend(details);
+        }
+        Output::json(result)
+    }
+
+    fn print_plan(&self, config: &PromptGuardConfig, injections: &[ShimInjection]) {
         let mode = if self.runtime {
             "Runtime Shim Mode (100% Coverage)"
+        } else if self.env_only {
+            "Environment Variables Only (no code changes)"
         } else {
             "Static Transform Mode"
         };
 
-        println!("\nThis will enable PromptGuard using:");
-        println!("  • Mode: {mode}");
-        println!("  • Proxy URL: {}", config.proxy_url);
-        println!("  • Providers: {}", config.providers.join(", "));
-
+        Output::line("\nThis will enable PromptGuard using:");
+        Output::line(&format!("  • Mode: {mode}"));
+        Output::line(&format!("  • Proxy URL: {}", config.proxy_url));
+        Output::line(&format!("  • Providers: {}", config.providers.join(", ")));
         if self.runtime {
-            println!("\nRuntime mode provides:");
-            println!("  ✓ 100% coverage of all SDK calls");
-            println!("  ✓ Catches dynamic URL construction");
-            println!("  ✓ Works with environment variables");
-            println!("  ✓ No code modification needed");
+            Output::line(&format!(
+                "  • Proxy unreachable: fail {}",
+                config.shim_fail_mode.as_str()
+            ));
+            if config.shim_log {
+                Output::line("  • Shim log: .promptguard/shim.log");
+            }
         }
-
-        if !Output::confirm("Continue?", true)? {
-            return Ok(());
+        if self.runtime && injections.contains(&ShimInjection::Preload) {
+            Output::line(&format!("  • Node shim loading: {NODE_PRELOAD_REQUIRE}"));
+        }
+        if self.runtime && injections.contains(&ShimInjection::Site) {
+            Output::line("  • Python shim loading: .pth file in the virtualenv");
+        }
+        if self.runtime && injections.contains(&ShimInjection::Docker) {
+            Output::line("  • Docker: shim variables in docker-compose services");
         }
-
-        let root_path = std::env::current_dir()?;
 
         if self.runtime {
-            // Runtime shim mode
-            self.enable_runtime_mode(&root_path, &mut config, &config_manager)?;
-        } else {
-            // Static transformation mode
-            Self::enable_static_mode(&root_path, &mut config, &config_manager)?;
+            Output::line("\nRuntime mode provides:");
+            Output::line("  ✓ 100% coverage of all SDK calls");
+            Output::line("  ✓ Catches dynamic URL construction");
+            Output::line("  ✓ Works with environment variables");
+            Output::line("  ✓ No code modification needed");
         }
-
-        Ok(())
     }
 
     fn enable_runtime_mode(
         &self,
         root_path: &PathBuf,
-        config: &mut crate::config::PromptGuardConfig,
+        config: &mut PromptGuardConfig,
         config_manager: &ConfigManager,
-    ) -> Result<()> {
+        injections: &[ShimInjection],
+    ) -> Result<serde_json::Value> {
         Output::section("Scanning project...", "🔍");
 
         // Scan for SDK usage to detect languages
@@ -85,26 +187,18 @@ impl EnableCommand {
 
         Output::step(&format!("Scanning {} files...", files.len()));
 
-        let mut detected_languages = HashSet::new();
+        let languages = ShimCommand::detect_languages(&files);
 
-        for file_path in &files {
-            if let Some(ext) = file_path.extension().and_then(|e| e.to_str()) {
-                if let Some(lang) = Language::from_extension(ext) {
-                    detected_languages.insert(lang);
-                }
-            }
-        }
-
-        if detected_languages.is_empty() {
+        if languages.is_empty() {
             Output::warning("No supported languages detected");
-            return Ok(());
+            return Ok(serde_json::json!({ "languages": [] }));
         }
 
         Output::step(&format!(
             "Detected languages: {}",
-            detected_languages
+            languages
                 .iter()
-                .map(super::super::types::Language::as_str)
+                .map(Language::as_str)
                 .collect::<Vec<_>>()
                 .join(", ")
         ));
@@ -112,21 +206,12 @@ impl EnableCommand {
         // Generate runtime shims
         Output::section("Generating runtime shims...", "⚙️");
 
-        let providers: Vec<Provider> = config
-            .providers
-            .iter()
-            .filter_map(|p| Provider::parse(p))
-            .collect();
-
-        let generator = ShimGenerator::new(
-            root_path,
-            config.proxy_url.clone(),
-            config.env_var_name.clone(),
-            providers.clone(),
-        );
+        let generator = ShimCommand::generator(root_path, config);
+        let injector = ShimCommand::injector(root_path, config).with_tests(self.include_tests);
+        // Fails before writing anything when there is no virtualenv to install into
+        let plan = ShimPlan::new(&injector, root_path, languages, injections)?;
 
-        let languages: Vec<Language> = detected_languages.into_iter().collect();
-        let shim_files = generator.generate_shims(&languages)?;
+        let shim_files = ShimCommand::generate_files(&generator, &plan)?;
 
         for shim_file in &shim_files {
             let rel_path = shim_file.strip_prefix(root_path).unwrap_or(shim_file);
@@ -136,37 +221,9 @@ impl EnableCommand {
         // Inject shim imports into entry points
         Output::section("Injecting shim imports...", "💉");
 
-        let injector = ShimInjector::new(root_path);
-        let mut total_injected = 0;
-
-        for language in &languages {
-            match language {
-                Language::Python => {
-                    let injected = injector.inject_shims(Language::Python)?;
-                    for entry_point in &injected {
-                        let rel_path = entry_point.strip_prefix(root_path).unwrap_or(entry_point);
-                        Output::step(&format!("✓ Injected into {}", rel_path.display()));
-                        total_injected += 1;
-                    }
-                },
-                Language::TypeScript | Language::JavaScript => {
-                    let entry_points = injector.detect_typescript_entry_points()?;
-                    if !entry_points.is_empty() {
-                        println!("\n  TypeScript/JavaScript entry points detected:");
-                        for entry_point in &entry_points {
-                            let rel_path =
-                                entry_point.strip_prefix(root_path).unwrap_or(entry_point);
-                            println!("    - {}", rel_path.display());
-                        }
-                        println!("\n  To complete setup, choose one:");
-                        println!("    1. Add this import to each entry file:");
-                        println!("       import './.promptguard/promptguard-shim';");
-                        println!("\n    2. Or use tsconfig.json path aliases (recommended):");
-                        println!("       See .promptguard/README.md for instructions");
-                    }
-                },
-            }
-        }
+        let injected =
+            ShimCommand::inject(&injector, &plan, &root_path.join(config.env_file.primary()))?;
+        injected.print(&plan, root_path, config.env_file.primary());
 
         // Scan environment variables
         Output::section("Checking environment variables...", "🌍");
@@ -174,35 +231,172 @@ impl EnableCommand {
         let env_scanner = EnvScanner::new(root_path);
         let env_report = env_scanner.generate_report()?;
 
-        if !env_report.is_empty() && !env_report.contains("No environment variables") {
-            println!("\n{env_report}");
-            println!("  Recommendation: Ensure API_URL variables point to PromptGuard proxy:");
-            println!("    {}", config.proxy_url);
-        } else {
+        if env_report.is_empty() || env_report.contains("No environment variables") {
             Output::step("No environment variable configuration needed");
+        } else if !self.json {
+            Output::line(&format!("\n{env_report}"));
+            Output::line("  Recommendation: Ensure API_URL variables point to PromptGuard proxy:");
+            Output::line(&format!("    {}", config.proxy_url));
         }
 
         // Update config
         config.enabled = true;
         config.runtime_mode = true;
+        config.env_only = false;
+        config.metadata.shim_checksums = generator.checksums()?;
         config_manager.save(config)?;
 
-        println!();
-        Output::success("PromptGuard runtime mode enabled!");
-        println!("\n  • Shim files generated: {}", shim_files.len());
-        println!("  • Entry points injected: {total_injected}");
-        println!("\n  Coverage: 100% - All SDK calls will route through PromptGuard");
-        println!("\n  Shim directory: .promptguard/");
-        println!("  (Safe to commit to version control)");
+        let relative = |paths: &[PathBuf]| {
+            paths
+                .iter()
+                .map(|p| p.strip_prefix(root_path).unwrap_or(p).display().to_string())
+                .collect::<Vec<_>>()
+        };
+        if !self.json {
+            Output::line("");
+            Output::success("PromptGuard runtime mode enabled!");
+            Output::line(&format!("\n  • Shim files generated: {}", shim_files.len()));
+            Output::line(&format!(
+                "  • Entry points injected: {}",
+                injected.entry_points.len()
+            ));
+            if plan.preload {
+                Output::line(&format!(
+                    "  • Start scripts preloading the shim: {}",
+                    injected.start_scripts.len()
+                ));
+            }
+            Output::line("\n  Coverage: 100% - All SDK calls will route through PromptGuard");
+            Output::line("\n  Shim directory: .promptguard/");
+            Output::line("  (Safe to commit to version control)");
+        }
 
-        Ok(())
+        Ok(serde_json::json!({
+            "languages": plan.languages.iter().map(Language::as_str).collect::<Vec<_>>(),
+            "shim_files": relative(&shim_files),
+            "entry_points": relative(&injected.entry_points),
+            "start_scripts": injected.start_scripts,
+            "fail_mode": config.shim_fail_mode.as_str(),
+            "shim_log": config.shim_log,
+        }))
+    }
+
+    fn enable_env_only_mode(
+        &self,
+        root_path: &Path,
+        config: &mut PromptGuardConfig,
+        config_manager: &ConfigManager,
+    ) -> Result<serde_json::Value> {
+        Output::section("Setting base URL variables...", "🌍");
+
+        let env_path = root_path.join(config.env_file.primary());
+        let providers: Vec<Provider> = config
+            .providers
+            .iter()
+            .filter_map(|p| Provider::parse(p))
+            .collect();
+        let mut env_vars = BTreeMap::new();
+        for provider in &providers {
+            let vars = ProviderInfo::get(*provider).base_url_env_vars();
+            if vars.is_empty() {
+                Output::warning(&format!(
+                    "{} SDKs don't read a base URL from the environment; route them with `promptguard enable` or `enable --runtime`",
+                    provider.display_name()
+                ));
+            }
+            for var in vars {
+                let url = config.proxy_url.for_provider(*provider);
+                // Another endpoint the project set itself is not overwritten
+                if let Some(current) = EnvManager::get_key(&env_path, var).filter(|current| {
+                    current != url && !config.metadata.env_only_vars.contains_key(var)
+                }) {
+                    Output::warning(&format!(
+                        "{var} is already set to {current} in {}; left unchanged",
+                        config.env_file.primary()
+                    ));
+                    continue;
+                }
+                EnvManager::add_or_update_key(&env_path, var, url)?;
+                Output::step(&format!("✓ Set {var} in {}", config.env_file.primary()));
+                env_vars.insert(var.to_string(), url.to_string());
+            }
+        }
+
+        // The variables only work if the installed SDKs read them
+        Output::section("Checking SDK versions...", "🔍");
+
+        let mut unsupported = Vec::new();
+        for dep in detect_declared_providers(root_path)
+            .iter()
+            .filter(|d| providers.contains(&d.provider))
+        {
+            let manifest = dep
+                .manifest
+                .strip_prefix(root_path)
+                .unwrap_or(&dep.manifest);
+            let version = dep.version.as_deref().unwrap_or("unpinned");
+            match dep.base_url_env_var() {
+                Some(var) if !dep.ignores_base_url_env() => Output::step(&format!(
+                    "✓ {} {version} ({}) reads {var}",
+                    dep.package,
+                    manifest.display()
+                )),
+                Some(var) => {
+                    unsupported.push(dep.package.clone());
+                    Output::warning(&format!(
+                        "{} {version} ({}) predates {var}; upgrade to {} or later",
+                        dep.package,
+                        manifest.display(),
+                        dep.min_base_url_env_version().unwrap_or_default()
+                    ));
+                },
+                None => {
+                    unsupported.push(dep.package.clone());
+                    Output::warning(&format!(
+                        "{} ({}) doesn't read a base URL from the environment",
+                        dep.package,
+                        manifest.display()
+                    ));
+                },
+            }
+        }
+
+        config.enabled = true;
+        config.runtime_mode = false;
+        config.env_only = true;
+        config.metadata.env_only_vars.extend(env_vars.clone());
+        config_manager.save(config)?;
+
+        if !self.json {
+            Output::line("");
+            Output::success("PromptGuard enabled through environment variables!");
+            Output::line(&format!("\n  • Variables set: {}", env_vars.len()));
+            if !unsupported.is_empty() {
+                Output::line(&format!(
+                    "  • SDKs that won't pick them up: {}",
+                    unsupported.len()
+                ));
+            }
+            Output::line(&format!(
+                "\n  Load {} into the app's environment (dotenv, docker --env-file, your platform's settings).",
+                config.env_file.primary()
+            ));
+            Output::line("  No source files were changed.");
+        }
+
+        Ok(serde_json::json!({
+            "env_file": config.env_file.primary(),
+            "variables": env_vars,
+            "unsupported_sdks": unsupported,
+        }))
     }
 
     fn enable_static_mode(
+        &self,
         root_path: &PathBuf,
-        config: &mut crate::config::PromptGuardConfig,
+        config: &mut PromptGuardConfig,
         config_manager: &ConfigManager,
-    ) -> Result<()> {
+    ) -> Result<serde_json::Value> {
         Output::section("Scanning files...", "📁");
 
         let scanner = FileScanner::new(root_path, Some(config.exclude_patterns.clone()))?;
@@ -217,7 +411,7 @@ impl EnableCommand {
             .filter_map(|p| Provider::parse(p))
             .collect();
 
-        let mut detection_results: HashMap<Provider, Vec<PathBuf>> = HashMap::new();
+        let mut detection_results: BTreeMap<Provider, Vec<PathBuf>> = BTreeMap::new();
 
         for file_path in &files {
             if let Ok(results) = detect_all_providers(file_path) {
@@ -234,12 +428,14 @@ impl EnableCommand {
 
         if detection_results.is_empty() {
             Output::warning("No SDK instances found to transform.");
-            return Ok(());
+            return Ok(serde_json::json!({ "files_modified": [] }));
         }
 
         Output::section("Applying transformations...", "🔧");
 
-        let mut files_modified = 0;
+        let mut files_modified = Vec::new();
+        let mut journal = Journal::load(root_path)?;
+        let mut dynamic_calls = Vec::new();
 
         for (provider, files) in &detection_results {
             let mut unique_files = files.clone();
@@ -247,17 +443,21 @@ impl EnableCommand {
             unique_files.dedup();
 
             for file_path in unique_files {
+                dynamic_calls.extend(ApplyCommand::dynamic_calls(
+                    &file_path, root_path, *provider,
+                ));
                 match transformer::transform_file(
                     &file_path,
                     *provider,
-                    &config.proxy_url,
-                    &config.env_var_name,
+                    config.proxy_url.for_provider(*provider),
+                    config.env_var_for(*provider),
                 ) {
                     Ok(result) => {
+                        journal.record(root_path, &file_path, *provider, &result.edits);
                         if result.modified {
-                            files_modified += 1;
                             let rel_path = file_path.strip_prefix(root_path).unwrap_or(&file_path);
                             Output::step(&format!("✓ {}", rel_path.display()));
+                            files_modified.push(rel_path.display().to_string());
                         }
                     },
                     Err(e) => {
@@ -270,18 +470,26 @@ impl EnableCommand {
                 }
             }
         }
+        journal.save(root_path)?;
+        ApplyCommand::warn_dynamic_config(&dynamic_calls);
 
         // Update config
         config.enabled = true;
         config.runtime_mode = false;
+        config.env_only = false;
         config_manager.save(config)?;
         Output::step("Updated configuration");
 
-        println!();
-        Output::success("PromptGuard enabled!");
-        println!("\n  • {files_modified} files modified");
-        println!("\nYour LLM requests will now go through PromptGuard.");
+        if !self.json {
+            Output::line("");
+            Output::success("PromptGuard enabled!");
+            Output::line(&format!("\n  • {} files modified", files_modified.len()));
+            Output::line("\nYour LLM requests will now go through PromptGuard.");
+        }
 
-        Ok(())
+        Ok(serde_json::json!({
+            "files_modified": files_modified,
+            "dynamic_config": dynamic_calls,
+        }))
     }
 }
@@ -25,10 +25,7 @@ impl EventsCommand {
         let events: serde_json::Value = client.get(&endpoint)?;
 
         if self.json {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&events).unwrap_or_default()
-            );
+            Output::json(serde_json::to_value(&events)?)?;
             return Ok(());
         }
 
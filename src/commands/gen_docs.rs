@@ -0,0 +1,315 @@
+//! Gen Docs Command - Man pages and Markdown reference
+//!
+//! Renders the clap command tree as one man page per command
+//! (`promptguard-scan.1`, ...) and a single Markdown reference, so the
+//! documentation always matches the flags the binary accepts.
+
+use crate::error::Result;
+use crate::output::Output;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+/// Commands in the tree, parents first, without hidden ones or `help`
+fn commands(command: &clap::Command) -> Vec<&clap::Command> {
+    let mut all = vec![command];
+    for sub in command
+        .get_subcommands()
+        .filter(|c| !c.is_hide_set() && c.get_name() != "help")
+    {
+        all.extend(commands(sub));
+    }
+    all
+}
+
+/// `promptguard scan` for the `scan` subcommand
+fn display_name(command: &clap::Command) -> String {
+    command
+        .get_bin_name()
+        .unwrap_or_else(|| command.get_name())
+        .to_string()
+}
+
+fn usage(command: &clap::Command) -> String {
+    let usage = command.clone().render_usage().to_string();
+    usage
+        .strip_prefix("Usage: ")
+        .unwrap_or(&usage)
+        .trim()
+        .to_string()
+}
+
+/// Options documented for `command`: globals only on the root
+fn options(command: &clap::Command, is_root: bool) -> Vec<&clap::Arg> {
+    command
+        .get_arguments()
+        .filter(|a| !a.is_hide_set() && !a.is_positional())
+        .filter(|a| is_root || !a.is_global_set())
+        .filter(|a| !matches!(a.get_id().as_str(), "help" | "version"))
+        .collect()
+}
+
+fn positionals(command: &clap::Command) -> Vec<&clap::Arg> {
+    command
+        .get_arguments()
+        .filter(|a| !a.is_hide_set() && a.is_positional())
+        .collect()
+}
+
+/// `-o, --output <FILE>`
+fn arg_names(arg: &clap::Arg) -> String {
+    let mut names: Vec<String> = arg
+        .get_short()
+        .map(|s| format!("-{s}"))
+        .into_iter()
+        .collect();
+    names.extend(arg.get_long().map(|l| format!("--{l}")));
+    names.extend(
+        arg.get_visible_aliases()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|l| format!("--{l}")),
+    );
+    let mut out = names.join(", ");
+    if arg.get_action().takes_values() {
+        let _ = write!(out, " <{}>", value_name(arg));
+    }
+    out
+}
+
+fn value_name(arg: &clap::Arg) -> String {
+    arg.get_value_names()
+        .and_then(|names| names.first())
+        .map_or_else(|| arg.get_id().as_str().to_uppercase(), ToString::to_string)
+}
+
+fn help(arg: &clap::Arg) -> String {
+    arg.get_long_help()
+        .or_else(|| arg.get_help())
+        .map(ToString::to_string)
+        .unwrap_or_default()
+}
+
+/// "Possible values: a, b. Default: a", or empty
+fn value_notes(arg: &clap::Arg) -> String {
+    let mut notes = Vec::new();
+    let values: Vec<String> = arg
+        .get_possible_values()
+        .iter()
+        .filter(|v| !v.is_hide_set())
+        .map(|v| v.get_name().to_string())
+        .collect();
+    if !values.is_empty() && arg.get_action().takes_values() {
+        notes.push(format!("Possible values: {}", values.join(", ")));
+    }
+    let defaults: Vec<String> = arg
+        .get_default_values()
+        .iter()
+        .map(|v| v.to_string_lossy().to_string())
+        .collect();
+    if !defaults.is_empty() && arg.get_action().takes_values() {
+        notes.push(format!("Default: {}", defaults.join(",")));
+    }
+    notes.join(". ")
+}
+
+fn about(command: &clap::Command) -> String {
+    command
+        .get_about()
+        .map(ToString::to_string)
+        .unwrap_or_default()
+}
+
+fn long_about(command: &clap::Command) -> String {
+    command
+        .get_long_about()
+        .or_else(|| command.get_about())
+        .map(ToString::to_string)
+        .unwrap_or_default()
+}
+
+/// Escape text for roff, including a leading control character
+fn roff(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let line = line.replace('\\', "\\e").replace('-', "\\-");
+            if line.starts_with('.') || line.starts_with('\'') {
+                format!("\\&{line}")
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn man_page(command: &clap::Command, is_root: bool, version: &str) -> String {
+    let name = display_name(command);
+    let page = name.replace(' ', "-");
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        ".TH {} 1 \"\" \"{}\" \"User Commands\"",
+        page.to_uppercase(),
+        roff(&format!("promptguard {version}"))
+    );
+    let _ = writeln!(
+        out,
+        ".SH NAME\n{} \\- {}",
+        roff(&page),
+        roff(&about(command))
+    );
+    let _ = writeln!(out, ".SH SYNOPSIS\n\\fB{}\\fR", roff(&usage(command)));
+
+    let description = long_about(command);
+    if !description.is_empty() {
+        let paragraphs: Vec<String> = description.split("\n\n").map(|p| roff(p.trim())).collect();
+        let _ = writeln!(out, ".SH DESCRIPTION\n{}", paragraphs.join("\n.PP\n"));
+    }
+
+    let args: Vec<&clap::Arg> = positionals(command)
+        .into_iter()
+        .chain(options(command, is_root))
+        .collect();
+    if !args.is_empty() {
+        let _ = writeln!(out, ".SH OPTIONS");
+        for arg in args {
+            let names = if arg.is_positional() {
+                format!("<{}>", value_name(arg))
+            } else {
+                arg_names(arg)
+            };
+            let _ = writeln!(out, ".TP\n\\fB{}\\fR\n{}", roff(&names), roff(&help(arg)));
+            let notes = value_notes(arg);
+            if !notes.is_empty() {
+                let _ = writeln!(out, ".IP\n{}", roff(&notes));
+            }
+        }
+    }
+
+    let subcommands: Vec<&clap::Command> = command
+        .get_subcommands()
+        .filter(|c| !c.is_hide_set() && c.get_name() != "help")
+        .collect();
+    if !subcommands.is_empty() {
+        let _ = writeln!(out, ".SH SUBCOMMANDS");
+        for sub in subcommands {
+            let _ = writeln!(
+                out,
+                ".TP\n\\fB{}\\fR(1)\n{}",
+                roff(&display_name(sub).replace(' ', "-")),
+                roff(&about(sub))
+            );
+        }
+    }
+
+    if let Some((parent, _)) = name.rsplit_once(' ') {
+        let _ = writeln!(
+            out,
+            ".SH SEE ALSO\n\\fB{}\\fR(1)",
+            roff(&parent.replace(' ', "-"))
+        );
+    }
+    out
+}
+
+/// GitHub-style heading anchor
+fn anchor(heading: &str) -> String {
+    heading
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+fn markdown(command: &clap::Command) -> String {
+    let all = commands(command);
+    let mut out = String::from(
+        "# Command-Line Reference\n\nGenerated by `promptguard gen-docs` from the CLI \
+         definitions; do not edit by hand.\n\n",
+    );
+    for cmd in &all {
+        let name = display_name(cmd);
+        let depth = name.matches(' ').count();
+        let _ = writeln!(
+            out,
+            "{}- [`{name}`](#{})",
+            "  ".repeat(depth),
+            anchor(&name)
+        );
+    }
+
+    for (i, cmd) in all.iter().enumerate() {
+        let name = display_name(cmd);
+        let _ = write!(out, "\n## {name}\n\n");
+        let description = long_about(cmd);
+        if !description.is_empty() {
+            let _ = write!(out, "{}\n\n", description.trim());
+        }
+        let _ = write!(out, "```\n{}\n```\n", usage(cmd));
+
+        let args = positionals(cmd);
+        if !args.is_empty() {
+            out.push_str("\n**Arguments:**\n\n");
+            for arg in args {
+                let _ = writeln!(out, "- `<{}>`: {}", value_name(arg), md_help(arg));
+            }
+        }
+        let args = options(cmd, i == 0);
+        if !args.is_empty() {
+            out.push_str("\n**Options:**\n\n");
+            for arg in args {
+                let _ = writeln!(out, "- `{}`: {}", arg_names(arg), md_help(arg));
+            }
+        }
+    }
+    out
+}
+
+/// Help with value notes, on one line for a list item
+fn md_help(arg: &clap::Arg) -> String {
+    let mut text = help(arg).split_whitespace().collect::<Vec<_>>().join(" ");
+    let notes = value_notes(arg);
+    if !notes.is_empty() {
+        let _ = write!(text, " ({notes})");
+    }
+    text
+}
+
+pub struct GenDocsCommand {
+    pub out_dir: PathBuf,
+    pub command: clap::Command,
+}
+
+impl GenDocsCommand {
+    pub fn execute(&self) -> Result<()> {
+        let mut command = self.command.clone();
+        command.build();
+        let version = command
+            .get_version()
+            .unwrap_or(env!("CARGO_PKG_VERSION"))
+            .to_string();
+
+        let man_dir = self.out_dir.join("man");
+        fs::create_dir_all(&man_dir)?;
+        let mut pages = 0;
+        for (i, cmd) in commands(&command).into_iter().enumerate() {
+            let file = format!("{}.1", display_name(cmd).replace(' ', "-"));
+            fs::write(man_dir.join(file), man_page(cmd, i == 0, &version))?;
+            pages += 1;
+        }
+        let reference = self.out_dir.join("cli-reference.md");
+        fs::write(&reference, markdown(&command))?;
+
+        Output::success(&format!(
+            "Wrote {pages} man pages to {} and {}",
+            man_dir.display(),
+            reference.display()
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,344 @@
+//! Hook Command - Pre-commit checks for unguarded LLM calls
+//!
+//! Installs `promptguard scan --staged --fail-on unguarded` as a git
+//! pre-commit hook, a pre-commit framework local hook, or a husky hook.
+//! Everything written sits between marker comments, so uninstalling removes
+//! exactly what was added and leaves other hooks alone.
+
+use crate::error::{PromptGuardError, Result};
+use crate::output::Output;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// What every hook runs
+pub const HOOK_COMMAND: &str = "promptguard scan --staged --fail-on unguarded";
+
+const BEGIN_MARKER: &str = "# >>> promptguard pre-commit hook >>>";
+const END_MARKER: &str = "# <<< promptguard pre-commit hook <<<";
+
+const PRE_COMMIT_CONFIG: &str = ".pre-commit-config.yaml";
+const HUSKY_HOOK: &str = ".husky/pre-commit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookManager {
+    /// A script in the repository's hooks directory
+    Git,
+    /// A local hook in `.pre-commit-config.yaml`
+    PreCommit,
+    /// A line in `.husky/pre-commit`
+    Husky,
+}
+
+impl HookManager {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "git" => Ok(Self::Git),
+            "pre-commit" => Ok(Self::PreCommit),
+            "husky" => Ok(Self::Husky),
+            other => Err(PromptGuardError::Custom(format!(
+                "Unknown hook manager '{other}'. Use git, pre-commit or husky"
+            ))),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Git => "git",
+            Self::PreCommit => "pre-commit",
+            Self::Husky => "husky",
+        }
+    }
+
+    /// The manager a project already uses: the pre-commit framework, then
+    /// husky, then plain git hooks
+    pub fn detect(root: &Path) -> Self {
+        if root.join(PRE_COMMIT_CONFIG).exists() {
+            Self::PreCommit
+        } else if root.join(".husky").is_dir() {
+            Self::Husky
+        } else {
+            Self::Git
+        }
+    }
+}
+
+pub enum HookAction {
+    Install,
+    Uninstall,
+}
+
+pub struct HookCommand {
+    pub action: HookAction,
+    /// Detected from the project when not given
+    pub manager: Option<HookManager>,
+    pub json: bool,
+}
+
+/// The hooks directory git runs, honouring `core.hooksPath` and worktrees
+fn git_hooks_dir(root: &Path) -> Result<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--git-path", "hooks"])
+        .current_dir(root)
+        .output()
+        .map_err(|e| PromptGuardError::Custom(format!("Git hooks need git: {e}")))?;
+    if !output.status.success() {
+        return Err(PromptGuardError::Custom(format!(
+            "Not a git repository: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(root.join(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+/// The marked block for a shell hook. Missing `promptguard` warns instead
+/// of blocking commits for contributors who haven't installed it.
+fn shell_block() -> String {
+    format!(
+        "{BEGIN_MARKER}\n\
+         if command -v promptguard >/dev/null 2>&1; then\n  \
+           {HOOK_COMMAND} || exit $?\n\
+         else\n  \
+           echo \"promptguard not found; skipping the LLM routing check\" >&2\n\
+         fi\n\
+         {END_MARKER}\n"
+    )
+}
+
+/// The marked local hook entry, indented as an item of `repos:`
+fn pre_commit_block(indent: &str) -> String {
+    let lines = [
+        "- repo: local".to_string(),
+        "  hooks:".to_string(),
+        "    - id: promptguard".to_string(),
+        "      name: promptguard scan".to_string(),
+        format!("      entry: {HOOK_COMMAND}"),
+        "      language: system".to_string(),
+        "      pass_filenames: false".to_string(),
+    ];
+    let mut block = format!("{indent}{BEGIN_MARKER}\n");
+    for line in lines {
+        block.push_str(indent);
+        block.push_str(&line);
+        block.push('\n');
+    }
+    block.push_str(indent);
+    block.push_str(END_MARKER);
+    block.push('\n');
+    block
+}
+
+/// `content` without the marked block, or `None` when there is none
+fn remove_block(content: &str) -> Option<String> {
+    let mut inside = false;
+    let mut found = false;
+    let mut kept = String::new();
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed == BEGIN_MARKER {
+            inside = true;
+            found = true;
+        } else if trimmed == END_MARKER {
+            inside = false;
+        } else if !inside {
+            kept.push_str(line);
+        }
+    }
+    found.then_some(kept)
+}
+
+/// Insert the shell block after the shebang, so it runs even when the rest
+/// of an existing hook ends in `exec`
+fn add_to_script(existing: Option<&str>, shebang: Option<&str>) -> String {
+    let block = shell_block();
+    match existing {
+        Some(content) if content.starts_with("#!") => {
+            let (first, rest) = content
+                .split_once('\n')
+                .map_or((content, ""), |(first, rest)| (first, rest));
+            format!("{first}\n{block}{rest}")
+        },
+        Some(content) => format!("{block}{content}"),
+        None => match shebang {
+            Some(shebang) => format!("{shebang}\n{block}"),
+            None => block,
+        },
+    }
+}
+
+/// Insert the local hook at the end of the `repos:` list, creating the list
+/// when the config has none
+fn add_to_pre_commit_config(content: &str) -> Result<String> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let Some(repos) = lines.iter().position(|l| l.trim_end() == "repos:") else {
+        let mut updated = content.to_string();
+        if !updated.is_empty() && !updated.ends_with('\n') {
+            updated.push('\n');
+        }
+        updated.push_str("repos:\n");
+        updated.push_str(&pre_commit_block(""));
+        return validate_pre_commit_config(updated);
+    };
+
+    // The list ends at the next top-level key
+    let end = lines[repos + 1..]
+        .iter()
+        .position(|l| l.starts_with(|c: char| !c.is_whitespace() && c != '-' && c != '#'))
+        .map_or(lines.len(), |i| repos + 1 + i);
+    let indent = lines[repos + 1..end]
+        .iter()
+        .find(|l| l.trim_start().starts_with("- "))
+        .map_or("  ", |l| &l[..l.len() - l.trim_start().len()]);
+    // Keep trailing blank lines and comments after the new entry
+    let mut insert = end;
+    while insert > repos + 1 && {
+        let l = lines[insert - 1].trim();
+        l.is_empty() || l.starts_with('#')
+    } {
+        insert -= 1;
+    }
+
+    let mut updated: String = lines[..insert].concat();
+    if !updated.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated.push_str(&pre_commit_block(indent));
+    updated.push_str(&lines[insert..].concat());
+    validate_pre_commit_config(updated)
+}
+
+/// Refuse to write a config the pre-commit framework would fail to read
+fn validate_pre_commit_config(content: String) -> Result<String> {
+    let parsed: serde_yaml::Value = serde_yaml::from_str(&content).map_err(|e| {
+        PromptGuardError::Parse(format!("{PRE_COMMIT_CONFIG} would not be valid YAML: {e}"))
+    })?;
+    let has_hook = parsed["repos"].as_sequence().is_some_and(|repos| {
+        repos.iter().any(|repo| {
+            repo["hooks"]
+                .as_sequence()
+                .is_some_and(|hooks| hooks.iter().any(|h| h["id"] == "promptguard"))
+        })
+    });
+    if !has_hook {
+        return Err(PromptGuardError::Parse(format!(
+            "Couldn't add the hook to {PRE_COMMIT_CONFIG}; add this entry under repos: by hand:\n{}",
+            pre_commit_block("  ")
+        )));
+    }
+    Ok(content)
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = fs::metadata(path)?.permissions();
+    perms.set_mode(perms.mode() | 0o755);
+    fs::set_permissions(path, perms)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Whether a script holds anything besides a shebang and blank lines
+fn has_commands(script: &str) -> bool {
+    script
+        .lines()
+        .any(|l| !l.trim().is_empty() && !l.starts_with("#!"))
+}
+
+impl HookCommand {
+    pub fn execute(&self) -> Result<()> {
+        let root = crate::config::project_root()?;
+        let manager = self.manager.unwrap_or_else(|| HookManager::detect(&root));
+        let path = match manager {
+            HookManager::Git => git_hooks_dir(&root)?.join("pre-commit"),
+            HookManager::PreCommit => root.join(PRE_COMMIT_CONFIG),
+            HookManager::Husky => root.join(HUSKY_HOOK),
+        };
+        let existing = fs::read_to_string(&path).ok();
+        let installed = existing
+            .as_deref()
+            .is_some_and(|c| c.lines().any(|l| l.trim() == BEGIN_MARKER));
+
+        let changed = match self.action {
+            HookAction::Install => {
+                if !installed {
+                    Self::install(manager, &path, existing.as_deref())?;
+                }
+                !installed
+            },
+            HookAction::Uninstall => {
+                if let Some(remaining) = existing.as_deref().and_then(remove_block) {
+                    let empty = match manager {
+                        HookManager::PreCommit => remaining.trim() == "repos:",
+                        _ => !has_commands(&remaining),
+                    };
+                    if empty {
+                        fs::remove_file(&path)?;
+                    } else {
+                        fs::write(&path, remaining)?;
+                    }
+                }
+                installed
+            },
+        };
+
+        let display = path.strip_prefix(&root).unwrap_or(&path).display();
+        if self.json {
+            Output::json(serde_json::json!({
+                "action": match self.action {
+                    HookAction::Install => "install",
+                    HookAction::Uninstall => "uninstall",
+                },
+                "manager": manager.as_str(),
+                "path": display.to_string(),
+                "changed": changed,
+                "command": HOOK_COMMAND,
+            }))?;
+            return Ok(());
+        }
+
+        match (&self.action, changed) {
+            (HookAction::Install, true) => {
+                Output::success(&format!(
+                    "Installed {} pre-commit hook in {display}",
+                    manager.as_str()
+                ));
+                Output::step(&format!("Commits now run: {HOOK_COMMAND}"));
+                if manager == HookManager::PreCommit {
+                    Output::step("Run 'pre-commit install' if the framework isn't set up yet");
+                }
+            },
+            (HookAction::Install, false) => {
+                Output::info(&format!("Pre-commit hook already installed in {display}"));
+            },
+            (HookAction::Uninstall, true) => {
+                Output::success(&format!("Removed the pre-commit hook from {display}"));
+            },
+            (HookAction::Uninstall, false) => {
+                Output::info(&format!("No PromptGuard pre-commit hook in {display}"));
+            },
+        }
+        Ok(())
+    }
+
+    fn install(manager: HookManager, path: &Path, existing: Option<&str>) -> Result<()> {
+        let content = match manager {
+            HookManager::Git => add_to_script(existing, Some("#!/bin/sh")),
+            // husky v9 hooks are plain command lists without a shebang
+            HookManager::Husky => add_to_script(existing, None),
+            HookManager::PreCommit => add_to_pre_commit_config(existing.unwrap_or_default())?,
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+        if manager != HookManager::PreCommit {
+            make_executable(path)?;
+        }
+        Ok(())
+    }
+}
@@ -1,31 +1,77 @@
 use crate::api::PromptGuardClient;
-use crate::config::{ConfigManager, PromptGuardConfig};
+use crate::commands::ApplyCommand;
+use crate::config::{ConfigManager, EnvFile, PromptGuardConfig, ProxyUrl};
 use crate::detector::detect_all_providers;
 use crate::detector::ProviderInfo;
 use crate::env::EnvManager;
-use crate::error::Result;
-use crate::output::Output;
-use crate::scanner::FileScanner;
-use crate::transformer;
+use crate::error::{PromptGuardError, Result};
+use crate::output::{Output, Progress};
+use crate::scanner::{FileScanner, Workspace};
+use crate::transformer::{self, Journal};
 use crate::types::Provider;
-use std::collections::HashMap;
-use std::io::Write;
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
+#[allow(clippy::struct_excessive_bools)]
 pub struct InitCommand {
     pub provider: Vec<String>,
     pub api_key: Option<String>,
     pub base_url: String,
-    pub env_file: String,
+    /// Env files to write the API key to; the first is the primary
+    pub env_file: Vec<String>,
     pub auto: bool,
     pub dry_run: bool,
     pub force: bool,
     pub exclude: Vec<String>,
     pub framework: Option<String>,
+    /// Write one config per workspace package instead of a single root config
+    pub per_package: bool,
+    /// Backend project to link the config to
+    pub project: Option<String>,
+    /// Walk through providers, framework and proxy URL before applying
+    pub interactive: bool,
+    /// Don't contact the backend; the key is only checked for format
+    pub offline: bool,
+    pub json: bool,
 }
 
+/// What `init` did (or would do with `--dry-run`), printed with `--json`
+#[derive(Default, Serialize)]
+struct InitResult {
+    /// False when nothing was written: a dry run, or init stopped early
+    changed: bool,
+    dry_run: bool,
+    providers: Vec<String>,
+    framework: Option<String>,
+    files_modified: Vec<String>,
+    /// Per-file diffs, with `--dry-run`
+    diffs: Vec<serde_json::Value>,
+    config_files: Vec<String>,
+    env_files: Vec<String>,
+}
+
+/// Files `save_config` wrote (or would write), relative to the project root
+struct SavedConfig {
+    config_file: String,
+    env_files: Vec<String>,
+}
+
+impl InitResult {
+    fn add(&mut self, saved: SavedConfig) {
+        self.config_files.push(saved.config_file);
+        self.env_files.extend(saved.env_files);
+    }
+}
+
+/// Frameworks `detect_framework` knows, offered by the wizard
+const FRAMEWORKS: &[&str] = &["nextjs", "express", "django", "fastapi", "flask"];
+
+/// Lines per page of the wizard's change preview
+const PREVIEW_PAGE_LINES: usize = 20;
+
 impl InitCommand {
-    pub fn execute(&self) -> Result<()> {
+    pub fn execute(mut self) -> Result<()> {
         if !self.dry_run {
             Output::header(&format!(
                 "🛡️  PromptGuard CLI v{}",
@@ -34,9 +80,13 @@ impl InitCommand {
         }
 
         // Check for git repository (Linus-approved safety)
-        let root_path = std::env::current_dir()?;
+        let root_path = crate::config::project_root()?;
+        let mut result = InitResult {
+            dry_run: self.dry_run,
+            ..InitResult::default()
+        };
         if !self.check_version_control(&root_path)? {
-            return Ok(());
+            return self.finish(&result);
         }
 
         // Check if already initialized
@@ -44,12 +94,18 @@ impl InitCommand {
         if config_manager.exists() && !self.dry_run {
             Output::warning("PromptGuard is already initialized in this project.");
             if !self.auto && !Output::confirm("Reinitialize?", false)? {
-                return Ok(());
+                return self.finish(&result);
             }
         }
 
         // Get API key
         let api_key = self.get_api_key()?;
+        // Before any source file is touched; package env files are checked as they are written
+        if !self.dry_run && !self.per_package {
+            for env_file in &self.env_file {
+                EnvManager::check_key_destination(&root_path.join(env_file), &api_key)?;
+            }
+        }
 
         // Scan project
         Output::section("Scanning project...", "📁");
@@ -70,7 +126,7 @@ impl InitCommand {
             ));
         }
 
-        let framework = self
+        let mut framework = self
             .framework
             .clone()
             .or_else(|| scanner.detect_framework());
@@ -78,6 +134,23 @@ impl InitCommand {
             Output::step(&format!("Detected framework: {fw}"));
         }
 
+        let workspace = Workspace::discover(&root_path);
+        if let Some(ref ws) = workspace {
+            let kinds: Vec<&str> = ws
+                .kinds
+                .iter()
+                .map(crate::scanner::workspace::WorkspaceKind::as_str)
+                .collect();
+            Output::step(&format!(
+                "Detected {} workspace ({} packages)",
+                kinds.join(" + "),
+                ws.packages.len()
+            ));
+        } else if self.per_package {
+            Output::warning("No workspace packages found; creating a single root config");
+        }
+        let per_package = self.per_package && workspace.is_some();
+
         let files = scanner.scan_files(None)?;
         Output::step(&format!("Scanning {} files...", files.len()));
 
@@ -99,12 +172,15 @@ impl InitCommand {
                     .collect()
             };
 
-        let mut detection_results: HashMap<Provider, Vec<PathBuf>> = HashMap::new();
+        let mut detection_results: BTreeMap<Provider, Vec<PathBuf>> = BTreeMap::new();
 
+        let mut progress = Progress::new("Scanning", files.len(), false);
         for file_path in &files {
+            progress.inc_file(file_path.strip_prefix(&root_path).unwrap_or(file_path));
             if let Ok(results) = detect_all_providers(file_path) {
                 for (provider, result) in results {
                     if providers_to_check.contains(&provider) && !result.instances.is_empty() {
+                        progress.found(result.instances.len());
                         detection_results
                             .entry(provider)
                             .or_default()
@@ -113,16 +189,19 @@ impl InitCommand {
                 }
             }
         }
+        progress.finish();
 
         if detection_results.is_empty() {
             Output::error("No LLM SDKs detected in this project.");
-            println!("\nPromptGuard works with:");
-            println!("  • OpenAI SDK (openai)");
-            println!("  • Anthropic SDK (@anthropic-ai/sdk)");
-            println!("  • Cohere SDK (cohere-ai)");
-            println!("  • HuggingFace SDK (@huggingface/inference)");
-            println!("\nMake sure you've installed one of these SDKs.");
-            return Ok(());
+            if !self.json {
+                Output::line("\nPromptGuard works with:");
+                Output::line("  • OpenAI SDK (openai)");
+                Output::line("  • Anthropic SDK (@anthropic-ai/sdk)");
+                Output::line("  • Cohere SDK (cohere-ai)");
+                Output::line("  • HuggingFace SDK (@huggingface/inference)");
+                Output::line("\nMake sure you've installed one of these SDKs.");
+            }
+            return self.finish(&result);
         }
 
         for (provider, files) in &detection_results {
@@ -130,11 +209,13 @@ impl InitCommand {
             unique_files.sort();
             unique_files.dedup();
 
-            println!(
-                "   • {} SDK ({} files)",
-                provider.display_name(),
-                unique_files.len()
-            );
+            if !self.json {
+                Output::line(&format!(
+                    "   • {} SDK ({} files)",
+                    provider.display_name(),
+                    unique_files.len()
+                ));
+            }
             for file in unique_files.iter().take(5) {
                 let rel_path = file.strip_prefix(&root_path).unwrap_or(file);
                 Output::step(&rel_path.display().to_string());
@@ -144,28 +225,44 @@ impl InitCommand {
             }
         }
 
+        if self.interactive && !self.wizard(&mut detection_results, &mut framework)? {
+            return self.finish(&result);
+        }
+
         // Show configuration
-        println!();
-        Output::section("Configuration:", "📝");
-        println!("   • Proxy URL: {}", self.base_url);
-        println!("   • Environment: {}", self.env_file);
-        println!("   • Version control: Git (backups via git diff/revert)");
+        if !self.json {
+            Output::line("");
+            Output::section("Configuration:", "📝");
+            Output::line(&format!("   • Proxy URL: {}", self.base_url));
+            Output::line(&format!("   • Environment: {}", self.env_file.join(", ")));
+            Output::line("   • Version control: Git (backups via git diff/revert)");
+        }
+
+        if self.interactive {
+            Self::preview(&root_path, &detection_results, &self.base_url)?;
+        }
 
         // Confirm changes
         if !self.auto && !self.dry_run {
-            println!();
+            if !self.json {
+                Output::line("");
+            }
             if !Output::confirm("Apply these changes?", true)? {
-                return Ok(());
+                return self.finish(&result);
             }
         }
 
         if self.dry_run {
-            println!();
+            if !self.json {
+                Output::line("");
+            }
             Output::info("DRY RUN - no changes will be made");
         }
 
         // Apply transformations
-        println!();
+        if !self.json {
+            Output::line("");
+        }
         Output::section(
             if self.dry_run {
                 "Preview:"
@@ -176,6 +273,8 @@ impl InitCommand {
         );
 
         let mut files_modified = Vec::new();
+        let mut journal = Journal::load(&root_path)?;
+        let mut dynamic_calls = Vec::new();
 
         for (provider, files) in &detection_results {
             let mut unique_files = files.clone();
@@ -183,27 +282,54 @@ impl InitCommand {
             unique_files.dedup();
 
             for file_path in unique_files {
-                match transformer::transform_file(
-                    &file_path,
-                    *provider,
-                    &self.base_url,
-                    "PROMPTGUARD_API_KEY",
-                ) {
-                    Ok(result) => {
-                        if result.modified && !self.dry_run {
+                dynamic_calls.extend(ApplyCommand::dynamic_calls(
+                    &file_path, &root_path, *provider,
+                ));
+                let rel_path = file_path.strip_prefix(&root_path).unwrap_or(&file_path);
+                let outcome = if self.dry_run {
+                    transformer::preview_diff(
+                        &file_path,
+                        &rel_path.display().to_string(),
+                        *provider,
+                        &self.base_url,
+                        "PROMPTGUARD_API_KEY",
+                    )
+                } else {
+                    transformer::transform_file(
+                        &file_path,
+                        *provider,
+                        &self.base_url,
+                        "PROMPTGUARD_API_KEY",
+                    )
+                    .map(|result| {
+                        journal.record(&root_path, &file_path, *provider, &result.edits);
+                        result.modified.then(String::new)
+                    })
+                };
+                match outcome {
+                    Ok(diff) => {
+                        if let Some(diff) = diff {
                             files_modified.push(file_path.clone());
-                        }
-
-                        let rel_path = file_path.strip_prefix(&root_path).unwrap_or(&file_path);
 
-                        if result.modified {
                             let info = ProviderInfo::get(*provider);
                             Output::step(&format!(
-                                "{} (added {} for {})",
+                                "{} ({} {} for {})",
                                 rel_path.display(),
+                                if self.dry_run { "would add" } else { "added" },
                                 info.ts_base_url_param,
                                 provider.display_name()
                             ));
+                            if self.json {
+                                if self.dry_run {
+                                    result.diffs.push(serde_json::json!({
+                                        "file": rel_path.display().to_string(),
+                                        "provider": provider.as_str(),
+                                        "diff": diff,
+                                    }));
+                                }
+                            } else {
+                                Output::diff(&diff);
+                            }
                         } else {
                             Output::excluded(&format!(
                                 "{} (no changes needed)",
@@ -222,76 +348,350 @@ impl InitCommand {
             }
         }
 
-        // Update .env file
+        if !self.dry_run {
+            journal.save(&root_path)?;
+        }
+        ApplyCommand::warn_dynamic_config(&dynamic_calls);
+
         // Security: Validate env_file doesn't escape project directory
-        if self.env_file.contains("..") || self.env_file.starts_with('/') {
+        if self
+            .env_file
+            .iter()
+            .any(|file| file.is_empty() || file.contains("..") || file.starts_with('/'))
+        {
             return Err(crate::error::PromptGuardError::Custom(
                 "Invalid env file path: must be relative and within project directory".to_string(),
             ));
         }
-        let env_path = root_path.join(&self.env_file);
+
+        result.providers = detection_results
+            .keys()
+            .map(|p| p.as_str().to_string())
+            .collect();
+        result.framework.clone_from(&framework);
+        result.files_modified = files_modified
+            .iter()
+            .map(|f| {
+                f.strip_prefix(&root_path)
+                    .unwrap_or(f)
+                    .display()
+                    .to_string()
+            })
+            .collect();
+        match workspace {
+            Some(ref ws) if per_package => self.save_package_configs(
+                ws,
+                &api_key,
+                &detection_results,
+                &files_modified,
+                &mut result,
+            )?,
+            _ => {
+                let saved = self.save_config(
+                    &root_path,
+                    &api_key,
+                    result.providers.clone(),
+                    &files_modified,
+                    framework,
+                )?;
+                result.add(saved);
+            },
+        }
+        result.changed = !self.dry_run;
+
+        if self.json {
+            return self.finish(&result);
+        }
+
+        // Summary
+        Output::line("");
         if !self.dry_run {
-            EnvManager::add_or_update_key(&env_path, "PROMPTGUARD_API_KEY", &api_key)?;
-            Output::step(&format!("{} (added PROMPTGUARD_API_KEY)", self.env_file));
+            Output::success("PromptGuard is now active!");
+            Output::line("\nNext steps:");
+            Output::line("  • Run your app normally - all LLM requests now go through PromptGuard");
+            Output::line("  • View logs: promptguard logs --follow");
+            Output::line("  • Check dashboard: https://app.promptguard.co/dashboard");
+            if self.offline || crate::airgap::is_enabled() {
+                Output::line("  • Once the backend is reachable: promptguard test (validates the key and proxy)");
+            }
+            Output::line(
+                "\n💡 To revert changes: promptguard revert (or git diff | git checkout -- .)",
+            );
         } else {
-            Output::step(&format!(
-                "{} (would add PROMPTGUARD_API_KEY)",
-                self.env_file
+            Output::line(&format!(
+                "✓ {} files would be modified",
+                files_modified.len()
             ));
+            Output::line(&format!(
+                "✓ {} config file(s) would be created (.promptguard.json)",
+                result.config_files.len()
+            ));
+            Output::line("\nTo apply: promptguard init");
         }
 
-        // Save configuration
-        if !self.dry_run {
-            let providers_list: Vec<String> = detection_results
-                .keys()
-                .map(|p| p.as_str().to_string())
-                .collect();
+        Output::line("\nNeed help? https://docs.promptguard.co/cli");
 
-            let mut config =
-                PromptGuardConfig::new(api_key, self.base_url.clone(), providers_list)?;
+        Ok(())
+    }
 
-            config.exclude_patterns = if self.exclude.is_empty() {
-                crate::config::default_exclude_patterns()
-            } else {
-                self.exclude.clone()
-            };
+    /// Print the `--json` result
+    fn finish(&self, result: &InitResult) -> Result<()> {
+        if !self.json {
+            return Ok(());
+        }
+        Output::json(serde_json::to_value(result)?)
+    }
 
-            config.env_file = self.env_file.clone();
-            config.framework = framework;
+    /// Ask which detected providers to route, confirm the framework and
+    /// read the proxy URL. Returns false if nothing is left to set up.
+    fn wizard(
+        &mut self,
+        detection_results: &mut BTreeMap<Provider, Vec<PathBuf>>,
+        framework: &mut Option<String>,
+    ) -> Result<bool> {
+        Output::line("");
+        Output::section("Setup:", "🧭");
+
+        let providers: Vec<Provider> = detection_results.keys().copied().collect();
+        let options: Vec<String> = detection_results
+            .iter()
+            .map(|(provider, files)| {
+                let mut unique = files.clone();
+                unique.sort();
+                unique.dedup();
+                format!("{} ({} files)", provider.display_name(), unique.len())
+            })
+            .collect();
+        let mut selected = vec![true; options.len()];
+        Output::select_many(
+            "Providers to route through PromptGuard",
+            &options,
+            &mut selected,
+        )?;
+        for (provider, keep) in providers.iter().zip(&selected) {
+            if !keep {
+                detection_results.remove(provider);
+            }
+        }
+        if detection_results.is_empty() {
+            Output::info("No providers selected; nothing to set up");
+            return Ok(false);
+        }
 
-            config.metadata.files_managed = files_modified
-                .iter()
-                .map(|f| {
-                    f.strip_prefix(&root_path)
-                        .unwrap_or(f)
-                        .to_string_lossy()
-                        .to_string()
-                })
-                .collect();
+        let keep_detected = match framework.as_deref() {
+            Some(fw) => Output::confirm(&format!("Framework is {fw}. Correct?"), true)?,
+            None => false,
+        };
+        if !keep_detected {
+            let answer = Output::input_default(
+                &format!("Framework ({}, or 'none')", FRAMEWORKS.join(", ")),
+                framework.as_deref().unwrap_or("none"),
+                |answer| {
+                    let answer = answer.to_lowercase();
+                    if answer == "none" || FRAMEWORKS.contains(&answer.as_str()) {
+                        Ok(())
+                    } else {
+                        Err(format!("Unknown framework '{answer}'"))
+                    }
+                },
+            )?
+            .to_lowercase();
+            *framework = (answer != "none").then_some(answer);
+        }
 
-            config_manager.save(&config)?;
-            Output::step(".promptguard.json (created)");
-        } else {
-            Output::step(".promptguard.json (would be created)");
+        self.base_url = Output::input_default("Proxy URL", &self.base_url, |answer| {
+            Self::validate_base_url(answer).map_err(|e| e.to_string())
+        })?;
+        Ok(true)
+    }
+
+    /// The rules `.promptguard.json` enforces, checked up front so the
+    /// wizard can ask again
+    fn validate_base_url(url: &str) -> Result<()> {
+        reqwest::Url::parse(url)
+            .ok()
+            .filter(|u| u.host_str().is_some())
+            .ok_or_else(|| PromptGuardError::Config(format!("'{url}' is not a valid URL")))?;
+        ProxyUrl::from(url.to_string()).validate()
+    }
+
+    /// Page through the diff of every file the transform would change
+    fn preview(
+        root_path: &Path,
+        detection_results: &BTreeMap<Provider, Vec<PathBuf>>,
+        base_url: &str,
+    ) -> Result<()> {
+        let mut diffs = String::new();
+        for (provider, files) in detection_results {
+            let mut unique_files = files.clone();
+            unique_files.sort();
+            unique_files.dedup();
+            for file_path in unique_files {
+                let rel_path = file_path.strip_prefix(root_path).unwrap_or(&file_path);
+                if let Ok(Some(diff)) = transformer::preview_diff(
+                    &file_path,
+                    &rel_path.display().to_string(),
+                    *provider,
+                    base_url,
+                    "PROMPTGUARD_API_KEY",
+                ) {
+                    diffs.push_str(&diff);
+                }
+            }
         }
+        if diffs.is_empty() {
+            return Ok(());
+        }
+        Output::line("");
+        Output::section("Planned changes:", "🔎");
+        Output::page(&diffs, PREVIEW_PAGE_LINES)
+    }
+
+    /// Update the env file and write `.promptguard.json` for one project
+    /// directory. Returns the files written, relative to the project root.
+    fn save_config(
+        &self,
+        dir: &Path,
+        api_key: &str,
+        providers: Vec<String>,
+        files_modified: &[PathBuf],
+        framework: Option<String>,
+    ) -> Result<SavedConfig> {
+        let root_path = crate::config::project_root()?;
+        let display = |name: &str| {
+            dir.join(name)
+                .strip_prefix(&root_path)
+                .map_or_else(|_| name.to_string(), |p| p.display().to_string())
+        };
 
-        // Summary
-        println!();
         if !self.dry_run {
-            Output::success("PromptGuard is now active!");
-            println!("\nNext steps:");
-            println!("  • Run your app normally - all LLM requests now go through PromptGuard");
-            println!("  • View logs: promptguard logs --follow");
-            println!("  • Check dashboard: https://app.promptguard.co/dashboard");
-            println!("\n💡 To revert changes: git diff (review) | git checkout -- . (undo)");
-        } else {
-            println!("✓ {} files would be modified", files_modified.len());
-            println!("✓ 1 file would be created (.promptguard.json)");
-            println!("\nTo apply: promptguard init");
+            for env_file in &self.env_file {
+                EnvManager::check_key_destination(&dir.join(env_file), api_key)?;
+            }
         }
 
-        println!("\nNeed help? https://docs.promptguard.co/cli");
+        // Every env file gets the key, so they start out in sync
+        let saved = SavedConfig {
+            config_file: display(".promptguard.json"),
+            env_files: self.env_file.iter().map(|f| display(f)).collect(),
+        };
+        for env_file in &self.env_file {
+            let env_path = dir.join(env_file);
+            if !self.dry_run {
+                EnvManager::add_or_update_key(&env_path, "PROMPTGUARD_API_KEY", api_key)?;
+                Output::step(&format!(
+                    "{} (added PROMPTGUARD_API_KEY)",
+                    display(env_file)
+                ));
+                continue;
+            }
+            Output::step(&format!(
+                "{} (would add PROMPTGUARD_API_KEY)",
+                display(env_file)
+            ));
+            if self.json {
+                continue;
+            }
+            let current = std::fs::read_to_string(&env_path).unwrap_or_default();
+            let updated = EnvManager::with_key(
+                &current,
+                "PROMPTGUARD_API_KEY",
+                &Output::mask_api_key(api_key),
+            );
+            let old_label = if env_path.exists() {
+                format!("a/{}", display(env_file))
+            } else {
+                "/dev/null".to_string()
+            };
+            Output::diff(&transformer::unified_diff(
+                &current,
+                &updated,
+                &old_label,
+                &format!("b/{}", display(env_file)),
+            ));
+        }
+
+        if self.dry_run {
+            Output::step(&format!("{} (would be created)", saved.config_file));
+            return Ok(saved);
+        }
+
+        let mut config =
+            PromptGuardConfig::new(api_key.to_string(), self.base_url.clone(), providers)?;
+
+        config.exclude_patterns = if self.exclude.is_empty() {
+            crate::config::default_exclude_patterns()
+        } else {
+            self.exclude.clone()
+        };
+
+        config.env_file = match self.env_file.as_slice() {
+            [file] => EnvFile::Single(file.clone()),
+            files => EnvFile::Many(files.to_vec()),
+        };
+        config.framework = framework;
+        config.project_id.clone_from(&self.project);
+
+        config.metadata.files_managed = files_modified
+            .iter()
+            .map(|f| {
+                f.strip_prefix(dir)
+                    .unwrap_or(f)
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+
+        ConfigManager::new(Some(dir.join(".promptguard.json")))?.save(&config)?;
+        Output::step(&format!("{} (created)", saved.config_file));
+        Ok(saved)
+    }
 
+    /// Write a config into every workspace package that uses an LLM SDK
+    fn save_package_configs(
+        &self,
+        workspace: &Workspace,
+        api_key: &str,
+        detection_results: &BTreeMap<Provider, Vec<PathBuf>>,
+        files_modified: &[PathBuf],
+        result: &mut InitResult,
+    ) -> Result<()> {
+        for package in &workspace.packages {
+            let in_package = |f: &PathBuf| {
+                workspace
+                    .package_for(f)
+                    .is_some_and(|p| p.path == package.path)
+            };
+
+            let mut providers: Vec<String> = detection_results
+                .iter()
+                .filter(|(_, files)| files.iter().any(in_package))
+                .map(|(p, _)| p.as_str().to_string())
+                .collect();
+            if providers.is_empty() {
+                continue;
+            }
+            providers.sort();
+
+            let package_files: Vec<PathBuf> = files_modified
+                .iter()
+                .filter(|f| in_package(f))
+                .cloned()
+                .collect();
+            let framework = self.framework.clone().or_else(|| {
+                FileScanner::new(&package.path, None)
+                    .ok()?
+                    .detect_framework()
+            });
+
+            result.add(self.save_config(
+                &package.path,
+                api_key,
+                providers,
+                &package_files,
+                framework,
+            )?);
+        }
         Ok(())
     }
 
@@ -299,28 +699,41 @@ impl InitCommand {
         let git_dir = root_path.join(".git");
 
         if !git_dir.exists() {
-            println!();
-            Output::warning("⚠️  NOT A GIT REPOSITORY");
-            println!();
-            println!("PromptGuard will modify your source files.");
-            println!("Without version control, you cannot easily revert these changes.");
-            println!();
-            println!("Recommended:");
-            println!("  git init");
-            println!("  git add .");
-            println!("  git commit -m 'Initial commit before PromptGuard'");
-            println!("  promptguard init");
-            println!();
+            if self.json {
+                // stdout carries only the result, so the advice is cut to a warning
+                if !self.force {
+                    Output::warning("Not a git repository; pass --force to proceed anyway");
+                    return Ok(false);
+                }
+            } else {
+                Output::line("");
+                Output::warning("⚠️  NOT A GIT REPOSITORY");
+                Output::line("");
+                Output::line("PromptGuard will modify your source files.");
+                Output::line(
+                    "Without version control, only 'promptguard revert' can undo these changes,",
+                );
+                Output::line("and only PromptGuard's own edits.");
+                Output::line("");
+                Output::line("Recommended:");
+                Output::line("  git init");
+                Output::line("  git add .");
+                Output::line("  git commit -m 'Initial commit before PromptGuard'");
+                Output::line("  promptguard init");
+                Output::line("");
+
+                if !self.force {
+                    Output::line("To proceed anyway: promptguard init --force");
+                    Output::line("");
+                    return Ok(false);
+                }
 
-            if !self.force {
-                println!("To proceed anyway: promptguard init --force");
-                println!();
-                return Ok(false);
+                Output::line(
+                    "⚠️  Proceeding with --force ('promptguard revert' will undo the edits)",
+                );
+                Output::line("");
             }
 
-            println!("⚠️  Proceeding with --force (no backups will be created)");
-            println!();
-
             if !self.auto
                 && !self.dry_run
                 && !Output::confirm(
@@ -340,32 +753,29 @@ impl InitCommand {
             key.clone()
         } else if let Ok(key) = std::env::var("PROMPTGUARD_API_KEY") {
             key
-        } else if !self.auto && !self.dry_run {
+        } else if let Some(key) = crate::config::user::UserConfig::load_or_default().api_key {
+            Output::info("Using the API key from your user config");
+            key
+        } else if !self.auto && !self.dry_run && !self.json && Output::can_prompt() {
             // Interactive mode - offer signup flow
             println!();
             Output::section("API Key Required", "🔑");
             println!();
             println!("You need a PromptGuard API key to continue.");
             println!();
-            println!("Options:");
-            println!("  1. I have an API key");
-            println!("  2. Sign up / Get API key");
-            println!("  3. Cancel");
-            println!();
 
-            let mut choice = String::new();
-            print!("Select option (1-3): ");
-            std::io::stdout().flush()?;
-            std::io::stdin().read_line(&mut choice)?;
-            let choice = choice.trim();
+            let choice = Output::select(
+                "Select an option",
+                &["I have an API key", "Sign up / Get API key", "Cancel"],
+            )?;
 
             match choice {
-                "1" => {
+                0 => {
                     // User has API key - prompt for it
                     println!();
                     Output::input("🔑 Paste your PromptGuard API key")?
                 },
-                "2" => {
+                1 => {
                     // Signup flow
                     println!();
                     Output::info("Opening signup page in your browser...");
@@ -416,8 +826,12 @@ impl InitCommand {
             return Err(crate::error::PromptGuardError::InvalidApiKey);
         }
 
-        // Validate API key against the backend (skip in dry-run mode)
-        if !self.dry_run {
+        // Validate API key against the backend (skip in dry-run, offline and air-gapped mode)
+        if crate::airgap::is_enabled() {
+            Output::info("Air-gapped mode: skipping online API key validation");
+        } else if self.offline {
+            Output::info("Offline: API key format checked; skipping backend validation");
+        } else if !self.dry_run {
             Output::info("Validating API key...");
 
             let client = PromptGuardClient::new(api_key.clone(), Some(self.base_url.clone()))?;
@@ -428,12 +842,14 @@ impl InitCommand {
                 },
                 Err(e) => {
                     Output::warning(&format!("Could not validate API key: {e}"));
-                    println!();
-                    println!("This could mean:");
-                    println!("  • The API key is invalid or expired");
-                    println!("  • The PromptGuard API is temporarily unavailable");
-                    println!("  • Network connectivity issues");
-                    println!();
+                    if !self.json {
+                        Output::line("");
+                        Output::line("This could mean:");
+                        Output::line("  • The API key is invalid or expired");
+                        Output::line("  • The PromptGuard API is temporarily unavailable");
+                        Output::line("  • Network connectivity issues");
+                        Output::line("");
+                    }
 
                     if !self.auto && !Output::confirm("Continue anyway?", false)? {
                         return Err(crate::error::PromptGuardError::Custom(
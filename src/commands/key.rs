@@ -1,91 +1,148 @@
-use crate::config::ConfigManager;
+use crate::api::PromptGuardClient;
+use crate::auth::resolve_base_url;
+use crate::config::{find_api_key, ApiKeySource, ConfigManager, EnvFile, PromptGuardConfig};
 use crate::env::EnvManager;
 use crate::error::{PromptGuardError, Result};
 use crate::output::Output;
-use std::io::{self, Write};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
 
-pub struct KeyCommand;
+/// Answer to `POST /keys/rotate`: a new key of the same type as the old one
+#[derive(Debug, Deserialize)]
+struct RotatedKey {
+    key: String,
+    #[serde(default)]
+    key_id: Option<String>,
+    /// Id of the key that was rotated out, used to revoke it
+    #[serde(default)]
+    previous_key_id: Option<String>,
+}
 
-impl KeyCommand {
-    pub fn execute() -> Result<()> {
-        Output::header("API Key Management");
+pub enum KeyAction {
+    /// Write a key to the active profile's env files, or to `env_file`
+    Set { key: String, env_file: Vec<String> },
+    /// The key in every env file of the config and its profiles
+    List,
+}
+
+pub struct KeyCommand {
+    /// Rotate the key straight away instead of showing the menu
+    pub rotate: bool,
+    /// Revoke the old key after rotating without asking
+    pub yes: bool,
+    pub action: Option<KeyAction>,
+    pub json: bool,
+}
+
+/// How `key list` names the top-level settings
+const DEFAULT_PROFILE_LABEL: &str = "default";
+
+/// An env file a profile reads its key from
+#[derive(Debug, Serialize)]
+struct KeyEntry {
+    file: String,
+    env_var: String,
+    /// Profiles using this file and variable; `default` is the top level
+    profiles: Vec<String>,
+    /// Masked key, if the file sets one
+    key: Option<String>,
+    key_type: Option<&'static str>,
+    committed: bool,
+}
+
+/// `test` or `prod` from the key's prefix, `invalid` for anything else
+fn key_type(key: &str) -> &'static str {
+    if key.starts_with("pg_sk_test_") {
+        "test"
+    } else if key.starts_with("pg_sk_prod_") {
+        "prod"
+    } else {
+        "invalid"
+    }
+}
 
+impl KeyCommand {
+    pub fn execute(&self) -> Result<()> {
         let config_manager = ConfigManager::new(None)?;
         if !config_manager.exists() {
             return Err(PromptGuardError::NotInitialized);
         }
+        let root_path = crate::config::project_root()?;
+        match &self.action {
+            Some(KeyAction::Set { key, env_file }) => {
+                return self.set(&config_manager, &root_path, key, env_file);
+            },
+            Some(KeyAction::List) => return self.list(&config_manager, &root_path),
+            None => {},
+        }
 
+        Output::header("API Key Management");
         let mut config = config_manager.load()?;
-        let root_path = std::env::current_dir()?;
-        let env_path = root_path.join(&config.env_file);
+        if self.rotate {
+            return self.rotate(&config_manager, &mut config, &root_path);
+        }
+        if self.json {
+            // No menu for scripts: report the active key
+            let found = find_api_key(&root_path, &config);
+            return Output::json(serde_json::json!({
+                "env_var": config.env_var_name,
+                "env_file": config.env_file,
+                "key": found.as_ref().map(|(key, _)| Output::mask_api_key(key)),
+                "key_type": found.as_ref().map(|(key, _)| key_type(key)),
+                "source": found.as_ref().map(|(_, source)| source.as_str()),
+            }));
+        }
 
         // Show current key (masked)
         println!("\nCurrent API key:");
-        println!(
-            "  {} = {}...{}",
-            config.env_var_name,
-            &config.api_key[..12], // pg_sk_test_
-            if config.api_key.len() > 16 {
-                &config.api_key[config.api_key.len() - 4..]
-            } else {
-                ""
-            }
-        );
-
-        println!("\nOptions:");
-        println!("  1. Update API key");
-        println!("  2. Show full key (masked)");
-        println!("  3. Rotate key");
-        println!("  4. Cancel");
-
-        print!("\nSelect option (1-4): ");
-        io::stdout().flush()?;
+        if config.api_key().is_empty() {
+            println!("  {} = (not set)", config.env_var_name);
+        } else {
+            println!(
+                "  {} = {}",
+                config.env_var_name,
+                Output::mask_api_key(config.api_key())
+            );
+        }
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
+        println!();
+        let choice = Output::select(
+            "Select an option",
+            &["Update API key", "Show full key", "Rotate key", "Cancel"],
+        )?;
 
-        match input.trim() {
-            "1" => {
+        match choice {
+            0 => {
                 // Update API key
-                print!("Enter new API key: ");
-                io::stdout().flush()?;
-
-                let mut new_key = String::new();
-                io::stdin().read_line(&mut new_key)?;
-                let new_key = new_key.trim().to_string();
+                let new_key = Output::input("Enter new API key")?;
 
                 // Validate key format
                 if !new_key.starts_with("pg_sk_test_") && !new_key.starts_with("pg_sk_prod_") {
                     return Err(PromptGuardError::InvalidApiKey);
                 }
 
-                // Update config
-                config.api_key = new_key.clone();
-                config_manager.save(&config)?;
-
-                // Update .env
-                EnvManager::add_or_update_key(&env_path, &config.env_var_name, &new_key)?;
+                // The key lives in the env files only, never in .promptguard.json
+                config
+                    .env_file
+                    .set_key(&root_path, &config.env_var_name, &new_key)?;
 
                 Output::success("API key updated successfully!");
-                println!("\nThe new key has been saved to:");
-                println!("  • .promptguard.json");
-                println!("  • {}", config.env_file);
+                Output::line(&format!(
+                    "\nThe new key has been saved to {} ({})",
+                    config.env_file, config.env_var_name
+                ));
             },
-            "2" => {
+            1 => {
                 // Show full key
                 println!("\nFull API key:");
-                println!("  {}", config.api_key);
-                println!("\n⚠️  Keep this key secure. Don't share it publicly.");
-            },
-            "3" => {
-                // Rotate key - requires API call
-                Output::info("Key rotation requires API access.");
-                println!("\nTo rotate your API key:");
-                println!("  1. Visit: https://app.promptguard.co/settings/api-keys");
-                println!("  2. Generate a new key");
-                println!("  3. Run: promptguard key");
-                println!("  4. Select option 1 to update");
+                println!("  {}", config.api_key());
+                println!(
+                    "\n{}",
+                    Output::plain("⚠️  Keep this key secure. Don't share it publicly.")
+                );
             },
+            2 => self.rotate(&config_manager, &mut config, &root_path)?,
             _ => {
                 Output::info("Cancelled");
             },
@@ -93,4 +150,222 @@ impl KeyCommand {
 
         Ok(())
     }
+
+    /// Mint a new key, write it where the old one was read from, and revoke
+    /// the old key once the new one is confirmed to work
+    fn rotate(
+        &self,
+        config_manager: &ConfigManager,
+        config: &mut PromptGuardConfig,
+        root_path: &Path,
+    ) -> Result<()> {
+        let Some((old_key, source)) = find_api_key(root_path, config) else {
+            return Err(PromptGuardError::Config(format!(
+                "No API key to rotate. Set {} first with 'promptguard key'",
+                config.env_var_name
+            )));
+        };
+
+        Output::info("Requesting a new API key...");
+        let base_url = resolve_base_url();
+        let client = PromptGuardClient::new(old_key, Some(base_url.clone()))?;
+        let rotated: RotatedKey = client
+            .post("/keys/rotate", &serde_json::json!({}))
+            .map_err(|e| PromptGuardError::Api(format!("Key rotation failed: {e}")))?;
+        if !rotated.key.starts_with("pg_sk_test_") && !rotated.key.starts_with("pg_sk_prod_") {
+            return Err(PromptGuardError::Api(
+                "Key rotation returned an invalid key; the old key is unchanged".to_string(),
+            ));
+        }
+
+        // The key lives in the env files only, never in .promptguard.json
+        config
+            .env_file
+            .set_key(root_path, &config.env_var_name, &rotated.key)?;
+        config.metadata.key_id.clone_from(&rotated.key_id);
+        config.metadata.key_rotated_at = Some(Utc::now());
+        config_manager.save(config)?;
+        Output::success(&format!(
+            "New key {} saved to {} ({})",
+            Output::mask_api_key(&rotated.key),
+            config.env_file,
+            config.env_var_name
+        ));
+        if source != ApiKeySource::EnvFile {
+            Output::warning(&format!(
+                "The old key was read from the {}; replace it there too, or it keeps taking precedence",
+                source.as_str()
+            ));
+        }
+
+        let result = |verified: bool, revoked: bool| {
+            serde_json::json!({
+                "action": "rotate",
+                "key": Output::mask_api_key(&rotated.key),
+                "key_type": key_type(&rotated.key),
+                "key_id": rotated.key_id,
+                "previous_key_id": rotated.previous_key_id,
+                "env_file": config.env_file,
+                "env_var": config.env_var_name,
+                "verified": verified,
+                "old_key_revoked": revoked,
+            })
+        };
+
+        let new_client = PromptGuardClient::new(rotated.key.clone(), Some(base_url))?;
+        if let Err(e) = new_client.health_check() {
+            Output::warning(&format!(
+                "Could not verify the new key ({e}); the old key was left active"
+            ));
+            return self.finish(result(false, false));
+        }
+
+        let Some(previous_key_id) = rotated.previous_key_id.as_deref() else {
+            Output::info(
+                "Revoke the old key at https://app.promptguard.co/settings/api-keys once everything uses the new one",
+            );
+            return self.finish(result(true, false));
+        };
+        if !self.json {
+            Output::line("\nDeployments and teammates still using the old key will stop working once it is revoked.");
+        }
+        let revoked = self.yes
+            || (Output::can_prompt() && Output::confirm("Revoke the old key now?", false)?);
+        if revoked {
+            let _: serde_json::Value = new_client.post(
+                &format!("/keys/{previous_key_id}/revoke"),
+                &serde_json::json!({}),
+            )?;
+            Output::success("Old key revoked");
+        } else {
+            Output::info(&format!(
+                "Old key left active. Revoke it later at https://app.promptguard.co/settings/api-keys (id {previous_key_id})"
+            ));
+        }
+
+        self.finish(result(true, revoked))
+    }
+
+    /// Print a `--json` result; the human output is already written
+    fn finish(&self, result: serde_json::Value) -> Result<()> {
+        if self.json {
+            Output::json(result)?;
+        }
+        Ok(())
+    }
+
+    fn set(
+        &self,
+        config_manager: &ConfigManager,
+        root_path: &Path,
+        key: &str,
+        env_file: &[String],
+    ) -> Result<()> {
+        if key_type(key) == "invalid" {
+            return Err(PromptGuardError::InvalidApiKey);
+        }
+        let config = config_manager.load()?;
+        let target = if env_file.is_empty() {
+            config.env_file.clone()
+        } else {
+            EnvFile::Many(env_file.to_vec())
+        };
+        target.set_key(root_path, &config.env_var_name, key)?;
+
+        Output::success(&format!(
+            "{} key {} saved to {target} ({})",
+            key_type(key),
+            Output::mask_api_key(key),
+            config.env_var_name
+        ));
+        self.finish(serde_json::json!({
+            "action": "set",
+            "key": Output::mask_api_key(key),
+            "key_type": key_type(key),
+            "env_files": target.files(),
+            "env_var": config.env_var_name,
+        }))
+    }
+
+    fn list(&self, config_manager: &ConfigManager, root_path: &Path) -> Result<()> {
+        let top_level = config_manager.load_profile(None)?;
+        let mut configs = vec![(DEFAULT_PROFILE_LABEL.to_string(), top_level.clone())];
+        for name in top_level.profiles.keys() {
+            configs.push((name.clone(), config_manager.load_profile(Some(name))?));
+        }
+
+        let mut entries: Vec<KeyEntry> = Vec::new();
+        for (profile, config) in &configs {
+            for file in config.env_file.files() {
+                if let Some(entry) = entries
+                    .iter_mut()
+                    .find(|e| e.file == *file && e.env_var == config.env_var_name)
+                {
+                    entry.profiles.push(profile.clone());
+                    continue;
+                }
+                let path = root_path.join(file);
+                let key =
+                    EnvManager::get_key(&path, &config.env_var_name).filter(|k| !k.is_empty());
+                entries.push(KeyEntry {
+                    file: file.clone(),
+                    env_var: config.env_var_name.clone(),
+                    profiles: vec![profile.clone()],
+                    key_type: key.as_deref().map(key_type),
+                    key: key.as_deref().map(Output::mask_api_key),
+                    committed: EnvManager::is_committed(&path),
+                });
+            }
+        }
+
+        let mut problems = Vec::new();
+        for entry in &entries {
+            match entry.key_type {
+                Some("prod") if entry.committed => problems.push(format!(
+                    "{} holds a production key but is not ignored by git",
+                    entry.file
+                )),
+                Some("invalid") => problems.push(format!(
+                    "{} sets {} to something that isn't a PromptGuard key",
+                    entry.file, entry.env_var
+                )),
+                _ => {},
+            }
+        }
+
+        if self.json {
+            Output::json(serde_json::json!({
+                "entries": entries,
+                "problems": problems,
+            }))?;
+        } else {
+            Output::header("API Keys by Env File");
+            let file_width = entries.iter().map(|e| e.file.len()).max().unwrap_or(0);
+            for entry in &entries {
+                let key = match (&entry.key, entry.key_type) {
+                    (Some(key), Some(kind)) => format!("{key} ({kind})"),
+                    _ => "(not set)".to_string(),
+                };
+                Output::line(&format!(
+                    "  {:<file_width$}  {}  {key}{}",
+                    entry.file,
+                    entry.profiles.join(", "),
+                    if entry.committed { "  [committed]" } else { "" }
+                ));
+            }
+            for problem in &problems {
+                Output::warning(problem);
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(PromptGuardError::Findings(format!(
+                "{} key problem{} found",
+                problems.len(),
+                if problems.len() == 1 { "" } else { "s" }
+            )))
+        }
+    }
 }
@@ -1,33 +1,52 @@
 use crate::api::PromptGuardClient;
-use crate::auth::{save_credentials, GlobalCredentials};
+use crate::auth::device::DeviceFlow;
+use crate::auth::{resolve_base_url, save_credentials, GlobalCredentials};
 use crate::error::Result;
 use crate::output::Output;
+use chrono::{TimeDelta, Utc};
 
 pub struct LoginCommand {
+    /// Log in with this key instead of the browser
     pub api_key: Option<String>,
     pub base_url: Option<String>,
+    /// Print the device-flow URL without opening it
+    pub no_browser: bool,
     pub json: bool,
 }
 
 impl LoginCommand {
     pub fn execute(&self) -> Result<()> {
-        let api_key = if let Some(key) = &self.api_key {
-            key.clone()
-        } else {
-            Output::info("Log in to PromptGuard. Get your API key at https://app.promptguard.co");
-            Output::input("API key")?
+        let creds = match &self.api_key {
+            Some(api_key) => {
+                if api_key.trim().is_empty() {
+                    Output::error("API key cannot be empty");
+                    return Ok(());
+                }
+                self.key_login(api_key)?
+            },
+            None => self.device_login()?,
         };
+        save_credentials(&creds)?;
 
-        if api_key.trim().is_empty() {
-            Output::error("API key cannot be empty");
-            return Ok(());
+        if self.json {
+            let result = serde_json::json!({
+                "status": "authenticated",
+                "method": if creds.api_key.is_empty() { "device" } else { "api_key" },
+                "expires_at": creds.token_expires_at,
+                "credentials_path": "~/.promptguard/credentials.json"
+            });
+            Output::json(result)?;
+        } else {
+            Output::success("Logged in. Credentials saved to ~/.promptguard/credentials.json");
         }
 
-        let base_url = self.base_url.clone();
+        Ok(())
+    }
 
+    fn key_login(&self, api_key: &str) -> Result<GlobalCredentials> {
         // Validate the key
         Output::info("Validating API key...");
-        let client = PromptGuardClient::new(api_key.clone(), base_url.clone())?;
+        let client = PromptGuardClient::new(api_key.to_string(), self.base_url.clone())?;
         match client.health_check() {
             Ok(()) => Output::success("API key is valid"),
             Err(e) => {
@@ -36,26 +55,42 @@ impl LoginCommand {
             },
         }
 
-        let creds = GlobalCredentials {
-            api_key,
-            base_url,
-            active_project: None,
-        };
-        save_credentials(&creds)?;
+        Ok(GlobalCredentials {
+            api_key: api_key.to_string(),
+            base_url: self.base_url.clone(),
+            ..GlobalCredentials::default()
+        })
+    }
 
-        if self.json {
-            let result = serde_json::json!({
-                "status": "authenticated",
-                "credentials_path": "~/.promptguard/credentials.json"
-            });
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&result).unwrap_or_default()
-            );
-        } else {
-            Output::success("Logged in. Credentials saved to ~/.promptguard/credentials.json");
+    /// Approve the CLI in the browser and keep the access token it's issued
+    fn device_login(&self) -> Result<GlobalCredentials> {
+        let base_url = self.base_url.clone().unwrap_or_else(resolve_base_url);
+        let flow = DeviceFlow::new(&base_url)?;
+        let code = flow.start()?;
+        let url = code
+            .verification_uri_complete
+            .as_deref()
+            .unwrap_or(&code.verification_uri);
+
+        // Instructions go to stderr so --json output stays parseable
+        eprintln!(
+            "To log in, open {} and enter the code:\n\n    {}\n",
+            code.verification_uri, code.user_code
+        );
+        if !self.no_browser && !self.json && open::that(url).is_err() {
+            Output::warning("Could not open a browser; open the URL above manually");
         }
+        eprintln!("Waiting for approval (Ctrl+C to cancel)...");
 
-        Ok(())
+        let token = flow.wait(&code)?;
+        Ok(GlobalCredentials {
+            access_token: Some(token.access_token),
+            token_expires_at: token
+                .expires_in
+                .and_then(|secs| TimeDelta::try_seconds(i64::try_from(secs).ok()?))
+                .map(|lifetime| Utc::now() + lifetime),
+            base_url: self.base_url.clone(),
+            ..GlobalCredentials::default()
+        })
     }
 }
@@ -12,10 +12,7 @@ impl LogoutCommand {
 
         if self.json {
             let result = serde_json::json!({ "status": "logged_out" });
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&result).unwrap_or_default()
-            );
+            Output::json(result)?;
         } else {
             Output::success("Logged out. Credentials removed from ~/.promptguard/credentials.json");
         }
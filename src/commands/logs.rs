@@ -1,9 +1,16 @@
 use crate::api::PromptGuardClient;
 use crate::config::ConfigManager;
+use crate::detector::registry::PROVIDERS;
 use crate::error::{PromptGuardError, Result};
 use crate::output::Output;
+use crate::types::Provider;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::fmt::Write;
+use std::io::Write as _;
+use std::thread;
+use std::time::Duration;
 
 /// Log entry from the API
 #[derive(Debug, Deserialize, Serialize)]
@@ -17,6 +24,8 @@ pub struct LogEntry {
     #[serde(default)]
     pub decision: Option<String>,
     #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
     pub threat_type: Option<String>,
     #[serde(default)]
     pub confidence: Option<f64>,
@@ -35,9 +44,46 @@ struct LogsResponse {
     has_more: bool,
 }
 
+/// Decision a log entry records, for `--decision`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    Blocked,
+    Allowed,
+}
+
+impl Decision {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "blocked" | "block" => Some(Self::Blocked),
+            "allowed" | "allow" => Some(Self::Allowed),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Blocked => "blocked",
+            Self::Allowed => "allowed",
+        }
+    }
+
+    /// The API writes both the verb and the past tense
+    fn matches(self, decision: &str) -> bool {
+        Self::parse(decision) == Some(self)
+    }
+}
+
 pub struct LogsCommand {
     pub limit: usize,
     pub log_type: Option<String>,
+    /// Only entries newer than this: a duration back from now (`30m`, `24h`,
+    /// `7d`, `2w`), a date or an RFC 3339 timestamp
+    pub since: Option<String>,
+    pub provider: Option<String>,
+    /// `blocked` or `allowed`
+    pub decision: Option<String>,
+    /// Keep polling for new entries
+    pub follow: bool,
     pub json: bool,
 }
 
@@ -46,6 +92,10 @@ impl Default for LogsCommand {
         Self {
             limit: 20,
             log_type: None,
+            since: None,
+            provider: None,
+            decision: None,
+            follow: false,
             json: false,
         }
     }
@@ -58,36 +108,69 @@ impl LogsCommand {
             return Err(PromptGuardError::NotInitialized);
         }
 
+        let since = self
+            .since
+            .as_deref()
+            .map(|since| parse_since(since, Utc::now()))
+            .transpose()?;
+        let provider = self
+            .provider
+            .as_deref()
+            .map(|name| {
+                Provider::parse(name).ok_or_else(|| {
+                    let known: Vec<&str> = PROVIDERS.iter().map(|p| p.provider.as_str()).collect();
+                    PromptGuardError::Custom(format!(
+                        "Unknown provider '{name}'. Providers: {}",
+                        known.join(", ")
+                    ))
+                })
+            })
+            .transpose()?;
+        let decision = self
+            .decision
+            .as_deref()
+            .map(|d| {
+                Decision::parse(d).ok_or_else(|| {
+                    PromptGuardError::Custom(format!(
+                        "Unknown decision '{d}'. Use blocked or allowed"
+                    ))
+                })
+            })
+            .transpose()?;
+
         let config = config_manager.load()?;
-        let client = PromptGuardClient::new(config.api_key, Some(config.proxy_url))?;
+        let client = PromptGuardClient::new(
+            config.api_key().to_string(),
+            Some(config.proxy_url.default_url().to_string()),
+        )?;
 
         if !self.json {
             Output::header("Activity Logs");
             Output::info("Fetching logs from PromptGuard API...");
         }
 
-        // Build query parameters
-        let mut endpoint = format!("/logs?limit={}", self.limit);
-        if let Some(ref log_type) = self.log_type {
-            let _ = write!(endpoint, "&type={log_type}");
-        }
-        if let Some(ref project_id) = config.project_id {
-            let _ = write!(endpoint, "&project_id={project_id}");
+        let mut query = LogQuery {
+            limit: self.limit,
+            log_type: self.log_type.clone(),
+            since,
+            provider,
+            decision,
+            project_id: config.project_id.clone(),
+        };
+        if self.follow {
+            return self.follow(&client, &mut query);
         }
 
         // Try to fetch logs from the API
-        match client.get::<LogsResponse>(&endpoint) {
+        match query.fetch(&client) {
             Ok(response) => {
                 if self.json {
-                    println!(
-                        "{}",
-                        serde_json::to_string_pretty(&response.logs).unwrap_or_default()
-                    );
+                    Output::json(serde_json::to_value(&response.logs)?)?;
                 } else {
-                    self.print_logs(&response.logs);
+                    Self::print_logs(&response.logs);
 
                     if response.has_more {
-                        println!();
+                        Output::line("");
                         Output::info(&format!(
                             "Showing {} of {} logs. Use --limit to see more.",
                             response.logs.len(),
@@ -100,16 +183,16 @@ impl LogsCommand {
                 // Graceful fallback if the logs endpoint isn't available yet
                 if !self.json {
                     Output::warning(&format!("Could not fetch logs from API: {e}"));
-                    println!();
-                    println!("View your complete activity logs at:");
-                    println!("  https://app.promptguard.co/dashboard/activity");
+                    Output::line("");
+                    Output::line("View your complete activity logs at:");
+                    Output::line("  https://app.promptguard.co/dashboard/activity");
 
                     if let Some(project_id) = config.project_id {
-                        println!("\nProject: {project_id}");
+                        Output::line(&format!("\nProject: {project_id}"));
                     }
 
-                    println!("\nFor real-time monitoring:");
-                    println!("  Visit the dashboard at https://app.promptguard.co/dashboard");
+                    Output::line("\nFor real-time monitoring:");
+                    Output::line("  Visit the dashboard at https://app.promptguard.co/dashboard");
                 } else {
                     return Err(PromptGuardError::Api(format!("Failed to fetch logs: {e}")));
                 }
@@ -119,55 +202,210 @@ impl LogsCommand {
         Ok(())
     }
 
-    fn print_logs(&self, logs: &[LogEntry]) {
+    /// `--follow`: poll for new entries until interrupted, oldest first,
+    /// backing off while the API can't be reached
+    fn follow(&self, client: &PromptGuardClient, query: &mut LogQuery) -> Result<()> {
+        if !self.json {
+            Output::info("Following new entries (Ctrl+C to stop)...");
+            Output::line("");
+            print_row(&HEADERS.map(String::from), &FOLLOW_WIDTHS);
+        }
+
+        let mut seen: VecDeque<String> = VecDeque::new();
+        let mut backoff = FOLLOW_INTERVAL;
+        let mut failing = false;
+        loop {
+            match query.fetch(client) {
+                Ok(response) => {
+                    if failing && !self.json {
+                        Output::info("Reconnected");
+                    }
+                    failing = false;
+                    backoff = FOLLOW_INTERVAL;
+
+                    // The API lists the newest first
+                    for log in response.logs.iter().rev() {
+                        if seen.contains(&log.id) {
+                            continue;
+                        }
+                        if self.json {
+                            println!("{}", serde_json::to_string(log).unwrap_or_default());
+                        } else {
+                            print_row(&row(log), &FOLLOW_WIDTHS);
+                        }
+                        seen.push_back(log.id.clone());
+                        if seen.len() > FOLLOW_SEEN_IDS {
+                            seen.pop_front();
+                        }
+                        if let Ok(timestamp) = DateTime::parse_from_rfc3339(&log.timestamp) {
+                            let timestamp = timestamp.with_timezone(&Utc);
+                            if query.since.is_none_or(|since| timestamp > since) {
+                                query.since = Some(timestamp);
+                            }
+                        }
+                    }
+                    let _ = std::io::stdout().flush();
+                },
+                Err(e) => {
+                    if !failing {
+                        Output::warning(&format!(
+                            "Could not fetch logs ({e}); retrying with backoff"
+                        ));
+                    }
+                    failing = true;
+                    backoff = (backoff * 2).min(FOLLOW_MAX_BACKOFF);
+                },
+            }
+            thread::sleep(backoff);
+        }
+    }
+
+    /// One row per entry: time, type, decision, provider, threat, latency
+    fn print_logs(logs: &[LogEntry]) {
         if logs.is_empty() {
-            println!();
+            Output::line("");
             Output::info("No logs found.");
             return;
         }
 
-        println!();
-        println!("Recent Activity:");
-        println!("─────────────────────────────────────────────────────────────");
-
-        for log in logs {
-            let icon = match log.log_type.as_str() {
-                "security" | "threat" => "🚨",
-                "block" => "🚫",
-                "allow" => "✅",
-                "request" => "📤",
-                "response" => "📥",
-                "error" => "❌",
-                _ => "📋",
-            };
+        let rows: Vec<[String; 6]> = logs.iter().map(row).collect();
+        let mut widths = HEADERS.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
 
-            let timestamp = &log.timestamp[..19.min(log.timestamp.len())]; // Truncate to readable format
+        Output::line("");
+        print_row(&HEADERS.map(String::from), &widths);
+        print_row(&widths.map(|w| "─".repeat(w)), &widths);
+        for row in &rows {
+            print_row(row, &widths);
+        }
+    }
+}
 
-            print!("{} [{}] {}", icon, timestamp, log.log_type.to_uppercase());
+/// Filters of one `/logs` request
+struct LogQuery {
+    limit: usize,
+    log_type: Option<String>,
+    since: Option<DateTime<Utc>>,
+    provider: Option<Provider>,
+    decision: Option<Decision>,
+    project_id: Option<String>,
+}
 
-            if let Some(ref decision) = log.decision {
-                print!(" - {decision}");
-            }
+impl LogQuery {
+    fn endpoint(&self) -> String {
+        let mut endpoint = format!("/logs?limit={}", self.limit);
+        if let Some(ref log_type) = self.log_type {
+            let _ = write!(endpoint, "&type={log_type}");
+        }
+        if let Some(since) = self.since {
+            let _ = write!(
+                endpoint,
+                "&since={}",
+                since.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+            );
+        }
+        if let Some(provider) = self.provider {
+            let _ = write!(endpoint, "&provider={}", provider.as_str());
+        }
+        if let Some(decision) = self.decision {
+            let _ = write!(endpoint, "&decision={}", decision.as_str());
+        }
+        if let Some(ref project_id) = self.project_id {
+            let _ = write!(endpoint, "&project_id={project_id}");
+        }
+        endpoint
+    }
 
-            if let Some(ref threat_type) = log.threat_type {
-                print!(" ({threat_type})");
-            }
+    fn fetch(&self, client: &PromptGuardClient) -> Result<LogsResponse> {
+        let mut response: LogsResponse = client.get(&self.endpoint())?;
+        // Filter again in case the API ignored a parameter it doesn't know
+        response.logs.retain(|log| {
+            self.since.is_none_or(|since| {
+                DateTime::parse_from_rfc3339(&log.timestamp).map_or(true, |t| t >= since)
+            }) && self.provider.is_none_or(|provider| {
+                log.provider
+                    .as_deref()
+                    .is_none_or(|p| Provider::parse(p) == Some(provider))
+            }) && self
+                .decision
+                .is_none_or(|decision| log.decision.as_deref().is_some_and(|d| decision.matches(d)))
+        });
+        Ok(response)
+    }
+}
 
-            if let Some(confidence) = log.confidence {
-                print!(" [{:.0}%]", confidence * 100.0);
-            }
+const HEADERS: [&str; 6] = ["TIME", "TYPE", "DECISION", "PROVIDER", "THREAT", "LATENCY"];
 
-            if let Some(latency) = log.latency_ms {
-                print!(" {latency}ms");
-            }
+/// Column widths for `--follow`, which can't size columns to rows to come
+const FOLLOW_WIDTHS: [usize; 6] = [19, 8, 8, 11, 24, 7];
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(2);
+const FOLLOW_MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Entry ids remembered so an entry at the `since` boundary isn't shown twice
+const FOLLOW_SEEN_IDS: usize = 1000;
 
-            println!();
+fn row(log: &LogEntry) -> [String; 6] {
+    [
+        // Truncate to readable format
+        log.timestamp[..19.min(log.timestamp.len())].replace('T', " "),
+        log.log_type.clone(),
+        log.decision.clone().unwrap_or_else(|| "-".to_string()),
+        log.provider.clone().unwrap_or_else(|| "-".to_string()),
+        match (&log.threat_type, log.confidence) {
+            (Some(threat), Some(confidence)) => {
+                format!("{threat} ({:.0}%)", confidence * 100.0)
+            },
+            (Some(threat), None) => threat.clone(),
+            (None, _) => "-".to_string(),
+        },
+        log.latency_ms
+            .map_or_else(|| "-".to_string(), |ms| format!("{ms}ms")),
+    ]
+}
 
-            if let Some(ref message) = log.message {
-                println!("   {message}");
-            }
-        }
+fn print_row(cells: &[String; 6], widths: &[usize; 6]) {
+    let padded: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{cell:<width$}"))
+        .collect();
+    Output::line(padded.join("  ").trim_end());
+}
 
-        println!("─────────────────────────────────────────────────────────────");
+/// Start of the `--since` window: `30m`, `24h`, `7d` or `2w` back from `now`,
+/// a `YYYY-MM-DD` date (midnight UTC) or an RFC 3339 timestamp
+pub fn parse_since(since: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let since = since.trim();
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(since) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    if let Some(midnight) = NaiveDate::parse_from_str(since, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+    {
+        return Ok(midnight.and_utc());
     }
+    let split = since.char_indices().last().map_or(0, |(i, _)| i);
+    let (amount, unit) = since.split_at(split);
+    let seconds = match unit {
+        "s" => Some(1),
+        "m" => Some(60),
+        "h" => Some(3600),
+        "d" => Some(86_400),
+        "w" => Some(604_800),
+        _ => None,
+    };
+    seconds
+        .zip(amount.parse::<i64>().ok())
+        .and_then(|(unit, amount)| amount.checked_mul(unit))
+        .and_then(chrono::TimeDelta::try_seconds)
+        .and_then(|delta| now.checked_sub_signed(delta))
+        .ok_or_else(|| {
+            PromptGuardError::Custom(format!(
+                "Invalid --since '{since}'. Use a duration (30m, 24h, 7d, 2w), a date (2024-05-01) or an RFC 3339 timestamp"
+            ))
+        })
 }
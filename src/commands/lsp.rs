@@ -0,0 +1,407 @@
+//! LSP Command - Language server for editors
+//!
+//! Speaks the Language Server Protocol over stdio so any LSP-capable editor
+//! can show unrouted SDK calls inline. Diagnostics are computed from the
+//! file on disk when a document is opened or saved, with the same
+//! classification as `promptguard coverage`. A quick fix routes the open
+//! buffer through the proxy by applying the transform to it as an edit.
+//!
+//! Edits made by the quick fix are not recorded in the config the way
+//! `promptguard apply` records them, so `promptguard disable` leaves them
+//! in place.
+
+use crate::commands::coverage::{self, CallSite};
+use crate::config::{ConfigManager, PromptGuardConfig};
+use crate::error::{PromptGuardError, Result};
+use crate::output::sarif::Rule;
+use crate::scanner::FileScanner;
+use crate::transformer;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    id: Option<serde_json::Value>,
+    method: Option<String>,
+    #[serde(default)]
+    params: serde_json::Value,
+}
+
+// LSP severities
+const SEVERITY_ERROR: u8 = 1;
+const SEVERITY_WARNING: u8 = 2;
+
+#[derive(Default)]
+struct Server {
+    root_path: PathBuf,
+    /// Buffer text of each open document, by URI
+    documents: HashMap<String, String>,
+    /// Unguarded call sites last published for each open document
+    gaps: HashMap<String, Vec<CallSite>>,
+    shutdown: bool,
+}
+
+impl Server {
+    /// Handle one request or notification. Returns the response for
+    /// requests and the notifications to send.
+    fn handle(&mut self, message: &Message) -> (Option<serde_json::Value>, Vec<serde_json::Value>) {
+        let method = message.method.as_deref().unwrap_or("");
+        let mut notifications = Vec::new();
+        let result = match method {
+            "initialize" => Ok(serde_json::json!({
+                "capabilities": {
+                    "textDocumentSync": { "openClose": true, "change": 1, "save": true },
+                    "codeActionProvider": { "codeActionKinds": ["quickfix"] },
+                },
+                "serverInfo": {
+                    "name": "promptguard",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            })),
+            "initialized" => {
+                if !ConfigManager::new(None).is_ok_and(|m| m.exists()) {
+                    notifications.push(notification(
+                        "window/showMessage",
+                        serde_json::json!({
+                            // MessageType.Warning
+                            "type": 2,
+                            "message": "PromptGuard is not initialized in this project. \
+                                        Run 'promptguard init' to check SDK calls.",
+                        }),
+                    ));
+                }
+                return (None, notifications);
+            },
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(serde_json::Value::Null)
+            },
+            "textDocument/didOpen" => {
+                let document = &message.params["textDocument"];
+                if let (Some(uri), Some(text)) =
+                    (document["uri"].as_str(), document["text"].as_str())
+                {
+                    self.documents.insert(uri.to_string(), text.to_string());
+                    notifications.push(self.publish(uri));
+                }
+                return (None, notifications);
+            },
+            "textDocument/didChange" => {
+                let uri = message.params["textDocument"]["uri"].as_str();
+                let text = message.params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                if let (Some(uri), Some(text)) = (uri, text) {
+                    self.documents.insert(uri.to_string(), text.to_string());
+                }
+                return (None, notifications);
+            },
+            "textDocument/didSave" => {
+                // A save can load a shim or change the config, so refresh every open document
+                let mut uris: Vec<String> = self.documents.keys().cloned().collect();
+                uris.sort();
+                for uri in uris {
+                    notifications.push(self.publish(&uri));
+                }
+                return (None, notifications);
+            },
+            "textDocument/didClose" => {
+                if let Some(uri) = message.params["textDocument"]["uri"].as_str() {
+                    self.documents.remove(uri);
+                    self.gaps.remove(uri);
+                    notifications.push(diagnostics_notification(uri, Vec::new()));
+                }
+                return (None, notifications);
+            },
+            "textDocument/codeAction" => Ok(self.code_actions(&message.params)),
+            "exit" => return (None, notifications),
+            _ if message.id.is_none() => return (None, notifications),
+            _ => Err((-32601, format!("Method not found: {method}"))),
+        };
+
+        let id = message.id.clone().unwrap_or(serde_json::Value::Null);
+        let response = match result {
+            Ok(result) => serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        };
+        (Some(response), notifications)
+    }
+
+    /// Diagnostics for `uri` from the file on disk
+    fn publish(&mut self, uri: &str) -> serde_json::Value {
+        let gaps = self.unguarded_sites(uri).unwrap_or_default();
+        let text = self.documents.get(uri).map_or("", String::as_str);
+        let diagnostics = gaps.iter().map(|site| diagnostic(text, site)).collect();
+        self.gaps.insert(uri.to_string(), gaps);
+        diagnostics_notification(uri, diagnostics)
+    }
+
+    fn unguarded_sites(&self, uri: &str) -> Option<Vec<CallSite>> {
+        let file_path = uri_to_path(uri)?;
+        let config = self.config()?;
+        let scanner =
+            FileScanner::new(&self.root_path, Some(config.exclude_patterns.clone())).ok()?;
+        if !file_path.starts_with(&self.root_path)
+            || file_path.starts_with(crate::state::project_state_dir(&self.root_path))
+            || scanner.is_excluded(&file_path)
+        {
+            return None;
+        }
+        let sites = coverage::file_call_sites(&self.root_path, &config, &file_path).ok()?;
+        Some(sites.into_iter().filter(|s| s.guard.is_none()).collect())
+    }
+
+    fn config(&self) -> Option<PromptGuardConfig> {
+        ConfigManager::new(None).ok()?.load().ok()
+    }
+
+    /// A quick fix routing the buffer through the proxy, when a published
+    /// diagnostic in the requested range is one the transform can fix
+    fn code_actions(&self, params: &serde_json::Value) -> serde_json::Value {
+        let Some(uri) = params["textDocument"]["uri"].as_str() else {
+            return serde_json::json!([]);
+        };
+        let (Some(text), Some(gaps), Some(file_path), Some(config)) = (
+            self.documents.get(uri),
+            self.gaps.get(uri),
+            uri_to_path(uri),
+            self.config(),
+        ) else {
+            return serde_json::json!([]);
+        };
+        // The runtime shim, not the transform, routes calls in runtime mode
+        if !config.enabled || config.runtime_mode {
+            return serde_json::json!([]);
+        }
+
+        let start = params["range"]["start"]["line"].as_u64().unwrap_or(0);
+        let end = params["range"]["end"]["line"].as_u64().unwrap_or(u64::MAX);
+        let in_range: Vec<&CallSite> = gaps
+            .iter()
+            .filter(|s| (start..=end).contains(&((s.line - 1) as u64)))
+            .collect();
+        if in_range.is_empty() {
+            return serde_json::json!([]);
+        }
+
+        let mut providers: Vec<_> = gaps.iter().map(|s| s.provider).collect();
+        providers.sort();
+        providers.dedup();
+        let mut routed = text.clone();
+        for provider in providers {
+            let proxy_url = config.proxy_url.for_provider(provider);
+            let env_var = config.env_var_for(provider);
+            if let Ok(Some(new_source)) =
+                transformer::route_source(&file_path, &routed, provider, proxy_url, env_var)
+            {
+                routed = new_source;
+            }
+        }
+        if routed == *text {
+            return serde_json::json!([]);
+        }
+
+        let diagnostics: Vec<serde_json::Value> =
+            in_range.iter().map(|site| diagnostic(text, site)).collect();
+        serde_json::json!([{
+            "title": "Route through PromptGuard",
+            "kind": "quickfix",
+            "isPreferred": true,
+            "diagnostics": diagnostics,
+            "edit": {
+                "changes": {
+                    uri: [{ "range": whole_document(text), "newText": routed }],
+                },
+            },
+        }])
+    }
+}
+
+fn notification(method: &str, params: serde_json::Value) -> serde_json::Value {
+    let mut message = serde_json::json!({ "jsonrpc": "2.0", "method": method });
+    message["params"] = params;
+    message
+}
+
+fn diagnostics_notification(uri: &str, diagnostics: Vec<serde_json::Value>) -> serde_json::Value {
+    notification(
+        "textDocument/publishDiagnostics",
+        serde_json::json!({ "uri": uri, "diagnostics": serde_json::Value::Array(diagnostics) }),
+    )
+}
+
+/// Diagnostic spanning the constructor name at `site`
+fn diagnostic(text: &str, site: &CallSite) -> serde_json::Value {
+    let (rule, severity) = if site.gateway.is_some() {
+        (Rule::ConflictingGateway, SEVERITY_ERROR)
+    } else {
+        (Rule::UnguardedSdkCall, SEVERITY_WARNING)
+    };
+    let line = text.lines().nth(site.line - 1).unwrap_or("");
+    let start = (site.column - 1).min(line.len());
+    let end = line[start..]
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+        .map_or(line.len(), |len| start + len);
+    serde_json::json!({
+        "range": {
+            "start": { "line": site.line - 1, "character": utf16_len(&line[..start]) },
+            "end": { "line": site.line - 1, "character": utf16_len(&line[..end]) },
+        },
+        "severity": severity,
+        "code": rule.id(),
+        "source": "promptguard",
+        "message": format!(
+            "{} client is not routed through PromptGuard: {}",
+            site.provider.display_name(),
+            site.gap.as_deref().unwrap_or_default()
+        ),
+    })
+}
+
+/// Range covering all of `text`
+fn whole_document(text: &str) -> serde_json::Value {
+    let last_line = text.rsplit('\n').next().unwrap_or("");
+    serde_json::json!({
+        "start": { "line": 0, "character": 0 },
+        "end": { "line": text.matches('\n').count(), "character": utf16_len(last_line) },
+    })
+}
+
+/// LSP positions count UTF-16 code units
+fn utf16_len(s: &str) -> usize {
+    s.chars().map(char::len_utf16).sum()
+}
+
+/// Path of a `file://` URI, percent-decoded
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Some(byte) = path
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    let path = String::from_utf8(decoded).ok()?;
+    // file:///C:/dir on Windows
+    let path = match path.strip_prefix('/') {
+        Some(rest) if rest.get(1..2) == Some(":") => rest.to_string(),
+        _ => path,
+    };
+    Some(Path::new(&path).to_path_buf())
+}
+
+/// Read one `Content-Length` framed message. Returns `None` at end of input.
+fn read_message(reader: &mut impl BufRead) -> Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.ok_or_else(|| {
+        PromptGuardError::Custom("LSP message without Content-Length".to_string())
+    })?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|e| PromptGuardError::Custom(format!("LSP message is not UTF-8: {e}")))
+}
+
+fn write_message(writer: &mut impl Write, message: &serde_json::Value) -> Result<()> {
+    let body = serde_json::to_string(message).unwrap_or_default();
+    write!(writer, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub struct LspCommand {
+    pub transport: String,
+}
+
+impl LspCommand {
+    pub fn execute(&self) -> Result<()> {
+        if self.transport != "stdio" {
+            return Err(PromptGuardError::Custom(format!(
+                "Unsupported transport '{}'. Only 'stdio' is supported.",
+                self.transport
+            )));
+        }
+
+        let mut server = Server {
+            root_path: crate::config::project_root()?,
+            ..Server::default()
+        };
+        let stdin = io::stdin();
+        let mut reader = stdin.lock();
+        let mut stdout = io::stdout();
+
+        while let Some(body) = read_message(&mut reader)? {
+            let message: Message = match serde_json::from_str(&body) {
+                Ok(m) => m,
+                Err(e) => {
+                    write_message(
+                        &mut stdout,
+                        &serde_json::json!({
+                            "jsonrpc": "2.0",
+                            "id": null,
+                            "error": { "code": -32700, "message": format!("Parse error: {e}") },
+                        }),
+                    )?;
+                    continue;
+                },
+            };
+            // Responses to requests this server never sends
+            if message.method.is_none() {
+                continue;
+            }
+
+            let (response, notifications) = server.handle(&message);
+            if let Some(response) = response {
+                write_message(&mut stdout, &response)?;
+            }
+            for notification in notifications {
+                write_message(&mut stdout, &notification)?;
+            }
+            if message.method.as_deref() == Some("exit") {
+                // Exiting without a shutdown request is an error per the spec
+                return if server.shutdown {
+                    Ok(())
+                } else {
+                    Err(PromptGuardError::Custom(
+                        "LSP client exited without a shutdown request".to_string(),
+                    ))
+                };
+            }
+        }
+        Ok(())
+    }
+}
@@ -5,7 +5,7 @@ use crate::error::{PromptGuardError, Result};
 use crate::scanner::FileScanner;
 use crate::types::{DetectionInstance, Provider};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::io::{self, BufRead, Write};
 use std::path::PathBuf;
 
@@ -174,8 +174,10 @@ fn handle_scan_text(params: &serde_json::Value) -> serde_json::Value {
     let result = (|| -> Result<serde_json::Value> {
         let config_manager = ConfigManager::new(None)?;
         let config = config_manager.load()?;
-        let client =
-            PromptGuardClient::new(config.api_key.clone(), Some(config.proxy_url.clone()))?;
+        let client = PromptGuardClient::new(
+            config.api_key().to_string(),
+            Some(config.proxy_url.default_url().to_string()),
+        )?;
 
         let mut body = serde_json::json!({ "content": text, "type": "prompt" });
         if let Some(pid) = resolve_project_id(&config) {
@@ -228,7 +230,7 @@ fn handle_scan_project(params: &serde_json::Value) -> serde_json::Value {
         },
     };
 
-    let mut all_detections: HashMap<Provider, Vec<DetectionInstance>> = HashMap::new();
+    let mut all_detections: BTreeMap<Provider, Vec<DetectionInstance>> = BTreeMap::new();
 
     for file_path in &files {
         if let Ok(results) = detect_all_providers(file_path) {
@@ -309,8 +311,10 @@ fn handle_redact(params: &serde_json::Value) -> serde_json::Value {
     let result = (|| -> Result<serde_json::Value> {
         let config_manager = ConfigManager::new(None)?;
         let config = config_manager.load()?;
-        let client =
-            PromptGuardClient::new(config.api_key.clone(), Some(config.proxy_url.clone()))?;
+        let client = PromptGuardClient::new(
+            config.api_key().to_string(),
+            Some(config.proxy_url.default_url().to_string()),
+        )?;
 
         let mut body = serde_json::json!({ "content": text });
         if let Some(pid) = resolve_project_id(&config) {
@@ -338,9 +342,9 @@ fn handle_status(_params: &serde_json::Value) -> serde_json::Value {
         let config_manager = ConfigManager::new(None)?;
         let config = config_manager.load()?;
 
-        let key_type = if config.api_key.starts_with("pg_sk_test_") {
+        let key_type = if config.api_key().starts_with("pg_sk_test_") {
             "test"
-        } else if config.api_key.starts_with("pg_sk_prod_") {
+        } else if config.api_key().starts_with("pg_sk_prod_") {
             "production"
         } else {
             "unknown"
@@ -396,10 +400,8 @@ fn handle_auth(params: &serde_json::Value) -> serde_json::Value {
             let result = (|| -> Result<()> {
                 let config_manager = ConfigManager::new(None)?;
 
-                if config_manager.exists() {
-                    let mut config = config_manager.load()?;
-                    config.api_key = key.to_string();
-                    config_manager.save(&config)?;
+                let config = if config_manager.exists() {
+                    config_manager.load()?
                 } else {
                     let config = crate::config::PromptGuardConfig::new(
                         key.to_string(),
@@ -407,8 +409,10 @@ fn handle_auth(params: &serde_json::Value) -> serde_json::Value {
                         Vec::new(),
                     )?;
                     config_manager.save(&config)?;
-                }
-                Ok(())
+                    config
+                };
+                let root = crate::config::project_root()?;
+                config.env_file.set_key(&root, &config.env_var_name, key)
             })();
 
             match result {
@@ -419,7 +423,7 @@ fn handle_auth(params: &serde_json::Value) -> serde_json::Value {
                         "production"
                     };
                     serde_json::json!({
-                        "content": [{"type": "text", "text": format!("Authenticated successfully with a {key_type} API key. PromptGuard is ready to use.\n\nTo associate requests with a specific project, set \"project_id\" in .promptguard.json or run 'promptguard projects select <id>'.")}]
+                        "content": [{"type": "text", "text": format!("Authenticated successfully with a {key_type} API key. PromptGuard is ready to use.\n\nTo associate requests with a specific project, run 'promptguard projects link <id>'.")}]
                     })
                 },
                 Err(e) => serde_json::json!({
@@ -1,49 +1,83 @@
 pub mod apply;
+pub mod audit;
+pub mod backup;
+pub mod benchmark;
+pub mod ci;
+pub mod completions;
 pub mod config;
+pub mod coverage;
 pub mod dashboard;
+pub mod demo;
 pub mod disable;
 pub mod doctor;
 pub mod enable;
 pub mod events;
+pub mod gen_docs;
+pub mod hook;
 pub mod init;
 pub mod key;
 pub mod login;
 pub mod logout;
 pub mod logs;
+pub mod lsp;
 pub mod mcp;
+pub mod package_offline;
 pub mod policy;
 pub mod projects;
 pub mod redact;
 pub mod redteam;
+pub mod report;
 pub mod revert;
 pub mod scan;
+pub mod secrets;
+pub mod shim;
+pub mod state;
+pub mod stats;
 pub mod status;
 pub mod test;
+pub mod uninstall;
 pub mod update;
 pub mod verify;
 pub mod whoami;
 
 pub use apply::ApplyCommand;
-pub use config::ConfigCommand;
+pub use audit::AuditCommand;
+pub use backup::{BackupAction, BackupCommand};
+pub use benchmark::BenchmarkCommand;
+pub use ci::CiCommand;
+pub use completions::CompletionsCommand;
+pub use config::{ConfigAction, ConfigCommand};
+pub use coverage::CoverageCommand;
 pub use dashboard::DashboardCommand;
+pub use demo::DemoCommand;
 pub use disable::DisableCommand;
 pub use doctor::DoctorCommand;
 pub use enable::EnableCommand;
 pub use events::EventsCommand;
+pub use gen_docs::GenDocsCommand;
+pub use hook::{HookAction, HookCommand, HookManager};
 pub use init::InitCommand;
-pub use key::KeyCommand;
+pub use key::{KeyAction, KeyCommand};
 pub use login::LoginCommand;
 pub use logout::LogoutCommand;
 pub use logs::LogsCommand;
+pub use lsp::LspCommand;
 pub use mcp::McpCommand;
+pub use package_offline::PackageOfflineCommand;
 pub use policy::{PolicyAction, PolicyCommand};
 pub use projects::{ProjectsAction, ProjectsCommand};
 pub use redact::RedactCommand;
 pub use redteam::RedTeamCommand;
+pub use report::ReportCommand;
 pub use revert::RevertCommand;
 pub use scan::ScanCommand;
+pub use secrets::{SecretsAction, SecretsCommand};
+pub use shim::{ShimAction, ShimCommand};
+pub use state::{StateAction, StateCommand};
+pub use stats::StatsCommand;
 pub use status::StatusCommand;
 pub use test::TestCommand;
+pub use uninstall::UninstallCommand;
 pub use update::UpdateCommand;
 pub use verify::VerifyCommand;
 pub use whoami::WhoamiCommand;
@@ -0,0 +1,227 @@
+use crate::detector::PROVIDERS;
+use crate::error::{PromptGuardError, Result};
+use crate::output::Output;
+use aws_lc_rs::digest::{digest, SHA256};
+use serde::Serialize;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const README: &str = include_str!("../../README.md");
+
+/// Commands that keep working without network access
+const OFFLINE_COMMANDS: &[&str] = &[
+    "init", "scan", "status", "doctor", "apply", "disable", "enable", "revert", "config", "state",
+    "demo", "verify",
+];
+
+#[derive(Serialize)]
+struct ManifestEntry {
+    path: String,
+    sha256: String,
+    size: u64,
+}
+
+#[derive(Serialize)]
+struct Manifest {
+    cli_version: String,
+    target: String,
+    created_at: String,
+    files: Vec<ManifestEntry>,
+}
+
+pub struct PackageOfflineCommand {
+    pub output: Option<String>,
+    /// Commands refused in air-gapped mode, from the CLI's own list
+    pub online_commands: Vec<&'static str>,
+    pub json: bool,
+}
+
+impl PackageOfflineCommand {
+    pub fn execute(&self) -> Result<()> {
+        let version = env!("CARGO_PKG_VERSION");
+        let target = format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH);
+        let bundle_dir = self.output.as_ref().map_or_else(
+            || PathBuf::from(format!("promptguard-offline-v{version}-{target}")),
+            PathBuf::from,
+        );
+
+        if bundle_dir.exists() && fs::read_dir(&bundle_dir)?.next().is_some() {
+            return Err(PromptGuardError::Custom(format!(
+                "Output directory {} already exists and is not empty",
+                bundle_dir.display()
+            )));
+        }
+
+        if !self.json {
+            Output::header("Package Offline Bundle");
+            Output::info(&format!("Writing bundle to {}", bundle_dir.display()));
+        }
+
+        let mut files = Vec::new();
+
+        let exe = std::env::current_exe()?;
+        let binary_name = if cfg!(windows) {
+            "promptguard.exe"
+        } else {
+            "promptguard"
+        };
+        let binary_path = bundle_dir.join("bin").join(binary_name);
+        fs::create_dir_all(bundle_dir.join("bin"))?;
+        fs::copy(&exe, &binary_path)?;
+        files.push(binary_path);
+
+        files.push(Self::write(
+            &bundle_dir,
+            "heuristics/providers.json",
+            &Self::providers_json()?,
+        )?);
+        files.push(Self::write(&bundle_dir, "docs/README.md", README)?);
+        files.push(Self::write(
+            &bundle_dir,
+            "docs/AIR_GAPPED.md",
+            &self.air_gapped_guide(version, binary_name),
+        )?);
+
+        let manifest = Manifest {
+            cli_version: version.to_string(),
+            target,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            files: files
+                .iter()
+                .map(|f| Self::manifest_entry(&bundle_dir, f))
+                .collect::<Result<Vec<_>>>()?,
+        };
+        let manifest_path = Self::write(
+            &bundle_dir,
+            "MANIFEST.json",
+            &serde_json::to_string_pretty(&manifest)?,
+        )?;
+
+        if self.json {
+            let result = serde_json::json!({
+                "bundle_dir": bundle_dir.display().to_string(),
+                "manifest": manifest_path.display().to_string(),
+                "cli_version": manifest.cli_version,
+                "target": manifest.target,
+                "files": manifest.files,
+            });
+            Output::json(result)?;
+            return Ok(());
+        }
+
+        for entry in &manifest.files {
+            Output::step(&format!("✓ {}", entry.path));
+        }
+        Output::step("✓ MANIFEST.json");
+        Output::line("");
+        Output::success(&format!("Offline bundle created: {}", bundle_dir.display()));
+        Output::line("\nNext steps:");
+        Output::line("  • Copy the bundle into the air-gapped network");
+        Output::line("  • Verify checksums against MANIFEST.json");
+        Output::line("  • Follow docs/AIR_GAPPED.md to install and configure");
+
+        Ok(())
+    }
+
+    fn write(bundle_dir: &Path, relative: &str, content: &str) -> Result<PathBuf> {
+        let path = bundle_dir.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, content)?;
+        Ok(path)
+    }
+
+    fn manifest_entry(bundle_dir: &Path, path: &Path) -> Result<ManifestEntry> {
+        let data = fs::read(path)?;
+        let sha256 = digest(&SHA256, &data)
+            .as_ref()
+            .iter()
+            .fold(String::new(), |mut hex, b| {
+                let _ = write!(hex, "{b:02x}");
+                hex
+            });
+        let relative = path.strip_prefix(bundle_dir).unwrap_or(path);
+        Ok(ManifestEntry {
+            path: relative.to_string_lossy().replace('\\', "/"),
+            sha256,
+            size: data.len() as u64,
+        })
+    }
+
+    /// The provider registry used for local SDK detection
+    fn providers_json() -> Result<String> {
+        let providers: Vec<_> = PROVIDERS
+            .iter()
+            .map(|info| {
+                serde_json::json!({
+                    "provider": info.provider.as_str(),
+                    "display_name": info.provider.display_name(),
+                    "python_class": info.py_class_name,
+                    "typescript_class": info.ts_class_name,
+                    "typescript_base_url_param": info.ts_base_url_param,
+                    "typescript_api_key_param": info.ts_api_key_param,
+                    "python_packages": info.py_packages,
+                    "npm_packages": info.npm_packages,
+                    "python_min_base_url_version": info.py_min_base_url_version,
+                    "npm_min_base_url_version": info.npm_min_base_url_version,
+                    "python_base_url_env_var": info.py_base_url_env_var,
+                    "npm_base_url_env_var": info.npm_base_url_env_var,
+                })
+            })
+            .collect();
+        Ok(serde_json::to_string_pretty(&providers)?)
+    }
+
+    fn air_gapped_guide(&self, version: &str, binary_name: &str) -> String {
+        let command_list = |commands: &[&str]| {
+            commands.iter().fold(String::new(), |mut list, c| {
+                let _ = writeln!(list, "- `promptguard {c}`");
+                list
+            })
+        };
+        let offline = command_list(OFFLINE_COMMANDS);
+        let online = command_list(&self.online_commands);
+
+        format!(
+            "# PromptGuard CLI v{version} - Air-Gapped Bundle\n\
+             \n\
+             ## Contents\n\
+             \n\
+             - `bin/{binary_name}` - CLI binary\n\
+             - `heuristics/providers.json` - provider registry used for local SDK detection\n\
+             - `docs/` - this guide and the CLI README\n\
+             - `MANIFEST.json` - SHA-256 checksums for every file in the bundle\n\
+             \n\
+             ## Install\n\
+             \n\
+             ```bash\n\
+             sha256sum bin/{binary_name}   # compare with MANIFEST.json\n\
+             install -m 0755 bin/{binary_name} /usr/local/bin/{binary_name}\n\
+             ```\n\
+             \n\
+             ## Configure\n\
+             \n\
+             Enable air-gapped mode for every invocation:\n\
+             \n\
+             ```bash\n\
+             export PROMPTGUARD_AIR_GAPPED=1\n\
+             ```\n\
+             \n\
+             Or pass `--air-gapped` to individual commands. Point `--base-url` at a\n\
+             PromptGuard proxy reachable from inside the network when running `init`.\n\
+             \n\
+             ## Available offline\n\
+             \n\
+             {offline}\
+             \n\
+             ## Disabled in air-gapped mode\n\
+             \n\
+             {online}\
+             \n\
+             Update checks are suppressed. To upgrade, produce a new bundle with\n\
+             `promptguard package-offline` on a connected machine and transfer it.\n"
+        )
+    }
+}
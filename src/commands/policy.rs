@@ -2,14 +2,19 @@
 //!
 //! Define guardrails in YAML, version in git, apply via CLI.
 //! Maps to the existing guardrail config API - a YAML front-end,
-//! not a new config system.
+//! not a new config system. Named presets are pulled into `policies/`
+//! so they can be reviewed and versioned with the repo, then pushed back.
 
 use crate::api::PromptGuardClient;
+use crate::auth::resolve_base_url;
 use crate::config::ConfigManager;
 use crate::error::{PromptGuardError, Result};
+use crate::output::Output;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
+use std::fmt::Write;
 use std::fs;
+use std::path::{Path, PathBuf};
 
 /// Wrapper for the guardrails API response.
 #[derive(Debug, Deserialize)]
@@ -23,31 +28,76 @@ struct GuardrailsUpdateRequest {
     guardrails: serde_json::Value,
 }
 
+/// A named, reusable policy on the backend.
+#[derive(Debug, Deserialize, Serialize)]
+struct Preset {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    guardrails: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct PresetList {
+    presets: Vec<PresetSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PresetSummary {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    updated_at: Option<String>,
+}
+
+/// Where `policy pull` writes presets, relative to the project root
+const PRESETS_DIR: &str = "policies";
+
 const VALID_LEVELS: &[&str] = &["strict", "moderate", "permissive"];
 const VALID_PII_MODES: &[&str] = &["redact", "mask", "block"];
 
 pub enum PolicyAction {
-    Apply { file: String, dry_run: bool },
-    Diff { file: String },
+    Apply {
+        file: String,
+        dry_run: bool,
+    },
+    Diff {
+        file: String,
+    },
     Export,
+    List,
+    Show {
+        name: String,
+    },
+    Pull {
+        name: String,
+        output: Option<String>,
+    },
+    Push {
+        file: String,
+        name: Option<String>,
+        dry_run: bool,
+    },
 }
 
 pub struct PolicyCommand {
     pub action: PolicyAction,
-    pub project_id: String,
+    /// Defaults to the project `.promptguard.json` is linked to
+    pub project_id: Option<String>,
     pub api_key: Option<String>,
     pub base_url: Option<String>,
 }
 
 impl PolicyCommand {
     pub fn execute(self) -> Result<()> {
+        let config = ConfigManager::new(None).ok().and_then(|cm| cm.load().ok());
         let api_key = if let Some(key) = &self.api_key {
             key.clone()
         } else {
-            ConfigManager::new(None)
-                .ok()
-                .and_then(|cm| cm.load().ok())
-                .map(|c| c.api_key)
+            config
+                .as_ref()
+                .map(|c| c.api_key().to_string())
                 .ok_or_else(|| {
                     PromptGuardError::Config(
                         "API key required. Run 'promptguard init' or pass --api-key".to_string(),
@@ -55,27 +105,56 @@ impl PolicyCommand {
                 })?
         };
 
-        let client = PromptGuardClient::new(api_key, self.base_url.clone())
+        let base_url = self.base_url.clone().unwrap_or_else(resolve_base_url);
+        let client = PromptGuardClient::new(api_key, Some(base_url))
             .map_err(|e| PromptGuardError::Config(format!("Failed to create client: {e}")))?;
 
+        let project_id = self
+            .project_id
+            .clone()
+            .or_else(|| config.and_then(|c| c.project_id));
+        let project = || {
+            project_id.as_deref().ok_or_else(|| {
+                PromptGuardError::Config(
+                    "--project-id is required (or link this repo with 'promptguard projects link <id>')"
+                        .to_string(),
+                )
+            })
+        };
+
         match self.action {
-            PolicyAction::Apply { ref file, dry_run } => self.apply(&client, file, dry_run),
-            PolicyAction::Diff { ref file } => self.diff(&client, file),
-            PolicyAction::Export => self.export(&client),
+            PolicyAction::Apply { ref file, dry_run } => {
+                Self::apply(&client, project()?, file, dry_run)
+            },
+            PolicyAction::Diff { ref file } => Self::diff(&client, project()?, file),
+            PolicyAction::Export => Self::export(&client, project()?),
+            PolicyAction::List => Self::list(&client),
+            PolicyAction::Show { ref name } => Self::show(&client, name),
+            PolicyAction::Pull {
+                ref name,
+                ref output,
+            } => Self::pull(&client, name, output.as_deref()),
+            PolicyAction::Push {
+                ref file,
+                ref name,
+                dry_run,
+            } => Self::push(&client, file, name.as_deref(), dry_run),
         }
     }
 
-    fn load_yaml(path: &str) -> Result<serde_json::Value> {
+    fn read_yaml(path: &str) -> Result<serde_json::Value> {
         let content = fs::read_to_string(path)
             .map_err(|e| PromptGuardError::Config(format!("Failed to read {path}: {e}")))?;
 
         let parsed: serde_yaml::Value = serde_yaml::from_str(&content)
             .map_err(|e| PromptGuardError::Config(format!("YAML parse error: {e}")))?;
 
-        let json_value = serde_json::to_value(&parsed).map_err(|e| {
-            PromptGuardError::Config(format!("Failed to convert YAML to JSON: {e}"))
-        })?;
+        serde_json::to_value(&parsed)
+            .map_err(|e| PromptGuardError::Config(format!("Failed to convert YAML to JSON: {e}")))
+    }
 
+    fn load_yaml(path: &str) -> Result<serde_json::Value> {
+        let json_value = Self::read_yaml(path)?;
         let guardrails = json_value
             .get("guardrails")
             .ok_or_else(|| {
@@ -145,8 +224,8 @@ impl PolicyCommand {
         }
     }
 
-    fn fetch_current(&self, client: &PromptGuardClient) -> Result<serde_json::Value> {
-        let endpoint = format!("/projects/{}/guardrails", self.project_id);
+    fn fetch_current(client: &PromptGuardClient, project_id: &str) -> Result<serde_json::Value> {
+        let endpoint = format!("/projects/{project_id}/guardrails");
         let resp: GuardrailsResponse = client.get(&endpoint)?;
         Ok(resp.guardrails)
     }
@@ -207,32 +286,37 @@ impl PolicyCommand {
         diffs
     }
 
-    fn apply(&self, client: &PromptGuardClient, file: &str, dry_run: bool) -> Result<()> {
-        println!("📋 Loading policy from {file}...\n");
+    fn apply(
+        client: &PromptGuardClient,
+        project_id: &str,
+        file: &str,
+        dry_run: bool,
+    ) -> Result<()> {
+        Output::line(&format!("📋 Loading policy from {file}...\n"));
 
         let desired = Self::load_yaml(file)?;
-        println!("✅ Policy validated successfully\n");
+        Output::line("✅ Policy validated successfully\n");
 
-        let current = self.fetch_current(client)?;
+        let current = Self::fetch_current(client, project_id)?;
         let diffs = Self::compute_diff(&current, &desired);
 
         if diffs.is_empty() {
-            println!("No changes - policy already matches.");
+            Output::line("No changes - policy already matches.");
             return Ok(());
         }
 
-        println!("Changes to apply:\n");
+        Output::line("Changes to apply:\n");
         for (field, old, new) in &diffs {
-            println!("  {field}: {old} -> {new}");
+            Output::line(&format!("  {field}: {old} -> {new}"));
         }
-        println!();
+        Output::line("");
 
         if dry_run {
-            println!("(dry-run) No changes applied.");
+            Output::line("(dry-run) No changes applied.");
             return Ok(());
         }
 
-        let endpoint = format!("/projects/{}/guardrails", self.project_id);
+        let endpoint = format!("/projects/{project_id}/guardrails");
         let _: serde_json::Value = client.put(
             &endpoint,
             &GuardrailsUpdateRequest {
@@ -240,33 +324,33 @@ impl PolicyCommand {
             },
         )?;
 
-        println!("✅ Policy applied successfully.");
+        Output::line("✅ Policy applied successfully.");
         Ok(())
     }
 
-    fn diff(&self, client: &PromptGuardClient, file: &str) -> Result<()> {
-        println!("📋 Comparing {file} against live config...\n");
+    fn diff(client: &PromptGuardClient, project_id: &str, file: &str) -> Result<()> {
+        Output::line(&format!("📋 Comparing {file} against live config...\n"));
 
         let desired = Self::load_yaml(file)?;
-        let current = self.fetch_current(client)?;
+        let current = Self::fetch_current(client, project_id)?;
         let diffs = Self::compute_diff(&current, &desired);
 
         if diffs.is_empty() {
-            println!("No differences - policy matches live config.");
+            Output::line("No differences - policy matches live config.");
         } else {
-            println!("Differences:\n");
+            Output::line("Differences:\n");
             for (field, old, new) in &diffs {
-                println!("  {field}:");
-                println!("    - {old}");
-                println!("    + {new}");
+                Output::line(&format!("  {field}:"));
+                Output::line(&format!("    - {old}"));
+                Output::line(&format!("    + {new}"));
             }
         }
 
         Ok(())
     }
 
-    fn export(&self, client: &PromptGuardClient) -> Result<()> {
-        let current = self.fetch_current(client)?;
+    fn export(client: &PromptGuardClient, project_id: &str) -> Result<()> {
+        let current = Self::fetch_current(client, project_id)?;
 
         let wrapper = serde_json::json!({ "guardrails": current });
         let yaml_value: serde_yaml::Value = serde_json::from_value(wrapper)
@@ -277,4 +361,160 @@ impl PolicyCommand {
         print!("{yaml_str}");
         Ok(())
     }
+
+    /// Preset names end up in URLs and file names
+    fn check_preset_name(name: &str) -> Result<()> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if valid {
+            Ok(())
+        } else {
+            Err(PromptGuardError::Config(format!(
+                "Invalid preset name '{name}': use letters, digits, '-' and '_'"
+            )))
+        }
+    }
+
+    /// The preset, or `None` if the backend has no preset by that name
+    fn fetch_preset(client: &PromptGuardClient, name: &str) -> Result<Option<Preset>> {
+        Self::check_preset_name(name)?;
+        match client.get(&format!("/policies/presets/{name}")) {
+            Ok(preset) => Ok(Some(preset)),
+            Err(PromptGuardError::Api(msg)) if msg.contains("404") => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn preset_yaml(preset: &Preset) -> Result<String> {
+        let yaml = serde_yaml::to_string(preset)
+            .map_err(|e| PromptGuardError::Config(format!("Failed to serialize YAML: {e}")))?;
+        Ok(format!(
+            "# PromptGuard policy preset '{}'\n# Edit, then run 'promptguard policy push <file>' to update it\n{yaml}",
+            preset.name
+        ))
+    }
+
+    fn list(client: &PromptGuardClient) -> Result<()> {
+        let list: PresetList = client.get("/policies/presets")?;
+        if list.presets.is_empty() {
+            Output::line("No policy presets. Create one with 'promptguard policy push <file>'.");
+            return Ok(());
+        }
+
+        let width = list
+            .presets
+            .iter()
+            .map(|p| p.name.chars().count())
+            .max()
+            .unwrap_or(0);
+        for preset in &list.presets {
+            let mut line = format!("  {:<width$}", preset.name);
+            if let Some(ref description) = preset.description {
+                line.push_str("  ");
+                line.push_str(description);
+            }
+            if let Some(ref updated_at) = preset.updated_at {
+                let _ = write!(line, "  (updated {updated_at})");
+            }
+            Output::line(line.trim_end());
+        }
+        Ok(())
+    }
+
+    fn show(client: &PromptGuardClient, name: &str) -> Result<()> {
+        let preset = Self::fetch_preset(client, name)?.ok_or_else(|| Self::not_found(name))?;
+        print!("{}", Self::preset_yaml(&preset)?);
+        Ok(())
+    }
+
+    fn pull(client: &PromptGuardClient, name: &str, output: Option<&str>) -> Result<()> {
+        let preset = Self::fetch_preset(client, name)?.ok_or_else(|| Self::not_found(name))?;
+        let path = match output {
+            Some(output) => PathBuf::from(output),
+            None => crate::config::project_root()?
+                .join(PRESETS_DIR)
+                .join(format!("{name}.yaml")),
+        };
+        let content = Self::preset_yaml(&preset)?;
+
+        if fs::read_to_string(&path).is_ok_and(|existing| existing == content) {
+            Output::line(&format!("{} is up to date.", path.display()));
+            return Ok(());
+        }
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, content)?;
+        Output::line(&format!("✅ Pulled preset '{name}' to {}", path.display()));
+        Ok(())
+    }
+
+    fn push(
+        client: &PromptGuardClient,
+        file: &str,
+        name: Option<&str>,
+        dry_run: bool,
+    ) -> Result<()> {
+        Output::line(&format!("📋 Loading policy from {file}...\n"));
+
+        let guardrails = Self::load_yaml(file)?;
+        let document = Self::read_yaml(file)?;
+        let name = name
+            .map(str::to_string)
+            .or_else(|| document.get("name")?.as_str().map(str::to_string))
+            .or_else(|| {
+                Path::new(file)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+            })
+            .unwrap_or_default();
+        Self::check_preset_name(&name)?;
+        let preset = Preset {
+            description: document
+                .get("description")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            name,
+            guardrails,
+        };
+        Output::line("✅ Policy validated successfully\n");
+
+        let existing = Self::fetch_preset(client, &preset.name)?;
+        match existing {
+            Some(ref existing) => {
+                let diffs = Self::compute_diff(&existing.guardrails, &preset.guardrails);
+                if diffs.is_empty() && existing.description == preset.description {
+                    Output::line(&format!(
+                        "No changes - preset '{}' already matches.",
+                        preset.name
+                    ));
+                    return Ok(());
+                }
+                Output::line(&format!("Changes to preset '{}':\n", preset.name));
+                for (field, old, new) in &diffs {
+                    Output::line(&format!("  {field}: {old} -> {new}"));
+                }
+                Output::line("");
+            },
+            None => Output::line(&format!("Preset '{}' will be created.\n", preset.name)),
+        }
+
+        if dry_run {
+            Output::line("(dry-run) Nothing pushed.");
+            return Ok(());
+        }
+
+        let _: serde_json::Value =
+            client.put(&format!("/policies/presets/{}", preset.name), &preset)?;
+        Output::line(&format!("✅ Pushed preset '{}'.", preset.name));
+        Ok(())
+    }
+
+    fn not_found(name: &str) -> PromptGuardError {
+        PromptGuardError::Config(format!(
+            "No policy preset named '{name}'. Run 'promptguard policy list' to see presets"
+        ))
+    }
 }
@@ -1,11 +1,23 @@
 use crate::api::PromptGuardClient;
 use crate::auth::{load_credentials, resolve_api_key, resolve_base_url, save_credentials};
-use crate::error::Result;
+use crate::config::ConfigManager;
+use crate::error::{PromptGuardError, Result};
 use crate::output::Output;
 
 pub enum ProjectsAction {
     List,
-    Select { project_id: String },
+    Select {
+        project_id: String,
+    },
+    /// Create a backend project, optionally linking this repo to it
+    Create {
+        name: String,
+        link: bool,
+    },
+    /// Record the project in `.promptguard.json` so logs and stats are scoped to it
+    Link {
+        project_id: String,
+    },
 }
 
 pub struct ProjectsCommand {
@@ -18,14 +30,27 @@ impl ProjectsCommand {
         match &self.action {
             ProjectsAction::List => self.list(),
             ProjectsAction::Select { project_id } => self.select(project_id),
+            ProjectsAction::Create { name, link } => self.create(name, *link),
+            ProjectsAction::Link { project_id } => self.link(project_id),
         }
     }
 
-    fn list(&self) -> Result<()> {
-        let api_key = resolve_api_key()?;
-        let base_url = resolve_base_url();
-        let client = PromptGuardClient::new(api_key, Some(base_url))?;
+    fn client() -> Result<PromptGuardClient> {
+        PromptGuardClient::new(resolve_api_key()?, Some(resolve_base_url()))
+    }
+
+    /// Project this repo's `.promptguard.json` is linked to
+    fn linked_project() -> Option<String> {
+        ConfigManager::new(None)
+            .ok()
+            .filter(ConfigManager::exists)
+            .and_then(|m| m.load().ok())
+            .and_then(|c| c.project_id)
+    }
 
+    fn list(&self) -> Result<()> {
+        let client = Self::client()?;
+        let linked_project = Self::linked_project();
         let active_project = load_credentials()
             .ok()
             .flatten()
@@ -37,11 +62,9 @@ impl ProjectsCommand {
             let result = serde_json::json!({
                 "projects": projects,
                 "active_project": active_project,
+                "linked_project": linked_project,
             });
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&result).unwrap_or_default()
-            );
+            Output::json(result)?;
             return Ok(());
         }
 
@@ -59,11 +82,13 @@ impl ProjectsCommand {
                     .get("name")
                     .and_then(|v| v.as_str())
                     .unwrap_or("Unnamed");
-                let marker = if active_project.as_deref() == Some(id) {
-                    " (active)"
-                } else {
-                    ""
-                };
+                let mut marker = String::new();
+                if active_project.as_deref() == Some(id) {
+                    marker.push_str(" (active)");
+                }
+                if linked_project.as_deref() == Some(id) {
+                    marker.push_str(" (linked)");
+                }
                 Output::step(&format!("{name} [{id}]{marker}"));
             }
         } else {
@@ -74,14 +99,10 @@ impl ProjectsCommand {
     }
 
     fn select(&self, project_id: &str) -> Result<()> {
-        let mut creds = load_credentials()?.unwrap_or_else(|| crate::auth::GlobalCredentials {
-            api_key: String::new(),
-            base_url: None,
-            active_project: None,
-        });
-
-        // If no API key in global creds, try resolving
-        if creds.api_key.is_empty() {
+        let mut creds = load_credentials()?.unwrap_or_default();
+
+        // If no API key or login in global creds, try resolving
+        if creds.secret().is_none() {
             creds.api_key = resolve_api_key()?;
         }
 
@@ -93,14 +114,97 @@ impl ProjectsCommand {
                 "active_project": project_id,
                 "status": "selected",
             });
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&result).unwrap_or_default()
-            );
+            Output::json(result)?;
         } else {
             Output::success(&format!("Active project set to: {project_id}"));
         }
 
         Ok(())
     }
+
+    fn create(&self, name: &str, link: bool) -> Result<()> {
+        if name.trim().is_empty() {
+            return Err(PromptGuardError::Custom(
+                "Project name cannot be empty".to_string(),
+            ));
+        }
+        let client = Self::client()?;
+        let project: serde_json::Value =
+            client.post("/projects", &serde_json::json!({ "name": name }))?;
+        let Some(id) = project.get("id").and_then(|v| v.as_str()) else {
+            return Err(PromptGuardError::Api(
+                "Project was created but the API returned no id".to_string(),
+            ));
+        };
+        if link {
+            Self::save_link(id)?;
+        }
+
+        if self.json {
+            let result = serde_json::json!({
+                "project": project,
+                "linked": link,
+                "status": "created",
+            });
+            Output::json(result)?;
+        } else {
+            Output::success(&format!("Created project {name} [{id}]"));
+            if link {
+                Output::step("Linked .promptguard.json to it");
+            } else {
+                Output::info(&format!(
+                    "Run 'promptguard projects link {id}' to scope this repo's logs and stats to it"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn link(&self, project_id: &str) -> Result<()> {
+        // Catch typos before they silently scope logs and stats to nothing
+        let projects: serde_json::Value = Self::client()?.get("/projects")?;
+        let project = projects
+            .as_array()
+            .and_then(|arr| {
+                arr.iter()
+                    .find(|p| p.get("id").and_then(|v| v.as_str()) == Some(project_id))
+            })
+            .ok_or_else(|| {
+                PromptGuardError::Config(format!(
+                    "No project with id '{project_id}'. Run 'promptguard projects list' to see your projects"
+                ))
+            })?;
+        let name = project.get("name").and_then(|v| v.as_str());
+        let previous = Self::save_link(project_id)?;
+
+        if self.json {
+            let result = serde_json::json!({
+                "linked_project": project_id,
+                "previous_project": previous,
+                "status": "linked",
+            });
+            Output::json(result)?;
+        } else {
+            let label = name.map_or_else(
+                || project_id.to_string(),
+                |name| format!("{name} [{project_id}]"),
+            );
+            Output::success(&format!("Linked .promptguard.json to project {label}"));
+            if let Some(previous) = previous.filter(|p| p != project_id) {
+                Output::step(&format!("Previously linked to {previous}"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Set `project_id` in the project config, returning the one it replaces
+    fn save_link(project_id: &str) -> Result<Option<String>> {
+        let config_manager = ConfigManager::new(None)?;
+        let mut config = config_manager.load()?;
+        let previous = config.project_id.replace(project_id.to_string());
+        config_manager.save(&config)?;
+        Ok(previous)
+    }
 }
@@ -1,7 +1,9 @@
 //! Redact Command - Remove PII and sensitive data from text
 //!
 //! Calls the `PromptGuard` `/security/redact` API endpoint to redact
-//! sensitive information like emails, phone numbers, SSNs, etc.
+//! sensitive information like emails, phone numbers, SSNs, etc. Input can be
+//! text, a file, stdin as a pipeline filter (`redact -`), or every file in a
+//! directory matching a glob, redacted into copies alongside the originals.
 
 use crate::api::PromptGuardClient;
 use crate::config::ConfigManager;
@@ -9,6 +11,12 @@ use crate::error::{PromptGuardError, Result};
 use crate::output::Output;
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Marker inserted before the extension of redacted copies
+const REDACTED_MARKER: &str = "redacted";
 
 /// Response from the /security/redact endpoint.
 ///
@@ -22,21 +30,36 @@ pub struct RedactResponse {
 }
 
 pub struct RedactCommand {
+    /// `-` filters stdin to stdout; a path redacts that file to stdout
+    pub input: Option<String>,
     /// Text to redact
     pub text: Option<String>,
-    /// File path to read and redact
+    /// File path to read and redact (`-` for stdin)
     pub file: Option<String>,
     /// Output file path (if not provided, prints to stdout)
     pub output: Option<String>,
+    /// Directory whose files are redacted into copies alongside them
+    pub dir: Option<String>,
+    /// Files in `dir` to redact, matched against the name or relative path
+    pub glob: String,
     /// Output as JSON
     pub json: bool,
 }
 
 impl RedactCommand {
     pub fn execute(&self) -> Result<()> {
+        if let Some(ref dir) = self.dir {
+            return self.redact_dir(Path::new(dir));
+        }
+        if let Some(ref input) = self.input {
+            return self.filter(input);
+        }
+
         // Get content to redact
         let content = if let Some(ref text) = self.text {
             text.clone()
+        } else if self.file.as_deref() == Some("-") {
+            std::io::read_to_string(std::io::stdin())?
         } else if let Some(ref file_path) = self.file {
             fs::read_to_string(file_path).map_err(|e| {
                 PromptGuardError::Io(std::io::Error::new(
@@ -46,15 +69,11 @@ impl RedactCommand {
             })?
         } else {
             return Err(PromptGuardError::Custom(
-                "Either --text or --file must be provided".to_string(),
+                "Pass --text or --file, --dir for a directory, or '-' to filter stdin".to_string(),
             ));
         };
 
-        // Get API key from config
-        let config_manager = ConfigManager::new(None)?;
-        let config = config_manager.load()?;
-
-        let client = PromptGuardClient::new(config.api_key, Some(config.proxy_url))?;
+        let client = Self::client()?;
 
         if !self.json {
             Output::header(&format!(
@@ -65,41 +84,28 @@ impl RedactCommand {
             Output::info(&format!("Processing {} characters...", content.len()));
         }
 
-        let response: RedactResponse = client.post(
-            "/security/redact",
-            &serde_json::json!({
-                "content": content,
-            }),
-        )?;
+        let response = Self::redact(&client, &content)?;
 
         if let Some(ref output_path) = self.output {
-            fs::write(output_path, &response.redacted).map_err(|e| {
-                PromptGuardError::Io(std::io::Error::new(
-                    e.kind(),
-                    format!("Failed to write output file '{output_path}': {e}"),
-                ))
-            })?;
+            Self::write_output(output_path, &response.redacted)?;
 
             if !self.json {
                 Output::success(&format!("Redacted content written to {output_path}"));
-                println!();
-                println!("PII types redacted: {}", response.pii_found.len());
+                Output::line("");
+                Output::line(&format!("PII types redacted: {}", response.pii_found.len()));
                 for pii_type in &response.pii_found {
-                    println!("  • {pii_type}");
+                    Output::line(&format!("  • {pii_type}"));
                 }
             }
         } else if self.json {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response).unwrap_or_default()
-            );
+            Output::json(serde_json::to_value(&response)?)?;
         } else {
-            println!();
-            println!("Redacted Text:");
-            println!("─────────────────────────────────────────────────");
+            Output::line("");
+            Output::line("Redacted Text:");
+            Output::line("─────────────────────────────────────────────────");
             println!("{}", response.redacted);
-            println!("─────────────────────────────────────────────────");
-            println!();
+            Output::line("─────────────────────────────────────────────────");
+            Output::line("");
 
             if response.pii_found.is_empty() {
                 Output::info("No sensitive entities detected.");
@@ -108,14 +114,208 @@ impl RedactCommand {
                     "{} PII type(s) redacted",
                     response.pii_found.len()
                 ));
-                println!();
-                println!("PII types found:");
+                Output::line("");
+                Output::line("PII types found:");
                 for pii_type in &response.pii_found {
-                    println!("  • {pii_type}");
+                    Output::line(&format!("  • {pii_type}"));
                 }
             }
         }
 
         Ok(())
     }
+
+    fn client() -> Result<PromptGuardClient> {
+        let config = ConfigManager::new(None)?.load()?;
+        PromptGuardClient::new(
+            config.api_key().to_string(),
+            Some(config.proxy_url.default_url().to_string()),
+        )
+    }
+
+    fn redact(client: &PromptGuardClient, content: &str) -> Result<RedactResponse> {
+        client.post(
+            "/security/redact",
+            &serde_json::json!({
+                "content": content,
+            }),
+        )
+    }
+
+    async fn redact_async(client: &PromptGuardClient, content: &str) -> Result<RedactResponse> {
+        client
+            .post_async(
+                "/security/redact",
+                &serde_json::json!({
+                    "content": content,
+                }),
+            )
+            .await
+    }
+
+    fn write_output(output_path: &str, redacted: &str) -> Result<()> {
+        fs::write(output_path, redacted).map_err(|e| {
+            PromptGuardError::Io(std::io::Error::new(
+                e.kind(),
+                format!("Failed to write output file '{output_path}': {e}"),
+            ))
+        })
+    }
+
+    /// Redact stdin (`-`) or a file and write only the redacted text, so the
+    /// command composes in pipelines
+    fn filter(&self, input: &str) -> Result<()> {
+        let content = if input == "-" {
+            std::io::read_to_string(std::io::stdin())?
+        } else {
+            fs::read_to_string(input).map_err(|e| {
+                PromptGuardError::Io(std::io::Error::new(
+                    e.kind(),
+                    format!("Failed to read file '{input}': {e}"),
+                ))
+            })?
+        };
+        let response = Self::redact(&Self::client()?, &content)?;
+
+        if self.json {
+            Output::json(serde_json::to_value(&response)?)?;
+            return Ok(());
+        }
+        let mut redacted = response.redacted;
+        // Keep the line ending a pipeline's next command may rely on
+        if content.ends_with('\n') && !redacted.ends_with('\n') {
+            redacted.push('\n');
+        }
+        if let Some(ref output_path) = self.output {
+            return Self::write_output(output_path, &redacted);
+        }
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(redacted.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    /// Files under `dir` matching the glob, skipping earlier redacted copies
+    fn batch_files(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        if !dir.is_dir() {
+            return Err(PromptGuardError::Custom(format!(
+                "'{}' is not a directory",
+                dir.display()
+            )));
+        }
+        let pattern = glob::Pattern::new(&self.glob).map_err(|e| {
+            PromptGuardError::Custom(format!("Invalid --glob '{}': {e}", self.glob))
+        })?;
+        let mut files: Vec<PathBuf> = WalkDir::new(dir)
+            .follow_links(false)
+            .into_iter()
+            .filter_map(std::result::Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .map(walkdir::DirEntry::into_path)
+            .filter(|path| {
+                let rel_path = path.strip_prefix(dir).unwrap_or(path);
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                (pattern.matches(&name) || pattern.matches_path(rel_path))
+                    && !Self::is_redacted_copy(path)
+            })
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// `notes.txt` → `notes.redacted.txt`
+    fn redacted_path(path: &Path) -> PathBuf {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match path.extension() {
+            Some(ext) => format!("{stem}.{REDACTED_MARKER}.{}", ext.to_string_lossy()),
+            None => format!("{stem}.{REDACTED_MARKER}"),
+        };
+        path.with_file_name(name)
+    }
+
+    fn is_redacted_copy(path: &Path) -> bool {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let marker = format!(".{REDACTED_MARKER}");
+        stem.ends_with(&marker) || path.extension().is_some_and(|e| e == REDACTED_MARKER)
+    }
+
+    /// Redact every matching file in `dir` into a copy next to it
+    fn redact_dir(&self, dir: &Path) -> Result<()> {
+        let files = self.batch_files(dir)?;
+        let client = Self::client()?;
+        if !self.json {
+            Output::header(&format!(
+                "🛡️  PromptGuard CLI v{}",
+                env!("CARGO_PKG_VERSION")
+            ));
+            Output::section("PII Redaction", "🔒");
+            Output::info(&format!(
+                "Redacting {} files matching '{}' in {}",
+                files.len(),
+                self.glob,
+                dir.display()
+            ));
+        }
+
+        let mut texts = Vec::new();
+        let mut failed = 0;
+        for path in &files {
+            let rel_path = path.strip_prefix(dir).unwrap_or(path).display().to_string();
+            let Ok(content) = fs::read_to_string(path) else {
+                Output::warning(&format!("{rel_path}: not UTF-8 text, skipped"));
+                failed += 1;
+                continue;
+            };
+            texts.push((path, rel_path, content));
+        }
+
+        // Files are redacted concurrently, then written in order
+        let mut responses = Vec::with_capacity(texts.len());
+        client.run_concurrent(
+            texts
+                .iter()
+                .map(|(_, _, content)| Self::redact_async(&client, content)),
+            |response| responses.push(response),
+        );
+
+        let mut results = Vec::new();
+        for ((path, rel_path, _), response) in texts.iter().zip(responses) {
+            let response = response?;
+            let output_path = Self::redacted_path(path);
+            fs::write(&output_path, &response.redacted)?;
+            let output_rel = output_path
+                .strip_prefix(dir)
+                .unwrap_or(&output_path)
+                .display()
+                .to_string();
+            if !self.json {
+                Output::step(&format!(
+                    "✓ {rel_path} → {output_rel} ({} PII types)",
+                    response.pii_found.len()
+                ));
+            }
+            results.push(serde_json::json!({
+                "file": rel_path,
+                "output": output_rel,
+                "piiFound": response.pii_found,
+            }));
+        }
+
+        if self.json {
+            Output::json(serde_json::to_value(&results)?)?;
+        } else {
+            Output::line("");
+            if files.is_empty() {
+                Output::info("No files matched");
+            } else {
+                Output::success(&format!("Redacted {} files", results.len()));
+            }
+        }
+        if failed > 0 {
+            return Err(PromptGuardError::Custom(format!(
+                "{failed} files could not be read as text"
+            )));
+        }
+        Ok(())
+    }
 }
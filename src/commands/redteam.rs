@@ -6,6 +6,7 @@
 use crate::api::PromptGuardClient;
 use crate::config::ConfigManager;
 use crate::error::{PromptGuardError, Result};
+use crate::output::Output;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -85,7 +86,9 @@ impl Default for RedTeamCommand {
 
 impl RedTeamCommand {
     pub fn execute(self) -> Result<()> {
-        println!("🔴 PromptGuard Red Team - Adversarial Security Testing\n");
+        if !self.json() {
+            Output::line("🔴 PromptGuard Red Team - Adversarial Security Testing\n");
+        }
 
         // Get API key from config or argument
         let api_key = if let Some(key) = &self.api_key {
@@ -94,7 +97,7 @@ impl RedTeamCommand {
             ConfigManager::new(None)
                 .ok()
                 .and_then(|cm| cm.load().ok())
-                .map(|c| c.api_key)
+                .map(|c| c.api_key().to_string())
                 .ok_or_else(|| {
                     PromptGuardError::Config(
                         "API key required. Run 'promptguard init' or pass --api-key".to_string(),
@@ -119,11 +122,18 @@ impl RedTeamCommand {
         Ok(())
     }
 
+    /// `--format json` or the global `--json`: print only the versioned JSON
+    fn json(&self) -> bool {
+        self.output_format == "json" || Output::is_json()
+    }
+
     fn run_all_tests(&self, client: &PromptGuardClient) -> Result<()> {
-        println!(
-            "Running all red team tests against preset '{}'...\n",
-            self.preset
-        );
+        if !self.json() {
+            Output::line(&format!(
+                "Running all red team tests against preset '{}'...\n",
+                self.preset
+            ));
+        }
 
         // Call the API
         let summary: RedTeamSummary = client
@@ -136,6 +146,10 @@ impl RedTeamCommand {
             )
             .map_err(|e| PromptGuardError::Api(format!("Failed to run tests: {e}")))?;
 
+        if self.json() {
+            return Output::json(serde_json::to_value(&summary)?);
+        }
+
         // Print results
         for result in &summary.results {
             let status = if result.blocked {
@@ -143,42 +157,37 @@ impl RedTeamCommand {
             } else {
                 "❌ PASSED THROUGH"
             };
-            println!(
+            Output::line(&format!(
                 "  {} - {} (confidence: {:.0}%)",
                 result.test_name,
                 status,
                 result.confidence * 100.0
-            );
+            ));
 
             if self.verbose {
-                println!(
+                Output::line(&format!(
                     "    Prompt: {}...",
                     &result.prompt[..result.prompt.len().min(60)]
-                );
-                println!("    Reason: {}", result.reason);
+                ));
+                Output::line(&format!("    Reason: {}", result.reason));
                 if let Some(threat) = &result.threat_type {
-                    println!("    Threat: {threat}");
+                    Output::line(&format!("    Threat: {threat}"));
                 }
             }
         }
 
         self.print_summary(&summary);
 
-        if self.output_format == "json" {
-            println!(
-                "\n{}",
-                serde_json::to_string_pretty(&summary).unwrap_or_default()
-            );
-        }
-
         Ok(())
     }
 
     fn run_single_test(&self, client: &PromptGuardClient, test_name: &str) -> Result<()> {
-        println!(
-            "Running test '{}' against preset '{}'...\n",
-            test_name, self.preset
-        );
+        if !self.json() {
+            Output::line(&format!(
+                "Running test '{}' against preset '{}'...\n",
+                test_name, self.preset
+            ));
+        }
 
         let result: RedTeamTestResult = client
             .post(
@@ -190,36 +199,38 @@ impl RedTeamCommand {
             )
             .map_err(|e| PromptGuardError::Api(format!("Failed to run test: {e}")))?;
 
+        if self.json() {
+            return Output::json(serde_json::to_value(&result)?);
+        }
+
         let status = if result.blocked {
             "✅ BLOCKED"
         } else {
             "❌ PASSED THROUGH"
         };
-        println!("Result: {status}");
-        println!("Decision: {}", result.decision);
-        println!("Reason: {}", result.reason);
-        println!("Confidence: {:.0}%", result.confidence * 100.0);
+        Output::line(&format!("Result: {status}"));
+        Output::line(&format!("Decision: {}", result.decision));
+        Output::line(&format!("Reason: {}", result.reason));
+        Output::line(&format!("Confidence: {:.0}%", result.confidence * 100.0));
 
         if let Some(threat) = &result.threat_type {
-            println!("Threat Type: {threat}");
-        }
-
-        if self.output_format == "json" {
-            println!(
-                "\n{}",
-                serde_json::to_string_pretty(&result).unwrap_or_default()
-            );
+            Output::line(&format!("Threat Type: {threat}"));
         }
 
         Ok(())
     }
 
     fn run_custom_test(&self, client: &PromptGuardClient, prompt: &str) -> Result<()> {
-        println!(
-            "Running custom adversarial test against preset '{}'...\n",
-            self.preset
-        );
-        println!("Prompt: {}...\n", &prompt[..prompt.len().min(100)]);
+        if !self.json() {
+            Output::line(&format!(
+                "Running custom adversarial test against preset '{}'...\n",
+                self.preset
+            ));
+            Output::line(&format!(
+                "Prompt: {}...\n",
+                &prompt[..prompt.len().min(100)]
+            ));
+        }
 
         let result: RedTeamTestResult = client
             .post(
@@ -231,32 +242,31 @@ impl RedTeamCommand {
             )
             .map_err(|e| PromptGuardError::Api(format!("Failed to run custom test: {e}")))?;
 
+        if self.json() {
+            return Output::json(serde_json::to_value(&result)?);
+        }
+
         let status = if result.blocked {
             "✅ BLOCKED"
         } else {
             "❌ PASSED THROUGH"
         };
-        println!("Result: {status}");
-        println!("Decision: {}", result.decision);
-        println!("Reason: {}", result.reason);
-        println!("Confidence: {:.0}%", result.confidence * 100.0);
-
-        if self.output_format == "json" {
-            println!(
-                "\n{}",
-                serde_json::to_string_pretty(&result).unwrap_or_default()
-            );
-        }
+        Output::line(&format!("Result: {status}"));
+        Output::line(&format!("Decision: {}", result.decision));
+        Output::line(&format!("Reason: {}", result.reason));
+        Output::line(&format!("Confidence: {:.0}%", result.confidence * 100.0));
 
         Ok(())
     }
 
     fn run_autonomous(&self, client: &PromptGuardClient) -> Result<()> {
-        println!(
-            "Running autonomous red team agent (budget: {}, preset: '{}')...\n",
-            self.budget, self.preset
-        );
-        println!("This may take a while - the agent uses LLM-powered mutation\n");
+        if !self.json() {
+            Output::line(&format!(
+                "Running autonomous red team agent (budget: {}, preset: '{}')...\n",
+                self.budget, self.preset
+            ));
+            Output::line("This may take a while - the agent uses LLM-powered mutation\n");
+        }
 
         let report: AutonomousReport = client
             .post(
@@ -268,79 +278,78 @@ impl RedTeamCommand {
             )
             .map_err(|e| PromptGuardError::Api(format!("Autonomous agent failed: {e}")))?;
 
-        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        println!("🤖 Autonomous Red Team Report");
-        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+        if self.json() {
+            return Output::json(serde_json::json!({
+                "grade": report.grade,
+                "bypass_rate": report.bypass_rate,
+                "total_attempts": report.total_attempts,
+                "bypasses_found": report.bypasses_found,
+                "bypasses": report.bypasses,
+                "recommendations": report.recommendations,
+            }));
+        }
+
+        Output::line("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        Output::line("🤖 Autonomous Red Team Report");
+        Output::line("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
 
-        println!("  Grade:            {}", report.grade);
-        println!("  Bypass Rate:      {:.1}%", report.bypass_rate * 100.0);
-        println!("  Total Attempts:   {}", report.total_attempts);
-        println!("  Bypasses Found:   {}\n", report.bypasses_found);
+        Output::line(&format!("  Grade:            {}", report.grade));
+        Output::line(&format!(
+            "  Bypass Rate:      {:.1}%",
+            report.bypass_rate * 100.0
+        ));
+        Output::line(&format!("  Total Attempts:   {}", report.total_attempts));
+        Output::line(&format!("  Bypasses Found:   {}\n", report.bypasses_found));
 
         if !report.bypasses.is_empty() && self.verbose {
-            println!("⚠️  Discovered Bypasses:\n");
+            Output::line("⚠️  Discovered Bypasses:\n");
             for (i, bypass) in report.bypasses.iter().enumerate() {
-                println!("  {}. {}", i + 1, bypass);
+                Output::line(&format!("  {}. {}", i + 1, bypass));
             }
-            println!();
+            Output::line("");
         }
 
         if !report.recommendations.is_empty() {
-            println!("📋 Recommendations:\n");
+            Output::line("📋 Recommendations:\n");
             for rec in &report.recommendations {
-                println!("  • {rec}");
+                Output::line(&format!("  • {rec}"));
             }
-            println!();
-        }
-
-        if self.output_format == "json" {
-            let json_out = serde_json::json!({
-                "grade": report.grade,
-                "bypass_rate": report.bypass_rate,
-                "total_attempts": report.total_attempts,
-                "bypasses_found": report.bypasses_found,
-                "bypasses": report.bypasses,
-                "recommendations": report.recommendations,
-            });
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&json_out).unwrap_or_default()
-            );
+            Output::line("");
         }
 
-        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+        Output::line("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
 
         Ok(())
     }
 
     fn print_summary(&self, summary: &RedTeamSummary) {
-        println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        println!("📊 Security Assessment Report");
-        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+        Output::line("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        Output::line("📊 Security Assessment Report");
+        Output::line("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
 
         let score = summary.block_rate * 100.0;
 
-        println!("  Total Attacks:      {}", summary.total_tests);
-        println!("  Attacks Blocked:    {} ✅", summary.blocked);
-        println!("  Attacks Passed:     {} ❌", summary.allowed);
-        println!("  Security Score:     {score:.1}/100\n");
+        Output::line(&format!("  Total Attacks:      {}", summary.total_tests));
+        Output::line(&format!("  Attacks Blocked:    {} ✅", summary.blocked));
+        Output::line(&format!("  Attacks Passed:     {} ❌", summary.allowed));
+        Output::line(&format!("  Security Score:     {score:.1}/100\n"));
 
         if summary.allowed > 0 {
-            println!("⚠️  Vulnerabilities Found:\n");
+            Output::line("⚠️  Vulnerabilities Found:\n");
             for result in &summary.results {
                 if !result.blocked {
-                    println!("  • {} - {}", result.test_name, result.reason);
+                    Output::line(&format!("  • {} - {}", result.test_name, result.reason));
                 }
             }
-            println!();
-            println!("📋 Recommendations:\n");
-            println!("  1. Enable PromptGuard ML detection for advanced threats");
-            println!("  2. Review and strengthen your policy presets");
-            println!("  3. Add custom rules for specific attack patterns");
+            Output::line("");
+            Output::line("📋 Recommendations:\n");
+            Output::line("  1. Enable PromptGuard ML detection for advanced threats");
+            Output::line("  2. Review and strengthen your policy presets");
+            Output::line("  3. Add custom rules for specific attack patterns");
         } else {
-            println!("✨ Your application passed all security tests!");
+            Output::line("✨ Your application passed all security tests!");
         }
 
-        println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+        Output::line("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
     }
 }
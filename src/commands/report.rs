@@ -0,0 +1,462 @@
+//! Report Command - Shareable security review document
+//!
+//! Collects the project's configuration, coverage of SDK call sites, the
+//! SDKs its manifests declare, and environment findings (the checks
+//! `doctor` runs) into one Markdown or HTML file.
+
+use crate::backup::BackupManager;
+use crate::commands::audit::{self, AuditEntry};
+use crate::commands::{coverage, DoctorCommand, ShimCommand};
+use crate::config::{ConfigManager, EnvFileState, PromptGuardConfig};
+use crate::detector::detect_declared_providers;
+use crate::error::{PromptGuardError, Result};
+use crate::output::Output;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Severity of an environment finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Ok,
+    Warning,
+    Error,
+}
+
+impl Level {
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Ok => "✓",
+            Self::Warning => "⚠",
+            Self::Error => "✗",
+        }
+    }
+
+    fn class(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// A titled block of the report; rendered the same way in every format
+#[derive(Debug, Default)]
+struct Section {
+    title: String,
+    /// Label/value pairs
+    facts: Vec<(String, String)>,
+    findings: Vec<(Level, String)>,
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+    note: Option<String>,
+}
+
+impl Section {
+    fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            ..Self::default()
+        }
+    }
+
+    fn fact(&mut self, label: &str, value: impl Into<String>) {
+        self.facts.push((label.to_string(), value.into()));
+    }
+
+    fn finding(&mut self, level: Level, text: impl Into<String>) {
+        self.findings.push((level, text.into()));
+    }
+}
+
+pub struct ReportCommand {
+    /// md or html; inferred from `output` when unset
+    pub format: Option<String>,
+    /// File to write; stdout when unset
+    pub output: Option<PathBuf>,
+}
+
+impl ReportCommand {
+    pub fn execute(&self) -> Result<()> {
+        let format = self.format()?;
+        let config_manager = ConfigManager::new(None)?;
+        if !config_manager.exists() {
+            return Err(PromptGuardError::NotInitialized);
+        }
+        let config = config_manager.load()?;
+        let root_path = crate::config::project_root()?;
+
+        let (sites, ignored) = coverage::call_sites(&root_path, &config, false)?;
+        let percent = coverage::percent(&sites);
+        let entries = audit::inventory(&root_path, sites);
+
+        let project = root_path.file_name().map_or_else(
+            || root_path.display().to_string(),
+            |n| n.to_string_lossy().to_string(),
+        );
+        let generated = chrono::Utc::now().format("%Y-%m-%d %H:%M UTC").to_string();
+        let sections = vec![
+            Self::summary(&config, &entries, ignored, percent),
+            Self::call_sites(&entries),
+            Self::dependencies(&root_path),
+            Self::environment(&root_path, &config),
+            Self::configuration(&config),
+        ];
+
+        let title = format!("PromptGuard Security Report: {project}");
+        let subtitle = format!(
+            "Generated {generated} by promptguard v{}",
+            env!("CARGO_PKG_VERSION")
+        );
+        let content = if format == "html" {
+            render_html(&title, &subtitle, &sections)
+        } else {
+            render_markdown(&title, &subtitle, &sections)
+        };
+
+        if let Some(path) = &self.output {
+            std::fs::write(path, content)?;
+            Output::success(&format!("Wrote report to {}", path.display()));
+        } else {
+            print!("{content}");
+        }
+        Ok(())
+    }
+
+    fn format(&self) -> Result<&str> {
+        let format = match (&self.format, &self.output) {
+            (Some(format), _) => format.as_str(),
+            (None, Some(path))
+                if path.extension().is_some_and(|e| {
+                    e.eq_ignore_ascii_case("html") || e.eq_ignore_ascii_case("htm")
+                }) =>
+            {
+                "html"
+            },
+            (None, _) => "md",
+        };
+        match format {
+            "md" | "markdown" => Ok("md"),
+            "html" => Ok("html"),
+            other => Err(PromptGuardError::Custom(format!(
+                "Unknown report format '{other}'. Supported: html, md"
+            ))),
+        }
+    }
+
+    fn summary(
+        config: &PromptGuardConfig,
+        entries: &[AuditEntry],
+        ignored: usize,
+        percent: f64,
+    ) -> Section {
+        let mut section = Section::new("Summary");
+        let status = if !config.enabled {
+            "Disabled"
+        } else if config.runtime_mode {
+            "Active (runtime shims)"
+        } else if config.env_only {
+            "Active (env-only)"
+        } else {
+            "Active (code transforms)"
+        };
+        section.fact("Status", status);
+        section.fact("Proxy URL", config.proxy_url.to_string());
+        section.fact("Providers", config.providers.join(", "));
+        let guarded = entries.iter().filter(|e| e.guarded).count();
+        section.fact(
+            "Coverage",
+            format!(
+                "{percent:.1}% ({guarded} of {} call sites guarded)",
+                entries.len()
+            ),
+        );
+        if ignored > 0 {
+            section.fact("Ignored call sites", ignored.to_string());
+        }
+        section
+    }
+
+    fn call_sites(entries: &[AuditEntry]) -> Section {
+        let mut section = Section::new("Call Sites");
+        if entries.is_empty() {
+            section.note = Some("No LLM SDK call sites found.".to_string());
+            return section;
+        }
+        section.headers = vec!["Location", "Provider", "Client", "Models", "Routed by"];
+        section.rows = entries
+            .iter()
+            .map(|e| {
+                vec![
+                    format!("{}:{}", e.file, e.line),
+                    e.provider.as_str().to_string(),
+                    e.client.clone(),
+                    e.models.join(", "),
+                    e.guard.map_or_else(
+                        || format!("✗ {}", e.gap.as_deref().unwrap_or("unguarded")),
+                        |g| format!("✓ {}", g.as_str()),
+                    ),
+                ]
+            })
+            .collect();
+        section
+    }
+
+    fn dependencies(root_path: &Path) -> Section {
+        let mut section = Section::new("Declared SDKs");
+        let declared = detect_declared_providers(root_path);
+        if declared.is_empty() {
+            section.note = Some("No LLM SDKs declared in dependency manifests.".to_string());
+            return section;
+        }
+        section.headers = vec!["Package", "Version", "Manifest", "Base URL support"];
+        section.rows = declared
+            .iter()
+            .map(|dep| {
+                let support = if dep.is_outdated() {
+                    format!(
+                        "⚠ requires >= {}",
+                        dep.min_base_url_version().unwrap_or_default()
+                    )
+                } else {
+                    "✓".to_string()
+                };
+                vec![
+                    dep.package.clone(),
+                    dep.version.clone().unwrap_or_else(|| "-".to_string()),
+                    dep.manifest
+                        .strip_prefix(root_path)
+                        .unwrap_or(&dep.manifest)
+                        .display()
+                        .to_string(),
+                    support,
+                ]
+            })
+            .collect();
+        section
+    }
+
+    fn environment(root_path: &Path, config: &PromptGuardConfig) -> Section {
+        let mut section = Section::new("Environment");
+        let var = &config.env_var_name;
+        match crate::config::find_api_key(root_path, config) {
+            Some((key, source))
+                if key.starts_with("pg_sk_test_") || key.starts_with("pg_sk_prod_") =>
+            {
+                section.finding(
+                    Level::Ok,
+                    format!("API key read from {} ({var})", source.as_str()),
+                );
+            },
+            Some((_, source)) => section.finding(
+                Level::Error,
+                format!("API key from {} has an invalid format", source.as_str()),
+            ),
+            None => section.finding(
+                Level::Error,
+                format!(
+                    "{var} is not set in the environment, {} or the OS keychain",
+                    config.env_file
+                ),
+            ),
+        }
+
+        for (file, state) in config.env_file.states(root_path, var) {
+            match state {
+                EnvFileState::Missing => {
+                    section.finding(Level::Warning, format!("{file} not found"));
+                    continue;
+                },
+                EnvFileState::NoKey => {
+                    section.finding(Level::Warning, format!("{file} is missing {var}"));
+                },
+                EnvFileState::InSync => {
+                    section.finding(Level::Ok, format!("{file} contains {var}"));
+                },
+                EnvFileState::OutOfSync => {
+                    section.finding(Level::Warning, format!("{file} holds a different {var}"));
+                },
+            }
+            if DoctorCommand::check_env_in_gitignore(root_path, file) {
+                section.finding(Level::Ok, format!("{file} is in .gitignore"));
+            } else {
+                section.finding(
+                    Level::Warning,
+                    format!("{file} is not in .gitignore; secrets may be committed"),
+                );
+            }
+        }
+
+        let backups =
+            BackupManager::new(Some(config.backup_extension.clone())).list_backups(root_path);
+        if !backups.is_empty() {
+            section.finding(
+                Level::Warning,
+                format!(
+                    "{} legacy *.bak backup files left in the tree",
+                    backups.len()
+                ),
+            );
+        }
+
+        if config.runtime_mode {
+            let generator = ShimCommand::generator(root_path, config);
+            if generator.is_outdated() {
+                section.finding(
+                    Level::Warning,
+                    "Runtime shims were generated by an older CLI",
+                );
+            }
+            let problems = generator.verify(&config.metadata.shim_checksums);
+            if problems.is_empty() {
+                section.finding(Level::Ok, "Runtime shims are unmodified since generation");
+            } else {
+                section.finding(
+                    Level::Warning,
+                    format!("{} runtime shim files modified or missing", problems.len()),
+                );
+            }
+        }
+        section
+    }
+
+    fn configuration(config: &PromptGuardConfig) -> Section {
+        let mut section = Section::new("Configuration");
+        section.fact("Config version", config.version.clone());
+        section.fact("Env files", config.env_file.files().join(", "));
+        section.fact("API key variable", config.env_var_name.clone());
+        section.fact("Fail mode", config.shim_fail_mode.as_str());
+        if let Some(last_applied) = config.metadata.last_applied {
+            section.fact(
+                "Last applied",
+                last_applied.format("%Y-%m-%d %H:%M UTC").to_string(),
+            );
+        }
+        section.fact(
+            "Managed files",
+            if config.metadata.files_managed.is_empty() {
+                "none".to_string()
+            } else {
+                config.metadata.files_managed.join(", ")
+            },
+        );
+        section.fact("Excluded", config.exclude_patterns.join(", "));
+        section
+    }
+}
+
+/// Escape emphasis markers (glob patterns, file names) and `|`, so a value
+/// renders literally and stays in its table cell
+fn md_cell(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '|' | '*' | '_' | '`' => {
+                escaped.push('\\');
+                escaped.push(c);
+            },
+            '\n' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_markdown(title: &str, subtitle: &str, sections: &[Section]) -> String {
+    let mut out = format!("# {title}\n\n_{subtitle}_\n");
+    for section in sections {
+        let _ = write!(out, "\n## {}\n\n", section.title);
+        for (label, value) in &section.facts {
+            let _ = writeln!(out, "- **{label}:** {}", md_cell(value));
+        }
+        for (level, text) in &section.findings {
+            let _ = writeln!(out, "- {} {}", level.symbol(), md_cell(text));
+        }
+        if !section.headers.is_empty() {
+            let _ = writeln!(out, "| {} |", section.headers.join(" | "));
+            let _ = writeln!(
+                out,
+                "|{}",
+                section.headers.iter().map(|_| " --- |").collect::<String>()
+            );
+            for row in &section.rows {
+                let cells: Vec<String> = row.iter().map(|c| md_cell(c)).collect();
+                let _ = writeln!(out, "| {} |", cells.join(" | "));
+            }
+        }
+        if let Some(note) = &section.note {
+            let _ = writeln!(out, "{note}");
+        }
+    }
+    out
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,Segoe UI,Helvetica,Arial,sans-serif;max-width:1100px;margin:2rem auto;padding:0 1rem;color:#1f2328}\
+h1{font-size:1.6rem}h2{border-bottom:1px solid #d0d7de;padding-bottom:.3rem;margin-top:2rem}\
+table{border-collapse:collapse;width:100%}th,td{border:1px solid #d0d7de;padding:.35rem .6rem;text-align:left;vertical-align:top}\
+th{background:#f6f8fa}dt{font-weight:600}dd{margin:0 0 .5rem 0}\
+.subtitle{color:#59636e}.ok{color:#1a7f37}.warning{color:#9a6700}.error{color:#d1242f}ul{padding-left:1.2rem}";
+
+fn render_html(title: &str, subtitle: &str, sections: &[Section]) -> String {
+    let title = html_escape(title);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<p class=\"subtitle\">{}</p>\n",
+        html_escape(subtitle)
+    );
+    for section in sections {
+        let _ = writeln!(out, "<h2>{}</h2>", html_escape(&section.title));
+        if !section.facts.is_empty() {
+            out.push_str("<dl>\n");
+            for (label, value) in &section.facts {
+                let _ = writeln!(
+                    out,
+                    "<dt>{}</dt><dd>{}</dd>",
+                    html_escape(label),
+                    html_escape(value)
+                );
+            }
+            out.push_str("</dl>\n");
+        }
+        if !section.findings.is_empty() {
+            out.push_str("<ul>\n");
+            for (level, text) in &section.findings {
+                let _ = writeln!(
+                    out,
+                    "<li class=\"{}\">{} {}</li>",
+                    level.class(),
+                    level.symbol(),
+                    html_escape(text)
+                );
+            }
+            out.push_str("</ul>\n");
+        }
+        if !section.headers.is_empty() {
+            out.push_str("<table>\n<tr>");
+            for header in &section.headers {
+                let _ = write!(out, "<th>{}</th>", html_escape(header));
+            }
+            out.push_str("</tr>\n");
+            for row in &section.rows {
+                out.push_str("<tr>");
+                for cell in row {
+                    let _ = write!(out, "<td>{}</td>", html_escape(cell));
+                }
+                out.push_str("</tr>\n");
+            }
+            out.push_str("</table>\n");
+        }
+        if let Some(note) = &section.note {
+            let _ = writeln!(out, "<p>{}</p>", html_escape(note));
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
@@ -1,10 +1,54 @@
-use crate::config::ConfigManager;
-use crate::env::EnvManager;
-use crate::error::Result;
+use crate::backup::{BackupManager, BackupStore};
+use crate::config::{ConfigManager, PromptGuardConfig};
+use crate::error::{PromptGuardError, Result};
 use crate::output::Output;
+use crate::scanner::FileScanner;
+use crate::transformer::{self, Journal, JournalEntry};
+use crate::types::Provider;
+use std::path::{Path, PathBuf};
 
 pub struct RevertCommand {
     pub yes: bool,
+    /// Only undo these providers' transformations
+    pub providers: Vec<String>,
+    /// Only undo transformations in files matching these globs
+    pub files: Vec<String>,
+    pub json: bool,
+}
+
+/// Which transformations to undo; the default selects all of them
+#[derive(Default)]
+pub struct RevertFilter {
+    /// Empty selects every provider
+    providers: Vec<Provider>,
+    /// Scanner limited to the `--files` globs; `None` selects every file
+    files: Option<FileScanner>,
+}
+
+impl RevertFilter {
+    fn provider(&self, provider: Provider) -> bool {
+        self.providers.is_empty() || self.providers.contains(&provider)
+    }
+
+    /// `file` is relative to `root`
+    fn file(&self, root: &Path, file: &str) -> bool {
+        self.files
+            .as_ref()
+            .is_none_or(|scanner| scanner.is_included(&root.join(file)))
+    }
+
+    fn entry(&self, root: &Path, entry: &JournalEntry) -> bool {
+        self.provider(entry.provider) && self.file(root, &entry.file)
+    }
+}
+
+/// What `restore_sources` did to the managed files
+#[derive(Default)]
+pub struct Restored {
+    pub files: Vec<PathBuf>,
+    /// Files whose edits could not be located, relative to the project root.
+    /// They are left unchanged and keep their journal entries.
+    pub unresolved: Vec<String>,
 }
 
 impl RevertCommand {
@@ -14,26 +58,26 @@ impl RevertCommand {
         let config_manager = ConfigManager::new(None)?;
         if !config_manager.exists() {
             Output::warning("No PromptGuard configuration found. Nothing to revert.");
-            return Ok(());
+            return self.finish(&Restored::default(), &[], false);
         }
 
         let config = config_manager.load()?;
-        let root_path = std::env::current_dir()?;
+        let root_path = crate::config::project_root()?;
+        if !self.providers.is_empty() || !self.files.is_empty() {
+            return self.revert_selected(&config_manager, config, &root_path);
+        }
         let git_dir = root_path.join(".git");
 
-        println!("\nThis will:");
-        println!("  • Remove PROMPTGUARD_API_KEY from .env");
-        println!("  • Delete .promptguard.json");
+        let journal = Journal::load(&root_path)?;
+        let backup_manager = BackupManager::new(Some(config.backup_extension.clone()));
+        let backups = backup_manager.list_backups(&root_path).len()
+            + BackupStore::new(&root_path).manifest()?.files().len();
+        let managed = config.metadata.files_managed.len();
 
-        if git_dir.exists() {
-            println!("\nTo revert your code changes:");
-            println!("  git diff                    # Review what changed");
-            println!("  git checkout -- .           # Revert all changes");
-            println!("  git checkout -- <file>      # Revert specific file");
-        } else {
-            println!("\n⚠️  No git repository found.");
-            println!("Without version control, you cannot automatically revert code changes.");
-            println!("You'll need to manually undo the transformations.");
+        // Nothing recorded to restore from, so code changes are undone by hand
+        let restorable = !journal.is_empty() || managed > 0 || backups > 0;
+        if !self.json {
+            Self::print_plan(&journal, managed, backups, restorable, git_dir.exists());
         }
 
         if !self.yes && !Output::confirm("\nContinue with cleanup?", true)? {
@@ -41,12 +85,25 @@ impl RevertCommand {
             return Ok(());
         }
 
-        // Remove API key from .env
-        let env_path = root_path.join(&config.env_file);
-        if EnvManager::remove_key(&env_path, &config.env_var_name)? {
+        let mut restored = Restored::default();
+        if restorable {
+            Output::section("Restoring source files...", "📦");
+            restored = Self::restore_sources(&config, &root_path)?;
+            Self::restore_from_backups(&config, &root_path, &mut restored.files);
+            if restored.files.is_empty() {
+                Output::info("No source files needed restoring");
+            }
+        }
+
+        // Remove API key from the env files
+        let removed = config
+            .env_file
+            .remove_key(&root_path, &config.env_var_name)?;
+        if !removed.is_empty() {
             Output::step(&format!(
                 "Removed {} from {}",
-                config.env_var_name, config.env_file
+                config.env_var_name,
+                removed.join(", ")
             ));
         }
 
@@ -54,13 +111,342 @@ impl RevertCommand {
         config_manager.delete()?;
         Output::step("Deleted .promptguard.json");
 
-        println!();
-        Output::success("PromptGuard configuration removed!");
+        if !self.json {
+            Output::line("");
+            Output::success("PromptGuard configuration removed!");
+
+            if !restorable && git_dir.exists() {
+                Output::line("\nNext: Use git to revert your code changes (see commands above)");
+            }
+        }
+
+        self.finish(&restored, &removed, true)
+    }
+
+    /// Print the `--json` result
+    fn finish(&self, restored: &Restored, key_removed_from: &[&str], changed: bool) -> Result<()> {
+        if !self.json {
+            return Ok(());
+        }
+        let root_path = crate::config::project_root()?;
+        let selected = !self.providers.is_empty() || !self.files.is_empty();
+        Output::json(serde_json::json!({
+            "changed": changed,
+            "config_removed": changed && !selected,
+            "providers": self.providers,
+            "files": self.files,
+            "restored_files": Self::relative(&root_path, &restored.files),
+            "unresolved": restored.unresolved,
+            "key_removed_from": key_removed_from,
+        }))
+    }
+
+    fn relative(root_path: &Path, paths: &[PathBuf]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| p.strip_prefix(root_path).unwrap_or(p).display().to_string())
+            .collect()
+    }
+
+    fn print_plan(journal: &Journal, managed: usize, backups: usize, restorable: bool, git: bool) {
+        Output::line("\nThis will:");
+        if !journal.is_empty() {
+            Output::line(&format!(
+                "  • Undo {} recorded edits in {} files (from .promptguard/journal.json)",
+                journal.entries.len(),
+                journal.files().len()
+            ));
+        } else if managed > 0 {
+            Output::line(&format!(
+                "  • Remove the injected base URL from {managed} managed files"
+            ));
+        }
+        if backups > 0 {
+            Output::line(&format!(
+                "  • Restore files still routed through the proxy from {backups} backups"
+            ));
+        }
+        Output::line("  • Remove PROMPTGUARD_API_KEY from .env");
+        Output::line("  • Delete .promptguard.json");
+
+        if !restorable {
+            if git {
+                Output::line("\nTo revert your code changes:");
+                Output::line("  git diff                    # Review what changed");
+                Output::line("  git checkout -- .           # Revert all changes");
+                Output::line("  git checkout -- <file>      # Revert specific file");
+            } else {
+                Output::line("\n⚠️  No git repository found.");
+                Output::line(
+                    "Without version control, you cannot automatically revert code changes.",
+                );
+                Output::line("You'll need to manually undo the transformations.");
+            }
+        }
+    }
+
+    /// Undo only the transformations selected with `--provider` or `--files`
+    /// (never both: a provider stays configured for the files it keeps),
+    /// keeping the key, the config and every other transformation
+    fn revert_selected(
+        &self,
+        config_manager: &ConfigManager,
+        mut config: PromptGuardConfig,
+        root_path: &Path,
+    ) -> Result<()> {
+        let providers = self
+            .providers
+            .iter()
+            .map(|name| {
+                Provider::parse(name)
+                    .ok_or_else(|| PromptGuardError::Config(format!("Unknown provider '{name}'")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let files = if self.files.is_empty() {
+            None
+        } else {
+            Some(
+                FileScanner::new(root_path, Some(Vec::new()))?
+                    .with_include_patterns(&self.files)?,
+            )
+        };
+        let filter = RevertFilter { providers, files };
+
+        let journal = Journal::load(root_path)?;
+        let edits: Vec<&JournalEntry> = journal
+            .entries
+            .iter()
+            .filter(|e| filter.entry(root_path, e))
+            .collect();
+        let mut targets: Vec<&String> = edits
+            .iter()
+            .map(|e| &e.file)
+            .chain(
+                config
+                    .metadata
+                    .files_managed
+                    .iter()
+                    .filter(|f| filter.file(root_path, f)),
+            )
+            .collect();
+        targets.sort();
+        targets.dedup();
+
+        let selection = if self.files.is_empty() {
+            self.providers.join(", ")
+        } else {
+            format!("files matching {}", self.files.join(", "))
+        };
+        if targets.is_empty() {
+            Output::warning(&format!(
+                "No PromptGuard transformations for {selection}. Nothing to revert."
+            ));
+            return self.finish(&Restored::default(), &[], false);
+        }
+
+        if !self.json {
+            Output::line("\nThis will:");
+            Output::line(&format!(
+                "  • Undo PromptGuard's edits for {selection} in up to {} files ({} recorded in .promptguard/journal.json)",
+                targets.len(),
+                edits.len()
+            ));
+            if self.files.is_empty() {
+                Output::line(&format!(
+                    "  • Remove {} from the providers in .promptguard.json",
+                    self.providers.join(", ")
+                ));
+            } else {
+                Output::line(
+                    "  • Add the globs to exclude_patterns so 'promptguard apply' skips them",
+                );
+            }
+            Output::line("  • Keep the API key, .promptguard.json and all other transformations");
+        }
+
+        if !self.yes && !Output::confirm("\nRevert the selected transformations?", true)? {
+            Output::info("Revert cancelled");
+            return Ok(());
+        }
+
+        Output::section("Restoring source files...", "📦");
+        let restored = Self::restore_matching(&config, root_path, &filter)?;
+
+        // Files still routed through the proxy for other providers stay managed
+        let urls: Vec<String> = config
+            .proxy_url
+            .urls()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        config.metadata.files_managed.retain(|f| {
+            let path = root_path.join(f);
+            !restored.files.contains(&path)
+                || std::fs::read_to_string(&path)
+                    .is_ok_and(|c| urls.iter().any(|url| c.contains(url)))
+        });
+        if self.files.is_empty() {
+            config
+                .providers
+                .retain(|p| Provider::parse(p).is_none_or(|p| !filter.provider(p)));
+        } else {
+            for glob in &self.files {
+                let glob = glob.trim_start_matches("./").trim_end_matches('/');
+                let pattern = if root_path.join(glob).is_dir() {
+                    format!("{glob}/**")
+                } else {
+                    glob.to_string()
+                };
+                if !config.exclude_patterns.contains(&pattern) {
+                    config.exclude_patterns.push(pattern);
+                }
+            }
+        }
+        config_manager.save(&config)?;
+
+        if !self.json {
+            Output::line("");
+        }
+        Output::success(&format!(
+            "Reverted {selection} in {} files; the rest of the setup is unchanged",
+            restored.files.len()
+        ));
+        self.finish(&restored, &[], true)
+    }
+
+    /// Undo the transform's source edits without touching anything else.
+    ///
+    /// Files unchanged since the transform are restored exactly from the
+    /// journal. Files edited since (or transformed before the journal existed)
+    /// get only the injected arguments removed; files where they can't be
+    /// found are listed in a warning and in `Restored::unresolved`.
+    pub fn restore_sources(config: &PromptGuardConfig, root_path: &Path) -> Result<Restored> {
+        Self::restore_matching(config, root_path, &RevertFilter::default())
+    }
+
+    /// `restore_sources` for the transformations `filter` selects
+    fn restore_matching(
+        config: &PromptGuardConfig,
+        root_path: &Path,
+        filter: &RevertFilter,
+    ) -> Result<Restored> {
+        let mut journal = Journal::load(root_path)?;
+        let report = journal.revert_where(root_path, |e| filter.entry(root_path, e))?;
+        let mut restored: Vec<PathBuf> = Vec::new();
+        for file in &report.reverted {
+            Output::step(&format!("✓ {file}"));
+            restored.push(root_path.join(file));
+        }
+        for file in &report.missing {
+            Output::warning(&format!("{file} no longer exists, skipped"));
+        }
+
+        let providers: Vec<Provider> = config
+            .providers
+            .iter()
+            .filter_map(|p| Provider::parse(p))
+            .filter(|p| filter.provider(*p))
+            .collect();
+        let mut candidates: Vec<PathBuf> = report
+            .conflicts
+            .iter()
+            .chain(&config.metadata.files_managed)
+            .filter(|f| filter.file(root_path, f))
+            .map(|f| root_path.join(f))
+            .filter(|f| f.is_file() && !restored.contains(f))
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+
+        let mut unresolved = Vec::new();
+        for file_path in candidates {
+            let mut modified = false;
+            for provider in &providers {
+                let result = transformer::untransform_file(
+                    &file_path,
+                    *provider,
+                    config.proxy_url.for_provider(*provider),
+                    config.env_var_for(*provider),
+                )?;
+                modified |= result.modified;
+            }
+            let rel_path = file_path.strip_prefix(root_path).unwrap_or(&file_path);
+            if modified {
+                Output::step(&format!(
+                    "✓ {} (removed injected base URL)",
+                    rel_path.display()
+                ));
+                restored.push(file_path);
+            } else if report.conflicts.iter().any(|c| Path::new(c) == rel_path) {
+                unresolved.push(rel_path.display().to_string());
+            }
+        }
+
+        journal.forget(root_path, &restored, |e| filter.entry(root_path, e));
+        journal.save(root_path)?;
 
-        if git_dir.exists() {
-            println!("\nNext: Use git to revert your code changes (see commands above)");
+        if !unresolved.is_empty() {
+            Output::warning(&format!(
+                "Could not locate PromptGuard's edits in {} (left unchanged). \
+                 Revert these manually; their journal entries were kept",
+                unresolved.join(", ")
+            ));
         }
 
-        Ok(())
+        Ok(Restored {
+            files: restored,
+            unresolved,
+        })
+    }
+
+    /// Restore files that still reference the proxy from their oldest
+    /// snapshot in `.promptguard/backups/` (or a legacy `*.bak` file),
+    /// skipping and then adding to `restored`
+    pub fn restore_from_backups(
+        config: &PromptGuardConfig,
+        root_path: &Path,
+        restored: &mut Vec<PathBuf>,
+    ) {
+        let still_routed = |path: &Path| {
+            std::fs::read_to_string(path)
+                .is_ok_and(|c| config.proxy_url.urls().iter().any(|url| c.contains(url)))
+        };
+
+        let store = BackupStore::new(root_path);
+        let manifest = store.manifest().unwrap_or_default();
+        for file in manifest.files() {
+            let original_path = root_path.join(&file);
+            if restored.contains(&original_path) || !still_routed(&original_path) {
+                continue;
+            }
+            let Some(entry) = manifest.for_file(&file).next() else {
+                continue;
+            };
+            if store.restore(entry).is_ok() {
+                Output::step(&format!("✓ {file} (from backup {})", entry.id));
+                restored.push(original_path);
+            }
+        }
+
+        let backup_manager = BackupManager::new(Some(config.backup_extension.clone()));
+        for backup_path in backup_manager.list_backups(root_path) {
+            let Some(original_path) = backup_path
+                .to_str()
+                .and_then(|p| p.strip_suffix(&config.backup_extension))
+                .map(PathBuf::from)
+            else {
+                continue;
+            };
+            if restored.contains(&original_path) || !still_routed(&original_path) {
+                continue;
+            }
+            if backup_manager.restore_backup(&original_path).is_ok() {
+                let rel_path = original_path
+                    .strip_prefix(root_path)
+                    .unwrap_or(&original_path);
+                Output::step(&format!("✓ {} (from backup)", rel_path.display()));
+                restored.push(original_path);
+            }
+        }
     }
 }
@@ -1,14 +1,18 @@
 use crate::api::PromptGuardClient;
-use crate::config::ConfigManager;
-use crate::detector::detect_all_providers;
+use crate::commands::coverage;
+use crate::config::{ConfigManager, DEFAULT_PROXY_URL};
+use crate::detector::{detect_all_providers, detect_declared_providers, DeclaredDependency};
 use crate::error::{PromptGuardError, Result};
-use crate::output::Output;
-use crate::scanner::FileScanner;
+use crate::output::sarif::{self, Rule, SarifResult};
+use crate::output::{Output, Progress, Table};
+use crate::scanner::{FileScanner, Workspace};
+use crate::transformer;
 use crate::types::{DetectionInstance, Provider};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 /// Response from the /security/scan endpoint.
 ///
@@ -28,19 +32,69 @@ pub struct SecurityScanResponse {
     pub processing_time_ms: Option<f64>,
 }
 
+/// Scan findings attributed to one workspace package
+#[derive(Debug, Serialize)]
+struct PackageFindings {
+    name: String,
+    path: String,
+    kind: Option<&'static str>,
+    instance_count: usize,
+    providers: Vec<&'static str>,
+}
+
 pub struct ScanCommand {
     pub provider: Option<String>,
     pub json: bool,
+    /// Report unguarded call sites and conflicting gateways as SARIF
+    pub sarif: bool,
     /// Text to scan for security threats via the API
     pub text: Option<String>,
     /// File path to scan for security threats via the API
     pub file: Option<String>,
+    /// Limit the local scan to files staged in git
+    pub staged: bool,
+    /// Findings that fail the scan: `none`, `detected` or `unguarded`
+    pub fail_on: String,
+}
+
+/// Supported files staged for the next commit, relative to `root`. Staged
+/// deletions are left out since there is nothing left to scan.
+fn staged_files(root: &Path) -> Result<BTreeSet<PathBuf>> {
+    let output = Command::new("git")
+        .args([
+            "diff",
+            "--cached",
+            "--name-only",
+            "--relative",
+            "--diff-filter=ACMR",
+            "-z",
+        ])
+        .current_dir(root)
+        .output()
+        .map_err(|e| PromptGuardError::Custom(format!("--staged needs git: {e}")))?;
+    if !output.status.success() {
+        return Err(PromptGuardError::Custom(format!(
+            "--staged needs a git repository: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .collect())
 }
 
 impl ScanCommand {
     pub fn execute(&self) -> Result<()> {
         // If --text or --file is provided, do an API security scan instead of local SDK detection
         if self.text.is_some() || self.file.is_some() {
+            if self.sarif {
+                return Err(PromptGuardError::Custom(
+                    "--format sarif reports SDK call sites; it can't be used with --text or --file"
+                        .to_string(),
+                ));
+            }
             return self.execute_api_scan();
         }
 
@@ -52,6 +106,8 @@ impl ScanCommand {
     fn execute_api_scan(&self) -> Result<()> {
         let content = if let Some(ref text) = self.text {
             text.clone()
+        } else if self.file.as_deref() == Some("-") {
+            std::io::read_to_string(std::io::stdin())?
         } else if let Some(ref file_path) = self.file {
             fs::read_to_string(file_path).map_err(|e| {
                 PromptGuardError::Io(std::io::Error::new(
@@ -69,7 +125,10 @@ impl ScanCommand {
         let config_manager = ConfigManager::new(None)?;
         let config = config_manager.load()?;
 
-        let client = PromptGuardClient::new(config.api_key, Some(config.proxy_url))?;
+        let client = PromptGuardClient::new(
+            config.api_key().to_string(),
+            Some(config.proxy_url.default_url().to_string()),
+        )?;
 
         if !self.json {
             Output::header(&format!(
@@ -89,43 +148,48 @@ impl ScanCommand {
         )?;
 
         if self.json {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response).unwrap_or_default()
-            );
+            Output::json(serde_json::to_value(&response)?)?;
         } else {
-            println!();
+            Output::line("");
             let decision_display = match response.decision.as_str() {
                 "block" => "🚫 BLOCKED",
                 "allow" => "✅ ALLOWED",
                 _ => &response.decision,
             };
-            println!("Decision: {decision_display}");
-            println!("Confidence: {:.1}%", response.confidence * 100.0);
+            Output::line(&format!("Decision: {decision_display}"));
+            Output::line(&format!("Confidence: {:.1}%", response.confidence * 100.0));
 
             if let Some(ref threat_type) = response.threat_type {
-                println!("Threat Type: {threat_type}");
+                Output::line(&format!("Threat Type: {threat_type}"));
             }
 
             if !response.reason.is_empty() {
-                println!("Reason: {}", response.reason);
+                Output::line(&format!("Reason: {}", response.reason));
             }
 
             if response.decision == "block" {
-                println!();
+                Output::line("");
                 Output::warning("This content was flagged as potentially malicious.");
             } else {
-                println!();
+                Output::line("");
                 Output::success("No security threats detected.");
             }
         }
 
+        if response.blocked || response.decision == "block" {
+            return Err(PromptGuardError::ThreatDetected(format!(
+                "Content blocked ({}, {:.1}% confidence)",
+                response.threat_type.as_deref().unwrap_or("threat detected"),
+                response.confidence * 100.0
+            )));
+        }
         Ok(())
     }
 
     /// Local SDK detection scan (original behavior)
     fn execute_local_scan(&self) -> Result<()> {
-        if !self.json {
+        let machine = self.json || self.sarif;
+        if !machine {
             Output::header(&format!(
                 "🛡️  PromptGuard CLI v{}",
                 env!("CARGO_PKG_VERSION")
@@ -133,14 +197,41 @@ impl ScanCommand {
             Output::section("LLM SDK Detection Report", "📊");
         }
 
-        let root_path = std::env::current_dir()?;
+        let root_path = crate::config::project_root()?;
+
+        // Providers declared in dependency manifests, before parsing source
+        let declared: Vec<DeclaredDependency> = detect_declared_providers(&root_path)
+            .into_iter()
+            .filter(|d| {
+                self.provider
+                    .as_deref()
+                    .is_none_or(|f| d.provider.as_str() == f)
+            })
+            .collect();
+
+        let staged = if self.staged {
+            Some(staged_files(&root_path)?)
+        } else {
+            None
+        };
         let scanner = FileScanner::new(&root_path, None)?;
-        let files = scanner.scan_files(None)?;
+        let files: Vec<PathBuf> = scanner
+            .scan_files(None)?
+            .into_iter()
+            .filter(|f| {
+                staged
+                    .as_ref()
+                    .is_none_or(|s| s.contains(f.strip_prefix(&root_path).unwrap_or(f)))
+            })
+            .collect();
 
         // Store full detection instances (with line/column info) for each provider
-        let mut detection_results: HashMap<Provider, Vec<DetectionInstance>> = HashMap::new();
+        let mut detection_results: BTreeMap<Provider, Vec<DetectionInstance>> = BTreeMap::new();
+        let mut ignored: Vec<(Provider, DetectionInstance)> = Vec::new();
 
+        let mut progress = Progress::new("Scanning", files.len(), machine);
         for file_path in &files {
+            progress.inc_file(file_path.strip_prefix(&root_path).unwrap_or(file_path));
             if let Ok(results) = detect_all_providers(file_path) {
                 for (provider, result) in results {
                     if let Some(ref filter) = self.provider {
@@ -149,30 +240,434 @@ impl ScanCommand {
                         }
                     }
 
+                    progress.found(result.instances.len());
                     if !result.instances.is_empty() {
                         detection_results
                             .entry(provider)
                             .or_default()
                             .extend(result.instances);
                     }
+                    ignored.extend(result.ignored.into_iter().map(|i| (provider, i)));
                 }
             }
         }
+        progress.finish();
+        ignored.sort_by(|(_, a), (_, b)| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+        for instances in detection_results.values_mut() {
+            instances.sort_by(|a, b| {
+                (&a.file_path, a.line, a.column).cmp(&(&b.file_path, b.line, b.column))
+            });
+        }
+
+        // The human table marks each call site guarded or not
+        let unguarded = if self.sarif || self.fail_on == "unguarded" || !self.json {
+            self.sarif_results(&root_path, &detection_results, staged.as_ref())?
+        } else {
+            Vec::new()
+        };
+
+        if self.sarif {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&sarif::log(&root_path, &unguarded, &[]))?
+            );
+            return self.check_fail_on(&detection_results, &unguarded);
+        }
+
+        let workspace = Workspace::discover(&root_path);
+        let packages = workspace
+            .as_ref()
+            .map(|ws| Self::group_by_package(&detection_results, ws));
 
         if self.json {
-            self.print_json(&detection_results, &root_path, files.len())?;
+            let mut extra = serde_json::Map::new();
+            extra.insert(
+                "declared_dependencies".to_string(),
+                Self::declared_json(&root_path, &declared),
+            );
+            extra.insert("sdk_versions".to_string(), Self::sdk_versions(&declared));
+            extra.insert(
+                "ignored".to_string(),
+                ignored
+                    .iter()
+                    .map(|(provider, inst)| {
+                        serde_json::json!({
+                            "provider": provider.as_str(),
+                            "file": inst.file_path.strip_prefix(&root_path).unwrap_or(&inst.file_path).to_string_lossy(),
+                            "line": inst.line,
+                            "column": inst.column,
+                        })
+                    })
+                    .collect(),
+            );
+            extra.insert(
+                "declared_not_detected".to_string(),
+                Self::declared_not_detected(&detection_results, &declared)
+                    .iter()
+                    .map(Provider::as_str)
+                    .collect(),
+            );
+            if let (Some(ws), Some(packages)) = (&workspace, &packages) {
+                extra.insert(
+                    "workspace".to_string(),
+                    serde_json::json!({
+                        "kinds": ws.kinds,
+                        "packages": packages,
+                    }),
+                );
+            }
+            self.print_json(&detection_results, &root_path, files.len(), extra)?;
         } else {
-            self.print_human(&detection_results, &root_path, files.len())?;
+            self.print_human(&detection_results, &root_path, files.len(), &unguarded)?;
+            Self::print_ignored(&ignored, &root_path);
+            Self::print_declared(&detection_results, &root_path, &declared);
+            if let (Some(ws), Some(packages)) = (&workspace, &packages) {
+                Self::print_packages(ws, packages);
+            }
+            Output::line("\nNext: promptguard init");
         }
 
-        Ok(())
+        self.check_fail_on(&detection_results, &unguarded)
+    }
+
+    /// Fail the scan when `--fail-on detected` finds any SDK usage, or
+    /// `--fail-on unguarded` a call site not routed through `PromptGuard`
+    fn check_fail_on(
+        &self,
+        detection_results: &BTreeMap<Provider, Vec<DetectionInstance>>,
+        unguarded: &[SarifResult],
+    ) -> Result<()> {
+        if self.fail_on == "detected" && !detection_results.is_empty() {
+            let total: usize = detection_results.values().map(Vec::len).sum();
+            let providers: Vec<&str> = detection_results
+                .keys()
+                .map(Provider::display_name)
+                .collect();
+            return Err(PromptGuardError::Findings(format!(
+                "{total} LLM SDK call site{} detected ({})",
+                if total == 1 { "" } else { "s" },
+                providers.join(", ")
+            )));
+        }
+        if self.fail_on != "unguarded" || unguarded.is_empty() {
+            return Ok(());
+        }
+        let mut sites: Vec<String> = unguarded
+            .iter()
+            .take(5)
+            .map(|r| match r.line {
+                Some(line) => format!("{}:{line}", r.file),
+                None => r.file.clone(),
+            })
+            .collect();
+        if unguarded.len() > sites.len() {
+            sites.push(format!("and {} more", unguarded.len() - sites.len()));
+        }
+        Err(PromptGuardError::Findings(format!(
+            "{} LLM call site{} not routed through PromptGuard ({}). Run 'promptguard init', \
+             or mark intentional direct calls with '# promptguard: ignore'",
+            unguarded.len(),
+            if unguarded.len() == 1 { "" } else { "s" },
+            sites.join(", ")
+        )))
+    }
+
+    /// Call sites not routed through `PromptGuard`, classified like
+    /// `coverage` once the project is initialized. Before that, every client
+    /// without a base URL is unguarded. `staged` limits results to those files.
+    fn sarif_results(
+        &self,
+        root: &Path,
+        detected: &BTreeMap<Provider, Vec<DetectionInstance>>,
+        staged: Option<&BTreeSet<PathBuf>>,
+    ) -> Result<Vec<SarifResult>> {
+        let config_manager = ConfigManager::new(None)?;
+        if config_manager.exists() {
+            let config = config_manager.load()?;
+            let (sites, _) = coverage::call_sites(root, &config, false)?;
+            return Ok(sites
+                .into_iter()
+                .filter(|s| {
+                    self.provider
+                        .as_deref()
+                        .is_none_or(|f| s.provider.as_str() == f)
+                })
+                .filter(|s| staged.is_none_or(|staged| staged.contains(Path::new(&s.file))))
+                .filter_map(|site| {
+                    let gap = site.gap?;
+                    Some(SarifResult {
+                        rule: if site.gateway.is_some() {
+                            Rule::ConflictingGateway
+                        } else {
+                            Rule::UnguardedSdkCall
+                        },
+                        message: format!("{} client: {gap}", site.provider.display_name()),
+                        file: site.file,
+                        line: Some(site.line),
+                        column: Some(site.column),
+                    })
+                })
+                .collect());
+        }
+
+        let mut results = Vec::new();
+        for (provider, instances) in detected {
+            let mut files: Vec<&PathBuf> = Vec::new();
+            for inst in instances {
+                let file = inst.file_path.strip_prefix(root).unwrap_or(&inst.file_path);
+                if !inst.has_base_url {
+                    results.push(SarifResult {
+                        rule: Rule::UnguardedSdkCall,
+                        message: format!(
+                            "{} client calls the provider directly; run 'promptguard init' to route it through PromptGuard",
+                            provider.display_name()
+                        ),
+                        file: file.to_string_lossy().to_string(),
+                        line: Some(inst.line),
+                        column: Some(inst.column),
+                    });
+                }
+                if !files.contains(&&inst.file_path) {
+                    files.push(&inst.file_path);
+                }
+            }
+            for file_path in files {
+                let file = file_path.strip_prefix(root).unwrap_or(file_path);
+                for stale in transformer::stale_base_urls(file_path, *provider, DEFAULT_PROXY_URL)?
+                {
+                    results.push(SarifResult {
+                        rule: Rule::ConflictingGateway,
+                        message: format!(
+                            "{} base URL points at {}",
+                            provider.display_name(),
+                            stale.url
+                        ),
+                        file: file.to_string_lossy().to_string(),
+                        line: Some(stale.line),
+                        column: None,
+                    });
+                }
+            }
+        }
+        results.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+        Ok(results)
+    }
+
+    /// Call sites suppressed with inline directives, listed so they stay auditable
+    fn print_ignored(ignored: &[(Provider, DetectionInstance)], root: &Path) {
+        if ignored.is_empty() {
+            return;
+        }
+
+        Output::line(&format!(
+            "\nIgnored by inline directives ({}):",
+            ignored.len()
+        ));
+        for (provider, inst) in ignored {
+            Output::line(&format!(
+                "  • {}:{}:{} ({})",
+                inst.file_path
+                    .strip_prefix(root)
+                    .unwrap_or(&inst.file_path)
+                    .display(),
+                inst.line,
+                inst.column,
+                provider.display_name()
+            ));
+        }
+    }
+
+    /// Providers declared in a manifest with no constructor found by the AST scan
+    fn declared_not_detected(
+        results: &BTreeMap<Provider, Vec<DetectionInstance>>,
+        declared: &[DeclaredDependency],
+    ) -> Vec<Provider> {
+        let mut missing: Vec<Provider> = Vec::new();
+        for dep in declared {
+            if !results.contains_key(&dep.provider) && !missing.contains(&dep.provider) {
+                missing.push(dep.provider);
+            }
+        }
+        missing.sort();
+        missing
+    }
+
+    fn declared_json(root: &Path, declared: &[DeclaredDependency]) -> serde_json::Value {
+        declared
+            .iter()
+            .map(|d| {
+                serde_json::json!({
+                    "provider": d.provider.as_str(),
+                    "package": d.package,
+                    "version": d.version,
+                    "manifest": d.manifest.strip_prefix(root).unwrap_or(&d.manifest).to_string_lossy(),
+                    "min_base_url_version": d.min_base_url_version(),
+                    "outdated": d.is_outdated(),
+                })
+            })
+            .collect()
+    }
+
+    /// Locked SDK versions per provider, from lockfiles only
+    fn sdk_versions(declared: &[DeclaredDependency]) -> serde_json::Value {
+        let mut versions: serde_json::Map<String, serde_json::Value> = serde_json::Map::new();
+        for dep in declared {
+            let Some(ref version) = dep.version else {
+                continue;
+            };
+            let entry = versions
+                .entry(dep.provider.as_str())
+                .or_insert_with(|| serde_json::json!([]));
+            if let Some(list) = entry.as_array_mut() {
+                let value = serde_json::json!(version);
+                if !list.contains(&value) {
+                    list.push(value);
+                }
+            }
+        }
+        serde_json::Value::Object(versions)
+    }
+
+    fn print_declared(
+        results: &BTreeMap<Provider, Vec<DetectionInstance>>,
+        root: &Path,
+        declared: &[DeclaredDependency],
+    ) {
+        if declared.is_empty() {
+            return;
+        }
+
+        Output::line("\nDeclared in dependency manifests:");
+        for dep in declared {
+            let version = dep
+                .version
+                .as_ref()
+                .map_or_else(String::new, |v| format!(" {v}"));
+            Output::line(&format!(
+                "  • {} ({}{version}) in {}",
+                dep.provider.display_name(),
+                dep.package,
+                dep.manifest
+                    .strip_prefix(root)
+                    .unwrap_or(&dep.manifest)
+                    .display()
+            ));
+        }
+
+        let outdated: Vec<&DeclaredDependency> =
+            declared.iter().filter(|d| d.is_outdated()).collect();
+        if !outdated.is_empty() {
+            Output::line("");
+            Output::warning("SDK versions too old for base URL overrides:");
+            for dep in outdated {
+                Output::line(&format!(
+                    "  • {} {} (requires >= {})",
+                    dep.package,
+                    dep.version.as_deref().unwrap_or_default(),
+                    dep.min_base_url_version().unwrap_or_default()
+                ));
+            }
+            Output::line("  Upgrade these SDKs before running promptguard init");
+        }
+
+        let missing = Self::declared_not_detected(results, declared);
+        if !missing.is_empty() {
+            Output::line("");
+            Output::warning("Declared but not detected in code (possible dynamic usage):");
+            for provider in missing {
+                Output::line(&format!("  • {}", provider.display_name()));
+            }
+            Output::line(
+                "  Review these manually or use runtime shims: promptguard enable --runtime",
+            );
+        }
+    }
+
+    /// Summarize findings per workspace package. Files outside every package
+    /// are attributed to the workspace root.
+    fn group_by_package(
+        results: &BTreeMap<Provider, Vec<DetectionInstance>>,
+        workspace: &Workspace,
+    ) -> Vec<PackageFindings> {
+        let mut packages: Vec<PackageFindings> = workspace
+            .packages
+            .iter()
+            .map(|p| PackageFindings {
+                name: p.name.clone(),
+                path: workspace.relative_path(p),
+                kind: Some(p.kind.as_str()),
+                instance_count: 0,
+                providers: Vec::new(),
+            })
+            .collect();
+        let mut root = PackageFindings {
+            name: "(root)".to_string(),
+            path: ".".to_string(),
+            kind: None,
+            instance_count: 0,
+            providers: Vec::new(),
+        };
+
+        for (provider, instances) in results {
+            for inst in instances {
+                let entry = match workspace
+                    .package_for(&inst.file_path)
+                    .and_then(|p| workspace.packages.iter().position(|q| q.path == p.path))
+                {
+                    Some(idx) => &mut packages[idx],
+                    None => &mut root,
+                };
+                entry.instance_count += 1;
+                if !entry.providers.contains(&provider.as_str()) {
+                    entry.providers.push(provider.as_str());
+                }
+            }
+        }
+
+        if root.instance_count > 0 {
+            packages.push(root);
+        }
+        for package in &mut packages {
+            package.providers.sort_unstable();
+        }
+        packages
+    }
+
+    fn print_packages(workspace: &Workspace, packages: &[PackageFindings]) {
+        let kinds: Vec<&str> = workspace
+            .kinds
+            .iter()
+            .map(crate::scanner::workspace::WorkspaceKind::as_str)
+            .collect();
+        Output::line(&format!(
+            "\nWorkspace packages ({}, {} packages):",
+            kinds.join(" + "),
+            workspace.packages.len()
+        ));
+        for package in packages {
+            if package.instance_count == 0 {
+                Output::excluded(&format!(
+                    "{} ({}) - no SDK usage",
+                    package.name, package.path
+                ));
+            } else {
+                Output::line(&format!(
+                    "  • {} ({}) - {} instances [{}]",
+                    package.name,
+                    package.path,
+                    package.instance_count,
+                    package.providers.join(", ")
+                ));
+            }
+        }
     }
 
     fn print_json(
         &self,
-        results: &HashMap<Provider, Vec<DetectionInstance>>,
+        results: &BTreeMap<Provider, Vec<DetectionInstance>>,
         root: &PathBuf,
         total_files: usize,
+        extra: serde_json::Map<String, serde_json::Value>,
     ) -> Result<()> {
         let mut providers_data = Vec::new();
 
@@ -218,84 +713,95 @@ impl ScanCommand {
             all_files.len()
         };
 
-        let output = serde_json::json!({
+        let mut output = serde_json::json!({
             "total_files_scanned": total_files,
             "files_with_sdks": unique_file_count,
             "total_instances": results.values().map(std::vec::Vec::len).sum::<usize>(),
             "providers": providers_data,
         });
 
-        println!("{}", serde_json::to_string_pretty(&output)?);
+        if let Some(fields) = output.as_object_mut() {
+            fields.extend(extra);
+        }
+
+        Output::json(output)?;
 
         Ok(())
     }
 
+    /// One row per provider and file: call sites, their lines, and whether
+    /// they route through `PromptGuard`
     fn print_human(
         &self,
-        results: &HashMap<Provider, Vec<DetectionInstance>>,
+        results: &BTreeMap<Provider, Vec<DetectionInstance>>,
         root: &PathBuf,
         total_files: usize,
+        unguarded: &[SarifResult],
     ) -> Result<()> {
-        for (provider, instances) in results {
-            // Get unique files
-            let mut unique_files: Vec<PathBuf> =
-                instances.iter().map(|i| i.file_path.clone()).collect();
-            unique_files.sort();
-            unique_files.dedup();
-
-            println!(
-                "\n{} SDK ({} files, {} instances)",
-                provider.display_name(),
-                unique_files.len(),
-                instances.len()
-            );
-
-            for file in unique_files.iter().take(10) {
-                let rel_path = file.strip_prefix(root).unwrap_or(file);
-                // Show instances in this file
-                let file_instances: Vec<&DetectionInstance> =
-                    instances.iter().filter(|i| &i.file_path == file).collect();
-
-                if file_instances.len() == 1 {
-                    let inst = file_instances[0];
-                    println!("├── {}:{}:{}", rel_path.display(), inst.line, inst.column);
-                } else {
-                    println!(
-                        "├── {} ({} instances)",
-                        rel_path.display(),
-                        file_instances.len()
-                    );
-                    for inst in file_instances.iter().take(3) {
-                        println!("│   └── line {}, column {}", inst.line, inst.column);
-                    }
-                    if file_instances.len() > 3 {
-                        println!("│   └── ... and {} more", file_instances.len() - 3);
-                    }
+        let unguarded: BTreeSet<(&str, usize)> = unguarded
+            .iter()
+            .filter_map(|r| Some((r.file.as_str(), r.line?)))
+            .collect();
+
+        let mut guarded_total = 0;
+        if !results.is_empty() {
+            let mut table =
+                Table::new(&["PROVIDER", "FILE", "CALLS", "LINES", "GUARDED"]).keep_end(1);
+            for (provider, instances) in results {
+                for file_instances in instances.chunk_by(|a, b| a.file_path == b.file_path) {
+                    let path = &file_instances[0].file_path;
+                    let file = path.strip_prefix(root).unwrap_or(path).to_string_lossy();
+                    let lines: Vec<String> =
+                        file_instances.iter().map(|i| i.line.to_string()).collect();
+                    let guarded = file_instances
+                        .iter()
+                        .filter(|i| !unguarded.contains(&(file.as_ref(), i.line)))
+                        .count();
+                    guarded_total += guarded;
+                    table.row(vec![
+                        provider.display_name().to_string(),
+                        file.to_string(),
+                        file_instances.len().to_string(),
+                        lines.join(", "),
+                        match guarded {
+                            0 => "no".to_string(),
+                            n if n == file_instances.len() => "yes".to_string(),
+                            n => format!("partial ({n}/{})", file_instances.len()),
+                        },
+                    ]);
                 }
             }
-
-            if unique_files.len() > 10 {
-                println!("└── ... and {} more files", unique_files.len() - 10);
-            }
+            Output::line("");
+            table.print();
         }
 
-        println!("\nSummary:");
-        println!("  • Total files scanned: {total_files}");
+        Output::line("\nSummary:");
+        Output::line(&format!("  • Total files scanned: {total_files}"));
 
         let total_instances: usize = results.values().map(std::vec::Vec::len).sum();
-        println!("  • Total instances: {total_instances}");
+        Output::line(&format!("  • Total instances: {total_instances}"));
+        if total_instances > 0 {
+            Output::line(&format!(
+                "  • Guarded: {guarded_total} of {total_instances}"
+            ));
+        }
 
-        println!("\nProviders detected:");
+        Output::line("\nProviders detected:");
         if results.is_empty() {
-            println!("  (none)");
+            Output::line("  (none)");
         } else {
-            for provider in results.keys() {
-                println!("  ✓ {}", provider.as_str());
+            for (provider, instances) in results {
+                let mut files: Vec<&PathBuf> = instances.iter().map(|i| &i.file_path).collect();
+                files.dedup();
+                Output::line(&format!(
+                    "  ✓ {} ({} files, {} instances)",
+                    provider.as_str(),
+                    files.len(),
+                    instances.len()
+                ));
             }
         }
 
-        println!("\nNext: promptguard init");
-
         Ok(())
     }
 }
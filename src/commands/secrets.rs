@@ -0,0 +1,349 @@
+//! Secrets Command - Hardcoded provider keys
+//!
+//! Finds `PromptGuard` and LLM provider keys written inline in source files.
+//! `secrets migrate` moves them into the env file and rewrites the code to
+//! read each key from its environment variable instead.
+
+use crate::analyzer::secrets::SecretMatch;
+use crate::analyzer::{find_secrets, SecretKind};
+use crate::backup::BackupStore;
+use crate::config::{ConfigManager, DEFAULT_ENV_FILE};
+use crate::env::EnvManager;
+use crate::error::{PromptGuardError, Result};
+use crate::output::Output;
+use crate::scanner::FileScanner;
+use crate::textfile;
+use crate::transformer::{secrets::move_to_env, unified_diff, StagedChanges};
+use crate::types::Language;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub enum SecretsAction {
+    /// Report hardcoded keys; exits 2 when any are found
+    Scan,
+    /// Move hardcoded keys into the env file
+    Migrate { yes: bool, dry_run: bool },
+}
+
+pub struct SecretsCommand {
+    pub action: SecretsAction,
+    pub json: bool,
+}
+
+/// A hardcoded key and the env var it belongs in
+#[derive(Debug, Serialize)]
+struct Finding {
+    file: String,
+    line: usize,
+    column: usize,
+    kind: SecretKind,
+    /// Masked; the key itself is never printed
+    value: String,
+    env_var: String,
+}
+
+/// One source file with hardcoded keys
+struct SourceFile {
+    path: PathBuf,
+    rel_path: String,
+    language: Language,
+    source: String,
+    secrets: Vec<SecretMatch>,
+}
+
+impl SecretsCommand {
+    pub fn execute(&self) -> Result<()> {
+        let root_path = crate::config::project_root()?;
+        let config_manager = ConfigManager::new(None)?;
+        let config = if config_manager.exists() {
+            Some(config_manager.load()?)
+        } else {
+            None
+        };
+        let exclude = config.as_ref().map(|c| c.exclude_patterns.clone());
+        let env_file = config
+            .as_ref()
+            .map_or(DEFAULT_ENV_FILE, |c| c.env_file.primary())
+            .to_string();
+
+        let sources = Self::sources(&root_path, exclude)?;
+        let env_path = root_path.join(&env_file);
+        let env_vars = Self::assign_env_vars(&sources, &env_path);
+        let findings: Vec<Finding> = sources
+            .iter()
+            .flat_map(|file| {
+                file.secrets.iter().map(|secret| Finding {
+                    file: file.rel_path.clone(),
+                    line: secret.line,
+                    column: secret.column,
+                    kind: secret.kind,
+                    value: Output::mask_api_key(&secret.value),
+                    env_var: env_vars[&secret.value].clone(),
+                })
+            })
+            .collect();
+
+        match self.action {
+            SecretsAction::Scan => self.scan(&findings, &env_file),
+            SecretsAction::Migrate { yes, dry_run } => {
+                self.migrate(&sources, &env_vars, &env_file, yes, dry_run)
+            },
+        }
+    }
+
+    /// Source files containing at least one key
+    fn sources(root_path: &Path, exclude: Option<Vec<String>>) -> Result<Vec<SourceFile>> {
+        let state_dir = crate::state::project_state_dir(root_path);
+        let mut sources = Vec::new();
+        for path in FileScanner::new(root_path, exclude)?.scan_files(None)? {
+            // Generated shims and snapshots aren't the user's code
+            if path.starts_with(&state_dir) {
+                continue;
+            }
+            let Some(language) = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .and_then(Language::from_extension)
+            else {
+                continue;
+            };
+            let Ok((_, source)) = textfile::read(&path) else {
+                continue;
+            };
+            let secrets = find_secrets(&source);
+            if secrets.is_empty() {
+                continue;
+            }
+            let rel_path = path
+                .strip_prefix(root_path)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            sources.push(SourceFile {
+                path,
+                rel_path,
+                language,
+                source,
+                secrets,
+            });
+        }
+        Ok(sources)
+    }
+
+    /// Env var for each distinct key. A key the env file already holds keeps
+    /// its var; a second key of the same kind gets a numbered one.
+    fn assign_env_vars(sources: &[SourceFile], env_path: &Path) -> BTreeMap<String, String> {
+        let mut assigned: BTreeMap<String, String> = BTreeMap::new();
+        for secret in sources.iter().flat_map(|f| &f.secrets) {
+            if assigned.contains_key(&secret.value) {
+                continue;
+            }
+            let base = secret.kind.env_var();
+            let mut var = base.to_string();
+            let mut n = 1;
+            while assigned.values().any(|v| *v == var)
+                || EnvManager::get_key(env_path, &var).is_some_and(|held| held != secret.value)
+            {
+                n += 1;
+                var = format!("{base}_{n}");
+            }
+            assigned.insert(secret.value.clone(), var);
+        }
+        assigned
+    }
+
+    fn scan(&self, findings: &[Finding], env_file: &str) -> Result<()> {
+        if self.json {
+            Output::json(serde_json::to_value(findings)?)?;
+        } else {
+            Output::header("Hardcoded Secrets");
+            if findings.is_empty() {
+                Output::success("No hardcoded API keys found");
+                return Ok(());
+            }
+            for finding in findings {
+                Output::warning(&format!(
+                    "{}:{}:{}  {} {}",
+                    finding.file,
+                    finding.line,
+                    finding.column,
+                    finding.kind.display_name(),
+                    finding.value
+                ));
+            }
+            Output::line(&format!(
+                "\nRun 'promptguard secrets migrate' to move them to {env_file} and read them from the environment"
+            ));
+        }
+        if findings.is_empty() {
+            return Ok(());
+        }
+        Err(PromptGuardError::Findings(format!(
+            "{} hardcoded API key{} found",
+            findings.len(),
+            if findings.len() == 1 { "" } else { "s" }
+        )))
+    }
+
+    fn migrate(
+        &self,
+        sources: &[SourceFile],
+        env_vars: &BTreeMap<String, String>,
+        env_file: &str,
+        yes: bool,
+        dry_run: bool,
+    ) -> Result<()> {
+        if !self.json {
+            Output::header("Migrate Hardcoded Secrets");
+        }
+
+        // Rewrite in memory first; the writes are staged and rolled back together
+        let mut rewrites: Vec<(&SourceFile, String)> = Vec::new();
+        let mut moved: Vec<(&SourceFile, &SecretMatch)> = Vec::new();
+        let mut skipped: Vec<(&SourceFile, &SecretMatch)> = Vec::new();
+        for file in sources {
+            let targets: Vec<(SecretMatch, String)> = file
+                .secrets
+                .iter()
+                .map(|s| (s.clone(), env_vars[&s.value].clone()))
+                .collect();
+            let (new_source, made) = move_to_env(&file.source, file.language, &targets);
+            for (index, secret) in file.secrets.iter().enumerate() {
+                if made.contains(&index) {
+                    moved.push((file, secret));
+                } else {
+                    skipped.push((file, secret));
+                }
+            }
+            if !made.is_empty() {
+                rewrites.push((file, new_source));
+            }
+        }
+
+        let keys: BTreeMap<&str, &str> = moved
+            .iter()
+            .map(|(_, s)| (env_vars[&s.value].as_str(), s.value.as_str()))
+            .collect();
+
+        // Printed only once the keys have actually moved
+        let print_json = || {
+            if !self.json {
+                return Ok(());
+            }
+            Output::json(serde_json::json!({
+                "env_file": env_file,
+                "dry_run": dry_run,
+                "moved": moved.iter().map(|(f, s)| Self::location_json(f, s, env_vars)).collect::<Vec<_>>(),
+                "skipped": skipped.iter().map(|(f, s)| Self::location_json(f, s, env_vars)).collect::<Vec<_>>(),
+            }))
+        };
+
+        if !self.json {
+            if moved.is_empty() && skipped.is_empty() {
+                Output::success("No hardcoded API keys found");
+                return Ok(());
+            }
+            for (file, secret) in &moved {
+                Output::step(&format!(
+                    "{}:{} {} → {}",
+                    file.rel_path,
+                    secret.line,
+                    secret.kind.display_name(),
+                    env_vars[&secret.value]
+                ));
+            }
+            if !skipped.is_empty() {
+                Output::warning("Not a standalone string literal (move these by hand):");
+                for (file, secret) in &skipped {
+                    Output::line(&format!(
+                        "  • {}:{} {}",
+                        file.rel_path,
+                        secret.line,
+                        secret.kind.display_name()
+                    ));
+                }
+            }
+            if dry_run {
+                for (file, new_source) in &rewrites {
+                    Output::diff(&unified_diff(
+                        &file.source,
+                        new_source,
+                        &format!("a/{}", file.rel_path),
+                        &format!("b/{}", file.rel_path),
+                    ));
+                }
+            }
+        }
+        if rewrites.is_empty() || dry_run {
+            return print_json();
+        }
+
+        // Moving keys into a file git would commit doesn't take them out of the repo
+        let root_path = crate::config::project_root()?;
+        let env_path = &root_path.join(env_file);
+        if EnvManager::is_committed(env_path) {
+            return Err(PromptGuardError::Config(format!(
+                "Refusing to move keys to {env_file}: git would commit it. Add it to .gitignore first"
+            )));
+        }
+        for value in keys.values() {
+            EnvManager::check_key_destination(env_path, value)?;
+        }
+
+        if !yes
+            && !Output::confirm(
+                &format!(
+                    "\nMove {} keys to {env_file} and rewrite {} files?",
+                    keys.len(),
+                    rewrites.len()
+                ),
+                true,
+            )?
+        {
+            Output::info("Migration cancelled");
+            return Ok(());
+        }
+
+        let files: Vec<PathBuf> = rewrites.iter().map(|(f, _)| f.path.clone()).collect();
+        BackupStore::new(&root_path).snapshot(&files, "secrets migrate")?;
+        let mut env = if env_path.exists() {
+            textfile::read(env_path)?.1
+        } else {
+            String::new()
+        };
+        for (var, value) in &keys {
+            env = EnvManager::with_key(&env, var, value);
+        }
+        let mut staged = StagedChanges::new();
+        staged.replace(env_path, env)?;
+        for (file, new_source) in &rewrites {
+            staged.replace(&file.path, new_source.clone())?;
+        }
+        staged.commit()?;
+
+        if !self.json {
+            Output::line("");
+            Output::success(&format!(
+                "Moved {} keys to {env_file} and rewrote {} files",
+                keys.len(),
+                rewrites.len()
+            ));
+            Output::line("  Keys that were ever committed stay in git history; rotate them with their provider");
+        }
+        print_json()
+    }
+
+    fn location_json(
+        file: &SourceFile,
+        secret: &SecretMatch,
+        env_vars: &BTreeMap<String, String>,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "file": file.rel_path,
+            "line": secret.line,
+            "kind": secret.kind,
+            "env_var": env_vars[&secret.value],
+        })
+    }
+}
@@ -0,0 +1,843 @@
+use crate::config::{ConfigManager, PromptGuardConfig};
+use crate::env::EnvManager;
+use crate::error::{PromptGuardError, Result};
+use crate::output::Output;
+use crate::scanner::FileScanner;
+use crate::shim::deno::{self, DENO_IMPORT_MAP_PATH};
+use crate::shim::docker::ComposeInjection;
+use crate::shim::{
+    ShimGenerator, ShimInjection, ShimInjector, ShimIntegrity, BUN_PRELOAD_PATH,
+    NODE_PRELOAD_REQUIRE, SHIM_VERSION,
+};
+use crate::types::{Language, Provider};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+pub enum ShimAction {
+    /// Write the shim files for the project's languages, without touching sources
+    Generate { inject: Vec<String> },
+    /// Load the generated shims: import them in entry points or set up the
+    /// loaders `inject` names
+    Inject {
+        inject: Vec<String>,
+        include_tests: bool,
+    },
+    /// Undo `Inject`, and delete the shim files unless `keep_files`
+    Remove { keep_files: bool },
+    /// Report installed shim files and where they are loaded from
+    Status,
+    /// Regenerate shims written by another CLI version; `check` only reports
+    Upgrade { check: bool, force: bool },
+}
+
+/// How the runtime shims load in a project, from its languages and the
+/// `--inject` modes
+#[allow(clippy::struct_excessive_bools)]
+pub struct ShimPlan {
+    pub languages: Vec<Language>,
+    /// Node processes load `.promptguard/preload.cjs` with `--require`
+    pub preload: bool,
+    pub docker: bool,
+    /// Bun ignores Node's loader hooks and can't run the TypeScript shim,
+    /// so Bun projects always load the shims from a bunfig.toml preload
+    pub bun: bool,
+    /// Deno only takes an import map, which is on the command line or in deno.json
+    pub deno: bool,
+    /// Virtualenv getting the `.pth` file for `--inject site`
+    pub virtualenv: Option<PathBuf>,
+}
+
+impl ShimPlan {
+    /// Fails when `--inject site` has no virtualenv to install into
+    pub fn new(
+        injector: &ShimInjector,
+        root_path: &Path,
+        languages: Vec<Language>,
+        injections: &[ShimInjection],
+    ) -> Result<Self> {
+        let node = languages.iter().any(Self::is_node);
+        let virtualenv = if injections.contains(&ShimInjection::Site)
+            && languages.contains(&Language::Python)
+        {
+            Some(injector.find_virtualenv().ok_or_else(|| {
+                PromptGuardError::Custom(
+                    "No virtualenv found for --inject site. Activate one or create .venv in the project"
+                        .to_string(),
+                )
+            })?)
+        } else {
+            None
+        };
+        Ok(Self {
+            preload: injections.contains(&ShimInjection::Preload) && node,
+            docker: injections.contains(&ShimInjection::Docker),
+            bun: node && injector.is_bun_project(),
+            deno: node && deno::is_deno_project(root_path),
+            virtualenv,
+            languages,
+        })
+    }
+
+    fn is_node(language: &Language) -> bool {
+        matches!(language, Language::TypeScript | Language::JavaScript)
+    }
+
+    /// Whether `language` loads the shim from entry point imports; languages
+    /// loaded some other way keep their entry points untouched
+    pub fn via_entry_points(&self, language: &Language) -> bool {
+        if Self::is_node(language) {
+            !self.preload && !self.bun && !self.deno
+        } else {
+            self.virtualenv.is_none()
+        }
+    }
+}
+
+/// What `ShimCommand::inject` changed
+#[derive(Default)]
+pub struct ShimInjections {
+    pub entry_points: Vec<PathBuf>,
+    pub start_scripts: Vec<String>,
+    pub node_options: bool,
+    pub bunfig: bool,
+    pub site: Option<PathBuf>,
+    pub compose: Vec<ComposeInjection>,
+}
+
+impl ShimInjections {
+    /// Print the changes, with how to load the shims where nothing could be injected
+    pub fn print(&self, plan: &ShimPlan, root_path: &Path, env_file: &str) {
+        for entry_point in &self.entry_points {
+            let rel_path = entry_point.strip_prefix(root_path).unwrap_or(entry_point);
+            Output::step(&format!("✓ Injected into {}", rel_path.display()));
+        }
+
+        if plan.preload {
+            for script in &self.start_scripts {
+                Output::step(&format!(
+                    "✓ Added {NODE_PRELOAD_REQUIRE} to npm script '{script}'"
+                ));
+            }
+            if self.node_options {
+                Output::step(&format!("✓ Set NODE_OPTIONS in {env_file}"));
+            }
+            Output::line("\n  Node processes started from the project root now load the preload.");
+            Output::line(&format!(
+                "  Elsewhere, run: NODE_OPTIONS=\"{NODE_PRELOAD_REQUIRE}\" node <entry>"
+            ));
+        }
+
+        if plan.bun {
+            if self.bunfig {
+                Output::step(&format!(
+                    "✓ Added {BUN_PRELOAD_PATH} to bunfig.toml preload"
+                ));
+            }
+            Output::line("\n  Bun loads the shim before every script run from the project root.");
+        }
+
+        if plan.deno {
+            Output::line("\n  Deno resolves SDK imports through the generated import map:");
+            Output::line(&format!(
+                "    deno run --import-map={DENO_IMPORT_MAP_PATH} <entry>"
+            ));
+            Output::line(&format!(
+                "  Or set \"importMap\": \"{DENO_IMPORT_MAP_PATH}\" in deno.json in place of"
+            ));
+            Output::line("  \"imports\" and \"scopes\" (the generated map includes them).");
+        }
+
+        if let Some(pth) = &self.site {
+            Output::step(&format!("✓ Added {}", pth.display()));
+            Output::line("\n  Every Python process in this virtualenv now loads the shim.");
+        }
+
+        if plan.docker {
+            for injection in &self.compose {
+                let rel_path = injection
+                    .file
+                    .strip_prefix(root_path)
+                    .unwrap_or(&injection.file);
+                Output::step(&format!(
+                    "✓ Set {} for service '{}' in {}",
+                    injection.variable,
+                    injection.service,
+                    rel_path.display()
+                ));
+            }
+            if self.compose.is_empty() {
+                Output::warning(
+                    "No docker-compose services running Python or Node.js to configure",
+                );
+            } else {
+                Output::line("\n  Copy .promptguard/ into the images (check .dockerignore) so containers can load it.");
+            }
+        }
+
+        let pending: Vec<&Language> = plan
+            .languages
+            .iter()
+            .filter(|l| plan.via_entry_points(l))
+            .collect();
+        if self.entry_points.is_empty() && !pending.is_empty() {
+            Output::warning("No entry points found to inject into");
+            Output::line("  Import the shim before any SDK is loaded:");
+            if pending.contains(&&Language::Python) {
+                Output::line(
+                    "    Python: sys.path.insert(0, '.promptguard'); import promptguard_shim",
+                );
+            }
+            if pending.iter().any(|l| ShimPlan::is_node(l)) {
+                Output::line(
+                    "    TypeScript/JavaScript: import './.promptguard/promptguard-shim';",
+                );
+            }
+        }
+    }
+}
+
+/// What `ShimCommand::remove` undid
+#[derive(Default)]
+pub struct ShimRemoval {
+    pub entry_points: usize,
+    pub site: Option<PathBuf>,
+    pub start_scripts: Vec<String>,
+    pub bunfig: bool,
+    pub compose: Vec<PathBuf>,
+    pub node_options: bool,
+    pub shim_dir: bool,
+}
+
+impl ShimRemoval {
+    pub fn to_json(&self, root_path: &Path) -> serde_json::Value {
+        serde_json::json!({
+            "entry_points": self.entry_points,
+            "start_scripts": self.start_scripts,
+            "node_options": self.node_options,
+            "bunfig": self.bunfig,
+            "site": self.site.as_ref().map(|p| p.display().to_string()),
+            "compose_files": ShimCommand::relative(root_path, &self.compose),
+            "shim_dir": self.shim_dir,
+        })
+    }
+
+    pub fn print(&self, root_path: &Path, env_file: &str) {
+        if self.entry_points > 0 {
+            Output::step(&format!(
+                "✓ Removed imports from {} files",
+                self.entry_points
+            ));
+        }
+        if let Some(pth) = &self.site {
+            Output::step(&format!("✓ Removed {}", pth.display()));
+        }
+        for script in &self.start_scripts {
+            Output::step(&format!("✓ Removed preload from npm script '{script}'"));
+        }
+        if self.bunfig {
+            Output::step("✓ Removed preload from bunfig.toml");
+        }
+        for compose_file in &self.compose {
+            let rel_path = compose_file.strip_prefix(root_path).unwrap_or(compose_file);
+            Output::step(&format!(
+                "✓ Removed shim variables from {}",
+                rel_path.display()
+            ));
+        }
+        if self.node_options {
+            Output::step(&format!(
+                "✓ Removed preload from NODE_OPTIONS in {env_file}"
+            ));
+        }
+        if self.shim_dir {
+            Output::step("✓ Removed .promptguard/ directory");
+        }
+    }
+}
+
+pub struct ShimCommand {
+    pub action: ShimAction,
+    pub yes: bool,
+    pub json: bool,
+}
+
+impl ShimCommand {
+    pub fn execute(&self) -> Result<()> {
+        let config_manager = ConfigManager::new(None)?;
+        if !config_manager.exists() {
+            return Err(PromptGuardError::NotInitialized);
+        }
+        let mut config = config_manager.load()?;
+        let root_path = crate::config::project_root()?;
+
+        match &self.action {
+            ShimAction::Generate { inject } => {
+                self.generate(&root_path, &mut config, &config_manager, inject)
+            },
+            ShimAction::Inject {
+                inject,
+                include_tests,
+            } => self.inject_action(
+                &root_path,
+                &mut config,
+                &config_manager,
+                inject,
+                *include_tests,
+            ),
+            ShimAction::Remove { keep_files } => {
+                self.remove_action(&root_path, &mut config, &config_manager, *keep_files)
+            },
+            ShimAction::Status => self.status(&root_path, &config),
+            ShimAction::Upgrade { check, force } => {
+                self.upgrade(&root_path, &mut config, &config_manager, *check, *force)
+            },
+        }
+    }
+
+    /// Parse `--inject` modes
+    pub fn injections(modes: &[String]) -> Result<Vec<ShimInjection>> {
+        modes
+            .iter()
+            .map(|mode| {
+                ShimInjection::parse(mode).ok_or_else(|| {
+                    PromptGuardError::Custom(format!(
+                        "Unknown injection mode '{mode}'. Use entry-points, preload, site, or docker"
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// Languages among `files` that have a runtime shim
+    pub fn detect_languages(files: &[PathBuf]) -> Vec<Language> {
+        files
+            .iter()
+            .filter_map(|path| path.extension().and_then(|e| e.to_str()))
+            .filter_map(Language::from_extension)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// The plan for this project's sources, or `None` when no language has a shim
+    fn project_plan(
+        root_path: &Path,
+        config: &PromptGuardConfig,
+        injector: &ShimInjector,
+        inject: &[String],
+    ) -> Result<Option<ShimPlan>> {
+        let injections = Self::injections(inject)?;
+        let scanner = FileScanner::new(root_path, Some(config.exclude_patterns.clone()))?;
+        let languages = Self::detect_languages(&scanner.scan_files(None)?);
+        if languages.is_empty() {
+            return Ok(None);
+        }
+        ShimPlan::new(injector, root_path, languages, &injections).map(Some)
+    }
+
+    /// The injector `enable --runtime` uses, honouring the configured exclude patterns
+    pub fn injector(root_path: &Path, config: &PromptGuardConfig) -> ShimInjector {
+        ShimInjector::new(root_path).with_exclude_patterns(config.exclude_patterns.clone())
+    }
+
+    /// Generate the shim files `plan` loads
+    pub fn generate_files(generator: &ShimGenerator, plan: &ShimPlan) -> Result<Vec<PathBuf>> {
+        let node = plan.languages.iter().any(ShimPlan::is_node);
+        let mut shim_files = generator.generate_shims(&plan.languages)?;
+        if plan.preload || (plan.docker && node) {
+            shim_files.push(generator.generate_node_preload()?);
+        }
+        if plan.docker && plan.languages.contains(&Language::Python) {
+            shim_files.push(generator.generate_python_sitecustomize()?);
+        }
+        if plan.bun {
+            shim_files.push(generator.generate_bun_preload()?);
+        }
+        if plan.deno {
+            shim_files.extend(generator.generate_deno_shim()?);
+        }
+        Ok(shim_files)
+    }
+
+    /// Load the generated shims the way `plan` describes
+    pub fn inject(
+        injector: &ShimInjector,
+        plan: &ShimPlan,
+        env_path: &Path,
+    ) -> Result<ShimInjections> {
+        let mut injected = ShimInjections::default();
+        for language in plan.languages.iter().filter(|l| plan.via_entry_points(l)) {
+            injected
+                .entry_points
+                .extend(injector.inject_shims(*language)?);
+        }
+        if plan.preload {
+            injected.start_scripts = injector.inject_node_preload()?;
+            injected.node_options =
+                EnvManager::add_option(env_path, "NODE_OPTIONS", NODE_PRELOAD_REQUIRE)?;
+        }
+        if plan.bun {
+            injected.bunfig = injector.inject_bun_preload()?;
+        }
+        if let Some(venv) = &plan.virtualenv {
+            injected.site = Some(injector.inject_python_site(venv)?);
+        }
+        if plan.docker {
+            injected.compose = injector.inject_compose_env(&plan.languages)?;
+        }
+        Ok(injected)
+    }
+
+    /// Undo every way `inject` loads the shims, then delete the shim
+    /// directory unless `keep_files`
+    pub fn remove(
+        root_path: &Path,
+        config: &PromptGuardConfig,
+        keep_files: bool,
+    ) -> Result<ShimRemoval> {
+        let injector = ShimInjector::new(root_path);
+        let mut removal = ShimRemoval {
+            entry_points: injector.remove_all_injections()?,
+            ..ShimRemoval::default()
+        };
+        if let Some(venv) = injector.find_virtualenv() {
+            removal.site = injector.remove_python_site(&venv)?;
+        }
+        removal.start_scripts = injector.remove_node_preload()?;
+        removal.bunfig = injector.remove_bun_preload()?;
+        removal.compose = injector.remove_compose_env()?;
+        removal.node_options = EnvManager::remove_option(
+            &root_path.join(config.env_file.primary()),
+            "NODE_OPTIONS",
+            NODE_PRELOAD_REQUIRE,
+        )?;
+
+        let generator = Self::generator(root_path, config);
+        if !keep_files && generator.shims_installed() {
+            generator.clean_shims()?;
+            removal.shim_dir = true;
+        }
+        Ok(removal)
+    }
+
+    /// The generator `enable --runtime` used for this project's shims
+    pub fn generator(root_path: &Path, config: &PromptGuardConfig) -> ShimGenerator {
+        let providers = config
+            .providers
+            .iter()
+            .filter_map(|p| Provider::parse(p))
+            .collect();
+        ShimGenerator::new(
+            root_path,
+            config.proxy_url.clone(),
+            config.env_var_name.clone(),
+            providers,
+        )
+        .with_api_key_var_overrides(config.env_var_overrides.clone())
+        .with_fail_mode(config.shim_fail_mode)
+        .with_shim_log(config.shim_log)
+    }
+
+    fn generate(
+        &self,
+        root_path: &Path,
+        config: &mut PromptGuardConfig,
+        config_manager: &ConfigManager,
+        inject: &[String],
+    ) -> Result<()> {
+        let injector = Self::injector(root_path, config);
+        let Some(plan) = Self::project_plan(root_path, config, &injector, inject)? else {
+            return Err(PromptGuardError::Custom(
+                "No supported languages detected".to_string(),
+            ));
+        };
+        let generator = Self::generator(root_path, config);
+        let shim_files = Self::generate_files(&generator, &plan)?;
+        config.metadata.shim_checksums = generator.checksums()?;
+        config_manager.save(config)?;
+
+        if self.json {
+            let result = serde_json::json!({
+                "languages": plan.languages.iter().map(Language::as_str).collect::<Vec<_>>(),
+                "files": Self::relative(root_path, &shim_files),
+                "version": SHIM_VERSION,
+            });
+            Output::json(result)?;
+            return Ok(());
+        }
+
+        Output::header("Generate Runtime Shims");
+        for shim_file in &shim_files {
+            let rel_path = shim_file.strip_prefix(root_path).unwrap_or(shim_file);
+            Output::step(&format!("✓ Generated {}", rel_path.display()));
+        }
+        Output::line("");
+        Output::success(&format!("Generated {} shim file(s)", shim_files.len()));
+        Output::line("  Load them with: promptguard shim inject");
+        Ok(())
+    }
+
+    fn inject_action(
+        &self,
+        root_path: &Path,
+        config: &mut PromptGuardConfig,
+        config_manager: &ConfigManager,
+        inject: &[String],
+        include_tests: bool,
+    ) -> Result<()> {
+        let generator = Self::generator(root_path, config);
+        if generator.installed_files().is_empty() {
+            return Err(PromptGuardError::Custom(
+                "No runtime shims generated. Run: promptguard shim generate".to_string(),
+            ));
+        }
+        let injector = Self::injector(root_path, config).with_tests(include_tests);
+        let Some(plan) = Self::project_plan(root_path, config, &injector, inject)? else {
+            return Err(PromptGuardError::Custom(
+                "No supported languages detected".to_string(),
+            ));
+        };
+        if plan.preload && !generator.node_preload_path().is_file() {
+            return Err(PromptGuardError::Custom(
+                "The Node preload was not generated. Run: promptguard shim generate --inject preload"
+                    .to_string(),
+            ));
+        }
+
+        if !self.json {
+            Output::header("Inject Runtime Shims");
+        }
+        if !self.yes && !Output::confirm("Load the shims in this project's entry points?", true)? {
+            return Ok(());
+        }
+
+        let injected = Self::inject(&injector, &plan, &root_path.join(config.env_file.primary()))?;
+        config.enabled = true;
+        config.runtime_mode = true;
+        config_manager.save(config)?;
+
+        if self.json {
+            let result = serde_json::json!({
+                "entry_points": Self::relative(root_path, &injected.entry_points),
+                "start_scripts": injected.start_scripts,
+                "node_options": injected.node_options,
+                "bunfig": injected.bunfig,
+                "site": injected.site.as_ref().map(|p| p.display().to_string()),
+                "compose_services": injected
+                    .compose
+                    .iter()
+                    .map(|c| c.service.clone())
+                    .collect::<Vec<_>>(),
+            });
+            Output::json(result)?;
+            return Ok(());
+        }
+
+        injected.print(&plan, root_path, config.env_file.primary());
+        Output::line("");
+        Output::success("Runtime shims injected");
+        Ok(())
+    }
+
+    fn remove_action(
+        &self,
+        root_path: &Path,
+        config: &mut PromptGuardConfig,
+        config_manager: &ConfigManager,
+        keep_files: bool,
+    ) -> Result<()> {
+        if !self.json {
+            Output::header("Remove Runtime Shims");
+            Output::line("\n  • Removing shim imports and loaders");
+            if !keep_files {
+                Output::line("  • Deleting .promptguard/");
+            }
+        }
+        if !self.yes && !Output::confirm("Continue?", true)? {
+            return Ok(());
+        }
+
+        let removal = Self::remove(root_path, config, keep_files)?;
+        if removal.shim_dir {
+            config.metadata.shim_checksums.clear();
+        }
+        if config.runtime_mode {
+            config.enabled = false;
+        }
+        config_manager.save(config)?;
+
+        if self.json {
+            Output::json(removal.to_json(root_path))?;
+            return Ok(());
+        }
+
+        removal.print(root_path, config.env_file.primary());
+        Output::line("");
+        Output::success("Runtime shims removed");
+        Ok(())
+    }
+
+    fn status(&self, root_path: &Path, config: &PromptGuardConfig) -> Result<()> {
+        let generator = Self::generator(root_path, config);
+        let injector = ShimInjector::new(root_path);
+        let installed = generator.installed_files();
+        let installed_version = generator.installed_version();
+        let integrity = generator.verify(&config.metadata.shim_checksums);
+        let entry_points = Self::relative(root_path, &injector.injected_files()?);
+        let start_scripts = injector.node_preload_scripts();
+        let bunfig = injector.has_bun_preload();
+        let site = injector
+            .find_virtualenv()
+            .and_then(|venv| injector.python_site_path(&venv));
+        let node_options =
+            EnvManager::get_key(&root_path.join(config.env_file.primary()), "NODE_OPTIONS")
+                .is_some_and(|value| value.contains(NODE_PRELOAD_REQUIRE));
+        let modified: Vec<&String> = integrity
+            .iter()
+            .filter_map(|problem| match problem {
+                ShimIntegrity::Modified(name) => Some(name),
+                ShimIntegrity::Missing(_) => None,
+            })
+            .collect();
+        let missing: Vec<&String> = integrity
+            .iter()
+            .filter_map(|problem| match problem {
+                ShimIntegrity::Missing(name) => Some(name),
+                ShimIntegrity::Modified(_) => None,
+            })
+            .collect();
+
+        if self.json {
+            let result = serde_json::json!({
+                "runtime_mode": config.enabled && config.runtime_mode,
+                "files": installed,
+                "installed_version": installed_version,
+                "cli_version": SHIM_VERSION,
+                "outdated": generator.is_outdated(),
+                "modified": modified,
+                "missing": missing,
+                "entry_points": entry_points,
+                "start_scripts": start_scripts,
+                "node_options": node_options,
+                "bunfig": bunfig,
+                "site": site.as_ref().map(|p| p.display().to_string()),
+            });
+            Output::json(result)?;
+            return Ok(());
+        }
+
+        Output::header("Runtime Shim Status");
+        let mode = if config.enabled && config.runtime_mode {
+            "enabled"
+        } else {
+            "disabled"
+        };
+        Output::line(&format!("\n  • Runtime mode: {mode}"));
+        if installed.is_empty() {
+            Output::line("  • Shim files: none (run 'promptguard shim generate')");
+            return Ok(());
+        }
+        Output::line(&format!("  • Shim files: {}", installed.join(", ")));
+        match &installed_version {
+            Some(version) if !generator.is_outdated() => {
+                Output::line(&format!("  • Version: v{version}"));
+            },
+            _ => Output::line(&format!(
+                "  • Version: ⚠ {} (run 'promptguard shim upgrade')",
+                installed_version.as_deref().map_or_else(
+                    || "unknown (pre-versioning)".to_string(),
+                    |v| format!("v{v}")
+                )
+            )),
+        }
+        if !modified.is_empty() || !missing.is_empty() {
+            Output::line(&format!(
+                "  • Integrity: ⚠ {} modified, {} missing",
+                modified.len(),
+                missing.len()
+            ));
+        }
+
+        Output::section("Loaded from", "💉");
+        let mut loaders = 0;
+        for entry_point in &entry_points {
+            Output::step(entry_point);
+            loaders += 1;
+        }
+        for script in &start_scripts {
+            Output::step(&format!("npm script '{script}' ({NODE_PRELOAD_REQUIRE})"));
+            loaders += 1;
+        }
+        if node_options {
+            Output::step(&format!("NODE_OPTIONS in {}", config.env_file.primary()));
+            loaders += 1;
+        }
+        if bunfig {
+            Output::step("bunfig.toml preload");
+            loaders += 1;
+        }
+        if let Some(pth) = &site {
+            Output::step(&pth.display().to_string());
+            loaders += 1;
+        }
+        if loaders == 0 {
+            Output::warning("The shims are not loaded anywhere. Run: promptguard shim inject");
+        }
+        Ok(())
+    }
+
+    /// Languages whose processes load the runtime shims: an injected entry
+    /// point or a loader (preload, `NODE_OPTIONS`, bunfig, Deno import map,
+    /// virtualenv `.pth`). Empty unless runtime mode is on and shims exist.
+    pub fn loaded_languages(
+        root_path: &Path,
+        config: &PromptGuardConfig,
+    ) -> Result<BTreeSet<Language>> {
+        let mut languages = BTreeSet::new();
+        if !config.enabled
+            || !config.runtime_mode
+            || !Self::generator(root_path, config).shims_installed()
+        {
+            return Ok(languages);
+        }
+        let injector = ShimInjector::new(root_path);
+        let mut node_loader = false;
+        for file in injector.injected_files()? {
+            match file
+                .extension()
+                .and_then(|e| e.to_str())
+                .and_then(Language::from_extension)
+            {
+                Some(Language::Python) => {
+                    languages.insert(Language::Python);
+                },
+                Some(_) => node_loader = true,
+                None => {},
+            }
+        }
+        if injector
+            .find_virtualenv()
+            .and_then(|venv| injector.python_site_path(&venv))
+            .is_some()
+        {
+            languages.insert(Language::Python);
+        }
+        // One Node process runs both, so either loader covers both
+        node_loader |= !injector.node_preload_scripts().is_empty()
+            || injector.has_bun_preload()
+            || root_path.join(DENO_IMPORT_MAP_PATH).is_file()
+            || EnvManager::get_key(&root_path.join(config.env_file.primary()), "NODE_OPTIONS")
+                .is_some_and(|value| value.contains(NODE_PRELOAD_REQUIRE));
+        if node_loader {
+            languages.extend([Language::TypeScript, Language::JavaScript]);
+        }
+        Ok(languages)
+    }
+
+    /// `paths` relative to the project root, for display
+    fn relative(root_path: &Path, paths: &[PathBuf]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| p.strip_prefix(root_path).unwrap_or(p).display().to_string())
+            .collect()
+    }
+
+    fn upgrade(
+        &self,
+        root_path: &Path,
+        config: &mut PromptGuardConfig,
+        config_manager: &ConfigManager,
+        check: bool,
+        force: bool,
+    ) -> Result<()> {
+        let generator = Self::generator(root_path, config);
+        let installed = generator.installed_files();
+        if installed.is_empty() {
+            return Err(PromptGuardError::Custom(
+                "No runtime shims installed. Run: promptguard enable --runtime".to_string(),
+            ));
+        }
+
+        let installed_version = generator.installed_version();
+        let outdated = generator.is_outdated();
+        let installed_label = installed_version.as_deref().map_or_else(
+            || "unknown (pre-versioning)".to_string(),
+            |v| format!("v{v}"),
+        );
+
+        if check || (!outdated && !force) {
+            if self.json {
+                let result = serde_json::json!({
+                    "installed_version": installed_version,
+                    "cli_version": SHIM_VERSION,
+                    "outdated": outdated,
+                    "upgraded": false,
+                });
+                Output::json(result)?;
+            } else {
+                Output::header("Runtime Shim Version");
+                Output::step(&format!("Installed shims: {installed_label}"));
+                Output::step(&format!("CLI version: v{SHIM_VERSION}"));
+                if outdated {
+                    Output::warning("Runtime shims are outdated");
+                    Output::line("  Regenerate them with: promptguard shim upgrade");
+                } else {
+                    Output::success("Runtime shims are up to date");
+                }
+            }
+            if check && outdated {
+                return Err(PromptGuardError::Custom(
+                    "Runtime shims are outdated. Run: promptguard shim upgrade".to_string(),
+                ));
+            }
+            return Ok(());
+        }
+
+        if !self.json {
+            Output::header("Upgrade Runtime Shims");
+            Output::line(&format!("\n  • Installed shims: {installed_label}"));
+            Output::line(&format!("  • CLI version: v{SHIM_VERSION}"));
+        }
+
+        // Regenerating overwrites manual edits, so name them before asking
+        let edited = generator.verify(&config.metadata.shim_checksums);
+        if !edited.is_empty() && !self.json {
+            Output::warning(&format!(
+                "{} shim file(s) were edited or removed since generation; upgrading overwrites them",
+                edited.len()
+            ));
+        }
+        if !self.yes && !Output::confirm("Regenerate the shims in place?", true)? {
+            return Ok(());
+        }
+
+        let recorded: BTreeSet<String> = installed
+            .into_iter()
+            .chain(config.metadata.shim_checksums.keys().cloned())
+            .collect();
+        let regenerated = generator.regenerate(&recorded)?;
+        config.metadata.shim_checksums = generator.checksums()?;
+        config_manager.save(config)?;
+
+        if self.json {
+            let result = serde_json::json!({
+                "installed_version": installed_version,
+                "cli_version": SHIM_VERSION,
+                "outdated": false,
+                "upgraded": true,
+                "files": Self::relative(root_path, &regenerated),
+            });
+            Output::json(result)?;
+            return Ok(());
+        }
+
+        for path in &regenerated {
+            let rel_path = path.strip_prefix(root_path).unwrap_or(path);
+            Output::step(&format!("✓ Regenerated {}", rel_path.display()));
+        }
+        Output::line("");
+        Output::success(&format!("Runtime shims upgraded to v{SHIM_VERSION}"));
+        Output::line("  Configuration and entry point imports were left as they are");
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+use crate::error::Result;
+use crate::output::Output;
+use crate::state;
+use std::path::PathBuf;
+
+pub enum StateAction {
+    Encrypt,
+    Decrypt,
+    Status,
+}
+
+pub struct StateCommand {
+    pub action: StateAction,
+    pub json: bool,
+}
+
+impl StateCommand {
+    pub fn execute(&self) -> Result<()> {
+        let dirs = Self::state_dirs()?;
+        match self.action {
+            StateAction::Encrypt => self.encrypt(&dirs),
+            StateAction::Decrypt => self.decrypt(&dirs),
+            StateAction::Status => self.status(&dirs),
+        }
+    }
+
+    /// Project and global state directories, in that order
+    fn state_dirs() -> Result<Vec<PathBuf>> {
+        let root_path = crate::config::project_root()?;
+        Ok(vec![
+            state::project_state_dir(&root_path),
+            state::global_state_dir()?,
+        ])
+    }
+
+    fn encrypt(&self, dirs: &[PathBuf]) -> Result<()> {
+        let created = !state::encryption_enabled();
+        let key = state::ensure_key()?;
+        let changed = state::encrypt_files(&key, &state::list_state_files(dirs))?;
+
+        if self.json {
+            let result = serde_json::json!({
+                "encryption": "enabled",
+                "key_created": created,
+                "files_encrypted": changed.len(),
+            });
+            Output::json(result)?;
+            return Ok(());
+        }
+
+        Output::header("Encrypt Local State");
+        if created {
+            Output::step("Generated state key at ~/.promptguard/state.key");
+        }
+        for file in &changed {
+            Output::step(&format!("✓ {}", file.display()));
+        }
+        Output::line("");
+        Output::success(&format!(
+            "State encryption enabled ({} files encrypted)",
+            changed.len()
+        ));
+        Output::line("\n  • New state files will be written encrypted");
+        Output::line(
+            "  • Keep ~/.promptguard/state.key safe - encrypted files cannot be read without it",
+        );
+
+        Ok(())
+    }
+
+    fn decrypt(&self, dirs: &[PathBuf]) -> Result<()> {
+        let changed = if state::encryption_enabled() {
+            let key = state::ensure_key()?;
+            let changed = state::decrypt_files(&key, &state::list_state_files(dirs))?;
+            state::remove_key()?;
+            changed
+        } else {
+            Vec::new()
+        };
+
+        if self.json {
+            let result = serde_json::json!({
+                "encryption": "disabled",
+                "files_decrypted": changed.len(),
+            });
+            Output::json(result)?;
+            return Ok(());
+        }
+
+        Output::header("Decrypt Local State");
+        for file in &changed {
+            Output::step(&format!("✓ {}", file.display()));
+        }
+        Output::line("");
+        Output::success(&format!(
+            "State encryption disabled ({} files decrypted)",
+            changed.len()
+        ));
+
+        Ok(())
+    }
+
+    fn status(&self, dirs: &[PathBuf]) -> Result<()> {
+        let enabled = state::encryption_enabled();
+        let files = state::list_state_files(dirs);
+        let encrypted = files
+            .iter()
+            .filter(|f| std::fs::read(f).is_ok_and(|d| state::is_encrypted(&d)))
+            .count();
+        let plaintext = files.len() - encrypted;
+
+        if self.json {
+            let result = serde_json::json!({
+                "encryption": if enabled { "enabled" } else { "disabled" },
+                "state_dirs": dirs.iter().map(|d| d.display().to_string()).collect::<Vec<_>>(),
+                "encrypted_files": encrypted,
+                "plaintext_files": plaintext,
+            });
+            Output::json(result)?;
+            return Ok(());
+        }
+
+        Output::header("Local State");
+        Output::line(&format!(
+            "\nEncryption: {}",
+            if enabled { "Enabled ✓" } else { "Disabled" }
+        ));
+        Output::line("\nState directories:");
+        for dir in dirs {
+            Output::line(&format!("  • {}", dir.display()));
+        }
+        Output::line("\nFiles:");
+        Output::line(&format!("  • Encrypted: {encrypted}"));
+        Output::line(&format!("  • Plaintext: {plaintext}"));
+
+        if enabled && plaintext > 0 {
+            Output::line("");
+            Output::warning("Some state files are not encrypted. Run: promptguard state encrypt");
+        } else if !enabled {
+            Output::line("\nTo enable: promptguard state encrypt");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+//! Stats Command - Proxy usage metrics
+//!
+//! Fetches request counts, block rate, top threat types and latency for a
+//! time window from the API and renders them as a compact terminal
+//! dashboard, for a quick health check without opening the web dashboard.
+
+use crate::api::PromptGuardClient;
+use crate::auth::{resolve_api_key, resolve_base_url};
+use crate::commands::logs::parse_since;
+use crate::config::ConfigManager;
+use crate::error::{PromptGuardError, Result};
+use crate::output::Output;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Usage metrics from the `/stats` endpoint
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct UsageStats {
+    #[serde(default)]
+    pub total_requests: u64,
+    #[serde(default)]
+    pub blocked_requests: u64,
+    #[serde(default)]
+    pub redacted_requests: u64,
+    /// Most frequent threat types, most frequent first
+    #[serde(default)]
+    pub top_threats: Vec<ThreatCount>,
+    #[serde(default)]
+    pub latency_ms: Latency,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ThreatCount {
+    pub threat_type: String,
+    pub count: u64,
+}
+
+/// Proxy latency in milliseconds; fields are absent without traffic
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Latency {
+    #[serde(default)]
+    pub avg: Option<f64>,
+    #[serde(default)]
+    pub p50: Option<f64>,
+    #[serde(default)]
+    pub p95: Option<f64>,
+    #[serde(default)]
+    pub p99: Option<f64>,
+}
+
+impl UsageStats {
+    /// Share of requests blocked, from 0 to 1
+    #[allow(clippy::cast_precision_loss)]
+    pub fn block_rate(&self) -> f64 {
+        if self.total_requests == 0 {
+            0.0
+        } else {
+            self.blocked_requests as f64 / self.total_requests as f64
+        }
+    }
+}
+
+pub struct StatsCommand {
+    /// Start of the window, as for `logs --since`
+    pub since: String,
+    /// Number of threat types to list
+    pub top: usize,
+    pub json: bool,
+}
+
+/// Longest bar in the threat chart
+const BAR_WIDTH: usize = 24;
+
+/// `12345` as `12,345`
+pub fn thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn bar(count: u64, max: u64) -> String {
+    if max == 0 {
+        return String::new();
+    }
+    let width = usize::try_from(count.saturating_mul(BAR_WIDTH as u64) / max).unwrap_or(BAR_WIDTH);
+    "█".repeat(width.max(usize::from(count > 0)))
+}
+
+impl StatsCommand {
+    pub fn execute(&self) -> Result<()> {
+        let since = parse_since(&self.since, Utc::now())?;
+        let client = PromptGuardClient::new(resolve_api_key()?, Some(resolve_base_url()))?;
+
+        let mut endpoint = format!(
+            "/stats?since={}&top={}",
+            since.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            self.top
+        );
+        let project_id = ConfigManager::new(None)
+            .ok()
+            .filter(ConfigManager::exists)
+            .and_then(|m| m.load().ok())
+            .and_then(|c| c.project_id);
+        if let Some(ref project_id) = project_id {
+            let _ = write!(endpoint, "&project_id={project_id}");
+        }
+
+        let mut stats: UsageStats = client.get(&endpoint).map_err(|e| {
+            PromptGuardError::Api(format!(
+                "Failed to fetch usage stats: {e}. See https://app.promptguard.co/dashboard"
+            ))
+        })?;
+        stats.top_threats.truncate(self.top);
+
+        if self.json {
+            Output::json(serde_json::json!({
+                "since": since.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                "project_id": project_id,
+                "total_requests": stats.total_requests,
+                "blocked_requests": stats.blocked_requests,
+                "redacted_requests": stats.redacted_requests,
+                "block_rate": stats.block_rate(),
+                "top_threats": stats.top_threats,
+                "latency_ms": stats.latency_ms,
+            }))?;
+            return Ok(());
+        }
+
+        Output::header(&format!(
+            "Proxy Usage since {}",
+            since.format("%Y-%m-%d %H:%M UTC")
+        ));
+        let count_width = thousands(stats.total_requests).len();
+        Output::line(&format!(
+            "  Requests  {:>count_width$}",
+            thousands(stats.total_requests)
+        ));
+        Output::line(&format!(
+            "  Blocked   {:>count_width$}  ({:.1}%)",
+            thousands(stats.blocked_requests),
+            stats.block_rate() * 100.0
+        ));
+        Output::line(&format!(
+            "  Redacted  {:>count_width$}",
+            thousands(stats.redacted_requests)
+        ));
+        let latency: Vec<String> = [
+            ("avg", stats.latency_ms.avg),
+            ("p50", stats.latency_ms.p50),
+            ("p95", stats.latency_ms.p95),
+            ("p99", stats.latency_ms.p99),
+        ]
+        .into_iter()
+        .filter_map(|(name, ms)| ms.map(|ms| format!("{name} {ms:.0}ms")))
+        .collect();
+        if latency.is_empty() {
+            Output::line("  Latency   -");
+        } else {
+            Output::line(&format!("  Latency   {}", latency.join(" · ")));
+        }
+
+        Output::section("Top Threats", "🛡️");
+        if stats.top_threats.is_empty() {
+            Output::info("No threats in this window");
+            return Ok(());
+        }
+        let name_width = stats
+            .top_threats
+            .iter()
+            .map(|t| t.threat_type.chars().count())
+            .max()
+            .unwrap_or(0);
+        let max = stats.top_threats.iter().map(|t| t.count).max().unwrap_or(0);
+        let count_width = thousands(max).len();
+        for threat in &stats.top_threats {
+            Output::line(&format!(
+                "  {:<name_width$}  {:>count_width$}  {}",
+                threat.threat_type,
+                thousands(threat.count),
+                bar(threat.count, max)
+            ));
+        }
+        Ok(())
+    }
+}
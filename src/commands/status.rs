@@ -1,6 +1,12 @@
-use crate::config::ConfigManager;
+use crate::commands::coverage::call_sites;
+use crate::commands::{ShimCommand, VerifyCommand};
+use crate::config::{ConfigManager, EnvFileState};
 use crate::error::Result;
-use crate::output::Output;
+use crate::output::{Output, JSON_SCHEMA_VERSION};
+use crate::shim::ShimIntegrity;
+use crate::types::{Language, Provider};
+use std::collections::BTreeMap;
+use std::fs;
 
 pub struct StatusCommand {
     pub json: bool,
@@ -16,21 +22,72 @@ impl StatusCommand {
 
         if !config_manager.exists() {
             if self.json {
-                println!("{{\"initialized\": false, \"status\": \"not_initialized\"}}");
+                let output = serde_json::json!({
+                    "version": JSON_SCHEMA_VERSION,
+                    "initialized": false,
+                    "status": "not_initialized",
+                });
+                Output::json(output)?;
             } else {
-                println!("\nStatus: ⊘ Not initialized\n");
-                println!("To get started: promptguard init");
+                Output::line("\nStatus: ⊘ Not initialized\n");
+                Output::line("To get started: promptguard init");
             }
             return Ok(());
         }
 
         let config = config_manager.load()?;
 
+        let root_path = crate::config::project_root()?;
+        let generator = ShimCommand::generator(&root_path, &config);
+        let env_files = config.env_file.states(&root_path, &config.env_var_name);
+
+        // Shim files changed since `enable --runtime` generated them
+        let shim_problems = if config.runtime_mode && !config.metadata.shim_checksums.is_empty() {
+            Some(generator.verify(&config.metadata.shim_checksums))
+        } else {
+            None
+        };
+        let shim_version = if config.runtime_mode {
+            generator.installed_version()
+        } else {
+            None
+        };
+        // Entries in the shims' local log, and the most recent one
+        let shim_log = (config.runtime_mode && config.shim_log)
+            .then(|| fs::read_to_string(generator.shim_log_path()).unwrap_or_default())
+            .map(|log| {
+                let entries: Vec<&str> = log.lines().filter(|l| !l.trim().is_empty()).collect();
+                let last = entries
+                    .last()
+                    .and_then(|l| serde_json::from_str::<serde_json::Value>(l).ok());
+                (entries.len(), last)
+            });
+
         if self.json {
+            // Guarded and unguarded call sites per configured or detected provider
+            let (sites, _) = call_sites(&root_path, &config, false)?;
+            let mut coverage: BTreeMap<Provider, (usize, usize)> = config
+                .providers
+                .iter()
+                .filter_map(|p| Provider::parse(p))
+                .map(|p| (p, (0, 0)))
+                .collect();
+            for site in &sites {
+                let (guarded, unguarded) = coverage.entry(site.provider).or_default();
+                if site.guard.is_some() {
+                    *guarded += 1;
+                } else {
+                    *unguarded += 1;
+                }
+            }
+            let drift = VerifyCommand::check_transforms(&config, false)?;
+            let shims_loaded = ShimCommand::loaded_languages(&root_path, &config)?;
+
             let output = serde_json::json!({
+                "version": JSON_SCHEMA_VERSION,
                 "initialized": true,
                 "status": if config.metadata.last_applied.is_some() { "active" } else { "disabled" },
-                "api_key": Output::mask_api_key(&config.api_key),
+                "api_key": Output::mask_api_key(config.api_key()),
                 "proxy_url": config.proxy_url,
                 "configuration": {
                     "config_file": ".promptguard.json",
@@ -40,33 +97,131 @@ impl StatusCommand {
                     "providers": config.providers,
                     "backup_enabled": config.backup_enabled,
                     "env_file": config.env_file,
+                    "env_files": env_files.iter().map(|(file, state)| serde_json::json!({
+                        "path": file,
+                        "state": state.as_str(),
+                    })).collect::<Vec<_>>(),
                     "framework": config.framework,
                     "exclude_patterns": config.exclude_patterns,
                     "cli_version": config.metadata.cli_version,
                     "backups": config.metadata.backups,
-                }
+                },
+                "runtime_mode": config.runtime_mode,
+                "env_only": config.env_only,
+                "providers": coverage.iter().map(|(provider, (guarded, unguarded))| serde_json::json!({
+                    "provider": provider.as_str(),
+                    "guarded": guarded,
+                    "unguarded": unguarded,
+                })).collect::<Vec<_>>(),
+                "drift": drift.as_ref().map(|issues| serde_json::json!({
+                    "ok": issues.is_empty(),
+                    "issues": issues,
+                })),
+                "shim": {
+                    "installed": config.runtime_mode
+                        && (shim_version.is_some() || !config.metadata.shim_checksums.is_empty()),
+                    "loaded": shims_loaded.iter().map(Language::as_str).collect::<Vec<_>>(),
+                },
+                "shim_version": shim_version,
+                "shims_outdated": config.runtime_mode && generator.is_outdated(),
+                "shim_integrity": shim_problems.as_ref().map(|problems| serde_json::json!({
+                    "ok": problems.is_empty(),
+                    "modified": problems.iter().filter_map(|p| match p {
+                        ShimIntegrity::Modified(name) => Some(name),
+                        ShimIntegrity::Missing(_) => None,
+                    }).collect::<Vec<_>>(),
+                    "missing": problems.iter().filter_map(|p| match p {
+                        ShimIntegrity::Missing(name) => Some(name),
+                        ShimIntegrity::Modified(_) => None,
+                    }).collect::<Vec<_>>(),
+                })),
+                "shim_log": shim_log.as_ref().map(|(entries, last)| serde_json::json!({
+                    "path": ".promptguard/shim.log",
+                    "entries": entries,
+                    "last": last,
+                })),
             });
-            println!("{}", serde_json::to_string_pretty(&output)?);
+            Output::json(output)?;
         } else {
-            println!("\nStatus: ✓ Active");
-            println!(
-                "API Key: {} (configured)",
-                Output::mask_api_key(&config.api_key)
-            );
-            println!("Proxy URL: {}", config.proxy_url);
+            Output::line("\nStatus: ✓ Active");
+            if config.api_key().is_empty() {
+                Output::line(&format!(
+                    "API Key: not set (expected in {} or {})",
+                    config.env_var_name, config.env_file
+                ));
+            } else {
+                Output::line(&format!(
+                    "API Key: {} (configured)",
+                    Output::mask_api_key(config.api_key())
+                ));
+            }
+            Output::line(&format!("Proxy URL: {}", config.proxy_url));
 
-            println!("\nConfiguration:");
-            println!("  • Config file: .promptguard.json");
+            Output::line("\nConfiguration:");
+            Output::line("  • Config file: .promptguard.json");
             if let Some(last_applied) = config.metadata.last_applied {
-                println!(
+                Output::line(&format!(
                     "  • Last applied: {}",
                     last_applied.format("%Y-%m-%d %H:%M:%S")
-                );
+                ));
+            }
+            Output::line(&format!(
+                "  • Files managed: {}",
+                config.metadata.files_managed.len()
+            ));
+            Output::line(&format!("  • Providers: {}", config.providers.join(", ")));
+            // One line per env file once there are several to keep in sync
+            if env_files.len() > 1 {
+                Output::line("  • Env files:");
+                for (file, state) in &env_files {
+                    let state = match state {
+                        EnvFileState::InSync => format!("✓ {}", config.env_var_name),
+                        EnvFileState::OutOfSync => {
+                            format!("⚠ {} differs (run 'promptguard key')", config.env_var_name)
+                        },
+                        EnvFileState::NoKey => format!("⚠ no {}", config.env_var_name),
+                        EnvFileState::Missing => "⚠ not found".to_string(),
+                    };
+                    Output::line(&format!("      {file}: {state}"));
+                }
+            }
+            if config.runtime_mode && generator.is_outdated() {
+                Output::line(&format!(
+                    "  • Shim version: ⚠ {} (run 'promptguard shim upgrade')",
+                    shim_version
+                        .as_deref()
+                        .map_or_else(|| "outdated".to_string(), |v| format!("v{v}"))
+                ));
+            } else if let Some(version) = &shim_version {
+                Output::line(&format!("  • Shim version: v{version}"));
+            }
+            match &shim_problems {
+                Some(problems) if problems.is_empty() => {
+                    Output::line("  • Runtime shims: unmodified");
+                },
+                Some(problems) => {
+                    Output::line(&format!(
+                        "  • Runtime shims: ⚠ {} file(s) modified or missing (run 'promptguard doctor' to regenerate)",
+                        problems.len()
+                    ));
+                },
+                None => {},
+            }
+            if let Some((entries, last)) = &shim_log {
+                let last = last.as_ref().map_or_else(String::new, |entry| {
+                    format!(
+                        ", last: {} {} at {}",
+                        entry["provider"].as_str().unwrap_or("?"),
+                        entry["action"].as_str().unwrap_or("?"),
+                        entry["timestamp"].as_str().unwrap_or("?")
+                    )
+                });
+                Output::line(&format!(
+                    "  • Shim log: {entries} intercepted client(s){last}"
+                ));
             }
-            println!("  • Files managed: {}", config.metadata.files_managed.len());
-            println!("  • Providers: {}", config.providers.join(", "));
 
-            println!("\nView full dashboard: https://app.promptguard.co/dashboard");
+            Output::line("\nView full dashboard: https://app.promptguard.co/dashboard");
         }
 
         Ok(())
@@ -0,0 +1,156 @@
+use crate::backup::BackupManager;
+use crate::commands::{DisableCommand, RevertCommand, ShimCommand};
+use crate::config::ConfigManager;
+use crate::error::Result;
+use crate::output::Output;
+use crate::transformer::Journal;
+use std::fs;
+use std::path::PathBuf;
+
+/// Remove every trace of `PromptGuard` from a project: transformed code,
+/// runtime shims and their injections, env-only variables, the API key,
+/// `apply` backups, `.promptguard/` and the config
+pub struct UninstallCommand {
+    pub yes: bool,
+}
+
+impl UninstallCommand {
+    pub fn execute(&self) -> Result<()> {
+        Output::header("Uninstall PromptGuard");
+
+        let config_manager = ConfigManager::new(None)?;
+        if !config_manager.exists() {
+            Output::warning("No PromptGuard configuration found. Nothing to uninstall.");
+            return Ok(());
+        }
+
+        let config = config_manager.load()?;
+        let root_path = crate::config::project_root()?;
+        let journal = Journal::load(&root_path)?;
+        let state_dir = crate::state::project_state_dir(&root_path);
+
+        let backup_manager = BackupManager::new(Some(config.backup_extension.clone()));
+        let mut managed: Vec<PathBuf> = config
+            .metadata
+            .files_managed
+            .iter()
+            .cloned()
+            .chain(journal.files())
+            .map(|f| root_path.join(f))
+            .collect();
+        managed.sort();
+        managed.dedup();
+        let backups: Vec<PathBuf> = managed
+            .iter()
+            .map(|f| backup_manager.backup_path(f))
+            .filter(|b| b.is_file())
+            .collect();
+
+        Output::line("\nThis will:");
+        if !managed.is_empty() {
+            Output::line(&format!(
+                "  • Remove PromptGuard's edits from {} managed files",
+                managed.len()
+            ));
+        }
+        Output::line("  • Remove runtime shim imports and loaders, if any");
+        if !config.metadata.env_only_vars.is_empty() {
+            Output::line(&format!(
+                "  • Remove {} from {}",
+                config
+                    .metadata
+                    .env_only_vars
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                config.env_file.primary()
+            ));
+        }
+        Output::line(&format!(
+            "  • Remove {} from {}",
+            config.env_var_name, config.env_file
+        ));
+        if !backups.is_empty() {
+            Output::line(&format!("  • Delete {} backup files", backups.len()));
+        }
+        if state_dir.exists() {
+            Output::line("  • Delete .promptguard/ (journal, backups, shims, caches)");
+        }
+        Output::line("  • Delete .promptguard.json");
+        if root_path.join(".git").exists() {
+            Output::line("\nReview with 'git diff' afterwards; nothing is committed.");
+        }
+
+        if !self.yes && !Output::confirm("\nUninstall PromptGuard from this project?", false)? {
+            Output::info("Uninstall cancelled");
+            return Ok(());
+        }
+
+        Output::section("Restoring source files...", "📦");
+        let mut restored = RevertCommand::restore_sources(&config, &root_path)?.files;
+        RevertCommand::restore_from_backups(&config, &root_path, &mut restored);
+
+        Output::section("Removing runtime shims...", "🧹");
+        let removal = ShimCommand::remove(&root_path, &config, false)?;
+        removal.print(&root_path, config.env_file.primary());
+
+        Output::section("Cleaning environment...", "🌍");
+        DisableCommand::remove_env_only_vars(&config, &root_path)?;
+        let removed = config
+            .env_file
+            .remove_key(&root_path, &config.env_var_name)?;
+        if !removed.is_empty() {
+            Output::step(&format!(
+                "✓ Removed {} from {}",
+                config.env_var_name,
+                removed.join(", ")
+            ));
+        }
+        // Env files `init` created hold nothing else once the key is gone
+        for file in config.env_file.files() {
+            let env_path = root_path.join(file);
+            if fs::read_to_string(&env_path).is_ok_and(|c| c.trim().is_empty()) {
+                fs::remove_file(&env_path)?;
+                Output::step(&format!("✓ Deleted empty {file}"));
+            }
+        }
+
+        Output::section("Deleting PromptGuard files...", "🗑️");
+        for backup in &backups {
+            fs::remove_file(backup)?;
+            let rel_path = backup.strip_prefix(&root_path).unwrap_or(backup);
+            Output::step(&format!("✓ Deleted {}", rel_path.display()));
+        }
+        // Edits the journal couldn't undo keep their entries, listed above
+        let journal_path = Journal::path(&root_path);
+        let keep_journal = !Journal::load(&root_path)?.is_empty();
+        if state_dir.exists() {
+            if keep_journal {
+                for entry in fs::read_dir(&state_dir)?.filter_map(std::result::Result::ok) {
+                    let path = entry.path();
+                    if path == journal_path {
+                        continue;
+                    }
+                    if path.is_dir() {
+                        fs::remove_dir_all(&path)?;
+                    } else {
+                        fs::remove_file(&path)?;
+                    }
+                }
+                Output::warning("Kept .promptguard/journal.json for the files left unchanged");
+            } else {
+                fs::remove_dir_all(&state_dir)?;
+                Output::step("✓ Deleted .promptguard/");
+            }
+        }
+        config_manager.delete()?;
+        Output::step("✓ Deleted .promptguard.json");
+
+        Output::line("");
+        Output::success("PromptGuard has been removed from this project");
+        Output::line("  To remove the CLI itself, see the Uninstallation section of the README");
+
+        Ok(())
+    }
+}
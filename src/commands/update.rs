@@ -1,11 +1,24 @@
 use crate::error::{PromptGuardError, Result};
 use crate::output::Output;
+use crate::state::{self, PREVIOUS_BINARY_NAME};
+use aws_lc_rs::digest::{digest, SHA256};
 use reqwest::blocking::Client;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-const GITHUB_API_URL: &str =
-    "https://api.github.com/repos/acebot712/promptguard-cli/releases/latest";
+/// Releases API of the CLI repository (overridable with `PROMPTGUARD_UPDATE_URL`
+/// in debug builds only, for tests)
+const GITHUB_API_URL: &str = "https://api.github.com/repos/acebot712/promptguard-cli";
+
+/// Updater settings under `~/.promptguard`
+const UPDATE_STATE_FILE: &str = "update.json";
+
+const STABLE: &str = "stable";
+const BETA: &str = "beta";
 
 #[derive(Debug, Deserialize)]
 struct GitHubRelease {
@@ -13,79 +26,148 @@ struct GitHubRelease {
     html_url: String,
     #[serde(default)]
     body: Option<String>,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    assets: Vec<GitHubAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// What the updater remembers between runs
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct UpdateState {
+    /// Channel picked with `--channel`; `stable` when unset
+    #[serde(default)]
+    channel: Option<String>,
+    /// Version of the cached binary `--rollback` restores
+    #[serde(default)]
+    previous_version: Option<String>,
 }
 
-pub struct UpdateCommand;
+impl UpdateState {
+    fn path() -> Result<PathBuf> {
+        Ok(state::global_state_dir()?.join(UPDATE_STATE_FILE))
+    }
 
-impl Default for UpdateCommand {
-    fn default() -> Self {
-        Self
+    fn load() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&state::read_state(&path)?)?)
     }
+
+    fn save(&self) -> Result<()> {
+        state::write_state(&Self::path()?, &serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[derive(Default)]
+pub struct UpdateCommand {
+    /// `stable` or `beta`; remembered for later runs
+    pub channel: Option<String>,
+    /// Restore the binary that the last update replaced
+    pub rollback: bool,
 }
 
 impl UpdateCommand {
     pub fn execute(&self) -> Result<()> {
+        if self.rollback {
+            return Self::rollback();
+        }
+
         Output::header("Update PromptGuard CLI");
 
+        let mut update_state = UpdateState::load()?;
+        if let Some(ref channel) = self.channel {
+            update_state.channel = Some(channel.clone());
+            update_state.save()?;
+        }
+        let channel = update_state.channel.as_deref().unwrap_or(STABLE);
+
         let current_version = env!("CARGO_PKG_VERSION");
-        println!("\nCurrent version: v{current_version}");
+        Output::line(&format!("\nCurrent version: v{current_version}"));
+        Output::line(&format!("Channel: {channel}"));
 
         Output::info("Checking for updates...");
 
-        // Check GitHub releases for the latest version
-        match self.check_latest_version() {
+        // Check GitHub releases for the latest version on the channel
+        match self.check_latest_version(channel) {
             Ok(release) => {
                 let latest_version = release.tag_name.trim_start_matches('v');
 
                 if self.is_newer_version(current_version, latest_version) {
-                    println!();
+                    Output::line("");
                     Output::success(&format!("New version available: v{latest_version}"));
-                    println!();
+                    Output::line("");
 
                     if let Some(ref body) = release.body {
-                        println!("What's new:");
+                        Output::line("What's new:");
                         // Print first few lines of release notes
                         for line in body.lines().take(5) {
-                            println!("  {line}");
+                            Output::line(&format!("  {line}"));
                         }
-                        println!();
+                        Output::line("");
+                    }
+
+                    if Self::self_update_blocker().is_none()
+                        && Output::can_prompt()
+                        && Output::confirm(&format!("Install v{latest_version} now?"), false)?
+                    {
+                        Self::install(&release, current_version, &mut update_state)?;
+                        Output::success(&format!(
+                            "Updated to v{latest_version}. Run 'promptguard update --rollback' to go back to v{current_version}"
+                        ));
+                        return Ok(());
                     }
 
-                    println!("To update, run one of the following:");
-                    println!();
+                    Output::line("To update, run one of the following:");
+                    Output::line("");
                     self.print_update_instructions();
 
-                    println!("Release notes: {}", release.html_url);
+                    Output::line(&format!("Release notes: {}", release.html_url));
                 } else {
-                    println!();
+                    Output::line("");
                     Output::success("You are running the latest version!");
                 }
             },
             Err(e) => {
                 Output::warning(&format!("Could not check for updates: {e}"));
-                println!();
-                println!("You can manually check for updates at:");
-                println!("  https://github.com/acebot712/promptguard-cli/releases");
-                println!();
+                Output::line("");
+                Output::line("You can manually check for updates at:");
+                Output::line("  https://github.com/acebot712/promptguard-cli/releases");
+                Output::line("");
                 self.print_update_instructions();
             },
         }
 
-        println!("\nDocumentation:");
-        println!("  https://docs.promptguard.co");
+        Output::line("\nDocumentation:");
+        Output::line("  https://docs.promptguard.co");
 
         Ok(())
     }
 
-    fn check_latest_version(&self) -> Result<GitHubRelease> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(10))
-            .user_agent(format!("promptguard-cli/{}", env!("CARGO_PKG_VERSION")))
-            .build()
-            .map_err(|e| PromptGuardError::Api(format!("Failed to create HTTP client: {e}")))?;
+    /// Newest release on `channel`: the latest stable release, or for `beta`
+    /// the newest release including pre-releases
+    fn check_latest_version(&self, channel: &str) -> Result<GitHubRelease> {
+        let client = crate::api::blocking_client(Duration::from_secs(10))?;
+        let api_url = std::env::var("PROMPTGUARD_UPDATE_URL")
+            .ok()
+            .filter(|_| cfg!(debug_assertions))
+            .unwrap_or_else(|| GITHUB_API_URL.to_string());
+        let endpoint = if channel == BETA {
+            format!("{}/releases?per_page=20", api_url.trim_end_matches('/'))
+        } else {
+            format!("{}/releases/latest", api_url.trim_end_matches('/'))
+        };
 
         let response = client
-            .get(GITHUB_API_URL)
+            .get(endpoint)
             .header("Accept", "application/vnd.github.v3+json")
             .send()
             .map_err(|e| PromptGuardError::Api(format!("Failed to check for updates: {e}")))?;
@@ -97,41 +179,295 @@ impl UpdateCommand {
             )));
         }
 
-        response
+        if channel != BETA {
+            return response.json().map_err(|e| {
+                PromptGuardError::Api(format!("Failed to parse GitHub response: {e}"))
+            });
+        }
+
+        let releases: Vec<GitHubRelease> = response
             .json()
-            .map_err(|e| PromptGuardError::Api(format!("Failed to parse GitHub response: {e}")))
+            .map_err(|e| PromptGuardError::Api(format!("Failed to parse GitHub response: {e}")))?;
+        releases
+            .into_iter()
+            .filter(|r| !r.draft)
+            .reduce(|newest, r| {
+                if self.is_newer_version(
+                    newest.tag_name.trim_start_matches('v'),
+                    r.tag_name.trim_start_matches('v'),
+                ) {
+                    r
+                } else {
+                    newest
+                }
+            })
+            .ok_or_else(|| PromptGuardError::Api("No releases published yet".to_string()))
     }
 
+    /// Compare `major.minor.patch[-pre]` versions; a pre-release sorts before
+    /// its release
     fn is_newer_version(&self, current: &str, latest: &str) -> bool {
-        let parse_version =
-            |v: &str| -> Vec<u32> { v.split('.').filter_map(|part| part.parse().ok()).collect() };
+        let split = |v: &str| -> (Vec<u32>, Option<String>) {
+            let (numbers, pre) = match v.split_once('-') {
+                Some((numbers, pre)) => (numbers.to_string(), Some(pre.to_string())),
+                None => (v.to_string(), None),
+            };
+            let numbers = numbers
+                .split('.')
+                .filter_map(|part| part.parse().ok())
+                .collect();
+            (numbers, pre)
+        };
 
-        let current_parts = parse_version(current);
-        let latest_parts = parse_version(latest);
+        let (current_parts, current_pre) = split(current);
+        let (latest_parts, latest_pre) = split(latest);
 
         for i in 0..3 {
             let current_num = current_parts.get(i).copied().unwrap_or(0);
             let latest_num = latest_parts.get(i).copied().unwrap_or(0);
 
             match latest_num.cmp(&current_num) {
-                std::cmp::Ordering::Greater => return true,
-                std::cmp::Ordering::Less => return false,
-                std::cmp::Ordering::Equal => {},
+                Ordering::Greater => return true,
+                Ordering::Less => return false,
+                Ordering::Equal => {},
             }
         }
 
-        false
+        match (current_pre, latest_pre) {
+            (Some(_), None) => true,
+            (Some(current), Some(latest)) => {
+                Self::compare_prerelease(&latest, &current) == Ordering::Greater
+            },
+            _ => false,
+        }
+    }
+
+    /// Semver pre-release order: dot-separated identifiers, numeric ones
+    /// compared as numbers
+    fn compare_prerelease(left: &str, right: &str) -> Ordering {
+        let mut left_ids = left.split('.');
+        let mut right_ids = right.split('.');
+        loop {
+            let ordering = match (left_ids.next(), right_ids.next()) {
+                (None, None) => return Ordering::Equal,
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+                (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => x.cmp(y),
+                },
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+    }
+
+    /// Why this binary can't replace itself, if it can't: package managers
+    /// own their installs, and releases only ship macOS and Linux builds
+    fn self_update_blocker() -> Option<&'static str> {
+        let exe = std::env::current_exe().ok()?;
+        let path = exe.to_string_lossy();
+        if path.contains("/Cellar/") || path.contains("/homebrew/") {
+            Some("installed with Homebrew")
+        } else if path.contains(".cargo/bin") {
+            Some("installed with cargo")
+        } else if Self::asset_name().is_none() {
+            Some("no prebuilt binary for this platform")
+        } else {
+            None
+        }
+    }
+
+    /// Release asset for this platform, named as in `install.sh`
+    fn asset_name() -> Option<String> {
+        let os = match std::env::consts::OS {
+            "macos" => "macos",
+            "linux" => "linux",
+            _ => return None,
+        };
+        let arch = match std::env::consts::ARCH {
+            "x86_64" => "x86_64",
+            "aarch64" => "arm64",
+            _ => return None,
+        };
+        Some(format!("promptguard-{os}-{arch}"))
+    }
+
+    fn download(client: &Client, url: &str) -> Result<Vec<u8>> {
+        let response = client
+            .get(url)
+            .send()
+            .map_err(|e| PromptGuardError::Api(format!("Failed to download {url}: {e}")))?;
+        if !response.status().is_success() {
+            return Err(PromptGuardError::Api(format!(
+                "Download of {url} returned status {}",
+                response.status()
+            )));
+        }
+        Ok(response
+            .bytes()
+            .map_err(|e| PromptGuardError::Api(format!("Failed to download {url}: {e}")))?
+            .to_vec())
+    }
+
+    /// Download the release binary, verify its checksum, keep the running
+    /// binary for `--rollback` and swap the new one in
+    fn install(
+        release: &GitHubRelease,
+        current_version: &str,
+        update_state: &mut UpdateState,
+    ) -> Result<()> {
+        let name = Self::asset_name().ok_or_else(|| {
+            PromptGuardError::Custom("No prebuilt binary for this platform".to_string())
+        })?;
+        let asset = |name: &str| release.assets.iter().find(|a| a.name == name);
+        let binary = asset(&name).ok_or_else(|| {
+            PromptGuardError::Api(format!("Release {} has no {name} binary", release.tag_name))
+        })?;
+        // Never install a binary that can't be verified
+        let checksum = asset(&format!("{name}.sha256")).ok_or_else(|| {
+            PromptGuardError::Api(format!(
+                "Release {} has no {name}.sha256 checksum; refusing to install an unverified binary",
+                release.tag_name
+            ))
+        })?;
+
+        let client = crate::api::blocking_client(Duration::from_mins(2))?;
+        Output::info(&format!("Downloading {name}..."));
+        let data = Self::download(&client, &binary.browser_download_url)?;
+        let expected =
+            String::from_utf8_lossy(&Self::download(&client, &checksum.browser_download_url)?)
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_lowercase();
+        let actual = digest(&SHA256, &data)
+            .as_ref()
+            .iter()
+            .fold(String::new(), |mut hex, b| {
+                let _ = write!(hex, "{b:02x}");
+                hex
+            });
+        if expected != actual {
+            return Err(PromptGuardError::Custom(format!(
+                "Checksum mismatch for {name}: expected {expected}, got {actual}"
+            )));
+        }
+
+        let exe = std::env::current_exe()?;
+        let previous = state::global_state_dir()?.join(PREVIOUS_BINARY_NAME);
+        if let Some(parent) = previous.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&exe, &previous)?;
+        Self::replace_binary(&exe, &data)?;
+
+        update_state.previous_version = Some(current_version.to_string());
+        update_state.save()
+    }
+
+    /// Swap the running binary with the cached one; the binary it replaces
+    /// is cached in turn, so a second rollback undoes the first
+    fn rollback() -> Result<()> {
+        Output::header("Roll Back PromptGuard CLI");
+
+        let mut update_state = UpdateState::load()?;
+        let previous = state::global_state_dir()?.join(PREVIOUS_BINARY_NAME);
+        let Some(previous_version) = update_state
+            .previous_version
+            .clone()
+            .filter(|_| previous.is_file())
+        else {
+            return Err(PromptGuardError::Config(
+                "No previous version to roll back to. 'promptguard update' keeps one after installing an update"
+                    .to_string(),
+            ));
+        };
+
+        let current_version = env!("CARGO_PKG_VERSION");
+        Output::line(&format!("\nCurrent version: v{current_version}"));
+        Output::line(&format!("Previous version: v{previous_version}"));
+
+        let exe = std::env::current_exe()?;
+        let data = fs::read(&previous)?;
+        fs::copy(&exe, &previous)?;
+        Self::replace_binary(&exe, &data)?;
+
+        update_state.previous_version = Some(current_version.to_string());
+        update_state.save()?;
+        Output::success(&format!("Rolled back to v{previous_version}"));
+        Ok(())
+    }
+
+    /// Write `data` next to `target` and rename it over, so the binary is
+    /// never left half-written
+    fn replace_binary(target: &Path, data: &[u8]) -> Result<()> {
+        let staged = target.with_file_name(".promptguard.new");
+        let write = || -> std::io::Result<()> {
+            fs::write(&staged, data)?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+            }
+            fs::rename(&staged, target)
+        };
+        write().map_err(|e| {
+            let _ = fs::remove_file(&staged);
+            if e.kind() == std::io::ErrorKind::PermissionDenied {
+                PromptGuardError::Custom(format!(
+                    "Cannot replace {}: permission denied. Re-run with sudo",
+                    target.display()
+                ))
+            } else {
+                PromptGuardError::Io(e)
+            }
+        })
     }
 
     fn print_update_instructions(&self) {
-        println!("  • Using curl (recommended):");
-        println!("      curl -fsSL https://raw.githubusercontent.com/acebot712/promptguard-cli/main/install.sh | sh");
-        println!();
-        println!("  • Using Homebrew:");
-        println!("      brew upgrade promptguard");
-        println!();
-        println!("  • Using cargo:");
-        println!("      cargo install --force promptguard-cli");
-        println!();
+        if let Some(reason) = Self::self_update_blocker() {
+            Output::line(&format!("  (This binary can't update itself: {reason}.)"));
+            Output::line("");
+        }
+        Output::line("  • Using curl (recommended):");
+        Output::line("      curl -fsSL https://raw.githubusercontent.com/acebot712/promptguard-cli/main/install.sh | sh");
+        Output::line("");
+        Output::line("  • Using Homebrew:");
+        Output::line("      brew upgrade promptguard");
+        Output::line("");
+        Output::line("  • Using cargo:");
+        Output::line("      cargo install --force promptguard-cli");
+        Output::line("");
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_refuses_release_without_checksum() {
+        let Some(name) = UpdateCommand::asset_name() else {
+            return;
+        };
+        let release = GitHubRelease {
+            tag_name: "v99.0.0".to_string(),
+            html_url: "https://example.com/v99.0.0".to_string(),
+            body: None,
+            draft: false,
+            assets: vec![GitHubAsset {
+                name,
+                browser_download_url: "http://127.0.0.1:9/binary".to_string(),
+            }],
+        };
+        let err = UpdateCommand::install(&release, "1.0.0", &mut UpdateState::default())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("refusing to install"), "{err}");
     }
 }
@@ -1,12 +1,90 @@
 use crate::api::PromptGuardClient;
 use crate::auth::{load_credentials, resolve_api_key, resolve_base_url};
+use crate::commands::stats::thousands;
+use crate::config::ConfigManager;
 use crate::error::Result;
 use crate::output::Output;
+use serde::{Deserialize, Serialize};
+
+/// Who the credentials belong to, from `/auth/whoami`
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Identity {
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub organization: Option<Named>,
+    /// Project the key is scoped to, if any
+    #[serde(default)]
+    pub project: Option<Named>,
+    /// `test` or `prod`
+    #[serde(default)]
+    pub key_type: Option<String>,
+    #[serde(default)]
+    pub plan: Option<Plan>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Named {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+impl Named {
+    /// `Acme (org_123)`, or just the id
+    fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{name} ({})", self.id),
+            None => self.id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Plan {
+    pub name: String,
+    #[serde(default)]
+    pub requests_used: Option<u64>,
+    #[serde(default)]
+    pub requests_limit: Option<u64>,
+}
 
 pub struct WhoamiCommand {
     pub json: bool,
 }
 
+/// Key type from the key's prefix; anything else is a `login` token
+fn key_type(api_key: &str) -> &'static str {
+    if api_key.starts_with("pg_sk_test_") {
+        "test"
+    } else if api_key.starts_with("pg_sk_prod_") {
+        "prod"
+    } else {
+        "login token"
+    }
+}
+
+/// Where `resolve_api_key` found the key, checked in the same order
+fn key_source() -> &'static str {
+    if std::env::var("PROMPTGUARD_API_KEY").is_ok_and(|k| !k.is_empty()) {
+        "environment variable (PROMPTGUARD_API_KEY)"
+    } else if ConfigManager::new(None)
+        .ok()
+        .and_then(|m| m.load().ok())
+        .is_some_and(|c| !c.api_key().is_empty())
+    {
+        "project config (.promptguard.json)"
+    } else if load_credentials()
+        .ok()
+        .flatten()
+        .is_some_and(|c| c.secret().is_some())
+    {
+        "global credentials (~/.promptguard/credentials.json)"
+    } else {
+        "user config (~/.config/promptguard/config.json)"
+    }
+}
+
 impl WhoamiCommand {
     pub fn execute(&self) -> Result<()> {
         let api_key = if let Ok(key) = resolve_api_key() {
@@ -17,10 +95,7 @@ impl WhoamiCommand {
                     "authenticated": false,
                     "error": "Not logged in"
                 });
-                println!(
-                    "{}",
-                    serde_json::to_string_pretty(&result).unwrap_or_default()
-                );
+                Output::json(result)?;
             } else {
                 Output::error("Not logged in. Run 'promptguard login' to authenticate.");
             }
@@ -29,56 +104,118 @@ impl WhoamiCommand {
 
         let base_url = resolve_base_url();
         let masked_key = Output::mask_api_key(&api_key);
-
-        // Determine the source of the key
-        let source = if std::env::var("PROMPTGUARD_API_KEY").is_ok() {
-            "environment variable (PROMPTGUARD_API_KEY)"
-        } else if crate::config::ConfigManager::new(None)
+        let source = key_source();
+        let credentials = load_credentials().ok().flatten();
+        let active_project = credentials.as_ref().and_then(|c| c.active_project.clone());
+        let token_expires_at = credentials
+            .as_ref()
+            .filter(|c| c.api_key.is_empty())
+            .and_then(|c| c.token_expires_at);
+        let linked_project = ConfigManager::new(None)
             .ok()
+            .filter(ConfigManager::exists)
             .and_then(|m| m.load().ok())
-            .is_some()
-        {
-            "project config (.promptguard.json)"
-        } else {
-            "global credentials (~/.promptguard/credentials.json)"
-        };
+            .and_then(|c| c.project_id);
 
-        let active_project = load_credentials()
-            .ok()
-            .flatten()
-            .and_then(|c| c.active_project);
+        let client = PromptGuardClient::new(api_key.clone(), Some(base_url.clone()))?;
+        let (identity, identity_error) = match client.get::<Identity>("/auth/whoami") {
+            Ok(identity) => (Some(identity), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let connected = identity.is_some() || client.health_check().is_ok();
+        let key_type = identity
+            .as_ref()
+            .and_then(|i| i.key_type.clone())
+            .unwrap_or_else(|| key_type(&api_key).to_string());
 
-        // Check API connectivity
-        let client = PromptGuardClient::new(api_key, Some(base_url.clone()))?;
-        let connected = client.health_check().is_ok();
+        // The usual "wrong key in this repo" mistake
+        let mut warnings = Vec::new();
+        if let (Some(linked), Some(project)) = (
+            &linked_project,
+            identity.as_ref().and_then(|i| i.project.as_ref()),
+        ) {
+            if *linked != project.id {
+                warnings.push(format!(
+                    "This key belongs to project {}, but .promptguard.json is linked to {linked}",
+                    project.label()
+                ));
+            }
+        }
 
         if self.json {
             let result = serde_json::json!({
                 "authenticated": true,
                 "api_key": masked_key,
+                "key_type": key_type,
                 "source": source,
                 "base_url": base_url,
                 "active_project": active_project,
+                "linked_project": linked_project,
+                "token_expires_at": token_expires_at,
+                "identity": identity,
+                "identity_error": identity_error,
                 "api_reachable": connected,
+                "warnings": warnings,
             });
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&result).unwrap_or_default()
-            );
-        } else {
-            Output::header("PromptGuard Identity");
-            Output::step(&format!("API Key: {masked_key}"));
-            Output::step(&format!("Source: {source}"));
-            Output::step(&format!("API: {base_url}"));
-            if let Some(ref proj) = active_project {
-                Output::step(&format!("Active Project: {proj}"));
+            Output::json(result)?;
+            return Ok(());
+        }
+
+        Output::header("PromptGuard Identity");
+        if let Some(ref identity) = identity {
+            if let Some(ref email) = identity.email {
+                Output::step(&format!("Account: {email}"));
+            }
+            if let Some(ref org) = identity.organization {
+                Output::step(&format!("Organization: {}", org.label()));
             }
+            if let Some(ref project) = identity.project {
+                Output::step(&format!("Key Project: {}", project.label()));
+            }
+        }
+        Output::step(&format!("API Key: {masked_key} ({key_type})"));
+        Output::step(&format!("Source: {source}"));
+        if let Some(expires_at) = token_expires_at {
+            Output::step(&format!(
+                "Login Expires: {}",
+                expires_at.format("%Y-%m-%d %H:%M UTC")
+            ));
+        }
+        if let Some(ref proj) = active_project {
+            Output::step(&format!("Active Project: {proj}"));
+        }
+        if let Some(ref proj) = linked_project {
+            Output::step(&format!("Linked Project: {proj} (.promptguard.json)"));
+        }
+        if let Some(plan) = identity.as_ref().and_then(|i| i.plan.as_ref()) {
+            let usage = match (plan.requests_used, plan.requests_limit) {
+                (Some(used), Some(limit)) => {
+                    format!(
+                        " — {} / {} requests this period",
+                        thousands(used),
+                        thousands(limit)
+                    )
+                },
+                (Some(used), None) => format!(" — {} requests this period", thousands(used)),
+                _ => String::new(),
+            };
+            Output::step(&format!("Plan: {}{usage}", plan.name));
+        }
+        Output::step(&format!("API: {base_url}"));
+
+        for warning in &warnings {
+            Output::warning(warning);
+        }
+        if let Some(ref e) = identity_error {
             if connected {
-                Output::success("API is reachable");
-            } else {
-                Output::warning("API is unreachable — check your network or key");
+                Output::warning(&format!("Could not fetch account details: {e}"));
             }
         }
+        if connected {
+            Output::success("API is reachable");
+        } else {
+            Output::warning("API is unreachable — check your network or key");
+        }
 
         Ok(())
     }
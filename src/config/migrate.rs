@@ -0,0 +1,258 @@
+/// Config version migrations
+///
+/// Every change to the layout of `.promptguard.json` bumps `CURRENT_VERSION`
+/// and adds a step to `MIGRATIONS` that upgrades the previous version's JSON.
+/// Loading runs the steps from the file's version up to the current one in
+/// memory; the file is rewritten the next time the config is saved. A config
+/// written by a newer CLI is refused instead of being rewritten in the older
+/// layout.
+use super::EnvFile;
+use crate::env::EnvManager;
+use crate::error::{PromptGuardError, Result};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Version written to every config this CLI saves
+pub const CURRENT_VERSION: &str = "1.1";
+
+/// Rewrites a config's JSON in place, without touching any file
+type Step = fn(&mut Map<String, Value>) -> Result<()>;
+
+/// One upgrade step, from one config version to the next
+pub struct Migration {
+    pub from: &'static str,
+    pub to: &'static str,
+    /// What the step changes, shown when it runs
+    pub summary: &'static str,
+    apply: Step,
+}
+
+const MOVE_API_KEY: &str = "the API key moves from the config to the env file";
+
+/// Every step, oldest first; each `to` is the next step's `from`
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: "1.0",
+    to: "1.1",
+    summary: MOVE_API_KEY,
+    apply: check_api_key,
+}];
+
+/// A config upgraded in memory
+#[derive(Debug, Default)]
+pub struct Upgrade {
+    /// Summaries of the steps that ran
+    pub applied: Vec<&'static str>,
+    /// `api_key` taken out of the config, for `move_api_key` on save
+    pub api_key: Option<String>,
+}
+
+/// Upgrade `config` to `CURRENT_VERSION` in memory
+pub fn upgrade(config: &mut Value) -> Result<Upgrade> {
+    let Some(map) = config.as_object_mut() else {
+        return Ok(Upgrade::default());
+    };
+    let mut version = map
+        .get("version")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+
+    check_version(&version)?;
+
+    let mut applied = Vec::new();
+    while let Some(step) = MIGRATIONS.iter().find(|m| m.from == version) {
+        (step.apply)(map)?;
+        version = step.to.to_string();
+        map.insert("version".to_string(), Value::String(version.clone()));
+        applied.push(step.summary);
+    }
+
+    // Hand-edited configs of the current version can still hold a key
+    check_api_key(map)?;
+    let api_key = map
+        .remove("api_key")
+        .and_then(|k| k.as_str().map(str::to_string))
+        .filter(|k| !k.is_empty());
+    if api_key.is_some() && !applied.contains(&MOVE_API_KEY) {
+        applied.push(MOVE_API_KEY);
+    }
+    Ok(Upgrade { applied, api_key })
+}
+
+/// Fail unless this CLI can read config `version`: the current one, or one
+/// it has migrations from. Newer versions are never rewritten.
+pub fn check_version(version: &str) -> Result<()> {
+    if is_newer(version, CURRENT_VERSION) {
+        return Err(PromptGuardError::Config(format!(
+            "Config version '{version}' was written by a newer promptguard; this one supports \
+             up to '{CURRENT_VERSION}'. Upgrade promptguard (promptguard update) instead of \
+             letting an older version rewrite it."
+        )));
+    }
+    if version != CURRENT_VERSION && !MIGRATIONS.iter().any(|m| m.from == version) {
+        let known: Vec<&str> = MIGRATIONS
+            .iter()
+            .map(|m| m.from)
+            .chain([CURRENT_VERSION])
+            .collect();
+        return Err(PromptGuardError::Config(format!(
+            "Unsupported config version '{version}'. Supported versions: {}. \
+             Please run 'promptguard init' to create a new configuration.",
+            known.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Whether version `a` is later than `b`, comparing dot-separated numbers
+fn is_newer(a: &str, b: &str) -> bool {
+    let parse = |v: &str| -> Option<Vec<u64>> { v.split('.').map(|p| p.parse().ok()).collect() };
+    match (parse(a), parse(b)) {
+        (Some(a), Some(b)) => a > b,
+        _ => false,
+    }
+}
+
+/// 1.0 → 1.1: the API key moves to the env files. `upgrade` takes it out of
+/// the config; this checks there is somewhere valid to put it.
+fn check_api_key(map: &mut Map<String, Value>) -> Result<()> {
+    if map
+        .get("api_key")
+        .and_then(Value::as_str)
+        .is_none_or(str::is_empty)
+    {
+        return Ok(());
+    }
+    let env_file: EnvFile = map
+        .get("env_file")
+        .and_then(|f| serde_json::from_value(f.clone()).ok())
+        .unwrap_or_default();
+    env_file.validate("in config")?;
+    let var = map
+        .get("env_var_name")
+        .and_then(Value::as_str)
+        .unwrap_or("PROMPTGUARD_API_KEY")
+        .to_string();
+    let valid_var = var.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && var.chars().next().is_some_and(|c| !c.is_ascii_digit());
+    if !valid_var {
+        return Err(PromptGuardError::Config(format!(
+            "Invalid env_var_name in config: '{var}' is not an environment variable name"
+        )));
+    }
+    Ok(())
+}
+
+/// Put a key `upgrade` took out of a config in `root` into each env file
+/// that doesn't already set `var`
+pub fn move_api_key(key: &str, env_file: &EnvFile, var: &str, root: &Path) -> Result<()> {
+    for file in env_file.files() {
+        let env_path = root.join(file);
+        if EnvManager::get_key(&env_path, var).is_none() {
+            EnvManager::add_or_update_key(&env_path, var, key)?;
+        }
+    }
+    Ok(())
+}
+
+/// Print a config message on stderr, once per process; `--quiet` drops it
+pub(crate) fn notice(message: &str) {
+    static SHOWN: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+    if !crate::logging::enabled(crate::logging::Level::Info) {
+        return;
+    }
+    if let Ok(mut shown) = SHOWN.lock() {
+        if shown.insert(message.to_string()) {
+            eprintln!("Warning: {message}");
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_upgrade_runs_steps_and_refuses_newer() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let mut config = serde_json::json!({
+            "version": "1.0",
+            "api_key": "pg_sk_test_migrate123456789012345678",
+            "env_var_name": "PG_TEST_MIGRATE_KEY",
+        });
+        let upgraded = upgrade(&mut config).unwrap();
+        assert_eq!(upgraded.applied, [MIGRATIONS[0].summary]);
+        assert_eq!(
+            upgraded.api_key.as_deref(),
+            Some("pg_sk_test_migrate123456789012345678")
+        );
+        assert_eq!(config["version"], CURRENT_VERSION);
+        assert!(config.get("api_key").is_none());
+        // Upgrading only rewrites the JSON in memory
+        assert!(!root.join(".env").exists());
+        assert!(upgrade(&mut config).unwrap().applied.is_empty());
+
+        for version in ["1.2", "2.0", "10.0"] {
+            let mut newer = serde_json::json!({ "version": version });
+            let err = upgrade(&mut newer).unwrap_err().to_string();
+            assert!(err.contains("newer promptguard"), "{err}");
+        }
+        let mut unknown = serde_json::json!({ "version": "0.9" });
+        assert!(upgrade(&mut unknown)
+            .unwrap_err()
+            .to_string()
+            .contains("Unsupported config version"));
+
+        let mut escaping = serde_json::json!({
+            "version": "1.0",
+            "api_key": "pg_sk_test_migrate123456789012345678",
+            "env_file": "../outside.env",
+        });
+        assert!(upgrade(&mut escaping).is_err());
+        let mut bad_var = serde_json::json!({
+            "version": "1.0",
+            "api_key": "pg_sk_test_migrate123456789012345678",
+            "env_var_name": "123",
+        });
+        assert!(upgrade(&mut bad_var).is_err());
+    }
+
+    #[test]
+    fn test_move_api_key_keeps_existing_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join(".env.local"),
+            "PG_TEST_MOVE_KEY=pg_sk_prod_kept\n",
+        )
+        .unwrap();
+        let env_file: EnvFile =
+            serde_json::from_value(serde_json::json!([".env", ".env.local"])).unwrap();
+        move_api_key("pg_sk_test_moved", &env_file, "PG_TEST_MOVE_KEY", root).unwrap();
+        assert_eq!(
+            EnvManager::get_key(&root.join(".env"), "PG_TEST_MOVE_KEY").as_deref(),
+            Some("pg_sk_test_moved")
+        );
+        assert_eq!(
+            EnvManager::get_key(&root.join(".env.local"), "PG_TEST_MOVE_KEY").as_deref(),
+            Some("pg_sk_prod_kept")
+        );
+    }
+
+    #[test]
+    fn test_migrations_chain_to_current() {
+        for pair in MIGRATIONS.windows(2) {
+            assert_eq!(pair[0].to, pair[1].from);
+        }
+        assert_eq!(MIGRATIONS.last().map(|m| m.to), Some(CURRENT_VERSION));
+        for migration in MIGRATIONS {
+            assert!(is_newer(migration.to, migration.from));
+        }
+    }
+}
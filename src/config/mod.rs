@@ -1,8 +1,17 @@
+pub mod migrate;
+pub mod schema;
+pub mod user;
+
+use crate::env::EnvManager;
 use crate::error::{PromptGuardError, Result};
+use crate::types::Provider;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigMetadata {
@@ -13,6 +22,17 @@ pub struct ConfigMetadata {
     pub files_managed: Vec<String>,
     #[serde(default)]
     pub backups: Vec<String>,
+    /// SHA-256 of each generated runtime shim file, keyed by file name in `.promptguard/`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub shim_checksums: BTreeMap<String, String>,
+    /// Base URL variables `enable --env-only` set in the env file, with their values
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env_only_vars: BTreeMap<String, String>,
+    /// Backend id of the key in the env files, set by `key --rotate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_rotated_at: Option<DateTime<Utc>>,
 }
 
 impl Default for ConfigMetadata {
@@ -22,40 +42,454 @@ impl Default for ConfigMetadata {
             cli_version: env!("CARGO_PKG_VERSION").to_string(),
             files_managed: Vec::new(),
             backups: Vec::new(),
+            shim_checksums: BTreeMap::new(),
+            env_only_vars: BTreeMap::new(),
+            key_id: None,
+            key_rotated_at: None,
+        }
+    }
+}
+
+pub const DEFAULT_PROXY_URL: &str = "https://api.promptguard.co/api/v1";
+
+/// Where SDK traffic is routed: one URL for every provider, or a map keyed
+/// by provider (`"openai"`, `"anthropic"`, ...) with an optional `"default"`
+/// entry for providers that aren't listed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ProxyUrl {
+    Single(String),
+    PerProvider(BTreeMap<String, String>),
+}
+
+impl ProxyUrl {
+    pub const DEFAULT_KEY: &'static str = "default";
+
+    /// URL for providers without their own entry, also used for API calls
+    pub fn default_url(&self) -> &str {
+        match self {
+            Self::Single(url) => url,
+            Self::PerProvider(urls) => urls
+                .get(Self::DEFAULT_KEY)
+                .map_or(DEFAULT_PROXY_URL, String::as_str),
+        }
+    }
+
+    /// URL that `provider`'s SDK traffic should go through
+    pub fn for_provider(&self, provider: Provider) -> &str {
+        match self {
+            Self::Single(url) => url,
+            Self::PerProvider(urls) => urls
+                .get(provider.as_str())
+                .map_or_else(|| self.default_url(), String::as_str),
+        }
+    }
+
+    /// Every configured URL, including the default
+    pub fn urls(&self) -> Vec<&str> {
+        let mut urls = vec![self.default_url()];
+        if let Self::PerProvider(map) = self {
+            urls.extend(map.values().map(String::as_str));
+        }
+        urls.sort_unstable();
+        urls.dedup();
+        urls
+    }
+
+    /// Provider overrides by display name, for the runtime shims
+    pub fn overrides(&self, providers: &[Provider]) -> BTreeMap<&'static str, &str> {
+        match self {
+            Self::Single(_) => BTreeMap::new(),
+            Self::PerProvider(urls) => providers
+                .iter()
+                .filter_map(|p| Some((p.display_name(), urls.get(p.as_str())?.as_str())))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        if let Self::PerProvider(urls) = self {
+            if let Some(key) = urls
+                .keys()
+                .find(|k| *k != Self::DEFAULT_KEY && Provider::parse(k).is_none())
+            {
+                return Err(PromptGuardError::Config(format!(
+                    "Unknown provider '{key}' in proxy_url"
+                )));
+            }
+        }
+
+        // Security: Validate proxy URLs are HTTPS (unless localhost for development)
+        for url in self.urls() {
+            if !url.starts_with("https://")
+                && !url.starts_with("http://localhost")
+                && !url.starts_with("http://127.0.0.1")
+            {
+                return Err(PromptGuardError::Config(
+                    "Invalid proxy_url: must use HTTPS (or localhost for development)".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<String> for ProxyUrl {
+    fn from(url: String) -> Self {
+        Self::Single(url)
+    }
+}
+
+impl fmt::Display for ProxyUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Single(url) => f.write_str(url),
+            Self::PerProvider(urls) => {
+                let entries: Vec<String> = urls.iter().map(|(k, v)| format!("{k}={v}")).collect();
+                f.write_str(&entries.join(", "))
+            },
+        }
+    }
+}
+
+pub const DEFAULT_ENV_FILE: &str = ".env";
+
+/// Env file(s) holding the API key: one path, or a list (`.env`,
+/// `.env.local`, `.env.production`) that key updates keep in sync. The key is
+/// read from the first file that sets it, and the other variables the CLI
+/// manages go to the first file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EnvFile {
+    Single(String),
+    Many(Vec<String>),
+}
+
+impl EnvFile {
+    /// Every file, in order
+    pub fn files(&self) -> &[String] {
+        match self {
+            Self::Single(file) => std::slice::from_ref(file),
+            Self::Many(files) => files,
+        }
+    }
+
+    /// The first file, where base URL and `NODE_OPTIONS` variables are written
+    pub fn primary(&self) -> &str {
+        self.files()
+            .first()
+            .map_or(DEFAULT_ENV_FILE, String::as_str)
+    }
+
+    /// Value of `var` in the first file that sets it
+    pub fn get_key(&self, root: &Path, var: &str) -> Option<String> {
+        self.files()
+            .iter()
+            .find_map(|file| EnvManager::get_key(&root.join(file), var).filter(|v| !v.is_empty()))
+    }
+
+    /// Set `var` in every file, creating the ones that don't exist. Nothing
+    /// is written if a production key would land in a committed file.
+    pub fn set_key(&self, root: &Path, var: &str, value: &str) -> Result<()> {
+        for file in self.files() {
+            EnvManager::check_key_destination(&root.join(file), value)?;
+        }
+        for file in self.files() {
+            EnvManager::add_or_update_key(&root.join(file), var, value)?;
+        }
+        Ok(())
+    }
+
+    /// Remove `var` from every file, returning the files that had it
+    pub fn remove_key(&self, root: &Path, var: &str) -> Result<Vec<&str>> {
+        let mut removed = Vec::new();
+        for file in self.files() {
+            if EnvManager::remove_key(&root.join(file), var)? {
+                removed.push(file.as_str());
+            }
+        }
+        Ok(removed)
+    }
+
+    /// How each file stands on `var`, compared with the value `get_key` reads
+    pub fn states(&self, root: &Path, var: &str) -> Vec<(&str, EnvFileState)> {
+        let current = self.get_key(root, var);
+        self.files()
+            .iter()
+            .map(|file| {
+                let path = root.join(file);
+                let state = if !path.exists() {
+                    EnvFileState::Missing
+                } else if !EnvManager::has_key(&path, var) {
+                    EnvFileState::NoKey
+                } else {
+                    match EnvManager::get_key(&path, var).filter(|v| !v.is_empty()) {
+                        None => EnvFileState::NoKey,
+                        Some(value) if Some(&value) == current.as_ref() => EnvFileState::InSync,
+                        Some(_) => EnvFileState::OutOfSync,
+                    }
+                };
+                (file.as_str(), state)
+            })
+            .collect()
+    }
+
+    /// `context` says where the setting is, e.g. "in config"
+    pub(crate) fn validate(&self, context: &str) -> Result<()> {
+        if self.files().is_empty() {
+            return Err(PromptGuardError::Config(format!(
+                "Invalid env_file {context}: the list names no files"
+            )));
+        }
+        // Security: Validate paths don't escape project directory
+        if self
+            .files()
+            .iter()
+            .any(|file| file.is_empty() || file.contains("..") || file.starts_with('/'))
+        {
+            return Err(PromptGuardError::Config(format!(
+                "Invalid env_file {context}: must be relative path within project"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// One env file's copy of the API key variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvFileState {
+    /// The file doesn't exist
+    Missing,
+    /// The file doesn't set the variable
+    NoKey,
+    /// The file sets the value in use
+    InSync,
+    /// The file sets a different value than the one in use
+    OutOfSync,
+}
+
+impl EnvFileState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Missing => "missing",
+            Self::NoKey => "no_key",
+            Self::InSync => "in_sync",
+            Self::OutOfSync => "out_of_sync",
+        }
+    }
+}
+
+impl Default for EnvFile {
+    fn default() -> Self {
+        Self::Single(DEFAULT_ENV_FILE.to_string())
+    }
+}
+
+impl From<String> for EnvFile {
+    fn from(file: String) -> Self {
+        Self::Single(file)
+    }
+}
+
+impl fmt::Display for EnvFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.files().join(", "))
+    }
+}
+
+/// What runtime shims do when the proxy can't be reached
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShimFailMode {
+    /// Keep routing through the proxy, so SDK calls raise connection errors
+    #[default]
+    Closed,
+    /// Leave clients on the provider's own endpoint while the proxy is down
+    Open,
+}
+
+impl ShimFailMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "closed" => Some(Self::Closed),
+            "open" => Some(Self::Open),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Closed => "closed",
+            Self::Open => "open",
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct PromptGuardConfig {
     pub version: String,
-    pub api_key: String,
+    /// Resolved on first use by `api_key()` (see `ApiKeySource`) and never
+    /// written back, so the config file is safe to commit
+    #[serde(skip)]
+    api_key: OnceLock<String>,
+    /// `api_key` of a config from an older version, moved to the env files
+    /// when the config is saved
+    #[serde(skip)]
+    legacy_api_key: Option<String>,
+    /// Directory the config was loaded from, where its env files are
+    #[serde(skip)]
+    root: PathBuf,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub project_id: Option<String>,
-    pub proxy_url: String,
+    pub proxy_url: ProxyUrl,
     pub providers: Vec<String>,
     #[serde(default = "default_exclude_patterns")]
     pub exclude_patterns: Vec<String>,
+    /// Snapshot files into `.promptguard/backups/` before `apply` changes them
     #[serde(default = "default_true")]
     pub backup_enabled: bool,
+    /// Extension of legacy backups older versions wrote next to each file
     #[serde(default = "default_backup_extension")]
     pub backup_extension: String,
-    #[serde(default = "default_env_file")]
-    pub env_file: String,
+    #[serde(default)]
+    pub env_file: EnvFile,
     #[serde(default = "default_env_var_name")]
     pub env_var_name: String,
+    /// API key variable per provider, for providers that shouldn't use `env_var_name`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env_var_overrides: BTreeMap<String, String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub framework: Option<String>,
     #[serde(default = "default_true")]
     pub enabled: bool,
     #[serde(default)]
     pub runtime_mode: bool,
+    /// Routing relies on the SDKs' base URL environment variables alone
+    #[serde(default)]
+    pub env_only: bool,
+    #[serde(default)]
+    pub shim_fail_mode: ShimFailMode,
+    /// Runtime shims log intercepted clients to `.promptguard/shim.log`
+    #[serde(default)]
+    pub shim_log: bool,
+    /// Named sets of overrides (`dev`, `staging`, `prod`), picked with `--profile`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ConfigProfile>,
+    /// How the CLI talks to the `PromptGuard` API
+    #[serde(default, skip_serializing_if = "ApiSettings::is_default")]
+    pub api: ApiSettings,
     #[serde(default)]
     pub metadata: ConfigMetadata,
+    /// Settings this version doesn't know, kept so saving doesn't drop them
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+    /// Profile applied when the config loaded
+    #[serde(skip)]
+    pub profile: Option<String>,
+    /// The settings the applied profile replaced, restored when saving
+    #[serde(skip)]
+    shadowed: ConfigProfile,
+    /// Settings overridden from the environment, by variable: the key, the
+    /// value before and the value from the variable. Never saved.
+    #[serde(skip)]
+    env_overrides: BTreeMap<String, EnvOverride>,
+}
+
+#[derive(Debug, Clone)]
+struct EnvOverride {
+    key: &'static str,
+    original: Option<serde_json::Value>,
+    value: Option<serde_json::Value>,
+}
+
+/// Settings that can't be overridden from the environment
+const NO_ENV_OVERRIDE_KEYS: &[&str] = &["version", "profiles", "metadata"];
+
+/// Variable overriding `key` (`PROMPTGUARD_PROXY_URL` for `proxy_url`)
+pub fn env_override_var(key: &str) -> String {
+    format!("PROMPTGUARD_{}", key.to_uppercase())
+}
+
+/// Settings a profile overrides; unset ones keep the top-level value
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigProfile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_url: Option<ProxyUrl>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_file: Option<EnvFile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_var_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_var_overrides: Option<BTreeMap<String, String>>,
+}
+
+/// API client settings (`api.max_retries`, `api.ca_bundle`, ...); unset ones
+/// keep the client's defaults
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiSettings {
+    /// Retries of a request that failed with a transient error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+    /// Seconds before a single request attempt is abandoned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// PEM file of extra trusted CAs, e.g. a corporate proxy's, relative to
+    /// the project root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<String>,
+    /// Skip TLS certificate verification, for lab environments only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_insecure: Option<bool>,
+}
+
+impl ApiSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
+impl ConfigProfile {
+    /// Exchange every setting this profile has with the config's own
+    fn swap(&mut self, config: &mut PromptGuardConfig) {
+        if let Some(proxy_url) = &mut self.proxy_url {
+            std::mem::swap(proxy_url, &mut config.proxy_url);
+        }
+        if let Some(env_file) = &mut self.env_file {
+            std::mem::swap(env_file, &mut config.env_file);
+        }
+        if let Some(env_var_name) = &mut self.env_var_name {
+            std::mem::swap(env_var_name, &mut config.env_var_name);
+        }
+        if let Some(overrides) = &mut self.env_var_overrides {
+            std::mem::swap(overrides, &mut config.env_var_overrides);
+        }
+    }
+
+    fn validate(&self, name: &str) -> Result<()> {
+        if let Some(env_file) = &self.env_file {
+            env_file.validate(&format!("in profile '{name}'"))?;
+        }
+        if let Some(proxy_url) = &self.proxy_url {
+            proxy_url.validate()?;
+        }
+        if let Some(key) = self
+            .env_var_overrides
+            .iter()
+            .flat_map(BTreeMap::keys)
+            .find(|k| Provider::parse(k).is_none())
+        {
+            return Err(PromptGuardError::Config(format!(
+                "Unknown provider '{key}' in env_var_overrides of profile '{name}'"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Built-in exclude patterns plus the user config's
 pub fn default_exclude_patterns() -> Vec<String> {
-    vec![
+    let mut patterns = vec![
         "**/*.test.js".to_string(),
         "**/*.test.ts".to_string(),
         "**/*.spec.js".to_string(),
@@ -65,7 +499,13 @@ pub fn default_exclude_patterns() -> Vec<String> {
         "**/__tests__/**".to_string(),
         "**/.venv/**".to_string(),
         "**/venv/**".to_string(),
-    ]
+    ];
+    for pattern in user::UserConfig::load_or_default().exclude_patterns {
+        if !patterns.contains(&pattern) {
+            patterns.push(pattern);
+        }
+    }
+    patterns
 }
 
 fn default_true() -> bool {
@@ -76,10 +516,6 @@ fn default_backup_extension() -> String {
     ".bak".to_string()
 }
 
-fn default_env_file() -> String {
-    ".env".to_string()
-}
-
 fn default_env_var_name() -> String {
     "PROMPTGUARD_API_KEY".to_string()
 }
@@ -92,22 +528,528 @@ impl PromptGuardConfig {
         }
 
         Ok(Self {
-            version: "1.0".to_string(),
-            api_key,
+            version: migrate::CURRENT_VERSION.to_string(),
+            api_key: OnceLock::from(api_key),
+            legacy_api_key: None,
+            root: PathBuf::new(),
             project_id: None,
-            proxy_url,
+            proxy_url: ProxyUrl::Single(proxy_url),
             providers,
             exclude_patterns: default_exclude_patterns(),
             backup_enabled: true,
             backup_extension: ".bak".to_string(),
-            env_file: ".env".to_string(),
+            env_file: EnvFile::default(),
             env_var_name: "PROMPTGUARD_API_KEY".to_string(),
+            env_var_overrides: BTreeMap::new(),
             framework: None,
             enabled: true,
             runtime_mode: false,
+            env_only: false,
+            shim_fail_mode: ShimFailMode::default(),
+            shim_log: false,
+            profiles: BTreeMap::new(),
+            api: ApiSettings::default(),
             metadata: ConfigMetadata::default(),
+            extra: BTreeMap::new(),
+            profile: None,
+            shadowed: ConfigProfile::default(),
+            env_overrides: BTreeMap::new(),
+        })
+    }
+
+    /// The project's API key, empty when none is set. Looked up on the first
+    /// call, since the keychain lookup runs a program.
+    pub fn api_key(&self) -> &str {
+        self.api_key.get_or_init(|| {
+            find_api_key(&self.root, self)
+                .map(|(key, _)| key)
+                .unwrap_or_default()
+        })
+    }
+
+    /// Environment variable `provider`'s client reads its API key from
+    pub fn env_var_for(&self, provider: Provider) -> &str {
+        self.env_var_overrides
+            .get(provider.as_str())
+            .unwrap_or(&self.env_var_name)
+    }
+
+    /// Check the settings that deserializing alone doesn't
+    pub fn validate(&self) -> Result<()> {
+        self.env_file.validate("in config")?;
+        self.proxy_url.validate()?;
+
+        if let Some(key) = self
+            .env_var_overrides
+            .keys()
+            .find(|k| Provider::parse(k).is_none())
+        {
+            return Err(PromptGuardError::Config(format!(
+                "Unknown provider '{key}' in env_var_overrides"
+            )));
+        }
+
+        for (name, profile) in &self.profiles {
+            profile.validate(name)?;
+        }
+
+        Ok(())
+    }
+
+    /// Overlay the profile `name` on the top-level settings
+    fn apply_profile(&mut self, name: &str) -> Result<()> {
+        let Some(profile) = self.profiles.get(name) else {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            return Err(PromptGuardError::Config(if known.is_empty() {
+                format!("Unknown profile '{name}': the config defines no profiles")
+            } else {
+                format!("Unknown profile '{name}'. Profiles: {}", known.join(", "))
+            }));
+        };
+        let mut shadowed = profile.clone();
+        shadowed.swap(self);
+        self.shadowed = shadowed;
+        self.profile = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Override settings from `PROMPTGUARD_<SETTING>` variables looked up
+    /// with `var` (`PROMPTGUARD_EXCLUDE` also sets `exclude_patterns`). Values
+    /// are read like `config set` reads them.
+    pub fn apply_env_overrides(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        for key in CONFIG_KEYS
+            .iter()
+            .copied()
+            .filter(|k| !NO_ENV_OVERRIDE_KEYS.contains(k))
+        {
+            let mut name = env_override_var(key);
+            let mut raw = var(&name);
+            if raw.is_none() && key == "exclude_patterns" {
+                name = "PROMPTGUARD_EXCLUDE".to_string();
+                raw = var(&name);
+            }
+            let Some(raw) = raw.filter(|r| !r.is_empty()) else {
+                continue;
+            };
+            let original = self.get_value(key)?;
+            self.set_value(key, &raw).map_err(|e| {
+                PromptGuardError::Config(match e {
+                    PromptGuardError::Config(message) => format!("{name}: {message}"),
+                    other => format!("{name}: {other}"),
+                })
+            })?;
+            let value = self.get_value(key)?;
+            self.env_overrides.insert(
+                name,
+                EnvOverride {
+                    key,
+                    original,
+                    value,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Variables overriding settings of this config
+    pub fn env_overrides(&self) -> impl Iterator<Item = &str> {
+        self.env_overrides.keys().map(String::as_str)
+    }
+
+    /// The config as it is stored: overrides from the environment undone,
+    /// unless the setting has been changed since, and top-level settings
+    /// without the applied profile, whose current values go back into its entry
+    fn stored(&self) -> Result<Self> {
+        let mut stored = self.clone();
+        if !self.env_overrides.is_empty() {
+            let mut value = self.to_value()?;
+            if let Some(object) = value.as_object_mut() {
+                for o in self.env_overrides.values() {
+                    if object.get(o.key) != o.value.as_ref() {
+                        continue;
+                    }
+                    match &o.original {
+                        Some(original) => object.insert(o.key.to_string(), original.clone()),
+                        None => object.remove(o.key),
+                    };
+                }
+            }
+            stored = serde_json::from_value(value).map_err(|e| {
+                PromptGuardError::Config(format!("Failed to serialize config: {e}"))
+            })?;
+            stored.profile.clone_from(&self.profile);
+            stored.shadowed = self.shadowed.clone();
+        }
+        if let Some(name) = &self.profile {
+            let mut current = self.shadowed.clone();
+            current.swap(&mut stored);
+            stored.profiles.insert(name.clone(), current);
+        }
+        Ok(stored)
+    }
+
+    /// Value of a setting by key, `None` when it isn't set. Map entries are
+    /// addressed with a dot (`proxy_url.anthropic`).
+    pub fn get_value(&self, key: &str) -> Result<Option<serde_json::Value>> {
+        let (field, entry) = Self::parse_key(key)?;
+        let value = self.to_value()?;
+        let Some(value) = value.get(field) else {
+            return Ok(None);
+        };
+        Ok(match entry {
+            None => Some(value.clone()),
+            Some(_) if field == "proxy_url" && value.is_string() => None,
+            Some(entry) => value.get(entry).cloned(),
+        })
+    }
+
+    /// Change a setting from its command-line form: JSON (`true`, `["openai"]`),
+    /// else a plain string, else a comma-separated list. The result must still
+    /// deserialize as a config and pass `validate`.
+    pub fn set_value(&mut self, key: &str, raw: &str) -> Result<()> {
+        let (field, entry) = Self::parse_key(key)?;
+        Self::check_writable(field)?;
+        // The first candidate of the right type is the one meant
+        let mut type_error = None;
+        for candidate in setting_candidates(raw, entry.is_none()) {
+            let mut value = self.to_value()?;
+            Self::insert(&mut value, field, entry, candidate);
+            match serde_json::from_value::<Self>(value) {
+                Ok(updated) => return self.replace_with(updated),
+                Err(e) => {
+                    type_error.get_or_insert(e);
+                },
+            }
+        }
+        Err(PromptGuardError::Config(match type_error {
+            Some(e) => format!("Invalid value for '{key}': {e}"),
+            None => format!("Invalid value for '{key}'"),
+        }))
+    }
+
+    /// Reset a setting to its default, or remove a map entry. Settings without
+    /// a default can't be unset.
+    pub fn unset_value(&mut self, key: &str) -> Result<()> {
+        let (field, entry) = Self::parse_key(key)?;
+        Self::check_writable(field)?;
+        let mut value = self.to_value()?;
+        if let Some(object) = value.as_object_mut() {
+            match entry {
+                None => {
+                    object.remove(field);
+                },
+                Some(entry) => {
+                    if let Some(map) = object.get_mut(field).and_then(|v| v.as_object_mut()) {
+                        map.remove(entry);
+                    }
+                },
+            }
+        }
+        let updated = serde_json::from_value::<Self>(value).map_err(|_| {
+            PromptGuardError::Config(format!("'{key}' has no default and can't be unset"))
+        })?;
+        self.replace_with(updated)
+    }
+
+    fn parse_key(key: &str) -> Result<(&str, Option<&str>)> {
+        let (field, entry) = match key.split_once('.') {
+            Some((field, entry)) => (field, Some(entry)),
+            None => (key, None),
+        };
+        if field == "api_key" {
+            return Err(PromptGuardError::Config(
+                "The API key isn't stored in the config; use 'promptguard key' or set it in the env file"
+                    .to_string(),
+            ));
+        }
+        if !CONFIG_KEYS.contains(&field) {
+            return Err(PromptGuardError::Config(format!(
+                "Unknown config key '{field}'. Keys: {}",
+                CONFIG_KEYS.join(", ")
+            )));
+        }
+        if entry.is_some() && !CONFIG_MAP_KEYS.contains(&field) {
+            return Err(PromptGuardError::Config(format!(
+                "'{field}' has no entries; use '{field}' itself"
+            )));
+        }
+        Ok((field, entry))
+    }
+
+    fn check_writable(field: &str) -> Result<()> {
+        if CONFIG_READ_ONLY_KEYS.contains(&field) {
+            return Err(PromptGuardError::Config(format!(
+                "'{field}' is managed by promptguard and can't be changed"
+            )));
+        }
+        Ok(())
+    }
+
+    /// The config as a template for other repositories: the saved settings
+    /// without this project's `metadata`, plus the API key when `secrets` is
+    /// set. `ConfigManager::import` reads it back.
+    pub fn export(&self, secrets: bool) -> Result<serde_json::Value> {
+        let mut value = self.stored()?.to_value()?;
+        if let Some(object) = value.as_object_mut() {
+            object.remove("metadata");
+            if secrets && !self.api_key().is_empty() {
+                object.insert("api_key".to_string(), self.api_key().into());
+            }
+        }
+        Ok(value)
+    }
+
+    fn to_value(&self) -> Result<serde_json::Value> {
+        serde_json::to_value(self)
+            .map_err(|e| PromptGuardError::Config(format!("Failed to serialize config: {e}")))
+    }
+
+    /// Put `value` at `field` or, for map settings, at `field.entry`. A single
+    /// `proxy_url` becomes the `default` of the map.
+    fn insert(
+        config: &mut serde_json::Value,
+        field: &str,
+        entry: Option<&str>,
+        value: serde_json::Value,
+    ) {
+        let Some(object) = config.as_object_mut() else {
+            return;
+        };
+        let Some(entry) = entry else {
+            object.insert(field.to_string(), value);
+            return;
+        };
+        let slot = object
+            .entry(field.to_string())
+            .or_insert_with(|| serde_json::json!({}));
+        if let serde_json::Value::String(url) = slot {
+            *slot = serde_json::json!({ ProxyUrl::DEFAULT_KEY: url.clone() });
+        }
+        if let Some(map) = slot.as_object_mut() {
+            map.insert(entry.to_string(), value);
+        }
+    }
+
+    /// Replace the settings with `updated` if it is valid
+    fn replace_with(&mut self, mut updated: Self) -> Result<()> {
+        updated.env_overrides = std::mem::take(&mut self.env_overrides);
+        if let Some(error) = schema::validate_config(&updated.stored()?.to_value()?)
+            .into_iter()
+            .next()
+        {
+            return Err(PromptGuardError::Config(error.to_string()));
+        }
+        updated.validate()?;
+        if let Some(provider) = updated
+            .providers
+            .iter()
+            .find(|p| Provider::parse(p).is_none())
+        {
+            return Err(PromptGuardError::Config(format!(
+                "Unknown provider '{provider}' in providers"
+            )));
+        }
+        updated.api_key = std::mem::take(&mut self.api_key);
+        updated.profile = self.profile.take();
+        updated.shadowed = std::mem::take(&mut self.shadowed);
+        *self = updated;
+        Ok(())
+    }
+}
+
+/// What a setting given on the command line may mean, most specific first:
+/// JSON, (for `lists`) a comma-separated list, the plain string, and (for
+/// `lists`) a list of just that string
+pub(crate) fn setting_candidates(raw: &str, lists: bool) -> Vec<serde_json::Value> {
+    let mut candidates = Vec::new();
+    if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(raw) {
+        candidates.push(parsed);
+    }
+    if lists && raw.contains(',') {
+        candidates.push(
+            raw.split(',')
+                .map(|item| serde_json::Value::String(item.trim().to_string()))
+                .collect(),
+        );
+    }
+    candidates.push(serde_json::Value::String(raw.to_string()));
+    if lists && !raw.contains(',') {
+        candidates.push(serde_json::json!([raw]));
+    }
+    candidates
+}
+
+/// Top-level settings `config get/set/unset` address
+pub const CONFIG_KEYS: &[&str] = &[
+    "version",
+    "project_id",
+    "proxy_url",
+    "providers",
+    "exclude_patterns",
+    "backup_enabled",
+    "backup_extension",
+    "env_file",
+    "env_var_name",
+    "env_var_overrides",
+    "framework",
+    "enabled",
+    "runtime_mode",
+    "env_only",
+    "shim_fail_mode",
+    "shim_log",
+    "profiles",
+    "api",
+    "metadata",
+];
+
+/// Settings keyed by provider or name, whose entries can be addressed one by one
+const CONFIG_MAP_KEYS: &[&str] = &[
+    "proxy_url",
+    "env_var_overrides",
+    "profiles",
+    "api",
+    "metadata",
+];
+
+/// Settings only promptguard itself writes
+const CONFIG_READ_ONLY_KEYS: &[&str] = &["version", "metadata"];
+
+/// Where the API key of a project was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiKeySource {
+    /// The process environment (`env_var_name`)
+    Environment,
+    /// `env_var_name` in the project's env file
+    EnvFile,
+    /// `api_key` left in `.promptguard.json` by an older version, until the
+    /// config is saved
+    Config,
+    /// The OS keychain: service `promptguard`, account `env_var_name`
+    Keychain,
+    /// `api_key` in the user config (`~/.config/promptguard/config.json`)
+    UserConfig,
+}
+
+impl ApiKeySource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Environment => "environment",
+            Self::EnvFile => "env file",
+            Self::Config => "config file",
+            Self::Keychain => "OS keychain",
+            Self::UserConfig => "user config",
+        }
+    }
+}
+
+/// Keychain service `PromptGuard` keys are stored under
+pub const KEYCHAIN_SERVICE: &str = "promptguard";
+
+/// Find the API key for the project in `root`: the environment, then a
+/// legacy `api_key` in its config or its env files, then the OS keychain,
+/// then the user config
+pub fn find_api_key(root: &Path, config: &PromptGuardConfig) -> Option<(String, ApiKeySource)> {
+    let var = &config.env_var_name;
+    if let Some(key) = std::env::var(var).ok().filter(|k| !k.is_empty()) {
+        return Some((key, ApiKeySource::Environment));
+    }
+    // Saving moves a legacy key to each env file without one, so until then
+    // it stands in for a key missing from the first
+    if let Some(key) = &config.legacy_api_key {
+        if EnvManager::get_key(&root.join(config.env_file.primary()), var).is_none() {
+            return Some((key.clone(), ApiKeySource::Config));
+        }
+    }
+    if let Some(key) = config.env_file.get_key(root, var) {
+        return Some((key, ApiKeySource::EnvFile));
+    }
+    keychain_lookup(var)
+        .map(|key| (key, ApiKeySource::Keychain))
+        .or_else(|| {
+            user::UserConfig::load_or_default()
+                .api_key
+                .map(|key| (key, ApiKeySource::UserConfig))
         })
+}
+
+/// Read `account` from the OS keychain with the platform's own tool
+/// (`security` on macOS, `secret-tool` from libsecret on Linux)
+fn keychain_lookup(account: &str) -> Option<String> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = std::process::Command::new("security");
+        command.args([
+            "find-generic-password",
+            "-s",
+            KEYCHAIN_SERVICE,
+            "-a",
+            account,
+            "-w",
+        ]);
+        command
+    } else if cfg!(target_os = "linux") {
+        let mut command = std::process::Command::new("secret-tool");
+        command.args(["lookup", "service", KEYCHAIN_SERVICE, "account", account]);
+        command
+    } else {
+        return None;
+    };
+    let output = command
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let key = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!key.is_empty()).then_some(key)
+}
+
+static PROJECT_ROOT: OnceLock<PathBuf> = OnceLock::new();
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Select the config profile for this process (the global `--profile` flag)
+pub fn set_profile(name: String) {
+    let _ = PROFILE.set(name);
+}
+
+/// Profile to apply when loading the config: `--profile`, else
+/// `PROMPTGUARD_PROFILE`
+pub fn active_profile() -> Option<String> {
+    PROFILE.get().cloned().or_else(|| {
+        std::env::var("PROMPTGUARD_PROFILE")
+            .ok()
+            .filter(|p| !p.is_empty())
+    })
+}
+
+/// Override the project root for this process (the global `--path` flag)
+pub fn set_project_root(path: PathBuf) {
+    let _ = PROJECT_ROOT.set(path);
+}
+
+/// Root directory of the project being operated on: the `--path`
+/// override if one was given, otherwise the nearest directory from the
+/// current one up that holds a `.promptguard.json`, otherwise the current
+/// directory
+pub fn project_root() -> Result<PathBuf> {
+    if let Some(path) = PROJECT_ROOT.get() {
+        return Ok(path.clone());
+    }
+    let cwd = std::env::current_dir()?;
+    Ok(find_project_root(&cwd).unwrap_or(cwd))
+}
+
+/// Walk up from `start` to the first directory with a `.promptguard.json`,
+/// like git and cargo do. The search stops at the enclosing git repository's
+/// root so a config outside the repository is never picked up.
+pub fn find_project_root(start: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        if dir.join(ConfigManager::DEFAULT_CONFIG_FILE).is_file() {
+            return Some(dir.to_path_buf());
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
     }
+    None
 }
 
 pub struct ConfigManager {
@@ -120,7 +1062,7 @@ impl ConfigManager {
     pub fn new(config_path: Option<PathBuf>) -> Result<Self> {
         let path = match config_path {
             Some(p) => p,
-            None => std::env::current_dir().map_or_else(
+            None => project_root().map_or_else(
                 |_| PathBuf::from(Self::DEFAULT_CONFIG_FILE),
                 |dir| dir.join(Self::DEFAULT_CONFIG_FILE),
             ),
@@ -129,50 +1071,140 @@ impl ConfigManager {
         Ok(Self { config_path: path })
     }
 
-    /// Supported config versions (for migration compatibility)
-    const SUPPORTED_VERSIONS: &'static [&'static str] = &["1.0"];
-
     pub fn load(&self) -> Result<PromptGuardConfig> {
+        self.load_profile(active_profile().as_deref())
+    }
+
+    /// Load the config with the settings of `profile`, if given, applied.
+    /// Configs of older versions are migrated in memory; loading never
+    /// writes, the file is upgraded when the config is next saved.
+    pub fn load_profile(&self, profile: Option<&str>) -> Result<PromptGuardConfig> {
         if !self.config_path.exists() {
             return Err(PromptGuardError::NotInitialized);
         }
 
-        let content = fs::read_to_string(&self.config_path)?;
-        let config: PromptGuardConfig = serde_json::from_str(&content)
-            .map_err(|e| PromptGuardError::Config(format!("Failed to parse config: {e}")))?;
+        let mut value = self.read_value()?;
+        let root = self.root();
+        let from = value
+            .get("version")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let upgrade = migrate::upgrade(&mut value)?;
+        let mut config = self.parse_value(value)?;
+        config.validate()?;
+        config.legacy_api_key = upgrade.api_key;
+        config.root = root.to_path_buf();
 
-        // Validate config version
-        if !Self::SUPPORTED_VERSIONS.contains(&config.version.as_str()) {
-            return Err(PromptGuardError::Config(format!(
-                "Unsupported config version '{}'. Supported versions: {}. \
-                 Please run 'promptguard init' to create a new configuration.",
-                config.version,
-                Self::SUPPORTED_VERSIONS.join(", ")
-            )));
+        for key in config.extra.keys().filter(|k| *k != "$schema") {
+            migrate::notice(&format!(
+                "Unknown setting '{key}' in {} is kept but ignored; check it for typos",
+                self.file_name()
+            ));
         }
-
-        // Security: Validate paths don't escape project directory
-        if config.env_file.contains("..") || config.env_file.starts_with('/') {
-            return Err(PromptGuardError::Config(
-                "Invalid env_file in config: must be relative path within project".to_string(),
+        if !upgrade.applied.is_empty() {
+            migrate::notice(&format!(
+                "{} is upgraded from config version {from} to {} the next time it is saved \
+                 (`promptguard config migrate` saves it now): {}",
+                self.file_name(),
+                migrate::CURRENT_VERSION,
+                upgrade.applied.join("; ")
             ));
         }
 
-        // Security: Validate proxy_url is a valid HTTPS URL (unless localhost for development)
-        if !config.proxy_url.starts_with("https://")
-            && !config.proxy_url.starts_with("http://localhost")
-            && !config.proxy_url.starts_with("http://127.0.0.1")
-        {
+        if let Some(name) = profile {
+            config.apply_profile(name)?;
+        }
+        config.apply_env_overrides(|name| std::env::var(name).ok())?;
+
+        Ok(config)
+    }
+
+    /// Replace this project's settings with an exported config's, keeping its
+    /// `metadata`. Older exports are migrated and an API key in the export
+    /// moves to the env files, as when loading.
+    pub fn import(&self, mut value: serde_json::Value) -> Result<PromptGuardConfig> {
+        let metadata = if self.exists() {
+            self.read_value()?.get("metadata").cloned()
+        } else {
+            None
+        };
+        let Some(object) = value.as_object_mut() else {
             return Err(PromptGuardError::Config(
-                "Invalid proxy_url: must use HTTPS (or localhost for development)".to_string(),
+                "An imported config must be a JSON object".to_string(),
             ));
+        };
+        object.remove("metadata");
+        if let Some(metadata) = metadata {
+            object.insert("metadata".to_string(), metadata);
         }
 
+        let upgrade = migrate::upgrade(&mut value)?;
+        let mut config = self.parse_value(value)?;
+        config.validate()?;
+        config.legacy_api_key = upgrade.api_key;
+        config.root = self.root().to_path_buf();
+        self.save(&config)?;
         Ok(config)
     }
 
+    /// Rewrite the config in the current layout now rather than on the next
+    /// save. Returns the version the file had and the steps that ran; when
+    /// none did, the file is left untouched.
+    pub fn migrate(&self) -> Result<(String, Vec<&'static str>)> {
+        let mut value = self.read_value()?;
+        let from = value
+            .get("version")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let upgrade = migrate::upgrade(&mut value)?;
+        if upgrade.applied.is_empty() {
+            return Ok((from, upgrade.applied));
+        }
+        let mut config = self.parse_value(value)?;
+        config.validate()?;
+        config.legacy_api_key = upgrade.api_key;
+        config.root = self.root().to_path_buf();
+        self.save(&config)?;
+        Ok((from, upgrade.applied))
+    }
+
+    fn read_value(&self) -> Result<serde_json::Value> {
+        let content = fs::read_to_string(&self.config_path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| PromptGuardError::Config(format!("Failed to parse config: {e}")))
+    }
+
+    /// Check a config's JSON against the schema and deserialize it
+    fn parse_value(&self, value: serde_json::Value) -> Result<PromptGuardConfig> {
+        let errors = schema::validate_config(&value);
+        if !errors.is_empty() {
+            let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            return Err(PromptGuardError::Config(format!(
+                "{} does not match the config schema:\n  {}",
+                self.file_name(),
+                errors.join("\n  ")
+            )));
+        }
+        serde_json::from_value(value)
+            .map_err(|e| PromptGuardError::Config(format!("Failed to parse config: {e}")))
+    }
+
+    fn file_name(&self) -> String {
+        self.config_path.file_name().map_or_else(
+            || self.config_path.display().to_string(),
+            |n| n.to_string_lossy().into_owned(),
+        )
+    }
+
+    /// Write the config, moving a legacy `api_key` to the env files first
     pub fn save(&self, config: &PromptGuardConfig) -> Result<()> {
-        let content = serde_json::to_string_pretty(&config)
+        let stored = config.stored()?;
+        if let Some(key) = &config.legacy_api_key {
+            migrate::move_api_key(key, &stored.env_file, &stored.env_var_name, self.root())?;
+        }
+        let content = serde_json::to_string_pretty(&stored)
             .map_err(|e| PromptGuardError::Config(format!("Failed to serialize config: {e}")))?;
 
         fs::write(&self.config_path, content)?;
@@ -194,4 +1226,8 @@ impl ConfigManager {
     pub fn config_path(&self) -> &Path {
         &self.config_path
     }
+
+    fn root(&self) -> &Path {
+        self.config_path.parent().unwrap_or_else(|| Path::new("."))
+    }
 }
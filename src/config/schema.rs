@@ -0,0 +1,349 @@
+/// JSON Schema for `.promptguard.json`
+///
+/// The schema ships inside the binary (`promptguard config schema` prints it
+/// for editors and CI) and every config is checked against it before it is
+/// deserialized, so a mistake is reported with the path of the setting at
+/// fault instead of a serde message. The validator covers the keywords the
+/// schema uses: `$ref` to `#/$defs/...`, `type`, `enum`, `pattern`,
+/// `minLength`, `minItems`, `properties`, `required`, `additionalProperties`,
+/// `propertyNames`, `items`, `oneOf` and `anyOf` (the schema's `oneOf` branches
+/// never overlap, so both accept the first branch that matches).
+use regex::Regex;
+use serde_json::Value;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// The embedded schema
+pub const CONFIG_SCHEMA: &str = include_str!("schema.json");
+
+/// A value that breaks the schema, located by a JSON path like `$.proxy_url.anthropic`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+fn schema() -> &'static Value {
+    static SCHEMA: OnceLock<Value> = OnceLock::new();
+    SCHEMA.get_or_init(|| serde_json::from_str(CONFIG_SCHEMA).unwrap_or(Value::Null))
+}
+
+/// Every way `config` breaks the config schema, ordered by path
+pub fn validate_config(config: &Value) -> Vec<SchemaError> {
+    let root = schema();
+    let mut errors = Vec::new();
+    Validator { root }.check(root, config, "$", &mut errors);
+    errors
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+}
+
+impl Validator<'_> {
+    fn check(&self, schema: &Value, value: &Value, path: &str, errors: &mut Vec<SchemaError>) {
+        let error = |errors: &mut Vec<SchemaError>, message: String| {
+            errors.push(SchemaError {
+                path: path.to_string(),
+                message,
+            });
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            match self.resolve(reference) {
+                Some(target) => self.check(target, value, path, errors),
+                None => error(errors, format!("unresolved schema reference {reference}")),
+            }
+        }
+
+        if let Some(types) = schema.get("type") {
+            let allowed: Vec<&str> = match types {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            if !allowed.iter().any(|t| type_matches(t, value)) {
+                error(
+                    errors,
+                    format!(
+                        "expected {}, found {}",
+                        allowed.join(" or "),
+                        describe(value)
+                    ),
+                );
+                return;
+            }
+        }
+
+        if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+            if !options.contains(value) {
+                let options: Vec<String> = options.iter().map(Value::to_string).collect();
+                error(
+                    errors,
+                    format!("{value} is not one of {}", options.join(", ")),
+                );
+            }
+        }
+
+        if let Value::String(s) = value {
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if (s.chars().count() as u64) < min {
+                    error(errors, "must not be empty".to_string());
+                }
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                if !Regex::new(pattern).is_ok_and(|re| re.is_match(s)) {
+                    error(errors, format!("{value} is not valid here"));
+                }
+            }
+        }
+
+        if let Value::Number(n) = value {
+            if let (Some(min), Some(n)) =
+                (schema.get("minimum").and_then(Value::as_f64), n.as_f64())
+            {
+                if n < min {
+                    error(errors, format!("must be at least {min}"));
+                }
+            }
+            if let (Some(max), Some(n)) =
+                (schema.get("maximum").and_then(Value::as_f64), n.as_f64())
+            {
+                if n > max {
+                    error(errors, format!("must be at most {max}"));
+                }
+            }
+        }
+
+        if let Value::Object(object) = value {
+            self.check_object(schema, object, path, errors);
+        }
+
+        if let Value::Array(items) = value {
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if (items.len() as u64) < min {
+                    error(errors, "must not be empty".to_string());
+                }
+            }
+        }
+
+        if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+            for (i, item) in items.iter().enumerate() {
+                self.check(item_schema, item, &format!("{path}[{i}]"), errors);
+            }
+        }
+
+        for keyword in ["oneOf", "anyOf"] {
+            if let Some(branches) = schema.get(keyword).and_then(Value::as_array) {
+                self.check_branches(branches, value, path, errors);
+            }
+        }
+    }
+
+    fn check_object(
+        &self,
+        schema: &Value,
+        object: &serde_json::Map<String, Value>,
+        path: &str,
+        errors: &mut Vec<SchemaError>,
+    ) {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(key) {
+                    errors.push(SchemaError {
+                        path: path.to_string(),
+                        message: format!("missing required setting '{key}'"),
+                    });
+                }
+            }
+        }
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (key, item) in object {
+            let item_path = format!("{path}.{key}");
+            if let Some(names) = schema.get("propertyNames") {
+                let mut name_errors = Vec::new();
+                self.check(
+                    names,
+                    &Value::String(key.clone()),
+                    &item_path,
+                    &mut name_errors,
+                );
+                if !name_errors.is_empty() {
+                    errors.push(SchemaError {
+                        path: item_path,
+                        message: format!("'{key}' is not a known name here"),
+                    });
+                    continue;
+                }
+            }
+            if let Some(property) = properties.and_then(|p| p.get(key)) {
+                self.check(property, item, &item_path, errors);
+                continue;
+            }
+            match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => errors.push(SchemaError {
+                    path: item_path,
+                    message: format!("unknown setting '{key}'"),
+                }),
+                Some(extra @ Value::Object(_)) => self.check(extra, item, &item_path, errors),
+                _ => {},
+            }
+        }
+    }
+
+    /// Accept the value if a branch does. Otherwise report the branch whose
+    /// type fits the value, or the types that would have.
+    fn check_branches(
+        &self,
+        branches: &[Value],
+        value: &Value,
+        path: &str,
+        errors: &mut Vec<SchemaError>,
+    ) {
+        let mut fitting = Vec::new();
+        for branch in branches {
+            let mut branch_errors = Vec::new();
+            self.check(branch, value, path, &mut branch_errors);
+            if branch_errors.is_empty() {
+                return;
+            }
+            if !self.type_mismatch(branch, value) {
+                fitting.push(branch_errors);
+            }
+        }
+        if fitting.len() == 1 {
+            errors.extend(fitting.remove(0));
+        } else {
+            let types: Vec<&str> = branches
+                .iter()
+                .filter_map(|b| self.branch_type(b))
+                .collect();
+            errors.push(SchemaError {
+                path: path.to_string(),
+                message: format!("expected {}, found {}", types.join(" or "), describe(value)),
+            });
+        }
+    }
+
+    fn branch_type<'s>(&'s self, branch: &'s Value) -> Option<&'s str> {
+        branch.get("type").and_then(Value::as_str).or_else(|| {
+            branch
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|r| self.resolve(r))
+                .and_then(|target| self.branch_type(target))
+        })
+    }
+
+    fn type_mismatch(&self, branch: &Value, value: &Value) -> bool {
+        self.branch_type(branch)
+            .is_some_and(|t| !type_matches(t, value))
+    }
+
+    fn resolve(&self, reference: &str) -> Option<&Value> {
+        let pointer = reference.strip_prefix('#')?;
+        self.root.pointer(pointer)
+    }
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => format!("boolean {b}"),
+        Value::Number(n) => format!("number {n}"),
+        Value::String(s) => format!("string \"{s}\""),
+        Value::Array(_) => "array".to_string(),
+        Value::Object(_) => "object".to_string(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::detector::registry::PROVIDERS;
+
+    fn errors(config: &Value) -> Vec<String> {
+        validate_config(config)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_schema_parses_and_knows_every_provider() {
+        let schema: Value = serde_json::from_str(CONFIG_SCHEMA).expect("Embedded schema is JSON");
+        let pattern = schema
+            .pointer("/$defs/provider/pattern")
+            .and_then(Value::as_str)
+            .unwrap();
+        let re = Regex::new(pattern).unwrap();
+        for info in PROVIDERS {
+            let name = info.provider.as_str();
+            assert!(re.is_match(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_errors_name_the_setting() {
+        let config = serde_json::json!({
+            "version": "1.0",
+            "proxy_url": { "default": "https://a.example", "antropic": "https://b.example" },
+            "providers": ["openai", "mistral"],
+            "shim_fail_mode": "sideways",
+            "runtime_mode": "yes",
+            "api": { "max_retries": 50, "timeout_secs": 0 },
+            "profiles": { "prod": { "proxy_url": "http://evil.example", "envfile": ".env" } }
+        });
+        assert_eq!(
+            errors(&config),
+            [
+                "$.api.max_retries: must be at most 10",
+                "$.api.timeout_secs: must be at least 1",
+                "$.profiles.prod.envfile: unknown setting 'envfile'",
+                "$.profiles.prod.proxy_url: \"http://evil.example\" is not valid here",
+                "$.providers[1]: \"mistral\" is not valid here",
+                "$.proxy_url.antropic: 'antropic' is not a known name here",
+                "$.runtime_mode: expected boolean, found string \"yes\"",
+                "$.shim_fail_mode: \"sideways\" is not one of \"closed\", \"open\"",
+            ]
+        );
+
+        let config = serde_json::json!({ "version": "1.0", "proxy_url": 3 });
+        assert_eq!(
+            errors(&config),
+            [
+                "$: missing required setting 'providers'",
+                "$.proxy_url: expected string or object, found number 3",
+            ]
+        );
+
+        let config = serde_json::json!({
+            "version": "1.0",
+            "proxy_url": "https://api.promptguard.co/api/v1",
+            "providers": ["OpenAI", "hf"],
+            "project_id": null,
+        });
+        assert!(errors(&config).is_empty());
+    }
+}
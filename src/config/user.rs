@@ -0,0 +1,168 @@
+/// User-level defaults shared by every project
+///
+/// `~/.config/promptguard/config.json` (or `$XDG_CONFIG_HOME/promptguard/`)
+/// holds the settings people would otherwise pass to each new repo: a default
+/// API key and base URL, extra exclude patterns and the telemetry preference.
+/// Anything a project's `.promptguard.json` or the command line sets wins.
+use super::{setting_candidates, ProxyUrl, DEFAULT_PROXY_URL};
+use crate::error::{PromptGuardError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Settings `config --global get/set/unset` address
+pub const USER_CONFIG_KEYS: &[&str] = &["api_key", "base_url", "exclude_patterns", "telemetry"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserConfig {
+    /// API key for projects that don't provide their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// Proxy URL `init` uses without `--base-url`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// Exclude patterns added to the built-in defaults of new configs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_patterns: Vec<String>,
+    /// Whether anonymous usage data may be sent; unset means no
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub telemetry: Option<bool>,
+}
+
+/// `$XDG_CONFIG_HOME/promptguard/config.json`, else `~/.config/promptguard/config.json`
+pub fn user_config_path() -> Result<PathBuf> {
+    let base = match std::env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home = std::env::var("HOME")
+                .or_else(|_| std::env::var("USERPROFILE"))
+                .map_err(|_| {
+                    PromptGuardError::Config("Cannot determine home directory".to_string())
+                })?;
+            PathBuf::from(home).join(".config")
+        },
+    };
+    Ok(base.join("promptguard").join("config.json"))
+}
+
+impl UserConfig {
+    /// Load the user config; a missing file is an empty config
+    pub fn load() -> Result<Self> {
+        let path = user_config_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        let config: Self = serde_json::from_str(&content).map_err(|e| {
+            PromptGuardError::Config(format!("Failed to parse {}: {e}", path.display()))
+        })?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// The user config, or an empty one when it can't be read
+    pub fn load_or_default() -> Self {
+        Self::load().unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = user_config_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| {
+            PromptGuardError::Config(format!("Failed to serialize user config: {e}"))
+        })?;
+        fs::write(&path, content)?;
+
+        // The file may hold an API key
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Some(key) = &self.api_key {
+            if !key.starts_with("pg_sk_test_") && !key.starts_with("pg_sk_prod_") {
+                return Err(PromptGuardError::InvalidApiKey);
+            }
+        }
+        if let Some(url) = &self.base_url {
+            ProxyUrl::Single(url.clone()).validate()?;
+        }
+        Ok(())
+    }
+
+    /// Telemetry is off unless opted into, and `DO_NOT_TRACK` always wins
+    pub fn telemetry_enabled(&self) -> bool {
+        self.telemetry == Some(true)
+            && std::env::var("DO_NOT_TRACK").map_or(true, |v| v.is_empty() || v == "0")
+    }
+
+    pub fn get_value(&self, key: &str) -> Result<Option<serde_json::Value>> {
+        Self::check_key(key)?;
+        let value = serde_json::to_value(self)
+            .map_err(|e| PromptGuardError::Config(format!("Failed to serialize config: {e}")))?;
+        Ok(value.get(key).cloned())
+    }
+
+    /// Change a setting from its command-line form, as `PromptGuardConfig::set_value`
+    pub fn set_value(&mut self, key: &str, raw: &str) -> Result<()> {
+        Self::check_key(key)?;
+        let mut type_error = None;
+        for candidate in setting_candidates(raw, true) {
+            let mut value = serde_json::to_value(&*self).map_err(|e| {
+                PromptGuardError::Config(format!("Failed to serialize config: {e}"))
+            })?;
+            if let Some(object) = value.as_object_mut() {
+                object.insert(key.to_string(), candidate);
+            }
+            match serde_json::from_value::<Self>(value) {
+                Ok(updated) => {
+                    updated.validate()?;
+                    *self = updated;
+                    return Ok(());
+                },
+                Err(e) => {
+                    type_error.get_or_insert(e);
+                },
+            }
+        }
+        Err(PromptGuardError::Config(match type_error {
+            Some(e) => format!("Invalid value for '{key}': {e}"),
+            None => format!("Invalid value for '{key}'"),
+        }))
+    }
+
+    pub fn unset_value(&mut self, key: &str) -> Result<()> {
+        Self::check_key(key)?;
+        match key {
+            "api_key" => self.api_key = None,
+            "base_url" => self.base_url = None,
+            "exclude_patterns" => self.exclude_patterns.clear(),
+            _ => self.telemetry = None,
+        }
+        Ok(())
+    }
+
+    fn check_key(key: &str) -> Result<()> {
+        if USER_CONFIG_KEYS.contains(&key) {
+            Ok(())
+        } else {
+            Err(PromptGuardError::Config(format!(
+                "Unknown user config key '{key}'. Keys: {}",
+                USER_CONFIG_KEYS.join(", ")
+            )))
+        }
+    }
+}
+
+/// Proxy URL for new projects: the user's default, else `PromptGuard`'s
+pub fn default_base_url() -> String {
+    UserConfig::load_or_default()
+        .base_url
+        .unwrap_or_else(|| DEFAULT_PROXY_URL.to_string())
+}
@@ -2,10 +2,14 @@
 ///
 /// This module eliminates the massive duplication between Python and TypeScript detectors
 /// by extracting common tree-sitter parsing and query logic.
+use super::hierarchy::SubclassQuery;
+use super::ignore::is_ignored;
 use crate::error::{PromptGuardError, Result};
+use crate::logging;
 use crate::types::{DetectionInstance, DetectionResult, Language, Provider};
 use std::fs;
 use std::path::Path;
+use std::time::Instant;
 use streaming_iterator::StreamingIterator;
 use tree_sitter::{Language as TSLanguage, Parser, Query, QueryCursor};
 
@@ -13,13 +17,15 @@ pub struct DetectorConfig {
     pub parser_language: TSLanguage,
     pub language: Language,
     pub capture_name: &'static str,
+    /// Extra query for constructors of local subclasses, built per file
+    pub subclass_query: SubclassQuery,
 }
 
 /// Generic tree-sitter based detection implementation.
 ///
 /// This function encapsulates the common pattern:
 /// 1. Parse source file with tree-sitter
-/// 2. Execute provider-specific query
+/// 2. Execute provider-specific query, plus one for local client subclasses
 /// 3. Extract detection instances from matches
 /// 4. Check for `base_url` configuration
 pub fn detect_in_file_generic(
@@ -40,13 +46,19 @@ pub fn detect_in_file_generic(
         PromptGuardError::Parse(format!("Failed to parse {} file", config.language.as_str()))
     })?;
 
-    let query = Query::new(&config.parser_language, query_str)
+    let query_str = match (config.subclass_query)(&source, tree.root_node(), provider) {
+        Some(subclasses) => format!("{query_str}\n{subclasses}"),
+        None => query_str.to_string(),
+    };
+    let query = Query::new(&config.parser_language, &query_str)
         .map_err(|e| PromptGuardError::Parse(format!("Query error: {e}")))?;
 
+    let started = Instant::now();
     let mut cursor = QueryCursor::new();
     let mut matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
 
     let mut instances = Vec::new();
+    let mut ignored = Vec::new();
 
     while let Some(match_) = matches.next() {
         for capture in match_.captures {
@@ -60,16 +72,28 @@ pub fn detect_in_file_generic(
                     .find(|c| query.capture_names()[c.index as usize] == "args")
                     .map_or((false, None), |c| check_base_url(&source, c.node, provider));
 
-                instances.push(DetectionInstance {
+                let instance = DetectionInstance {
                     file_path: file_path.to_path_buf(),
                     line: start_position.row + 1,
                     column: start_position.column + 1,
                     has_base_url: has_base_url.0,
                     current_base_url: has_base_url.1,
-                });
+                };
+                if is_ignored(&source, start_position.row) {
+                    ignored.push(instance);
+                } else {
+                    instances.push(instance);
+                }
             }
         }
     }
 
-    Ok(DetectionResult { instances })
+    logging::trace(format_args!(
+        "{} query on {}: {} call(s) in {} µs",
+        provider.as_str(),
+        file_path.display(),
+        instances.len() + ignored.len(),
+        started.elapsed().as_micros()
+    ));
+    Ok(DetectionResult { instances, ignored })
 }
@@ -0,0 +1,184 @@
+/// Local subclasses of SDK client classes
+///
+/// `class GuardedClient(OpenAI)` / `class GuardedClient extends OpenAI` makes
+/// `GuardedClient()` an `OpenAI` call site that the registry queries can't
+/// see. These helpers collect such subclasses (including subclasses of
+/// subclasses) within one file and build a query matching their constructors.
+use super::registry::ProviderInfo;
+use crate::types::Provider;
+use tree_sitter::Node;
+
+/// Builds the subclass-constructor query for a parsed file, if it has any subclasses
+pub type SubclassQuery = fn(&str, Node, Provider) -> Option<String>;
+
+/// Query for constructors of local Python subclasses of `provider`'s client
+pub fn python_subclass_query(source: &str, root: Node, provider: Provider) -> Option<String> {
+    // Gemini and Bedrock are matched by module path, not a distinctive class name
+    if matches!(provider, Provider::Gemini | Provider::Bedrock) {
+        return None;
+    }
+    let mut classes = Vec::new();
+    collect_classes(
+        source,
+        root,
+        "class_definition",
+        &python_bases,
+        &mut classes,
+    );
+    let names = subclasses_of(ProviderInfo::get(provider).py_class_name, &classes);
+    (!names.is_empty()).then(|| {
+        format!(
+            r"
+            (call
+                function: (identifier) @function
+                (#any-of? @function {})
+                arguments: (argument_list) @args
+            ) @call_expr
+        ",
+            quoted(&names)
+        )
+    })
+}
+
+/// Query for `new` expressions of local TypeScript subclasses of `provider`'s client
+pub fn typescript_subclass_query(source: &str, root: Node, provider: Provider) -> Option<String> {
+    let class_name = ProviderInfo::get(provider).ts_class_name;
+    if class_name.is_empty() {
+        return None;
+    }
+    let mut classes = Vec::new();
+    for kind in ["class_declaration", "abstract_class_declaration"] {
+        collect_classes(source, root, kind, &typescript_bases, &mut classes);
+    }
+    let names = subclasses_of(class_name, &classes);
+    (!names.is_empty()).then(|| {
+        format!(
+            r"
+            (new_expression
+                constructor: (identifier) @constructor
+                (#any-of? @constructor {})
+                arguments: (arguments) @args
+            ) @new_expr
+        ",
+            quoted(&names)
+        )
+    })
+}
+
+/// A class and the names of its direct base classes
+type ClassDef = (String, Vec<String>);
+
+fn collect_classes(
+    source: &str,
+    node: Node,
+    kind: &str,
+    bases: &dyn Fn(&str, Node) -> Vec<String>,
+    classes: &mut Vec<ClassDef>,
+) {
+    if node.kind() == kind {
+        if let Some(name) = node.child_by_field_name("name") {
+            classes.push((text(source, name).to_string(), bases(source, node)));
+        }
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_classes(source, child, kind, bases, classes);
+    }
+}
+
+/// Base class names from `class X(Base, module.Base):`
+fn python_bases(source: &str, class: Node) -> Vec<String> {
+    let Some(superclasses) = class.child_by_field_name("superclasses") else {
+        return Vec::new();
+    };
+    let mut cursor = superclasses.walk();
+    let bases = superclasses
+        .named_children(&mut cursor)
+        .filter_map(|base| last_name(source, base, "attribute", "attribute"))
+        .collect();
+    bases
+}
+
+/// Base class name from `class X extends Base` / `extends module.Base`
+fn typescript_bases(source: &str, class: Node) -> Vec<String> {
+    let mut bases = Vec::new();
+    let mut cursor = class.walk();
+    for heritage in class
+        .named_children(&mut cursor)
+        .filter(|n| n.kind() == "class_heritage")
+    {
+        let mut heritage_cursor = heritage.walk();
+        for clause in heritage
+            .named_children(&mut heritage_cursor)
+            .filter(|n| n.kind() == "extends_clause")
+        {
+            if let Some(name) = clause
+                .child_by_field_name("value")
+                .and_then(|v| last_name(source, v, "member_expression", "property"))
+            {
+                bases.push(name);
+            }
+        }
+    }
+    bases
+}
+
+/// Identifier text, or the final segment of a dotted `member_kind` expression
+fn last_name(source: &str, node: Node, member_kind: &str, member_field: &str) -> Option<String> {
+    match node.kind() {
+        "identifier" => Some(text(source, node).to_string()),
+        kind if kind == member_kind => node
+            .child_by_field_name(member_field)
+            .map(|n| text(source, n).to_string()),
+        _ => None,
+    }
+}
+
+/// Every class that inherits from `root`, directly or through another local class
+fn subclasses_of(root: &str, classes: &[ClassDef]) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    loop {
+        let before = found.len();
+        for (name, bases) in classes {
+            let inherits = bases.iter().any(|b| b == root || found.contains(b));
+            if inherits && name != root && !found.contains(name) {
+                found.push(name.clone());
+            }
+        }
+        if found.len() == before {
+            break;
+        }
+    }
+    found.sort();
+    found
+}
+
+fn quoted(names: &[String]) -> String {
+    names
+        .iter()
+        .map(|n| format!("\"{n}\""))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn text<'a>(source: &'a str, node: Node) -> &'a str {
+    &source[node.start_byte()..node.end_byte()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitive_subclasses() {
+        let classes = vec![
+            ("Guarded".to_string(), vec!["OpenAI".to_string()]),
+            ("Audited".to_string(), vec!["Guarded".to_string()]),
+            ("Unrelated".to_string(), vec!["Base".to_string()]),
+        ];
+        assert_eq!(
+            subclasses_of("OpenAI", &classes),
+            vec!["Audited", "Guarded"]
+        );
+    }
+}
@@ -0,0 +1,49 @@
+/// Inline suppression directives
+///
+/// A trailing `# promptguard: ignore` / `// promptguard: ignore` comment
+/// suppresses the constructor starting on that line, and a
+/// `promptguard-ignore-next-line` comment on its own line suppresses the
+/// constructor on the line below. Suppressed call sites are neither
+/// transformed nor counted as unprotected, but `scan` still lists them.
+pub const IGNORE_DIRECTIVE: &str = "promptguard: ignore";
+pub const IGNORE_NEXT_LINE_DIRECTIVE: &str = "promptguard-ignore-next-line";
+
+/// Whether the call starting on 0-based `row` of `source` is suppressed
+pub fn is_ignored(source: &str, row: usize) -> bool {
+    let mut lines = source.lines().skip(row.saturating_sub(1));
+    let previous = if row > 0 { lines.next() } else { None };
+    let current = lines.next();
+
+    current.is_some_and(|line| has_comment_directive(line, IGNORE_DIRECTIVE))
+        || previous.is_some_and(|line| {
+            let line = line.trim_start();
+            (line.starts_with('#') || line.starts_with("//"))
+                && has_comment_directive(line, IGNORE_NEXT_LINE_DIRECTIVE)
+        })
+}
+
+/// `directive` appears right after a `#` or `//` comment marker
+fn has_comment_directive(line: &str, directive: &str) -> bool {
+    line.match_indices(directive).any(|(index, _)| {
+        let before = line[..index].trim_end();
+        before.ends_with('#') || before.ends_with("//")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directives() {
+        let source = "a = OpenAI()  # promptguard: ignore\n\
+                      // promptguard-ignore-next-line\n\
+                      b = new OpenAI({})\n\
+                      c = OpenAI()\n\
+                      d = \"promptguard: ignore\"\n";
+        assert!(is_ignored(source, 0));
+        assert!(is_ignored(source, 2));
+        assert!(!is_ignored(source, 3));
+        assert!(!is_ignored(source, 4));
+    }
+}
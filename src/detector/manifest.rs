@@ -0,0 +1,610 @@
+/// Dependency-manifest based SDK detection
+///
+/// Inspects `package.json`, `deno.json`, `requirements*.txt`, `pyproject.toml`,
+/// and lockfiles for provider packages listed in the registry. This catches SDKs
+/// that are installed but used dynamically (factories, DI containers,
+/// `importlib`) where the AST queries find nothing.
+use super::registry::{ProviderInfo, PROVIDERS};
+use crate::scanner::is_skip_dir;
+use crate::types::Provider;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Maximum directory depth searched for manifests (covers monorepo packages)
+const MANIFEST_MAX_DEPTH: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ecosystem {
+    Npm,
+    Python,
+}
+
+/// Package name and, for lockfiles, the exact resolved version
+type Package = (String, Option<String>);
+
+type Parser = fn(&str) -> Vec<Package>;
+
+/// A provider SDK declared in a dependency manifest or lockfile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeclaredDependency {
+    pub provider: Provider,
+    pub package: String,
+    /// Exact version, when read from a lockfile
+    pub version: Option<String>,
+    pub manifest: PathBuf,
+    ecosystem: Ecosystem,
+}
+
+impl DeclaredDependency {
+    /// Oldest SDK version that accepts a base URL override, if the registry knows one
+    pub fn min_base_url_version(&self) -> Option<&'static str> {
+        let info = ProviderInfo::get(self.provider);
+        let min = match self.ecosystem {
+            Ecosystem::Npm => info.npm_min_base_url_version,
+            Ecosystem::Python => info.py_min_base_url_version,
+        };
+        (!min.is_empty()).then_some(min)
+    }
+
+    /// Environment variable this SDK reads its base URL from, if any
+    pub fn base_url_env_var(&self) -> Option<&'static str> {
+        let info = ProviderInfo::get(self.provider);
+        let var = match self.ecosystem {
+            Ecosystem::Npm => info.npm_base_url_env_var,
+            Ecosystem::Python => info.py_base_url_env_var,
+        };
+        (!var.is_empty()).then_some(var)
+    }
+
+    /// Oldest SDK version reading `base_url_env_var`, if the registry knows one
+    pub fn min_base_url_env_version(&self) -> Option<&'static str> {
+        let info = ProviderInfo::get(self.provider);
+        let min = match self.ecosystem {
+            Ecosystem::Npm => info.npm_min_base_url_env_version,
+            Ecosystem::Python => info.py_min_base_url_env_version,
+        };
+        (!min.is_empty()).then_some(min)
+    }
+
+    /// Whether the SDK ignores the base URL environment variable: it reads
+    /// none, or the locked version predates it
+    pub fn ignores_base_url_env(&self) -> bool {
+        self.base_url_env_var().is_none()
+            || match (&self.version, self.min_base_url_env_version()) {
+                (Some(version), Some(min)) => version_lt(version, min),
+                _ => false,
+            }
+    }
+
+    /// Whether the locked version is too old for `PromptGuard` to redirect it
+    pub fn is_outdated(&self) -> bool {
+        match (&self.version, self.min_base_url_version()) {
+            (Some(version), Some(min)) => version_lt(version, min),
+            _ => false,
+        }
+    }
+}
+
+/// Numeric `major.minor.patch` comparison; pre-release suffixes are ignored
+fn version_lt(version: &str, min: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> {
+        v.trim_start_matches(['v', '='])
+            .split('.')
+            .map(|part| {
+                part.chars()
+                    .take_while(char::is_ascii_digit)
+                    .collect::<String>()
+                    .parse()
+                    .unwrap_or(0)
+            })
+            .collect()
+    };
+    let (have, want) = (parse(version), parse(min));
+    for i in 0..have.len().max(want.len()) {
+        let part = |v: &[u64]| v.get(i).copied().unwrap_or(0);
+        if part(&have) != part(&want) {
+            return part(&have) < part(&want);
+        }
+    }
+    false
+}
+
+/// Find every provider SDK declared in manifests under `root`, sorted by manifest path
+pub fn detect_declared_providers(root: &Path) -> Vec<DeclaredDependency> {
+    let mut declared = Vec::new();
+
+    for entry in WalkDir::new(root)
+        .max_depth(MANIFEST_MAX_DEPTH)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !is_skip_dir(&e.file_name().to_string_lossy()))
+        .filter_map(std::result::Result::ok)
+        .filter(|e| e.file_type().is_file())
+    {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy();
+        let Some((ecosystem, packages)) = parse_manifest(path, &name) else {
+            continue;
+        };
+
+        for (package, version) in packages {
+            if let Some(provider) = provider_for_package(ecosystem, &package) {
+                let dep = DeclaredDependency {
+                    provider,
+                    package,
+                    version,
+                    manifest: path.to_path_buf(),
+                    ecosystem,
+                };
+                if !declared.contains(&dep) {
+                    declared.push(dep);
+                }
+            }
+        }
+    }
+
+    declared.sort_by(|a, b| {
+        a.manifest
+            .cmp(&b.manifest)
+            .then_with(|| a.provider.cmp(&b.provider))
+    });
+    declared
+}
+
+/// Packages listed in a manifest, or `None` if the file is not a manifest
+fn parse_manifest(path: &Path, name: &str) -> Option<(Ecosystem, Vec<Package>)> {
+    let (ecosystem, parser): (Ecosystem, Parser) = match name {
+        "package.json" => (Ecosystem::Npm, |c| unversioned(parse_package_json(c))),
+        "package-lock.json" => (Ecosystem::Npm, parse_package_lock),
+        "pnpm-lock.yaml" => (Ecosystem::Npm, parse_pnpm_lock),
+        "yarn.lock" => (Ecosystem::Npm, parse_yarn_lock),
+        "deno.json" | "deno.jsonc" => (Ecosystem::Npm, |c| unversioned(parse_deno_config(c))),
+        "deno.lock" => (Ecosystem::Npm, parse_deno_lock),
+        "pyproject.toml" => (Ecosystem::Python, |c| unversioned(parse_pyproject(c))),
+        "poetry.lock" | "uv.lock" => (Ecosystem::Python, parse_toml_lock),
+        n if n.starts_with("requirements")
+            && Path::new(n)
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("txt")) =>
+        {
+            (Ecosystem::Python, |c| unversioned(parse_requirements(c)))
+        },
+        _ => return None,
+    };
+    let content = fs::read_to_string(path).ok()?;
+    Some((ecosystem, parser(&content)))
+}
+
+/// Manifests declare ranges, not versions; only lockfiles pin exact versions
+fn unversioned(names: Vec<String>) -> Vec<Package> {
+    names.into_iter().map(|n| (n, None)).collect()
+}
+
+fn provider_for_package(ecosystem: Ecosystem, package: &str) -> Option<Provider> {
+    PROVIDERS
+        .iter()
+        .find(|info| match ecosystem {
+            Ecosystem::Npm => info.npm_packages.contains(&package),
+            Ecosystem::Python => info
+                .py_packages
+                .iter()
+                .any(|p| normalize_python_name(p) == normalize_python_name(package)),
+        })
+        .map(|info| info.provider)
+}
+
+/// PEP 503 name normalization (`Google_GenAI` == `google-genai`)
+fn normalize_python_name(name: &str) -> String {
+    name.to_lowercase().replace(['_', '.'], "-")
+}
+
+/// Leading distribution name of a requirement specifier (`openai[datalib]>=1.0` → `openai`)
+fn requirement_name(spec: &str) -> Option<String> {
+    let name: String = spec
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .collect();
+    (!name.is_empty()).then_some(name)
+}
+
+fn parse_package_json(content: &str) -> Vec<String> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    [
+        "dependencies",
+        "devDependencies",
+        "peerDependencies",
+        "optionalDependencies",
+    ]
+    .iter()
+    .filter_map(|key| json.get(key).and_then(|v| v.as_object()))
+    .flat_map(|deps| deps.keys().cloned())
+    .collect()
+}
+
+fn parse_package_lock(content: &str) -> Vec<Package> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    let version = |entry: &serde_json::Value| {
+        entry
+            .get("version")
+            .and_then(|v| v.as_str())
+            .map(String::from)
+    };
+    let mut packages: Vec<Package> = json
+        .get("packages")
+        .and_then(|p| p.as_object())
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|(k, v)| {
+                    let (_, name) = k.rsplit_once("node_modules/")?;
+                    Some((name.to_string(), version(v)))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    // lockfileVersion 1
+    if let Some(deps) = json.get("dependencies").and_then(|d| d.as_object()) {
+        packages.extend(deps.iter().map(|(k, v)| (k.clone(), version(v))));
+    }
+    packages
+}
+
+/// Packages from `pnpm-lock.yaml` entry keys (`/openai@4.0.0:`, `openai@4.0.0(zod@3.23.8):`)
+fn parse_pnpm_lock(content: &str) -> Vec<Package> {
+    content
+        .lines()
+        .filter(|line| line.trim_end().ends_with(':'))
+        .filter_map(|line| {
+            let key = line
+                .trim()
+                .trim_end_matches(':')
+                .trim_matches(|c| c == '"' || c == '\'')
+                .trim_start_matches('/');
+            let (name, version) = split_name_version(key)?;
+            let version = version.split('(').next().unwrap_or(version);
+            Some((name.to_string(), Some(version.to_string())))
+        })
+        .collect()
+}
+
+/// Packages from `yarn.lock`: a `"name@range", "name@range":` key followed by `version "x.y.z"`
+fn parse_yarn_lock(content: &str) -> Vec<Package> {
+    let mut packages = Vec::new();
+    let mut current: Option<String> = None;
+
+    for line in content.lines() {
+        if !line.starts_with(' ') && line.trim_end().ends_with(':') {
+            let first = line
+                .trim_end_matches(':')
+                .split(", ")
+                .next()
+                .unwrap_or("")
+                .trim_matches('"');
+            current = split_name_version(first).map(|(name, _)| name.to_string());
+        } else if let Some(name) = current.take_if(|_| line.trim_start().starts_with("version")) {
+            let version = line
+                .trim()
+                .trim_start_matches("version")
+                .trim_start_matches(':')
+                .trim()
+                .trim_matches('"');
+            packages.push((name, Some(version.to_string())));
+        }
+    }
+
+    packages
+}
+
+/// Parse JSON with comments (`deno.jsonc`); `//` and `/* */` outside strings are dropped
+pub fn parse_jsonc(content: &str) -> Option<serde_json::Value> {
+    let mut json = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            json.push(c);
+            match c {
+                '\\' => json.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {},
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('/', Some('/')) => while chars.next_if(|&n| n != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut last = ' ';
+                for n in chars.by_ref() {
+                    if last == '*' && n == '/' {
+                        break;
+                    }
+                    last = n;
+                }
+            },
+            _ => {
+                in_string = c == '"';
+                json.push(c);
+            },
+        }
+    }
+    serde_json::from_str(&json).ok()
+}
+
+/// Package named by an `npm:` specifier (`npm:@anthropic-ai/sdk@^0.20` → `@anthropic-ai/sdk`)
+pub fn npm_specifier_package(specifier: &str) -> Option<&str> {
+    let rest = specifier.strip_prefix("npm:")?.trim_start_matches('/');
+    let scope_len = if rest.starts_with('@') {
+        rest.find('/')? + 1
+    } else {
+        0
+    };
+    let end = rest[scope_len..]
+        .find(['@', '/'])
+        .map_or(rest.len(), |i| i + scope_len);
+    (end > scope_len).then(|| &rest[..end])
+}
+
+/// Packages mapped to `npm:` specifiers in the `imports` of `deno.json`
+fn parse_deno_config(content: &str) -> Vec<String> {
+    parse_jsonc(content)
+        .as_ref()
+        .and_then(|json| json.get("imports"))
+        .and_then(|imports| imports.as_object())
+        .map(|imports| {
+            imports
+                .values()
+                .filter_map(|v| npm_specifier_package(v.as_str()?))
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Packages from the `npm` section of `deno.lock` (`packages.npm` before
+/// lockfile v4), keyed `name@version` with peer dependencies after `_`
+fn parse_deno_lock(content: &str) -> Vec<Package> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    json.get("npm")
+        .or_else(|| json.get("packages").and_then(|p| p.get("npm")))
+        .and_then(|npm| npm.as_object())
+        .map(|entries| {
+            entries
+                .keys()
+                .filter_map(|key| {
+                    let (name, version) = split_name_version(key)?;
+                    let version = version.split('_').next().unwrap_or(version);
+                    Some((name.to_string(), Some(version.to_string())))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Split `name@version`, allowing for scoped names (`@scope/name@version`)
+fn split_name_version(key: &str) -> Option<(&str, &str)> {
+    let split = key.get(1..)?.find('@')? + 1;
+    Some((&key[..split], &key[split + 1..]))
+}
+
+fn parse_requirements(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('-'))
+        .filter_map(requirement_name)
+        .collect()
+}
+
+/// Dependency names from `[project]` arrays and `[tool.poetry.*dependencies]` tables
+fn parse_pyproject(content: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut table = String::new();
+    let mut in_array = false;
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') && !in_array {
+            table = line.trim_matches(|c| c == '[' || c == ']').to_string();
+            continue;
+        }
+
+        if in_array {
+            names.extend(quoted_requirements(line));
+            if closes_array(line) {
+                in_array = false;
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        let value = value.trim();
+
+        let dep_table = table.starts_with("tool.poetry") && table.ends_with("dependencies");
+        let array_table = table == "project.optional-dependencies" || table == "dependency-groups";
+        if dep_table && key != "python" {
+            names.push(key.to_string());
+        } else if ((table == "project" && key == "dependencies") || array_table)
+            && value.starts_with('[')
+        {
+            names.extend(quoted_requirements(value));
+            in_array = !closes_array(value);
+        }
+    }
+
+    names
+}
+
+/// Whether a TOML line ends an array (`]`), ignoring brackets inside extras like `pkg[extra]`
+fn closes_array(line: &str) -> bool {
+    line.split('#')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .trim_end_matches(',')
+        .ends_with(']')
+}
+
+fn quoted_requirements(line: &str) -> Vec<String> {
+    line.split(['"', '\''])
+        .skip(1)
+        .step_by(2)
+        .filter_map(requirement_name)
+        .collect()
+}
+
+/// Packages from `[[package]]` entries of `poetry.lock` / `uv.lock`
+fn parse_toml_lock(content: &str) -> Vec<Package> {
+    let mut packages = Vec::new();
+    let mut name: Option<String> = None;
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            name = None;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"').to_string();
+        match key.trim() {
+            "name" => name = Some(value),
+            "version" => {
+                if let Some(n) = name.take() {
+                    packages.push((n, Some(value)));
+                }
+            },
+            _ => {},
+        }
+    }
+
+    packages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_requirements() {
+        let content = "# LLM deps\nopenai[datalib]>=1.0\n-r base.txt\nAnthropic==0.30 ; python_version > '3.8'\n";
+        assert_eq!(parse_requirements(content), vec!["openai", "Anthropic"]);
+    }
+
+    #[test]
+    fn test_parse_pyproject() {
+        let content = r#"
+[project]
+name = "svc"
+dependencies = [
+    "openai[datalib]>=1.0",
+    "httpx",
+]
+
+[tool.poetry.dependencies]
+python = "^3.11"
+groq = "^0.9"
+"#;
+        assert_eq!(parse_pyproject(content), vec!["openai", "httpx", "groq"]);
+    }
+
+    #[test]
+    fn test_parse_pnpm_lock_scoped_packages() {
+        let content = "packages:\n  /@anthropic-ai/sdk@0.20.1:\n    resolution: {}\n  openai@4.52.0(zod@3.23.8):\n";
+        assert_eq!(
+            parse_pnpm_lock(content),
+            vec![
+                ("@anthropic-ai/sdk".to_string(), Some("0.20.1".to_string())),
+                ("openai".to_string(), Some("4.52.0".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_yarn_lock_versions() {
+        let content =
+            "\"openai@^4.0.0\", \"openai@^4.20.0\":\n  version \"4.52.0\"\n  resolved \"x\"\n";
+        assert_eq!(
+            parse_yarn_lock(content),
+            vec![("openai".to_string(), Some("4.52.0".to_string()))]
+        );
+    }
+
+    #[test]
+    fn test_parse_toml_lock_versions() {
+        let content = "[[package]]\nname = \"openai\"\nversion = \"0.28.1\"\n\n[package.dependencies]\nname = \"x\"\n\n[[package]]\nname = \"groq\"\nversion = \"0.9.0\"\n";
+        assert_eq!(
+            parse_toml_lock(content),
+            vec![
+                ("openai".to_string(), Some("0.28.1".to_string())),
+                ("groq".to_string(), Some("0.9.0".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_deno_manifests() {
+        let config = r#"{
+  // SDKs
+  "imports": {
+    "openai": "npm:openai@^4.20.0", /* pinned */
+    "anthropic": "npm:@anthropic-ai/sdk",
+    "std/": "https://deno.land/std@0.220.0/"
+  }
+}"#;
+        assert_eq!(
+            parse_deno_config(config),
+            vec!["@anthropic-ai/sdk", "openai"]
+        );
+
+        let lock = r#"{"version": "4", "npm": {"openai@4.52.0_zod@3.23.8": {}, "@anthropic-ai/sdk@0.20.1": {}}}"#;
+        assert_eq!(
+            parse_deno_lock(lock),
+            vec![
+                ("@anthropic-ai/sdk".to_string(), Some("0.20.1".to_string())),
+                ("openai".to_string(), Some("4.52.0".to_string())),
+            ]
+        );
+        assert_eq!(
+            npm_specifier_package("npm:openai/resources"),
+            Some("openai")
+        );
+        assert_eq!(npm_specifier_package("npm:@scope"), None);
+    }
+
+    #[test]
+    fn test_version_lt() {
+        assert!(version_lt("0.28.1", "1.0.0"));
+        assert!(version_lt("3.3.0", "4"));
+        assert!(!version_lt("1.0.0", "1.0.0"));
+        assert!(!version_lt("1.30.0-beta.1", "1.0.0"));
+    }
+
+    #[test]
+    fn test_python_names_are_normalized() {
+        assert_eq!(
+            provider_for_package(Ecosystem::Python, "Google_GenAI"),
+            Some(Provider::Gemini)
+        );
+        assert_eq!(provider_for_package(Ecosystem::Npm, "Google_GenAI"), None);
+    }
+
+    #[test]
+    fn test_boto3_is_not_a_bedrock_dependency() {
+        assert_eq!(provider_for_package(Ecosystem::Python, "boto3"), None);
+        assert_eq!(
+            provider_for_package(Ecosystem::Npm, "@aws-sdk/client-bedrock-runtime"),
+            Some(Provider::Bedrock)
+        );
+    }
+}
@@ -1,17 +1,26 @@
 mod core;
+pub mod hierarchy;
+mod ignore;
+pub mod manifest;
+pub mod module_config;
 mod python;
 pub mod queries;
 pub mod registry;
 mod typescript;
 
+pub use ignore::is_ignored;
+pub use manifest::{detect_declared_providers, DeclaredDependency};
+pub use module_config::python_module_assignments;
 pub use python::PythonDetector;
 pub use queries::{get_python_transform_query, get_typescript_query};
 pub use registry::{ProviderInfo, PROVIDERS};
-pub use typescript::TypeScriptDetector;
+pub use typescript::{ts_options_object, TypeScriptDetector};
 
 use crate::error::Result;
+use crate::logging;
 use crate::types::{DetectionResult, Language, Provider};
 use std::path::Path;
+use std::time::Instant;
 
 pub trait Detector {
     fn detect_in_file(&self, file_path: &Path, provider: Provider) -> Result<DetectionResult>;
@@ -25,25 +34,34 @@ pub fn detect_all_providers(file_path: &Path) -> Result<Vec<(Provider, Detection
         return Ok(Vec::new());
     };
 
+    let started = Instant::now();
     let mut results = Vec::new();
 
     for provider_info in PROVIDERS {
         let provider = provider_info.provider;
         let result = match language {
             Language::TypeScript | Language::JavaScript => {
-                let detector = TypeScriptDetector::new();
-                detector.detect_in_file(file_path, provider)?
+                TypeScriptDetector::new().detect_in_file(file_path, provider)
             },
-            Language::Python => {
-                let detector = PythonDetector::new();
-                detector.detect_in_file(file_path, provider)?
-            },
-        };
+            Language::Python => PythonDetector::new().detect_in_file(file_path, provider),
+        }
+        .inspect_err(|e| {
+            logging::debug(format_args!(
+                "Could not analyze {}: {e}",
+                file_path.display()
+            ));
+        })?;
 
-        if !result.instances.is_empty() {
+        if !result.instances.is_empty() || !result.ignored.is_empty() {
             results.push((provider, result));
         }
     }
 
+    logging::trace(format_args!(
+        "{}: {} provider(s) in {} ms",
+        file_path.display(),
+        results.len(),
+        started.elapsed().as_millis()
+    ));
     Ok(results)
 }
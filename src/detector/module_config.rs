@@ -0,0 +1,134 @@
+/// Module-level SDK configuration in Python
+///
+/// Code written against the module-level client (`openai.api_key = ...`,
+/// `openai.base_url = ...`) never calls a constructor, so the constructor
+/// queries miss it. These assignments are found here instead: a base URL
+/// assignment is the call site to rewrite, and an API key assignment with no
+/// base URL marks where one should be added.
+use super::registry::ProviderInfo;
+use crate::error::{PromptGuardError, Result};
+use crate::types::Provider;
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Parser, Query, QueryCursor};
+
+const ASSIGNMENT_QUERY: &str = r"
+    (expression_statement
+        (assignment
+            left: (attribute
+                object: (identifier) @module
+                attribute: (identifier) @attr)
+            right: (_) @value)
+    ) @statement
+";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleAssignment {
+    /// `module.attr` being assigned
+    pub target: String,
+    /// Sets the base URL rather than the API key
+    pub is_base_url: bool,
+    /// 0-based row of the statement
+    pub row: usize,
+    pub column: usize,
+    /// Byte range of the whole statement
+    pub statement: (usize, usize),
+    /// Byte range of the assigned value
+    pub value: (usize, usize),
+    /// Contents of the value when it is a plain string literal
+    pub literal: Option<String>,
+}
+
+/// Assignments to `provider`'s module-level base URL and API key globals
+pub fn python_module_assignments(
+    source: &str,
+    provider: Provider,
+) -> Result<Vec<ModuleAssignment>> {
+    let base_url_attrs = ProviderInfo::get(provider).py_module_base_url_attrs;
+    let Some(module) = base_url_attrs.first().and_then(|a| a.split('.').next()) else {
+        return Ok(Vec::new());
+    };
+    let api_key_attr = format!("{module}.api_key");
+
+    let language = tree_sitter_python::LANGUAGE.into();
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language)
+        .map_err(|_| PromptGuardError::Parse("Failed to set Python language".to_string()))?;
+    let tree = parser
+        .parse(source, None)
+        .ok_or_else(|| PromptGuardError::Parse("Failed to parse Python file".to_string()))?;
+    let query = Query::new(&language, ASSIGNMENT_QUERY)
+        .map_err(|e| PromptGuardError::Parse(format!("Query error: {e}")))?;
+
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
+    let mut assignments = Vec::new();
+    while let Some(match_) = matches.next() {
+        let capture = |name: &str| {
+            match_
+                .captures
+                .iter()
+                .find(|c| query.capture_names()[c.index as usize] == name)
+                .map(|c| c.node)
+        };
+        let (Some(statement), Some(module_node), Some(attr), Some(value)) = (
+            capture("statement"),
+            capture("module"),
+            capture("attr"),
+            capture("value"),
+        ) else {
+            continue;
+        };
+
+        let target = format!("{}.{}", text(source, module_node), text(source, attr));
+        let is_base_url = base_url_attrs.contains(&target.as_str());
+        if !is_base_url && target != api_key_attr {
+            continue;
+        }
+        let raw = text(source, value);
+        let literal = (value.kind() == "string" && raw.starts_with(['"', '\'']))
+            .then(|| raw.trim_matches(|c| c == '"' || c == '\'').to_string());
+        assignments.push(ModuleAssignment {
+            target,
+            is_base_url,
+            row: statement.start_position().row,
+            column: statement.start_position().column,
+            statement: (statement.start_byte(), statement.end_byte()),
+            value: (value.start_byte(), value.end_byte()),
+            literal,
+        });
+    }
+
+    Ok(assignments)
+}
+
+fn text<'a>(source: &'a str, node: tree_sitter::Node) -> &'a str {
+    &source[node.start_byte()..node.end_byte()]
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finds_module_globals() {
+        let source = "import openai\n\
+                      openai.api_key = os.environ[\"KEY\"]\n\
+                      openai.api_base = \"https://api.openai.com/v1\"\n\
+                      openai.organization = \"org\"\n\
+                      client.base_url = \"x\"\n";
+        let found = python_module_assignments(source, Provider::OpenAI).unwrap();
+        assert_eq!(found.len(), 2);
+        assert!(!found[0].is_base_url && found[0].literal.is_none());
+        assert_eq!(found[1].target, "openai.api_base");
+        assert_eq!(
+            found[1].literal.as_deref(),
+            Some("https://api.openai.com/v1")
+        );
+
+        assert!(python_module_assignments(source, Provider::Anthropic)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,8 +1,12 @@
 use super::core::{detect_in_file_generic, DetectorConfig};
+use super::hierarchy::python_subclass_query;
+use super::ignore::is_ignored;
+use super::module_config::python_module_assignments;
 use super::queries::get_python_detection_query;
 use super::Detector;
 use crate::error::Result;
-use crate::types::{DetectionResult, Language, Provider};
+use crate::types::{DetectionInstance, DetectionResult, Language, Provider};
+use std::fs;
 use std::path::Path;
 
 pub struct PythonDetector;
@@ -34,6 +38,43 @@ impl PythonDetector {
 
         (has_base_url, current_base_url)
     }
+
+    /// Module-level configuration: every base URL assignment, or else the
+    /// first API key assignment (where a base URL would be added)
+    fn add_module_config(
+        file_path: &Path,
+        provider: Provider,
+        result: &mut DetectionResult,
+    ) -> Result<()> {
+        let source = fs::read_to_string(file_path)?;
+        let assignments = python_module_assignments(&source, provider)?;
+        let has_base_url = assignments.iter().any(|a| a.is_base_url);
+        let sites = assignments
+            .iter()
+            .filter(|a| a.is_base_url == has_base_url)
+            .take(if has_base_url { usize::MAX } else { 1 });
+
+        for assignment in sites {
+            let instance = DetectionInstance {
+                file_path: file_path.to_path_buf(),
+                line: assignment.row + 1,
+                column: assignment.column + 1,
+                has_base_url,
+                current_base_url: has_base_url.then(|| {
+                    assignment
+                        .literal
+                        .clone()
+                        .unwrap_or_else(|| "(configured)".to_string())
+                }),
+            };
+            if is_ignored(&source, assignment.row) {
+                result.ignored.push(instance);
+            } else {
+                result.instances.push(instance);
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Detector for PythonDetector {
@@ -42,16 +83,19 @@ impl Detector for PythonDetector {
             parser_language: tree_sitter_python::LANGUAGE.into(),
             language: Language::Python,
             capture_name: "call_expr",
+            subclass_query: python_subclass_query,
         };
 
         let query_str = get_python_detection_query(provider);
 
-        detect_in_file_generic(
+        let mut result = detect_in_file_generic(
             file_path,
             provider,
             &config,
             &query_str,
             Self::check_has_base_url,
-        )
+        )?;
+        Self::add_module_config(file_path, provider, &mut result)?;
+        Ok(result)
     }
 }
@@ -2,7 +2,8 @@
 ///
 /// Adding a new LLM provider? Add ONE entry here.
 /// All provider metadata lives in this single table:
-/// package names, class names, parameter names, API endpoints.
+/// package names, class names, parameter names, API endpoints, and what the
+/// runtime shims wrap (their templates are filled in from these entries).
 use crate::types::Provider;
 
 #[derive(Debug, Clone)]
@@ -12,6 +13,34 @@ pub struct ProviderInfo {
     pub ts_class_name: &'static str,
     pub ts_base_url_param: &'static str,
     pub ts_api_key_param: &'static str,
+    /// `PyPI` distributions that provide the SDK
+    pub py_packages: &'static [&'static str],
+    /// npm packages that provide the SDK
+    pub npm_packages: &'static [&'static str],
+    /// Oldest Python SDK version supporting a base URL override ("" = any)
+    pub py_min_base_url_version: &'static str,
+    /// Oldest npm SDK version supporting a base URL override ("" = any)
+    pub npm_min_base_url_version: &'static str,
+    /// Module-level Python globals that set the base URL (`openai.base_url = ...`),
+    /// preferred spelling first
+    pub py_module_base_url_attrs: &'static [&'static str],
+    /// Python module whose clients the runtime shim wraps ("" = not shimmed)
+    pub py_shim_module: &'static str,
+    /// Client classes in `py_shim_module` the shim wraps when present
+    pub py_shim_classes: &'static [&'static str],
+    /// Python constructor keywords for the base URL and API key
+    pub py_base_url_param: &'static str,
+    pub py_api_key_param: &'static str,
+    /// Whether the TypeScript/JavaScript runtime shims wrap `ts_class_name`
+    pub ts_shim: bool,
+    /// Environment variable the Python SDK reads its base URL from ("" = none)
+    pub py_base_url_env_var: &'static str,
+    /// Environment variable the npm SDK reads its base URL from ("" = none)
+    pub npm_base_url_env_var: &'static str,
+    /// Oldest Python SDK version reading `py_base_url_env_var` ("" = any)
+    pub py_min_base_url_env_version: &'static str,
+    /// Oldest npm SDK version reading `npm_base_url_env_var` ("" = any)
+    pub npm_min_base_url_env_version: &'static str,
 }
 
 pub const PROVIDERS: &[ProviderInfo] = &[
@@ -21,6 +50,20 @@ pub const PROVIDERS: &[ProviderInfo] = &[
         ts_class_name: "OpenAI",
         ts_base_url_param: "baseURL",
         ts_api_key_param: "apiKey",
+        py_packages: &["openai"],
+        npm_packages: &["openai"],
+        py_min_base_url_version: "1.0.0",
+        npm_min_base_url_version: "4.0.0",
+        py_module_base_url_attrs: &["openai.base_url", "openai.api_base"],
+        py_shim_module: "openai",
+        py_shim_classes: &["OpenAI", "AsyncOpenAI"],
+        py_base_url_param: "base_url",
+        py_api_key_param: "api_key",
+        ts_shim: true,
+        py_base_url_env_var: "OPENAI_BASE_URL",
+        npm_base_url_env_var: "OPENAI_BASE_URL",
+        py_min_base_url_env_version: "1.0.0",
+        npm_min_base_url_env_version: "4.0.0",
     },
     ProviderInfo {
         provider: Provider::Anthropic,
@@ -28,6 +71,20 @@ pub const PROVIDERS: &[ProviderInfo] = &[
         ts_class_name: "Anthropic",
         ts_base_url_param: "baseURL",
         ts_api_key_param: "apiKey",
+        py_packages: &["anthropic"],
+        npm_packages: &["@anthropic-ai/sdk"],
+        py_min_base_url_version: "",
+        npm_min_base_url_version: "",
+        py_module_base_url_attrs: &[],
+        py_shim_module: "anthropic",
+        py_shim_classes: &["Anthropic", "AsyncAnthropic"],
+        py_base_url_param: "base_url",
+        py_api_key_param: "api_key",
+        ts_shim: true,
+        py_base_url_env_var: "ANTHROPIC_BASE_URL",
+        npm_base_url_env_var: "ANTHROPIC_BASE_URL",
+        py_min_base_url_env_version: "",
+        npm_min_base_url_env_version: "",
     },
     ProviderInfo {
         provider: Provider::Cohere,
@@ -35,6 +92,20 @@ pub const PROVIDERS: &[ProviderInfo] = &[
         ts_class_name: "CohereClient",
         ts_base_url_param: "baseURL",
         ts_api_key_param: "apiKey",
+        py_packages: &["cohere"],
+        npm_packages: &["cohere-ai"],
+        py_min_base_url_version: "5.0.0",
+        npm_min_base_url_version: "7.0.0",
+        py_module_base_url_attrs: &[],
+        py_shim_module: "cohere",
+        py_shim_classes: &["Client", "AsyncClient", "ClientV2", "AsyncClientV2"],
+        py_base_url_param: "base_url",
+        py_api_key_param: "api_key",
+        ts_shim: true,
+        py_base_url_env_var: "CO_API_URL",
+        npm_base_url_env_var: "",
+        py_min_base_url_env_version: "5.0.0",
+        npm_min_base_url_env_version: "",
     },
     ProviderInfo {
         provider: Provider::HuggingFace,
@@ -42,6 +113,20 @@ pub const PROVIDERS: &[ProviderInfo] = &[
         ts_class_name: "HfInference",
         ts_base_url_param: "baseUrl",
         ts_api_key_param: "accessToken",
+        py_packages: &["huggingface-hub"],
+        npm_packages: &["@huggingface/inference"],
+        py_min_base_url_version: "",
+        npm_min_base_url_version: "",
+        py_module_base_url_attrs: &[],
+        py_shim_module: "huggingface_hub",
+        py_shim_classes: &["InferenceClient", "AsyncInferenceClient"],
+        py_base_url_param: "base_url",
+        py_api_key_param: "token",
+        ts_shim: true,
+        py_base_url_env_var: "HF_INFERENCE_ENDPOINT",
+        npm_base_url_env_var: "",
+        py_min_base_url_env_version: "",
+        npm_min_base_url_env_version: "",
     },
     ProviderInfo {
         provider: Provider::Gemini,
@@ -49,6 +134,20 @@ pub const PROVIDERS: &[ProviderInfo] = &[
         ts_class_name: "GoogleGenAI",
         ts_base_url_param: "baseURL",
         ts_api_key_param: "apiKey",
+        py_packages: &["google-genai", "google-generativeai"],
+        npm_packages: &["@google/genai", "@google/generative-ai"],
+        py_min_base_url_version: "",
+        npm_min_base_url_version: "",
+        py_module_base_url_attrs: &[],
+        py_shim_module: "",
+        py_shim_classes: &[],
+        py_base_url_param: "",
+        py_api_key_param: "",
+        ts_shim: false,
+        py_base_url_env_var: "",
+        npm_base_url_env_var: "",
+        py_min_base_url_env_version: "",
+        npm_min_base_url_env_version: "",
     },
     ProviderInfo {
         provider: Provider::Groq,
@@ -56,6 +155,20 @@ pub const PROVIDERS: &[ProviderInfo] = &[
         ts_class_name: "Groq",
         ts_base_url_param: "baseURL",
         ts_api_key_param: "apiKey",
+        py_packages: &["groq"],
+        npm_packages: &["groq-sdk"],
+        py_min_base_url_version: "",
+        npm_min_base_url_version: "",
+        py_module_base_url_attrs: &[],
+        py_shim_module: "groq",
+        py_shim_classes: &["Groq", "AsyncGroq"],
+        py_base_url_param: "base_url",
+        py_api_key_param: "api_key",
+        ts_shim: true,
+        py_base_url_env_var: "GROQ_BASE_URL",
+        npm_base_url_env_var: "GROQ_BASE_URL",
+        py_min_base_url_env_version: "",
+        npm_min_base_url_env_version: "",
     },
     ProviderInfo {
         provider: Provider::Bedrock,
@@ -63,6 +176,22 @@ pub const PROVIDERS: &[ProviderInfo] = &[
         ts_class_name: "BedrockRuntimeClient",
         ts_base_url_param: "",
         ts_api_key_param: "",
+        // boto3 serves every AWS service, so depending on it says nothing
+        // about Bedrock; Python usage is found from `bedrock-runtime` clients
+        py_packages: &[],
+        npm_packages: &["@aws-sdk/client-bedrock-runtime"],
+        py_min_base_url_version: "",
+        npm_min_base_url_version: "",
+        py_module_base_url_attrs: &[],
+        py_shim_module: "",
+        py_shim_classes: &[],
+        py_base_url_param: "",
+        py_api_key_param: "",
+        ts_shim: false,
+        py_base_url_env_var: "",
+        npm_base_url_env_var: "",
+        py_min_base_url_env_version: "",
+        npm_min_base_url_env_version: "",
     },
 ];
 
@@ -75,6 +204,17 @@ impl ProviderInfo {
         }
         &PROVIDERS[0]
     }
+
+    /// Base URL environment variables read by this provider's SDKs, deduplicated
+    pub fn base_url_env_vars(&self) -> Vec<&'static str> {
+        let mut vars = Vec::new();
+        for var in [self.py_base_url_env_var, self.npm_base_url_env_var] {
+            if !var.is_empty() && !vars.contains(&var) {
+                vars.push(var);
+            }
+        }
+        vars
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,5 @@
 use super::core::{detect_in_file_generic, DetectorConfig};
+use super::hierarchy::typescript_subclass_query;
 use super::queries::get_typescript_query;
 use super::registry::ProviderInfo;
 use super::Detector;
@@ -8,6 +9,66 @@ use std::path::Path;
 
 pub struct TypeScriptDetector;
 
+/// The object literal a constructor is configured with: the inline
+/// `new OpenAI({...})` argument, or the initializer of the single
+/// `const config = {...}` in the file for `new OpenAI(config)`
+pub fn ts_options_object<'t>(
+    source: &str,
+    args_node: tree_sitter::Node<'t>,
+) -> Option<tree_sitter::Node<'t>> {
+    let mut cursor = args_node.walk();
+    let args: Vec<tree_sitter::Node<'t>> = args_node
+        .named_children(&mut cursor)
+        .filter(|n| n.kind() != "comment")
+        .collect();
+    if let Some(object) = args.iter().find(|n| n.kind() == "object") {
+        return Some(*object);
+    }
+    let name = args.first().filter(|n| n.kind() == "identifier")?;
+    let name = &source[name.start_byte()..name.end_byte()];
+
+    let mut root = args_node;
+    while let Some(parent) = root.parent() {
+        root = parent;
+    }
+    let mut initializers = Vec::new();
+    collect_initializers(source, root, name, &mut initializers);
+    match initializers.as_slice() {
+        [Some(object)] => Some(*object),
+        _ => None,
+    }
+}
+
+/// Object literal initializer of every declaration of `name` under `node`;
+/// `None` for declarations initialized some other way
+fn collect_initializers<'t>(
+    source: &str,
+    node: tree_sitter::Node<'t>,
+    name: &str,
+    found: &mut Vec<Option<tree_sitter::Node<'t>>>,
+) {
+    if node.kind() == "variable_declarator"
+        && node
+            .child_by_field_name("name")
+            .is_some_and(|n| &source[n.start_byte()..n.end_byte()] == name)
+    {
+        let mut value = node.child_by_field_name("value");
+        while let Some(inner) = value.filter(|v| {
+            matches!(
+                v.kind(),
+                "as_expression" | "satisfies_expression" | "parenthesized_expression"
+            )
+        }) {
+            value = inner.named_child(0);
+        }
+        found.push(value.filter(|v| v.kind() == "object"));
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_initializers(source, child, name, found);
+    }
+}
+
 impl Default for TypeScriptDetector {
     fn default() -> Self {
         Self::new()
@@ -25,7 +86,8 @@ impl TypeScriptDetector {
         provider: Provider,
     ) -> (bool, Option<String>) {
         let info = ProviderInfo::get(provider);
-        let args_text = &source[args_node.start_byte()..args_node.end_byte()];
+        let options = ts_options_object(source, args_node).unwrap_or(args_node);
+        let args_text = &source[options.start_byte()..options.end_byte()];
 
         let has_base_url = args_text.contains(&format!("{}:", info.ts_base_url_param))
             || args_text.contains(&format!("\"{}\": ", info.ts_base_url_param))
@@ -48,6 +110,7 @@ impl Detector for TypeScriptDetector {
             parser_language: tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
             language: Language::TypeScript,
             capture_name: "new_expr",
+            subclass_query: typescript_subclass_query,
         };
 
         let query_str = get_typescript_query(provider);
@@ -1,17 +1,23 @@
-use crate::error::Result;
-use std::fs;
+use crate::error::{PromptGuardError, Result};
+use crate::textfile::{self, TextFormat};
 use std::path::Path;
+use std::process::{Command, Stdio};
 
 pub struct EnvManager;
 
 impl EnvManager {
     pub fn add_or_update_key(env_path: &Path, key: &str, value: &str) -> Result<()> {
-        let content = if env_path.exists() {
-            fs::read_to_string(env_path)?
+        let (format, content) = if env_path.exists() {
+            textfile::read(env_path)?
         } else {
-            String::new()
+            (TextFormat::default(), String::new())
         };
 
+        textfile::write(env_path, format, &Self::with_key(&content, key, value))
+    }
+
+    /// `content` with `key` set to `value`, updating an existing entry or appending one
+    pub fn with_key(content: &str, key: &str, value: &str) -> String {
         let mut lines: Vec<String> = content
             .lines()
             .map(std::string::ToString::to_string)
@@ -39,10 +45,7 @@ impl EnvManager {
             lines.push(format!("{key}={value}"));
         }
 
-        let new_content = lines.join("\n");
-        fs::write(env_path, new_content)?;
-
-        Ok(())
+        lines.join("\n")
     }
 
     pub fn remove_key(env_path: &Path, key: &str) -> Result<bool> {
@@ -50,7 +53,7 @@ impl EnvManager {
             return Ok(false);
         }
 
-        let content = fs::read_to_string(env_path)?;
+        let (format, content) = textfile::read(env_path)?;
         let key_prefix = format!("{key}=");
 
         let new_lines: Vec<String> = content
@@ -64,18 +67,70 @@ impl EnvManager {
         let removed = new_lines.len() < content.lines().count();
 
         if removed {
-            fs::write(env_path, new_lines.join("\n"))?;
+            textfile::write(env_path, format, &new_lines.join("\n"))?;
         }
 
         Ok(removed)
     }
 
+    /// Value of `key`, without surrounding quotes
+    pub fn get_key(env_path: &Path, key: &str) -> Option<String> {
+        let (_, content) = textfile::read(env_path).ok()?;
+        let key_prefix = format!("{key}=");
+        content.lines().find_map(|line| {
+            let value = line
+                .strip_prefix("export ")
+                .unwrap_or(line)
+                .strip_prefix(&key_prefix)?
+                .trim();
+            let unquoted = ['"', '\'']
+                .iter()
+                .find_map(|q| value.strip_prefix(*q)?.strip_suffix(*q))
+                .unwrap_or(value);
+            Some(unquoted.to_string())
+        })
+    }
+
+    /// Add `option` to the space-separated options in `key` (like
+    /// `NODE_OPTIONS`), keeping any already set. Returns whether it was added.
+    pub fn add_option(env_path: &Path, key: &str, option: &str) -> Result<bool> {
+        let current = Self::get_key(env_path, key).unwrap_or_default();
+        if current.contains(option) {
+            return Ok(false);
+        }
+        let value = if current.is_empty() {
+            option.to_string()
+        } else {
+            format!("{current} {option}")
+        };
+        Self::add_or_update_key(env_path, key, &format!("\"{value}\""))?;
+        Ok(true)
+    }
+
+    /// Remove `option` from `key`, removing the key when nothing else is left
+    pub fn remove_option(env_path: &Path, key: &str, option: &str) -> Result<bool> {
+        let Some(current) = Self::get_key(env_path, key).filter(|v| v.contains(option)) else {
+            return Ok(false);
+        };
+        let rest = current
+            .replace(option, "")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if rest.is_empty() {
+            Self::remove_key(env_path, key)?;
+        } else {
+            Self::add_or_update_key(env_path, key, &format!("\"{rest}\""))?;
+        }
+        Ok(true)
+    }
+
     pub fn has_key(env_path: &Path, key: &str) -> bool {
         if !env_path.exists() {
             return false;
         }
 
-        if let Ok(content) = fs::read_to_string(env_path) {
+        if let Ok((_, content)) = textfile::read(env_path) {
             let key_prefix = format!("{key}=");
             content.lines().any(|line| {
                 line.starts_with(&key_prefix) || line.starts_with(&format!("export {key_prefix}"))
@@ -84,4 +139,33 @@ impl EnvManager {
             false
         }
     }
+
+    /// Whether git would commit `env_path`: it is tracked, or it is inside a
+    /// repository and not ignored. Outside a repository nothing is committed.
+    pub fn is_committed(env_path: &Path) -> bool {
+        let Some(dir) = env_path.ancestors().skip(1).find(|dir| dir.is_dir()) else {
+            return false;
+        };
+        // `check-ignore` never reports tracked files as ignored
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["check-ignore", "-q", "--"])
+            .arg(env_path)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.code() == Some(1))
+    }
+
+    /// Refuse to write a production key to a file git would commit
+    pub fn check_key_destination(env_path: &Path, value: &str) -> Result<()> {
+        if value.starts_with("pg_sk_prod_") && Self::is_committed(env_path) {
+            return Err(PromptGuardError::Config(format!(
+                "Refusing to write a production key to {}: git would commit it. Add the file to .gitignore, or use a pg_sk_test_ key there",
+                env_path.display()
+            )));
+        }
+        Ok(())
+    }
 }
@@ -37,8 +37,73 @@ pub enum PromptGuardError {
     #[error("Invalid API key format. Must start with 'pg_sk_test_' or 'pg_sk_prod_'")]
     InvalidApiKey,
 
+    #[error(
+        "{0} requires network access and is disabled in air-gapped mode. \
+         Unset PROMPTGUARD_AIR_GAPPED or drop --air-gapped to use it"
+    )]
+    AirGapped(String),
+
+    /// Scanned content the API blocked; not a failure of the CLI itself
+    #[error("{0}")]
+    ThreatDetected(String),
+
+    /// Local scan findings rejected by `--fail-on`; exits like a blocked scan
+    #[error("{0}")]
+    Findings(String),
+
+    /// A prompt nobody can answer: stdin is not a terminal, or `--no-input`
+    #[error("'{prompt}' needs an answer, but {reason}; {hint}")]
+    NoInput {
+        prompt: String,
+        reason: &'static str,
+        hint: &'static str,
+    },
+
     #[error("{0}")]
     Custom(String),
 }
 
+impl PromptGuardError {
+    /// Stable identifier for scripts to branch on, printed with the message.
+    /// Codes are never reused or renumbered.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotInitialized => "PG001",
+            Self::InvalidApiKey => "PG002",
+            Self::Config(_) => "PG003",
+            Self::Api(_) => "PG004",
+            Self::QuotaExceeded(_) => "PG005",
+            Self::AirGapped(_) => "PG006",
+            Self::ThreatDetected(_) => "PG007",
+            Self::Findings(_) => "PG008",
+            Self::NoInput { .. } => "PG009",
+            Self::Io(_) => "PG010",
+            Self::Json(_) => "PG011",
+            Self::Parse(_) => "PG012",
+            Self::Custom(_) => "PG000",
+        }
+    }
+
+    /// Process exit code: 2 when a scan blocked the content or found call
+    /// sites `--fail-on` rejects, so scripts can tell a verdict from an
+    /// error, otherwise 1
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::ThreatDetected(_) | Self::Findings(_) => 2,
+            _ => 1,
+        }
+    }
+
+    /// Exit code in CI mode (`promptguard ci` or `--ci`): 0 clean,
+    /// 2 findings, 3 when the project configuration is missing or invalid,
+    /// 1 for anything else
+    pub fn ci_exit_code(&self) -> i32 {
+        match self {
+            Self::ThreatDetected(_) | Self::Findings(_) => 2,
+            Self::Config(_) | Self::NotInitialized | Self::InvalidApiKey => 3,
+            _ => 1,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, PromptGuardError>;
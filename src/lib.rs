@@ -8,11 +8,16 @@
 #![allow(clippy::unnecessary_wraps)]
 
 pub mod analyzer;
+pub mod backup;
 pub mod config;
 pub mod detector;
+pub mod env;
 pub mod error;
+pub mod logging;
 pub mod scanner;
 pub mod shim;
+pub mod state;
+pub mod textfile;
 pub mod transformer;
 pub mod types;
 
@@ -0,0 +1,67 @@
+//! Leveled diagnostics on stderr.
+//!
+//! Commands print their results through `Output`; this is for explaining
+//! how they got there: `-v` logs skipped files, file-level detection
+//! failures and HTTP requests, `-vv` adds per-query timings, and `--quiet`
+//! keeps only errors.
+use colored::Colorize;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 0,
+    Info = 1,
+    Debug = 2,
+    Trace = 3,
+}
+
+impl Level {
+    /// Level for the global `--quiet` and `-v` flags
+    pub fn from_flags(verbose: u8, quiet: bool) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Self::Error,
+            (false, 0) => Self::Info,
+            (false, 1) => Self::Debug,
+            (false, _) => Self::Trace,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Info => "info",
+            Self::Debug => "debug",
+            Self::Trace => "trace",
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+
+/// Set the most detailed level that is logged (call once at startup)
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Whether messages at `level` are logged
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+fn log(level: Level, message: impl Display) {
+    if enabled(level) {
+        let label = format!("[{}]", level.label());
+        eprintln!("{} {message}", label.dimmed());
+    }
+}
+
+/// Why something happened: skipped files, failed detections, HTTP requests
+pub fn debug(message: impl Display) {
+    log(Level::Debug, message);
+}
+
+/// Fine-grained detail such as per-file and per-query timings
+pub fn trace(message: impl Display) {
+    log(Level::Trace, message);
+}
@@ -8,6 +8,7 @@
 #![allow(clippy::unused_self)]
 #![allow(clippy::unnecessary_wraps)]
 
+mod airgap;
 mod analyzer;
 mod api;
 mod auth;
@@ -17,38 +18,78 @@ mod config;
 mod detector;
 mod env;
 mod error;
+mod logging;
 mod output;
 mod scanner;
 mod shim;
+mod state;
+mod textfile;
 mod transformer;
 mod types;
 
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use commands::{
-    ApplyCommand, ConfigCommand, DashboardCommand, DisableCommand, DoctorCommand, EnableCommand,
-    EventsCommand, InitCommand, KeyCommand, LoginCommand, LogoutCommand, LogsCommand, McpCommand,
-    PolicyAction, PolicyCommand, ProjectsAction, ProjectsCommand, RedTeamCommand, RedactCommand,
-    RevertCommand, ScanCommand, StatusCommand, TestCommand, UpdateCommand, VerifyCommand,
-    WhoamiCommand,
+    ApplyCommand, AuditCommand, BackupAction, BackupCommand, BenchmarkCommand, CiCommand,
+    CompletionsCommand, ConfigAction, ConfigCommand, CoverageCommand, DashboardCommand,
+    DemoCommand, DisableCommand, DoctorCommand, EnableCommand, EventsCommand, GenDocsCommand,
+    HookAction, HookCommand, HookManager, InitCommand, KeyAction, KeyCommand, LoginCommand,
+    LogoutCommand, LogsCommand, LspCommand, McpCommand, PackageOfflineCommand, PolicyAction,
+    PolicyCommand, ProjectsAction, ProjectsCommand, RedTeamCommand, RedactCommand, ReportCommand,
+    RevertCommand, ScanCommand, SecretsAction, SecretsCommand, ShimAction, ShimCommand,
+    StateAction, StateCommand, StatsCommand, StatusCommand, TestCommand, UninstallCommand,
+    UpdateCommand, VerifyCommand, WhoamiCommand,
 };
+use std::io::IsTerminal;
 
 #[derive(Parser)]
 #[command(name = "promptguard")]
 #[command(about = "Drop-in LLM security for your applications", long_about = None)]
 #[command(version)]
+#[allow(clippy::struct_excessive_bools)]
 struct Cli {
-    /// Increase output verbosity (can be repeated: -v, -vv, -vvv)
+    /// Log diagnostics on stderr: -v for skipped files, failed detections and
+    /// HTTP requests, -vv adds per-file and per-query timings
     #[arg(short, long, action = clap::ArgAction::Count, global = true)]
     verbose: u8,
 
-    /// Suppress non-essential output
+    /// Only print errors (and the result of commands that print one)
     #[arg(short, long, global = true)]
     quiet: bool,
 
-    /// Disable colored output (also respects `NO_COLOR` env var)
+    /// Disable colors and emoji (also respects a non-empty `NO_COLOR` env var)
     #[arg(long, global = true)]
     no_color: bool,
 
+    /// Disable all commands that need network access (also respects `PROMPTGUARD_AIR_GAPPED`)
+    #[arg(long, global = true)]
+    air_gapped: bool,
+
+    /// Run against the project in this directory instead of the one found from the current directory
+    #[arg(long, global = true, value_name = "DIR", visible_alias = "project-dir")]
+    path: Option<std::path::PathBuf>,
+
+    /// Apply this profile from .promptguard.json (also respects `PROMPTGUARD_PROFILE`)
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// CI mode: no prompts or colors, and exit 2 on findings, 3 on config errors
+    #[arg(long, global = true)]
+    ci: bool,
+
+    /// Print the result as JSON (for scripting); objects carry a `schema_version`
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// How to report progress on stderr: auto (a bar on a terminal), json
+    /// (one event per line: start, progress per file, finish) or none
+    #[arg(long, global = true, value_name = "STYLE", default_value = "auto", value_parser = ["auto", "json", "none"])]
+    progress: String,
+
+    /// Fail instead of prompting (implied when stdin is not a terminal);
+    /// pass --yes to commands that ask for confirmation
+    #[arg(long, global = true)]
+    no_input: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -68,19 +109,21 @@ enum Commands {
         #[arg(long)]
         api_key: Option<String>,
 
-        /// Proxy URL to route LLM requests through
-        #[arg(long, default_value = "https://api.promptguard.co/api/v1")]
-        base_url: String,
+        /// Proxy URL to route LLM requests through [default: the user config's
+        /// `base_url`, else <https://api.promptguard.co/api/v1>]
+        #[arg(long)]
+        base_url: Option<String>,
 
-        /// Environment file to store API key
-        #[arg(long, default_value = ".env")]
-        env_file: String,
+        /// Environment file to store API key; repeat or comma-separate to keep
+        /// several in sync (`.env,.env.local`)
+        #[arg(long, default_value = ".env", value_delimiter = ',', value_hint = clap::ValueHint::FilePath)]
+        env_file: Vec<String>,
 
         /// Skip confirmation prompts (for CI/CD)
-        #[arg(short = 'y', long)]
+        #[arg(short = 'y', long, visible_alias = "yes")]
         auto: bool,
 
-        /// Preview changes without applying them
+        /// Preview changes as a unified diff without applying them
         #[arg(long)]
         dry_run: bool,
 
@@ -95,6 +138,25 @@ enum Commands {
         /// Override detected framework (nextjs, express, django, fastapi, flask)
         #[arg(long)]
         framework: Option<String>,
+
+        /// In a monorepo, write one config per workspace package instead of one root config
+        #[arg(long)]
+        per_package: bool,
+
+        /// Link the repo to this backend project (see `promptguard projects list`)
+        #[arg(long, value_name = "PROJECT_ID")]
+        project: Option<String>,
+
+        /// Pick providers, confirm the framework and proxy URL, and review the
+        /// planned changes before applying them
+        #[arg(short, long, conflicts_with_all = ["auto", "dry_run"])]
+        interactive: bool,
+
+        /// Don't contact the backend (for build machines without network
+        /// access): only the key's format is checked, and `promptguard test`
+        /// runs the health checks later
+        #[arg(long)]
+        offline: bool,
     },
 
     /// Scan project for LLM SDK usage or scan text for security threats
@@ -109,38 +171,43 @@ enum Commands {
         #[arg(long)]
         provider: Option<String>,
 
-        /// Output results as JSON (for scripting)
-        #[arg(long)]
-        json: bool,
+        /// Output format: human, json, or sarif (unguarded call sites and
+        /// conflicting gateways as code-scanning alerts)
+        #[arg(long, value_name = "FORMAT", default_value = "human", value_parser = ["human", "json", "sarif"])]
+        format: String,
 
-        /// Text content to scan for security threats via the API
+        /// Text content to scan for security threats via the API. Exits 2
+        /// when the content is blocked.
         #[arg(long, conflicts_with = "file")]
         text: Option<String>,
 
-        /// File path to scan for security threats via the API
-        #[arg(long, conflicts_with = "text")]
+        /// File path to scan for security threats via the API (`-` reads
+        /// stdin). Exits 2 when the content is blocked.
+        #[arg(long, conflicts_with = "text", value_hint = clap::ValueHint::FilePath)]
         file: Option<String>,
+
+        /// Only scan files staged in git, for pre-commit hooks
+        #[arg(long, conflicts_with_all = ["text", "file"])]
+        staged: bool,
+
+        /// Exit 2 when the scan finds call sites of this kind. `detected`
+        /// fails on any LLM SDK usage, `unguarded` on any client not routed
+        /// through `PromptGuard`, and `none` always exits 0 after a scan.
+        #[arg(long, value_name = "KIND", default_value = "none", value_parser = ["none", "detected", "unguarded"], conflicts_with_all = ["text", "file"])]
+        fail_on: String,
     },
 
     /// Show current `PromptGuard` status and configuration
     ///
     /// Displays whether `PromptGuard` is active, which providers are configured,
     /// and details about the current setup.
-    Status {
-        /// Output as JSON (for scripting)
-        #[arg(long)]
-        json: bool,
-    },
+    Status,
 
     /// Diagnose common configuration issues
     ///
     /// Checks API key validity, file permissions, security settings,
     /// and other common problems. Run this if something isn't working.
-    Doctor {
-        /// Output as JSON (for scripting)
-        #[arg(long)]
-        json: bool,
-    },
+    Doctor,
 
     /// Re-apply `PromptGuard` transformations to source files
     ///
@@ -149,47 +216,134 @@ enum Commands {
         /// Skip confirmation prompt
         #[arg(short = 'y', long)]
         yes: bool,
+
+        /// Show a diff of the changes without applying them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Only transform files matching these globs (repeatable), e.g. "src/agents/**"
+        #[arg(long = "files", value_name = "GLOB")]
+        files: Vec<String>,
     },
 
     /// Temporarily disable `PromptGuard` (keeps configuration)
     ///
     /// LLM requests will go directly to providers until re-enabled.
-    Disable,
+    Disable {
+        /// Skip confirmation prompt
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
 
     /// Re-enable `PromptGuard` after disabling
     ///
     /// Restores proxy routing for LLM requests.
     Enable {
+        /// Skip confirmation prompt
+        #[arg(short = 'y', long)]
+        yes: bool,
+
         /// Use runtime shims for 100% SDK call coverage (recommended)
         #[arg(long)]
         runtime: bool,
+
+        /// Change no code: set the SDKs' base URL variables (`OPENAI_BASE_URL`,
+        /// `ANTHROPIC_BASE_URL`, ...) in the env file and check the installed
+        /// SDK versions read them
+        #[arg(long, conflicts_with = "runtime")]
+        env_only: bool,
+
+        /// How runtime shims are loaded: entry-points (import added to entry
+        /// files), preload (Node `--require` via start scripts and `NODE_OPTIONS`),
+        /// site (Python `.pth` file in the virtualenv), or docker (variables in
+        /// docker-compose services). Comma-separated.
+        #[arg(long, value_name = "MODE", requires = "runtime", value_delimiter = ',')]
+        inject: Vec<String>,
+
+        /// When the proxy is unreachable: open (clients fall back to the
+        /// provider's own endpoint) or closed (SDK calls fail). Saved in config.
+        #[arg(long, value_name = "MODE", requires = "runtime")]
+        fail_mode: Option<String>,
+
+        /// Log intercepted clients (timestamp, provider, endpoint; no payloads)
+        /// to .promptguard/shim.log. `--shim-log false` turns it off. Saved in config.
+        #[arg(
+            long,
+            value_name = "BOOL",
+            requires = "runtime",
+            num_args = 0..=1,
+            default_missing_value = "true"
+        )]
+        shim_log: Option<bool>,
+
+        /// Also inject the Python shim into test modules and runners
+        /// (conftest.py, test_*.py, tests/), which are skipped by default
+        #[arg(long, requires = "runtime")]
+        include_tests: bool,
     },
 
     /// Completely remove `PromptGuard` from this project
     ///
     /// Reverts all file changes and removes configuration.
-    /// Use git to review changes before confirming.
+    /// With --provider or --files, undoes only the matching transformations
+    /// and leaves the rest of the setup in place.
     Revert {
         /// Skip confirmation prompt
         #[arg(short = 'y', long)]
         yes: bool,
+
+        /// Only undo this provider's transformations (repeatable), e.g. anthropic
+        #[arg(long = "provider", value_name = "PROVIDER")]
+        providers: Vec<String>,
+
+        /// Only undo transformations in files matching these globs (repeatable), e.g. "src/legacy/**"
+        #[arg(long = "files", value_name = "GLOB", conflicts_with = "providers")]
+        files: Vec<String>,
+    },
+
+    /// Remove `PromptGuard` from this project entirely
+    ///
+    /// Undoes code transformations (journal, then `apply` backups), removes
+    /// runtime shims and their injections, env-only variables and the API
+    /// key, then deletes the backups, .promptguard/ and .promptguard.json.
+    Uninstall {
+        /// Skip confirmation prompt
+        #[arg(short = 'y', long)]
+        yes: bool,
     },
 
     /// View and manage `PromptGuard` configuration
     ///
     /// Shows current settings including providers, proxy URL,
-    /// exclude patterns, and metadata.
+    /// exclude patterns, and metadata. `get`, `set` and `unset`
+    /// read and change single settings.
     Config {
-        /// Output as JSON (for scripting)
-        #[arg(long)]
-        json: bool,
+        #[command(subcommand)]
+        action: Option<ConfigSubcommand>,
+
+        /// Use the user config (~/.config/promptguard/config.json) instead of the project's
+        #[arg(long, global = true)]
+        global: bool,
     },
 
     /// Manage API keys
     ///
     /// View, update, or rotate your `PromptGuard` API key.
     /// Keys can be test (`pg_sk_test`_*) or production (`pg_sk_prod`_*).
-    Key,
+    #[command(args_conflicts_with_subcommands = true)]
+    Key {
+        #[command(subcommand)]
+        action: Option<KeySubcommand>,
+
+        /// Rotate the key: mint a new one, write it to the env files, then
+        /// offer to revoke the old one
+        #[arg(long)]
+        rotate: bool,
+
+        /// With --rotate, revoke the old key without asking
+        #[arg(short = 'y', long, requires = "rotate")]
+        yes: bool,
+    },
 
     /// View activity logs from `PromptGuard` API
     ///
@@ -204,54 +358,202 @@ enum Commands {
         #[arg(short = 't', long = "type")]
         log_type: Option<String>,
 
-        /// Output results as JSON (for scripting)
+        /// Only entries newer than this: 30m, 24h, 7d, 2w, a date
+        /// (2024-05-01) or an RFC 3339 timestamp
+        #[arg(long, value_name = "WHEN")]
+        since: Option<String>,
+
+        /// Only requests to this provider (openai, anthropic, ...)
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Only entries with this decision: blocked or allowed
         #[arg(long)]
-        json: bool,
+        decision: Option<String>,
+
+        /// Keep running and print new entries as they arrive (one JSON
+        /// object per line with --json)
+        #[arg(short, long)]
+        follow: bool,
     },
 
     /// Test `PromptGuard` configuration
     ///
-    /// Validates API key, tests proxy connectivity, and verifies
-    /// that your setup is working correctly.
-    Test,
+    /// Validates API key, tests proxy connectivity, and sends a sample
+    /// completion through the proxy for each configured provider, reporting
+    /// status, latency and the `PromptGuard` response headers.
+    Test {
+        /// Only check the health endpoint; don't send a completion
+        #[arg(long, conflicts_with = "attack")]
+        health_only: bool,
+
+        /// Also send canary prompt injections through the proxy and check
+        /// they are blocked; exits with code 2 if any get through
+        #[arg(long)]
+        attack: bool,
+    },
 
     /// Verify end-to-end `PromptGuard` integration
     ///
-    /// Runs connectivity, authentication, threat detection, and PII
-    /// redaction checks against the live API. Use after setup to
-    /// confirm everything works, or in CI to validate the integration.
+    /// Checks that every call site in the managed files still routes through
+    /// the proxy, then runs connectivity, authentication, threat detection,
+    /// and PII redaction checks against the live API. Exits non-zero when
+    /// any check fails, so CI can catch transformations lost in a merge.
     Verify {
-        /// Output results as JSON (for CI/scripting)
+        /// Only check transformations, without calling the API (implied by --air-gapped)
         #[arg(long)]
-        json: bool,
+        offline: bool,
+
+        /// Output format: human, json, or sarif (transformation drift as
+        /// code-scanning alerts)
+        #[arg(long, value_name = "FORMAT", default_value = "human", value_parser = ["human", "json", "sarif"])]
+        format: String,
+    },
+
+    /// Report how many SDK call sites are routed through the proxy
+    ///
+    /// Classifies every detected client construction as guarded by a
+    /// transform, a loaded runtime shim, or env-only base URL variables, and
+    /// lists the rest by file and line.
+    Coverage {
+        /// Exit non-zero when coverage is below this percentage
+        #[arg(long, value_name = "PERCENT")]
+        min: Option<f64>,
+    },
+
+    /// Inventory every LLM SDK client in the project
+    ///
+    /// Lists provider, file, line, client class, nearby model names and
+    /// whether each call site is guarded, as an AI bill of materials for
+    /// compliance reviews.
+    Audit {
+        /// Output format: human, json or csv
+        #[arg(long, default_value = "human")]
+        format: String,
+
+        /// Write the inventory to this file instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<std::path::PathBuf>,
+    },
+
+    /// Write a security review report for the project
+    ///
+    /// Aggregates configuration status, call-site coverage, declared SDKs and
+    /// environment findings into a shareable Markdown or HTML document.
+    Report {
+        /// Output format: md or html (default: from the --output extension, else md)
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Write the report to this file instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<std::path::PathBuf>,
+    },
+
+    /// Check routing in CI with GitHub Actions annotations
+    ///
+    /// Prints an `::error` annotation for every call site not routed through
+    /// `PromptGuard`, and for managed files that no longer exist, then a
+    /// one-line summary. Never prompts. Exits 0 when clean, 2 on findings,
+    /// 3 when .promptguard.json is missing or invalid, 1 otherwise.
+    Ci,
+
+    /// Print a shell completion script
+    ///
+    /// Completes every subcommand and flag. Load it from your shell's
+    /// startup file, e.g. `source <(promptguard completions bash)` in
+    /// ~/.bashrc, or write it to a directory on $fpath for zsh.
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_parser = ["bash", "zsh", "fish", "powershell"])]
+        shell: String,
+    },
+
+    /// Check commits for unguarded LLM calls with a pre-commit hook
+    ///
+    /// Runs `promptguard scan --staged --fail-on unguarded` before each
+    /// commit. Uses the pre-commit framework or husky when the project has
+    /// them, otherwise a plain git hook.
+    Hook {
+        #[command(subcommand)]
+        action: HookSubcommand,
+    },
+
+    /// Generate man pages and a Markdown CLI reference
+    #[command(hide = true)]
+    GenDocs {
+        /// Directory for `man/*.1` and `cli-reference.md`
+        #[arg(long, default_value = "target/docs", value_hint = clap::ValueHint::DirPath)]
+        out_dir: std::path::PathBuf,
+    },
+
+    /// Benchmark threat detection accuracy and latency
+    ///
+    /// Sends labeled prompts to the configured `PromptGuard` endpoint (or
+    /// local heuristics with --offline / in air-gapped mode) and reports
+    /// accuracy, precision, recall, per-request latency and a per-category
+    /// breakdown.
+    Benchmark {
+        /// Labeled prompts: CSV with `prompt` and `label` columns, or JSONL
+        /// with `prompt` and `label` fields (built-in set if omitted)
+        #[arg(long, value_hint = clap::ValueHint::FilePath)]
+        dataset: Option<String>,
+
+        /// Use local heuristics instead of the API
+        #[arg(long)]
+        offline: bool,
+
+        /// Only run the first N samples (-v lists misclassified ones)
+        #[arg(long)]
+        limit: Option<usize>,
     },
 
     /// Check for CLI updates
     ///
-    /// Checks GitHub releases for a newer version and provides
-    /// instructions for updating.
-    Update,
+    /// Checks GitHub releases for a newer version on the release channel and
+    /// offers to install it. The replaced binary is kept so
+    /// `promptguard update --rollback` can restore it.
+    Update {
+        /// Release channel to follow from now on
+        #[arg(long, value_parser = ["stable", "beta"], conflicts_with = "rollback")]
+        channel: Option<String>,
+
+        /// Restore the binary the last update replaced
+        #[arg(long)]
+        rollback: bool,
+    },
 
     /// Redact PII and sensitive data from text
     ///
     /// Calls the `PromptGuard` API to identify and redact sensitive information
     /// like emails, phone numbers, SSNs, credit cards, etc.
     Redact {
+        /// `-` to filter stdin to stdout (`cat chat.log | promptguard redact -`),
+        /// or a file to redact to stdout; only the redacted text is printed
+        #[arg(value_name = "INPUT", conflicts_with_all = ["text", "file", "dir"])]
+        input: Option<String>,
+
         /// Text content to redact
         #[arg(long, conflicts_with = "file")]
         text: Option<String>,
 
-        /// File path to read and redact
-        #[arg(long, conflicts_with = "text")]
+        /// File path to read and redact (`-` reads stdin)
+        #[arg(long, conflicts_with = "text", value_hint = clap::ValueHint::FilePath)]
         file: Option<String>,
 
         /// Output file path (if not provided, prints to stdout)
-        #[arg(short, long)]
+        #[arg(short, long, value_hint = clap::ValueHint::FilePath, conflicts_with = "dir")]
         output: Option<String>,
 
-        /// Output results as JSON (for scripting)
-        #[arg(long)]
-        json: bool,
+        /// Redact every file in this directory matching --glob, writing
+        /// `name.redacted.ext` copies alongside the originals
+        #[arg(long, conflicts_with_all = ["text", "file"], value_hint = clap::ValueHint::DirPath)]
+        dir: Option<String>,
+
+        /// Files to redact with --dir, matched against the file name or the
+        /// path relative to the directory
+        #[arg(long, default_value = "*.txt", requires = "dir")]
+        glob: String,
     },
 
     /// Run adversarial security tests against your AI application
@@ -271,10 +573,6 @@ enum Commands {
         #[arg(long, default_value = "human")]
         format: String,
 
-        /// Show detailed output for each test
-        #[arg(short, long)]
-        verbose: bool,
-
         /// Run a specific test by name
         #[arg(long)]
         test: Option<String>,
@@ -301,13 +599,14 @@ enum Commands {
     /// Define guardrails in YAML, version in git, and apply via CLI.
     /// Supports apply, diff, and export operations.
     Policy {
-        /// Action to perform: apply, diff, or export
+        /// Action to perform: apply, diff, export, list, show, pull, or push
         #[command(subcommand)]
         action: PolicySubcommand,
 
-        /// Project ID to manage policies for
+        /// Project ID for apply, diff and export [default: the project
+        /// .promptguard.json is linked to]
         #[arg(long, global = true)]
-        project_id: String,
+        project_id: Option<String>,
 
         /// `PromptGuard` API key (or uses configured key)
         #[arg(long, global = true)]
@@ -328,12 +627,29 @@ enum Commands {
         transport: String,
     },
 
+    /// Start a language server that flags unrouted SDK calls in editors
+    ///
+    /// Publishes a diagnostic for every SDK client that doesn't route
+    /// through `PromptGuard` when a file is opened or saved, with a quick
+    /// fix that applies the transform to the buffer. Point any LSP client
+    /// at `promptguard lsp`.
+    Lsp {
+        /// Transport type (currently only 'stdio' is supported)
+        #[arg(short, long, default_value = "stdio")]
+        transport: String,
+
+        /// Same as '--transport stdio', for clients that pass it
+        #[arg(long, hide = true)]
+        stdio: bool,
+    },
+
     /// Authenticate with `PromptGuard` and store credentials globally
     ///
-    /// Saves your API key to `~/.promptguard/credentials.json` so all
-    /// commands and projects can use it without per-project setup.
+    /// Opens the browser to approve this device, then saves the access token
+    /// to `~/.promptguard/credentials.json` so all commands and projects can
+    /// use it without per-project setup. `--api-key` saves a key instead.
     Login {
-        /// API key to authenticate with (or enter interactively)
+        /// API key to authenticate with, instead of the browser
         #[arg(long)]
         api_key: Option<String>,
 
@@ -341,40 +657,29 @@ enum Commands {
         #[arg(long)]
         base_url: Option<String>,
 
-        /// Output results as JSON
-        #[arg(long)]
-        json: bool,
+        /// Print the approval URL without opening a browser
+        #[arg(long, conflicts_with = "api_key")]
+        no_browser: bool,
     },
 
     /// Remove stored `PromptGuard` credentials
     ///
     /// Deletes `~/.promptguard/credentials.json`.
-    Logout {
-        /// Output results as JSON
-        #[arg(long)]
-        json: bool,
-    },
+    Logout,
 
     /// Show current authentication status
     ///
     /// Displays which API key is active, its source (env, project, global),
     /// and whether the API is reachable.
-    Whoami {
-        /// Output results as JSON
-        #[arg(long)]
-        json: bool,
-    },
+    Whoami,
 
     /// Manage `PromptGuard` projects
     ///
-    /// List, select, and view projects associated with your account.
+    /// List, create, and select projects associated with your account, and
+    /// link this repo to one.
     Projects {
         #[command(subcommand)]
         action: ProjectsSubcommand,
-
-        /// Output results as JSON
-        #[arg(long, global = true)]
-        json: bool,
     },
 
     /// View recent security events
@@ -389,20 +694,343 @@ enum Commands {
         /// Filter by event type
         #[arg(short = 't', long = "type")]
         event_type: Option<String>,
+    },
+
+    /// Show proxy usage metrics for a time window
+    ///
+    /// Request counts, block rate, top threat types and latency from the
+    /// `PromptGuard` API, as a terminal summary of the web dashboard.
+    Stats {
+        /// Start of the window: a duration (30m, 24h, 7d, 2w), a date or an
+        /// RFC 3339 timestamp
+        #[arg(long, default_value = "24h")]
+        since: String,
+
+        /// Number of threat types to list
+        #[arg(long, default_value = "5")]
+        top: usize,
+    },
+
+    /// Open the `PromptGuard` dashboard in your browser (--json prints the URL instead)
+    Dashboard,
+
+    /// Find API keys hardcoded in source files
+    ///
+    /// Reports inline `PromptGuard` and LLM provider keys (`sk-...`,
+    /// `sk-ant-...`, ...) and exits 2 when any are found. `migrate` moves them
+    /// into the env file and rewrites the code to read them from the environment.
+    Secrets {
+        #[command(subcommand)]
+        action: Option<SecretsSubcommand>,
+    },
+
+    /// List, restore, diff and prune source file backups
+    ///
+    /// `apply` snapshots every file it changes into .promptguard/backups/,
+    /// recorded in a manifest with the original path, hash, time and operation.
+    Backup {
+        #[command(subcommand)]
+        action: BackupSubcommand,
+    },
 
-        /// Output results as JSON
+    /// Manage at-rest encryption of local state files
+    ///
+    /// Encrypts credentials, caches, and journals stored under `.promptguard/`
+    /// and `~/.promptguard/`. Shim source files are never encrypted.
+    State {
+        #[command(subcommand)]
+        action: StateSubcommand,
+    },
+
+    /// Generate, inject, remove and inspect the runtime shims directly
+    Shim {
+        #[command(subcommand)]
+        action: ShimSubcommand,
+    },
+
+    /// Run a seeded, offline walkthrough of the full workflow
+    ///
+    /// Generates a synthetic project in a temp directory, then scans,
+    /// initializes, and protects it with simulated API responses. The same
+    /// seed always produces the same demo.
+    Demo {
+        /// Seed for the generated project and simulated responses
+        #[arg(long, default_value = "42")]
+        seed: u64,
+
+        /// Keep the generated project instead of deleting it
         #[arg(long)]
-        json: bool,
+        keep: bool,
+    },
+
+    /// Produce an offline bundle for air-gapped networks
+    ///
+    /// Writes the CLI binary, local detection heuristics, docs, and a
+    /// checksum manifest into a directory that can be copied offline.
+    PackageOffline {
+        /// Output directory (default: ./promptguard-offline-v<version>-<os>-<arch>)
+        #[arg(short, long, value_hint = clap::ValueHint::DirPath)]
+        output: Option<String>,
     },
+}
+
+/// A command name and whether a parsed command is that command
+type NetworkCommand = (&'static str, fn(&Commands) -> bool);
+
+/// Commands that need network access, named as `promptguard <name>` in
+/// air-gapped errors and in the offline bundle's guide
+const NETWORK_COMMANDS: &[NetworkCommand] = &[
+    (
+        "scan --text/--file",
+        |c| matches!(c, Commands::Scan { text, file, .. } if text.is_some() || file.is_some()),
+    ),
+    ("logs", |c| matches!(c, Commands::Logs { .. })),
+    ("test", |c| matches!(c, Commands::Test { .. })),
+    ("update", |c| {
+        matches!(
+            c,
+            Commands::Update {
+                rollback: false,
+                ..
+            }
+        )
+    }),
+    ("redact", |c| matches!(c, Commands::Redact { .. })),
+    ("redteam", |c| matches!(c, Commands::Redteam { .. })),
+    ("policy", |c| matches!(c, Commands::Policy { .. })),
+    ("key --rotate", |c| {
+        matches!(c, Commands::Key { rotate: true, .. })
+    }),
+    ("login", |c| matches!(c, Commands::Login { .. })),
+    ("whoami", |c| matches!(c, Commands::Whoami)),
+    ("projects", |c| matches!(c, Commands::Projects { .. })),
+    ("events", |c| matches!(c, Commands::Events { .. })),
+    ("stats", |c| matches!(c, Commands::Stats { .. })),
+    ("dashboard", |c| matches!(c, Commands::Dashboard)),
+];
+
+impl Commands {
+    /// Name of the command if it needs network access to run
+    fn network_feature(&self) -> Option<String> {
+        NETWORK_COMMANDS
+            .iter()
+            .find(|(_, needs_network)| needs_network(self))
+            .map(|(name, _)| format!("promptguard {name}"))
+    }
+
+    /// Name of the command if it has no `--json` result, so the flag is
+    /// refused instead of leaving stdout with a fragment of human output
+    fn without_json(&self) -> Option<&'static str> {
+        Some(match self {
+            Self::Uninstall { .. } => "uninstall",
+            Self::Audit { .. } => "audit (use --format json)",
+            Self::Report { .. } => "report",
+            Self::Ci => "ci",
+            Self::Completions { .. } => "completions",
+            Self::GenDocs { .. } => "gen-docs",
+            Self::Update { .. } => "update",
+            Self::Policy { .. } => "policy",
+            Self::Mcp { .. } => "mcp",
+            Self::Lsp { .. } => "lsp",
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Subcommand)]
+enum BackupSubcommand {
+    /// List snapshots, oldest first
+    List,
+
+    /// Restore a snapshot by id, or the newest snapshot of a file
+    Restore {
+        /// Backup id or file path
+        target: String,
 
-    /// Open the `PromptGuard` dashboard in your browser
-    Dashboard {
-        /// Output the URL as JSON instead of opening browser
+        /// Skip confirmation prompt
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+
+    /// Delete old snapshots
+    Prune {
+        /// Snapshots to keep per file, newest first
+        #[arg(long, default_value_t = 5)]
+        keep: usize,
+
+        /// Also delete snapshots older than this (e.g. 30d, 12h)
+        #[arg(long, value_name = "AGE")]
+        older_than: Option<String>,
+    },
+
+    /// Show changes to a file since a snapshot
+    Diff {
+        /// Backup id or file path
+        target: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum SecretsSubcommand {
+    /// Move hardcoded keys to the env file and read them from the environment
+    Migrate {
+        /// Skip confirmation prompt
+        #[arg(short = 'y', long)]
+        yes: bool,
+
+        /// Show the changes as a diff without writing them
         #[arg(long)]
-        json: bool,
+        dry_run: bool,
     },
 }
 
+#[derive(Subcommand)]
+enum StateSubcommand {
+    /// Generate a state key and encrypt existing state files
+    Encrypt,
+
+    /// Decrypt all state files and remove the state key
+    Decrypt,
+
+    /// Show whether state encryption is enabled
+    Status,
+}
+
+#[derive(Subcommand)]
+enum ConfigSubcommand {
+    /// Print a setting (`providers`, `proxy_url`, `proxy_url.anthropic`, ...)
+    Get { key: String },
+
+    /// Change a setting. Values are checked against the config schema:
+    /// `true`/`false` for flags, `openai,anthropic` or JSON for lists
+    Set { key: String, value: String },
+
+    /// Reset a setting to its default, or remove a map entry
+    Unset { key: String },
+
+    /// Check the config against the JSON Schema; exits non-zero if it is invalid
+    Validate,
+
+    /// Rewrite a config from an older version in the current layout now,
+    /// moving a stored API key to the env file
+    Migrate,
+
+    /// Print the JSON Schema for .promptguard.json
+    Schema,
+
+    /// Export the settings as a template for other repositories (without
+    /// this project's metadata)
+    Export {
+        /// Leave the API key out of the export
+        #[arg(long)]
+        no_secrets: bool,
+
+        /// Write the export to this file instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<std::path::PathBuf>,
+    },
+
+    /// Replace the project's settings with an exported config, keeping its
+    /// metadata; creates the config if the project has none
+    Import {
+        /// Exported config to read (`-` for stdin)
+        file: std::path::PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum HookSubcommand {
+    /// Add the scan to the project's pre-commit hook
+    Install {
+        /// Where to install: git, pre-commit or husky (detected by default)
+        #[arg(long, value_parser = ["git", "pre-commit", "husky"])]
+        manager: Option<String>,
+    },
+
+    /// Remove the scan from the pre-commit hook, keeping any other checks
+    Uninstall {
+        /// Where to remove it from: git, pre-commit or husky (detected by default)
+        #[arg(long, value_parser = ["git", "pre-commit", "husky"])]
+        manager: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ShimSubcommand {
+    /// Write the shim files for the project's languages into .promptguard/,
+    /// without editing any source
+    Generate {
+        /// Also generate what these injection modes load: preload, site, docker
+        #[arg(long, value_name = "MODE", value_delimiter = ',')]
+        inject: Vec<String>,
+    },
+
+    /// Load the generated shims from entry points, or the loaders --inject names
+    Inject {
+        /// How to load the shims: entry-points (default), preload, site, docker
+        #[arg(long, value_name = "MODE", value_delimiter = ',')]
+        inject: Vec<String>,
+
+        /// Also inject into Python test modules and runners
+        #[arg(long)]
+        include_tests: bool,
+
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Remove shim imports and loaders, and delete .promptguard/
+    Remove {
+        /// Leave the generated shim files in place
+        #[arg(long)]
+        keep_files: bool,
+
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Show the installed shim files and where they are loaded from
+    Status,
+
+    /// Regenerate shims created by another CLI version, keeping the configuration
+    Upgrade {
+        /// Only report whether the shims are outdated (fails if they are)
+        #[arg(long)]
+        check: bool,
+
+        /// Regenerate even if the shims match this CLI version
+        #[arg(long, conflicts_with = "check")]
+        force: bool,
+
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeySubcommand {
+    /// Write a key to the env files of the active profile
+    ///
+    /// `promptguard --profile prod key set pg_sk_prod_...` writes to the prod
+    /// profile's env files. Production keys are refused for files git would
+    /// commit.
+    Set {
+        /// The `pg_sk_test_` or `pg_sk_prod_` key
+        key: String,
+
+        /// Write to these env files instead; repeat or comma-separate
+        #[arg(long, value_delimiter = ',', value_hint = clap::ValueHint::FilePath)]
+        env_file: Vec<String>,
+    },
+
+    /// Show the key type in every env file of the config and its profiles;
+    /// exits non-zero if a production key is in a committed file
+    List,
+}
+
 #[derive(Subcommand)]
 enum ProjectsSubcommand {
     /// List all projects
@@ -413,6 +1041,24 @@ enum ProjectsSubcommand {
         /// Project ID to select
         project_id: String,
     },
+
+    /// Create a project
+    Create {
+        /// Name of the new project
+        name: String,
+
+        /// Also link this repo to the new project
+        #[arg(long)]
+        link: bool,
+    },
+
+    /// Link this repo to a project, scoping its logs and stats
+    ///
+    /// Writes `project_id` to .promptguard.json.
+    Link {
+        /// Project ID to link
+        project_id: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -420,6 +1066,7 @@ enum PolicySubcommand {
     /// Apply a YAML policy file to the project
     Apply {
         /// Path to the YAML policy file
+        #[arg(value_hint = clap::ValueHint::FilePath)]
         file: String,
 
         /// Preview changes without applying
@@ -430,23 +1077,106 @@ enum PolicySubcommand {
     /// Show differences between a YAML file and the live config
     Diff {
         /// Path to the YAML policy file
+        #[arg(value_hint = clap::ValueHint::FilePath)]
         file: String,
     },
 
     /// Export the current live config as YAML (to stdout)
     Export,
+
+    /// List the policy presets on your account
+    List,
+
+    /// Print a policy preset as YAML
+    Show {
+        /// Preset name
+        name: String,
+    },
+
+    /// Download a policy preset into the repo [default: policies/<name>.yaml]
+    Pull {
+        /// Preset name
+        name: String,
+
+        /// File to write instead of policies/<name>.yaml
+        #[arg(short, long, value_hint = clap::ValueHint::FilePath)]
+        output: Option<String>,
+    },
+
+    /// Create or update a policy preset from a YAML file
+    Push {
+        /// Path to the YAML policy file
+        #[arg(value_hint = clap::ValueHint::FilePath)]
+        file: String,
+
+        /// Preset name [default: the file's `name`, else its file name]
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Show what would change without pushing
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 fn main() {
     let cli = Cli::parse();
+    let ci = cli.ci || matches!(cli.command, Commands::Ci);
+    // Prompts need a terminal to draw on; on a pipe they fail at once
+    let no_input = cli.no_input || !std::io::stdin().is_terminal();
 
     // Initialize output settings based on global flags
     output::Output::init(
         cli.verbose,
         cli.quiet,
-        cli.no_color || std::env::var("NO_COLOR").is_ok(),
+        ci || cli.no_color || std::env::var("NO_COLOR").is_ok_and(|v| !v.is_empty()),
+        ci,
+        no_input,
+        cli.json,
+        output::ProgressStyle::parse(&cli.progress).unwrap_or_default(),
     );
+    airgap::init(cli.air_gapped);
+
+    if let Some(ref path) = cli.path {
+        match path.canonicalize() {
+            Ok(dir) if dir.is_dir() => config::set_project_root(dir),
+            _ => exit_with(
+                &error::PromptGuardError::Config(format!(
+                    "--path {} is not a directory",
+                    path.display()
+                )),
+                ci,
+                cli.json,
+            ),
+        }
+    }
+
+    if let Some(profile) = cli.profile.clone() {
+        config::set_profile(profile);
+    }
+
+    if cli.json {
+        if let Some(name) = cli.command.without_json() {
+            exit_with(
+                &error::PromptGuardError::Custom(format!(
+                    "--json is not supported by promptguard {name}"
+                )),
+                ci,
+                true,
+            );
+        }
+    }
+
+    if let Some(feature) = cli.command.network_feature() {
+        if let Err(e) = airgap::ensure_online(&feature) {
+            if !cli.json {
+                eprintln!("Local commands (scan, init, apply, revert, status) work offline.");
+            }
+            exit_with(&e, ci, cli.json);
+        }
+    }
 
+    let json = cli.json;
     let result = match cli.command {
         Commands::Init {
             provider,
@@ -458,67 +1188,200 @@ fn main() {
             force,
             exclude,
             framework,
+            per_package,
+            project,
+            interactive,
+            offline,
         } => InitCommand {
             provider,
             api_key,
-            base_url,
+            base_url: base_url.unwrap_or_else(config::user::default_base_url),
             env_file,
             auto,
             dry_run,
             force,
             exclude,
             framework,
+            per_package,
+            project,
+            interactive,
+            offline,
+            json,
         }
         .execute(),
 
         Commands::Scan {
             provider,
-            json,
+            format,
             text,
             file,
+            staged,
+            fail_on,
         } => ScanCommand {
             provider,
-            json,
+            json: json || format == "json",
+            sarif: format == "sarif",
             text,
             file,
+            staged,
+            fail_on,
         }
         .execute(),
 
-        Commands::Status { json } => StatusCommand { json }.execute(),
-
-        Commands::Doctor { json } => DoctorCommand { json }.execute(),
+        Commands::Status => StatusCommand { json }.execute(),
 
-        Commands::Apply { yes } => ApplyCommand { yes }.execute(),
+        Commands::Doctor => DoctorCommand { json }.execute(),
 
-        Commands::Revert { yes } => RevertCommand { yes }.execute(),
+        Commands::Apply {
+            yes,
+            dry_run,
+            files,
+        } => ApplyCommand {
+            yes,
+            dry_run,
+            files,
+            json,
+        }
+        .execute(),
 
-        Commands::Disable => DisableCommand::execute(),
-        Commands::Enable { runtime } => EnableCommand { runtime }.execute(),
-        Commands::Config { json } => ConfigCommand { json }.execute(),
-        Commands::Key => KeyCommand::execute(),
+        Commands::Revert {
+            yes,
+            providers,
+            files,
+        } => RevertCommand {
+            yes,
+            providers,
+            files,
+            json,
+        }
+        .execute(),
+        Commands::Uninstall { yes } => UninstallCommand { yes }.execute(),
+
+        Commands::Disable { yes } => DisableCommand { yes, json }.execute(),
+        Commands::Enable {
+            yes,
+            runtime,
+            env_only,
+            inject,
+            fail_mode,
+            shim_log,
+            include_tests,
+        } => EnableCommand {
+            yes,
+            runtime,
+            env_only,
+            inject,
+            fail_mode,
+            shim_log,
+            include_tests,
+            json,
+        }
+        .execute(),
+        Commands::Config { action, global } => ConfigCommand {
+            action: match action {
+                None => ConfigAction::Show,
+                Some(ConfigSubcommand::Get { key }) => ConfigAction::Get { key },
+                Some(ConfigSubcommand::Set { key, value }) => ConfigAction::Set { key, value },
+                Some(ConfigSubcommand::Unset { key }) => ConfigAction::Unset { key },
+                Some(ConfigSubcommand::Validate) => ConfigAction::Validate,
+                Some(ConfigSubcommand::Migrate) => ConfigAction::Migrate,
+                Some(ConfigSubcommand::Schema) => ConfigAction::Schema,
+                Some(ConfigSubcommand::Export { no_secrets, output }) => {
+                    ConfigAction::Export { no_secrets, output }
+                },
+                Some(ConfigSubcommand::Import { file }) => ConfigAction::Import { file },
+            },
+            json,
+            global,
+        }
+        .execute(),
+        Commands::Key {
+            action,
+            rotate,
+            yes,
+        } => KeyCommand {
+            rotate,
+            yes,
+            json,
+            action: action.map(|action| match action {
+                KeySubcommand::Set { key, env_file } => KeyAction::Set { key, env_file },
+                KeySubcommand::List => KeyAction::List,
+            }),
+        }
+        .execute(),
         Commands::Logs {
             limit,
             log_type,
-            json,
+            since,
+            provider,
+            decision,
+            follow,
         } => LogsCommand {
             limit,
             log_type,
+            since,
+            provider,
+            decision,
+            follow,
+            json,
+        }
+        .execute(),
+        Commands::Test {
+            health_only,
+            attack,
+        } => TestCommand {
+            health_only,
+            attack,
+            json,
+        }
+        .execute(),
+        Commands::Verify { offline, format } => VerifyCommand {
+            json: json || format == "json",
+            sarif: format == "sarif",
+            offline,
+        }
+        .execute(),
+        Commands::Update { channel, rollback } => UpdateCommand { channel, rollback }.execute(),
+        Commands::Coverage { min } => CoverageCommand { min, json }.execute(),
+        Commands::Audit { format, output } => AuditCommand { format, output }.execute(),
+        Commands::Report { format, output } => ReportCommand { format, output }.execute(),
+        Commands::Completions { shell } => CompletionsCommand {
+            shell,
+            command: Cli::command(),
+        }
+        .execute(),
+        Commands::GenDocs { out_dir } => GenDocsCommand {
+            out_dir,
+            command: Cli::command(),
+        }
+        .execute(),
+        Commands::Benchmark {
+            dataset,
+            offline,
+            limit,
+        } => BenchmarkCommand {
+            dataset,
+            offline,
+            limit,
+            verbose: cli.verbose > 0,
             json,
         }
         .execute(),
-        Commands::Test => TestCommand::execute(),
-        Commands::Verify { json } => VerifyCommand { json }.execute(),
-        Commands::Update => UpdateCommand.execute(),
 
         Commands::Redact {
+            input,
             text,
             file,
             output,
-            json,
+            dir,
+            glob,
         } => RedactCommand {
+            input,
             text,
             file,
             output,
+            dir,
+            glob,
             json,
         }
         .execute(),
@@ -527,7 +1390,6 @@ fn main() {
             target_url,
             api_key,
             format,
-            verbose,
             test,
             prompt,
             preset,
@@ -537,7 +1399,7 @@ fn main() {
             target_url,
             api_key,
             output_format: format,
-            verbose,
+            verbose: cli.verbose > 0,
             test_name: test,
             custom_prompt: prompt,
             preset,
@@ -556,6 +1418,18 @@ fn main() {
                 PolicySubcommand::Apply { file, dry_run } => PolicyAction::Apply { file, dry_run },
                 PolicySubcommand::Diff { file } => PolicyAction::Diff { file },
                 PolicySubcommand::Export => PolicyAction::Export,
+                PolicySubcommand::List => PolicyAction::List,
+                PolicySubcommand::Show { name } => PolicyAction::Show { name },
+                PolicySubcommand::Pull { name, output } => PolicyAction::Pull { name, output },
+                PolicySubcommand::Push {
+                    file,
+                    name,
+                    dry_run,
+                } => PolicyAction::Push {
+                    file,
+                    name,
+                    dry_run,
+                },
             };
             PolicyCommand {
                 action: policy_action,
@@ -567,26 +1441,30 @@ fn main() {
         },
 
         Commands::Mcp { transport } => McpCommand { transport }.execute(),
+        Commands::Lsp { transport, .. } => LspCommand { transport }.execute(),
 
         Commands::Login {
             api_key,
             base_url,
-            json,
+            no_browser,
         } => LoginCommand {
             api_key,
             base_url,
+            no_browser,
             json,
         }
         .execute(),
 
-        Commands::Logout { json } => LogoutCommand { json }.execute(),
+        Commands::Logout => LogoutCommand { json }.execute(),
 
-        Commands::Whoami { json } => WhoamiCommand { json }.execute(),
+        Commands::Whoami => WhoamiCommand { json }.execute(),
 
-        Commands::Projects { action, json } => {
+        Commands::Projects { action } => {
             let projects_action = match action {
                 ProjectsSubcommand::List => ProjectsAction::List,
                 ProjectsSubcommand::Select { project_id } => ProjectsAction::Select { project_id },
+                ProjectsSubcommand::Create { name, link } => ProjectsAction::Create { name, link },
+                ProjectsSubcommand::Link { project_id } => ProjectsAction::Link { project_id },
             };
             ProjectsCommand {
                 action: projects_action,
@@ -595,22 +1473,158 @@ fn main() {
             .execute()
         },
 
-        Commands::Events {
+        Commands::Stats { since, top } => StatsCommand { since, top, json }.execute(),
+
+        Commands::Events { limit, event_type } => EventsCommand {
             limit,
             event_type,
             json,
-        } => EventsCommand {
-            limit,
-            event_type,
+        }
+        .execute(),
+
+        Commands::Dashboard => DashboardCommand { json }.execute(),
+
+        Commands::Secrets { action } => SecretsCommand {
+            action: match action {
+                None => SecretsAction::Scan,
+                Some(SecretsSubcommand::Migrate { yes, dry_run }) => {
+                    SecretsAction::Migrate { yes, dry_run }
+                },
+            },
             json,
         }
         .execute(),
 
-        Commands::Dashboard { json } => DashboardCommand { json }.execute(),
+        Commands::Backup { action } => {
+            let backup_action = match action {
+                BackupSubcommand::List => BackupAction::List,
+                BackupSubcommand::Restore { target, yes } => BackupAction::Restore { target, yes },
+                BackupSubcommand::Prune { keep, older_than } => {
+                    BackupAction::Prune { keep, older_than }
+                },
+                BackupSubcommand::Diff { target } => BackupAction::Diff { target },
+            };
+            BackupCommand {
+                action: backup_action,
+                json,
+            }
+            .execute()
+        },
+
+        Commands::State { action } => {
+            let state_action = match action {
+                StateSubcommand::Encrypt => StateAction::Encrypt,
+                StateSubcommand::Decrypt => StateAction::Decrypt,
+                StateSubcommand::Status => StateAction::Status,
+            };
+            StateCommand {
+                action: state_action,
+                json,
+            }
+            .execute()
+        },
+
+        Commands::Hook { action } => {
+            let (action, manager) = match action {
+                HookSubcommand::Install { manager } => (HookAction::Install, manager),
+                HookSubcommand::Uninstall { manager } => (HookAction::Uninstall, manager),
+            };
+            manager
+                .as_deref()
+                .map(HookManager::parse)
+                .transpose()
+                .and_then(|manager| {
+                    HookCommand {
+                        action,
+                        manager,
+                        json,
+                    }
+                    .execute()
+                })
+        },
+
+        Commands::Shim { action } => match action {
+            ShimSubcommand::Generate { inject } => ShimCommand {
+                action: ShimAction::Generate { inject },
+                yes: true,
+                json,
+            }
+            .execute(),
+            ShimSubcommand::Inject {
+                inject,
+                include_tests,
+                yes,
+            } => ShimCommand {
+                action: ShimAction::Inject {
+                    inject,
+                    include_tests,
+                },
+                yes,
+                json,
+            }
+            .execute(),
+            ShimSubcommand::Remove { keep_files, yes } => ShimCommand {
+                action: ShimAction::Remove { keep_files },
+                yes,
+                json,
+            }
+            .execute(),
+            ShimSubcommand::Status => ShimCommand {
+                action: ShimAction::Status,
+                yes: true,
+                json,
+            }
+            .execute(),
+            ShimSubcommand::Upgrade { check, force, yes } => ShimCommand {
+                action: ShimAction::Upgrade { check, force },
+                yes,
+                json,
+            }
+            .execute(),
+        },
+
+        Commands::Demo { seed, keep } => DemoCommand { seed, keep, json }.execute(),
+
+        Commands::Ci => CiCommand.execute(),
+
+        Commands::PackageOffline { output } => PackageOfflineCommand {
+            output,
+            online_commands: NETWORK_COMMANDS.iter().map(|(name, _)| *name).collect(),
+            json,
+        }
+        .execute(),
     };
 
     if let Err(e) = result {
-        eprintln!("Error: {e}");
-        std::process::exit(1);
+        exit_with(&e, ci, json);
+    }
+}
+
+/// Report `e` on stderr with its stable code, as a JSON object with
+/// `--json`, and exit with the matching status
+fn exit_with(e: &error::PromptGuardError, ci: bool, json: bool) -> ! {
+    let exit_code = if ci { e.ci_exit_code() } else { e.exit_code() };
+    if json {
+        let mut error = serde_json::json!({
+            "code": e.code(),
+            "message": e.to_string(),
+            "exit_code": exit_code,
+        });
+        if let error::PromptGuardError::QuotaExceeded(info) = e {
+            error["quota"] = serde_json::json!({
+                "current_plan": info.current_plan,
+                "requests_used": info.requests_used,
+                "requests_limit": info.requests_limit,
+                "upgrade_url": info.upgrade_url,
+            });
+        }
+        let report = serde_json::json!({
+            "schema_version": output::JSON_SCHEMA_VERSION,
+            "error": error,
+        });
+        eprintln!("{report}");
+    } else {
+        eprintln!("Error [{}]: {e}", e.code());
     }
+    std::process::exit(exit_code);
 }
@@ -1,29 +1,65 @@
 use crate::error::{PromptGuardError, Result};
+use crate::logging::{self, Level};
 use colored::{ColoredString, Colorize};
-use std::io::{self, Write};
+use dialoguer::theme::{ColorfulTheme, SimpleTheme, Theme};
+use dialoguer::{Confirm, Input, MultiSelect, Select};
+use std::io::{self, IsTerminal};
 use std::sync::OnceLock;
 
+mod progress;
+pub mod sarif;
+mod table;
+pub use progress::{Progress, ProgressStyle};
+pub use table::Table;
+
+/// Bumped when a field of a command's `--json` output is removed or changes meaning
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
 /// Global output configuration
 static OUTPUT_CONFIG: OnceLock<OutputConfig> = OnceLock::new();
 
 #[derive(Debug, Clone, Default)]
+#[allow(clippy::struct_excessive_bools)]
 struct OutputConfig {
     verbose: u8,
     quiet: bool,
     no_color: bool,
+    /// `--ci`: optional prompts take their default, confirmations need `--yes`
+    non_interactive: bool,
+    /// `--no-input`, or stdin is not a terminal: prompts fail instead of
+    /// waiting for an answer that never comes
+    no_input: bool,
+    /// `--json`: stdout carries only the JSON result, so progress messages
+    /// are dropped and warnings go to stderr
+    json: bool,
+    progress: ProgressStyle,
 }
 
 pub struct Output;
 
 impl Output {
     /// Initialize output settings (call once at startup)
-    pub fn init(verbose: u8, quiet: bool, no_color: bool) {
+    #[allow(clippy::fn_params_excessive_bools, clippy::too_many_arguments)]
+    pub fn init(
+        verbose: u8,
+        quiet: bool,
+        no_color: bool,
+        non_interactive: bool,
+        no_input: bool,
+        json: bool,
+        progress: ProgressStyle,
+    ) {
         let config = OutputConfig {
             verbose,
             quiet,
             no_color,
+            non_interactive,
+            no_input,
+            json,
+            progress,
         };
         let _ = OUTPUT_CONFIG.set(config);
+        logging::set_level(Level::from_flags(verbose, quiet));
 
         // Disable colors globally if requested
         if no_color {
@@ -36,7 +72,30 @@ impl Output {
     }
 
     fn is_quiet() -> bool {
-        Self::config().quiet
+        Self::config().quiet || Self::config().json
+    }
+
+    /// Whether `--json` was passed
+    pub fn is_json() -> bool {
+        Self::config().json
+    }
+
+    /// Print a command's `--json` result with its `schema_version`; lists
+    /// are wrapped as `{"items": [...]}` so they can carry it too
+    pub fn json(value: serde_json::Value) -> Result<()> {
+        let mut map = match value {
+            serde_json::Value::Object(map) => map,
+            items => serde_json::Map::from_iter([("items".to_string(), items)]),
+        };
+        map.insert("schema_version".to_string(), JSON_SCHEMA_VERSION.into());
+        let value = serde_json::Value::Object(map);
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        Ok(())
+    }
+
+    /// `--progress`: a bar, JSON events, or nothing
+    pub fn progress_style() -> ProgressStyle {
+        Self::config().progress
     }
 
     fn verbosity() -> u8 {
@@ -56,7 +115,7 @@ impl Output {
         if Self::is_quiet() {
             return;
         }
-        let colored_text = Self::colorize(text, |s| s.cyan().bold());
+        let colored_text = Self::colorize(&Self::plain(text), |s| s.cyan().bold());
         let separator = Self::colorize(&"=".repeat(50), |s| s.cyan());
         println!("\n{colored_text}");
         println!("{separator}");
@@ -66,26 +125,51 @@ impl Output {
         if Self::is_quiet() {
             return;
         }
-        let bold_title = Self::colorize(title, |s| s.bold());
-        println!("\n{icon} {bold_title}");
+        let bold_title = Self::colorize(&Self::plain(title), |s| s.bold());
+        if Self::config().no_color {
+            println!("\n{bold_title}");
+        } else {
+            println!("\n{icon} {bold_title}");
+        }
+    }
+
+    /// `text` with its emoji removed when colors are off (`--no-color`,
+    /// `NO_COLOR`, `--ci`); every helper here prints through it
+    pub fn plain(text: &str) -> String {
+        if Self::config().no_color {
+            strip_emoji(text)
+        } else {
+            text.to_string()
+        }
     }
 
     pub fn success(message: &str) {
+        if Self::is_quiet() {
+            return;
+        }
         let check = Self::colorize("✓", |s| s.green().bold());
-        let msg = Self::colorize(message, |s| s.green());
+        let msg = Self::colorize(&Self::plain(message), |s| s.green());
         println!("{check} {msg}");
     }
 
     pub fn error(message: &str) {
         let x_mark = Self::colorize("✗", |s| s.red().bold());
-        let msg = Self::colorize(message, |s| s.red());
+        let msg = Self::colorize(&Self::plain(message), |s| s.red());
         eprintln!("{x_mark} {msg}");
     }
 
+    /// Skipped with `--quiet`, which keeps only errors
     pub fn warning(message: &str) {
+        if Self::config().quiet {
+            return;
+        }
         let warn = Self::colorize("⚠", |s| s.yellow().bold());
-        let msg = Self::colorize(message, |s| s.yellow());
-        println!("{warn} {msg}");
+        let msg = Self::colorize(&Self::plain(message), |s| s.yellow());
+        if Self::is_json() {
+            eprintln!("{warn} {msg}");
+        } else {
+            println!("{warn} {msg}");
+        }
     }
 
     pub fn info(message: &str) {
@@ -93,7 +177,7 @@ impl Output {
             return;
         }
         let info = Self::colorize("ℹ", |s| s.blue().bold());
-        println!("{info} {message}");
+        println!("{info} {}", Self::plain(message));
     }
 
     pub fn step(message: &str) {
@@ -101,7 +185,7 @@ impl Output {
             return;
         }
         let bullet = Self::colorize("•", |s| s.bright_black());
-        println!("  {bullet} {message}");
+        println!("  {bullet} {}", Self::plain(message));
     }
 
     pub fn excluded(message: &str) {
@@ -109,10 +193,40 @@ impl Output {
             return;
         }
         let circle = Self::colorize("○", |s| s.bright_black());
-        let msg = Self::colorize(message, |s| s.bright_black());
+        let msg = Self::colorize(&Self::plain(message), |s| s.bright_black());
         println!("  {circle} {msg}");
     }
 
+    /// A line of a command's human-readable output, dropped with `--quiet`
+    /// (errors only) and `--json`
+    pub fn line(text: &str) {
+        if Self::is_quiet() {
+            return;
+        }
+        println!("{}", Self::plain(text));
+    }
+
+    /// Print a unified diff, coloring added and removed lines
+    pub fn diff(diff: &str) {
+        if Self::is_quiet() {
+            return;
+        }
+        for line in diff.lines() {
+            let colored = if line.starts_with("+++") || line.starts_with("---") {
+                Self::colorize(line, |s| s.bold())
+            } else if line.starts_with("@@") {
+                Self::colorize(line, |s| s.cyan())
+            } else if line.starts_with('+') {
+                Self::colorize(line, |s| s.green())
+            } else if line.starts_with('-') {
+                Self::colorize(line, |s| s.red())
+            } else {
+                line.to_string()
+            };
+            println!("{colored}");
+        }
+    }
+
     pub fn mask_api_key(key: &str) -> String {
         if key.len() <= 12 {
             return "*".repeat(key.len());
@@ -123,36 +237,233 @@ impl Output {
         format!("{prefix}{masked_part}")
     }
 
+    /// Whether prompts are answered by someone: not in CI mode, with
+    /// `--no-input`, or when stdin is not a terminal
+    pub fn can_prompt() -> bool {
+        let config = Self::config();
+        !config.non_interactive && !config.no_input
+    }
+
+    /// Fail with `hint` instead of blocking on a prompt nobody can answer
+    fn ensure_input(prompt: &str, hint: &'static str) -> Result<()> {
+        let config = Self::config();
+        let reason = if config.non_interactive {
+            "prompts are disabled in CI mode"
+        } else if !config.no_input {
+            return Ok(());
+        } else if io::stdin().is_terminal() {
+            "--no-input was passed"
+        } else {
+            "stdin is not a terminal"
+        };
+        Err(Self::no_input(prompt, reason, hint))
+    }
+
+    fn no_input(prompt: &str, reason: &'static str, hint: &'static str) -> PromptGuardError {
+        PromptGuardError::NoInput {
+            prompt: Self::plain(prompt).trim().to_string(),
+            reason,
+            hint,
+        }
+    }
+
+    /// Ask a yes/no question; fails rather than assuming an answer, so only
+    /// `--yes` skips it. JSON output is for scripts, which never answer.
     pub fn confirm(prompt: &str, default: bool) -> Result<bool> {
-        let default_str = if default { "Y/n" } else { "y/N" };
-        let bold_prompt = Self::colorize(prompt, |s| s.bold());
-        print!("{bold_prompt} [{default_str}]: ");
-        io::stdout().flush().map_err(PromptGuardError::Io)?;
+        const HINT: &str = "pass --yes to proceed without confirming";
+        if Self::is_json() {
+            return Err(Self::no_input(prompt, "--json was passed", HINT));
+        }
+        Self::ensure_input(prompt, HINT)?;
+        Confirm::with_theme(&*Self::theme())
+            .with_prompt(Self::question(prompt))
+            .default(default)
+            .interact()
+            .map_err(|e| Self::prompt_failed(prompt, HINT, e))
+    }
+
+    pub fn input(prompt: &str) -> Result<String> {
+        const HINT: &str = "pass the answer as a flag";
+        if Self::config().non_interactive {
+            return Err(PromptGuardError::Custom(format!(
+                "'{prompt}' needs an answer, but prompts are disabled in CI mode; pass it as a flag"
+            )));
+        }
+        Self::ensure_input(prompt, HINT)?;
+        Input::<String>::with_theme(&*Self::theme())
+            .with_prompt(Self::question(prompt))
+            .interact_text()
+            .map(|answer| answer.trim().to_string())
+            .map_err(|e| Self::prompt_failed(prompt, HINT, e))
+    }
 
-        let mut input = String::new();
-        io::stdin()
-            .read_line(&mut input)
-            .map_err(PromptGuardError::Io)?;
+    /// Like `input`, but an empty answer (or CI mode) keeps `default`, and
+    /// answers `validate` rejects are asked again with its message
+    pub fn input_default(
+        prompt: &str,
+        default: &str,
+        mut validate: impl FnMut(&str) -> std::result::Result<(), String>,
+    ) -> Result<String> {
+        const HINT: &str = "pass the answer as a flag";
+        if Self::config().non_interactive {
+            return Ok(default.to_string());
+        }
+        Self::ensure_input(prompt, HINT)?;
+        Input::<String>::with_theme(&*Self::theme())
+            .with_prompt(Self::question(prompt))
+            .default(default.to_string())
+            .validate_with(|answer: &String| validate(answer.trim()))
+            .interact_text()
+            .map(|answer| answer.trim().to_string())
+            .map_err(|e| Self::prompt_failed(prompt, HINT, e))
+    }
 
-        let input = input.trim().to_lowercase();
+    /// Pick one of `options`; returns its index
+    pub fn select(prompt: &str, options: &[&str]) -> Result<usize> {
+        const HINT: &str = "pass the choice as a flag";
+        if Self::config().non_interactive {
+            return Err(PromptGuardError::Custom(format!(
+                "'{prompt}' needs an answer, but prompts are disabled in CI mode; pass it as a flag"
+            )));
+        }
+        Self::ensure_input(prompt, HINT)?;
+        Select::with_theme(&*Self::theme())
+            .with_prompt(Self::question(prompt))
+            .items(options)
+            .default(0)
+            .interact()
+            .map_err(|e| Self::prompt_failed(prompt, HINT, e))
+    }
 
-        if input.is_empty() {
-            return Ok(default);
+    /// Checkbox list: space toggles an item, Enter accepts.
+    /// `selected` is left as-is in CI mode.
+    pub fn select_many(prompt: &str, options: &[String], selected: &mut [bool]) -> Result<()> {
+        const HINT: &str = "pass the selection as flags";
+        if Self::config().non_interactive {
+            return Ok(());
+        }
+        Self::ensure_input(prompt, HINT)?;
+        let chosen = MultiSelect::with_theme(&*Self::theme())
+            .with_prompt(Self::question(prompt))
+            .items(options)
+            .defaults(selected)
+            .interact()
+            .map_err(|e| Self::prompt_failed(prompt, HINT, e))?;
+        for (i, on) in selected.iter_mut().enumerate() {
+            *on = chosen.contains(&i);
         }
+        Ok(())
+    }
 
-        Ok(matches!(input.as_str(), "y" | "yes"))
+    /// Prompts are drawn on stderr, plain when colors are off
+    fn theme() -> Box<dyn Theme> {
+        if Self::config().no_color {
+            Box::new(SimpleTheme)
+        } else {
+            Box::new(ColorfulTheme::default())
+        }
     }
 
-    pub fn input(prompt: &str) -> Result<String> {
-        let bold_prompt = Self::colorize(prompt, |s| s.bold());
-        print!("{bold_prompt}: ");
-        io::stdout().flush().map_err(PromptGuardError::Io)?;
+    /// `prompt` without emoji under `--no-color`; a leading blank line is
+    /// printed before the prompt instead of inside it
+    fn question(prompt: &str) -> String {
+        let text = Self::plain(prompt);
+        if text.starts_with('\n') {
+            eprintln!();
+        }
+        text.trim().to_string()
+    }
+
+    /// A prompt that couldn't be shown: it needs stderr to be a terminal too
+    fn prompt_failed(
+        prompt: &str,
+        hint: &'static str,
+        error: dialoguer::Error,
+    ) -> PromptGuardError {
+        let dialoguer::Error::IO(error) = error;
+        if error.kind() == io::ErrorKind::NotConnected {
+            Self::no_input(prompt, "stderr is not a terminal", hint)
+        } else {
+            PromptGuardError::Io(error)
+        }
+    }
+
+    /// Print `text` `lines` lines at a time, asking before each further
+    /// page. Declining skips the rest.
+    pub fn page(text: &str, lines: usize) -> Result<()> {
+        let all: Vec<&str> = text.lines().collect();
+        for (i, chunk) in all.chunks(lines.max(1)).enumerate() {
+            // Without a terminal to ask on, show everything
+            if i > 0
+                && Self::can_prompt()
+                && !Confirm::with_theme(&*Self::theme())
+                    .with_prompt("Show more?")
+                    .default(true)
+                    .interact()
+                    .unwrap_or(true)
+            {
+                return Ok(());
+            }
+            for line in chunk {
+                println!("{line}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Asks for the emoji rendering of the character before it, as in 🛡️
+const VARIATION_SELECTOR: char = '\u{FE0F}';
+
+/// Pictographs like 🔧 and ✅; the ✓ ✗ ⚠ status markers are kept
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1F000}'..='\u{1FAFF}' | '\u{2705}' | '\u{2728}' | '\u{274C}' | '\u{2699}'
+    )
+}
+
+/// Drop emoji along with the spaces that separated them from the text
+fn strip_emoji(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut stripped = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == VARIATION_SELECTOR {
+            // "⚠️" keeps the plain "⚠"
+            continue;
+        }
+        if is_emoji(c) {
+            stripped = true;
+            while chars
+                .next_if(|&n| n == ' ' || n == VARIATION_SELECTOR || is_emoji(n))
+                .is_some()
+            {}
+        } else {
+            out.push(c);
+        }
+    }
+    if stripped {
+        // "Blocked: 3 ✅" leaves a trailing space behind
+        out.truncate(out.trim_end_matches(' ').len());
+    }
+    out
+}
 
-        let mut input = String::new();
-        io::stdin()
-            .read_line(&mut input)
-            .map_err(PromptGuardError::Io)?;
+#[cfg(test)]
+mod tests {
+    use super::strip_emoji;
 
-        Ok(input.trim().to_string())
+    #[test]
+    fn strip_emoji_removes_pictographs_and_their_spacing() {
+        assert_eq!(strip_emoji("🛡️  PromptGuard CLI v1"), "PromptGuard CLI v1");
+        assert_eq!(strip_emoji("✅ ALLOWED"), "ALLOWED");
+        assert_eq!(
+            strip_emoji("  Attacks Blocked:    3 ✅"),
+            "  Attacks Blocked:    3"
+        );
+        assert_eq!(strip_emoji("\n💡 To revert"), "\nTo revert");
+        assert_eq!(strip_emoji("✓ done: "), "✓ done: ");
+        assert_eq!(strip_emoji("⚠️  Bypasses"), "⚠  Bypasses");
     }
 }
@@ -0,0 +1,182 @@
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressState};
+use serde_json::json;
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::time::Instant;
+
+/// Layout of the bar; `rate` and `eta` are filled in by `bar_style`
+const BAR_TEMPLATE: &str = "{prefix} [{bar:30}] {pos}/{len} files  {rate}  ETA {eta}";
+
+/// Redraws per second
+const REDRAW_HZ: u8 = 10;
+
+/// How progress is shown (`--progress`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProgressStyle {
+    /// A bar on stderr when it is a terminal
+    #[default]
+    Auto,
+    /// Newline-delimited JSON events on stderr, for IDEs and wrappers
+    Json,
+    None,
+}
+
+impl ProgressStyle {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(Self::Auto),
+            "json" => Some(Self::Json),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+
+/// File-count progress bar with throughput and ETA, drawn on stderr by `indicatif`.
+///
+/// Hidden when requested (e.g. `--json`), in quiet or verbose mode, or when
+/// stderr is not a terminal, so piped output and CI logs stay clean.
+/// With `--progress json` it writes one JSON event per line instead
+/// (`start`, `progress` per file, `finish`), whatever the output mode, so a
+/// GUI can show live progress while parsing the result from stdout.
+pub struct Progress {
+    label: String,
+    total: usize,
+    done: usize,
+    /// Findings reported through `found` so far
+    findings: usize,
+    started: Instant,
+    bar: ProgressBar,
+    events: bool,
+}
+
+impl Progress {
+    pub fn new(label: &str, total: usize, hidden: bool) -> Self {
+        let style = super::Output::progress_style();
+        let visible = style == ProgressStyle::Auto
+            && !hidden
+            && total > 0
+            && !super::Output::is_quiet()
+            && !crate::logging::enabled(crate::logging::Level::Debug)
+            && io::stderr().is_terminal();
+        let bar = if visible {
+            let bar = ProgressBar::with_draw_target(
+                Some(total as u64),
+                ProgressDrawTarget::stderr_with_hz(REDRAW_HZ),
+            );
+            bar.set_style(bar_style());
+            bar.set_prefix(label.to_string());
+            bar
+        } else {
+            ProgressBar::hidden()
+        };
+        let progress = Self {
+            label: label.to_string(),
+            total,
+            done: 0,
+            findings: 0,
+            started: Instant::now(),
+            bar,
+            events: style == ProgressStyle::Json,
+        };
+        progress.event(json!({ "event": "start" }));
+        progress
+    }
+
+    /// Record one processed item
+    pub fn inc(&mut self) {
+        self.advance(None);
+    }
+
+    /// Record that `file` is being processed; events name it
+    pub fn inc_file(&mut self, file: &Path) {
+        self.advance(Some(file));
+    }
+
+    /// Add findings (e.g. call sites) to the running count in events
+    pub fn found(&mut self, findings: usize) {
+        self.findings += findings;
+    }
+
+    fn advance(&mut self, file: Option<&Path>) {
+        self.done = (self.done + 1).min(self.total);
+        if self.events {
+            let mut event = json!({ "event": "progress", "done": self.done });
+            if let Some(file) = file {
+                event["file"] = file.to_string_lossy().replace('\\', "/").into();
+            }
+            self.event(event);
+        }
+        self.bar.set_position(self.done as u64);
+    }
+
+    /// Clear the bar so regular output continues on a clean line
+    pub fn finish(&mut self) {
+        if self.events {
+            let elapsed_ms = u64::try_from(self.started.elapsed().as_millis()).unwrap_or(u64::MAX);
+            self.event(json!({ "event": "finish", "done": self.done, "elapsed_ms": elapsed_ms }));
+            self.events = false;
+        }
+        self.bar.finish_and_clear();
+    }
+
+    /// Write one event line, adding the label, total and findings so far
+    fn event(&self, mut event: serde_json::Value) {
+        if !self.events {
+            return;
+        }
+        event["label"] = self.label.clone().into();
+        event["total"] = self.total.into();
+        event["findings"] = self.findings.into();
+        let mut stderr = io::stderr().lock();
+        let _ = writeln!(stderr, "{event}");
+        let _ = stderr.flush();
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// `BAR_TEMPLATE` with files per second and an ETA in minutes and seconds
+fn bar_style() -> indicatif::ProgressStyle {
+    indicatif::ProgressStyle::with_template(BAR_TEMPLATE)
+        .unwrap_or_else(|_| indicatif::ProgressStyle::default_bar())
+        .progress_chars("#-")
+        .with_key("rate", |state: &ProgressState, w: &mut dyn fmt::Write| {
+            let _ = write!(w, "{:.0} files/s", state.per_sec());
+        })
+        .with_key("eta", |state: &ProgressState, w: &mut dyn fmt::Write| {
+            let eta = if state.pos() > 0 {
+                format_eta(state.eta().as_secs_f64())
+            } else {
+                "--".to_string()
+            };
+            let _ = w.write_str(&eta);
+        })
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn format_eta(seconds: f64) -> String {
+    let seconds = seconds.ceil() as u64;
+    if seconds >= 60 {
+        format!("{}m{:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_eta() {
+        assert_eq!(format_eta(0.2), "1s");
+        assert_eq!(format_eta(59.0), "59s");
+        assert_eq!(format_eta(125.0), "2m05s");
+    }
+}
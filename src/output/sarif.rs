@@ -0,0 +1,170 @@
+//! SARIF 2.1.0 output, so findings show up as code-scanning alerts (e.g.
+//! GitHub's Security tab via `github/codeql-action/upload-sarif`)
+
+use std::path::Path;
+
+/// Kinds of finding reported as SARIF results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// An SDK client that doesn't route through `PromptGuard`
+    UnguardedSdkCall,
+    /// A base URL pointing at another gateway or the provider itself
+    ConflictingGateway,
+    /// A file `PromptGuard` transformed that no longer exists
+    MissingManagedFile,
+}
+
+impl Rule {
+    const ALL: [Rule; 3] = [
+        Rule::UnguardedSdkCall,
+        Rule::ConflictingGateway,
+        Rule::MissingManagedFile,
+    ];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::UnguardedSdkCall => "PG001",
+            Self::ConflictingGateway => "PG002",
+            Self::MissingManagedFile => "PG003",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::UnguardedSdkCall => "unguarded-sdk-call",
+            Self::ConflictingGateway => "conflicting-gateway",
+            Self::MissingManagedFile => "missing-managed-file",
+        }
+    }
+
+    fn level(self) -> &'static str {
+        match self {
+            Self::UnguardedSdkCall | Self::MissingManagedFile => "warning",
+            Self::ConflictingGateway => "error",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::UnguardedSdkCall => "LLM SDK call site is not routed through PromptGuard",
+            Self::ConflictingGateway => "LLM SDK base URL points at a different gateway",
+            Self::MissingManagedFile => "File transformed by PromptGuard no longer exists",
+        }
+    }
+
+    fn help(self) -> &'static str {
+        match self {
+            Self::UnguardedSdkCall => {
+                "Requests from this client skip prompt-injection and PII protection. Run \
+                 'promptguard apply' to add the proxy base URL, or 'promptguard enable --runtime' \
+                 to route clients without editing code."
+            },
+            Self::ConflictingGateway => {
+                "The client sends requests to an endpoint other than the PromptGuard proxy, so \
+                 they are not scanned. Point the base URL at the proxy ('promptguard apply' \
+                 rewrites string literals), or mark the call site with 'promptguard: ignore'."
+            },
+            Self::MissingManagedFile => {
+                "The file was renamed or deleted since 'promptguard apply'. Run 'promptguard \
+                 apply' again so the config tracks the current files."
+            },
+        }
+    }
+}
+
+/// One finding, located relative to the project root
+#[derive(Debug, Clone)]
+pub struct SarifResult {
+    pub rule: Rule,
+    pub message: String,
+    pub file: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+/// Path from the enclosing git repository to `root`, with a trailing `/`,
+/// or empty when `root` is the repository root or not in one
+pub fn repo_prefix(root: &Path) -> String {
+    root.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .and_then(|repo| root.strip_prefix(repo).ok())
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .filter(|p| !p.is_empty())
+        .map(|p| format!("{p}/"))
+        .unwrap_or_default()
+}
+
+/// SARIF log with one run. `root` is the project root the result paths are
+/// relative to; they are re-rooted at the enclosing git repository so code
+/// scanning can place them. `errors` become tool execution notifications
+/// for failures that have no location.
+pub fn log(root: &Path, results: &[SarifResult], errors: &[String]) -> serde_json::Value {
+    let prefix = repo_prefix(root);
+
+    let rules: Vec<serde_json::Value> = Rule::ALL
+        .iter()
+        .map(|rule| {
+            serde_json::json!({
+                "id": rule.id(),
+                "name": rule.name(),
+                "shortDescription": { "text": rule.description() },
+                "help": { "text": rule.help() },
+                "defaultConfiguration": { "level": rule.level() },
+            })
+        })
+        .collect();
+
+    let results: Vec<serde_json::Value> = results
+        .iter()
+        .map(|result| {
+            let mut region = serde_json::Map::new();
+            if let Some(line) = result.line {
+                region.insert("startLine".to_string(), line.into());
+                if let Some(column) = result.column {
+                    region.insert("startColumn".to_string(), column.into());
+                }
+            }
+            let mut location = serde_json::json!({
+                "artifactLocation": {
+                    "uri": format!("{prefix}{}", result.file.replace('\\', "/")),
+                    "uriBaseId": "%SRCROOT%",
+                },
+            });
+            if !region.is_empty() {
+                location["region"] = serde_json::Value::Object(region);
+            }
+            serde_json::json!({
+                "ruleId": result.rule.id(),
+                "ruleIndex": Rule::ALL.iter().position(|r| *r == result.rule),
+                "level": result.rule.level(),
+                "message": { "text": result.message },
+                "locations": [{ "physicalLocation": location }],
+            })
+        })
+        .collect();
+
+    let notifications: Vec<serde_json::Value> = errors
+        .iter()
+        .map(|e| serde_json::json!({ "level": "error", "message": { "text": e } }))
+        .collect();
+
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "PromptGuard",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                },
+            },
+            "invocations": [{
+                "executionSuccessful": errors.is_empty(),
+                "toolExecutionNotifications": notifications,
+            }],
+            "results": results,
+        }],
+    })
+}
@@ -0,0 +1,174 @@
+use std::fs::File;
+use std::io::{self, IsTerminal};
+use std::process::{Command, Stdio};
+
+/// Space between columns
+const GAP: &str = "  ";
+
+/// Columns are never truncated below this many characters
+const MIN_COLUMN_WIDTH: usize = 8;
+
+/// Left-aligned text table that fits the terminal: when the rows are wider
+/// than the terminal, the widest columns are shortened first and their cells
+/// truncated with `…`. Piped output is never truncated.
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    /// Columns truncated from the start, keeping the end (file paths)
+    keep_end: Vec<usize>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            headers: headers.iter().map(ToString::to_string).collect(),
+            rows: Vec::new(),
+            keep_end: Vec::new(),
+        }
+    }
+
+    /// Truncate `column` from the start, so the file name stays visible
+    pub fn keep_end(mut self, column: usize) -> Self {
+        self.keep_end.push(column);
+        self
+    }
+
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    pub fn print(&self) {
+        for line in self.render(terminal_width()) {
+            super::Output::line(&line);
+        }
+    }
+
+    /// The table as lines, fitted to `max_width` characters when given
+    pub fn render(&self, max_width: Option<usize>) -> Vec<String> {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        if let Some(max_width) = max_width {
+            fit(&mut widths, max_width);
+        }
+
+        let separator: Vec<String> = widths.iter().map(|w| "─".repeat(*w)).collect();
+        std::iter::once(&self.headers)
+            .chain(std::iter::once(&separator))
+            .chain(&self.rows)
+            .map(|cells| self.line(cells, &widths))
+            .collect()
+    }
+
+    fn line(&self, cells: &[String], widths: &[usize]) -> String {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(column, (cell, &width))| {
+                let cell = truncate(cell, width, self.keep_end.contains(&column));
+                format!("{cell:<width$}")
+            })
+            .collect();
+        padded.join(GAP).trim_end().to_string()
+    }
+}
+
+/// Shorten the widest columns until the row fits `max_width`
+fn fit(widths: &mut [usize], max_width: usize) {
+    let gaps = GAP.len() * widths.len().saturating_sub(1);
+    let mut total: usize = widths.iter().sum::<usize>() + gaps;
+    while total > max_width {
+        let Some(widest) = widths
+            .iter_mut()
+            .filter(|w| **w > MIN_COLUMN_WIDTH)
+            .max_by_key(|w| **w)
+        else {
+            return;
+        };
+        *widest -= 1;
+        total -= 1;
+    }
+}
+
+/// `cell` cut to `width` characters, marking the cut with `…`
+fn truncate(cell: &str, width: usize, keep_end: bool) -> String {
+    let len = cell.chars().count();
+    if len <= width {
+        return cell.to_string();
+    }
+    let keep = width.saturating_sub(1);
+    if keep_end {
+        let tail: String = cell.chars().skip(len - keep).collect();
+        format!("…{tail}")
+    } else {
+        let head: String = cell.chars().take(keep).collect();
+        format!("{head}…")
+    }
+}
+
+/// Columns available on stdout: `COLUMNS` when set, else the terminal's
+/// size from `stty`. `None` when output is piped, so nothing is cut.
+fn terminal_width() -> Option<usize> {
+    if let Some(columns) = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse().ok())
+        .filter(|&c: &usize| c > 0)
+    {
+        return Some(columns);
+    }
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    let tty = File::open("/dev/tty").ok()?;
+    let output = Command::new("stty")
+        .arg("size")
+        .stdin(tty)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    // "rows columns"
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Table;
+
+    fn table() -> Table {
+        let mut table = Table::new(&["PROVIDER", "FILE", "LINES"]).keep_end(1);
+        table.row(vec![
+            "OpenAI".to_string(),
+            "services/billing/handlers/invoice.py".to_string(),
+            "3, 17".to_string(),
+        ]);
+        table
+    }
+
+    #[test]
+    fn renders_aligned_columns() {
+        let lines = table().render(None);
+        assert_eq!(
+            lines[0],
+            "PROVIDER  FILE                                  LINES"
+        );
+        assert_eq!(
+            lines[2],
+            "OpenAI    services/billing/handlers/invoice.py  3, 17"
+        );
+    }
+
+    #[test]
+    fn truncates_the_widest_column_to_fit() {
+        let lines = table().render(Some(40));
+        assert!(lines.iter().all(|l| l.chars().count() <= 40), "{lines:?}");
+        assert_eq!(lines[2], "OpenAI    …ng/handlers/invoice.py  3, 17");
+    }
+}
@@ -1,10 +1,16 @@
 use crate::config::default_exclude_patterns;
 use crate::error::Result;
+use crate::logging;
 use glob::Pattern;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Instant;
 use walkdir::WalkDir;
 
+pub mod workspace;
+
+pub use workspace::Workspace;
+
 /// Directories always skipped during filesystem traversal.
 /// Canonical list - used by scanner, envscanner, and injector.
 pub const SKIP_DIRS: &[&str] = &[
@@ -27,6 +33,8 @@ pub fn is_skip_dir(name: &str) -> bool {
 pub struct FileScanner {
     root_path: PathBuf,
     exclude_patterns: Vec<Pattern>,
+    /// When non-empty, only files matching one of these are scanned
+    include_patterns: Vec<Pattern>,
 }
 
 impl FileScanner {
@@ -53,9 +61,33 @@ impl FileScanner {
         Ok(Self {
             root_path: root_path.as_ref().to_path_buf(),
             exclude_patterns: exclude_patterns?,
+            include_patterns: Vec::new(),
         })
     }
 
+    /// Limit scanning to files matching one of `patterns`, relative to the
+    /// root (e.g. `src/agents/**`). A plain directory path includes
+    /// everything beneath it.
+    pub fn with_include_patterns(mut self, patterns: &[String]) -> Result<Self> {
+        self.include_patterns = patterns
+            .iter()
+            .map(|p| {
+                let p = p.trim_start_matches("./").trim_end_matches('/');
+                let p = if self.root_path.join(p).is_dir() {
+                    format!("{p}/**")
+                } else {
+                    p.to_string()
+                };
+                Pattern::new(&p).map_err(|e| {
+                    crate::error::PromptGuardError::Custom(format!(
+                        "Invalid --files glob '{p}': {e}"
+                    ))
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(self)
+    }
+
     pub fn find_git_root(&self) -> Option<PathBuf> {
         let mut current = self.root_path.clone();
         loop {
@@ -100,17 +132,17 @@ impl FileScanner {
             }
         }
 
-        // Check Python frameworks
-        for filename in ["requirements.txt", "pyproject.toml"] {
-            if let Some(fw) = self.detect_python_framework(filename) {
-                return Some(fw);
-            }
-        }
+        self.detect_python_framework()
+    }
 
-        None
+    /// Django, `FastAPI` or Flask, as declared in requirements.txt or pyproject.toml
+    pub fn detect_python_framework(&self) -> Option<String> {
+        ["requirements.txt", "pyproject.toml"]
+            .iter()
+            .find_map(|filename| self.python_framework_in(filename))
     }
 
-    fn detect_python_framework(&self, filename: &str) -> Option<String> {
+    fn python_framework_in(&self, filename: &str) -> Option<String> {
         let content = fs::read_to_string(self.root_path.join(filename)).ok()?;
         let lower = content.to_lowercase();
         for fw in ["django", "fastapi", "flask"] {
@@ -121,27 +153,46 @@ impl FileScanner {
         None
     }
 
+    /// Whether `path` matches one of the exclude patterns
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.should_exclude(path)
+    }
+
     fn should_exclude(&self, path: &Path) -> bool {
-        let rel_path = match path.strip_prefix(&self.root_path) {
-            Ok(p) => p,
-            Err(_) => return false,
-        };
+        self.excluded_by(path).is_some()
+    }
 
+    /// The first exclude pattern that matches `path`
+    fn excluded_by(&self, path: &Path) -> Option<&Pattern> {
+        let rel_path = path.strip_prefix(&self.root_path).ok()?;
         let path_str = rel_path.to_string_lossy();
 
-        for pattern in &self.exclude_patterns {
-            if pattern.matches(&path_str) {
-                return true;
-            }
+        self.exclude_patterns.iter().find(|pattern| {
             // Also check filename alone
-            if let Some(filename) = path.file_name() {
-                if pattern.matches(&filename.to_string_lossy()) {
-                    return true;
-                }
-            }
-        }
+            pattern.matches(&path_str)
+                || path
+                    .file_name()
+                    .is_some_and(|filename| pattern.matches(&filename.to_string_lossy()))
+        })
+    }
 
-        false
+    /// Whether `path` matches the include patterns (always true without any)
+    pub fn is_included(&self, path: &Path) -> bool {
+        if self.include_patterns.is_empty() {
+            return true;
+        }
+        let Ok(rel_path) = path.strip_prefix(&self.root_path) else {
+            return false;
+        };
+        let path_str = rel_path.to_string_lossy().replace('\\', "/");
+        // `*` stays within one directory; `**` crosses directories
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..glob::MatchOptions::new()
+        };
+        self.include_patterns
+            .iter()
+            .any(|p| p.matches_with(&path_str, options))
     }
 
     pub fn scan_files(&self, extensions: Option<Vec<String>>) -> Result<Vec<PathBuf>> {
@@ -155,39 +206,72 @@ impl FileScanner {
             ]
         });
 
+        let started = Instant::now();
         let mut files = Vec::new();
 
-        for entry in WalkDir::new(&self.root_path)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(std::result::Result::ok)
-        {
+        for entry in WalkDir::new(&self.root_path).follow_links(false) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    logging::debug(format_args!("Skipping unreadable entry: {e}"));
+                    continue;
+                },
+            };
             let path = entry.path();
 
             if !path.is_file() {
                 continue;
             }
 
-            if self.should_exclude(path) {
+            let rel = path.strip_prefix(&self.root_path).unwrap_or(path);
+            let rel_path = rel.display();
+            if let Some(pattern) = self.excluded_by(path) {
+                let message = format!("Skipping {rel_path}: matches exclude pattern '{pattern}'");
+                // node_modules and friends would drown out the files worth explaining
+                let vendored = rel
+                    .components()
+                    .any(|c| is_skip_dir(&c.as_os_str().to_string_lossy()));
+                if vendored {
+                    logging::trace(message);
+                } else {
+                    logging::debug(message);
+                }
+                continue;
+            }
+            if !self.is_included(path) {
+                logging::trace(format_args!("Skipping {rel_path}: outside --files"));
                 continue;
             }
 
-            if let Some(ext) = path.extension() {
-                if exts.iter().any(|e| e == &ext.to_string_lossy()) {
+            match path.extension() {
+                Some(ext) if exts.iter().any(|e| e == &ext.to_string_lossy()) => {
                     files.push(path.to_path_buf());
-                }
+                },
+                _ => logging::trace(format_args!(
+                    "Skipping {rel_path}: not a {} file",
+                    exts.join("/")
+                )),
             }
         }
 
-        // Sort by modification time (newest first)
+        // Sort by modification time (newest first), then path for a stable order
         files.sort_by_cached_key(|p| {
-            std::cmp::Reverse(
-                p.metadata()
-                    .and_then(|m| m.modified())
-                    .unwrap_or(std::time::SystemTime::UNIX_EPOCH),
+            (
+                std::cmp::Reverse(
+                    p.metadata()
+                        .and_then(|m| m.modified())
+                        .unwrap_or(std::time::SystemTime::UNIX_EPOCH),
+                ),
+                p.clone(),
             )
         });
 
+        logging::debug(format_args!(
+            "Found {} source files under {} in {} ms",
+            files.len(),
+            self.root_path.display(),
+            started.elapsed().as_millis()
+        ));
         Ok(files)
     }
 }
@@ -0,0 +1,262 @@
+/// Monorepo workspace discovery
+///
+/// Recognizes npm/yarn workspaces (`workspaces` in `package.json`), pnpm
+/// workspaces (`pnpm-workspace.yaml`), and Python monorepos (more than one
+/// `pyproject.toml`). Findings can then be attributed to the package that
+/// owns each file.
+use super::is_skip_dir;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Maximum directory depth searched for nested `pyproject.toml` files
+const PYTHON_MAX_DEPTH: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceKind {
+    Npm,
+    Yarn,
+    Pnpm,
+    Python,
+}
+
+impl WorkspaceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkspaceKind::Npm => "npm",
+            WorkspaceKind::Yarn => "yarn",
+            WorkspaceKind::Pnpm => "pnpm",
+            WorkspaceKind::Python => "python",
+        }
+    }
+}
+
+/// A single package inside a workspace
+#[derive(Debug, Clone)]
+pub struct WorkspacePackage {
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: WorkspaceKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub kinds: Vec<WorkspaceKind>,
+    pub packages: Vec<WorkspacePackage>,
+}
+
+impl Workspace {
+    /// Discover workspace packages under `root`. Returns `None` for single-package projects.
+    pub fn discover(root: &Path) -> Option<Self> {
+        let mut kinds = Vec::new();
+        let mut packages = Vec::new();
+
+        if let Some((kind, js_packages)) = discover_js(root) {
+            kinds.push(kind);
+            packages.extend(js_packages);
+        }
+
+        let py_packages = discover_python(root);
+        if !py_packages.is_empty() {
+            kinds.push(WorkspaceKind::Python);
+            packages.extend(py_packages);
+        }
+
+        if packages.is_empty() {
+            return None;
+        }
+
+        packages.sort_by(|a, b| a.path.cmp(&b.path));
+        packages.dedup_by(|a, b| a.path == b.path);
+
+        Some(Self {
+            root: root.to_path_buf(),
+            kinds,
+            packages,
+        })
+    }
+
+    /// The innermost package containing `file`, if any
+    pub fn package_for(&self, file: &Path) -> Option<&WorkspacePackage> {
+        self.packages
+            .iter()
+            .filter(|p| file.starts_with(&p.path))
+            .max_by_key(|p| p.path.components().count())
+    }
+
+    /// Package path relative to the workspace root
+    pub fn relative_path(&self, package: &WorkspacePackage) -> String {
+        package
+            .path
+            .strip_prefix(&self.root)
+            .unwrap_or(&package.path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+}
+
+fn discover_js(root: &Path) -> Option<(WorkspaceKind, Vec<WorkspacePackage>)> {
+    let (kind, patterns) = if let Some(patterns) = pnpm_patterns(root) {
+        (WorkspaceKind::Pnpm, patterns)
+    } else {
+        let patterns = package_json_patterns(root)?;
+        let kind = if root.join("yarn.lock").exists() || root.join(".yarnrc.yml").exists() {
+            WorkspaceKind::Yarn
+        } else {
+            WorkspaceKind::Npm
+        };
+        (kind, patterns)
+    };
+
+    let (excludes, includes): (Vec<&String>, Vec<&String>) =
+        patterns.iter().partition(|p| p.starts_with('!'));
+    let excludes: Vec<glob::Pattern> = excludes
+        .iter()
+        .filter_map(|p| glob::Pattern::new(p.trim_start_matches('!')).ok())
+        .collect();
+
+    let mut packages = Vec::new();
+    for pattern in includes {
+        let full = root.join(pattern.trim_end_matches('/'));
+        let Ok(paths) = glob::glob(&full.to_string_lossy()) else {
+            continue;
+        };
+        for dir in paths.filter_map(std::result::Result::ok) {
+            let manifest = dir.join("package.json");
+            let rel_dir = dir.strip_prefix(root).unwrap_or(&dir);
+            if !manifest.is_file()
+                || rel_dir
+                    .components()
+                    .any(|c| is_skip_dir(&c.as_os_str().to_string_lossy()))
+            {
+                continue;
+            }
+            let rel = rel_dir.to_string_lossy().replace('\\', "/");
+            if excludes.iter().any(|e| e.matches(&rel)) {
+                continue;
+            }
+            let name = fs::read_to_string(&manifest)
+                .ok()
+                .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+                .and_then(|v| v.get("name").and_then(|n| n.as_str()).map(String::from))
+                .unwrap_or_else(|| dir_name(&dir));
+            packages.push(WorkspacePackage {
+                name,
+                path: dir,
+                kind,
+            });
+        }
+    }
+
+    Some((kind, packages))
+}
+
+/// Workspace globs from `pnpm-workspace.yaml`
+fn pnpm_patterns(root: &Path) -> Option<Vec<String>> {
+    let content = fs::read_to_string(root.join("pnpm-workspace.yaml")).ok()?;
+    let yaml: serde_yaml::Value = serde_yaml::from_str(&content).ok()?;
+    Some(string_list(
+        yaml.get("packages")?
+            .as_sequence()?
+            .iter()
+            .map(|v| v.as_str()),
+    ))
+}
+
+/// Workspace globs from `package.json` (`["a/*"]` or `{"packages": ["a/*"]}`)
+fn package_json_patterns(root: &Path) -> Option<Vec<String>> {
+    let content = fs::read_to_string(root.join("package.json")).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let workspaces = json.get("workspaces")?;
+    let list = workspaces
+        .as_array()
+        .or_else(|| workspaces.get("packages").and_then(|p| p.as_array()))?;
+    Some(string_list(list.iter().map(|v| v.as_str())))
+}
+
+fn string_list<'a>(items: impl Iterator<Item = Option<&'a str>>) -> Vec<String> {
+    items.flatten().map(String::from).collect()
+}
+
+/// Packages of a Python monorepo: every nested `pyproject.toml` when there is more than one
+fn discover_python(root: &Path) -> Vec<WorkspacePackage> {
+    let mut manifests: Vec<PathBuf> = WalkDir::new(root)
+        .max_depth(PYTHON_MAX_DEPTH)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !is_skip_dir(&e.file_name().to_string_lossy()))
+        .filter_map(std::result::Result::ok)
+        .filter(|e| e.file_type().is_file() && e.file_name() == "pyproject.toml")
+        .map(walkdir::DirEntry::into_path)
+        .collect();
+    manifests.sort();
+
+    if manifests.len() < 2 {
+        return Vec::new();
+    }
+
+    manifests
+        .into_iter()
+        .filter_map(|manifest| {
+            let dir = manifest.parent()?.to_path_buf();
+            if dir == root {
+                return None;
+            }
+            let name = fs::read_to_string(&manifest)
+                .ok()
+                .and_then(|c| pyproject_name(&c))
+                .unwrap_or_else(|| dir_name(&dir));
+            Some(WorkspacePackage {
+                name,
+                path: dir,
+                kind: WorkspaceKind::Python,
+            })
+        })
+        .collect()
+}
+
+/// `name` from the `[project]` or `[tool.poetry]` table of a `pyproject.toml`
+fn pyproject_name(content: &str) -> Option<String> {
+    let mut in_table = false;
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_table = line == "[project]" || line == "[tool.poetry]";
+            continue;
+        }
+        if !in_table {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            if key.trim() == "name" {
+                return Some(
+                    value
+                        .trim()
+                        .trim_matches(|c| c == '"' || c == '\'')
+                        .to_string(),
+                );
+            }
+        }
+    }
+    None
+}
+
+fn dir_name(dir: &Path) -> String {
+    dir.file_name()
+        .map_or_else(|| ".".to_string(), |n| n.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pyproject_name() {
+        let content = "[build-system]\nname = \"nope\"\n\n[project]\nname = \"api-server\"\n";
+        assert_eq!(pyproject_name(content), Some("api-server".to_string()));
+        assert_eq!(pyproject_name("[tool.ruff]\nline-length = 100\n"), None);
+    }
+}
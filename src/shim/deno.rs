@@ -0,0 +1,199 @@
+/// Deno project detection and the runtime shim import map
+///
+/// Deno resolves `npm:` specifiers without Node's loader, so neither the
+/// `require` hook nor entry-point imports of the Node shims apply. Instead an
+/// import map points every SDK specifier the project uses (`openai`,
+/// `npm:openai`, `npm:openai@^4`) at a wrapper in `.promptguard/deno/`, and a
+/// scope sends the wrapper's own import back to the real SDK.
+use crate::detector::manifest::{npm_specifier_package, parse_jsonc};
+use crate::scanner::is_skip_dir;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Deno configuration file names, in the order Deno looks for them
+pub const DENO_CONFIG_FILENAMES: &[&str] = &["deno.json", "deno.jsonc"];
+
+/// Generated import map, relative to the project root
+pub const DENO_IMPORT_MAP_PATH: &str = ".promptguard/deno-import-map.json";
+
+const SOURCE_EXTENSIONS: &[&str] = &["ts", "tsx", "mts", "js", "jsx", "mjs"];
+
+/// The project's `deno.json` or `deno.jsonc`
+pub fn deno_config(root: &Path) -> Option<PathBuf> {
+    DENO_CONFIG_FILENAMES
+        .iter()
+        .map(|name| root.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Whether the project runs on Deno: a Deno config file or `deno.lock`
+pub fn is_deno_project(root: &Path) -> bool {
+    deno_config(root).is_some() || root.join("deno.lock").is_file()
+}
+
+/// `npm:` specifiers importing the root of one of `packages` in project
+/// sources, as written (`npm:openai`, `npm:openai@4.52.0`). Subpath imports
+/// (`npm:openai/resources`) are left alone.
+pub fn npm_specifiers(root: &Path, packages: &[&str]) -> BTreeSet<String> {
+    let mut found = BTreeSet::new();
+    for entry in WalkDir::new(root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| {
+            e.depth() == 0
+                || !e
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|n| is_skip_dir(n) || n == ".promptguard")
+        })
+        .filter_map(std::result::Result::ok)
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            e.path()
+                .extension()
+                .and_then(|x| x.to_str())
+                .is_some_and(|x| SOURCE_EXTENSIONS.contains(&x))
+        })
+    {
+        let Ok(content) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        for quote in ['"', '\''] {
+            let open = format!("{quote}npm:");
+            for (start, _) in content.match_indices(&open) {
+                let rest = &content[start + 1..];
+                let Some(end) = rest.find(quote) else {
+                    continue;
+                };
+                let specifier = &rest[..end];
+                let Some(package) = npm_specifier_package(specifier) else {
+                    continue;
+                };
+                let version = specifier.split_once(package).map_or("", |(_, v)| v);
+                if packages.contains(&package) && !version.contains('/') {
+                    found.insert(specifier.to_string());
+                }
+            }
+        }
+    }
+    found
+}
+
+/// The project's import map (`imports` and `scopes` of its Deno config),
+/// extended to resolve SDK imports to the wrappers in `wrappers`, keyed by
+/// package name with paths relative to the shim directory. Paths from the
+/// project's own map are rebased, as the map lives in the shim directory.
+pub fn import_map(
+    root: &Path,
+    wrappers: &BTreeMap<String, String>,
+    wrapper_dir: &str,
+) -> serde_json::Value {
+    let config = deno_config(root)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| parse_jsonc(&content))
+        .unwrap_or_default();
+    let section = |key: &str| {
+        config
+            .get(key)
+            .and_then(|v| v.as_object())
+            .cloned()
+            .unwrap_or_default()
+    };
+    let mut imports: serde_json::Map<String, serde_json::Value> = section("imports")
+        .iter()
+        .map(|(k, v)| (k.clone(), rebase_value(v)))
+        .collect();
+    let mut scopes: serde_json::Map<String, serde_json::Value> = section("scopes")
+        .iter()
+        .map(|(scope, map)| {
+            let rebased: serde_json::Map<String, serde_json::Value> = map
+                .as_object()
+                .map(|m| {
+                    m.iter()
+                        .map(|(k, v)| (k.clone(), rebase_value(v)))
+                        .collect()
+                })
+                .unwrap_or_default();
+            (rebase(scope), serde_json::Value::Object(rebased))
+        })
+        .collect();
+
+    let packages: Vec<&str> = wrappers.keys().map(String::as_str).collect();
+    let specifiers = npm_specifiers(root, &packages);
+    let mut real = serde_json::Map::new();
+    for (package, wrapper) in wrappers {
+        let wrapper = serde_json::Value::String(format!("./{wrapper}"));
+        // What the wrapper's bare import resolves to: the project's mapping,
+        // else the unversioned npm package
+        let target = imports
+            .get(package)
+            .cloned()
+            .unwrap_or_else(|| serde_json::Value::String(format!("npm:{package}")));
+        real.insert(package.clone(), target);
+        imports.insert(package.clone(), wrapper.clone());
+        imports.insert(format!("npm:{package}"), wrapper.clone());
+        for specifier in specifiers
+            .iter()
+            .filter(|s| npm_specifier_package(s) == Some(package.as_str()))
+        {
+            imports.insert(specifier.clone(), wrapper.clone());
+        }
+    }
+    scopes.insert(format!("./{wrapper_dir}/"), serde_json::Value::Object(real));
+
+    serde_json::json!({ "imports": imports, "scopes": scopes })
+}
+
+/// A path relative to the project root, made relative to the shim directory
+fn rebase(path: &str) -> String {
+    if let Some(rest) = path.strip_prefix("./") {
+        format!("../{rest}")
+    } else if path.starts_with("../") {
+        format!("../{path}")
+    } else {
+        path.to_string()
+    }
+}
+
+fn rebase_value(value: &serde_json::Value) -> serde_json::Value {
+    value
+        .as_str()
+        .map_or_else(|| value.clone(), |v| serde_json::Value::String(rebase(v)))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_import_map_covers_project_specifiers() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        assert!(!is_deno_project(root));
+        fs::write(
+            root.join("deno.json"),
+            r#"{"imports": {"openai": "npm:openai@^4.20.0", "@/": "./src/"}}"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("main.ts"),
+            "import OpenAI from \"npm:openai@4.52.0\";\nimport { x } from 'npm:openai/resources';\n",
+        )
+        .unwrap();
+        assert!(is_deno_project(root));
+
+        let wrappers = BTreeMap::from([("openai".to_string(), "deno/openai.ts".to_string())]);
+        let map = import_map(root, &wrappers, "deno");
+        let wrapper = serde_json::json!("./deno/openai.ts");
+        for specifier in ["openai", "npm:openai", "npm:openai@4.52.0"] {
+            assert_eq!(map["imports"][specifier], wrapper, "{specifier}");
+        }
+        assert!(map["imports"].get("npm:openai/resources").is_none());
+        assert_eq!(map["imports"]["@/"], "../src/");
+        assert_eq!(map["scopes"]["./deno/"]["openai"], "npm:openai@^4.20.0");
+    }
+}
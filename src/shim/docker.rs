@@ -0,0 +1,637 @@
+/// Docker entry point detection and compose environment injection
+///
+/// Finds the scripts run by `Dockerfile` `CMD`/`ENTRYPOINT` instructions and
+/// docker-compose `command`/`entrypoint` keys, so they are treated as entry
+/// points, and adds the environment that loads the shim to compose services.
+use crate::error::Result;
+use crate::scanner::is_skip_dir;
+use crate::shim::injector::NODE_PRELOAD_REQUIRE;
+use crate::textfile;
+use crate::types::Language;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Compose file names, in the order docker compose looks for them
+pub const COMPOSE_FILENAMES: &[&str] = &[
+    "compose.yaml",
+    "compose.yml",
+    "docker-compose.yaml",
+    "docker-compose.yml",
+];
+
+/// Trailing comment on every line added to a compose file
+const COMPOSE_MARKER: &str = "# promptguard: runtime shim";
+
+/// Python imports `sitecustomize` from `sys.path` at startup, scripts included,
+/// so putting the shim directory on `PYTHONPATH` loads the shim.
+/// (`PYTHONSTARTUP` only runs for interactive sessions.)
+pub const PYTHON_DOCKER_PATH: &str = "./.promptguard";
+
+const SCRIPT_EXTENSIONS: &[&str] = &["py", "js", "mjs", "cjs", "ts", "mts", "cts", "jsx", "tsx"];
+
+const PYTHON_PROGRAMS: &[&str] = &[
+    "python",
+    "gunicorn",
+    "uvicorn",
+    "hypercorn",
+    "flask",
+    "celery",
+    "streamlit",
+];
+
+const NODE_PROGRAMS: &[&str] = &[
+    "node", "npm", "npx", "yarn", "pnpm", "ts-node", "tsx", "nodemon",
+];
+
+/// An environment variable `inject_compose_env` added to a compose service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComposeInjection {
+    pub file: PathBuf,
+    pub service: String,
+    pub variable: &'static str,
+}
+
+/// Dockerfiles in the project: `Dockerfile`, `Dockerfile.*` and `*.Dockerfile`
+pub fn dockerfiles(root: &Path) -> Vec<PathBuf> {
+    let mut found: Vec<PathBuf> = WalkDir::new(root)
+        .max_depth(3)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_str().is_some_and(is_skip_dir))
+        .filter_map(std::result::Result::ok)
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy();
+            name == "Dockerfile"
+                || name.starts_with("Dockerfile.")
+                || name.to_ascii_lowercase().ends_with(".dockerfile")
+        })
+        .map(walkdir::DirEntry::into_path)
+        .collect();
+    found.sort();
+    found
+}
+
+/// Compose files in the project root
+pub fn compose_files(root: &Path) -> Vec<PathBuf> {
+    COMPOSE_FILENAMES
+        .iter()
+        .map(|name| root.join(name))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Project scripts started by Dockerfiles and compose services
+pub fn entry_scripts(root: &Path) -> Vec<PathBuf> {
+    let mut scripts = BTreeSet::new();
+
+    for dockerfile in dockerfiles(root) {
+        let Ok(content) = fs::read_to_string(&dockerfile) else {
+            continue;
+        };
+        let build_dir = dockerfile.parent().unwrap_or(root).to_path_buf();
+        for command in dockerfile_commands(&content) {
+            scripts.extend(resolve_scripts(
+                root,
+                std::slice::from_ref(&build_dir),
+                &command,
+            ));
+        }
+    }
+
+    for compose_file in compose_files(root) {
+        for service in compose_services(&compose_file) {
+            let mut bases = Vec::new();
+            if let Some(context) = &service.context {
+                bases.push(root.join(context));
+            }
+            for command in [&service.command, &service.entrypoint]
+                .into_iter()
+                .flatten()
+            {
+                scripts.extend(resolve_scripts(root, &bases, command));
+            }
+        }
+    }
+
+    scripts.into_iter().collect()
+}
+
+/// Add the variables that load the shim to each compose service running
+/// Python or Node.js, for the `languages` that have shims. Services whose
+/// runtime can't be told from their command, image, or Dockerfile are skipped.
+pub fn inject_compose_env(root: &Path, languages: &[Language]) -> Result<Vec<ComposeInjection>> {
+    let python = languages.contains(&Language::Python);
+    let node = languages
+        .iter()
+        .any(|l| matches!(l, Language::TypeScript | Language::JavaScript));
+
+    let mut injections = Vec::new();
+    for compose_file in compose_files(root) {
+        let (format, content) = textfile::read(&compose_file)?;
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+        let mut changed = false;
+
+        for service in compose_services(&compose_file) {
+            let variables: Vec<(&'static str, String)> = match service.runtime(root) {
+                Some(Language::Python) if python => {
+                    vec![("PYTHONPATH", PYTHON_DOCKER_PATH.to_string())]
+                },
+                Some(Language::TypeScript | Language::JavaScript) if node => {
+                    vec![("NODE_OPTIONS", NODE_PRELOAD_REQUIRE.to_string())]
+                },
+                _ => continue,
+            };
+            let variables: Vec<(&'static str, String)> = variables
+                .into_iter()
+                .filter(|(name, _)| !service.environment.contains(*name))
+                .collect();
+            if variables.is_empty() {
+                continue;
+            }
+            if add_service_environment(&mut lines, &service.name, &variables) {
+                changed = true;
+                injections.extend(variables.iter().map(|(name, _)| ComposeInjection {
+                    file: compose_file.clone(),
+                    service: service.name.clone(),
+                    variable: name,
+                }));
+            }
+        }
+
+        if changed {
+            let mut updated = lines.join("\n");
+            updated.push('\n');
+            textfile::write(&compose_file, format, &updated)?;
+        }
+    }
+    Ok(injections)
+}
+
+/// Remove every line `inject_compose_env` added. Returns the files changed.
+pub fn remove_compose_env(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut changed = Vec::new();
+    for compose_file in compose_files(root) {
+        let (format, content) = textfile::read(&compose_file)?;
+        if !content.contains(COMPOSE_MARKER) {
+            continue;
+        }
+        let mut updated: String = content
+            .lines()
+            .filter(|line| !line.trim_end().ends_with(COMPOSE_MARKER))
+            .collect::<Vec<_>>()
+            .join("\n");
+        updated.push('\n');
+        textfile::write(&compose_file, format, &updated)?;
+        changed.push(compose_file);
+    }
+    Ok(changed)
+}
+
+/// What a compose service runs, as far as shim loading is concerned
+struct ComposeService {
+    name: String,
+    command: Option<Vec<String>>,
+    entrypoint: Option<Vec<String>>,
+    image: Option<String>,
+    /// Build context and Dockerfile, relative to the project root
+    context: Option<PathBuf>,
+    dockerfile: Option<PathBuf>,
+    /// Names of the variables the service already sets
+    environment: BTreeSet<String>,
+}
+
+impl ComposeService {
+    /// Python or Node.js (as `JavaScript`), from the command, image, or Dockerfile
+    fn runtime(&self, root: &Path) -> Option<Language> {
+        if let Some(runtime) = [&self.entrypoint, &self.command]
+            .into_iter()
+            .flatten()
+            .find_map(|command| command_runtime(command))
+        {
+            return Some(runtime);
+        }
+        if let Some(runtime) = self.image.as_deref().and_then(image_runtime) {
+            return Some(runtime);
+        }
+        let dockerfile = root.join(self.context.as_deref()?).join(
+            self.dockerfile
+                .as_deref()
+                .unwrap_or(Path::new("Dockerfile")),
+        );
+        let content = fs::read_to_string(dockerfile).ok()?;
+        dockerfile_commands(&content)
+            .iter()
+            .find_map(|command| command_runtime(command))
+            .or_else(|| {
+                content
+                    .lines()
+                    .filter_map(|line| instruction(line, "FROM"))
+                    .filter_map(|from| from.split_whitespace().next().and_then(image_runtime))
+                    .next_back()
+            })
+    }
+}
+
+fn compose_services(compose_file: &Path) -> Vec<ComposeService> {
+    let Some(parsed) = fs::read_to_string(compose_file)
+        .ok()
+        .and_then(|content| serde_yaml::from_str::<serde_yaml::Value>(&content).ok())
+    else {
+        return Vec::new();
+    };
+    let Some(services) = parsed.get("services").and_then(|s| s.as_mapping()) else {
+        return Vec::new();
+    };
+
+    services
+        .iter()
+        .filter_map(|(name, service)| {
+            let name = name.as_str()?.to_string();
+            let (context, dockerfile) = match service.get("build") {
+                Some(serde_yaml::Value::String(context)) => (Some(PathBuf::from(context)), None),
+                Some(build) => (
+                    Some(PathBuf::from(
+                        build.get("context").and_then(|c| c.as_str()).unwrap_or("."),
+                    )),
+                    build
+                        .get("dockerfile")
+                        .and_then(|d| d.as_str())
+                        .map(PathBuf::from),
+                ),
+                None => (None, None),
+            };
+            let environment = match service.get("environment") {
+                Some(serde_yaml::Value::Mapping(map)) => map
+                    .keys()
+                    .filter_map(|k| k.as_str().map(str::to_string))
+                    .collect(),
+                Some(serde_yaml::Value::Sequence(items)) => items
+                    .iter()
+                    .filter_map(|item| item.as_str())
+                    .map(|item| item.split('=').next().unwrap_or(item).to_string())
+                    .collect(),
+                _ => BTreeSet::new(),
+            };
+            Some(ComposeService {
+                name,
+                command: service.get("command").and_then(yaml_command),
+                entrypoint: service.get("entrypoint").and_then(yaml_command),
+                image: service
+                    .get("image")
+                    .and_then(|i| i.as_str())
+                    .map(str::to_string),
+                context,
+                dockerfile,
+                environment,
+            })
+        })
+        .collect()
+}
+
+/// A compose `command`/`entrypoint`, in string or list form, as words
+fn yaml_command(value: &serde_yaml::Value) -> Option<Vec<String>> {
+    match value {
+        serde_yaml::Value::String(command) => Some(shell_words(command)),
+        serde_yaml::Value::Sequence(items) => Some(
+            items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .flat_map(shell_words)
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Words of each `CMD` and `ENTRYPOINT` instruction in a Dockerfile
+fn dockerfile_commands(content: &str) -> Vec<Vec<String>> {
+    // Join continuation lines so multi-line instructions parse as one
+    let joined = content.replace("\\\r\n", " ").replace("\\\n", " ");
+    joined
+        .lines()
+        .filter_map(|line| instruction(line, "CMD").or_else(|| instruction(line, "ENTRYPOINT")))
+        .map(|args| {
+            // Exec form is a JSON array, shell form a plain command line
+            match serde_json::from_str::<Vec<String>>(args) {
+                Ok(words) => words.iter().flat_map(|w| shell_words(w)).collect(),
+                Err(_) => shell_words(args),
+            }
+        })
+        .collect()
+}
+
+/// Arguments of a Dockerfile instruction, if `line` is one
+fn instruction<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let line = line.trim_start();
+    let (keyword, rest) = line.split_once(char::is_whitespace)?;
+    keyword.eq_ignore_ascii_case(name).then(|| rest.trim())
+}
+
+fn shell_words(command: &str) -> Vec<String> {
+    command
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c| c == '"' || c == '\'').to_string())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Script paths a command names: files with a script extension, `python -m`
+/// modules, and `module:app` targets of WSGI/ASGI servers
+fn script_candidates(command: &[String]) -> Vec<String> {
+    let mut candidates = Vec::new();
+    let mut previous = "";
+    for word in command {
+        let extension = Path::new(word)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("");
+        if previous == "-m" {
+            let module = word.replace('.', "/");
+            candidates.push(format!("{module}.py"));
+            candidates.push(format!("{module}/__main__.py"));
+        } else if SCRIPT_EXTENSIONS.contains(&extension) {
+            candidates.push(word.clone());
+        } else if let Some((module, _)) = word.split_once(':') {
+            let is_module = !module.is_empty()
+                && module.split('.').all(|part| {
+                    part.chars()
+                        .next()
+                        .is_some_and(|c| c.is_alphabetic() || c == '_')
+                        && part.chars().all(|c| c.is_alphanumeric() || c == '_')
+                });
+            if is_module {
+                candidates.push(format!("{}.py", module.replace('.', "/")));
+            }
+        }
+        previous = word;
+    }
+    candidates
+}
+
+/// Project files a command runs. Absolute container paths like `/app/main.py`
+/// are matched by their longest suffix that exists in the project.
+fn resolve_scripts(root: &Path, bases: &[PathBuf], command: &[String]) -> Vec<PathBuf> {
+    let mut bases = bases.to_vec();
+    bases.push(root.to_path_buf());
+
+    script_candidates(command)
+        .into_iter()
+        .filter_map(|candidate| {
+            let parts: Vec<&str> = candidate
+                .split('/')
+                .filter(|p| !p.is_empty() && *p != ".")
+                .collect();
+            if parts.contains(&"..") {
+                return None;
+            }
+            let suffixes = if candidate.starts_with('/') {
+                parts.len()
+            } else {
+                1
+            };
+            (0..suffixes).find_map(|skip| {
+                let relative = parts[skip..].join("/");
+                bases
+                    .iter()
+                    .map(|base| base.join(&relative))
+                    .find(|path| path.is_file())
+            })
+        })
+        .filter(|path| {
+            path.strip_prefix(root).is_ok_and(|rel| {
+                !rel.components()
+                    .any(|c| c.as_os_str().to_str().is_some_and(is_skip_dir))
+            })
+        })
+        .collect()
+}
+
+fn command_runtime(command: &[String]) -> Option<Language> {
+    command.iter().find_map(|word| {
+        let program = word.rsplit('/').next().unwrap_or(word);
+        let extension = Path::new(word).extension().and_then(|e| e.to_str());
+        if PYTHON_PROGRAMS.iter().any(|p| program.starts_with(p)) || extension == Some("py") {
+            Some(Language::Python)
+        } else if NODE_PROGRAMS.contains(&program)
+            || extension.is_some_and(|e| e != "py" && SCRIPT_EXTENSIONS.contains(&e))
+        {
+            Some(Language::JavaScript)
+        } else {
+            None
+        }
+    })
+}
+
+/// Runtime of official-style images such as `python:3.12-slim` or `node:20`
+fn image_runtime(image: &str) -> Option<Language> {
+    let name = image.split(':').next().unwrap_or(image);
+    let name = name.rsplit('/').next().unwrap_or(name);
+    if name.starts_with("python") {
+        Some(Language::Python)
+    } else if name.starts_with("node") {
+        Some(Language::JavaScript)
+    } else {
+        None
+    }
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn is_content(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && !trimmed.starts_with('#')
+}
+
+/// Insert `variables` into the `environment` of `service`, adding the key
+/// when missing. Inline (`[...]`/`{...}`) environments are left alone.
+fn add_service_environment(
+    lines: &mut Vec<String>,
+    service: &str,
+    variables: &[(&'static str, String)],
+) -> bool {
+    let Some(services_line) = lines
+        .iter()
+        .position(|line| indent_of(line) == 0 && line.trim_end() == "services:")
+    else {
+        return false;
+    };
+
+    // Service headers sit at the first indentation under `services:`
+    let mut service_indent = None;
+    let mut header = None;
+    for (index, line) in lines.iter().enumerate().skip(services_line + 1) {
+        if !is_content(line) {
+            continue;
+        }
+        let indent = indent_of(line);
+        if indent == 0 {
+            break;
+        }
+        let service_indent = *service_indent.get_or_insert(indent);
+        let key = line.trim().trim_end_matches(':');
+        let key = key.trim_matches(|c| c == '"' || c == '\'');
+        if indent == service_indent && line.trim_end().ends_with(':') && key == service {
+            header = Some((index, indent));
+            break;
+        }
+    }
+    let Some((header, service_indent)) = header else {
+        return false;
+    };
+
+    // The service block runs until the next line at or above its indentation
+    let end = lines
+        .iter()
+        .enumerate()
+        .skip(header + 1)
+        .find(|(_, line)| is_content(line) && indent_of(line) <= service_indent)
+        .map_or(lines.len(), |(index, _)| index);
+    let Some(child_indent) = lines[header + 1..end]
+        .iter()
+        .find(|line| is_content(line))
+        .map(|line| indent_of(line))
+    else {
+        return false;
+    };
+    let child = " ".repeat(child_indent);
+
+    let environment = (header + 1..end).find(|&index| {
+        let line = &lines[index];
+        indent_of(line) == child_indent && line.trim_start().starts_with("environment:")
+    });
+
+    let (at, new_lines): (usize, Vec<String>) =
+        match environment {
+            Some(index) => {
+                if !lines[index].trim_end().ends_with("environment:") {
+                    return false;
+                }
+                let first_item = lines[index + 1..end].iter().find(|line| is_content(line));
+                let list = first_item.is_some_and(|line| line.trim_start().starts_with("- "));
+                let item_indent = first_item
+                    .map(|line| indent_of(line))
+                    .filter(|&indent| indent > child_indent || list)
+                    .unwrap_or(child_indent + 2);
+                let item = " ".repeat(item_indent);
+                let new_lines = variables
+                    .iter()
+                    .map(|(name, value)| {
+                        if list {
+                            format!("{item}- \"{name}={value}\"  {COMPOSE_MARKER}")
+                        } else {
+                            format!("{item}{name}: \"{value}\"  {COMPOSE_MARKER}")
+                        }
+                    })
+                    .collect();
+                (index + 1, new_lines)
+            },
+            None => (
+                header + 1,
+                std::iter::once(format!("{child}environment:  {COMPOSE_MARKER}"))
+                    .chain(variables.iter().map(|(name, value)| {
+                        format!("{child}  {name}: \"{value}\"  {COMPOSE_MARKER}")
+                    }))
+                    .collect(),
+            ),
+        };
+    lines.splice(at..at, new_lines);
+    true
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_dockerfile_entry_scripts() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("api/app")).unwrap();
+        fs::write(root.join("worker.py"), "").unwrap();
+        fs::write(root.join("api/app/main.py"), "").unwrap();
+        fs::write(root.join("server.js"), "").unwrap();
+        fs::write(
+            root.join("Dockerfile"),
+            "FROM python:3.12\nWORKDIR /app\nCOPY . .\nCMD [\"python\", \"/app/worker.py\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("api/Dockerfile"),
+            "FROM python:3.12\nENTRYPOINT uvicorn app.main:app \\\n    --host 0.0.0.0:8000\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("docker-compose.yml"),
+            "services:\n  web:\n    image: node:20\n    command: node ./server.js\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            entry_scripts(root),
+            vec![
+                root.join("api/app/main.py"),
+                root.join("server.js"),
+                root.join("worker.py"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compose_env_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("api")).unwrap();
+        fs::write(root.join("api/Dockerfile"), "FROM python:3.12-slim\n").unwrap();
+        let original = "\
+services:
+  api:
+    build: ./api
+    ports:
+      - \"8000:8000\"
+  web:
+    image: node:20
+    environment:
+      - PORT=3000
+  db:
+    image: postgres:16
+";
+        let compose = root.join("docker-compose.yml");
+        fs::write(&compose, original).unwrap();
+
+        let injected = inject_compose_env(root, &[Language::Python, Language::TypeScript]).unwrap();
+        let added: Vec<(&str, &str)> = injected
+            .iter()
+            .map(|i| (i.service.as_str(), i.variable))
+            .collect();
+        assert_eq!(added, vec![("api", "PYTHONPATH"), ("web", "NODE_OPTIONS")]);
+
+        let content = fs::read_to_string(&compose).unwrap();
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&content).unwrap();
+        assert_eq!(
+            parsed["services"]["api"]["environment"]["PYTHONPATH"].as_str(),
+            Some(PYTHON_DOCKER_PATH)
+        );
+        assert_eq!(
+            parsed["services"]["web"]["environment"][0].as_str(),
+            Some(format!("NODE_OPTIONS={NODE_PRELOAD_REQUIRE}").as_str())
+        );
+        assert!(parsed["services"]["db"].get("environment").is_none());
+
+        // Running again adds nothing
+        assert!(
+            inject_compose_env(root, &[Language::Python, Language::TypeScript])
+                .unwrap()
+                .is_empty()
+        );
+
+        assert_eq!(remove_compose_env(root).unwrap(), vec![compose.clone()]);
+        assert_eq!(fs::read_to_string(&compose).unwrap(), original);
+    }
+}
@@ -2,41 +2,121 @@
 ///
 /// Generates runtime interception code for Python and TypeScript/JavaScript
 /// that automatically routes all LLM SDK calls through `PromptGuard` proxy.
+use crate::config::{ProxyUrl, ShimFailMode};
 use crate::error::Result;
 use crate::shim::templates;
 use crate::types::{Language, Provider};
+use aws_lc_rs::digest::{digest, SHA256};
+use std::collections::BTreeMap;
+use std::fmt::Write;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 const SHIM_DIR_NAME: &str = ".promptguard";
+
+/// CLI version stamped into every generated shim
+pub const SHIM_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Precedes the version in each shim's header comment
+const SHIM_VERSION_MARKER: &str = "Generated by PromptGuard CLI v";
 const PYTHON_SHIM_FILENAME: &str = "promptguard_shim.py";
+const PYTHON_SITECUSTOMIZE_FILENAME: &str = "sitecustomize.py";
 const TYPESCRIPT_SHIM_FILENAME: &str = "promptguard-shim.ts";
 const JAVASCRIPT_SHIM_FILENAME: &str = "promptguard-shim.js";
+const CJS_SHIM_FILENAME: &str = "promptguard-shim.cjs";
+const ESM_SHIM_FILENAME: &str = "promptguard-shim.mjs";
+const NODE_PRELOAD_FILENAME: &str = "preload.cjs";
+const BUN_PRELOAD_FILENAME: &str = "bun-preload.mjs";
+/// Holds one Bun wrapper module per SDK package
+const BUN_WRAPPER_DIR: &str = "bun";
+/// Written by the shims at runtime, so not one of `GENERATED_FILENAMES`
+const SHIM_LOG_FILENAME: &str = "shim.log";
+const DENO_SHIM_FILENAME: &str = "deno-shim.ts";
+const DENO_IMPORT_MAP_FILENAME: &str = "deno-import-map.json";
+/// Holds one Deno wrapper module per SDK package
+const DENO_WRAPPER_DIR: &str = "deno";
+
+/// Every file `ShimGenerator` writes into the shim directory
+const GENERATED_FILENAMES: &[&str] = &[
+    PYTHON_SHIM_FILENAME,
+    PYTHON_SITECUSTOMIZE_FILENAME,
+    "__init__.py",
+    TYPESCRIPT_SHIM_FILENAME,
+    JAVASCRIPT_SHIM_FILENAME,
+    CJS_SHIM_FILENAME,
+    ESM_SHIM_FILENAME,
+    NODE_PRELOAD_FILENAME,
+    BUN_PRELOAD_FILENAME,
+    DENO_SHIM_FILENAME,
+    DENO_IMPORT_MAP_FILENAME,
+    "package.json",
+    ".gitignore",
+    "README.md",
+];
+
+/// A generated shim file that no longer matches its recorded checksum
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShimIntegrity {
+    /// Edited or truncated since it was generated
+    Modified(String),
+    Missing(String),
+}
 
 /// Shim generator for creating runtime interception code
 pub struct ShimGenerator {
     project_root: PathBuf,
-    proxy_url: String,
+    proxy_url: ProxyUrl,
     api_key_var: String,
+    /// Per-provider API key variables, keyed by provider name
+    api_key_var_overrides: BTreeMap<String, String>,
     providers: Vec<Provider>,
+    fail_mode: ShimFailMode,
+    /// Whether the shims record intercepted clients in `SHIM_LOG_FILENAME`
+    shim_log: bool,
 }
 
 impl ShimGenerator {
     /// Create a new shim generator
     pub fn new(
         project_root: impl AsRef<Path>,
-        proxy_url: String,
+        proxy_url: impl Into<ProxyUrl>,
         api_key_var: String,
         providers: Vec<Provider>,
     ) -> Self {
         Self {
             project_root: project_root.as_ref().to_path_buf(),
-            proxy_url,
+            proxy_url: proxy_url.into(),
             api_key_var,
+            api_key_var_overrides: BTreeMap::new(),
             providers,
+            fail_mode: ShimFailMode::default(),
+            shim_log: false,
         }
     }
 
+    /// What the shims do when the proxy is unreachable
+    pub fn with_fail_mode(mut self, fail_mode: ShimFailMode) -> Self {
+        self.fail_mode = fail_mode;
+        self
+    }
+
+    /// Have the shims log each intercepted client (no payloads) to `shim.log`
+    pub fn with_shim_log(mut self, shim_log: bool) -> Self {
+        self.shim_log = shim_log;
+        self
+    }
+
+    /// Get the local log the shims write to when logging is on
+    pub fn shim_log_path(&self) -> PathBuf {
+        self.shim_dir().join(SHIM_LOG_FILENAME)
+    }
+
+    /// Read some providers' API keys from their own variables instead of `api_key_var`
+    pub fn with_api_key_var_overrides(mut self, overrides: BTreeMap<String, String>) -> Self {
+        self.api_key_var_overrides = overrides;
+        self
+    }
+
     /// Get the shim directory path
     pub fn shim_dir(&self) -> PathBuf {
         self.project_root.join(SHIM_DIR_NAME)
@@ -57,6 +137,68 @@ impl ShimGenerator {
         self.shim_dir().join(JAVASCRIPT_SHIM_FILENAME)
     }
 
+    /// Get the Node.js preload file path
+    pub fn node_preload_path(&self) -> PathBuf {
+        self.shim_dir().join(NODE_PRELOAD_FILENAME)
+    }
+
+    /// Per-provider proxy URLs as a literal valid in both Python and TypeScript
+    fn provider_proxy_urls(&self) -> String {
+        serde_json::to_string(&self.proxy_url.overrides(&self.providers))
+            .unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// Per-provider API key variables, keyed by display name like the proxy URLs
+    fn provider_api_key_vars(&self) -> String {
+        let vars: BTreeMap<&str, &str> = self
+            .providers
+            .iter()
+            .filter_map(|p| {
+                let var = self.api_key_var_overrides.get(p.as_str())?;
+                Some((p.display_name(), var.as_str()))
+            })
+            .collect();
+        serde_json::to_string(&vars).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// JavaScript shim targets of the configured providers
+    fn javascript_targets(&self) -> impl Iterator<Item = templates::JavaScriptTarget> + '_ {
+        self.providers
+            .iter()
+            .filter_map(|p| templates::get_javascript_target(*p))
+    }
+
+    /// Fill in the routing configuration and SDK targets of a JavaScript shim template
+    fn fill_javascript_template(&self, template: &str) -> String {
+        let targets: serde_json::Map<String, serde_json::Value> = self
+            .javascript_targets()
+            .map(|target| {
+                (
+                    target.package.to_string(),
+                    serde_json::json!({
+                        "provider": target.provider.display_name(),
+                        "classes": target.classes,
+                        "param": target.param,
+                        "keyParam": target.key_param,
+                    }),
+                )
+            })
+            .collect();
+
+        template
+            .replace("{{PROXY_URL}}", self.proxy_url.default_url())
+            .replace("{{FAIL_MODE}}", self.fail_mode.as_str())
+            .replace("{{SHIM_LOG}}", if self.shim_log { "true" } else { "false" })
+            .replace("{{PROVIDER_PROXY_URLS}}", &self.provider_proxy_urls())
+            .replace("{{API_KEY_VAR}}", &self.api_key_var)
+            .replace("{{PROVIDER_API_KEY_VARS}}", &self.provider_api_key_vars())
+            .replace(
+                "{{NODE_TARGETS}}",
+                &serde_json::to_string(&targets).unwrap_or_else(|_| "{}".to_string()),
+            )
+            .replace("{{CLI_VERSION}}", SHIM_VERSION)
+    }
+
     /// Ensure shim directory exists
     fn ensure_shim_dir(&self) -> Result<()> {
         let shim_dir = self.shim_dir();
@@ -75,20 +217,31 @@ impl ShimGenerator {
 
         for provider in &self.providers {
             // Add provider patch function
-            provider_patches.push_str(templates::get_python_provider_patch(*provider));
+            provider_patches.push_str(&templates::get_python_provider_patch(*provider));
             provider_patches.push('\n');
 
             // Add install call
-            install_calls.push_str(templates::get_python_install_call(*provider));
+            install_calls.push_str(&templates::get_python_install_call(*provider));
             install_calls.push('\n');
         }
 
+        // Frameworks patch after the SDKs they wrap
+        let (framework_patches, framework_install_calls) =
+            templates::get_python_framework_patches(&self.providers);
+        install_calls.push_str(&framework_install_calls);
+
         // Generate shim content from template
         let content = templates::PYTHON_SHIM_TEMPLATE
-            .replace("{{PROXY_URL}}", &self.proxy_url)
+            .replace("{{PROXY_URL}}", self.proxy_url.default_url())
+            .replace("{{FAIL_MODE}}", self.fail_mode.as_str())
+            .replace("{{SHIM_LOG}}", if self.shim_log { "true" } else { "false" })
+            .replace("{{PROVIDER_PROXY_URLS}}", &self.provider_proxy_urls())
             .replace("{{API_KEY_VAR}}", &self.api_key_var)
+            .replace("{{PROVIDER_API_KEY_VARS}}", &self.provider_api_key_vars())
             .replace("{{PROVIDER_PATCHES}}", &provider_patches)
-            .replace("{{INSTALL_CALLS}}", &install_calls);
+            .replace("{{FRAMEWORK_PATCHES}}", &framework_patches)
+            .replace("{{INSTALL_CALLS}}", &install_calls)
+            .replace("{{CLI_VERSION}}", SHIM_VERSION);
 
         // Write shim file
         let shim_path = self.python_shim_path();
@@ -111,38 +264,210 @@ impl ShimGenerator {
         let mut provider_exports = String::new();
 
         for provider in &self.providers {
-            provider_exports.push_str(templates::get_typescript_provider_export(*provider));
+            provider_exports.push_str(&templates::get_typescript_provider_export(*provider));
             provider_exports.push('\n');
         }
 
         // Generate shim content from template
         let content = templates::TYPESCRIPT_SHIM_TEMPLATE
-            .replace("{{PROXY_URL}}", &self.proxy_url)
+            .replace("{{PROXY_URL}}", self.proxy_url.default_url())
+            .replace("{{FAIL_MODE}}", self.fail_mode.as_str())
+            .replace("{{SHIM_LOG}}", if self.shim_log { "true" } else { "false" })
+            .replace("{{PROVIDER_PROXY_URLS}}", &self.provider_proxy_urls())
             .replace("{{API_KEY_VAR}}", &self.api_key_var)
-            .replace("{{PROVIDER_EXPORTS}}", &provider_exports);
+            .replace("{{PROVIDER_API_KEY_VARS}}", &self.provider_api_key_vars())
+            .replace("{{PROVIDER_EXPORTS}}", &provider_exports)
+            .replace("{{CLI_VERSION}}", SHIM_VERSION);
 
         // Write TypeScript shim file
         let ts_shim_path = self.typescript_shim_path();
         fs::write(&ts_shim_path, &content)?;
 
-        // Also create JavaScript version (same content, just .js extension)
-        // TypeScript can be used as JavaScript
-        let js_shim_path = self.javascript_shim_path();
-        fs::write(&js_shim_path, &content)?;
+        self.generate_javascript_shims()?;
+
+        Ok(ts_shim_path)
+    }
+
+    /// Generate the `CommonJS` and ES module shims, plus a `.js` copy of the
+    /// one matching the project's package.json `"type"`
+    pub fn generate_javascript_shims(&self) -> Result<Vec<PathBuf>> {
+        self.ensure_shim_dir()?;
+
+        let cjs = self.fill_javascript_template(templates::JAVASCRIPT_CJS_SHIM_TEMPLATE);
 
-        // Create package.json for the shim module
-        let package_json = r#"{
+        let mut esm_exports = String::new();
+        for name in self.javascript_targets().flat_map(|target| target.classes) {
+            let _ = writeln!(esm_exports, "export const {name} = load(\"{name}\");");
+        }
+        let esm = templates::JAVASCRIPT_ESM_SHIM_TEMPLATE
+            .replace("{{ESM_EXPORTS}}", &esm_exports)
+            .replace("{{CLI_VERSION}}", SHIM_VERSION);
+
+        let esm_project = super::is_esm_package(&self.project_root);
+        let cjs_path = self.shim_dir().join(CJS_SHIM_FILENAME);
+        let esm_path = self.shim_dir().join(ESM_SHIM_FILENAME);
+        let js_path = self.javascript_shim_path();
+        fs::write(&cjs_path, &cjs)?;
+        fs::write(&esm_path, &esm)?;
+        fs::write(&js_path, if esm_project { &esm } else { &cjs })?;
+
+        // `.js` files follow the nearest package.json, so this one decides how
+        // promptguard-shim.js is loaded and must match the project
+        // Written by hand because export conditions are matched in order
+        let module_type = if esm_project { "module" } else { "commonjs" };
+        let package_json = format!(
+            r#"{{
   "name": "@promptguard/shim",
   "version": "1.0.0",
   "private": true,
   "description": "PromptGuard runtime interception shim",
-  "main": "promptguard-shim.js",
-  "types": "promptguard-shim.ts"
-}
-"#;
+  "type": "{module_type}",
+  "main": "{JAVASCRIPT_SHIM_FILENAME}",
+  "types": "{TYPESCRIPT_SHIM_FILENAME}",
+  "exports": {{
+    ".": {{
+      "types": "./{TYPESCRIPT_SHIM_FILENAME}",
+      "import": "./{ESM_SHIM_FILENAME}",
+      "require": "./{CJS_SHIM_FILENAME}"
+    }}
+  }}
+}}
+"#
+        );
         fs::write(self.shim_dir().join("package.json"), package_json)?;
 
-        Ok(ts_shim_path)
+        Ok(vec![cjs_path, esm_path, js_path])
+    }
+
+    /// Generate the Node.js preload, loaded with `--require` instead of imported
+    pub fn generate_node_preload(&self) -> Result<PathBuf> {
+        self.generate_javascript_shims()?;
+
+        let preload_path = self.node_preload_path();
+        fs::write(
+            &preload_path,
+            templates::NODE_PRELOAD_TEMPLATE.replace("{{CLI_VERSION}}", SHIM_VERSION),
+        )?;
+        Ok(preload_path)
+    }
+
+    /// Generate the Bun preload and the per-SDK wrappers it resolves imports to
+    pub fn generate_bun_preload(&self) -> Result<PathBuf> {
+        self.generate_javascript_shims()?;
+
+        let wrapper_dir = self.shim_dir().join(BUN_WRAPPER_DIR);
+        if wrapper_dir.exists() {
+            fs::remove_dir_all(&wrapper_dir)?;
+        }
+        fs::create_dir_all(&wrapper_dir)?;
+
+        let mut wrappers = serde_json::Map::new();
+        let mut packages = Vec::new();
+        for target in self.javascript_targets() {
+            let file_name = format!(
+                "{}.mjs",
+                target.package.trim_start_matches('@').replace('/', "__")
+            );
+            let mut exports = String::new();
+            for name in target.classes {
+                let _ = writeln!(
+                    exports,
+                    "export const {name} = shim.wrapClient(sdk.{name}, \"{}\");",
+                    target.package
+                );
+            }
+            let wrapper = templates::BUN_WRAPPER_TEMPLATE
+                .replace("{{PACKAGE}}", target.package)
+                .replace("{{BUN_EXPORTS}}", &exports)
+                .replace("{{CLI_VERSION}}", SHIM_VERSION);
+            fs::write(wrapper_dir.join(&file_name), wrapper)?;
+
+            wrappers.insert(
+                target.package.to_string(),
+                serde_json::Value::String(format!("{BUN_WRAPPER_DIR}/{file_name}")),
+            );
+            packages.push(target.package.replace('.', "\\.").replace('/', "\\/"));
+        }
+
+        let preload = templates::BUN_PRELOAD_TEMPLATE
+            .replace(
+                "{{BUN_WRAPPERS}}",
+                &serde_json::to_string(&wrappers).unwrap_or_else(|_| "{}".to_string()),
+            )
+            .replace("{{BUN_FILTER}}", &format!("/^(?:{})$/", packages.join("|")))
+            .replace("{{CLI_VERSION}}", SHIM_VERSION);
+        let preload_path = self.shim_dir().join(BUN_PRELOAD_FILENAME);
+        fs::write(&preload_path, preload)?;
+        Ok(preload_path)
+    }
+
+    /// Generate the Deno shim, its per-SDK wrappers, and the import map that
+    /// resolves the project's SDK imports to them
+    pub fn generate_deno_shim(&self) -> Result<Vec<PathBuf>> {
+        self.ensure_shim_dir()?;
+
+        let wrapper_dir = self.shim_dir().join(DENO_WRAPPER_DIR);
+        if wrapper_dir.exists() {
+            fs::remove_dir_all(&wrapper_dir)?;
+        }
+        fs::create_dir_all(&wrapper_dir)?;
+
+        let mut wrappers = BTreeMap::new();
+        for target in self.javascript_targets() {
+            let file_name = format!(
+                "{}.ts",
+                target.package.trim_start_matches('@').replace('/', "__")
+            );
+            let mut exports = String::new();
+            for name in target.classes {
+                let _ = writeln!(
+                    exports,
+                    "export const {name} = wrapClient(sdk.{name}, \"{}\");",
+                    target.package
+                );
+            }
+            let wrapper = templates::DENO_WRAPPER_TEMPLATE
+                .replace("{{PACKAGE}}", target.package)
+                .replace("{{DENO_EXPORTS}}", &exports)
+                .replace("{{CLI_VERSION}}", SHIM_VERSION);
+            fs::write(wrapper_dir.join(&file_name), wrapper)?;
+            wrappers.insert(
+                target.package.to_string(),
+                format!("{DENO_WRAPPER_DIR}/{file_name}"),
+            );
+        }
+
+        let shim_path = self.shim_dir().join(DENO_SHIM_FILENAME);
+        fs::write(
+            &shim_path,
+            self.fill_javascript_template(templates::DENO_SHIM_TEMPLATE),
+        )?;
+
+        let import_map = super::deno::import_map(&self.project_root, &wrappers, DENO_WRAPPER_DIR);
+        let import_map_path = self.shim_dir().join(DENO_IMPORT_MAP_FILENAME);
+        fs::write(
+            &import_map_path,
+            serde_json::to_string_pretty(&import_map).unwrap_or_default() + "\n",
+        )?;
+        Ok(vec![shim_path, import_map_path])
+    }
+
+    /// Generate `sitecustomize.py`, which Python imports at startup when the
+    /// shim directory is on `PYTHONPATH` (as `--inject docker` sets it)
+    pub fn generate_python_sitecustomize(&self) -> Result<PathBuf> {
+        self.generate_python_shim()?;
+
+        let path = self.shim_dir().join(PYTHON_SITECUSTOMIZE_FILENAME);
+        fs::write(
+            &path,
+            format!(
+                "# PromptGuard runtime shim - auto-generated\n\
+                 # {SHIM_VERSION_MARKER}{SHIM_VERSION}\n\
+                 # Imported at startup when .promptguard is on PYTHONPATH\n\
+                 import promptguard_shim  # noqa: F401\n"
+            ),
+        )?;
+        Ok(path)
     }
 
     /// Generate shim files for detected languages
@@ -175,8 +500,9 @@ impl ShimGenerator {
     /// Create .gitignore in shim directory
     fn create_gitignore(&self) -> Result<()> {
         let gitignore_path = self.shim_dir().join(".gitignore");
-        let content =
-            "# PromptGuard shim directory\n# This directory is auto-generated - safe to commit\n";
+        let content = format!(
+            "# PromptGuard shim directory\n# This directory is auto-generated - safe to commit\n\n# Local interception log\n{SHIM_LOG_FILENAME}\n"
+        );
         fs::write(gitignore_path, content)?;
         Ok(())
     }
@@ -211,16 +537,26 @@ automatically injects the PromptGuard proxy URL if not already configured.
 
 - `promptguard_shim.py` - Python runtime shim
 - `promptguard-shim.ts` - TypeScript runtime shim
-- `promptguard-shim.js` - JavaScript runtime shim
+- `promptguard-shim.cjs` - JavaScript runtime shim (CommonJS)
+- `promptguard-shim.mjs` - JavaScript runtime shim (ES module)
+- `promptguard-shim.js` - Copy of whichever of the two matches the project's package.json type
+- `preload.cjs` - Node.js preload (`promptguard enable --runtime --inject preload`)
+- `bun-preload.mjs` - Bun preload, registered in bunfig.toml, with the SDK wrappers in `bun/`
+- `deno-shim.ts` - Deno runtime shim, with the SDK wrappers in `deno/`
+- `deno-import-map.json` - Deno import map resolving SDK imports to those wrappers
+  (`deno run --import-map=.promptguard/deno-import-map.json`)
+- `sitecustomize.py` - Loads the Python shim when this directory is on `PYTHONPATH` (`--inject docker`)
 - `__init__.py` - Python package initialization
+- `shim.log` - Intercepted clients (timestamp, provider, endpoint; no payloads), written
+  when shim logging is on (`promptguard enable --runtime --shim-log`, or `PROMPTGUARD_SHIM_LOG=true`)
 
 ## Maintenance
 
 These files are **auto-generated** by the PromptGuard CLI. Do not edit manually.
 
-To regenerate:
+To regenerate after updating the CLI:
 ```bash
-promptguard enable --runtime
+promptguard shim upgrade
 ```
 
 To disable:
@@ -237,6 +573,105 @@ only routing logic to ensure API calls use the PromptGuard proxy.
         Ok(())
     }
 
+    /// SHA-256 of every generated file in the shim directory, keyed by file
+    /// name. The journal and other state kept alongside them are skipped.
+    pub fn checksums(&self) -> Result<BTreeMap<String, String>> {
+        let mut checksums = BTreeMap::new();
+        for name in GENERATED_FILENAMES {
+            let path = self.shim_dir().join(name);
+            if path.is_file() {
+                checksums.insert((*name).to_string(), sha256_hex(&fs::read(&path)?));
+            }
+        }
+        Ok(checksums)
+    }
+
+    /// Shim files that differ from `expected` (as returned by `checksums`)
+    pub fn verify(&self, expected: &BTreeMap<String, String>) -> Vec<ShimIntegrity> {
+        expected
+            .iter()
+            .filter_map(
+                |(name, sha256)| match fs::read(self.shim_dir().join(name)) {
+                    Ok(data) if sha256_hex(&data) == *sha256 => None,
+                    Ok(_) => Some(ShimIntegrity::Modified(name.clone())),
+                    Err(_) => Some(ShimIntegrity::Missing(name.clone())),
+                },
+            )
+            .collect()
+    }
+
+    /// Regenerate the shims previously generated, as listed by `recorded` file names
+    pub fn regenerate<'a>(
+        &self,
+        recorded: impl IntoIterator<Item = &'a String>,
+    ) -> Result<Vec<PathBuf>> {
+        let recorded: Vec<&String> = recorded.into_iter().collect();
+        let has = |name: &str| recorded.iter().any(|r| r.as_str() == name);
+
+        let mut languages = Vec::new();
+        if has(PYTHON_SHIM_FILENAME) {
+            languages.push(Language::Python);
+        }
+        if has(TYPESCRIPT_SHIM_FILENAME) || has(JAVASCRIPT_SHIM_FILENAME) {
+            languages.push(Language::TypeScript);
+        }
+        let mut generated = self.generate_shims(&languages)?;
+        if has(NODE_PRELOAD_FILENAME) {
+            generated.push(self.generate_node_preload()?);
+        }
+        if has(BUN_PRELOAD_FILENAME) {
+            generated.push(self.generate_bun_preload()?);
+        }
+        if has(DENO_SHIM_FILENAME) {
+            generated.extend(self.generate_deno_shim()?);
+        }
+        if has(PYTHON_SITECUSTOMIZE_FILENAME) {
+            generated.push(self.generate_python_sitecustomize()?);
+        }
+        Ok(generated)
+    }
+
+    /// Generated files currently present in the shim directory
+    pub fn installed_files(&self) -> Vec<String> {
+        GENERATED_FILENAMES
+            .iter()
+            .filter(|name| self.shim_dir().join(name).is_file())
+            .map(|name| (*name).to_string())
+            .collect()
+    }
+
+    /// Oldest CLI version among the installed shims, or `None` when none are
+    /// installed or any predates version stamping
+    pub fn installed_version(&self) -> Option<String> {
+        let stamped = [
+            PYTHON_SHIM_FILENAME,
+            TYPESCRIPT_SHIM_FILENAME,
+            CJS_SHIM_FILENAME,
+            ESM_SHIM_FILENAME,
+            NODE_PRELOAD_FILENAME,
+            BUN_PRELOAD_FILENAME,
+            DENO_SHIM_FILENAME,
+            PYTHON_SITECUSTOMIZE_FILENAME,
+        ];
+        stamped
+            .iter()
+            .filter_map(|name| fs::read_to_string(self.shim_dir().join(name)).ok())
+            .map(|content| {
+                let start = content.find(SHIM_VERSION_MARKER)? + SHIM_VERSION_MARKER.len();
+                let version = content[start..].split_whitespace().next()?;
+                Some(version.to_string())
+            })
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .min_by_key(|version| version_key(version))
+    }
+
+    /// Whether installed shims were generated by a different CLI version
+    pub fn is_outdated(&self) -> bool {
+        !self.installed_files().is_empty()
+            && self.installed_version().as_deref() != Some(SHIM_VERSION)
+    }
+
     /// Remove all generated shim files
     pub fn clean_shims(&self) -> Result<()> {
         let shim_dir = self.shim_dir();
@@ -250,10 +685,30 @@ only routing logic to ensure API calls use the PromptGuard proxy.
     pub fn shims_installed(&self) -> bool {
         let shim_dir = self.shim_dir();
         shim_dir.exists()
-            && (self.python_shim_path().exists() || self.typescript_shim_path().exists())
+            && (self.python_shim_path().exists()
+                || self.typescript_shim_path().exists()
+                || self.node_preload_path().exists())
     }
 }
 
+/// Sort key ordering dotted versions numerically
+fn version_key(version: &str) -> Vec<u64> {
+    version
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    digest(&SHA256, data)
+        .as_ref()
+        .iter()
+        .fold(String::new(), |mut hex, b| {
+            let _ = write!(hex, "{b:02x}");
+            hex
+        })
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
@@ -328,4 +783,61 @@ mod tests {
         generator.clean_shims().unwrap();
         assert!(!generator.shims_installed());
     }
+
+    #[test]
+    fn test_verify_detects_edits() {
+        let temp_dir = TempDir::new().unwrap();
+        let generator = ShimGenerator::new(
+            temp_dir.path(),
+            "https://api.promptguard.co/api/v1".to_string(),
+            "PROMPTGUARD_API_KEY".to_string(),
+            vec![Provider::OpenAI],
+        );
+        generator.generate_shims(&[Language::Python]).unwrap();
+        let checksums = generator.checksums().unwrap();
+        assert!(checksums.contains_key(PYTHON_SHIM_FILENAME));
+        assert!(generator.verify(&checksums).is_empty());
+
+        fs::write(generator.python_shim_path(), "# truncated").unwrap();
+        fs::remove_file(generator.shim_dir().join("README.md")).unwrap();
+        assert_eq!(
+            generator.verify(&checksums),
+            vec![
+                ShimIntegrity::Missing("README.md".to_string()),
+                ShimIntegrity::Modified(PYTHON_SHIM_FILENAME.to_string()),
+            ]
+        );
+
+        generator.regenerate(checksums.keys()).unwrap();
+        assert!(generator.verify(&checksums).is_empty());
+    }
+
+    #[test]
+    fn test_installed_version_detects_old_shims() {
+        let temp_dir = TempDir::new().unwrap();
+        let generator = ShimGenerator::new(
+            temp_dir.path(),
+            "https://api.promptguard.co/api/v1".to_string(),
+            "PROMPTGUARD_API_KEY".to_string(),
+            vec![Provider::OpenAI],
+        );
+        assert!(!generator.is_outdated());
+
+        generator
+            .generate_shims(&[Language::Python, Language::TypeScript])
+            .unwrap();
+        assert_eq!(generator.installed_version().as_deref(), Some(SHIM_VERSION));
+        assert!(!generator.is_outdated());
+
+        let ts = fs::read_to_string(generator.typescript_shim_path()).unwrap();
+        let old = ts.replace(&format!("CLI v{SHIM_VERSION}"), "CLI v0.9.0");
+        fs::write(generator.typescript_shim_path(), old).unwrap();
+        assert_eq!(generator.installed_version().as_deref(), Some("0.9.0"));
+        assert!(generator.is_outdated());
+
+        // Shims from before versions were stamped
+        fs::write(generator.python_shim_path(), "# PromptGuard shim\n").unwrap();
+        assert_eq!(generator.installed_version(), None);
+        assert!(generator.is_outdated());
+    }
 }
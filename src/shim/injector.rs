@@ -2,21 +2,79 @@
 ///
 /// Detects application entry points and injects shim imports to enable
 /// runtime interception of LLM SDK calls.
-use crate::error::Result;
-use crate::scanner::is_skip_dir;
+use crate::error::{PromptGuardError, Result};
+use crate::scanner::{is_skip_dir, FileScanner};
+use crate::shim::docker::{self, ComposeInjection};
+use crate::textfile;
 use crate::types::Language;
 use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-const PYTHON_SHIM_IMPORT: &str = "\n# PromptGuard runtime shim - auto-injected\nimport sys\nimport os\nsys.path.insert(0, os.path.join(os.path.dirname(__file__), '.promptguard'))\nimport promptguard_shim\n";
-
 const PYTHON_SHIM_IMPORT_MARKER: &str = "# PromptGuard runtime shim - auto-injected";
 
+/// Installed into site-packages, where Python runs its `import` lines at startup
+const PYTHON_SITE_PTH_FILENAME: &str = "promptguard_shim.pth";
+
+const TYPESCRIPT_SHIM_IMPORT_MARKER: &str = "// PromptGuard runtime shim - auto-injected";
+
+/// Extensions `remove_all_injections` checks for TypeScript/JavaScript imports
+const TYPESCRIPT_EXTENSIONS: &[&str] = &["ts", "tsx", "mts", "cts", "js", "jsx", "mjs", "cjs"];
+
+/// Node.js flag loading the generated preload, added to start scripts and `NODE_OPTIONS`
+pub const NODE_PRELOAD_REQUIRE: &str = "--require ./.promptguard/preload.cjs";
+
+/// Bun preload registered in bunfig.toml
+pub const BUN_PRELOAD_PATH: &str = "./.promptguard/bun-preload.mjs";
+
+/// Trailing comment on a bunfig.toml `preload` line added by `enable --runtime`
+const BUNFIG_MARKER: &str = "# promptguard: runtime shim";
+
+/// How runtime mode loads the shim
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShimInjection {
+    /// Import the shim at the top of each detected entry point
+    EntryPoints,
+    /// Load a Node.js preload with `--require`, leaving entry points untouched
+    Preload,
+    /// Load the Python shim from a `.pth` file in the virtualenv's site-packages
+    Site,
+    /// Set the variables that load the shims in docker-compose services
+    Docker,
+}
+
+impl ShimInjection {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "entry-points" => Some(Self::EntryPoints),
+            "preload" => Some(Self::Preload),
+            "site" => Some(Self::Site),
+            "docker" => Some(Self::Docker),
+            _ => None,
+        }
+    }
+}
+
+/// Python test modules and runners, never treated as entry points unless
+/// `with_tests` asks for them. Matched against the path relative to the
+/// project root and the file name.
+pub const PYTHON_TEST_PATTERNS: &[&str] = &[
+    "test_*.py",
+    "*_test.py",
+    "conftest.py",
+    "noxfile.py",
+    "runtests.py",
+    "**/tests/**",
+    "**/test/**",
+];
+
 /// Entry point detector and injector
 pub struct ShimInjector {
     project_root: PathBuf,
+    /// Exclude patterns from config; `None` uses the scanner defaults
+    exclude_patterns: Option<Vec<String>>,
+    include_tests: bool,
 }
 
 impl ShimInjector {
@@ -24,18 +82,46 @@ impl ShimInjector {
     pub fn new(project_root: impl AsRef<Path>) -> Self {
         Self {
             project_root: project_root.as_ref().to_path_buf(),
+            exclude_patterns: None,
+            include_tests: false,
         }
     }
 
+    /// Skip Python entry points matching the configured exclude patterns
+    pub fn with_exclude_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.exclude_patterns = Some(patterns);
+        self
+    }
+
+    /// Treat test modules and runners like any other Python entry point
+    pub fn with_tests(mut self, include_tests: bool) -> Self {
+        self.include_tests = include_tests;
+        self
+    }
+
     /// Detect Python entry points
     ///
-    /// Looks for common Python entry point patterns:
+    /// In a Django, `FastAPI` or Flask project (see `FileScanner::detect_python_framework`)
+    /// these are the framework's bootstrap modules:
+    /// - Django: manage.py, wsgi.py, asgi.py
+    /// - `FastAPI`: modules creating the `FastAPI()` app, asgi.py
+    /// - Flask: modules with the app factory or the `Flask()` app, wsgi.py
+    ///
+    /// Other projects, and framework projects without any of those, use the
+    /// common entry point patterns:
     /// - Files with `if __name__ == "__main__":`
     /// - main.py, app.py, server.py, run.py
     /// - manage.py (Django)
     /// - wsgi.py, asgi.py (WSGI/ASGI apps)
+    ///
+    /// Scripts Docker containers start are always included. Files matching the
+    /// exclude patterns never are, nor are tests (`PYTHON_TEST_PATTERNS`, and
+    /// directories below the root with a `conftest.py`) without `with_tests`.
     pub fn detect_python_entry_points(&self) -> Result<Vec<PathBuf>> {
-        let mut entry_points = HashSet::new();
+        let scanner = FileScanner::new(&self.project_root, self.exclude_patterns.clone())?;
+        let skipped = |path: &Path| {
+            scanner.is_excluded(path) || (!self.include_tests && self.is_test_file(path))
+        };
 
         // Common entry point filenames
         let common_entry_files = [
@@ -49,6 +135,7 @@ impl ShimInjector {
             "__main__.py",
         ];
 
+        let mut sources = Vec::new();
         for entry in WalkDir::new(&self.project_root)
             .max_depth(3) // Don't go too deep
             .follow_links(false)
@@ -63,36 +150,74 @@ impl ShimInjector {
                 continue;
             }
 
-            if !path.is_file() {
+            if !path.is_file()
+                || !path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("py"))
+                || skipped(path)
+            {
                 continue;
             }
 
-            let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-
-            // Check if it's a common entry point file
-            if common_entry_files.contains(&file_name) {
-                entry_points.insert(path.to_path_buf());
-                continue;
-            }
+            let content = fs::read_to_string(path).unwrap_or_default();
+            sources.push((path.to_path_buf(), content));
+        }
 
-            // Check if file contains if __name__ == "__main__":
-            if path
-                .extension()
-                .is_some_and(|ext| ext.eq_ignore_ascii_case("py"))
-            {
-                if let Ok(content) = fs::read_to_string(path) {
-                    if content.contains("if __name__ == \"__main__\":")
-                        || content.contains("if __name__ == '__main__':")
-                    {
-                        entry_points.insert(path.to_path_buf());
-                    }
-                }
-            }
+        let framework = scanner.detect_python_framework();
+        let mut entry_points: HashSet<PathBuf> = sources
+            .iter()
+            .filter(|(path, content)| {
+                framework
+                    .as_deref()
+                    .is_some_and(|fw| is_framework_bootstrap(fw, path, content))
+            })
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        // No framework bootstrap: every script that looks runnable
+        if entry_points.is_empty() {
+            entry_points.extend(
+                sources
+                    .iter()
+                    .filter(|(path, content)| {
+                        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                        common_entry_files.contains(&file_name)
+                            || content.contains("if __name__ == \"__main__\":")
+                            || content.contains("if __name__ == '__main__':")
+                    })
+                    .map(|(path, _)| path.clone()),
+            );
         }
 
+        // Scripts Docker containers start
+        entry_points.extend(
+            docker::entry_scripts(&self.project_root)
+                .into_iter()
+                .filter(|path| path.extension().is_some_and(|ext| ext == "py"))
+                .filter(|path| !skipped(path)),
+        );
+
         Ok(entry_points.into_iter().collect())
     }
 
+    /// Test modules and runners: `PYTHON_TEST_PATTERNS`, or anything in a
+    /// directory below the root holding a `conftest.py`
+    fn is_test_file(&self, path: &Path) -> bool {
+        let rel = path.strip_prefix(&self.project_root).unwrap_or(path);
+        let rel_str = rel.to_string_lossy().replace('\\', "/");
+        let file_name = rel.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let matches_pattern = PYTHON_TEST_PATTERNS
+            .iter()
+            .filter_map(|p| glob::Pattern::new(p).ok())
+            .any(|p| p.matches(&rel_str) || p.matches(file_name));
+        matches_pattern
+            || rel
+                .ancestors()
+                .skip(1)
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .any(|dir| self.project_root.join(dir).join("conftest.py").is_file())
+    }
+
     /// Detect TypeScript/JavaScript entry points
     ///
     /// Looks for:
@@ -178,12 +303,23 @@ impl ShimInjector {
             }
         }
 
+        // Scripts Docker containers start
+        entry_points.extend(
+            docker::entry_scripts(&self.project_root)
+                .into_iter()
+                .filter(|path| {
+                    path.extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| TYPESCRIPT_EXTENSIONS.contains(&ext))
+                }),
+        );
+
         Ok(entry_points.into_iter().collect())
     }
 
     /// Inject Python shim import into a file
     pub fn inject_python_shim(&self, file_path: &Path) -> Result<bool> {
-        let content = fs::read_to_string(file_path)?;
+        let (format, content) = textfile::read(file_path)?;
 
         // Check if already injected
         if content.contains(PYTHON_SHIM_IMPORT_MARKER) {
@@ -226,10 +362,11 @@ impl ShimInjector {
         }
 
         // Insert shim import
+        let shim_import = self.python_shim_import(file_path);
         let mut new_content = String::new();
         for (i, line) in lines.iter().enumerate() {
             if i == inject_pos {
-                new_content.push_str(PYTHON_SHIM_IMPORT);
+                new_content.push_str(&shim_import);
             }
             new_content.push_str(line);
             new_content.push('\n');
@@ -237,48 +374,400 @@ impl ShimInjector {
 
         // Handle case where inject_pos is at the end
         if inject_pos >= lines.len() {
-            new_content.push_str(PYTHON_SHIM_IMPORT);
+            new_content.push_str(&shim_import);
         }
 
-        fs::write(file_path, new_content)?;
+        textfile::write(file_path, format, &new_content)?;
         Ok(true)
     }
 
     /// Remove Python shim import from a file
     pub fn remove_python_shim(&self, file_path: &Path) -> Result<bool> {
-        let content = fs::read_to_string(file_path)?;
+        let (format, content) = textfile::read(file_path)?;
 
         if !content.contains(PYTHON_SHIM_IMPORT_MARKER) {
             return Ok(false); // Not injected
         }
 
-        // Remove the shim import block
-        let lines: Vec<&str> = content.lines().collect();
-        let mut new_lines = Vec::new();
-        let mut skip_until_blank = false;
+        // Remove the shim import block: the blank line before the marker,
+        // then the block's own lines up to `import promptguard_shim`
+        let mut new_lines: Vec<&str> = Vec::new();
+        let mut in_block = false;
 
-        for line in lines {
+        for line in content.lines() {
             if line.contains(PYTHON_SHIM_IMPORT_MARKER) {
-                skip_until_blank = true;
+                if new_lines.last().is_some_and(|l| l.trim().is_empty()) {
+                    new_lines.pop();
+                }
+                in_block = true;
                 continue;
             }
 
-            if skip_until_blank {
-                if line.trim().is_empty() {
-                    skip_until_blank = false;
+            if in_block {
+                let trimmed = line.trim();
+                if trimmed == "import promptguard_shim" || trimmed.is_empty() {
+                    in_block = false;
+                    continue;
                 }
-                continue;
+                if matches!(trimmed, "import sys" | "import os")
+                    || (trimmed.starts_with("sys.path.insert(") && trimmed.contains(".promptguard"))
+                {
+                    continue;
+                }
+                in_block = false;
             }
 
             new_lines.push(line);
         }
 
         let new_content = new_lines.join("\n") + "\n";
-        fs::write(file_path, new_content)?;
+        textfile::write(file_path, format, &new_content)?;
         Ok(true)
     }
 
-    /// Inject TypeScript/JavaScript shim imports
+    /// The active virtualenv (`VIRTUAL_ENV`), or one in the project root
+    /// (`.venv`, `venv`, `env`)
+    pub fn find_virtualenv(&self) -> Option<PathBuf> {
+        std::env::var_os("VIRTUAL_ENV")
+            .map(PathBuf::from)
+            .into_iter()
+            .chain(
+                [".venv", "venv", "env"]
+                    .iter()
+                    .map(|d| self.project_root.join(d)),
+            )
+            .find(|dir| dir.join("pyvenv.cfg").is_file())
+    }
+
+    /// Install a `.pth` file into `virtualenv` that imports the shim at
+    /// interpreter startup, so entry points are never edited. Returns its path.
+    ///
+    /// The shim is only imported while `.promptguard/` exists, so deleting
+    /// the directory never breaks Python in the virtualenv.
+    pub fn inject_python_site(&self, virtualenv: &Path) -> Result<PathBuf> {
+        let site_packages = site_packages(virtualenv)?;
+        let shim_dir = self.project_root.join(".promptguard");
+        // Debug formatting escapes quotes and backslashes the way Python reads them
+        let shim_dir = format!("{:?}", shim_dir.to_string_lossy());
+        let content = format!(
+            "{PYTHON_SHIM_IMPORT_MARKER}\nimport os, sys; os.path.isdir({shim_dir}) and (sys.path.insert(0, {shim_dir}) or __import__('promptguard_shim'))\n"
+        );
+        let pth_path = site_packages.join(PYTHON_SITE_PTH_FILENAME);
+        fs::write(&pth_path, content)?;
+        Ok(pth_path)
+    }
+
+    /// The `.pth` file `inject_python_site` installed into `virtualenv`, if present
+    pub fn python_site_path(&self, virtualenv: &Path) -> Option<PathBuf> {
+        let pth_path = site_packages(virtualenv)
+            .ok()?
+            .join(PYTHON_SITE_PTH_FILENAME);
+        fs::read_to_string(&pth_path)
+            .is_ok_and(|content| content.starts_with(PYTHON_SHIM_IMPORT_MARKER))
+            .then_some(pth_path)
+    }
+
+    /// Remove the `.pth` file `inject_python_site` installed, if present
+    pub fn remove_python_site(&self, virtualenv: &Path) -> Result<Option<PathBuf>> {
+        let Some(pth_path) = self.python_site_path(virtualenv) else {
+            return Ok(None);
+        };
+        fs::remove_file(&pth_path)?;
+        Ok(Some(pth_path))
+    }
+
+    /// Inject TypeScript/JavaScript shim import into a file
+    ///
+    /// The import goes above the file's other imports, after any shebang and
+    /// `"use strict"`-style directives. `CommonJS` files get a `require` instead.
+    pub fn inject_typescript_shim(&self, file_path: &Path) -> Result<bool> {
+        let (format, content) = textfile::read(file_path)?;
+
+        if content.contains(TYPESCRIPT_SHIM_IMPORT_MARKER) {
+            return Ok(false); // Already injected
+        }
+
+        let lines: Vec<&str> = content.lines().collect();
+        let mut inject_pos = 0;
+        if lines.first().is_some_and(|l| l.starts_with("#!")) {
+            inject_pos = 1;
+        }
+        while lines
+            .get(inject_pos)
+            .is_some_and(|l| is_directive(l.trim()))
+        {
+            inject_pos += 1;
+        }
+
+        let specifier = self.shim_specifier(file_path);
+        let is_typescript = file_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| matches!(ext, "ts" | "tsx" | "mts" | "cts"));
+        // TypeScript resolves the .ts shim itself; Node needs the exact variant
+        let statement = if is_typescript {
+            format!("import '{specifier}';")
+        } else if is_commonjs(file_path, &content) {
+            format!("require('{specifier}.cjs');")
+        } else {
+            format!("import '{specifier}.mjs';")
+        };
+
+        let mut new_lines: Vec<&str> = lines[..inject_pos].to_vec();
+        new_lines.push(TYPESCRIPT_SHIM_IMPORT_MARKER);
+        new_lines.push(&statement);
+        new_lines.extend(&lines[inject_pos..]);
+
+        textfile::write(file_path, format, &(new_lines.join("\n") + "\n"))?;
+        Ok(true)
+    }
+
+    /// Remove TypeScript/JavaScript shim import from a file
+    pub fn remove_typescript_shim(&self, file_path: &Path) -> Result<bool> {
+        let (format, content) = textfile::read(file_path)?;
+
+        if !content.contains(TYPESCRIPT_SHIM_IMPORT_MARKER) {
+            return Ok(false); // Not injected
+        }
+
+        // The marker is always followed by exactly one import/require line
+        let mut new_lines = Vec::new();
+        let mut lines = content.lines();
+        while let Some(line) = lines.next() {
+            if line.trim() == TYPESCRIPT_SHIM_IMPORT_MARKER {
+                lines.next();
+                continue;
+            }
+            new_lines.push(line);
+        }
+
+        let new_content = new_lines.join("\n") + "\n";
+        textfile::write(file_path, format, &new_content)?;
+        Ok(true)
+    }
+
+    /// Add `NODE_PRELOAD_REQUIRE` to the `node` invocations of package.json
+    /// start scripts (`start`, `start:*`, `dev`, `serve`). Returns the names
+    /// of the scripts that changed.
+    pub fn inject_node_preload(&self) -> Result<Vec<String>> {
+        self.edit_start_scripts(|command| {
+            if command.contains(NODE_PRELOAD_REQUIRE) {
+                None
+            } else {
+                with_node_flag(command, NODE_PRELOAD_REQUIRE)
+            }
+        })
+    }
+
+    /// package.json scripts loading `NODE_PRELOAD_REQUIRE`
+    pub fn node_preload_scripts(&self) -> Vec<String> {
+        let Ok((_, content)) = textfile::read(&self.project_root.join("package.json")) else {
+            return Vec::new();
+        };
+        serde_json::from_str::<serde_json::Value>(&content)
+            .ok()
+            .and_then(|parsed| parsed.get("scripts").and_then(|v| v.as_object()).cloned())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, command)| {
+                command
+                    .as_str()
+                    .is_some_and(|c| c.contains(NODE_PRELOAD_REQUIRE))
+            })
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Remove `NODE_PRELOAD_REQUIRE` from package.json start scripts
+    pub fn remove_node_preload(&self) -> Result<Vec<String>> {
+        self.edit_start_scripts(|command| {
+            command
+                .contains(NODE_PRELOAD_REQUIRE)
+                .then(|| command.replace(&format!(" {NODE_PRELOAD_REQUIRE}"), ""))
+        })
+    }
+
+    /// Rewrite start scripts in place, so the rest of package.json keeps its formatting
+    fn edit_start_scripts(&self, edit: impl Fn(&str) -> Option<String>) -> Result<Vec<String>> {
+        let package_json_path = self.project_root.join("package.json");
+        if !package_json_path.exists() {
+            return Ok(Vec::new());
+        }
+        let (format, mut content) = textfile::read(&package_json_path)?;
+        let parsed: serde_json::Value = serde_json::from_str(&content)?;
+        let Some(scripts) = parsed.get("scripts").and_then(|v| v.as_object()) else {
+            return Ok(Vec::new());
+        };
+        let Some(scripts_start) = content.find("\"scripts\"") else {
+            return Ok(Vec::new());
+        };
+
+        let mut changed = Vec::new();
+        for (name, command) in scripts {
+            let is_start_script =
+                matches!(name.as_str(), "start" | "dev" | "serve") || name.starts_with("start:");
+            let Some(command) = command.as_str().filter(|_| is_start_script) else {
+                continue;
+            };
+            let Some(new_command) = edit(command) else {
+                continue;
+            };
+            let old_literal = serde_json::to_string(command)?;
+            let new_literal = serde_json::to_string(&new_command)?;
+            let Some(offset) = content[scripts_start..].find(&old_literal) else {
+                continue;
+            };
+            let start = scripts_start + offset;
+            content.replace_range(start..start + old_literal.len(), &new_literal);
+            changed.push(name.clone());
+        }
+
+        if !changed.is_empty() {
+            textfile::write(&package_json_path, format, &content)?;
+        }
+        Ok(changed)
+    }
+
+    /// Whether the project runs on Bun: a Bun lockfile or bunfig.toml
+    pub fn is_bun_project(&self) -> bool {
+        ["bun.lockb", "bun.lock", "bunfig.toml"]
+            .iter()
+            .any(|name| self.project_root.join(name).is_file())
+    }
+
+    /// Add `BUN_PRELOAD_PATH` to the top-level `preload` of bunfig.toml,
+    /// creating the file or key when missing. Returns false if already present.
+    pub fn inject_bun_preload(&self) -> Result<bool> {
+        let bunfig_path = self.project_root.join("bunfig.toml");
+        let (format, content) = if bunfig_path.exists() {
+            textfile::read(&bunfig_path)?
+        } else {
+            (textfile::TextFormat::default(), String::new())
+        };
+        if content.contains(BUN_PRELOAD_PATH) {
+            return Ok(false);
+        }
+
+        let entry = format!("\"{BUN_PRELOAD_PATH}\"");
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+        // Top-level keys end at the first [table] header
+        let top_level_end = lines
+            .iter()
+            .position(|line| line.trim_start().starts_with('['))
+            .unwrap_or(lines.len());
+        let preload_line = lines[..top_level_end].iter().position(|line| {
+            line.split_once('=')
+                .is_some_and(|(key, _)| key.trim() == "preload")
+        });
+
+        match preload_line {
+            Some(index) => {
+                let line = lines[index].clone();
+                let (key, value) = line.split_once('=').unwrap_or_default();
+                let value = value.trim();
+                lines[index] = if let Some(inner) = value.strip_prefix('[') {
+                    if inner.trim().starts_with(']') {
+                        format!("{key}= [{entry}]")
+                    } else if inner.trim().is_empty() {
+                        // Multi-line array: the entry goes on its own line
+                        lines.insert(index + 1, format!("  {entry},"));
+                        line.clone()
+                    } else {
+                        format!("{key}= [{entry}, {inner}")
+                    }
+                } else {
+                    // A single preload string becomes an array
+                    format!("{key}= [{entry}, {value}]")
+                };
+            },
+            None => lines.insert(0, format!("preload = [{entry}]  {BUNFIG_MARKER}")),
+        }
+
+        let mut updated = lines.join("\n");
+        updated.push('\n');
+        textfile::write(&bunfig_path, format, &updated)?;
+        Ok(true)
+    }
+
+    /// Whether bunfig.toml preloads `BUN_PRELOAD_PATH`
+    pub fn has_bun_preload(&self) -> bool {
+        fs::read_to_string(self.project_root.join("bunfig.toml"))
+            .is_ok_and(|content| content.contains(BUN_PRELOAD_PATH))
+    }
+
+    /// Remove `BUN_PRELOAD_PATH` from bunfig.toml, deleting the file if
+    /// `enable --runtime` created it and nothing else is left
+    pub fn remove_bun_preload(&self) -> Result<bool> {
+        let bunfig_path = self.project_root.join("bunfig.toml");
+        if !bunfig_path.exists() {
+            return Ok(false);
+        }
+        let (format, content) = textfile::read(&bunfig_path)?;
+        if !content.contains(BUN_PRELOAD_PATH) {
+            return Ok(false);
+        }
+
+        let entry = format!("\"{BUN_PRELOAD_PATH}\"");
+        let lines: Vec<String> = content
+            .lines()
+            .filter(|line| !line.trim_end().ends_with(BUNFIG_MARKER))
+            .filter(|line| line.trim() != format!("{entry},") && line.trim() != entry)
+            .map(|line| {
+                line.replace(&format!("{entry}, "), "")
+                    .replace(&format!(", {entry}"), "")
+                    .replace(&entry, "")
+            })
+            .collect();
+
+        if lines.iter().all(|line| line.trim().is_empty()) {
+            fs::remove_file(&bunfig_path)?;
+        } else {
+            let mut updated = lines.join("\n");
+            updated.push('\n');
+            textfile::write(&bunfig_path, format, &updated)?;
+        }
+        Ok(true)
+    }
+
+    /// Set `NODE_OPTIONS`/`PYTHONPATH` in docker-compose services running one
+    /// of `languages`, so containers load the shims
+    pub fn inject_compose_env(&self, languages: &[Language]) -> Result<Vec<ComposeInjection>> {
+        docker::inject_compose_env(&self.project_root, languages)
+    }
+
+    /// Remove the variables `inject_compose_env` added. Returns the files changed.
+    pub fn remove_compose_env(&self) -> Result<Vec<PathBuf>> {
+        docker::remove_compose_env(&self.project_root)
+    }
+
+    /// Directories between the project root and `file_path`
+    fn depth(&self, file_path: &Path) -> usize {
+        file_path
+            .parent()
+            .and_then(|dir| dir.strip_prefix(&self.project_root).ok())
+            .map_or(0, |rel| rel.components().count())
+    }
+
+    /// Python block importing the generated shim, which puts the shim
+    /// directory (relative to `file_path`) on `sys.path` first
+    fn python_shim_import(&self, file_path: &Path) -> String {
+        let up = "'..', ".repeat(self.depth(file_path));
+        format!(
+            "\n{PYTHON_SHIM_IMPORT_MARKER}\nimport sys\nimport os\nsys.path.insert(0, os.path.join(os.path.dirname(__file__), {up}'.promptguard'))\nimport promptguard_shim\n"
+        )
+    }
+
+    /// Module specifier for the generated shim, relative to `file_path`
+    fn shim_specifier(&self, file_path: &Path) -> String {
+        let depth = self.depth(file_path);
+        let prefix = if depth == 0 {
+            "./".to_string()
+        } else {
+            "../".repeat(depth)
+        };
+        format!("{prefix}.promptguard/promptguard-shim")
+    }
+
     /// Inject shims into all detected entry points for a language
     pub fn inject_shims(&self, language: Language) -> Result<Vec<PathBuf>> {
         match language {
@@ -295,21 +784,34 @@ impl ShimInjector {
                 Ok(injected)
             },
             Language::TypeScript | Language::JavaScript => {
-                // For TS/JS, we don't auto-inject, just detect
-                let entry_points = self.detect_typescript_entry_points()?;
-                Ok(entry_points)
+                let mut entry_points = self.detect_typescript_entry_points()?;
+                entry_points.sort();
+                let mut injected = Vec::new();
+
+                for entry_point in entry_points {
+                    // Build output is regenerated, so only sources are edited
+                    if self.in_skip_dir(&entry_point) {
+                        continue;
+                    }
+                    if self.inject_typescript_shim(&entry_point)? {
+                        injected.push(entry_point);
+                    }
+                }
+
+                Ok(injected)
             },
         }
     }
 
-    /// Remove shim injections from all files
-    pub fn remove_all_injections(&self) -> Result<usize> {
-        let mut removed_count = 0;
-
-        // Find all Python files with injections
+    /// Source files carrying an injected shim import
+    pub fn injected_files(&self) -> Result<Vec<PathBuf>> {
+        let mut injected = Vec::new();
         for entry in WalkDir::new(&self.project_root)
             .max_depth(5)
             .follow_links(false)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !e.file_name().to_str().is_some_and(is_skip_dir))
         {
             let entry = entry.map_err(std::io::Error::other)?;
             let path = entry.path();
@@ -318,15 +820,146 @@ impl ShimInjector {
                 continue;
             }
 
-            if path.extension().and_then(|e| e.to_str()) == Some("py")
-                && self.remove_python_shim(path)?
-            {
-                removed_count += 1;
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+            let marker = if ext == "py" {
+                PYTHON_SHIM_IMPORT_MARKER
+            } else if TYPESCRIPT_EXTENSIONS.contains(&ext) {
+                TYPESCRIPT_SHIM_IMPORT_MARKER
+            } else {
+                continue;
+            };
+            if textfile::read(path).is_ok_and(|(_, content)| content.contains(marker)) {
+                injected.push(path.to_path_buf());
             }
         }
+        Ok(injected)
+    }
 
+    /// Remove shim injections from all files
+    pub fn remove_all_injections(&self) -> Result<usize> {
+        let mut removed_count = 0;
+        for path in self.injected_files()? {
+            let removed = if path.extension().is_some_and(|ext| ext == "py") {
+                self.remove_python_shim(&path)?
+            } else {
+                self.remove_typescript_shim(&path)?
+            };
+            if removed {
+                removed_count += 1;
+            }
+        }
         Ok(removed_count)
     }
+
+    fn in_skip_dir(&self, path: &Path) -> bool {
+        path.strip_prefix(&self.project_root)
+            .unwrap_or(path)
+            .components()
+            .any(|c| c.as_os_str().to_str().is_some_and(is_skip_dir))
+    }
+}
+
+/// Whether `path` is where `framework` bootstraps the application
+fn is_framework_bootstrap(framework: &str, path: &Path, content: &str) -> bool {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    match framework {
+        "django" => matches!(file_name, "manage.py" | "wsgi.py" | "asgi.py"),
+        "fastapi" => file_name == "asgi.py" || assigns_call(content, "FastAPI"),
+        "flask" => {
+            file_name == "wsgi.py"
+                || content.contains("def create_app(")
+                || assigns_call(content, "Flask")
+        },
+        _ => false,
+    }
+}
+
+/// Whether `content` assigns the result of calling `class` (`app = FastAPI(...)`,
+/// `app: Flask = flask.Flask(__name__)`)
+fn assigns_call(content: &str, class: &str) -> bool {
+    content.lines().any(|line| {
+        let Some((target, value)) = line.split_once('=') else {
+            return false;
+        };
+        let value = value.trim_start();
+        let callee = value.split('(').next().unwrap_or("");
+        !target.trim_start().starts_with('#')
+            && !target.contains('(')
+            && value.contains('(')
+            && callee.rsplit('.').next() == Some(class)
+    })
+}
+
+/// `site-packages` of a virtualenv: `Lib/site-packages` on Windows,
+/// `lib/pythonX.Y/site-packages` elsewhere
+fn site_packages(virtualenv: &Path) -> Result<PathBuf> {
+    let windows = virtualenv.join("Lib").join("site-packages");
+    if windows.is_dir() {
+        return Ok(windows);
+    }
+    let mut candidates: Vec<PathBuf> = fs::read_dir(virtualenv.join("lib"))
+        .into_iter()
+        .flatten()
+        .filter_map(std::result::Result::ok)
+        .filter(|e| e.file_name().to_string_lossy().starts_with("python"))
+        .map(|e| e.path().join("site-packages"))
+        .filter(|p| p.is_dir())
+        .collect();
+    candidates.sort();
+    candidates.pop().ok_or_else(|| {
+        PromptGuardError::Custom(format!(
+            "No site-packages directory in virtualenv {}",
+            virtualenv.display()
+        ))
+    })
+}
+
+/// `command` with `flag` added after each `node` it runs, or `None` if it never runs `node`
+fn with_node_flag(command: &str, flag: &str) -> Option<String> {
+    let mut words: Vec<&str> = Vec::new();
+    let mut at_command_start = true;
+    let mut found = false;
+    for word in command.split(' ') {
+        words.push(word);
+        if word.is_empty() {
+            continue;
+        }
+        if word == "node" && at_command_start {
+            words.push(flag);
+            found = true;
+        }
+        // `NODE_ENV=production node ...` still starts the command with node
+        let is_env_assignment = word.contains('=') && !word.starts_with('-');
+        at_command_start = matches!(word, "&&" | "||" | ";" | "|")
+            || word.ends_with(';')
+            || (at_command_start && is_env_assignment);
+    }
+    found.then(|| words.join(" "))
+}
+
+/// `"use strict";`, `'use client'` and similar prologue directives
+fn is_directive(line: &str) -> bool {
+    let line = line.trim_end_matches(';');
+    ["\"use ", "'use "]
+        .iter()
+        .any(|q| line.starts_with(q) && line.ends_with(&q[..1]))
+}
+
+/// Whether `file_path` is a `CommonJS` module, where `import` is a syntax error
+fn is_commonjs(file_path: &Path, content: &str) -> bool {
+    match file_path.extension().and_then(|e| e.to_str()) {
+        Some("cjs") => true,
+        // Plain .js is CommonJS unless package.json says otherwise or it
+        // already uses ES module syntax (bundled code)
+        Some("js") => {
+            !file_path.parent().is_some_and(super::is_esm_package)
+                && !content.lines().any(|l| {
+                    let l = l.trim_start();
+                    l.starts_with("import ") || l.starts_with("import{") || l.starts_with("export ")
+                })
+        },
+        _ => false,
+    }
 }
 
 #[cfg(test)]
@@ -358,6 +991,93 @@ mod tests {
         assert!(entry_points.iter().any(|p| p.ends_with("script.py")));
     }
 
+    #[test]
+    fn test_framework_entry_points() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let main = "if __name__ == \"__main__\":\n    main()\n";
+        fs::create_dir_all(root.join("mysite")).unwrap();
+        fs::create_dir_all(root.join("tests")).unwrap();
+        fs::write(root.join("requirements.txt"), "Django>=5.0\n").unwrap();
+        fs::write(root.join("manage.py"), main).unwrap();
+        fs::write(
+            root.join("mysite/wsgi.py"),
+            "application = get_wsgi_application()\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("mysite/asgi.py"),
+            "application = get_asgi_application()\n",
+        )
+        .unwrap();
+        fs::write(root.join("seed.py"), main).unwrap();
+        fs::write(root.join("tests/run.py"), main).unwrap();
+
+        let injector = ShimInjector::new(root);
+        let mut entry_points = injector.detect_python_entry_points().unwrap();
+        entry_points.sort();
+        assert_eq!(
+            entry_points,
+            vec![
+                root.join("manage.py"),
+                root.join("mysite/asgi.py"),
+                root.join("mysite/wsgi.py")
+            ]
+        );
+
+        // Nested bootstrap modules find the shim directory at the project root
+        injector
+            .inject_python_shim(&root.join("mysite/wsgi.py"))
+            .unwrap();
+        let content = fs::read_to_string(root.join("mysite/wsgi.py")).unwrap();
+        assert!(content.contains("os.path.dirname(__file__), '..', '.promptguard'"));
+
+        // FastAPI: the module creating the app, not CLI scripts or tests
+        fs::write(root.join("requirements.txt"), "fastapi\nuvicorn\n").unwrap();
+        fs::remove_dir_all(root.join("mysite")).unwrap();
+        fs::remove_file(root.join("manage.py")).unwrap();
+        fs::write(root.join("api.py"), "app = FastAPI(title=\"api\")\n").unwrap();
+        fs::write(root.join("test_api.py"), main).unwrap();
+        assert_eq!(
+            injector.detect_python_entry_points().unwrap(),
+            vec![root.join("api.py")]
+        );
+    }
+
+    #[test]
+    fn test_python_entry_points_skip_tests_and_excludes() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let main = "if __name__ == \"__main__\":\n    main()\n";
+        fs::create_dir_all(root.join("integration")).unwrap();
+        fs::create_dir_all(root.join("scripts")).unwrap();
+        fs::write(root.join("main.py"), main).unwrap();
+        fs::write(root.join("conftest.py"), "").unwrap();
+        fs::write(root.join("runtests.py"), main).unwrap();
+        fs::write(root.join("integration/conftest.py"), "").unwrap();
+        fs::write(root.join("integration/harness.py"), main).unwrap();
+        fs::write(root.join("scripts/seed.py"), main).unwrap();
+
+        let injector =
+            ShimInjector::new(root).with_exclude_patterns(vec!["scripts/**".to_string()]);
+        let mut entry_points = injector.detect_python_entry_points().unwrap();
+        entry_points.sort();
+        assert_eq!(entry_points, vec![root.join("main.py")]);
+
+        // Tests are injected on request; exclude patterns still apply
+        let injector = injector.with_tests(true);
+        let mut entry_points = injector.detect_python_entry_points().unwrap();
+        entry_points.sort();
+        assert_eq!(
+            entry_points,
+            vec![
+                root.join("integration/harness.py"),
+                root.join("main.py"),
+                root.join("runtests.py"),
+            ]
+        );
+    }
+
     #[test]
     fn test_inject_python_shim() {
         let temp_dir = TempDir::new().unwrap();
@@ -396,4 +1116,194 @@ mod tests {
         let after_remove = fs::read_to_string(&test_file).unwrap();
         assert!(!after_remove.contains("import promptguard_shim"));
     }
+
+    #[test]
+    fn test_remove_python_shim_keeps_following_code() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("test.py");
+        let injector = ShimInjector::new(temp_dir.path());
+
+        // The block ends at `import promptguard_shim`, not at a blank line
+        for original in [
+            "print('hello')\n",
+            "#!/usr/bin/env python3\nfrom openai import OpenAI\nclient = OpenAI()\n",
+        ] {
+            fs::write(&test_file, original).unwrap();
+            injector.inject_python_shim(&test_file).unwrap();
+            assert!(injector.remove_python_shim(&test_file).unwrap());
+            assert_eq!(fs::read_to_string(&test_file).unwrap(), original);
+        }
+    }
+
+    #[test]
+    fn test_inject_preserves_crlf_and_bom() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("main.py");
+        fs::write(&test_file, b"\xEF\xBB\xBFimport os\r\nprint('hello')\r\n").unwrap();
+
+        let injector = ShimInjector::new(temp_dir.path());
+        injector.inject_python_shim(&test_file).unwrap();
+
+        let bytes = fs::read(&test_file).unwrap();
+        assert!(bytes.starts_with(b"\xEF\xBB\xBF"));
+        let content = String::from_utf8(bytes).unwrap();
+        assert!(content.contains("import promptguard_shim\r\n"));
+        assert_eq!(
+            content.matches('\n').count(),
+            content.matches("\r\n").count()
+        );
+    }
+
+    #[test]
+    fn test_typescript_shim_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("src")).unwrap();
+        let ts_file = temp_dir.path().join("src/index.ts");
+        let original = "#!/usr/bin/env node\n'use strict';\nimport OpenAI from 'openai';\n";
+        fs::write(&ts_file, original).unwrap();
+
+        let injector = ShimInjector::new(temp_dir.path());
+        assert!(injector.inject_typescript_shim(&ts_file).unwrap());
+        assert!(!injector.inject_typescript_shim(&ts_file).unwrap());
+
+        let content = fs::read_to_string(&ts_file).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines[2], TYPESCRIPT_SHIM_IMPORT_MARKER);
+        assert_eq!(lines[3], "import '../.promptguard/promptguard-shim';");
+
+        assert!(injector.remove_typescript_shim(&ts_file).unwrap());
+        assert_eq!(fs::read_to_string(&ts_file).unwrap(), original);
+    }
+
+    #[test]
+    fn test_commonjs_entry_point_gets_require() {
+        let temp_dir = TempDir::new().unwrap();
+        let js_file = temp_dir.path().join("server.js");
+        fs::write(&js_file, "const OpenAI = require('openai');\n").unwrap();
+
+        let injector = ShimInjector::new(temp_dir.path());
+        injector.inject_typescript_shim(&js_file).unwrap();
+
+        let content = fs::read_to_string(&js_file).unwrap();
+        assert!(content.contains("require('./.promptguard/promptguard-shim.cjs');"));
+    }
+
+    #[test]
+    fn test_with_node_flag() {
+        let flag = NODE_PRELOAD_REQUIRE;
+        assert_eq!(
+            with_node_flag("node dist/index.js", flag).as_deref(),
+            Some("node --require ./.promptguard/preload.cjs dist/index.js")
+        );
+        assert_eq!(
+            with_node_flag("tsc && NODE_ENV=production node dist/index.js", flag).as_deref(),
+            Some("tsc && NODE_ENV=production node --require ./.promptguard/preload.cjs dist/index.js")
+        );
+        assert_eq!(with_node_flag("next start", flag), None);
+        assert_eq!(with_node_flag("echo node", flag), None);
+    }
+
+    #[test]
+    fn test_python_site_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let venv = temp_dir.path().join(".venv");
+        let site = venv.join("lib/python3.12/site-packages");
+        fs::create_dir_all(&site).unwrap();
+        fs::write(venv.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+
+        let injector = ShimInjector::new(temp_dir.path());
+        let pth = injector.inject_python_site(&venv).unwrap();
+        assert_eq!(pth, site.join(PYTHON_SITE_PTH_FILENAME));
+        let content = fs::read_to_string(&pth).unwrap();
+        assert!(content.contains("__import__('promptguard_shim')"));
+        assert!(content.lines().nth(1).unwrap().starts_with("import "));
+
+        assert_eq!(
+            injector.remove_python_site(&venv).unwrap(),
+            Some(pth.clone())
+        );
+        assert!(!pth.exists());
+    }
+
+    #[test]
+    fn test_esm_entry_point_gets_mjs_import() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("package.json"),
+            r#"{"type": "module"}"#,
+        )
+        .unwrap();
+        let js_file = temp_dir.path().join("server.js");
+        fs::write(&js_file, "console.log('hi');\n").unwrap();
+
+        let injector = ShimInjector::new(temp_dir.path());
+        injector.inject_typescript_shim(&js_file).unwrap();
+
+        let content = fs::read_to_string(&js_file).unwrap();
+        assert!(content.contains("import './.promptguard/promptguard-shim.mjs';"));
+    }
+
+    #[test]
+    fn test_bun_preload_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let injector = ShimInjector::new(temp_dir.path());
+        let bunfig = temp_dir.path().join("bunfig.toml");
+        assert!(!injector.is_bun_project());
+
+        // No bunfig.toml: created, then removed again
+        fs::write(temp_dir.path().join("bun.lockb"), "").unwrap();
+        assert!(injector.is_bun_project());
+        assert!(injector.inject_bun_preload().unwrap());
+        assert!(!injector.inject_bun_preload().unwrap());
+        assert!(injector.has_bun_preload());
+        assert!(fs::read_to_string(&bunfig)
+            .unwrap()
+            .contains(BUN_PRELOAD_PATH));
+        assert!(injector.remove_bun_preload().unwrap());
+        assert!(!bunfig.exists());
+        assert!(!injector.has_bun_preload());
+
+        // Existing preload and tables are kept
+        let original = "preload = [\"./setup.ts\"]\n\n[test]\npreload = [\"./test-setup.ts\"]\n";
+        fs::write(&bunfig, original).unwrap();
+        assert!(injector.inject_bun_preload().unwrap());
+        let content = fs::read_to_string(&bunfig).unwrap();
+        assert!(content.starts_with(&format!(
+            "preload = [\"{BUN_PRELOAD_PATH}\", \"./setup.ts\"]"
+        )));
+        assert!(content.contains("[test]\npreload = [\"./test-setup.ts\"]"));
+        assert!(injector.remove_bun_preload().unwrap());
+        assert_eq!(fs::read_to_string(&bunfig).unwrap(), original);
+    }
+
+    #[test]
+    fn test_injected_files_and_loaders() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let injector = ShimInjector::new(root);
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("main.py"), "print('hi')\n").unwrap();
+        fs::write(root.join("src/index.ts"), "console.log('hi');\n").unwrap();
+        fs::write(root.join("util.py"), "x = 1\n").unwrap();
+        fs::write(
+            root.join("package.json"),
+            r#"{"scripts": {"start": "node index.js", "test": "jest"}}"#,
+        )
+        .unwrap();
+
+        injector.inject_python_shim(&root.join("main.py")).unwrap();
+        injector
+            .inject_typescript_shim(&root.join("src/index.ts"))
+            .unwrap();
+        assert_eq!(
+            injector.injected_files().unwrap(),
+            vec![root.join("main.py"), root.join("src/index.ts")]
+        );
+        assert!(injector.node_preload_scripts().is_empty());
+        injector.inject_node_preload().unwrap();
+        assert_eq!(injector.node_preload_scripts(), vec!["start".to_string()]);
+
+        assert_eq!(injector.remove_all_injections().unwrap(), 2);
+        assert!(injector.injected_files().unwrap().is_empty());
+    }
 }
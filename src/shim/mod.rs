@@ -42,12 +42,42 @@
 /// 3. Inject `import promptguard_shim` at the top of each entry point
 /// 4. When app starts, shim patches SDK constructors before any SDK imports
 ///
+/// Framework clients built deep inside libraries are patched too: `LangChain`'s
+/// `ChatOpenAI`/`ChatAnthropic` and the `OpenAI` Agents SDK's `OpenAIProvider`
+/// get their `__init__` wrapped in place, for the configured providers.
+///
+/// With `--inject site`, entry points are left alone and a `.pth` file in the
+/// virtualenv's site-packages imports the shim at interpreter startup.
+///
 /// ### TypeScript/JavaScript
 ///
-/// 1. Generate `.promptguard/promptguard-shim.ts` with wrapper classes
+/// 1. Generate `.promptguard/promptguard-shim.ts` with wrapper classes, plus
+///    `CommonJS` (`.cjs`) and ES module (`.mjs`) shims for plain Node
 /// 2. Detect entry points (package.json main, index.ts, etc.)
-/// 3. Recommend tsconfig.json path aliases or direct imports
-/// 4. App imports from shim instead of original SDK package
+/// 3. Inject `import './.promptguard/promptguard-shim'` at the top of each
+///    entry point: the `.cjs` variant via `require` in `CommonJS` files, the
+///    `.mjs` variant in ES modules
+/// 4. `promptguard disable` removes the injected lines again
+///
+/// With `--inject preload`, entry points are left alone and `.promptguard/preload.cjs`
+/// is loaded with `node --require` from package.json start scripts and
+/// `NODE_OPTIONS` in the env file. It hooks `require` so SDK client classes
+/// are wrapped wherever they are loaded.
+///
+/// Bun projects (`bun.lockb`, `bun.lock` or `bunfig.toml`) always load
+/// `.promptguard/bun-preload.mjs` from bunfig.toml `preload`: a Bun runtime
+/// plugin resolves SDK imports to wrappers that re-export the wrapped clients.
+///
+/// Deno projects (`deno.json`, `deno.jsonc` or `deno.lock`) get
+/// `.promptguard/deno-shim.ts` and an import map resolving `openai`,
+/// `npm:openai` and every other SDK specifier the sources use to wrappers in
+/// `.promptguard/deno/`. Deno is pointed at the map with `--import-map`.
+///
+/// ### Docker
+///
+/// Scripts started by `Dockerfile` and docker-compose commands count as entry
+/// points. With `--inject docker`, compose services also get `NODE_OPTIONS`
+/// (Node.js) or `PYTHONPATH` pointing at `.promptguard/sitecustomize.py` (Python).
 ///
 /// ## Benefits
 ///
@@ -55,9 +85,21 @@
 /// - **Zero Config**: Works without understanding the codebase
 /// - **Safe**: Can be disabled without breaking the app
 /// - **Transparent**: Developers see exactly what's being intercepted
+pub mod deno;
+pub mod docker;
 pub mod generator;
 pub mod injector;
 pub mod templates;
 
-pub use generator::ShimGenerator;
-pub use injector::ShimInjector;
+pub use generator::{ShimGenerator, ShimIntegrity, SHIM_VERSION};
+pub use injector::{ShimInjection, ShimInjector, BUN_PRELOAD_PATH, NODE_PRELOAD_REQUIRE};
+
+/// Whether `.js` files in `dir` are ES modules, per the nearest package.json `"type"`
+pub(crate) fn is_esm_package(dir: &std::path::Path) -> bool {
+    dir.ancestors()
+        .map(|d| d.join("package.json"))
+        .find(|p| p.is_file())
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .is_some_and(|v| v.get("type").and_then(|t| t.as_str()) == Some("module"))
+}
@@ -3,7 +3,9 @@
 /// These templates are embedded at compile time and used to generate
 /// runtime interception code that ensures all SDK calls route through
 /// `PromptGuard`, regardless of how the SDK is initialized.
+use crate::detector::ProviderInfo;
 use crate::types::Provider;
+use std::fmt::Write;
 
 /// Python runtime shim template
 ///
@@ -12,6 +14,7 @@ use crate::types::Provider;
 pub const PYTHON_SHIM_TEMPLATE: &str = r#"#!/usr/bin/env python3
 """
 PromptGuard Runtime Shim - Auto-generated
+Generated by PromptGuard CLI v{{CLI_VERSION}}
 DO NOT EDIT THIS FILE MANUALLY
 
 This module intercepts LLM SDK initialization to ensure all API calls
@@ -32,12 +35,28 @@ _shimmed_providers: set = set()
 
 # Configuration
 PROXY_URL = os.environ.get("PROMPTGUARD_PROXY_URL", "{{PROXY_URL}}")
+# Per-provider routing from .promptguard.json; PROMPTGUARD_PROXY_URL overrides it
+PROVIDER_PROXY_URLS: Dict[str, str] = {} if "PROMPTGUARD_PROXY_URL" in os.environ else {{PROVIDER_PROXY_URLS}}
 API_KEY_VAR = os.environ.get("PROMPTGUARD_API_KEY_VAR", "{{API_KEY_VAR}}")
+# Per-provider API key variables from .promptguard.json
+PROVIDER_API_KEY_VARS: Dict[str, str] = {{PROVIDER_API_KEY_VARS}}
 ENABLED = os.environ.get("PROMPTGUARD_ENABLED", "true").lower() in ("true", "1", "yes")
 
 # Debug mode for troubleshooting
 DEBUG = os.environ.get("PROMPTGUARD_DEBUG", "false").lower() in ("true", "1", "yes")
 
+# When the proxy is unreachable: "open" leaves clients on the provider's own
+# endpoint, "closed" keeps the proxy so SDK calls raise connection errors
+FAIL_MODE = os.environ.get("PROMPTGUARD_FAIL_MODE", "{{FAIL_MODE}}").lower()
+_PROBE_TIMEOUT = 1.0
+_PROBE_TTL = 30.0
+_probe_results: Dict[str, Any] = {}
+
+# Append one JSON line per intercepted client to shim.log next to this file:
+# timestamp, provider, endpoint and what the shim did, never payloads or keys
+LOG_ENABLED = os.environ.get("PROMPTGUARD_SHIM_LOG", "{{SHIM_LOG}}").lower() in ("true", "1", "yes")
+_LOG_PATH = os.path.join(os.path.dirname(os.path.abspath(__file__)), "shim.log")
+
 
 def _debug(msg: str) -> None:
     """Print debug message if DEBUG mode is enabled."""
@@ -45,7 +64,62 @@ def _debug(msg: str) -> None:
         print(f"[PromptGuard Shim] {msg}", file=sys.stderr)
 
 
-def _ensure_base_url(kwargs: Dict[str, Any], provider: str, param_name: str = "base_url") -> Dict[str, Any]:
+def _log_call(provider: str, endpoint: Optional[str], action: str) -> None:
+    """Record an intercepted client in shim.log when LOG_ENABLED."""
+    if not LOG_ENABLED:
+        return
+    import datetime
+    import json
+
+    entry = {
+        "timestamp": datetime.datetime.now(datetime.timezone.utc).isoformat(),
+        "provider": provider,
+        "endpoint": endpoint,
+        "action": action,
+    }
+    try:
+        with open(_LOG_PATH, "a", encoding="utf-8") as log:
+            log.write(json.dumps(entry) + "\n")
+    except OSError as e:
+        _debug(f"Could not write {_LOG_PATH}: {e}")
+
+
+def _proxy_reachable(url: str) -> bool:
+    """Whether the proxy accepts TCP connections; cached for _PROBE_TTL seconds."""
+    import socket
+    import time
+    from urllib.parse import urlsplit
+
+    now = time.monotonic()
+    cached = _probe_results.get(url)
+    if cached is not None and now - cached[1] < _PROBE_TTL:
+        return cached[0]
+
+    parts = urlsplit(url)
+    try:
+        port = parts.port or (443 if parts.scheme == "https" else 80)
+        socket.create_connection((parts.hostname, port), timeout=_PROBE_TIMEOUT).close()
+        reachable = True
+    except (OSError, ValueError):
+        reachable = False
+    _probe_results[url] = (reachable, now)
+    return reachable
+
+
+def _use_proxy(url: str, provider: str) -> bool:
+    """Whether clients should be pointed at `url`, per FAIL_MODE."""
+    if FAIL_MODE != "open" or _proxy_reachable(url):
+        return True
+    warnings.warn(
+        f"PromptGuard proxy {url} is unreachable; {provider} falls back to its own endpoint",
+        RuntimeWarning,
+    )
+    return False
+
+
+def _ensure_base_url(
+    kwargs: Dict[str, Any], provider: str, param_name: str = "base_url", key_param: str = "api_key"
+) -> Dict[str, Any]:
     """
     Ensure base_url is set to PromptGuard proxy if not already configured.
 
@@ -53,6 +127,7 @@ def _ensure_base_url(kwargs: Dict[str, Any], provider: str, param_name: str = "b
         kwargs: Constructor keyword arguments
         provider: Provider name (for logging)
         param_name: Parameter name for base URL (default: base_url)
+        key_param: Parameter name for the API key (default: api_key)
 
     Returns:
         Modified kwargs with base_url injected if needed
@@ -61,27 +136,66 @@ def _ensure_base_url(kwargs: Dict[str, Any], provider: str, param_name: str = "b
         _debug(f"PromptGuard disabled, skipping {provider} interception")
         return kwargs
 
-    if param_name in kwargs:
+    # Libraries pass `base_url=None` through for "use the default"
+    if kwargs.get(param_name) is not None:
         current_url = kwargs[param_name]
         _debug(f"{provider}: base_url already set to {current_url}")
+        _log_call(provider, str(current_url), "preconfigured")
         return kwargs
 
     # Inject PromptGuard proxy URL
+    proxy_url = PROVIDER_PROXY_URLS.get(provider, PROXY_URL)
+    if not _use_proxy(proxy_url, provider):
+        _log_call(provider, None, "fallback")
+        return kwargs
     kwargs = kwargs.copy()
-    kwargs[param_name] = PROXY_URL
-    _debug(f"{provider}: injected base_url={PROXY_URL}")
+    kwargs[param_name] = proxy_url
+    _debug(f"{provider}: injected base_url={proxy_url}")
+    _log_call(provider, proxy_url, "routed")
+
+    key_var = PROVIDER_API_KEY_VARS.get(provider, API_KEY_VAR)
+    if key_param not in kwargs and os.environ.get(key_var):
+        kwargs[key_param] = os.environ[key_var]
+        _debug(f"{provider}: using API key from {key_var}")
 
     return kwargs
 
 
-{{PROVIDER_PATCHES}}
+def _patch_init(cls: Any, provider: str, params: tuple, key_params: tuple) -> bool:
+    """
+    Wrap a framework class's __init__ in place, so the class routes through
+    PromptGuard however it is imported and subclasses inherit it.
 
+    Args:
+        cls: Class to patch (None when the framework lacks it)
+        provider: Provider name (for logging)
+        params: Base URL parameter names, aliases included; the first is injected
+        key_params: API key parameter names, aliases included; the first is injected
+    """
+    if cls is None or getattr(cls.__init__, "_promptguard", False):
+        return False
+    original_init = cls.__init__
+
+    def __init__(self, *args, **kwargs):
+        kwargs = {k: v for k, v in kwargs.items() if not (k in params and v is None)}
+        param = next((p for p in params if p in kwargs), params[0])
+        key_param = next((k for k in key_params if k in kwargs), key_params[0])
+        kwargs = _ensure_base_url(kwargs, provider, param, key_param)
+        original_init(self, *args, **kwargs)
+
+    __init__._promptguard = True
+    cls.__init__ = __init__
+    return True
+
+
+{{PROVIDER_PATCHES}}
+{{FRAMEWORK_PATCHES}}
 
 def _install_shims() -> None:
     """Install all runtime shims for detected providers."""
     providers_shimmed = []
 
-    {{INSTALL_CALLS}}
+{{INSTALL_CALLS}}
 
     if providers_shimmed:
         _debug(f"Installed shims for: {', '.join(providers_shimmed)}")
@@ -93,146 +207,176 @@ def _install_shims() -> None:
 _install_shims()
 "#;
 
-/// `OpenAI` Python provider patch template
-pub const PYTHON_OPENAI_PATCH: &str = r#"
-def _shim_openai() -> None:
-    """Monkey-patch OpenAI SDK."""
-    if "openai" in _shimmed_providers:
-        return
+/// Python patch for one provider, filled in from its registry entry
+pub const PYTHON_PROVIDER_PATCH_TEMPLATE: &str = r#"
+def _shim_{{KEY}}() -> bool:
+    """Monkey-patch {{PROVIDER}} sync and async clients."""
+    if "{{KEY}}" in _shimmed_providers:
+        return True
 
     try:
-        import openai
-
-        # Store original class
-        if "OpenAI" not in _original_classes:
-            _original_classes["OpenAI"] = openai.OpenAI
-
-        original_openai = _original_classes["OpenAI"]
-
-        class PatchedOpenAI(original_openai):
-            """PromptGuard-wrapped OpenAI client."""
-
-            def __init__(self, **kwargs):
-                kwargs = _ensure_base_url(kwargs, "OpenAI", "base_url")
-                super().__init__(**kwargs)
-
-        # Apply monkey-patch
-        openai.OpenAI = PatchedOpenAI
-        _shimmed_providers.add("openai")
-        _debug("OpenAI SDK shimmed successfully")
+        import {{MODULE}}
+{{CLASS_PATCHES}}{{MODULE_PATCH}}
+        _shimmed_providers.add("{{KEY}}")
+        _debug("{{PROVIDER}} SDK shimmed successfully")
+        return True
 
     except ImportError:
-        _debug("OpenAI SDK not installed, skipping")
+        _debug("{{PROVIDER}} SDK not installed, skipping")
     except Exception as e:
-        warnings.warn(f"Failed to shim OpenAI SDK: {e}", RuntimeWarning)
+        warnings.warn(f"Failed to shim {{PROVIDER}} SDK: {e}", RuntimeWarning)
+    return False
 "#;
 
-/// Anthropic Python provider patch template
-pub const PYTHON_ANTHROPIC_PATCH: &str = r#"
-def _shim_anthropic() -> None:
-    """Monkey-patch Anthropic SDK."""
-    if "anthropic" in _shimmed_providers:
-        return
-
-    try:
-        import anthropic
-
-        # Store original class
-        if "Anthropic" not in _original_classes:
-            _original_classes["Anthropic"] = anthropic.Anthropic
+/// Wraps one client class of a provider's Python module
+pub const PYTHON_CLASS_PATCH_TEMPLATE: &str = r#"
+        if hasattr({{MODULE}}, "{{CLASS}}"):
+            if "{{MODULE}}.{{CLASS}}" not in _original_classes:
+                _original_classes["{{MODULE}}.{{CLASS}}"] = {{MODULE}}.{{CLASS}}
 
-        original_anthropic = _original_classes["Anthropic"]
+            class Patched{{CLASS}}(_original_classes["{{MODULE}}.{{CLASS}}"]):
+                """PromptGuard-wrapped {{PROVIDER}} {{CLASS}}."""
 
-        class PatchedAnthropic(original_anthropic):
-            """PromptGuard-wrapped Anthropic client."""
+                def __init__(self, *args, **kwargs):
+                    kwargs = _ensure_base_url(kwargs, "{{PROVIDER}}", "{{PARAM}}", "{{KEY_PARAM}}")
+                    super().__init__(*args, **kwargs)
 
-            def __init__(self, **kwargs):
-                kwargs = _ensure_base_url(kwargs, "Anthropic", "base_url")
-                super().__init__(**kwargs)
-
-        # Apply monkey-patch
-        anthropic.Anthropic = PatchedAnthropic
-        _shimmed_providers.add("anthropic")
-        _debug("Anthropic SDK shimmed successfully")
+            {{MODULE}}.{{CLASS}} = Patched{{CLASS}}
+"#;
 
-    except ImportError:
-        _debug("Anthropic SDK not installed, skipping")
-    except Exception as e:
-        warnings.warn(f"Failed to shim Anthropic SDK: {e}", RuntimeWarning)
+/// Points a module-level base URL global (`openai.base_url`) at the proxy
+pub const PYTHON_MODULE_PATCH_TEMPLATE: &str = r#"
+        # Module-level calls, streaming included, go through a client
+        # built from `{{ATTR}}`
+        _module_url = PROVIDER_PROXY_URLS.get("{{PROVIDER}}", PROXY_URL)
+        if (
+            ENABLED
+            and getattr({{MODULE}}, "{{ATTR_NAME}}", None) is None
+            and _use_proxy(_module_url, "{{PROVIDER}}")
+        ):
+            {{ATTR}} = _module_url
+            _log_call("{{PROVIDER}}", _module_url, "routed")
+            _debug("{{PROVIDER}}: injected module-level {{ATTR_NAME}}=" + str({{ATTR}}))
 "#;
 
-/// Cohere Python provider patch template
-pub const PYTHON_COHERE_PATCH: &str = r#"
-def _shim_cohere() -> None:
-    """Monkey-patch Cohere SDK."""
-    if "cohere" in _shimmed_providers:
-        return
+/// Python patch for the provider clients of an agent or LLM framework,
+/// which builds them deep inside the library
+pub const PYTHON_FRAMEWORK_PATCH_TEMPLATE: &str = r#"
+def _shim_{{KEY}}() -> bool:
+    """Route {{FRAMEWORK}} {{PROVIDER}} clients through PromptGuard."""
+    if "{{KEY}}" in _shimmed_providers:
+        return True
 
     try:
-        import cohere
-
-        # Store original class
-        if "CohereClient" not in _original_classes:
-            _original_classes["CohereClient"] = cohere.Client
-
-        original_cohere = _original_classes["CohereClient"]
-
-        class PatchedCohereClient(original_cohere):
-            """PromptGuard-wrapped Cohere client."""
-
-            def __init__(self, **kwargs):
-                kwargs = _ensure_base_url(kwargs, "Cohere", "base_url")
-                super().__init__(**kwargs)
-
-        # Apply monkey-patch
-        cohere.Client = PatchedCohereClient
-        _shimmed_providers.add("cohere")
-        _debug("Cohere SDK shimmed successfully")
+        import {{MODULE}}
+{{CLASS_PATCHES}}
+        _shimmed_providers.add("{{KEY}}")
+        _debug("{{FRAMEWORK}} {{PROVIDER}} clients shimmed successfully")
+        return True
 
     except ImportError:
-        _debug("Cohere SDK not installed, skipping")
+        _debug("{{FRAMEWORK}} not installed, skipping")
     except Exception as e:
-        warnings.warn(f"Failed to shim Cohere SDK: {e}", RuntimeWarning)
+        warnings.warn(f"Failed to shim {{FRAMEWORK}}: {e}", RuntimeWarning)
+    return False
 "#;
 
-/// `HuggingFace` Python provider patch template
-pub const PYTHON_HUGGINGFACE_PATCH: &str = r#"
-def _shim_huggingface() -> None:
-    """Monkey-patch HuggingFace InferenceClient."""
-    if "huggingface" in _shimmed_providers:
-        return
-
-    try:
-        from huggingface_hub import InferenceClient
-        import huggingface_hub
-
-        # Store original class
-        if "InferenceClient" not in _original_classes:
-            _original_classes["InferenceClient"] = InferenceClient
-
-        original_hf = _original_classes["InferenceClient"]
-
-        class PatchedInferenceClient(original_hf):
-            """PromptGuard-wrapped HuggingFace InferenceClient."""
-
-            def __init__(self, **kwargs):
-                kwargs = _ensure_base_url(kwargs, "HuggingFace", "base_url")
-                super().__init__(**kwargs)
+/// Patches one framework class in place
+pub const PYTHON_FRAMEWORK_CLASS_PATCH_TEMPLATE: &str = r#"        _patch_init(getattr({{MODULE}}, "{{CLASS}}", None), "{{PROVIDER}}", {{PARAMS}}, {{KEY_PARAMS}})
+"#;
 
-        # Apply monkey-patch
-        huggingface_hub.InferenceClient = PatchedInferenceClient
-        _shimmed_providers.add("huggingface")
-        _debug("HuggingFace SDK shimmed successfully")
+/// A framework whose own client classes the Python shim patches
+pub struct PythonFrameworkTarget {
+    /// Suffix of the generated `_shim_*` function
+    pub key: &'static str,
+    pub framework: &'static str,
+    /// Provider the framework's classes talk to; patched only when configured
+    pub provider: Provider,
+    pub module: &'static str,
+    pub classes: &'static [&'static str],
+    /// Base URL parameter names, the one to inject first, then aliases
+    pub params: &'static [&'static str],
+    /// API key parameter names, the one to inject first, then aliases
+    pub key_params: &'static [&'static str],
+}
 
-    except ImportError:
-        _debug("HuggingFace SDK not installed, skipping")
-    except Exception as e:
-        warnings.warn(f"Failed to shim HuggingFace SDK: {e}", RuntimeWarning)
-"#;
+/// Frameworks patched on top of the provider SDKs. Many pass `base_url=None`
+/// to the SDK or read their own settings, so patching the SDK alone misses them.
+pub const PYTHON_FRAMEWORK_TARGETS: &[PythonFrameworkTarget] = &[
+    PythonFrameworkTarget {
+        key: "langchain_openai",
+        framework: "LangChain",
+        provider: Provider::OpenAI,
+        module: "langchain_openai",
+        classes: &["ChatOpenAI", "OpenAI", "OpenAIEmbeddings"],
+        params: &["base_url", "openai_api_base"],
+        key_params: &["api_key", "openai_api_key"],
+    },
+    PythonFrameworkTarget {
+        key: "langchain_anthropic",
+        framework: "LangChain",
+        provider: Provider::Anthropic,
+        module: "langchain_anthropic",
+        classes: &["ChatAnthropic", "AnthropicLLM"],
+        params: &["base_url", "anthropic_api_url"],
+        key_params: &["api_key", "anthropic_api_key"],
+    },
+    PythonFrameworkTarget {
+        key: "openai_agents",
+        framework: "OpenAI Agents SDK",
+        provider: Provider::OpenAI,
+        module: "agents",
+        classes: &["OpenAIProvider"],
+        params: &["base_url"],
+        key_params: &["api_key"],
+    },
+];
+
+/// Python framework patches for `providers`, and their install calls
+pub fn get_python_framework_patches(providers: &[Provider]) -> (String, String) {
+    let tuple = |names: &[&str]| {
+        let quoted: Vec<String> = names.iter().map(|n| format!("\"{n}\"")).collect();
+        format!("({},)", quoted.join(", "))
+    };
+    let mut patches = String::new();
+    let mut install_calls = String::new();
+    for target in PYTHON_FRAMEWORK_TARGETS
+        .iter()
+        .filter(|t| providers.contains(&t.provider))
+    {
+        let fill = |template: &str| {
+            template
+                .replace("{{KEY}}", target.key)
+                .replace("{{FRAMEWORK}}", target.framework)
+                .replace("{{MODULE}}", target.module)
+                .replace("{{PROVIDER}}", target.provider.display_name())
+        };
+        let class_patches: String = target
+            .classes
+            .iter()
+            .map(|class| {
+                fill(PYTHON_FRAMEWORK_CLASS_PATCH_TEMPLATE)
+                    .replace("{{CLASS}}", class)
+                    .replace("{{PARAMS}}", &tuple(target.params))
+                    .replace("{{KEY_PARAMS}}", &tuple(target.key_params))
+            })
+            .collect();
+        patches.push_str(
+            &fill(PYTHON_FRAMEWORK_PATCH_TEMPLATE).replace("{{CLASS_PATCHES}}", &class_patches),
+        );
+        let _ = writeln!(
+            install_calls,
+            "    if _shim_{}():\n        providers_shimmed.append(\"{}\")",
+            target.key, target.framework
+        );
+    }
+    (patches, install_calls)
+}
 
 /// TypeScript/JavaScript runtime shim template
 pub const TYPESCRIPT_SHIM_TEMPLATE: &str = r#"/**
  * PromptGuard Runtime Shim - Auto-generated
+ * Generated by PromptGuard CLI v{{CLI_VERSION}}
  * DO NOT EDIT THIS FILE MANUALLY
  *
  * This module intercepts LLM SDK initialization to ensure all API calls
@@ -240,9 +384,24 @@ pub const TYPESCRIPT_SHIM_TEMPLATE: &str = r#"/**
  */
 
 const PROXY_URL = process.env.PROMPTGUARD_PROXY_URL || "{{PROXY_URL}}";
+// Per-provider routing from .promptguard.json; PROMPTGUARD_PROXY_URL overrides it
+const PROVIDER_PROXY_URLS: Record<string, string> = process.env.PROMPTGUARD_PROXY_URL
+  ? {}
+  : {{PROVIDER_PROXY_URLS}};
 const API_KEY_VAR = process.env.PROMPTGUARD_API_KEY_VAR || "{{API_KEY_VAR}}";
+// Per-provider API key variables from .promptguard.json
+const PROVIDER_API_KEY_VARS: Record<string, string> = {{PROVIDER_API_KEY_VARS}};
 const ENABLED = (process.env.PROMPTGUARD_ENABLED || "true").toLowerCase() !== "false";
 const DEBUG = (process.env.PROMPTGUARD_DEBUG || "false").toLowerCase() === "true";
+// When the proxy is unreachable: "open" leaves clients on the provider's own
+// endpoint, "closed" keeps the proxy so SDK calls raise connection errors
+const FAIL_MODE = (process.env.PROMPTGUARD_FAIL_MODE || "{{FAIL_MODE}}").toLowerCase();
+
+// Append one JSON line per intercepted client to shim.log next to this file:
+// timestamp, provider, endpoint and what the shim did, never payloads or keys
+const LOG_ENABLED = ["true", "1", "yes"].includes(
+  (process.env.PROMPTGUARD_SHIM_LOG || "{{SHIM_LOG}}").toLowerCase()
+);
 
 function debug(msg: string): void {
   if (DEBUG) {
@@ -250,10 +409,70 @@ function debug(msg: string): void {
   }
 }
 
+function logCall(provider: string, endpoint: string | null, action: string): void {
+  if (!LOG_ENABLED || typeof __dirname === "undefined") {
+    return;
+  }
+  const entry = { timestamp: new Date().toISOString(), provider, endpoint, action };
+  const logPath = require("path").join(__dirname, "shim.log");
+  try {
+    require("fs").appendFileSync(logPath, JSON.stringify(entry) + "\n");
+  } catch (e) {
+    debug(`Could not write ${logPath}: ${e}`);
+  }
+}
+
+// Clients are built synchronously, so each probe only informs clients
+// created after it completes; a failed URL is probed again after PROBE_TTL
+const PROBE_TTL = 30000;
+const probes = new Map<string, { reachable: boolean; at: number }>();
+
+function probe(url: string): void {
+  const previous = probes.get(url);
+  if (previous && Date.now() - previous.at < PROBE_TTL) {
+    return;
+  }
+  probes.set(url, { reachable: previous ? previous.reachable : true, at: Date.now() });
+  try {
+    const parsed = new URL(url);
+    const port = Number(parsed.port) || (parsed.protocol === "https:" ? 443 : 80);
+    const socket = require("net").connect({ host: parsed.hostname, port, timeout: 1000 });
+    socket.unref();
+    const done = (reachable: boolean) => {
+      probes.set(url, { reachable, at: Date.now() });
+      socket.destroy();
+    };
+    socket.on("connect", () => done(true));
+    socket.on("timeout", () => done(false));
+    socket.on("error", () => done(false));
+  } catch (e) {
+    probes.set(url, { reachable: false, at: Date.now() });
+  }
+}
+
+function useProxy(url: string, provider: string): boolean {
+  if (FAIL_MODE !== "open") {
+    return true;
+  }
+  probe(url);
+  if (probes.get(url)?.reachable === false) {
+    console.warn(`[PromptGuard Shim] Proxy ${url} is unreachable; ${provider} falls back to its own endpoint`);
+    return false;
+  }
+  return true;
+}
+
+if (FAIL_MODE === "open") {
+  for (const url of [PROXY_URL, ...Object.values(PROVIDER_PROXY_URLS)]) {
+    probe(url);
+  }
+}
+
 function ensureBaseURL<T extends Record<string, any>>(
   config: T | undefined,
   provider: string,
-  paramName: string = "baseURL"
+  paramName: string = "baseURL",
+  keyParam: string = "apiKey"
 ): T {
   if (!ENABLED) {
     debug(`PromptGuard disabled, skipping ${provider} interception`);
@@ -264,182 +483,608 @@ function ensureBaseURL<T extends Record<string, any>>(
 
   if (paramName in cfg) {
     debug(`${provider}: ${paramName} already set to ${cfg[paramName]}`);
+    logCall(provider, String(cfg[paramName]), "preconfigured");
     return cfg;
   }
 
   // Inject PromptGuard proxy URL
-  const modified = { ...cfg, [paramName]: PROXY_URL };
-  debug(`${provider}: injected ${paramName}=${PROXY_URL}`);
+  const proxyUrl = PROVIDER_PROXY_URLS[provider] || PROXY_URL;
+  if (!useProxy(proxyUrl, provider)) {
+    logCall(provider, null, "fallback");
+    return cfg;
+  }
+  const modified: Record<string, any> = { ...cfg, [paramName]: proxyUrl };
+  debug(`${provider}: injected ${paramName}=${proxyUrl}`);
+  logCall(provider, proxyUrl, "routed");
+
+  const keyVar = PROVIDER_API_KEY_VARS[provider] || API_KEY_VAR;
+  if (!(keyParam in modified) && process.env[keyVar]) {
+    modified[keyParam] = process.env[keyVar];
+    debug(`${provider}: using API key from ${keyVar}`);
+  }
 
-  return modified;
+  return modified as T;
 }
 
 {{PROVIDER_EXPORTS}}
 "#;
 
-/// `OpenAI` TypeScript provider export template
-pub const TYPESCRIPT_OPENAI_EXPORT: &str = r#"
-// OpenAI SDK wrapper
-let OriginalOpenAI: any = null;
+/// TypeScript wrapper for one provider, filled in from its registry entry
+pub const TYPESCRIPT_PROVIDER_EXPORT_TEMPLATE: &str = r#"
+// {{PROVIDER}} SDK wrapper
+let Original{{CLASS}}: any = null;
 
 try {
-  const openaiModule = require("openai");
-  OriginalOpenAI = openaiModule.OpenAI || openaiModule.default?.OpenAI;
+  const sdkModule = require("{{PACKAGE}}");
+  Original{{CLASS}} = sdkModule.{{CLASS}} || sdkModule.default?.{{CLASS}};
 
-  if (OriginalOpenAI) {
-    export class OpenAI extends OriginalOpenAI {
+  if (Original{{CLASS}}) {
+    export class {{CLASS}} extends Original{{CLASS}} {
       constructor(config?: any) {
-        const modifiedConfig = ensureBaseURL(config, "OpenAI", "baseURL");
+        const modifiedConfig = ensureBaseURL(config, "{{PROVIDER}}", "{{PARAM}}", "{{KEY_PARAM}}");
         super(modifiedConfig);
       }
     }
-    debug("OpenAI SDK shimmed successfully");
+    debug("{{PROVIDER}} SDK shimmed successfully");
   } else {
-    debug("OpenAI class not found in module");
+    debug("{{CLASS}} class not found in module");
   }
 } catch (e) {
-  debug(`OpenAI SDK not available: ${e}`);
+  debug(`{{PROVIDER}} SDK not available: ${e}`);
   // Re-export empty class as fallback
-  export class OpenAI {
+  export class {{CLASS}} {
     constructor() {
-      throw new Error("OpenAI SDK not installed");
+      throw new Error("{{PROVIDER}} SDK not installed");
     }
   }
 }
 "#;
 
-/// Anthropic TypeScript provider export template
-pub const TYPESCRIPT_ANTHROPIC_EXPORT: &str = r#"
-// Anthropic SDK wrapper
-let OriginalAnthropic: any = null;
+/// `CommonJS` runtime shim template, shared by the `.cjs`/`.js` shim, the
+/// ESM shim and the Node.js preload
+pub const JAVASCRIPT_CJS_SHIM_TEMPLATE: &str = r#"/**
+ * PromptGuard Runtime Shim (CommonJS) - Auto-generated
+ * Generated by PromptGuard CLI v{{CLI_VERSION}}
+ * DO NOT EDIT THIS FILE MANUALLY
+ *
+ * Once this file is required, every CommonJS require of a supported SDK
+ * returns client classes that route through PromptGuard. The wrapped
+ * classes are also exported, for code that imports them from here.
+ */
+"use strict";
 
-try {
-  const anthropicModule = require("@anthropic-ai/sdk");
-  OriginalAnthropic = anthropicModule.Anthropic || anthropicModule.default?.Anthropic;
+const Module = require("module");
 
-  if (OriginalAnthropic) {
-    export class Anthropic extends OriginalAnthropic {
-      constructor(config?: any) {
-        const modifiedConfig = ensureBaseURL(config, "Anthropic", "baseURL");
-        super(modifiedConfig);
-      }
-    }
-    debug("Anthropic SDK shimmed successfully");
-  } else {
-    debug("Anthropic class not found in module");
+const PROXY_URL = process.env.PROMPTGUARD_PROXY_URL || "{{PROXY_URL}}";
+// Per-provider routing from .promptguard.json; PROMPTGUARD_PROXY_URL overrides it
+const PROVIDER_PROXY_URLS = process.env.PROMPTGUARD_PROXY_URL
+  ? {}
+  : {{PROVIDER_PROXY_URLS}};
+const API_KEY_VAR = process.env.PROMPTGUARD_API_KEY_VAR || "{{API_KEY_VAR}}";
+// Per-provider API key variables from .promptguard.json
+const PROVIDER_API_KEY_VARS = {{PROVIDER_API_KEY_VARS}};
+const ENABLED = (process.env.PROMPTGUARD_ENABLED || "true").toLowerCase() !== "false";
+const DEBUG = (process.env.PROMPTGUARD_DEBUG || "false").toLowerCase() === "true";
+// When the proxy is unreachable: "open" leaves clients on the provider's own
+// endpoint, "closed" keeps the proxy so SDK calls raise connection errors
+const FAIL_MODE = (process.env.PROMPTGUARD_FAIL_MODE || "{{FAIL_MODE}}").toLowerCase();
+
+// Append one JSON line per intercepted client to shim.log next to this file:
+// timestamp, provider, endpoint and what the shim did, never payloads or keys
+const LOG_ENABLED = ["true", "1", "yes"].includes(
+  (process.env.PROMPTGUARD_SHIM_LOG || "{{SHIM_LOG}}").toLowerCase()
+);
+
+// Package name -> client classes and the options they take
+const TARGETS = {{NODE_TARGETS}};
+
+function debug(msg) {
+  if (DEBUG) {
+    console.error(`[PromptGuard Shim] ${msg}`);
   }
-} catch (e) {
-  debug(`Anthropic SDK not available: ${e}`);
-  // Re-export empty class as fallback
-  export class Anthropic {
-    constructor() {
-      throw new Error("Anthropic SDK not installed");
-    }
+}
+
+function logCall(provider, endpoint, action) {
+  if (!LOG_ENABLED || typeof __dirname === "undefined") {
+    return;
+  }
+  const entry = { timestamp: new Date().toISOString(), provider, endpoint, action };
+  const logPath = require("path").join(__dirname, "shim.log");
+  try {
+    require("fs").appendFileSync(logPath, JSON.stringify(entry) + "\n");
+  } catch (e) {
+    debug(`Could not write ${logPath}: ${e}`);
   }
 }
-"#;
 
-/// Cohere TypeScript provider export template
-pub const TYPESCRIPT_COHERE_EXPORT: &str = r#"
-// Cohere SDK wrapper
-let OriginalCohereClient: any = null;
+// Clients are built synchronously, so each probe only informs clients
+// created after it completes; a failed URL is probed again after PROBE_TTL
+const PROBE_TTL = 30000;
+const probes = new Map();
 
-try {
-  const cohereModule = require("cohere-ai");
-  OriginalCohereClient = cohereModule.CohereClient || cohereModule.default?.CohereClient;
+function probe(url) {
+  const previous = probes.get(url);
+  if (previous && Date.now() - previous.at < PROBE_TTL) {
+    return;
+  }
+  probes.set(url, { reachable: previous ? previous.reachable : true, at: Date.now() });
+  try {
+    const parsed = new URL(url);
+    const port = Number(parsed.port) || (parsed.protocol === "https:" ? 443 : 80);
+    const socket = require("net").connect({ host: parsed.hostname, port, timeout: 1000 });
+    socket.unref();
+    const done = (reachable) => {
+      probes.set(url, { reachable, at: Date.now() });
+      socket.destroy();
+    };
+    socket.on("connect", () => done(true));
+    socket.on("timeout", () => done(false));
+    socket.on("error", () => done(false));
+  } catch (e) {
+    probes.set(url, { reachable: false, at: Date.now() });
+  }
+}
 
-  if (OriginalCohereClient) {
-    export class CohereClient extends OriginalCohereClient {
-      constructor(config?: any) {
-        const modifiedConfig = ensureBaseURL(config, "Cohere", "baseURL");
-        super(modifiedConfig);
+function useProxy(url, provider) {
+  if (FAIL_MODE !== "open") {
+    return true;
+  }
+  probe(url);
+  if (probes.get(url)?.reachable === false) {
+    console.warn(`[PromptGuard Shim] Proxy ${url} is unreachable; ${provider} falls back to its own endpoint`);
+    return false;
+  }
+  return true;
+}
+
+if (FAIL_MODE === "open") {
+  for (const url of [PROXY_URL, ...Object.values(PROVIDER_PROXY_URLS)]) {
+    probe(url);
+  }
+}
+
+function withProxy(config, target) {
+  const cfg = config || {};
+  if (target.param in cfg) {
+    debug(`${target.provider}: ${target.param} already set to ${cfg[target.param]}`);
+    logCall(target.provider, String(cfg[target.param]), "preconfigured");
+    return cfg;
+  }
+
+  const proxyUrl = PROVIDER_PROXY_URLS[target.provider] || PROXY_URL;
+  if (!useProxy(proxyUrl, target.provider)) {
+    logCall(target.provider, null, "fallback");
+    return cfg;
+  }
+  const modified = { ...cfg, [target.param]: proxyUrl };
+  debug(`${target.provider}: injected ${target.param}=${proxyUrl}`);
+  logCall(target.provider, proxyUrl, "routed");
+
+  const keyVar = PROVIDER_API_KEY_VARS[target.provider] || API_KEY_VAR;
+  if (!(target.keyParam in modified) && process.env[keyVar]) {
+    modified[target.keyParam] = process.env[keyVar];
+    debug(`${target.provider}: using API key from ${keyVar}`);
+  }
+  return modified;
+}
+
+const wrapped = new WeakMap();
+
+// Proxies rather than subclasses, so `instanceof`, static members and
+// user subclasses (`class Client extends OpenAI`) keep working
+function wrap(value, target) {
+  if ((typeof value !== "function" && typeof value !== "object") || value === null) {
+    return value;
+  }
+  if (wrapped.has(value)) {
+    return wrapped.get(value);
+  }
+  const proxy = new Proxy(value, {
+    construct(original, args, newTarget) {
+      const [config, ...rest] = args;
+      return Reflect.construct(original, [withProxy(config, target), ...rest], newTarget);
+    },
+    get(obj, prop, receiver) {
+      const member = Reflect.get(obj, prop, receiver);
+      if (prop !== "default" && !target.classes.includes(prop)) {
+        return member;
       }
-    }
-    debug("Cohere SDK shimmed successfully");
-  } else {
-    debug("CohereClient class not found in module");
+      // Proxies must return non-configurable, read-only values unchanged
+      const descriptor = Reflect.getOwnPropertyDescriptor(obj, prop);
+      if (descriptor && !descriptor.configurable && descriptor.writable === false) {
+        return member;
+      }
+      return wrap(member, target);
+    },
+  });
+  wrapped.set(value, proxy);
+  return proxy;
+}
+
+if (ENABLED) {
+  const originalLoad = Module._load;
+  Module._load = function (request, parent, isMain) {
+    const exports = originalLoad.apply(this, arguments);
+    const target = TARGETS[request];
+    return target ? wrap(exports, target) : exports;
+  };
+  debug(`Intercepting ${Object.keys(TARGETS).join(", ") || "no SDKs"}`);
+} else {
+  debug("PromptGuard disabled, skipping interception");
+}
+
+// For runtimes whose module loading can't be hooked (Bun), so their
+// wrappers can wrap SDK exports themselves
+Object.defineProperty(module.exports, "wrapClient", {
+  value: (value, pkg) => (ENABLED && TARGETS[pkg] ? wrap(value, TARGETS[pkg]) : value),
+});
+
+// Loaded on first use, so SDKs that aren't installed only fail when used
+for (const [pkg, target] of Object.entries(TARGETS)) {
+  for (const name of target.classes) {
+    Object.defineProperty(module.exports, name, {
+      enumerable: true,
+      get() {
+        return require(pkg)[name];
+      },
+    });
   }
-} catch (e) {
-  debug(`Cohere SDK not available: ${e}`);
-  // Re-export empty class as fallback
-  export class CohereClient {
-    constructor() {
-      throw new Error("Cohere SDK not installed");
+}
+"#;
+
+/// ES module runtime shim template. ES module imports of an SDK can't be
+/// intercepted, so ESM code imports the wrapped clients from this file.
+pub const JAVASCRIPT_ESM_SHIM_TEMPLATE: &str = r#"/**
+ * PromptGuard Runtime Shim (ES module) - Auto-generated
+ * Generated by PromptGuard CLI v{{CLI_VERSION}}
+ * DO NOT EDIT THIS FILE MANUALLY
+ *
+ * Import SDK clients from this file instead of the SDK package:
+ *   import { OpenAI } from "./.promptguard/promptguard-shim.mjs";
+ * Importing it also routes CommonJS requires of the SDKs through PromptGuard.
+ */
+import { createRequire } from "node:module";
+
+const require = createRequire(import.meta.url);
+const shim = require("./promptguard-shim.cjs");
+
+function load(name) {
+  try {
+    return shim[name];
+  } catch (e) {
+    if ((process.env.PROMPTGUARD_DEBUG || "false").toLowerCase() === "true") {
+      console.error(`[PromptGuard Shim] ${name} not available: ${e}`);
     }
+    return undefined;
   }
 }
+
+{{ESM_EXPORTS}}
+export default shim;
 "#;
 
-/// `HuggingFace` TypeScript provider export template
-pub const TYPESCRIPT_HUGGINGFACE_EXPORT: &str = r#"
-// HuggingFace SDK wrapper
-let OriginalHfInference: any = null;
+/// Node.js preload template, loaded with `node --require` before the application
+pub const NODE_PRELOAD_TEMPLATE: &str = r#"/**
+ * PromptGuard Node Preload - Auto-generated
+ * Generated by PromptGuard CLI v{{CLI_VERSION}}
+ * DO NOT EDIT THIS FILE MANUALLY
+ *
+ * Load with `node --require ./.promptguard/preload.cjs app.js` or
+ * NODE_OPTIONS="--require ./.promptguard/preload.cjs" to route SDK clients
+ * through PromptGuard without any change to application code.
+ */
+"use strict";
 
-try {
-  const hfModule = require("@huggingface/inference");
-  OriginalHfInference = hfModule.HfInference || hfModule.default?.HfInference;
+require("./promptguard-shim.cjs");
+"#;
 
-  if (OriginalHfInference) {
-    export class HfInference extends OriginalHfInference {
-      constructor(config?: any) {
-        const modifiedConfig = ensureBaseURL(config, "HuggingFace", "baseUrl");
-        super(modifiedConfig);
+/// Bun preload template, registered in bunfig.toml `preload`
+pub const BUN_PRELOAD_TEMPLATE: &str = r#"/**
+ * PromptGuard Bun Preload - Auto-generated
+ * Generated by PromptGuard CLI v{{CLI_VERSION}}
+ * DO NOT EDIT THIS FILE MANUALLY
+ *
+ * Bun doesn't load modules through Node's loader, so the CommonJS hook
+ * can't see SDK imports. This runtime plugin resolves each SDK to a
+ * wrapper in ./bun/ that re-exports it with its client classes wrapped.
+ */
+import { plugin } from "bun";
+
+const WRAPPERS = {{BUN_WRAPPERS}};
+const SHIM_DIR = import.meta.dir;
+
+plugin({
+  name: "promptguard-shim",
+  setup(build) {
+    build.onResolve({ filter: {{BUN_FILTER}} }, (args) => {
+      // The wrappers themselves import the real SDK
+      if (args.importer && args.importer.startsWith(SHIM_DIR)) {
+        return undefined;
       }
-    }
-    debug("HuggingFace SDK shimmed successfully");
-  } else {
-    debug("HfInference class not found in module");
+      return { path: `${SHIM_DIR}/${WRAPPERS[args.path]}` };
+    });
+  },
+});
+"#;
+
+/// Bun wrapper for one SDK package, re-exporting it with client classes wrapped
+pub const BUN_WRAPPER_TEMPLATE: &str = r#"// PromptGuard Bun wrapper for {{PACKAGE}} - Auto-generated
+// Generated by PromptGuard CLI v{{CLI_VERSION}}
+import * as sdk from "{{PACKAGE}}";
+import shim from "../promptguard-shim.cjs";
+
+export * from "{{PACKAGE}}";
+{{BUN_EXPORTS}}export default shim.wrapClient(sdk.default, "{{PACKAGE}}");
+"#;
+
+/// Deno runtime shim template. Deno resolves `npm:` imports itself, so the
+/// wrappers in ./deno/ wrap SDK exports with this module instead.
+pub const DENO_SHIM_TEMPLATE: &str = r#"/**
+ * PromptGuard Runtime Shim (Deno) - Auto-generated
+ * Generated by PromptGuard CLI v{{CLI_VERSION}}
+ * DO NOT EDIT THIS FILE MANUALLY
+ *
+ * Deno doesn't load npm packages through Node's loader, so SDK imports are
+ * remapped by .promptguard/deno-import-map.json to wrappers in ./deno/,
+ * which re-export each SDK with its client classes wrapped by this module.
+ */
+
+// Without --allow-env every variable reads as unset
+function env(name: string): string | undefined {
+  try {
+    return Deno.env.get(name);
+  } catch {
+    return undefined;
   }
-} catch (e) {
-  debug(`HuggingFace SDK not available: ${e}`);
-  // Re-export empty class as fallback
-  export class HfInference {
-    constructor() {
-      throw new Error("HuggingFace SDK not installed");
-    }
+}
+
+const PROXY_URL = env("PROMPTGUARD_PROXY_URL") || "{{PROXY_URL}}";
+// Per-provider routing from .promptguard.json; PROMPTGUARD_PROXY_URL overrides it
+const PROVIDER_PROXY_URLS: Record<string, string> = env("PROMPTGUARD_PROXY_URL")
+  ? {}
+  : {{PROVIDER_PROXY_URLS}};
+const API_KEY_VAR = env("PROMPTGUARD_API_KEY_VAR") || "{{API_KEY_VAR}}";
+// Per-provider API key variables from .promptguard.json
+const PROVIDER_API_KEY_VARS: Record<string, string> = {{PROVIDER_API_KEY_VARS}};
+const ENABLED = (env("PROMPTGUARD_ENABLED") || "true").toLowerCase() !== "false";
+const DEBUG = (env("PROMPTGUARD_DEBUG") || "false").toLowerCase() === "true";
+// When the proxy is unreachable: "open" leaves clients on the provider's own
+// endpoint, "closed" keeps the proxy so SDK calls raise connection errors
+const FAIL_MODE = (env("PROMPTGUARD_FAIL_MODE") || "{{FAIL_MODE}}").toLowerCase();
+// Append one JSON line per intercepted client to shim.log next to this file:
+// timestamp, provider, endpoint and what the shim did, never payloads or keys
+const LOG_ENABLED = ["true", "1", "yes"].includes(
+  (env("PROMPTGUARD_SHIM_LOG") || "{{SHIM_LOG}}").toLowerCase()
+);
+const LOG_PATH = new URL("./shim.log", import.meta.url);
+
+interface Target {
+  provider: string;
+  classes: string[];
+  param: string;
+  keyParam: string;
+}
+
+// Package name -> client classes and the options they take
+const TARGETS: Record<string, Target> = {{NODE_TARGETS}};
+
+function debug(msg: string): void {
+  if (DEBUG) {
+    console.error(`[PromptGuard Shim] ${msg}`);
+  }
+}
+
+// Clients are built synchronously, so each probe only informs clients
+// created after it completes; a failed URL is probed again after PROBE_TTL
+const PROBE_TTL = 30000;
+const probes = new Map<string, { reachable: boolean; at: number }>();
+
+function probe(url: string): void {
+  const previous = probes.get(url);
+  if (previous && Date.now() - previous.at < PROBE_TTL) {
+    return;
+  }
+  probes.set(url, { reachable: previous ? previous.reachable : true, at: Date.now() });
+  const timeout = new Promise<never>((_, reject) => {
+    const timer = setTimeout(() => reject(new Error("timeout")), 1000);
+    Deno.unrefTimer(timer);
+  });
+  const connect = async () => {
+    const parsed = new URL(url);
+    const port = Number(parsed.port) || (parsed.protocol === "https:" ? 443 : 80);
+    return await Deno.connect({ hostname: parsed.hostname, port });
+  };
+  Promise.race([connect(), timeout])
+    .then((conn) => {
+      conn.close();
+      probes.set(url, { reachable: true, at: Date.now() });
+    })
+    .catch((e) => {
+      // Without --allow-net for the proxy host, assume it is reachable
+      if (e instanceof Deno.errors.PermissionDenied || e?.name === "NotCapable") {
+        return;
+      }
+      probes.set(url, { reachable: false, at: Date.now() });
+    });
+}
+
+function useProxy(url: string, provider: string): boolean {
+  if (FAIL_MODE !== "open") {
+    return true;
   }
+  probe(url);
+  if (probes.get(url)?.reachable === false) {
+    console.warn(`[PromptGuard Shim] Proxy ${url} is unreachable; ${provider} falls back to its own endpoint`);
+    return false;
+  }
+  return true;
+}
+
+if (FAIL_MODE === "open") {
+  for (const url of [PROXY_URL, ...Object.values(PROVIDER_PROXY_URLS)]) {
+    probe(url);
+  }
+}
+
+function logCall(provider: string, endpoint: string | null, action: string): void {
+  if (!LOG_ENABLED) {
+    return;
+  }
+  const entry = { timestamp: new Date().toISOString(), provider, endpoint, action };
+  try {
+    // Needs --allow-write for the shim directory
+    Deno.writeTextFileSync(LOG_PATH, JSON.stringify(entry) + "\n", { append: true });
+  } catch (e) {
+    debug(`Could not write ${LOG_PATH}: ${e}`);
+  }
+}
+
+function withProxy(config: Record<string, unknown> | undefined, target: Target) {
+  const cfg = config || {};
+  if (target.param in cfg) {
+    debug(`${target.provider}: ${target.param} already set to ${cfg[target.param]}`);
+    logCall(target.provider, String(cfg[target.param]), "preconfigured");
+    return cfg;
+  }
+
+  const proxyUrl = PROVIDER_PROXY_URLS[target.provider] || PROXY_URL;
+  if (!useProxy(proxyUrl, target.provider)) {
+    logCall(target.provider, null, "fallback");
+    return cfg;
+  }
+  const modified: Record<string, unknown> = { ...cfg, [target.param]: proxyUrl };
+  debug(`${target.provider}: injected ${target.param}=${proxyUrl}`);
+  logCall(target.provider, proxyUrl, "routed");
+
+  const keyVar = PROVIDER_API_KEY_VARS[target.provider] || API_KEY_VAR;
+  const key = env(keyVar);
+  if (!(target.keyParam in modified) && key) {
+    modified[target.keyParam] = key;
+    debug(`${target.provider}: using API key from ${keyVar}`);
+  }
+  return modified;
+}
+
+const wrapped = new WeakMap<object, unknown>();
+
+/** Wrap a client class exported by `pkg`, so constructing it routes through PromptGuard */
+export function wrapClient<T>(value: T, pkg: string): T {
+  const target = TARGETS[pkg];
+  if (!ENABLED || !target || typeof value !== "function") {
+    return value;
+  }
+  if (wrapped.has(value)) {
+    return wrapped.get(value) as T;
+  }
+  // A proxy rather than a subclass, so `instanceof` and static members keep working
+  const proxy = new Proxy(value, {
+    construct(original, args, newTarget) {
+      const [config, ...rest] = args;
+      return Reflect.construct(original, [withProxy(config, target), ...rest], newTarget);
+    },
+  });
+  wrapped.set(value, proxy);
+  debug(`${target.provider} SDK shimmed successfully`);
+  return proxy;
 }
 "#;
 
+/// Deno wrapper for one SDK package, re-exporting it with client classes wrapped.
+/// The import map resolves the SDK import here to the project's own mapping.
+pub const DENO_WRAPPER_TEMPLATE: &str = r#"// PromptGuard Deno wrapper for {{PACKAGE}} - Auto-generated
+// Generated by PromptGuard CLI v{{CLI_VERSION}}
+import * as sdk from "{{PACKAGE}}";
+import { wrapClient } from "../deno-shim.ts";
+
+export * from "{{PACKAGE}}";
+{{DENO_EXPORTS}}export default wrapClient(sdk.default, "{{PACKAGE}}");
+"#;
+
+/// SDK the JavaScript shims intercept for a provider
+pub struct JavaScriptTarget {
+    pub provider: Provider,
+    /// npm package name, as passed to `require`
+    pub package: &'static str,
+    /// Exported client classes
+    pub classes: &'static [&'static str],
+    /// Constructor option holding the base URL
+    pub param: &'static str,
+    /// Constructor option holding the API key
+    pub key_param: &'static str,
+}
+
+/// Get the JavaScript shim target for a given provider
+pub fn get_javascript_target(provider: Provider) -> Option<JavaScriptTarget> {
+    let info = ProviderInfo::get(provider);
+    if !info.ts_shim {
+        return None;
+    }
+    Some(JavaScriptTarget {
+        provider,
+        package: info.npm_packages.first()?,
+        classes: std::slice::from_ref(&info.ts_class_name),
+        param: info.ts_base_url_param,
+        key_param: info.ts_api_key_param,
+    })
+}
+
 /// Get Python provider patch code for a given provider
-pub fn get_python_provider_patch(provider: Provider) -> &'static str {
-    match provider {
-        Provider::OpenAI => PYTHON_OPENAI_PATCH,
-        Provider::Anthropic => PYTHON_ANTHROPIC_PATCH,
-        Provider::Cohere => PYTHON_COHERE_PATCH,
-        Provider::HuggingFace => PYTHON_HUGGINGFACE_PATCH,
-        Provider::Gemini => "# Gemini Python shim - coming soon\n",
-        Provider::Groq => "# Groq Python shim - coming soon\n",
-        Provider::Bedrock => "# Bedrock: use promptguard SDK auto-instrumentation instead\n# pip install promptguard-sdk && promptguard.init()\n",
+pub fn get_python_provider_patch(provider: Provider) -> String {
+    let info = ProviderInfo::get(provider);
+    let name = provider.display_name();
+    if info.py_shim_module.is_empty() {
+        return format!("# {name}: not covered by the Python runtime shim\n");
     }
+
+    let fill = |template: &str| {
+        template
+            .replace("{{MODULE}}", info.py_shim_module)
+            .replace("{{PROVIDER}}", name)
+            .replace("{{PARAM}}", info.py_base_url_param)
+            .replace("{{KEY_PARAM}}", info.py_api_key_param)
+    };
+    let class_patches: String = info
+        .py_shim_classes
+        .iter()
+        .map(|class| fill(PYTHON_CLASS_PATCH_TEMPLATE).replace("{{CLASS}}", class))
+        .collect();
+    let module_patch = info
+        .py_module_base_url_attrs
+        .first()
+        .map_or_else(String::new, |attr| {
+            fill(PYTHON_MODULE_PATCH_TEMPLATE)
+                .replace("{{ATTR_NAME}}", attr.rsplit('.').next().unwrap_or(attr))
+                .replace("{{ATTR}}", attr)
+        });
+
+    fill(PYTHON_PROVIDER_PATCH_TEMPLATE)
+        .replace("{{KEY}}", provider.as_str())
+        .replace("{{CLASS_PATCHES}}", &class_patches)
+        .replace("{{MODULE_PATCH}}", &module_patch)
 }
 
 /// Get Python install call for a given provider
-pub fn get_python_install_call(provider: Provider) -> &'static str {
-    match provider {
-        Provider::OpenAI => "    _shim_openai()\n    providers_shimmed.append('OpenAI')",
-        Provider::Anthropic => "    _shim_anthropic()\n    providers_shimmed.append('Anthropic')",
-        Provider::Cohere => "    _shim_cohere()\n    providers_shimmed.append('Cohere')",
-        Provider::HuggingFace => {
-            "    _shim_huggingface()\n    providers_shimmed.append('HuggingFace')"
-        },
-        Provider::Gemini => "    # Gemini shim - coming soon",
-        Provider::Groq => "    # Groq shim - coming soon",
-        Provider::Bedrock => {
-            "    # Bedrock: use promptguard SDK auto-instrumentation (promptguard.init())"
-        },
+pub fn get_python_install_call(provider: Provider) -> String {
+    let name = provider.display_name();
+    if ProviderInfo::get(provider).py_shim_module.is_empty() {
+        return format!("    # {name}: not covered by the Python runtime shim");
     }
+    format!(
+        "    if _shim_{}():\n        providers_shimmed.append(\"{name}\")",
+        provider.as_str()
+    )
 }
 
 /// Get TypeScript provider export code for a given provider
-pub fn get_typescript_provider_export(provider: Provider) -> &'static str {
-    match provider {
-        Provider::OpenAI => TYPESCRIPT_OPENAI_EXPORT,
-        Provider::Anthropic => TYPESCRIPT_ANTHROPIC_EXPORT,
-        Provider::Cohere => TYPESCRIPT_COHERE_EXPORT,
-        Provider::HuggingFace => TYPESCRIPT_HUGGINGFACE_EXPORT,
-        Provider::Gemini => "// Gemini TypeScript shim - coming soon\n",
-        Provider::Groq => "// Groq TypeScript shim - coming soon\n",
-        Provider::Bedrock => "// Bedrock: use promptguard SDK auto-instrumentation instead\n// npm install promptguard-sdk && require('promptguard-sdk').init()\n",
-    }
+pub fn get_typescript_provider_export(provider: Provider) -> String {
+    let name = provider.display_name();
+    let Some(target) = get_javascript_target(provider) else {
+        return format!("// {name}: not covered by the TypeScript runtime shim\n");
+    };
+    TYPESCRIPT_PROVIDER_EXPORT_TEMPLATE
+        .replace("{{PROVIDER}}", name)
+        .replace("{{PACKAGE}}", target.package)
+        .replace("{{CLASS}}", target.classes[0])
+        .replace("{{PARAM}}", target.param)
+        .replace("{{KEY_PARAM}}", target.key_param)
 }
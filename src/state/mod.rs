@@ -0,0 +1,305 @@
+/// Local state storage with optional at-rest encryption
+///
+/// Everything the CLI persists outside of source files (credentials, caches,
+/// journals) is read and written through this module. When encryption is
+/// enabled, files are sealed with ChaCha20-Poly1305 using a random 256-bit key
+/// stored in `~/.promptguard/state.key` (mode 0600).
+///
+/// Shim sources and their manifests under `.promptguard/` are executed by
+/// language runtimes, and the shims read the Deno import map and append to
+/// `shim.log`, so none of them are ever encrypted.
+use crate::error::{PromptGuardError, Result};
+use crate::textfile;
+use aws_lc_rs::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use aws_lc_rs::rand::{SecureRandom, SystemRandom};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Name of the state directory, both per-project and in the home directory.
+pub const STATE_DIR_NAME: &str = ".promptguard";
+
+const KEY_FILE_NAME: &str = "state.key";
+
+/// Copy of the binary replaced by `promptguard update`, kept for `--rollback`
+pub const PREVIOUS_BINARY_NAME: &str = "promptguard.previous";
+const KEY_LEN: usize = 32;
+
+/// Header prepended to every encrypted state file.
+const MAGIC: &[u8] = b"PGENC1";
+
+/// Files under a state directory that must stay readable by other tools.
+const PLAINTEXT_EXTENSIONS: &[&str] = &["py", "ts", "js", "mjs", "cjs", "md"];
+const PLAINTEXT_FILES: &[&str] = &[
+    "package.json",
+    ".gitignore",
+    // Read by Deno when the shim is loaded
+    "deno-import-map.json",
+    // Appended to by the shims at runtime
+    "shim.log",
+    KEY_FILE_NAME,
+    PREVIOUS_BINARY_NAME,
+];
+
+/// Global state directory (`~/.promptguard`)
+pub fn global_state_dir() -> Result<PathBuf> {
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .map_err(|_| PromptGuardError::Config("Cannot determine home directory".to_string()))?;
+    Ok(PathBuf::from(home).join(STATE_DIR_NAME))
+}
+
+/// Project state directory (`<root>/.promptguard`)
+pub fn project_state_dir(root: &Path) -> PathBuf {
+    root.join(STATE_DIR_NAME)
+}
+
+fn key_path() -> Result<PathBuf> {
+    Ok(global_state_dir()?.join(KEY_FILE_NAME))
+}
+
+/// Whether new state files are written encrypted
+pub fn encryption_enabled() -> bool {
+    key_path().is_ok_and(|p| p.exists())
+}
+
+fn load_key() -> Result<Option<Vec<u8>>> {
+    let path = key_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let key = fs::read(&path)?;
+    if key.len() != KEY_LEN {
+        return Err(PromptGuardError::Config(format!(
+            "State key {} is corrupt (expected {KEY_LEN} bytes)",
+            path.display()
+        )));
+    }
+    Ok(Some(key))
+}
+
+/// Load the state key, generating and storing a new one if none exists
+pub fn ensure_key() -> Result<Vec<u8>> {
+    if let Some(key) = load_key()? {
+        return Ok(key);
+    }
+
+    let mut key = vec![0u8; KEY_LEN];
+    SystemRandom::new()
+        .fill(&mut key)
+        .map_err(|_| PromptGuardError::Custom("Failed to generate state key".to_string()))?;
+
+    let path = key_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, &key)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(key)
+}
+
+/// Delete the state key, turning encryption off for future writes
+pub fn remove_key() -> Result<()> {
+    let path = key_path()?;
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn sealing_key(key: &[u8]) -> Result<LessSafeKey> {
+    UnboundKey::new(&CHACHA20_POLY1305, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| PromptGuardError::Config("Invalid state key".to_string()))
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+pub fn encrypt_bytes(key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce_bytes)
+        .map_err(|_| PromptGuardError::Custom("Failed to generate nonce".to_string()))?;
+
+    let mut in_out = plaintext.to_vec();
+    sealing_key(key)?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce_bytes),
+            Aad::from(MAGIC),
+            &mut in_out,
+        )
+        .map_err(|_| PromptGuardError::Custom("Failed to encrypt state".to_string()))?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + in_out.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce_bytes);
+    out.extend_from_slice(&in_out);
+    Ok(out)
+}
+
+pub fn decrypt_bytes(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let body = data
+        .strip_prefix(MAGIC)
+        .filter(|b| b.len() >= NONCE_LEN)
+        .ok_or_else(|| PromptGuardError::Parse("Not an encrypted state file".to_string()))?;
+    let (nonce_bytes, ciphertext) = body.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce_bytes)
+        .map_err(|_| PromptGuardError::Parse("Invalid nonce".to_string()))?;
+
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = sealing_key(key)?
+        .open_in_place(nonce, Aad::from(MAGIC), &mut in_out)
+        .map_err(|_| {
+            PromptGuardError::Config(
+                "Failed to decrypt state file (wrong key or corrupted data)".to_string(),
+            )
+        })?;
+    Ok(plaintext.to_vec())
+}
+
+/// Read a state file, decrypting it if necessary
+pub fn read_state(path: &Path) -> Result<String> {
+    let data = fs::read(path)?;
+    let plaintext = if is_encrypted(&data) {
+        let key = load_key()?.ok_or_else(|| {
+            PromptGuardError::Config(format!(
+                "{} is encrypted but no state key was found at ~/.promptguard/{KEY_FILE_NAME}",
+                path.display()
+            ))
+        })?;
+        decrypt_bytes(&key, &data)?
+    } else {
+        data
+    };
+    String::from_utf8(plaintext)
+        .map_err(|e| PromptGuardError::Parse(format!("{} is not valid UTF-8: {e}", path.display())))
+}
+
+/// Write a state file, encrypting it when encryption is enabled
+pub fn write_state(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match load_key()? {
+        Some(key) => fs::write(path, encrypt_bytes(&key, content.as_bytes())?)?,
+        None => fs::write(path, content)?,
+    }
+    Ok(())
+}
+
+/// Whether a file inside a state directory holds state (as opposed to shim code)
+pub fn is_state_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    if PLAINTEXT_FILES.contains(&name) {
+        return false;
+    }
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    !PLAINTEXT_EXTENSIONS.contains(&ext)
+}
+
+/// List all state files under the given directories
+pub fn list_state_files(dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in dirs {
+        if !dir.exists() {
+            continue;
+        }
+        for entry in WalkDir::new(dir)
+            .follow_links(false)
+            .into_iter()
+            .filter_map(std::result::Result::ok)
+        {
+            let path = entry.path();
+            if path.is_file() && is_state_file(path) {
+                files.push(path.to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Encrypt every plaintext state file in place, replacing each atomically so
+/// an interrupted run never leaves a truncated file. Returns the files changed.
+pub fn encrypt_files(key: &[u8], files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut changed = Vec::new();
+    for file in files {
+        let data = fs::read(file)?;
+        if is_encrypted(&data) {
+            continue;
+        }
+        textfile::write_atomic(file, &encrypt_bytes(key, &data)?)?;
+        changed.push(file.clone());
+    }
+    Ok(changed)
+}
+
+/// Decrypt every encrypted state file in place, atomically like
+/// `encrypt_files`. Returns the files changed.
+pub fn decrypt_files(key: &[u8], files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut changed = Vec::new();
+    for file in files {
+        let data = fs::read(file)?;
+        if !is_encrypted(&data) {
+            continue;
+        }
+        textfile::write_atomic(file, &decrypt_bytes(key, &data)?)?;
+        changed.push(file.clone());
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let key = [7u8; KEY_LEN];
+        let sealed = encrypt_bytes(&key, b"{\"api_key\": \"pg_sk_test_x\"}").unwrap();
+
+        assert!(is_encrypted(&sealed));
+        assert!(!String::from_utf8_lossy(&sealed).contains("pg_sk_test_x"));
+        assert_eq!(
+            decrypt_bytes(&key, &sealed).unwrap(),
+            b"{\"api_key\": \"pg_sk_test_x\"}"
+        );
+    }
+
+    #[test]
+    fn test_decrypt_with_wrong_key_fails() {
+        let sealed = encrypt_bytes(&[1u8; KEY_LEN], b"secret").unwrap();
+        assert!(decrypt_bytes(&[2u8; KEY_LEN], &sealed).is_err());
+    }
+
+    #[test]
+    fn test_shim_files_are_not_state() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join(STATE_DIR_NAME);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("promptguard_shim.py"), "import os").unwrap();
+        fs::write(dir.join("package.json"), "{}").unwrap();
+        fs::write(dir.join("journal.json"), "[]").unwrap();
+        fs::write(dir.join("deno-import-map.json"), "{}").unwrap();
+        fs::write(dir.join("shim.log"), "openai\n").unwrap();
+
+        let files = list_state_files(std::slice::from_ref(&dir));
+        assert_eq!(files, vec![dir.join("journal.json")]);
+
+        let key = [3u8; KEY_LEN];
+        assert_eq!(encrypt_files(&key, &files).unwrap().len(), 1);
+        assert!(encrypt_files(&key, &files).unwrap().is_empty());
+        assert_eq!(decrypt_files(&key, &files).unwrap().len(), 1);
+        assert_eq!(fs::read_to_string(dir.join("journal.json")).unwrap(), "[]");
+    }
+}
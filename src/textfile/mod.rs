@@ -0,0 +1,181 @@
+/// Reading and writing user source files without changing their format
+///
+/// Transformers and injectors work on LF-only text with no byte-order mark.
+/// Files are decoded into that form on read and re-encoded on write with
+/// their original line endings, BOM, and encoding, so a transform on a
+/// Windows checkout only shows the lines it actually changed.
+///
+/// Writes go to a temporary file in the same directory that is synced and
+/// renamed over the original, so a crash never leaves a half-written file,
+/// and the original's permissions (e.g. executable bits) are kept.
+use crate::error::Result;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// On-disk format of a text file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextFormat {
+    pub encoding: Encoding,
+    /// Most line endings are `\r\n`
+    pub crlf: bool,
+}
+
+impl TextFormat {
+    /// Decode `bytes` into LF-terminated text without a BOM
+    pub fn decode(bytes: &[u8]) -> io::Result<(Self, String)> {
+        let (encoding, text) = if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+            (Encoding::Utf8Bom, utf8(rest)?)
+        } else if let Some(rest) = bytes.strip_prefix(UTF16_LE_BOM) {
+            (Encoding::Utf16Le, utf16(rest, u16::from_le_bytes)?)
+        } else if let Some(rest) = bytes.strip_prefix(UTF16_BE_BOM) {
+            (Encoding::Utf16Be, utf16(rest, u16::from_be_bytes)?)
+        } else {
+            (Encoding::Utf8, utf8(bytes)?)
+        };
+
+        let crlf_count = text.matches("\r\n").count();
+        let crlf = crlf_count > 0 && crlf_count * 2 >= text.matches('\n').count();
+        let text = if crlf {
+            text.replace("\r\n", "\n")
+        } else {
+            text
+        };
+
+        Ok((Self { encoding, crlf }, text))
+    }
+
+    /// Encode LF-terminated `text` back into this format
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        let text = if self.crlf {
+            text.replace("\r\n", "\n").replace('\n', "\r\n")
+        } else {
+            text.to_string()
+        };
+
+        match self.encoding {
+            Encoding::Utf8 => text.into_bytes(),
+            Encoding::Utf8Bom => [UTF8_BOM, text.as_bytes()].concat(),
+            Encoding::Utf16Le => UTF16_LE_BOM
+                .iter()
+                .copied()
+                .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+                .collect(),
+            Encoding::Utf16Be => UTF16_BE_BOM
+                .iter()
+                .copied()
+                .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+                .collect(),
+        }
+    }
+}
+
+/// Read a text file as LF-terminated text, along with its on-disk format
+pub fn read(path: &Path) -> Result<(TextFormat, String)> {
+    Ok(TextFormat::decode(&fs::read(path)?)?)
+}
+
+/// Write LF-terminated `text` to `path` in `format`
+pub fn write(path: &Path, format: TextFormat, text: &str) -> Result<()> {
+    write_atomic(path, &format.encode(text))
+}
+
+/// Replace the contents of `path` atomically, keeping its permissions.
+/// Symlinks are followed so the link itself survives.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let target = if path.is_symlink() {
+        fs::canonicalize(path)?
+    } else {
+        path.to_path_buf()
+    };
+    let dir = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let (temp_path, mut file) = create_temp(&dir, &target)?;
+    let written = (|| -> io::Result<()> {
+        file.write_all(contents)?;
+        if let Ok(metadata) = fs::metadata(&target) {
+            fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temp_path, &target)
+    })();
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+
+    // Persist the rename itself; not supported on every platform
+    if let Ok(dir) = File::open(&dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Create a new hidden temp file next to `target`
+fn create_temp(dir: &Path, target: &Path) -> io::Result<(PathBuf, File)> {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    let name = target
+        .file_name()
+        .map_or_else(String::new, |n| n.to_string_lossy().into_owned());
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    loop {
+        let attempt = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let temp_path = dir.join(format!(
+            ".{name}.pg-tmp-{}-{nanos}-{attempt}",
+            std::process::id()
+        ));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+        {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {},
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn utf8(bytes: &[u8]) -> io::Result<String> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "stream did not contain valid UTF-8",
+        )
+    })
+}
+
+fn utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> io::Result<String> {
+    let units: Vec<u16> = bytes
+        .chunks(2)
+        .map(|pair| unit([pair[0], pair.get(1).copied().unwrap_or(0)]))
+        .collect();
+    String::from_utf16(&units).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "stream did not contain valid UTF-16",
+        )
+    })
+}
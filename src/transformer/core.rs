@@ -1,45 +1,95 @@
+use crate::detector::hierarchy::SubclassQuery;
+use crate::detector::is_ignored;
 use crate::error::{PromptGuardError, Result};
-use crate::types::TransformResult;
-use std::fs;
-use std::path::Path;
+use crate::types::{Provider, StaleBaseUrl};
 use streaming_iterator::StreamingIterator;
 use tree_sitter::{Language as TSLanguage, Parser, Query, QueryCursor};
 
 pub struct TransformConfig {
     pub parser_language: TSLanguage,
     pub language_name: &'static str,
+    pub provider: Provider,
+    /// Extra query for constructors of local subclasses, built per file
+    pub subclass_query: SubclassQuery,
 }
 
-pub fn transform_file_generic<F, G>(
-    file_path: &Path,
+/// Apply the query-driven modifications to `source`. Returns `None` when nothing changes.
+pub fn transform_source_generic<F, G>(
+    source: &str,
     config: &TransformConfig,
     query_str: &str,
     extract_modification: F,
     finalize: G,
-) -> Result<TransformResult>
+) -> Result<Option<String>>
 where
     F: Fn(&str, tree_sitter::Node) -> Option<(usize, usize, String)>,
     G: Fn(String) -> String,
 {
-    let source = fs::read_to_string(file_path)?;
+    let mut modifications = collect_args(source, config, query_str, extract_modification)?;
+    if modifications.is_empty() {
+        return Ok(None);
+    }
+
+    modifications.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+    // Calls sharing one options object produce the same edit
+    modifications.dedup_by_key(|(start, _, _)| *start);
+
+    let mut new_source = source.to_string();
+    for (start, end, replacement) in modifications {
+        new_source.replace_range(start..end, &replacement);
+    }
+
+    Ok(Some(finalize(new_source)))
+}
+
+/// 1-based lines of matched calls whose arguments satisfy `predicate`
+pub fn matching_lines<F>(
+    source: &str,
+    config: &TransformConfig,
+    query_str: &str,
+    predicate: F,
+) -> Result<Vec<usize>>
+where
+    F: Fn(&str, tree_sitter::Node) -> bool,
+{
+    let mut lines = collect_args(source, config, query_str, |source, args_node| {
+        predicate(source, args_node).then(|| args_node.start_position().row + 1)
+    })?;
+    lines.sort_unstable();
+    lines.dedup();
+    Ok(lines)
+}
 
+/// Run `extract` on the `@args` capture of every query match
+pub fn collect_args<T, F>(
+    source: &str,
+    config: &TransformConfig,
+    query_str: &str,
+    extract: F,
+) -> Result<Vec<T>>
+where
+    F: Fn(&str, tree_sitter::Node) -> Option<T>,
+{
     let mut parser = Parser::new();
     parser.set_language(&config.parser_language).map_err(|_| {
         PromptGuardError::Parse(format!("Failed to set {} language", config.language_name))
     })?;
 
-    let tree = parser.parse(&source, None).ok_or_else(|| {
+    let tree = parser.parse(source, None).ok_or_else(|| {
         PromptGuardError::Parse(format!("Failed to parse {} file", config.language_name))
     })?;
 
-    let query = Query::new(&config.parser_language, query_str)
+    let query_str = match (config.subclass_query)(source, tree.root_node(), config.provider) {
+        Some(subclasses) => format!("{query_str}\n{subclasses}"),
+        None => query_str.to_string(),
+    };
+    let query = Query::new(&config.parser_language, &query_str)
         .map_err(|e| PromptGuardError::Parse(format!("Query error: {e}")))?;
 
     let mut cursor = QueryCursor::new();
     let mut matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
 
-    let mut modifications: Vec<(usize, usize, String)> = Vec::new();
-
+    let mut results = Vec::new();
     while let Some(match_) = matches.next() {
         let args_node = match match_
             .captures
@@ -50,25 +100,74 @@ where
             None => continue,
         };
 
-        if let Some(modification) = extract_modification(&source, args_node) {
-            modifications.push(modification);
+        if let Some(result) = extract(source, args_node) {
+            results.push(result);
         }
     }
 
-    if modifications.is_empty() {
-        return Ok(TransformResult { modified: false });
-    }
+    Ok(results)
+}
 
-    modifications.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+/// Whether the call owning `args_node` carries an inline ignore directive
+pub fn is_ignored_call(source: &str, args_node: tree_sitter::Node) -> bool {
+    let call = args_node.parent().unwrap_or(args_node);
+    is_ignored(source, call.start_position().row)
+}
 
-    let mut new_source = source;
-    for (start, end, replacement) in modifications {
-        new_source.replace_range(start..end, &replacement);
-    }
+/// Text of `container` with `items[index]` and its separating comma removed
+pub fn without_item(
+    source: &str,
+    container: tree_sitter::Node,
+    items: &[tree_sitter::Node],
+    index: usize,
+) -> String {
+    let base = container.start_byte();
+    let (start, end) = if index > 0 {
+        (items[index - 1].end_byte(), items[index].end_byte())
+    } else if let Some(next) = items.get(1) {
+        (items[0].start_byte(), next.start_byte())
+    } else {
+        (items[0].start_byte(), items[0].end_byte())
+    };
+    let text = &source[base..container.end_byte()];
+    format!("{}{}", &text[..start - base], &text[end - base..])
+}
 
-    new_source = finalize(new_source);
+/// `value` as a stale base URL when it is a plain string literal other than `proxy_url`
+pub fn stale_literal(
+    source: &str,
+    value: tree_sitter::Node,
+    proxy_url: &str,
+) -> Option<StaleBaseUrl> {
+    let url = string_content(source, value)?;
+    let plain = source[value.start_byte()..].starts_with(['"', '\'']);
+    (plain && url != proxy_url).then(|| StaleBaseUrl {
+        line: value.start_position().row + 1,
+        url: url.to_string(),
+        start: value.start_byte(),
+        end: value.end_byte(),
+    })
+}
 
-    fs::write(file_path, &new_source)?;
+/// Contents of a string literal node, without quotes
+pub fn string_content<'a>(source: &'a str, node: tree_sitter::Node) -> Option<&'a str> {
+    if node.kind() != "string" {
+        return None;
+    }
+    Some(source[node.start_byte()..node.end_byte()].trim_matches(|c| c == '"' || c == '\''))
+}
 
-    Ok(TransformResult { modified: true })
+/// Value of a `name=value` / `name: value` node (`kind`) whose `key_field` is `name`
+pub fn named_value<'t>(
+    source: &str,
+    node: tree_sitter::Node<'t>,
+    kind: &str,
+    key_field: &str,
+    name: &str,
+) -> Option<tree_sitter::Node<'t>> {
+    let key = node.child_by_field_name(key_field)?;
+    let key_text = source[key.start_byte()..key.end_byte()].trim_matches(|c| c == '"' || c == '\'');
+    (node.kind() == kind && key_text == name)
+        .then(|| node.child_by_field_name("value"))
+        .flatten()
 }
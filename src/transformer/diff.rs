@@ -0,0 +1,208 @@
+/// Line-based unified diffs for previewing transformations
+///
+/// Uses the Myers algorithm, which is cheap here because transformations
+/// only touch a handful of lines per file.
+use crate::types::TextEdit;
+use std::fmt::Write;
+
+/// Lines of unchanged context around each hunk
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// One line of the edit script, with its position in the old and new file
+#[derive(Debug, Clone, Copy)]
+struct Edit<'a> {
+    op: Op,
+    text: &'a str,
+    old: usize,
+    new: usize,
+}
+
+/// Unified diff between `old` and `new`, or an empty string if they are equal.
+/// A missing file is diffed as empty (`/dev/null`).
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let edits = edit_script(&a, &b);
+    if edits.iter().all(|e| e.op == Op::Equal) {
+        return String::new();
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "--- {old_label}");
+    let _ = writeln!(out, "+++ {new_label}");
+
+    for (start, end) in hunks(&edits) {
+        let lines = &edits[start..end];
+        let old_count = lines.iter().filter(|e| e.op != Op::Insert).count();
+        let new_count = lines.iter().filter(|e| e.op != Op::Delete).count();
+        let _ = writeln!(
+            out,
+            "@@ -{} +{} @@",
+            range(lines[0].old, old_count),
+            range(lines[0].new, new_count)
+        );
+        for edit in lines {
+            let prefix = match edit.op {
+                Op::Equal => ' ',
+                Op::Delete => '-',
+                Op::Insert => '+',
+            };
+            let _ = writeln!(out, "{prefix}{}", edit.text);
+        }
+    }
+
+    out
+}
+
+/// Changed spans between `old` and `new`, with byte ranges in `new`.
+/// Each span covers whole lines, so it can be located and undone later.
+pub fn text_edits(old: &str, new: &str) -> Vec<TextEdit> {
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+
+    let mut edits: Vec<TextEdit> = Vec::new();
+    let mut offset = 0;
+    let mut pending: Option<TextEdit> = None;
+
+    for edit in edit_script(&a, &b) {
+        if edit.op == Op::Equal {
+            if let Some(done) = pending.take() {
+                edits.push(done);
+            }
+            offset += edit.text.len();
+            continue;
+        }
+        let current = pending.get_or_insert_with(|| TextEdit {
+            start: offset,
+            end: offset,
+            original: String::new(),
+            replacement: String::new(),
+        });
+        if edit.op == Op::Delete {
+            current.original.push_str(edit.text);
+        } else {
+            current.replacement.push_str(edit.text);
+            current.end += edit.text.len();
+            offset += edit.text.len();
+        }
+    }
+    edits.extend(pending);
+
+    edits
+}
+
+/// Hunk header range (`start,count`, 1-based; start is the preceding line when empty)
+fn range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{count}", start + 1),
+    }
+}
+
+/// Group changes into `[start, end)` windows of the edit script, merging
+/// changes whose context would overlap
+fn hunks(edits: &[Edit]) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (i, edit) in edits.iter().enumerate() {
+        if edit.op == Op::Equal {
+            continue;
+        }
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + CONTEXT + 1).min(edits.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    hunks
+}
+
+/// Shortest edit script from `a` to `b` (Myers, with backtracking over saved frontiers)
+#[allow(
+    clippy::cast_possible_wrap,
+    clippy::cast_sign_loss,
+    clippy::many_single_char_names
+)]
+fn edit_script<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<Edit<'a>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    let idx = |k: isize| (k + max) as usize;
+
+    let mut v = vec![0isize; 2 * max as usize + 2];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[idx(k - 1)] < v[idx(k + 1)]) {
+                v[idx(k + 1)]
+            } else {
+                v[idx(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && v[idx(k - 1)] < v[idx(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[idx(prev_k)];
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            edits.push(Edit {
+                op: Op::Equal,
+                text: a[x as usize],
+                old: x as usize,
+                new: y as usize,
+            });
+        }
+        if d > 0 {
+            if x == prev_x {
+                y -= 1;
+                edits.push(Edit {
+                    op: Op::Insert,
+                    text: b[y as usize],
+                    old: x as usize,
+                    new: y as usize,
+                });
+            } else {
+                x -= 1;
+                edits.push(Edit {
+                    op: Op::Delete,
+                    text: a[x as usize],
+                    old: x as usize,
+                    new: y as usize,
+                });
+            }
+        }
+    }
+
+    edits.reverse();
+    edits
+}
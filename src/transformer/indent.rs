@@ -0,0 +1,183 @@
+/// Indentation for inserted argument lines
+///
+/// Multi-line argument lists are indented one unit past the line the call
+/// starts on. The unit comes from `.editorconfig` when it sets one for the
+/// file, otherwise from the indentation the file already uses, and only
+/// falls back to the language default for files with no indented lines.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Indentation unit (`"\t"`, `"  "`, ...) `.editorconfig` sets for `file_path`
+pub fn editorconfig_unit(file_path: &Path) -> Option<String> {
+    let dir = file_path.parent()?;
+    let name = file_path.file_name()?.to_string_lossy().into_owned();
+
+    // Nearest files take precedence, so collect outward and apply inward
+    let mut configs = Vec::new();
+    for ancestor in dir.ancestors() {
+        let Ok(contents) = fs::read_to_string(ancestor.join(".editorconfig")) else {
+            continue;
+        };
+        let is_root = parse_sections(&contents)
+            .first()
+            .is_some_and(|(glob, props)| {
+                glob.is_none() && props.get("root").is_some_and(|v| v == "true")
+            });
+        configs.push((ancestor, contents));
+        if is_root {
+            break;
+        }
+    }
+
+    let mut props: BTreeMap<String, String> = BTreeMap::new();
+    for (config_dir, contents) in configs.iter().rev() {
+        let relative = file_path
+            .strip_prefix(config_dir)
+            .map_or_else(|_| name.clone(), |p| p.to_string_lossy().replace('\\', "/"));
+        for (glob, section) in parse_sections(contents) {
+            if glob.is_some_and(|g| section_matches(&g, &name, &relative)) {
+                props.extend(section);
+            }
+        }
+    }
+
+    match props.get("indent_style").map(String::as_str) {
+        Some("tab") => Some("\t".to_string()),
+        Some("space") => {
+            let size = props
+                .get("indent_size")
+                .filter(|s| *s != "tab")
+                .or_else(|| props.get("tab_width"))
+                .and_then(|s| s.parse::<usize>().ok())
+                .filter(|n| (1..=16).contains(n))?;
+            Some(" ".repeat(size))
+        },
+        _ => None,
+    }
+}
+
+/// Indentation unit most used in `source`: tabs, or the most common
+/// increase in leading spaces between consecutive lines
+pub fn infer_unit(source: &str) -> Option<String> {
+    let indents: Vec<&str> = source
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| &l[..l.len() - l.trim_start().len()])
+        .collect();
+
+    let tabbed = indents.iter().filter(|i| i.starts_with('\t')).count();
+    let spaced = indents.iter().filter(|i| i.starts_with(' ')).count();
+    if tabbed > 0 && tabbed >= spaced {
+        return Some("\t".to_string());
+    }
+
+    let mut steps: BTreeMap<usize, usize> = BTreeMap::new();
+    for pair in indents.windows(2) {
+        let (prev, next) = (pair[0].len(), pair[1].len());
+        if next > prev && pair[1].bytes().all(|b| b == b' ') {
+            *steps.entry(next - prev).or_default() += 1;
+        }
+    }
+    // Most frequent step; ties go to the smaller one
+    let (step, _) = steps
+        .into_iter()
+        .max_by(|(a_step, a_count), (b_step, b_count)| {
+            a_count.cmp(b_count).then(b_step.cmp(a_step))
+        })?;
+    Some(" ".repeat(step))
+}
+
+/// Leading whitespace of the line containing byte `offset`
+pub fn line_indent(source: &str, offset: usize) -> &str {
+    let start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = &source[start..];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+type Section = (Option<String>, BTreeMap<String, String>);
+
+/// `[glob]` sections in order; the preamble before the first one has no glob
+fn parse_sections(contents: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = vec![(None, BTreeMap::new())];
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+        if let Some(glob) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push((Some(glob.to_string()), BTreeMap::new()));
+        } else if let Some((key, value)) = line.split_once('=') {
+            if let Some((_, props)) = sections.last_mut() {
+                props.insert(key.trim().to_lowercase(), value.trim().to_lowercase());
+            }
+        }
+    }
+    sections
+}
+
+/// Globs without a `/` match the file name; others match the path relative to the `.editorconfig`
+fn section_matches(glob: &str, name: &str, relative: &str) -> bool {
+    let target = if glob.contains('/') { relative } else { name };
+    let glob = glob.trim_start_matches('/');
+    expand_braces(glob)
+        .iter()
+        .any(|g| glob::Pattern::new(g).is_ok_and(|p| p.matches(target)))
+}
+
+/// `*.{js,ts}` → `*.js`, `*.ts`
+fn expand_braces(glob: &str) -> Vec<String> {
+    let (Some(open), Some(close)) = (glob.find('{'), glob.find('}')) else {
+        return vec![glob.to_string()];
+    };
+    if close < open {
+        return vec![glob.to_string()];
+    }
+    glob[open + 1..close]
+        .split(',')
+        .flat_map(|alt| expand_braces(&format!("{}{alt}{}", &glob[..open], &glob[close + 1..])))
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infer_unit() {
+        assert_eq!(
+            infer_unit("def f():\n\tif x:\n\t\treturn 1\n").as_deref(),
+            Some("\t")
+        );
+        assert_eq!(
+            infer_unit("function f() {\n  if (x) {\n    return 1;\n  }\n}\n").as_deref(),
+            Some("  ")
+        );
+        assert_eq!(infer_unit("x = 1\n"), None);
+    }
+
+    #[test]
+    fn test_editorconfig_unit() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            dir.path().join(".editorconfig"),
+            "root = true\n\n[*]\nindent_style = space\nindent_size = 4\n\n[*.{ts,js}]\nindent_size = 2\n\n[Makefile]\nindent_style = tab\n",
+        )
+        .unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir(&src).unwrap();
+
+        assert_eq!(
+            editorconfig_unit(&src.join("app.py")).as_deref(),
+            Some("    ")
+        );
+        assert_eq!(
+            editorconfig_unit(&src.join("app.ts")).as_deref(),
+            Some("  ")
+        );
+        assert_eq!(
+            editorconfig_unit(&dir.path().join("Makefile")).as_deref(),
+            Some("\t")
+        );
+    }
+}
@@ -0,0 +1,255 @@
+/// Transformation journal (`.promptguard/journal.json`)
+///
+/// Every edit made by a transform is recorded with the text it replaced, so
+/// `revert` and `disable` can undo changes exactly even without backups or
+/// git. Entries are undone newest-first; a file whose current contents no
+/// longer match the journal is left untouched and reported as a conflict.
+/// Undoing some of a file's edits (one provider's, say) redoes the others on
+/// the original text.
+use crate::error::{PromptGuardError, Result};
+use crate::state::{project_state_dir, read_state, write_state};
+use crate::textfile;
+use crate::types::{Provider, TextEdit};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const JOURNAL_FILE_NAME: &str = "journal.json";
+
+const JOURNAL_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// File path relative to the project root
+    pub file: String,
+    /// Byte range of `replacement` in the file right after this edit
+    pub start: usize,
+    pub end: usize,
+    pub original: String,
+    pub replacement: String,
+    pub provider: Provider,
+    pub cli_version: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Journal {
+    pub version: u32,
+    pub entries: Vec<JournalEntry>,
+}
+
+impl Default for Journal {
+    fn default() -> Self {
+        Self {
+            version: JOURNAL_VERSION,
+            entries: Vec::new(),
+        }
+    }
+}
+
+/// Outcome of undoing journaled edits
+#[derive(Debug, Default)]
+pub struct JournalRevert {
+    /// Files with the selected edits undone
+    pub reverted: Vec<String>,
+    /// Files edited since the transform, or whose other edits overlap the
+    /// selected ones; their entries are kept
+    pub conflicts: Vec<String>,
+    /// Files recorded in the journal that no longer exist
+    pub missing: Vec<String>,
+}
+
+impl Journal {
+    pub fn path(root: &Path) -> PathBuf {
+        project_state_dir(root).join(JOURNAL_FILE_NAME)
+    }
+
+    /// Load the project journal, or an empty one if none was written yet
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = Self::path(root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(&read_state(&path)?).map_err(|e| {
+            PromptGuardError::Parse(format!("Invalid journal {}: {e}", path.display()))
+        })
+    }
+
+    /// Persist the journal. An empty journal removes the file.
+    pub fn save(&self, root: &Path) -> Result<()> {
+        let path = Self::path(root);
+        if self.entries.is_empty() {
+            if path.exists() {
+                fs::remove_file(&path)?;
+                // Only succeeds if nothing else (shims, caches) lives there
+                let _ = fs::remove_dir(project_state_dir(root));
+            }
+            return Ok(());
+        }
+        write_state(&path, &serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Record the edits one transform made to `file`
+    pub fn record(&mut self, root: &Path, file: &Path, provider: Provider, edits: &[TextEdit]) {
+        let file = relative(root, file);
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        self.entries.extend(edits.iter().map(|edit| JournalEntry {
+            file: file.clone(),
+            start: edit.start,
+            end: edit.end,
+            original: edit.original.clone(),
+            replacement: edit.replacement.clone(),
+            provider,
+            cli_version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: timestamp.clone(),
+        }));
+    }
+
+    /// Files with journaled edits, sorted
+    pub fn files(&self) -> Vec<String> {
+        let mut files: Vec<String> = self.entries.iter().map(|e| e.file.clone()).collect();
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// Drop the entries `select` picks for `files` (e.g. after restoring
+    /// them from backups)
+    pub fn forget(
+        &mut self,
+        root: &Path,
+        files: &[PathBuf],
+        select: impl Fn(&JournalEntry) -> bool,
+    ) {
+        let files: Vec<String> = files.iter().map(|f| relative(root, f)).collect();
+        self.entries
+            .retain(|e| !(files.contains(&e.file) && select(e)));
+    }
+
+    /// Undo the edits `select` picks. Entries for reverted and missing files
+    /// are removed; entries for conflicting files are kept. The edits of a
+    /// file that aren't selected are redone on its original text, and their
+    /// entries moved to where they land.
+    pub fn revert_where(
+        &mut self,
+        root: &Path,
+        select: impl Fn(&JournalEntry) -> bool,
+    ) -> Result<JournalRevert> {
+        let mut by_file: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (i, entry) in self.entries.iter().enumerate() {
+            by_file.entry(entry.file.clone()).or_default().push(i);
+        }
+
+        let mut report = JournalRevert::default();
+        let mut moved = Vec::new();
+        for (file, indices) in by_file {
+            let entries: Vec<&JournalEntry> = indices.iter().map(|&i| &self.entries[i]).collect();
+            let selected: Vec<bool> = entries.iter().map(|e| select(e)).collect();
+            if !selected.contains(&true) {
+                continue;
+            }
+            let path = root.join(&file);
+            let Ok((format, content)) = textfile::read(&path) else {
+                report.missing.push(file);
+                continue;
+            };
+            match undo_selected(content, &entries, &selected) {
+                Some((content, kept)) => {
+                    textfile::write(&path, format, &content)?;
+                    let unselected = indices.iter().zip(&selected).filter(|(_, s)| !**s);
+                    moved.extend(unselected.map(|(&i, _)| i).zip(kept));
+                    report.reverted.push(file);
+                },
+                None => report.conflicts.push(file),
+            }
+        }
+        for (i, (start, end)) in moved {
+            self.entries[i].start = start;
+            self.entries[i].end = end;
+        }
+
+        self.entries
+            .retain(|e| !select(e) || report.conflicts.contains(&e.file));
+        Ok(report)
+    }
+}
+
+/// Apply `entries` in reverse, or `None` if any replacement is no longer where it was written
+fn undo(mut content: String, entries: &[&JournalEntry]) -> Option<String> {
+    for entry in entries.iter().rev() {
+        if content.get(entry.start..entry.end)? != entry.replacement {
+            return None;
+        }
+        content.replace_range(entry.start..entry.end, &entry.original);
+    }
+    Some(content)
+}
+
+/// Byte range of an entry's replacement
+type Span = (usize, usize);
+
+/// Undo every entry, then redo the unselected ones in order, each shifted
+/// past the selected edits before it. Returns the contents and the new range
+/// of each unselected entry, or `None` if the file changed since or an
+/// unselected edit touches text a selected one wrote.
+fn undo_selected(
+    content: String,
+    entries: &[&JournalEntry],
+    selected: &[bool],
+) -> Option<(String, Vec<Span>)> {
+    let mut content = undo(content, entries)?;
+    // Regions the selected edits wrote, as they stand with every edit
+    // applied, with the length of the text they stand for once undone
+    let mut undone: Vec<(usize, usize, usize)> = Vec::new();
+    let mut kept = Vec::new();
+    for (entry, &select) in entries.iter().zip(selected) {
+        let (start, end) = (entry.start, entry.start + entry.original.len());
+        let overlaps = |&(s, e, _): &(usize, usize, usize)| {
+            (start < e && s < end) || (start == end && s < start && start < e)
+        };
+        let (touched, mut rest): (Vec<_>, Vec<_>) = undone.into_iter().partition(overlaps);
+        let shift = |at: usize| at - entry.original.len() + entry.replacement.len();
+        if !select {
+            if !touched.is_empty() {
+                return None;
+            }
+            let at = rest
+                .iter()
+                .filter(|r| r.1 <= start)
+                .fold(start, |at, &(s, e, original)| at - (e - s) + original);
+            if content.get(at..at + entry.original.len())? != entry.original {
+                return None;
+            }
+            content.replace_range(at..at + entry.original.len(), &entry.replacement);
+            kept.push((at, at + entry.replacement.len()));
+        }
+        for (s, e, _) in &mut rest {
+            if *s >= end {
+                (*s, *e) = (shift(*s), shift(*e));
+            }
+        }
+        if select {
+            // Selected edits that overlap are undone as one region
+            let lo = touched.iter().map(|r| r.0).fold(start, usize::min);
+            let hi = touched.iter().map(|r| r.1).fold(end, usize::max);
+            let len = touched
+                .iter()
+                .fold(hi - lo, |len, &(s, e, original)| len - (e - s) + original);
+            rest.push((lo, shift(hi), len));
+        }
+        undone = rest;
+    }
+    Some((content, kept))
+}
+
+fn relative(root: &Path, file: &Path) -> String {
+    file.strip_prefix(root)
+        .unwrap_or(file)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
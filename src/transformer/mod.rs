@@ -1,22 +1,123 @@
 mod core;
+pub mod diff;
+mod indent;
+pub mod journal;
 mod python;
+pub mod secrets;
+pub mod staging;
 mod typescript;
 
+pub use diff::unified_diff;
+pub use journal::{Journal, JournalEntry};
 pub use python::PythonTransformer;
+pub use staging::StagedChanges;
 pub use typescript::TypeScriptTransformer;
 
 use crate::error::Result;
-use crate::types::{Language, Provider, TransformResult};
+use crate::textfile::{self, TextFormat};
+use crate::types::{Language, Provider, StaleBaseUrl, TransformResult};
 use std::path::Path;
 
 pub trait Transformer {
+    /// Transformed source, or `None` when no changes are needed. Inserted
+    /// lines are indented with `indent_unit` when given (from
+    /// `.editorconfig`), otherwise with the unit the source already uses.
+    fn transform_source(
+        &self,
+        source: &str,
+        provider: Provider,
+        proxy_url: &str,
+        api_key_env_var: &str,
+        indent_unit: Option<&str>,
+    ) -> Result<Option<String>>;
+
+    /// Source with the arguments injected by `transform_source` removed, or
+    /// `None` when there is nothing to remove. Injected arguments are
+    /// recognized by their value (the proxy URL / API key env var).
+    fn untransform_source(
+        &self,
+        source: &str,
+        provider: Provider,
+        proxy_url: &str,
+        api_key_env_var: &str,
+    ) -> Result<Option<String>>;
+
+    /// 1-based lines of constructors configured from `**kwargs`, spread
+    /// options, or a variable without a local object literal. These are left
+    /// alone by `transform_source` because the options may already set a
+    /// base URL, or can't be edited in this file.
+    fn dynamic_config_lines(&self, source: &str, provider: Provider) -> Result<Vec<usize>>;
+
+    /// Literal base URLs passed to constructors that differ from `proxy_url`
+    fn stale_base_urls(
+        &self,
+        source: &str,
+        provider: Provider,
+        proxy_url: &str,
+    ) -> Result<Vec<StaleBaseUrl>>;
+
     fn transform_file(
         &self,
         file_path: &Path,
         provider: Provider,
         proxy_url: &str,
         api_key_env_var: &str,
-    ) -> Result<TransformResult>;
+    ) -> Result<TransformResult> {
+        let (format, source) = textfile::read(file_path)?;
+        let indent_unit = indent::editorconfig_unit(file_path);
+        let new_source = self.transform_source(
+            &source,
+            provider,
+            proxy_url,
+            api_key_env_var,
+            indent_unit.as_deref(),
+        )?;
+        write_result(file_path, format, &source, new_source)
+    }
+
+    fn untransform_file(
+        &self,
+        file_path: &Path,
+        provider: Provider,
+        proxy_url: &str,
+        api_key_env_var: &str,
+    ) -> Result<TransformResult> {
+        let (format, source) = textfile::read(file_path)?;
+        let new_source = self.untransform_source(&source, provider, proxy_url, api_key_env_var)?;
+        write_result(file_path, format, &source, new_source)
+    }
+}
+
+/// Write `new_source` in the file's original format. Edits are recorded
+/// against the decoded text, which is what the journal undoes.
+fn write_result(
+    file_path: &Path,
+    format: TextFormat,
+    source: &str,
+    new_source: Option<String>,
+) -> Result<TransformResult> {
+    match new_source {
+        Some(new_source) => {
+            textfile::write(file_path, format, &new_source)?;
+            Ok(TransformResult {
+                modified: true,
+                edits: diff::text_edits(source, &new_source),
+            })
+        },
+        None => Ok(TransformResult {
+            modified: false,
+            edits: Vec::new(),
+        }),
+    }
+}
+
+fn transformer_for(file_path: &Path) -> Option<Box<dyn Transformer>> {
+    let ext = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+
+    match Language::from_extension(ext)? {
+        Language::TypeScript | Language::JavaScript => Some(Box::new(TypeScriptTransformer::new())),
+        Language::Python => Some(Box::new(PythonTransformer::new())),
+    }
 }
 
 pub fn transform_file(
@@ -25,17 +126,126 @@ pub fn transform_file(
     proxy_url: &str,
     api_key_env_var: &str,
 ) -> Result<TransformResult> {
-    let ext = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let Some(transformer) = transformer_for(file_path) else {
+        return Ok(TransformResult {
+            modified: false,
+            edits: Vec::new(),
+        });
+    };
+
+    transformer.transform_file(file_path, provider, proxy_url, api_key_env_var)
+}
 
-    let language = Language::from_extension(ext);
-    let Some(language) = language else {
-        return Ok(TransformResult { modified: false });
+/// Remove the injected `base_url`/`baseURL`, leaving every other
+/// edit made since the transform in place
+pub fn untransform_file(
+    file_path: &Path,
+    provider: Provider,
+    proxy_url: &str,
+    api_key_env_var: &str,
+) -> Result<TransformResult> {
+    let Some(transformer) = transformer_for(file_path) else {
+        return Ok(TransformResult {
+            modified: false,
+            edits: Vec::new(),
+        });
     };
 
-    let transformer: Box<dyn Transformer> = match language {
-        Language::TypeScript | Language::JavaScript => Box::new(TypeScriptTransformer::new()),
-        Language::Python => Box::new(PythonTransformer::new()),
+    transformer.untransform_file(file_path, provider, proxy_url, api_key_env_var)
+}
+
+/// Lines in `file_path` where `provider`'s client is built from options the
+/// transform can't rewrite and needs runtime mode instead
+pub fn dynamic_config_lines(file_path: &Path, provider: Provider) -> Result<Vec<usize>> {
+    let Some(transformer) = transformer_for(file_path) else {
+        return Ok(Vec::new());
     };
 
-    transformer.transform_file(file_path, provider, proxy_url, api_key_env_var)
+    let (_, source) = textfile::read(file_path)?;
+    transformer.dynamic_config_lines(&source, provider)
+}
+
+/// `provider` base URLs in `file_path` that are string literals other than `proxy_url`
+pub fn stale_base_urls(
+    file_path: &Path,
+    provider: Provider,
+    proxy_url: &str,
+) -> Result<Vec<StaleBaseUrl>> {
+    let Some(transformer) = transformer_for(file_path) else {
+        return Ok(Vec::new());
+    };
+
+    let (_, source) = textfile::read(file_path)?;
+    transformer.stale_base_urls(&source, provider, proxy_url)
+}
+
+/// `source`, the possibly unsaved contents of `file_path`, routed through
+/// `proxy_url`: the transform applied and stale base URL literals pointed
+/// at the proxy. Returns `None` when it needs no changes.
+pub fn route_source(
+    file_path: &Path,
+    source: &str,
+    provider: Provider,
+    proxy_url: &str,
+    api_key_env_var: &str,
+) -> Result<Option<String>> {
+    let Some(transformer) = transformer_for(file_path) else {
+        return Ok(None);
+    };
+
+    let new_source = transformer.transform_source(
+        source,
+        provider,
+        proxy_url,
+        api_key_env_var,
+        indent::editorconfig_unit(file_path).as_deref(),
+    )?;
+    let current = new_source.as_deref().unwrap_or(source);
+    let stale = transformer.stale_base_urls(current, provider, proxy_url)?;
+    Ok(retarget_source(current, stale, proxy_url).or(new_source))
+}
+
+/// `source` with each stale literal replaced by `proxy_url`
+fn retarget_source(source: &str, mut stale: Vec<StaleBaseUrl>, proxy_url: &str) -> Option<String> {
+    if stale.is_empty() {
+        return None;
+    }
+    let mut new_source = source.to_string();
+    stale.sort_by_key(|s| std::cmp::Reverse(s.start));
+    for s in stale {
+        new_source.replace_range(s.start..s.end, &format!("\"{proxy_url}\""));
+    }
+    Some(new_source)
+}
+
+/// Unified diff of the changes `transform_file` would make, labelled with
+/// `display_path`. Returns `None` when the file needs no changes.
+pub fn preview_diff(
+    file_path: &Path,
+    display_path: &str,
+    provider: Provider,
+    proxy_url: &str,
+    api_key_env_var: &str,
+) -> Result<Option<String>> {
+    let Some(transformer) = transformer_for(file_path) else {
+        return Ok(None);
+    };
+
+    let (_, source) = textfile::read(file_path)?;
+    Ok(transformer
+        .transform_source(
+            &source,
+            provider,
+            proxy_url,
+            api_key_env_var,
+            indent::editorconfig_unit(file_path).as_deref(),
+        )?
+        .map(|new_source| {
+            unified_diff(
+                &source,
+                &new_source,
+                &format!("a/{display_path}"),
+                &format!("b/{display_path}"),
+            )
+        }))
 }
@@ -1,9 +1,15 @@
-use super::core::{transform_file_generic, TransformConfig};
-use crate::detector::get_python_transform_query;
+use super::core::{
+    collect_args, is_ignored_call, matching_lines, named_value, stale_literal, string_content,
+    transform_source_generic, without_item, TransformConfig,
+};
+use super::indent::{infer_unit, line_indent};
+use crate::detector::hierarchy::python_subclass_query;
+use crate::detector::{
+    get_python_transform_query, is_ignored, python_module_assignments, ProviderInfo,
+};
 use crate::transformer::Transformer;
-use crate::types::{Provider, TransformResult};
+use crate::types::{Provider, StaleBaseUrl};
 use std::fmt::Write;
-use std::path::Path;
 
 pub struct PythonTransformer;
 
@@ -24,13 +30,25 @@ fn has_base_url(source: &str, args_node: tree_sitter::Node) -> bool {
     args_text.contains("base_url=") || args_text.contains("base_url =")
 }
 
+/// Arguments unpacked from `*args`/`**kwargs` may already set `base_url`, so
+/// adding one could raise a duplicate keyword error at runtime
+fn has_splat(args_node: tree_sitter::Node) -> bool {
+    let mut cursor = args_node.walk();
+    let found = args_node
+        .named_children(&mut cursor)
+        .any(|n| matches!(n.kind(), "dictionary_splat" | "list_splat"));
+    found
+}
+
 fn transform_args(
     source: &str,
     args_node: tree_sitter::Node,
     proxy_url: &str,
     api_key_env_var: &str,
+    unit: &str,
 ) -> Option<String> {
-    if has_base_url(source, args_node) {
+    if has_base_url(source, args_node) || has_splat(args_node) || is_ignored_call(source, args_node)
+    {
         return None;
     }
 
@@ -39,31 +57,152 @@ fn transform_args(
         .trim_start_matches('(')
         .trim_end_matches(')')
         .trim();
+    let base = line_indent(source, args_node.start_byte());
 
     let mut new_args = String::from("(\n");
 
     if inner.is_empty() {
         let _ = writeln!(
             new_args,
-            "    api_key=os.environ.get(\"{api_key_env_var}\"),"
+            "{base}{unit}api_key=os.environ.get(\"{api_key_env_var}\"),"
         );
-        let _ = writeln!(new_args, "    base_url=\"{proxy_url}\"");
+        let _ = writeln!(new_args, "{base}{unit}base_url=\"{proxy_url}\"");
     } else {
         let trimmed = inner.trim();
-        new_args.push_str("    ");
+        new_args.push_str(base);
+        new_args.push_str(unit);
         new_args.push_str(trimmed);
         if !trimmed.ends_with(',') {
             new_args.push(',');
         }
         new_args.push('\n');
-        let _ = writeln!(new_args, "    base_url=\"{proxy_url}\"");
+        let _ = writeln!(new_args, "{base}{unit}base_url=\"{proxy_url}\"");
     }
 
+    new_args.push_str(base);
     new_args.push(')');
     Some(new_args)
 }
 
-fn ensure_os_import(source: String) -> String {
+/// Argument list with the injected `base_url` removed, plus the
+/// `api_key` it added when the call originally had no arguments
+fn untransform_args(
+    source: &str,
+    args_node: tree_sitter::Node,
+    proxy_url: &str,
+    api_key_env_var: &str,
+) -> Option<String> {
+    let mut cursor = args_node.walk();
+    let args: Vec<tree_sitter::Node> = args_node
+        .named_children(&mut cursor)
+        .filter(|n| n.kind() != "comment")
+        .collect();
+
+    let index = args.iter().position(|arg| {
+        named_value(source, *arg, "keyword_argument", "name", "base_url")
+            .and_then(|v| string_content(source, v))
+            == Some(proxy_url)
+    })?;
+
+    let injected_key = format!("os.environ.get(\"{api_key_env_var}\")");
+    let only_injected_key = args.len() == 2
+        && named_value(
+            source,
+            args[1 - index],
+            "keyword_argument",
+            "name",
+            "api_key",
+        )
+        .is_some_and(|v| source[v.start_byte()..v.end_byte()] == injected_key);
+    if args.len() == 1 || only_injected_key {
+        return Some("()".to_string());
+    }
+
+    Some(without_item(source, args_node, &args, index))
+}
+
+/// Point module-level configuration (`openai.base_url = ...`) at the proxy.
+/// String-literal base URL assignments are rewritten; with none at all, a
+/// base URL assignment is added after the first API key assignment.
+fn transform_module_config(
+    source: &str,
+    provider: Provider,
+    proxy_url: &str,
+) -> crate::error::Result<Option<String>> {
+    let assignments = python_module_assignments(source, provider)?;
+    let live = |row: usize| !is_ignored(source, row);
+    let replacement = format!("\"{proxy_url}\"");
+
+    let mut edits: Vec<(usize, usize, String)> = Vec::new();
+    if assignments.iter().any(|a| a.is_base_url) {
+        edits.extend(
+            assignments
+                .iter()
+                .filter(|a| a.is_base_url && live(a.row))
+                .filter(|a| a.literal.as_deref().is_some_and(|url| url != proxy_url))
+                .map(|a| (a.value.0, a.value.1, replacement.clone())),
+        );
+    } else if let Some(key) = assignments.iter().find(|a| live(a.row)) {
+        let line_start = source[..key.statement.0].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[key.statement.1..]
+            .find('\n')
+            .map_or(source.len(), |i| key.statement.1 + i);
+        let indent = &source[line_start..key.statement.0];
+        let rest = source[key.statement.1..line_end].trim();
+        // Only when the statement has the line to itself
+        if indent.trim().is_empty() && (rest.is_empty() || rest.starts_with('#')) {
+            let attr = ProviderInfo::get(provider).py_module_base_url_attrs[0];
+            edits.push((
+                line_end,
+                line_end,
+                format!("\n{indent}{attr} = {replacement}"),
+            ));
+        }
+    }
+    if edits.is_empty() {
+        return Ok(None);
+    }
+
+    let mut new_source = source.to_string();
+    edits.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+    for (start, end, text) in edits {
+        new_source.replace_range(start..end, &text);
+    }
+    Ok(Some(new_source))
+}
+
+/// Remove module-level base URL assignments that point at the proxy
+fn untransform_module_config(
+    source: &str,
+    provider: Provider,
+    proxy_url: &str,
+) -> crate::error::Result<Option<String>> {
+    let mut lines: Vec<(usize, usize)> = python_module_assignments(source, provider)?
+        .iter()
+        .filter(|a| a.is_base_url && a.literal.as_deref() == Some(proxy_url))
+        .filter_map(|a| {
+            let line_start = source[..a.statement.0].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = source[a.statement.1..]
+                .find('\n')
+                .map_or(source.len(), |i| a.statement.1 + i + 1);
+            (source[line_start..a.statement.0].trim().is_empty()
+                && source[a.statement.1..line_end].trim().is_empty())
+            .then_some((line_start, line_end))
+        })
+        .collect();
+    if lines.is_empty() {
+        return Ok(None);
+    }
+
+    let mut new_source = source.to_string();
+    lines.sort_by_key(|(start, _)| std::cmp::Reverse(*start));
+    for (start, end) in lines {
+        new_source.replace_range(start..end, "");
+    }
+    Ok(Some(new_source))
+}
+
+pub(super) fn ensure_os_import(source: String) -> String {
     if source.contains("import os") {
         return source;
     }
@@ -71,28 +210,114 @@ fn ensure_os_import(source: String) -> String {
 }
 
 impl Transformer for PythonTransformer {
-    fn transform_file(
+    fn transform_source(
         &self,
-        file_path: &Path,
+        source: &str,
         provider: Provider,
         proxy_url: &str,
         api_key_env_var: &str,
-    ) -> crate::error::Result<TransformResult> {
+        indent_unit: Option<&str>,
+    ) -> crate::error::Result<Option<String>> {
+        let unit = indent_unit
+            .map(str::to_string)
+            .or_else(|| infer_unit(source))
+            .unwrap_or_else(|| "    ".to_string());
         let config = TransformConfig {
             parser_language: tree_sitter_python::LANGUAGE.into(),
             language_name: "Python",
+            provider,
+            subclass_query: python_subclass_query,
         };
         let query_str = get_python_transform_query(provider);
 
-        transform_file_generic(
-            file_path,
+        let transformed = transform_source_generic(
+            source,
             &config,
             &query_str,
             |source, args_node| {
-                transform_args(source, args_node, proxy_url, api_key_env_var)
+                transform_args(source, args_node, proxy_url, api_key_env_var, &unit)
                     .map(|new_args| (args_node.start_byte(), args_node.end_byte(), new_args))
             },
             ensure_os_import,
-        )
+        )?;
+
+        let current = transformed.as_deref().unwrap_or(source);
+        Ok(transform_module_config(current, provider, proxy_url)?.or(transformed))
+    }
+
+    fn untransform_source(
+        &self,
+        source: &str,
+        provider: Provider,
+        proxy_url: &str,
+        api_key_env_var: &str,
+    ) -> crate::error::Result<Option<String>> {
+        let config = TransformConfig {
+            parser_language: tree_sitter_python::LANGUAGE.into(),
+            language_name: "Python",
+            provider,
+            subclass_query: python_subclass_query,
+        };
+        let query_str = get_python_transform_query(provider);
+
+        let untransformed = transform_source_generic(
+            source,
+            &config,
+            &query_str,
+            |source, args_node| {
+                untransform_args(source, args_node, proxy_url, api_key_env_var)
+                    .map(|new_args| (args_node.start_byte(), args_node.end_byte(), new_args))
+            },
+            |s| s,
+        )?;
+
+        let current = untransformed.as_deref().unwrap_or(source);
+        Ok(untransform_module_config(current, provider, proxy_url)?.or(untransformed))
+    }
+
+    fn dynamic_config_lines(
+        &self,
+        source: &str,
+        provider: Provider,
+    ) -> crate::error::Result<Vec<usize>> {
+        let config = TransformConfig {
+            parser_language: tree_sitter_python::LANGUAGE.into(),
+            language_name: "Python",
+            provider,
+            subclass_query: python_subclass_query,
+        };
+        let query_str = get_python_transform_query(provider);
+
+        matching_lines(source, &config, &query_str, |source, args_node| {
+            has_splat(args_node)
+                && !has_base_url(source, args_node)
+                && !is_ignored_call(source, args_node)
+        })
+    }
+
+    fn stale_base_urls(
+        &self,
+        source: &str,
+        provider: Provider,
+        proxy_url: &str,
+    ) -> crate::error::Result<Vec<StaleBaseUrl>> {
+        let config = TransformConfig {
+            parser_language: tree_sitter_python::LANGUAGE.into(),
+            language_name: "Python",
+            provider,
+            subclass_query: python_subclass_query,
+        };
+        let query_str = get_python_transform_query(provider);
+
+        collect_args(source, &config, &query_str, |source, args_node| {
+            if is_ignored_call(source, args_node) {
+                return None;
+            }
+            let mut cursor = args_node.walk();
+            let value = args_node
+                .named_children(&mut cursor)
+                .find_map(|arg| named_value(source, arg, "keyword_argument", "name", "base_url"))?;
+            stale_literal(source, value, proxy_url)
+        })
     }
 }
@@ -0,0 +1,109 @@
+/// Moving hardcoded keys out of source
+///
+/// A string literal that holds nothing but a key is replaced with a read of
+/// the environment variable the key moves to: `os.environ["OPENAI_API_KEY"]`
+/// in Python, `process.env.OPENAI_API_KEY` in JavaScript and TypeScript.
+/// Keys inside longer strings, comments or prefixed Python strings are left
+/// for the user to move by hand.
+use super::python::ensure_os_import;
+use crate::analyzer::secrets::SecretMatch;
+use crate::types::Language;
+
+const QUOTES: [char; 3] = ['"', '\'', '`'];
+
+/// Code reading `var` from the environment
+pub fn env_read(language: Language, var: &str) -> String {
+    match language {
+        Language::Python => format!("os.environ[\"{var}\"]"),
+        Language::TypeScript | Language::JavaScript => format!("process.env.{var}"),
+    }
+}
+
+/// `source` with the literal of each `(secret, env var)` in `moves` replaced
+/// by an env read, and the indexes of the moves that were made
+pub fn move_to_env(
+    source: &str,
+    language: Language,
+    moves: &[(SecretMatch, String)],
+) -> (String, Vec<usize>) {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+
+    let mut spans: Vec<(usize, usize, usize)> = Vec::new();
+    for (index, (secret, _)) in moves.iter().enumerate() {
+        let Some(&line_start) = line_starts.get(secret.line - 1) else {
+            continue;
+        };
+        let start = line_start + secret.column - 1;
+        let end = start + secret.value.len();
+        if source.get(start..end) != Some(secret.value.as_str()) {
+            continue;
+        }
+        let (Some(open), Some(close)) = (
+            source[..start].chars().next_back(),
+            source[end..].chars().next(),
+        ) else {
+            continue;
+        };
+        if open != close || !QUOTES.contains(&open) {
+            continue;
+        }
+        // `f"..."`, `b'...'` and friends would keep their prefix
+        if source[..start - 1]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_')
+        {
+            continue;
+        }
+        spans.push((start - 1, end + 1, index));
+    }
+
+    spans.sort_by_key(|&(start, _, _)| std::cmp::Reverse(start));
+    let mut new_source = source.to_string();
+    let mut made = Vec::new();
+    for (start, end, index) in spans {
+        new_source.replace_range(start..end, &env_read(language, &moves[index].1));
+        made.push(index);
+    }
+    made.sort_unstable();
+
+    if language == Language::Python && !made.is_empty() {
+        new_source = ensure_os_import(new_source);
+    }
+    (new_source, made)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::find_secrets;
+
+    #[test]
+    fn test_move_to_env() {
+        let source = "from openai import OpenAI\n\
+                      client = OpenAI(api_key=\"sk-proj-abcdefghijklmnopqrstuvwx\")\n\
+                      header = \"Bearer sk-proj-abcdefghijklmnopqrstuvwx\"\n";
+        let moves: Vec<(SecretMatch, String)> = find_secrets(source)
+            .into_iter()
+            .map(|s| (s, "OPENAI_API_KEY".to_string()))
+            .collect();
+        assert_eq!(moves.len(), 2);
+
+        let (python, made) = move_to_env(source, Language::Python, &moves);
+        assert_eq!(made, vec![0]);
+        assert!(python.starts_with("import os\n\n"));
+        assert!(python.contains("OpenAI(api_key=os.environ[\"OPENAI_API_KEY\"])"));
+        assert!(python.contains("\"Bearer sk-proj-"));
+
+        let source = "const client = new OpenAI({ apiKey: 'sk-proj-abcdefghijklmnopqrstuvwx' });\n";
+        let moves = vec![(find_secrets(source).remove(0), "OPENAI_API_KEY".to_string())];
+        let (typescript, made) = move_to_env(source, Language::TypeScript, &moves);
+        assert_eq!(made, vec![0]);
+        assert_eq!(
+            typescript,
+            "const client = new OpenAI({ apiKey: process.env.OPENAI_API_KEY });\n"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+/// Multi-file transforms that are written as a unit
+///
+/// Every transform is computed in memory first. Nothing touches the disk
+/// until `commit`, which writes the changed files one by one and, if any
+/// write fails, puts back the files it already wrote before returning the
+/// error, so an interrupted `apply` never leaves half the project transformed.
+use super::{diff, indent, retarget_source, transformer_for};
+use crate::error::{PromptGuardError, Result};
+use crate::textfile::{self, TextFormat};
+use crate::types::{Provider, StaleBaseUrl, TextEdit};
+use std::collections::btree_map::{BTreeMap, Entry};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+struct StagedFile {
+    /// Bytes on disk when first staged, restored on rollback; `None` for a
+    /// file the commit creates
+    raw: Option<Vec<u8>>,
+    format: TextFormat,
+    current: String,
+    edits: Vec<(Provider, Vec<TextEdit>)>,
+    changed: bool,
+}
+
+/// Edits a committed file received, grouped by the provider transform that made them
+pub type CommittedEdits = Vec<(PathBuf, Provider, Vec<TextEdit>)>;
+
+#[derive(Default)]
+pub struct StagedChanges {
+    files: BTreeMap<PathBuf, StagedFile>,
+}
+
+impl StagedChanges {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stage `provider`'s transform of `file_path`. Returns whether it changed anything.
+    pub fn transform(
+        &mut self,
+        file_path: &Path,
+        provider: Provider,
+        proxy_url: &str,
+        api_key_env_var: &str,
+    ) -> Result<bool> {
+        let Some(transformer) = transformer_for(file_path) else {
+            return Ok(false);
+        };
+        let indent_unit = indent::editorconfig_unit(file_path);
+        let file = self.stage(file_path)?;
+        let new_source = transformer.transform_source(
+            &file.current,
+            provider,
+            proxy_url,
+            api_key_env_var,
+            indent_unit.as_deref(),
+        )?;
+        Ok(file.update(provider, new_source))
+    }
+
+    /// Stale base URLs in the staged contents of `file_path`
+    pub fn stale_base_urls(
+        &mut self,
+        file_path: &Path,
+        provider: Provider,
+        proxy_url: &str,
+    ) -> Result<Vec<StaleBaseUrl>> {
+        let Some(transformer) = transformer_for(file_path) else {
+            return Ok(Vec::new());
+        };
+        let file = self.stage(file_path)?;
+        transformer.stale_base_urls(&file.current, provider, proxy_url)
+    }
+
+    /// Stage pointing every stale base URL in `file_path` at `proxy_url`
+    pub fn retarget(
+        &mut self,
+        file_path: &Path,
+        provider: Provider,
+        proxy_url: &str,
+    ) -> Result<bool> {
+        let stale = self.stale_base_urls(file_path, provider, proxy_url)?;
+        let file = self.stage(file_path)?;
+        let new_source = retarget_source(&file.current, stale, proxy_url);
+        Ok(file.update(provider, new_source))
+    }
+
+    /// Stage new contents for `file_path` that don't come from a provider
+    /// transform, such as an env file gaining a key. A file that doesn't
+    /// exist yet is created by `commit` and removed again on rollback.
+    pub fn replace(&mut self, file_path: &Path, new_source: String) -> Result<bool> {
+        let file = if file_path.exists() {
+            self.stage(file_path)?
+        } else {
+            self.files
+                .entry(file_path.to_path_buf())
+                .or_insert_with(|| StagedFile {
+                    raw: None,
+                    format: TextFormat::default(),
+                    current: String::new(),
+                    edits: Vec::new(),
+                    changed: false,
+                })
+        };
+        if file.current == new_source {
+            return Ok(false);
+        }
+        file.current = new_source;
+        file.changed = true;
+        Ok(true)
+    }
+
+    /// Files whose staged contents differ from disk, sorted
+    pub fn changed_files(&self) -> Vec<&Path> {
+        self.files
+            .iter()
+            .filter(|(_, f)| f.changed)
+            .map(|(path, _)| path.as_path())
+            .collect()
+    }
+
+    /// Write every changed file. On failure, files already written are
+    /// restored and the error names the file that could not be written.
+    pub fn commit(self) -> Result<CommittedEdits> {
+        let mut written: Vec<(&Path, &StagedFile)> = Vec::new();
+        for (path, file) in self.files.iter().filter(|(_, f)| f.changed) {
+            if let Err(e) = textfile::write(path, file.format, &file.current) {
+                let mut unrestored = Vec::new();
+                for (path, file) in written.iter().rev() {
+                    let restored = match &file.raw {
+                        Some(raw) => textfile::write_atomic(path, raw),
+                        None => fs::remove_file(path).map_err(PromptGuardError::Io),
+                    };
+                    if restored.is_err() {
+                        unrestored.push(path.display().to_string());
+                    }
+                }
+                let outcome = if unrestored.is_empty() {
+                    "No files were changed".to_string()
+                } else {
+                    format!("Could not restore: {}", unrestored.join(", "))
+                };
+                return Err(PromptGuardError::Custom(format!(
+                    "Failed to write {}: {e}. {outcome}",
+                    path.display()
+                )));
+            }
+            written.push((path, file));
+        }
+
+        Ok(self
+            .files
+            .into_iter()
+            .flat_map(|(path, file)| {
+                file.edits
+                    .into_iter()
+                    .map(move |(provider, edits)| (path.clone(), provider, edits))
+            })
+            .collect())
+    }
+
+    fn stage(&mut self, file_path: &Path) -> Result<&mut StagedFile> {
+        match self.files.entry(file_path.to_path_buf()) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let raw = fs::read(file_path)?;
+                let (format, current) = TextFormat::decode(&raw)?;
+                Ok(entry.insert(StagedFile {
+                    raw: Some(raw),
+                    format,
+                    current,
+                    edits: Vec::new(),
+                    changed: false,
+                }))
+            },
+        }
+    }
+}
+
+impl StagedFile {
+    fn update(&mut self, provider: Provider, new_source: Option<String>) -> bool {
+        let Some(new_source) = new_source else {
+            return false;
+        };
+        self.edits
+            .push((provider, diff::text_edits(&self.current, &new_source)));
+        self.current = new_source;
+        self.changed = true;
+        true
+    }
+}
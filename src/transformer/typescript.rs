@@ -1,9 +1,13 @@
-use super::core::{transform_file_generic, TransformConfig};
-use crate::detector::{get_typescript_query, ProviderInfo};
+use super::core::{
+    collect_args, is_ignored_call, matching_lines, named_value, stale_literal, string_content,
+    transform_source_generic, without_item, TransformConfig,
+};
+use super::indent::{infer_unit, line_indent};
+use crate::detector::hierarchy::typescript_subclass_query;
+use crate::detector::{get_typescript_query, ts_options_object, ProviderInfo};
 use crate::transformer::Transformer;
-use crate::types::{Provider, TransformResult};
+use crate::types::{Provider, StaleBaseUrl};
 use std::fmt::Write;
-use std::path::Path;
 
 pub struct TypeScriptTransformer;
 
@@ -28,14 +32,25 @@ fn ts_has_base_url(source: &str, object_node: tree_sitter::Node, provider: Provi
         || object_text.contains("base_url:")
 }
 
+/// Options spread in from another object (`{...config}`, `...args`) may
+/// already set the base URL, so the call can't be rewritten safely
+fn ts_has_spread(node: tree_sitter::Node) -> bool {
+    let mut cursor = node.walk();
+    let found = node
+        .named_children(&mut cursor)
+        .any(|n| n.kind() == "spread_element" || (n.kind() == "object" && ts_has_spread(n)));
+    found
+}
+
 fn transform_ts_object(
     source: &str,
     object_node: tree_sitter::Node,
     provider: Provider,
     proxy_url: &str,
     api_key_env_var: &str,
+    unit: &str,
 ) -> Option<String> {
-    if ts_has_base_url(source, object_node, provider) {
+    if ts_has_base_url(source, object_node, provider) || ts_has_spread(object_node) {
         return None;
     }
 
@@ -45,66 +60,201 @@ fn transform_ts_object(
         .trim_start_matches('{')
         .trim_end_matches('}')
         .trim();
+    let base = line_indent(source, object_node.start_byte());
 
     let mut new_object = String::from("{\n");
 
     if inner.is_empty() {
         let _ = writeln!(
             new_object,
-            "  {}: process.env.{api_key_env_var},",
+            "{base}{unit}{}: process.env.{api_key_env_var},",
             info.ts_api_key_param
         );
-        let _ = writeln!(new_object, "  {}: \"{proxy_url}\"", info.ts_base_url_param);
+        let _ = writeln!(
+            new_object,
+            "{base}{unit}{}: \"{proxy_url}\"",
+            info.ts_base_url_param
+        );
     } else {
         let trimmed = inner.trim();
-        new_object.push_str("  ");
+        new_object.push_str(base);
+        new_object.push_str(unit);
         new_object.push_str(trimmed);
         if !trimmed.ends_with(',') {
             new_object.push(',');
         }
         new_object.push('\n');
-        let _ = writeln!(new_object, "  {}: \"{proxy_url}\"", info.ts_base_url_param);
+        let _ = writeln!(
+            new_object,
+            "{base}{unit}{}: \"{proxy_url}\"",
+            info.ts_base_url_param
+        );
     }
 
+    new_object.push_str(base);
     new_object.push('}');
     Some(new_object)
 }
 
+/// Object literal with the injected base URL property removed,
+/// plus the API key property it added to an originally empty object
+fn untransform_ts_object(
+    source: &str,
+    object_node: tree_sitter::Node,
+    provider: Provider,
+    proxy_url: &str,
+    api_key_env_var: &str,
+) -> Option<String> {
+    let info = ProviderInfo::get(provider);
+    let mut cursor = object_node.walk();
+    let pairs: Vec<tree_sitter::Node> = object_node
+        .named_children(&mut cursor)
+        .filter(|n| n.kind() != "comment")
+        .collect();
+
+    let index = pairs.iter().position(|pair| {
+        named_value(source, *pair, "pair", "key", info.ts_base_url_param)
+            .and_then(|v| string_content(source, v))
+            == Some(proxy_url)
+    })?;
+
+    let injected_key = format!("process.env.{api_key_env_var}");
+    let only_injected_key = pairs.len() == 2
+        && named_value(
+            source,
+            pairs[1 - index],
+            "pair",
+            "key",
+            info.ts_api_key_param,
+        )
+        .is_some_and(|v| source[v.start_byte()..v.end_byte()] == injected_key);
+    if pairs.len() == 1 || only_injected_key {
+        return Some("{}".to_string());
+    }
+
+    Some(without_item(source, object_node, &pairs, index))
+}
+
 impl Transformer for TypeScriptTransformer {
-    fn transform_file(
+    fn transform_source(
         &self,
-        file_path: &Path,
+        source: &str,
         provider: Provider,
         proxy_url: &str,
         api_key_env_var: &str,
-    ) -> crate::error::Result<TransformResult> {
+        indent_unit: Option<&str>,
+    ) -> crate::error::Result<Option<String>> {
+        let unit = indent_unit
+            .map(str::to_string)
+            .or_else(|| infer_unit(source))
+            .unwrap_or_else(|| "  ".to_string());
         let config = TransformConfig {
             parser_language: tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
             language_name: "TypeScript",
+            provider,
+            subclass_query: typescript_subclass_query,
         };
         let query_str = get_typescript_query(provider);
 
-        transform_file_generic(
-            file_path,
+        transform_source_generic(
+            source,
             &config,
             &query_str,
             |source, args_node| {
-                let mut cursor = args_node.walk();
-                for child in args_node.children(&mut cursor) {
-                    if child.kind() == "object" {
-                        return transform_ts_object(
-                            source,
-                            child,
-                            provider,
-                            proxy_url,
-                            api_key_env_var,
-                        )
-                        .map(|new_obj| (child.start_byte(), child.end_byte(), new_obj));
-                    }
+                if is_ignored_call(source, args_node) {
+                    return None;
                 }
-                None
+                let object = ts_options_object(source, args_node)?;
+                transform_ts_object(source, object, provider, proxy_url, api_key_env_var, &unit)
+                    .map(|new_obj| (object.start_byte(), object.end_byte(), new_obj))
             },
             |s| s,
         )
     }
+
+    fn untransform_source(
+        &self,
+        source: &str,
+        provider: Provider,
+        proxy_url: &str,
+        api_key_env_var: &str,
+    ) -> crate::error::Result<Option<String>> {
+        let config = TransformConfig {
+            parser_language: tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            language_name: "TypeScript",
+            provider,
+            subclass_query: typescript_subclass_query,
+        };
+        let query_str = get_typescript_query(provider);
+
+        transform_source_generic(
+            source,
+            &config,
+            &query_str,
+            |source, args_node| {
+                let object = ts_options_object(source, args_node)?;
+                untransform_ts_object(source, object, provider, proxy_url, api_key_env_var)
+                    .map(|new_obj| (object.start_byte(), object.end_byte(), new_obj))
+            },
+            |s| s,
+        )
+    }
+
+    fn dynamic_config_lines(
+        &self,
+        source: &str,
+        provider: Provider,
+    ) -> crate::error::Result<Vec<usize>> {
+        let config = TransformConfig {
+            parser_language: tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            language_name: "TypeScript",
+            provider,
+            subclass_query: typescript_subclass_query,
+        };
+        let query_str = get_typescript_query(provider);
+
+        matching_lines(source, &config, &query_str, |source, args_node| {
+            let object = ts_options_object(source, args_node);
+            let configured = object.is_some_and(|o| ts_has_base_url(source, o, provider));
+            let mut cursor = args_node.walk();
+            let has_args = args_node
+                .named_children(&mut cursor)
+                .any(|c| c.kind() != "comment");
+            // Options from an unresolvable expression or spread in from elsewhere
+            let dynamic = ts_has_spread(args_node) || object.map_or(has_args, ts_has_spread);
+            dynamic && !configured && !is_ignored_call(source, args_node)
+        })
+    }
+
+    fn stale_base_urls(
+        &self,
+        source: &str,
+        provider: Provider,
+        proxy_url: &str,
+    ) -> crate::error::Result<Vec<StaleBaseUrl>> {
+        let config = TransformConfig {
+            parser_language: tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            language_name: "TypeScript",
+            provider,
+            subclass_query: typescript_subclass_query,
+        };
+        let query_str = get_typescript_query(provider);
+        let param = ProviderInfo::get(provider).ts_base_url_param;
+
+        let mut stale = collect_args(source, &config, &query_str, |source, args_node| {
+            if is_ignored_call(source, args_node) {
+                return None;
+            }
+            let object = ts_options_object(source, args_node)?;
+            let mut cursor = object.walk();
+            let value = object
+                .named_children(&mut cursor)
+                .find_map(|pair| named_value(source, pair, "pair", "key", param))?;
+            stale_literal(source, value, proxy_url)
+        })?;
+        // Calls sharing one options object report it once
+        stale.sort_by_key(|s| s.start);
+        stale.dedup_by_key(|s| s.start);
+        Ok(stale)
+    }
 }
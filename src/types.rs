@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Ordered by registry position, so sorted output lists providers consistently
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Provider {
     OpenAI,
@@ -62,7 +63,7 @@ pub struct DetectionInstance {
     pub current_base_url: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Language {
     TypeScript,
     JavaScript,
@@ -91,6 +92,8 @@ impl Language {
 #[derive(Debug, Clone)]
 pub struct DetectionResult {
     pub instances: Vec<DetectionInstance>,
+    /// Call sites suppressed with an inline `promptguard: ignore` directive
+    pub ignored: Vec<DetectionInstance>,
 }
 
 impl Default for DetectionResult {
@@ -103,6 +106,7 @@ impl DetectionResult {
     pub fn new() -> Self {
         Self {
             instances: Vec::new(),
+            ignored: Vec::new(),
         }
     }
 }
@@ -111,4 +115,28 @@ impl DetectionResult {
 #[derive(Debug, Clone)]
 pub struct TransformResult {
     pub modified: bool,
+    /// Edits made to the file, in ascending order of position
+    pub edits: Vec<TextEdit>,
+}
+
+/// A single replaced span. `start..end` is the byte range of `replacement`
+/// in the file after the edit; `original` is the text it replaced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub start: usize,
+    pub end: usize,
+    pub original: String,
+    pub replacement: String,
+}
+
+/// A string-literal base URL that isn't the configured proxy URL, e.g. one
+/// injected before the proxy URL was changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleBaseUrl {
+    /// 1-based line of the literal
+    pub line: usize,
+    pub url: String,
+    /// Byte range of the literal, quotes included
+    pub start: usize,
+    pub end: usize,
 }
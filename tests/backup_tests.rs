@@ -0,0 +1,39 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+/// Integration tests for source file backups
+///
+/// These tests verify that snapshots are taken once per content, found by
+/// path or id, restored, and pruned.
+use std::fs;
+use tempfile::TempDir;
+
+use promptguard::backup::BackupStore;
+
+#[test]
+fn test_snapshot_and_restore() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("src/app.py");
+    fs::create_dir_all(file.parent().unwrap()).unwrap();
+    fs::write(&file, "v1\n").unwrap();
+
+    let store = BackupStore::new(dir.path());
+    let taken = store
+        .snapshot(std::slice::from_ref(&file), "apply")
+        .unwrap();
+    assert_eq!(taken.len(), 1);
+    assert_eq!(taken[0].file, "src/app.py");
+    // Unchanged files aren't copied twice
+    assert!(store
+        .snapshot(std::slice::from_ref(&file), "apply")
+        .unwrap()
+        .is_empty());
+
+    fs::write(&file, "v2\n").unwrap();
+    let manifest = store.manifest().unwrap();
+    let entry = manifest.find("src/app.py").unwrap();
+    assert_eq!(manifest.find(&entry.id), Some(entry));
+    store.restore(entry).unwrap();
+    assert_eq!(fs::read_to_string(&file).unwrap(), "v1\n");
+
+    assert_eq!(store.prune(|_| true).unwrap().len(), 1);
+    assert!(!store.dir().exists());
+}
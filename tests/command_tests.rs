@@ -12,10 +12,12 @@ use std::fs;
 use tempfile::TempDir;
 
 // Import from the main crate
+use promptguard::config::user::UserConfig;
 use promptguard::config::{ConfigManager, PromptGuardConfig};
-use promptguard::detector::detect_all_providers;
-use promptguard::scanner::FileScanner;
-use promptguard::transformer;
+use promptguard::detector::{detect_all_providers, detect_declared_providers, ProviderInfo};
+use promptguard::scanner::workspace::WorkspaceKind;
+use promptguard::scanner::{FileScanner, Workspace};
+use promptguard::transformer::{self, Journal, StagedChanges};
 use promptguard::types::Provider;
 
 /// Helper to find a provider in detection results
@@ -230,6 +232,53 @@ fn test_scanner_excludes_patterns() {
     assert!(!has_venv, "Should exclude .venv");
 }
 
+/// Test that include patterns limit scanning to matching files and directories
+#[test]
+fn test_scanner_include_patterns() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let agents = temp_dir.path().join("src").join("agents");
+    fs::create_dir_all(&agents).expect("Failed to create dir");
+    fs::write(agents.join("planner.py"), "x = 1").expect("Failed to write");
+    fs::write(temp_dir.path().join("src").join("app.py"), "x = 1").expect("Failed to write");
+    fs::write(temp_dir.path().join("main.ts"), "x").expect("Failed to write");
+
+    for patterns in [
+        vec!["src/agents/**".to_string()],
+        vec!["./src/agents/".to_string()],
+    ] {
+        let files = FileScanner::new(temp_dir.path(), None)
+            .expect("Failed to create scanner")
+            .with_include_patterns(&patterns)
+            .expect("Valid globs")
+            .scan_files(None)
+            .expect("Failed to scan");
+        assert_eq!(files, vec![agents.join("planner.py")]);
+    }
+
+    let files = FileScanner::new(temp_dir.path(), None)
+        .expect("Failed to create scanner")
+        .with_include_patterns(&["*.ts".to_string(), "src/*.py".to_string()])
+        .expect("Valid globs")
+        .scan_files(None)
+        .expect("Failed to scan");
+    assert_eq!(files.len(), 2);
+}
+
+/// Test that repeated scans return files in the same order
+#[test]
+fn test_scanner_order_is_stable() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    for name in ["b.py", "a.py", "c.ts", "d.js"] {
+        fs::write(temp_dir.path().join(name), "x = 1").expect("Failed to write");
+    }
+
+    let scanner = FileScanner::new(temp_dir.path(), None).expect("Failed to create scanner");
+    let first = scanner.scan_files(None).expect("Failed to scan");
+    for _ in 0..5 {
+        assert_eq!(scanner.scan_files(None).expect("Failed to scan"), first);
+    }
+}
+
 /// Test that scanner finds Python and TypeScript files
 #[test]
 fn test_scanner_finds_supported_files() {
@@ -265,10 +314,628 @@ fn test_scanner_finds_supported_files() {
     );
 }
 
+/// Test that npm workspaces are discovered and files map to their package
+#[test]
+fn test_workspace_discovers_npm_packages() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+
+    fs::write(
+        root.join("package.json"),
+        r#"{"name": "mono", "workspaces": ["packages/*", "!packages/ignored"]}"#,
+    )
+    .expect("Failed to write");
+    for (dir, name) in [("api", "@mono/api"), ("web", "@mono/web"), ("ignored", "x")] {
+        let pkg = root.join("packages").join(dir);
+        fs::create_dir_all(&pkg).expect("Failed to create dir");
+        fs::write(pkg.join("package.json"), format!(r#"{{"name": "{name}"}}"#))
+            .expect("Failed to write");
+    }
+
+    let workspace = Workspace::discover(root).expect("Workspace should be detected");
+    let names: Vec<&str> = workspace.packages.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["@mono/api", "@mono/web"]);
+    assert_eq!(workspace.kinds, vec![WorkspaceKind::Npm]);
+
+    let file = root.join("packages/web/src/chat.ts");
+    let owner = workspace
+        .package_for(&file)
+        .expect("File should belong to a package");
+    assert_eq!(owner.name, "@mono/web");
+    assert!(workspace
+        .package_for(&root.join("scripts/seed.py"))
+        .is_none());
+}
+
+/// Test that pnpm workspaces and Python monorepos are discovered together
+#[test]
+fn test_workspace_discovers_pnpm_and_python_packages() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+
+    fs::write(
+        root.join("pnpm-workspace.yaml"),
+        "packages:\n  - 'apps/*'\n",
+    )
+    .expect("Failed to write");
+    fs::create_dir_all(root.join("apps/site")).expect("Failed to create dir");
+    fs::write(root.join("apps/site/package.json"), "{}").expect("Failed to write");
+
+    for dir in ["services/ingest", "services/agent"] {
+        fs::create_dir_all(root.join(dir)).expect("Failed to create dir");
+        fs::write(
+            root.join(dir).join("pyproject.toml"),
+            format!("[project]\nname = \"{}\"\n", dir.replace('/', "-")),
+        )
+        .expect("Failed to write");
+    }
+
+    let workspace = Workspace::discover(root).expect("Workspace should be detected");
+    assert_eq!(
+        workspace.kinds,
+        vec![WorkspaceKind::Pnpm, WorkspaceKind::Python]
+    );
+
+    let names: Vec<&str> = workspace.packages.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["site", "services-agent", "services-ingest"]);
+}
+
+/// Test that a single-package project is not treated as a workspace
+#[test]
+fn test_workspace_not_detected_for_single_package() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    fs::write(temp_dir.path().join("package.json"), r#"{"name": "app"}"#).expect("Failed to write");
+    fs::write(
+        temp_dir.path().join("pyproject.toml"),
+        "[project]\nname = \"app\"\n",
+    )
+    .expect("Failed to write");
+
+    assert!(Workspace::discover(temp_dir.path()).is_none());
+}
+
+/// Test that providers declared in manifests are reported even without code usage
+#[test]
+fn test_declared_providers_from_manifests() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+
+    fs::write(
+        root.join("package.json"),
+        r#"{"dependencies": {"openai": "^4.0.0", "express": "^4"}, "devDependencies": {"@anthropic-ai/sdk": "^0.20.0"}}"#,
+    )
+    .expect("Failed to write");
+    fs::create_dir_all(root.join("worker")).expect("Failed to create dir");
+    fs::write(
+        root.join("worker/requirements.txt"),
+        "groq==0.9.0\nrequests\n",
+    )
+    .expect("Failed to write");
+
+    let declared = detect_declared_providers(root);
+    let found: Vec<(Provider, &str)> = declared
+        .iter()
+        .map(|d| (d.provider, d.package.as_str()))
+        .collect();
+
+    assert_eq!(
+        found,
+        vec![
+            (Provider::OpenAI, "openai"),
+            (Provider::Anthropic, "@anthropic-ai/sdk"),
+            (Provider::Groq, "groq"),
+        ]
+    );
+}
+
+/// Test lockfile versions are reported and old SDKs flagged
+#[test]
+fn test_locked_sdk_versions_flag_outdated() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+
+    fs::write(
+        root.join("poetry.lock"),
+        "[[package]]\nname = \"openai\"\nversion = \"0.28.1\"\n\n[[package]]\nname = \"anthropic\"\nversion = \"0.34.0\"\n",
+    )
+    .expect("Failed to write");
+    fs::write(
+        root.join("package-lock.json"),
+        r#"{"lockfileVersion": 3, "packages": {"node_modules/openai": {"version": "4.52.0"}}}"#,
+    )
+    .expect("Failed to write");
+
+    let declared = detect_declared_providers(root);
+    let found: Vec<(&str, Option<&str>, bool)> = declared
+        .iter()
+        .map(|d| (d.package.as_str(), d.version.as_deref(), d.is_outdated()))
+        .collect();
+
+    assert_eq!(
+        found,
+        vec![
+            ("openai", Some("4.52.0"), false),
+            ("openai", Some("0.28.1"), true),
+            ("anthropic", Some("0.34.0"), false),
+        ]
+    );
+}
+
+/// Test which declared SDKs read the base URL variables `enable --env-only` sets
+#[test]
+fn test_declared_sdks_read_base_url_env() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+
+    fs::write(
+        root.join("poetry.lock"),
+        "[[package]]\nname = \"openai\"\nversion = \"0.28.1\"\n\n[[package]]\nname = \"anthropic\"\nversion = \"0.34.0\"\n",
+    )
+    .expect("Failed to write");
+    fs::write(
+        root.join("package.json"),
+        r#"{"dependencies": {"openai": "^4.52.0", "@huggingface/inference": "^2.0.0"}}"#,
+    )
+    .expect("Failed to write");
+
+    let declared = detect_declared_providers(root);
+    let found: Vec<(&str, Option<&str>, bool)> = declared
+        .iter()
+        .map(|d| {
+            (
+                d.package.as_str(),
+                d.base_url_env_var(),
+                d.ignores_base_url_env(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("openai", Some("OPENAI_BASE_URL"), false),
+            ("@huggingface/inference", None, true),
+            ("openai", Some("OPENAI_BASE_URL"), true),
+            ("anthropic", Some("ANTHROPIC_BASE_URL"), false),
+        ]
+    );
+    assert_eq!(
+        ProviderInfo::get(Provider::Groq).base_url_env_vars(),
+        vec!["GROQ_BASE_URL"]
+    );
+    assert!(ProviderInfo::get(Provider::Bedrock)
+        .base_url_env_vars()
+        .is_empty());
+}
+
 // =============================================================================
 // TRANSFORMER TESTS - Code Modification
 // =============================================================================
 
+/// Test dry-run diff preview leaves the file untouched
+#[test]
+fn test_preview_diff_does_not_modify_file() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+
+    let python_file = temp_dir.path().join("app.py");
+    let original = "from openai import OpenAI\n\nclient = OpenAI()\n";
+    fs::write(&python_file, original).expect("Failed to write");
+
+    let diff = transformer::preview_diff(
+        &python_file,
+        "app.py",
+        Provider::OpenAI,
+        "https://api.promptguard.co/api/v1",
+        "PROMPTGUARD_API_KEY",
+    )
+    .expect("Preview should succeed")
+    .expect("File should need changes");
+
+    assert!(diff.starts_with("--- a/app.py\n+++ b/app.py\n@@ "));
+    assert!(diff.contains("-client = OpenAI()\n"));
+    assert!(diff.contains("+    base_url=\"https://api.promptguard.co/api/v1\"\n"));
+    assert_eq!(
+        fs::read_to_string(&python_file).expect("Failed to read"),
+        original
+    );
+}
+
+/// Test untransform removes only the injected `base_url`, keeping later edits
+#[test]
+fn test_untransform_python_keeps_user_edits() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let proxy = "https://api.promptguard.co/api/v1";
+
+    let python_file = temp_dir.path().join("app.py");
+    fs::write(
+        &python_file,
+        "from openai import OpenAI\n\nclient = OpenAI(timeout=30)\n",
+    )
+    .expect("Failed to write");
+    transformer::transform_file(&python_file, Provider::OpenAI, proxy, "PROMPTGUARD_API_KEY")
+        .expect("Transform should succeed");
+
+    let edited = fs::read_to_string(&python_file).expect("Failed to read") + "print('edited')\n";
+    fs::write(&python_file, edited).expect("Failed to write");
+
+    let result =
+        transformer::untransform_file(&python_file, Provider::OpenAI, proxy, "PROMPTGUARD_API_KEY")
+            .expect("Untransform should succeed");
+    assert!(result.modified);
+
+    let content = fs::read_to_string(&python_file).expect("Failed to read");
+    assert!(!content.contains(proxy));
+    assert!(content.contains("timeout=30"));
+    assert!(content.contains("print('edited')"));
+}
+
+/// Test untransform restores an empty TypeScript constructor and ignores other URLs
+#[test]
+fn test_untransform_typescript_round_trip() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let proxy = "https://api.promptguard.co/api/v1";
+
+    let ts_file = temp_dir.path().join("app.ts");
+    fs::write(
+        &ts_file,
+        "import OpenAI from 'openai';\n\nconst client = new OpenAI({});\n",
+    )
+    .expect("Failed to write");
+    let result =
+        transformer::transform_file(&ts_file, Provider::OpenAI, proxy, "PROMPTGUARD_API_KEY")
+            .expect("Transform should succeed");
+    assert!(result.modified);
+    transformer::untransform_file(&ts_file, Provider::OpenAI, proxy, "PROMPTGUARD_API_KEY")
+        .expect("Untransform should succeed");
+
+    let content = fs::read_to_string(&ts_file).expect("Failed to read");
+    assert!(content.contains("new OpenAI({})"));
+    assert!(!content.contains(proxy));
+
+    let custom = "const other = new OpenAI({ baseURL: 'https://example.com/v1' });\n";
+    fs::write(&ts_file, custom).expect("Failed to write");
+    let result =
+        transformer::untransform_file(&ts_file, Provider::OpenAI, proxy, "PROMPTGUARD_API_KEY")
+            .expect("Untransform should succeed");
+    assert!(!result.modified);
+    assert_eq!(
+        fs::read_to_string(&ts_file).expect("Failed to read"),
+        custom
+    );
+}
+
+/// Test transforms keep CRLF line endings and the UTF-8 BOM
+#[test]
+fn test_transform_preserves_crlf_and_bom() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+
+    let python_file = temp_dir.path().join("app.py");
+    let original = b"\xEF\xBB\xBFfrom openai import OpenAI\r\n\r\nclient = OpenAI()\r\n";
+    fs::write(&python_file, original).expect("Failed to write");
+
+    let result = transformer::transform_file(
+        &python_file,
+        Provider::OpenAI,
+        "https://api.promptguard.co/api/v1",
+        "PROMPTGUARD_API_KEY",
+    )
+    .expect("Transform should succeed");
+    assert!(result.modified);
+
+    let bytes = fs::read(&python_file).expect("Failed to read");
+    assert!(bytes.starts_with(b"\xEF\xBB\xBF"));
+    let content = String::from_utf8(bytes).expect("Should be UTF-8");
+    assert!(content.contains("base_url=\"https://api.promptguard.co/api/v1\"\r\n"));
+    assert_eq!(
+        content.matches('\n').count(),
+        content.matches("\r\n").count()
+    );
+
+    let mut journal = Journal::default();
+    journal.record(
+        temp_dir.path(),
+        &python_file,
+        Provider::OpenAI,
+        &result.edits,
+    );
+    journal
+        .revert_where(temp_dir.path(), |_| true)
+        .expect("Revert should succeed");
+    assert_eq!(fs::read(&python_file).expect("Failed to read"), original);
+}
+
+/// Test constructors built from `**kwargs` or spread options are reported, not rewritten
+#[test]
+fn test_dynamic_config_is_left_alone() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let proxy = "https://api.promptguard.co/api/v1";
+
+    let python_file = temp_dir.path().join("app.py");
+    let python = "from openai import OpenAI\n\nclient = OpenAI(**settings)\n";
+    fs::write(&python_file, python).expect("Failed to write");
+    let ts_file = temp_dir.path().join("app.ts");
+    let ts = "import OpenAI from 'openai';\n\nconst client = new OpenAI({ ...config });\n";
+    fs::write(&ts_file, ts).expect("Failed to write");
+
+    for (file, original) in [(&python_file, python), (&ts_file, ts)] {
+        let result =
+            transformer::transform_file(file, Provider::OpenAI, proxy, "PROMPTGUARD_API_KEY")
+                .expect("Transform should succeed");
+        assert!(!result.modified);
+        assert_eq!(fs::read_to_string(file).expect("Failed to read"), original);
+        assert_eq!(
+            transformer::dynamic_config_lines(file, Provider::OpenAI).expect("Should parse"),
+            vec![3]
+        );
+    }
+
+    fs::write(
+        &python_file,
+        "client = OpenAI(**settings, base_url=\"https://example.com\")\n",
+    )
+    .expect("Failed to write");
+    assert!(
+        transformer::dynamic_config_lines(&python_file, Provider::OpenAI)
+            .expect("Should parse")
+            .is_empty()
+    );
+}
+
+/// Test `new OpenAI(config)` transforms the local object literal or is reported for runtime mode
+#[test]
+fn test_typescript_options_variable() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let proxy = "https://api.promptguard.co/api/v1";
+
+    let ts_file = temp_dir.path().join("app.ts");
+    let source = "import OpenAI from 'openai';\n\
+                  const config = { apiKey: process.env.OPENAI_API_KEY };\n\
+                  const a = new OpenAI(config);\n\
+                  const b = new OpenAI(config);\n";
+    fs::write(&ts_file, source).expect("Failed to write");
+
+    let result =
+        transformer::transform_file(&ts_file, Provider::OpenAI, proxy, "PROMPTGUARD_API_KEY")
+            .expect("Transform should succeed");
+    assert!(result.modified);
+    let content = fs::read_to_string(&ts_file).expect("Failed to read");
+    assert_eq!(content.matches(proxy).count(), 1);
+    assert!(content.contains("const a = new OpenAI(config);"));
+    let results = detect_all_providers(&ts_file).expect("Detection should succeed");
+    let result = find_provider(&results, Provider::OpenAI).expect("OpenAI should be detected");
+    assert!(result.instances.iter().all(|i| i.has_base_url));
+
+    let imported = temp_dir.path().join("imported.ts");
+    let source = "import OpenAI from 'openai';\n\
+                  import { config } from './config';\n\
+                  const client = new OpenAI(config);\n";
+    fs::write(&imported, source).expect("Failed to write");
+    let result =
+        transformer::transform_file(&imported, Provider::OpenAI, proxy, "PROMPTGUARD_API_KEY")
+            .expect("Transform should succeed");
+    assert!(!result.modified);
+    assert_eq!(
+        transformer::dynamic_config_lines(&imported, Provider::OpenAI).expect("Should parse"),
+        vec![3]
+    );
+}
+
+/// Test base URLs left over from a previous proxy URL are found and retargeted
+#[test]
+fn test_stale_base_urls_are_retargeted() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let old_proxy = "https://old.promptguard.example/api/v1";
+    let new_proxy = "https://api.promptguard.co/api/v1";
+
+    let python_file = temp_dir.path().join("app.py");
+    fs::write(
+        &python_file,
+        "from openai import OpenAI\n\nclient = OpenAI()\n",
+    )
+    .expect("Failed to write");
+    let ts_file = temp_dir.path().join("app.ts");
+    fs::write(
+        &ts_file,
+        "import OpenAI from 'openai';\nconst client = new OpenAI({});\n",
+    )
+    .expect("Failed to write");
+
+    for file in [&python_file, &ts_file] {
+        transformer::transform_file(file, Provider::OpenAI, old_proxy, "PROMPTGUARD_API_KEY")
+            .expect("Transform should succeed");
+        let unchanged =
+            transformer::transform_file(file, Provider::OpenAI, new_proxy, "PROMPTGUARD_API_KEY")
+                .expect("Transform should succeed");
+        assert!(
+            !unchanged.modified,
+            "Configured calls are not re-transformed"
+        );
+
+        let mut staged = StagedChanges::new();
+        let stale = staged
+            .stale_base_urls(file, Provider::OpenAI, new_proxy)
+            .expect("Should parse");
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].url, old_proxy);
+        assert!(staged
+            .stale_base_urls(file, Provider::OpenAI, old_proxy)
+            .expect("Should parse")
+            .is_empty());
+
+        assert!(staged
+            .retarget(file, Provider::OpenAI, new_proxy)
+            .expect("Retarget should succeed"));
+        staged.commit().expect("Commit should succeed");
+        let content = fs::read_to_string(file).expect("Failed to read");
+        assert!(content.contains(new_proxy) && !content.contains(old_proxy));
+    }
+}
+
+/// Test inserted arguments follow the file's indentation style and `.editorconfig`
+#[test]
+fn test_transform_matches_indentation() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let proxy = "https://api.promptguard.co/api/v1";
+
+    let python_file = temp_dir.path().join("app.py");
+    fs::write(
+        &python_file,
+        "from openai import OpenAI\n\ndef make():\n\tif True:\n\t\treturn OpenAI()\n",
+    )
+    .expect("Failed to write");
+    transformer::transform_file(&python_file, Provider::OpenAI, proxy, "PROMPTGUARD_API_KEY")
+        .expect("Transform should succeed");
+    let content = fs::read_to_string(&python_file).expect("Failed to read");
+    assert!(content.contains(&format!(
+        "\t\treturn OpenAI(\n\t\t\tapi_key=os.environ.get(\"PROMPTGUARD_API_KEY\"),\n\t\t\tbase_url=\"{proxy}\"\n\t\t)\n"
+    )));
+
+    fs::write(
+        temp_dir.path().join(".editorconfig"),
+        "root = true\n\n[*.ts]\nindent_style = space\nindent_size = 4\n",
+    )
+    .expect("Failed to write");
+    let ts_file = temp_dir.path().join("app.ts");
+    fs::write(
+        &ts_file,
+        "import OpenAI from 'openai';\nconst client = new OpenAI({});\n",
+    )
+    .expect("Failed to write");
+    transformer::transform_file(&ts_file, Provider::OpenAI, proxy, "PROMPTGUARD_API_KEY")
+        .expect("Transform should succeed");
+    let content = fs::read_to_string(&ts_file).expect("Failed to read");
+    assert!(content.contains(&format!(
+        "new OpenAI({{\n    apiKey: process.env.PROMPTGUARD_API_KEY,\n    baseURL: \"{proxy}\"\n}});"
+    )));
+}
+
+/// Test inline ignore directives suppress detection and transformation
+#[test]
+fn test_inline_ignore_directives() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+
+    let ts_file = temp_dir.path().join("app.ts");
+    let source = "import OpenAI from 'openai';\n\
+                  const a = new OpenAI({}); // promptguard: ignore\n\
+                  // promptguard-ignore-next-line\n\
+                  const b = new OpenAI({});\n\
+                  const c = new OpenAI({});\n";
+    fs::write(&ts_file, source).expect("Failed to write");
+
+    let results = detect_all_providers(&ts_file).expect("Detection should succeed");
+    let (_, result) = results
+        .iter()
+        .find(|(p, _)| *p == Provider::OpenAI)
+        .expect("OpenAI should be detected");
+    assert_eq!(
+        result.instances.iter().map(|i| i.line).collect::<Vec<_>>(),
+        vec![5]
+    );
+    assert_eq!(
+        result.ignored.iter().map(|i| i.line).collect::<Vec<_>>(),
+        vec![2, 4]
+    );
+
+    transformer::transform_file(
+        &ts_file,
+        Provider::OpenAI,
+        "https://api.promptguard.co/api/v1",
+        "PROMPTGUARD_API_KEY",
+    )
+    .expect("Transform should succeed");
+    let content = fs::read_to_string(&ts_file).expect("Failed to read");
+    assert!(content.contains("const a = new OpenAI({}); // promptguard: ignore"));
+    assert!(content.contains("const b = new OpenAI({});"));
+    assert!(!content.contains("const c = new OpenAI({});"));
+}
+
+/// Test constructors of local client subclasses are detected and transformed
+#[test]
+fn test_subclass_constructors_are_call_sites() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let proxy = "https://api.promptguard.co/api/v1";
+
+    let python_file = temp_dir.path().join("app.py");
+    let python = "import openai\n\n\
+                  class GuardedClient(openai.OpenAI):\n    pass\n\n\
+                  class AuditedClient(GuardedClient):\n    pass\n\n\
+                  client = AuditedClient()\n";
+    fs::write(&python_file, python).expect("Failed to write");
+    let ts_file = temp_dir.path().join("app.ts");
+    let ts = "import OpenAI from 'openai';\n\n\
+              class GuardedClient extends OpenAI {}\n\n\
+              const client = new GuardedClient({});\n";
+    fs::write(&ts_file, ts).expect("Failed to write");
+
+    for (file, line) in [(&python_file, 9), (&ts_file, 5)] {
+        let results = detect_all_providers(file).expect("Detection should succeed");
+        let (_, result) = results
+            .iter()
+            .find(|(p, _)| *p == Provider::OpenAI)
+            .expect("OpenAI should be detected");
+        assert_eq!(
+            result.instances.iter().map(|i| i.line).collect::<Vec<_>>(),
+            vec![line]
+        );
+
+        let result =
+            transformer::transform_file(file, Provider::OpenAI, proxy, "PROMPTGUARD_API_KEY")
+                .expect("Transform should succeed");
+        assert!(result.modified);
+        assert!(fs::read_to_string(file)
+            .expect("Failed to read")
+            .contains(proxy));
+    }
+}
+
+/// Test module-level `openai.api_key` / `openai.api_base` configuration is routed through the proxy
+#[test]
+fn test_module_level_config_is_transformed() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let proxy = "https://api.promptguard.co/api/v1";
+
+    let key_only = temp_dir.path().join("key_only.py");
+    let original = "import openai\n\nopenai.api_key = load_key()\nopenai.chat.completions.create(model=\"m\")\n";
+    fs::write(&key_only, original).expect("Failed to write");
+    let legacy = temp_dir.path().join("legacy.py");
+    fs::write(
+        &legacy,
+        "import openai\nopenai.api_key = load_key()\nopenai.api_base = \"https://api.openai.com/v1\"\n",
+    )
+    .expect("Failed to write");
+
+    let results = detect_all_providers(&key_only).expect("Detection should succeed");
+    let result = find_provider(&results, Provider::OpenAI).expect("OpenAI should be detected");
+    assert_eq!(result.instances.len(), 1);
+    assert_eq!(result.instances[0].line, 3);
+    assert!(!result.instances[0].has_base_url);
+
+    for file in [&key_only, &legacy] {
+        let result =
+            transformer::transform_file(file, Provider::OpenAI, proxy, "PROMPTGUARD_API_KEY")
+                .expect("Transform should succeed");
+        assert!(result.modified);
+        let again =
+            transformer::transform_file(file, Provider::OpenAI, proxy, "PROMPTGUARD_API_KEY")
+                .expect("Transform should succeed");
+        assert!(!again.modified, "Transform should be idempotent");
+    }
+
+    let content = fs::read_to_string(&key_only).expect("Failed to read");
+    assert!(content.contains(&format!(
+        "openai.api_key = load_key()\nopenai.base_url = \"{proxy}\"\n"
+    )));
+    assert!(!content.contains("import os"));
+    let content = fs::read_to_string(&legacy).expect("Failed to read");
+    assert!(content.contains(&format!("openai.api_base = \"{proxy}\"")));
+
+    transformer::untransform_file(&key_only, Provider::OpenAI, proxy, "PROMPTGUARD_API_KEY")
+        .expect("Untransform should succeed");
+    assert_eq!(
+        fs::read_to_string(&key_only).expect("Failed to read"),
+        original
+    );
+}
+
 /// Test Python `OpenAI` transformation adds `base_url` parameter
 #[test]
 fn test_transform_python_openai_adds_base_url() {
@@ -454,7 +1121,7 @@ fn test_config_custom_settings() {
     )
     .expect("Failed to create config");
 
-    config.env_file = ".env.local".to_string();
+    config.env_file = ".env.local".to_string().into();
     config.env_var_name = "MY_CUSTOM_KEY".to_string();
     config.exclude_patterns = vec!["dist".to_string(), "build".to_string()];
 
@@ -462,7 +1129,7 @@ fn test_config_custom_settings() {
 
     let loaded = config_manager.load().expect("Failed to load");
 
-    assert_eq!(loaded.env_file, ".env.local");
+    assert_eq!(loaded.env_file.primary(), ".env.local");
     assert_eq!(loaded.env_var_name, "MY_CUSTOM_KEY");
     assert!(loaded.exclude_patterns.contains(&"dist".to_string()));
 }
@@ -490,6 +1157,591 @@ fn test_config_delete() {
     assert!(!config_manager.exists());
 }
 
+/// Test the API key is never written to the config, and legacy configs holding one are migrated
+#[test]
+fn test_config_api_key_moves_to_env_file() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config_path = temp_dir.path().join(".promptguard.json");
+    let env_path = temp_dir.path().join(".env.legacy");
+    let key = "pg_sk_test_legacy12345678901234567890";
+    fs::write(
+        &config_path,
+        format!(
+            r#"{{
+  "version": "1.0",
+  "api_key": "{key}",
+  "proxy_url": "https://api.promptguard.co/api/v1",
+  "providers": ["openai"],
+  "env_file": ".env.legacy",
+  "env_var_name": "PG_TEST_LEGACY_KEY"
+}}"#
+        ),
+    )
+    .expect("Failed to write");
+
+    let legacy = fs::read_to_string(&config_path).expect("Failed to read");
+
+    // Loading is read-only: the key comes from the config until it is saved
+    let manager = ConfigManager::new(Some(config_path.clone())).expect("Failed to create manager");
+    let config = manager.load().expect("Legacy config should load");
+    assert_eq!(config.api_key(), key);
+    assert_eq!(
+        fs::read_to_string(&config_path).expect("Failed to read"),
+        legacy
+    );
+    assert!(!env_path.exists());
+
+    manager.save(&config).expect("Failed to save");
+    let content = fs::read_to_string(&config_path).expect("Failed to read");
+    assert!(
+        !content.contains("api_key"),
+        "Key left in config: {content}"
+    );
+    assert!(!content.contains(key));
+    let env = fs::read_to_string(&env_path).expect("Env file should be written");
+    assert!(env.contains(&format!("PG_TEST_LEGACY_KEY={key}")));
+
+    // Loading again reads the key from the env file
+    let reloaded = manager.load().expect("Migrated config should load");
+    assert_eq!(reloaded.api_key(), key);
+
+    // A key already in the env file wins over the stale one in the config
+    fs::write(
+        &env_path,
+        "PG_TEST_LEGACY_KEY=pg_sk_prod_current1234567890123456789\n",
+    )
+    .expect("Failed to write");
+    fs::write(
+        &config_path,
+        content.replacen('{', &format!("{{\n  \"api_key\": \"{key}\","), 1),
+    )
+    .expect("Failed to write");
+    let config = manager.load().expect("Legacy config should load");
+    assert_eq!(config.api_key(), "pg_sk_prod_current1234567890123456789");
+
+    manager.save(&config).expect("Failed to save");
+    let saved = fs::read_to_string(&config_path).expect("Failed to read");
+    assert!(!saved.contains(key), "{saved}");
+    assert!(!saved.contains("pg_sk_prod_current"), "{saved}");
+    assert_eq!(
+        fs::read_to_string(&env_path).expect("Failed to read"),
+        "PG_TEST_LEGACY_KEY=pg_sk_prod_current1234567890123456789\n"
+    );
+}
+
+/// Test `config migrate` rewrites a legacy config now, moving its key to the env file
+#[test]
+fn test_config_migrate_command() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    let key = "pg_sk_test_migrate1234567890123456789";
+    fs::write(
+        root.join(".promptguard.json"),
+        format!(
+            r#"{{
+  "version": "1.0",
+  "api_key": "{key}",
+  "proxy_url": "https://api.promptguard.co/api/v1",
+  "providers": ["openai"]
+}}"#
+        ),
+    )
+    .expect("Failed to write");
+
+    let migrated = promptguard_json(root, &["config", "migrate"]);
+    assert_eq!(migrated["migrated"], true, "{migrated}");
+    assert_eq!(migrated["from"], "1.0");
+    assert_eq!(migrated["to"], "1.1");
+
+    let config = fs::read_to_string(root.join(".promptguard.json")).expect("Failed to read");
+    assert!(!config.contains(key), "{config}");
+    assert!(config.contains("\"version\": \"1.1\""), "{config}");
+    let env = fs::read_to_string(root.join(".env")).expect("Env file should be written");
+    assert!(env.contains(&format!("PROMPTGUARD_API_KEY={key}")), "{env}");
+
+    let again = promptguard_json(root, &["config", "migrate"]);
+    assert_eq!(again["migrated"], false, "{again}");
+    assert_eq!(
+        fs::read_to_string(root.join(".promptguard.json")).expect("Failed to read"),
+        config
+    );
+}
+
+/// Test `PROMPTGUARD_<SETTING>` variables override settings without being saved
+#[test]
+fn test_config_env_overrides() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config_path = temp_dir.path().join(".promptguard.json");
+    let manager = ConfigManager::new(Some(config_path.clone())).expect("Failed to create manager");
+    let config = PromptGuardConfig::new(
+        "pg_sk_test_demo123456789012345678901234".to_string(),
+        "https://api.promptguard.co/api/v1".to_string(),
+        vec!["openai".to_string()],
+    )
+    .expect("Failed to create config");
+    manager.save(&config).expect("Failed to save");
+
+    let vars = std::collections::HashMap::from([
+        (
+            "PROMPTGUARD_PROXY_URL",
+            "https://ci.promptguard.example/api/v1",
+        ),
+        ("PROMPTGUARD_EXCLUDE", "generated/**, vendor/**"),
+        ("PROMPTGUARD_RUNTIME_MODE", "true"),
+        ("PROMPTGUARD_FRAMEWORK", "fastapi"),
+        ("PROMPTGUARD_ENV_FILE", ""),
+    ]);
+    let mut config = manager.load().expect("Config should load");
+    config
+        .apply_env_overrides(|name| vars.get(name).map(ToString::to_string))
+        .expect("Overrides should apply");
+    assert_eq!(
+        config.proxy_url.default_url(),
+        "https://ci.promptguard.example/api/v1"
+    );
+    assert_eq!(config.exclude_patterns, ["generated/**", "vendor/**"]);
+    assert!(config.runtime_mode);
+    assert_eq!(config.framework.as_deref(), Some("fastapi"));
+    assert_eq!(config.env_file.primary(), ".env");
+    assert_eq!(config.env_overrides().count(), 4);
+
+    config.set_value("shim_log", "true").expect("Set");
+    config.set_value("runtime_mode", "false").expect("Set");
+    manager.save(&config).expect("Failed to save");
+    let saved = fs::read_to_string(&config_path).expect("Failed to read");
+    assert!(!saved.contains("ci.promptguard.example"), "{saved}");
+    assert!(!saved.contains("generated/**"));
+    assert!(!saved.contains("fastapi"));
+    let saved = manager.load().expect("Config should load");
+    assert!(saved.shim_log);
+    assert!(
+        !saved.runtime_mode,
+        "Changes made after the override are saved"
+    );
+
+    let mut config = manager.load().expect("Config should load");
+    let err = config
+        .apply_env_overrides(|name| (name == "PROMPTGUARD_BACKUP_ENABLED").then(|| "maybe".into()))
+        .expect_err("Invalid override");
+    assert!(
+        err.to_string().contains("PROMPTGUARD_BACKUP_ENABLED"),
+        "{err}"
+    );
+}
+
+/// Test a list of env files is read in order and kept in sync
+#[test]
+fn test_config_multiple_env_files() {
+    use promptguard::config::{EnvFile, EnvFileState};
+    use promptguard::env::EnvManager;
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    let config_path = root.join(".promptguard.json");
+    fs::write(
+        &config_path,
+        r#"{
+  "version": "1.0",
+  "api_key": "pg_sk_test_multi1234567890123456789012",
+  "proxy_url": "https://api.promptguard.co/api/v1",
+  "providers": ["openai"],
+  "env_file": [".env", ".env.local", ".env.production"],
+  "env_var_name": "PG_TEST_MULTI_KEY"
+}"#,
+    )
+    .expect("Failed to write");
+    fs::write(
+        root.join(".env.local"),
+        "PG_TEST_MULTI_KEY=pg_sk_prod_local\n",
+    )
+    .expect("Write");
+
+    // Saving the migrated config writes the key to the files that don't have one yet
+    let manager = ConfigManager::new(Some(config_path.clone())).expect("Failed to create manager");
+    let mut config = manager.load().expect("Config should load");
+    assert_eq!(config.api_key(), "pg_sk_test_multi1234567890123456789012");
+    manager.save(&config).expect("Failed to save");
+    assert_eq!(config.env_file.primary(), ".env");
+    let states = config.env_file.states(root, "PG_TEST_MULTI_KEY");
+    assert_eq!(
+        states,
+        [
+            (".env", EnvFileState::InSync),
+            (".env.local", EnvFileState::OutOfSync),
+            (".env.production", EnvFileState::InSync),
+        ]
+    );
+
+    config
+        .env_file
+        .set_key(root, "PG_TEST_MULTI_KEY", "pg_sk_prod_rotated")
+        .expect("Set key");
+    for file in config.env_file.files() {
+        assert_eq!(
+            EnvManager::get_key(&root.join(file), "PG_TEST_MULTI_KEY").as_deref(),
+            Some("pg_sk_prod_rotated")
+        );
+    }
+    assert_eq!(
+        config
+            .env_file
+            .remove_key(root, "PG_TEST_MULTI_KEY")
+            .expect("Remove"),
+        [".env", ".env.local", ".env.production"]
+    );
+    assert!(config
+        .env_file
+        .states(root, "PG_TEST_MULTI_KEY")
+        .iter()
+        .all(|(_, state)| *state == EnvFileState::NoKey));
+
+    // One file stays a plain string, several become a list
+    config.set_value("env_file", ".env").expect("Set");
+    assert_eq!(config.env_file, EnvFile::Single(".env".to_string()));
+    config
+        .set_value("env_file", ".env, .env.local")
+        .expect("Set");
+    assert_eq!(config.env_file.files(), [".env", ".env.local"]);
+    assert!(config.set_value("env_file", "[]").is_err());
+    assert!(config.set_value("env_file", ".env,../outside.env").is_err());
+    manager.save(&config).expect("Failed to save");
+    let saved: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&config_path).expect("Read")).expect("JSON");
+    assert_eq!(saved["env_file"], serde_json::json!([".env", ".env.local"]));
+}
+
+/// Test an exported config stamps its settings onto another project
+#[test]
+fn test_config_export_import() {
+    let source_dir = TempDir::new().expect("Failed to create temp dir");
+    let source = ConfigManager::new(Some(source_dir.path().join(".promptguard.json")))
+        .expect("Failed to create manager");
+    let mut config = PromptGuardConfig::new(
+        "pg_sk_test_export12345678901234567890123".to_string(),
+        "https://proxy.example.com/v1".to_string(),
+        vec!["openai".to_string(), "anthropic".to_string()],
+    )
+    .expect("Failed to create config");
+    config
+        .set_value("exclude_patterns", "generated/**")
+        .expect("A single pattern is a list of one");
+    config.metadata.files_managed = vec!["app.py".to_string()];
+
+    let template = config.export(false).expect("Export");
+    assert!(template.get("api_key").is_none());
+    assert!(template.get("metadata").is_none());
+    assert_eq!(
+        template["exclude_patterns"],
+        serde_json::json!(["generated/**"])
+    );
+    let with_key = config.export(true).expect("Export");
+    assert_eq!(
+        with_key["api_key"],
+        "pg_sk_test_export12345678901234567890123"
+    );
+    source.save(&config).expect("Failed to save");
+
+    // The target keeps its own metadata and gets the key in its env file
+    let target_dir = TempDir::new().expect("Failed to create temp dir");
+    let target = ConfigManager::new(Some(target_dir.path().join(".promptguard.json")))
+        .expect("Failed to create manager");
+    let mut existing = PromptGuardConfig::new(
+        "pg_sk_test_target12345678901234567890123".to_string(),
+        "https://api.promptguard.co/api/v1".to_string(),
+        vec!["cohere".to_string()],
+    )
+    .expect("Failed to create config");
+    existing.metadata.files_managed = vec!["worker.py".to_string()];
+    target.save(&existing).expect("Failed to save");
+
+    let imported = target.import(with_key).expect("Import");
+    assert_eq!(imported.providers, ["openai", "anthropic"]);
+    assert_eq!(imported.metadata.files_managed, ["worker.py"]);
+    let loaded = target.load().expect("Config should load");
+    assert_eq!(
+        loaded.proxy_url.default_url(),
+        "https://proxy.example.com/v1"
+    );
+    assert_eq!(loaded.exclude_patterns, ["generated/**"]);
+    assert_eq!(loaded.api_key(), "pg_sk_test_export12345678901234567890123");
+    let saved = fs::read_to_string(target.config_path()).expect("Failed to read");
+    assert!(!saved.contains("pg_sk_test_export"));
+
+    // A bad template changes nothing
+    let bad = serde_json::json!({ "version": "1.1", "proxy_url": "http://evil.example", "providers": [] });
+    assert!(target.import(bad).is_err());
+    assert!(target.import(serde_json::json!(["openai"])).is_err());
+    assert_eq!(
+        fs::read_to_string(target.config_path()).expect("Failed to read"),
+        saved
+    );
+}
+
+/// Test the project config is found from subdirectories, up to the git root
+#[test]
+fn test_find_project_root_walks_up() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let outer = temp_dir.path();
+    let repo = outer.join("repo");
+    let nested = repo.join("src").join("app");
+    fs::create_dir_all(&nested).expect("Failed to create dirs");
+    fs::create_dir(repo.join(".git")).expect("Failed to create .git");
+
+    // A config outside the repository is not this project's
+    fs::write(outer.join(".promptguard.json"), "{}").expect("Failed to write");
+    assert_eq!(promptguard::config::find_project_root(&nested), None);
+
+    fs::write(repo.join(".promptguard.json"), "{}").expect("Failed to write");
+    assert_eq!(
+        promptguard::config::find_project_root(&nested),
+        Some(repo.clone())
+    );
+    assert_eq!(
+        promptguard::config::find_project_root(&repo),
+        Some(repo.clone())
+    );
+
+    // The nearest config wins, e.g. a package of a monorepo
+    let package = repo.join("src");
+    fs::write(package.join(".promptguard.json"), "{}").expect("Failed to write");
+    assert_eq!(
+        promptguard::config::find_project_root(&nested),
+        Some(package)
+    );
+}
+
+/// Test settings this version doesn't know survive a load and save
+#[test]
+fn test_config_keeps_unknown_settings() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config_path = temp_dir.path().join(".promptguard.json");
+    fs::write(
+        &config_path,
+        r#"{
+  "$schema": "./promptguard.schema.json",
+  "version": "1.1",
+  "proxy_url": "https://api.promptguard.co/api/v1",
+  "providers": ["openai"],
+  "future_setting": {"mode": "strict"}
+}"#,
+    )
+    .expect("Failed to write");
+
+    let manager = ConfigManager::new(Some(config_path.clone())).expect("Failed to create manager");
+    let mut config = manager.load().expect("Config should load");
+    assert_eq!(
+        config.extra.get("future_setting"),
+        Some(&serde_json::json!({"mode": "strict"}))
+    );
+    config.set_value("shim_log", "true").expect("Set");
+    manager.save(&config).expect("Failed to save");
+
+    let saved: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&config_path).expect("Failed to read"))
+            .expect("Saved config is JSON");
+    assert_eq!(saved["future_setting"]["mode"], "strict");
+    assert_eq!(saved["$schema"], "./promptguard.schema.json");
+    assert_eq!(saved["shim_log"], true);
+
+    fs::write(
+        &config_path,
+        fs::read_to_string(&config_path)
+            .expect("Failed to read")
+            .replace("\"1.1\"", "\"9.0\""),
+    )
+    .expect("Failed to write");
+    let err = manager.load().expect_err("Newer version must not load");
+    assert!(err.to_string().contains("newer promptguard"), "{err}");
+    assert!(fs::read_to_string(&config_path)
+        .expect("Failed to read")
+        .contains("\"9.0\""));
+}
+
+/// Test a profile overrides the top-level settings and saves back into its own entry
+#[test]
+fn test_config_profiles() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config_path = temp_dir.path().join(".promptguard.json");
+    fs::write(
+        &config_path,
+        r#"{
+  "version": "1.0",
+  "proxy_url": "https://api.promptguard.co/api/v1",
+  "providers": ["openai"],
+  "profiles": {
+    "prod": {
+      "proxy_url": "https://prod.promptguard.example/api/v1",
+      "env_file": ".env.prod",
+      "env_var_name": "PG_TEST_PROD_KEY"
+    }
+  }
+}"#,
+    )
+    .expect("Failed to write");
+    fs::write(
+        temp_dir.path().join(".env.prod"),
+        "PG_TEST_PROD_KEY=pg_sk_prod_profile1234567890123456789\n",
+    )
+    .expect("Failed to write");
+
+    let manager = ConfigManager::new(Some(config_path.clone())).expect("Failed to create manager");
+    let base = manager.load_profile(None).expect("Base config should load");
+    assert_eq!(base.profile, None);
+    assert_eq!(base.env_file.primary(), ".env");
+
+    let mut prod = manager
+        .load_profile(Some("prod"))
+        .expect("Profile should load");
+    assert_eq!(prod.profile.as_deref(), Some("prod"));
+    assert_eq!(
+        prod.proxy_url.default_url(),
+        "https://prod.promptguard.example/api/v1"
+    );
+    assert_eq!(prod.env_var_name, "PG_TEST_PROD_KEY");
+    assert_eq!(prod.api_key(), "pg_sk_prod_profile1234567890123456789");
+
+    prod.set_value("proxy_url", "https://prod2.promptguard.example/api/v1")
+        .expect("Set under profile");
+    prod.set_value("shim_log", "true")
+        .expect("Set shared setting");
+    manager.save(&prod).expect("Failed to save");
+
+    let base = manager.load_profile(None).expect("Base config should load");
+    assert_eq!(
+        base.proxy_url.default_url(),
+        "https://api.promptguard.co/api/v1"
+    );
+    assert_eq!(base.env_var_name, "PROMPTGUARD_API_KEY");
+    assert!(base.shim_log);
+    assert_eq!(
+        base.profiles["prod"].proxy_url,
+        Some(
+            "https://prod2.promptguard.example/api/v1"
+                .to_string()
+                .into()
+        )
+    );
+
+    let err = manager
+        .load_profile(Some("staging"))
+        .expect_err("Unknown profile");
+    assert!(err.to_string().contains("prod"), "{err}");
+
+    let content = fs::read_to_string(&config_path).expect("Failed to read");
+    fs::write(
+        &config_path,
+        content.replace("https://prod2", "http://prod2"),
+    )
+    .expect("Failed to write");
+    assert!(
+        manager.load_profile(None).is_err(),
+        "HTTP proxy in a profile should be rejected"
+    );
+}
+
+/// Test user config settings are type-checked and validated
+#[test]
+fn test_user_config_settings() {
+    let mut user = UserConfig::default();
+    assert!(!user.telemetry_enabled());
+    assert!(user.set_value("api_key", "not-a-key").is_err());
+    user.set_value("api_key", "pg_sk_test_user123456789012345678901")
+        .expect("Valid key");
+    assert!(user
+        .set_value("base_url", "http://insecure.example")
+        .is_err());
+    user.set_value("base_url", "https://user.promptguard.example/api/v1")
+        .expect("HTTPS URL");
+    user.set_value("exclude_patterns", "gen/**, scripts/**")
+        .expect("List");
+    assert_eq!(user.exclude_patterns, ["gen/**", "scripts/**"]);
+    assert!(user.set_value("telemetry", "maybe").is_err());
+    user.set_value("telemetry", "false").expect("Bool");
+    assert_eq!(user.telemetry, Some(false));
+    assert!(user.set_value("proxy_url", "https://x.example").is_err());
+    assert_eq!(
+        user.get_value("base_url").expect("Known key"),
+        Some(serde_json::json!("https://user.promptguard.example/api/v1"))
+    );
+
+    user.unset_value("api_key").expect("Known key");
+    assert_eq!(user.api_key, None);
+    assert_eq!(user.get_value("api_key").expect("Known key"), None);
+}
+
+/// Test single settings are read and changed with type validation
+#[test]
+fn test_config_get_set_unset() {
+    let mut config = PromptGuardConfig::new(
+        "pg_sk_test_demo123456789012345678901234".to_string(),
+        "https://api.promptguard.co/api/v1".to_string(),
+        vec!["openai".to_string()],
+    )
+    .expect("Failed to create config");
+
+    assert_eq!(
+        config.get_value("providers").expect("Known key"),
+        Some(serde_json::json!(["openai"]))
+    );
+    assert_eq!(config.get_value("framework").expect("Known key"), None);
+    assert!(config.get_value("nonsense").is_err());
+    assert!(config.get_value("api_key").is_err());
+
+    config.set_value("runtime_mode", "true").expect("Bool");
+    assert!(config.runtime_mode);
+    assert!(config.set_value("runtime_mode", "yes").is_err());
+    config.set_value("env_var_name", "MY_KEY").expect("String");
+    assert_eq!(config.env_var_name, "MY_KEY");
+    let err = config
+        .set_value("env_var_name", "123")
+        .expect_err("Not a variable name");
+    assert!(err.to_string().contains("$.env_var_name"), "{err}");
+    config
+        .set_value("providers", "openai, anthropic")
+        .expect("List");
+    assert_eq!(config.providers, ["openai", "anthropic"]);
+    let err = config
+        .set_value("providers", "openai,antropic")
+        .expect_err("Unknown provider");
+    assert!(err.to_string().contains("antropic"), "{err}");
+    assert!(config.set_value("shim_fail_mode", "sideways").is_err());
+    config.set_value("shim_fail_mode", "open").expect("Variant");
+    assert!(config
+        .set_value("proxy_url", "http://evil.example")
+        .is_err());
+    assert!(config.set_value("version", "2.0").is_err());
+
+    config
+        .set_value(
+            "proxy_url.anthropic",
+            "https://eu.promptguard.example/api/v1",
+        )
+        .expect("Map entry");
+    assert_eq!(
+        config.proxy_url.for_provider(Provider::Anthropic),
+        "https://eu.promptguard.example/api/v1"
+    );
+    assert_eq!(
+        config.proxy_url.for_provider(Provider::OpenAI),
+        "https://api.promptguard.co/api/v1"
+    );
+    config
+        .unset_value("proxy_url.anthropic")
+        .expect("Map entry");
+    assert_eq!(
+        config.proxy_url.for_provider(Provider::Anthropic),
+        "https://api.promptguard.co/api/v1"
+    );
+
+    config.set_value("framework", "langchain").expect("Option");
+    config.unset_value("framework").expect("Option");
+    assert_eq!(config.framework, None);
+    config.unset_value("runtime_mode").expect("Default");
+    assert!(!config.runtime_mode);
+    assert!(config.unset_value("providers").is_err());
+    assert_eq!(config.api_key(), "pg_sk_test_demo123456789012345678901234");
+}
+
 // =============================================================================
 // API KEY VALIDATION TESTS - Security
 // =============================================================================
@@ -674,71 +1926,1960 @@ fn test_status_initialized() {
     assert_eq!(loaded.providers[0], "openai");
 }
 
+/// Test `status --json` reports coverage, drift and the schema version
+#[test]
+fn test_status_json_schema() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).expect("Failed to create .git");
+    let app = "from openai import OpenAI\nclient = OpenAI()\n";
+    fs::write(root.join("app.py"), app).expect("Failed to write app.py");
+    let init = promptguard(
+        root,
+        &[
+            "init",
+            "-y",
+            "--api-key",
+            "pg_sk_test_status1234567890123456789",
+        ],
+    );
+    assert!(init.status.success());
+    fs::write(root.join("worker.py"), app).expect("Failed to write worker.py");
+
+    let status = |root: &std::path::Path| -> serde_json::Value {
+        let output = promptguard(root, &["status", "--json"]);
+        assert!(output.status.success());
+        serde_json::from_slice(&output.stdout).expect("status --json is JSON")
+    };
+    let report = status(root);
+    assert_eq!(report["version"], 1);
+    assert_eq!(report["runtime_mode"], false);
+    assert_eq!(report["shim"]["installed"], false);
+    assert_eq!(
+        report["providers"],
+        serde_json::json!([{"provider": "openai", "guarded": 1, "unguarded": 1}])
+    );
+    assert_eq!(report["drift"]["ok"], true);
+
+    // Undoing the transform by hand is drift
+    fs::write(root.join("app.py"), app).expect("Failed to write app.py");
+    let report = status(root);
+    assert_eq!(report["drift"]["ok"], false);
+    assert_eq!(report["drift"]["issues"][0]["file"], "app.py");
+}
+
 // =============================================================================
-// SECURITY TESTS - Path Traversal Prevention
+// UNINSTALL COMMAND TESTS - Complete Removal
 // =============================================================================
 
-/// Test that path traversal attempts are rejected in env file paths
+/// Run the CLI in `dir` without network access or user-level settings
+fn promptguard(dir: &std::path::Path, args: &[&str]) -> std::process::Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_promptguard"))
+        .arg("--air-gapped")
+        .arg("--path")
+        .arg(dir)
+        .args(args)
+        .env("XDG_CONFIG_HOME", dir.join(".user-config"))
+        .env_remove("PROMPTGUARD_API_KEY")
+        .env_remove("PROMPTGUARD_PROFILE")
+        .output()
+        .expect("Failed to run promptguard")
+}
+
+/// Test uninstall undoes init, leaving only the user's files
 #[test]
-fn test_path_traversal_prevention() {
-    // These should be rejected
-    let malicious_paths = vec![
-        "../../../etc/passwd",
-        "..\\..\\windows\\system32",
-        "/etc/passwd",
-        "C:\\Windows\\system32",
-    ];
+fn test_uninstall_removes_everything() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).expect("Failed to create .git");
+    let app = "from openai import OpenAI\nclient = OpenAI()\n";
+    let server =
+        "import OpenAI from \"openai\";\nconst client = new OpenAI();\nexport default client;\n";
+    fs::write(root.join("app.py"), app).expect("Failed to write app.py");
+    fs::write(root.join("server.ts"), server).expect("Failed to write server.ts");
+    fs::write(root.join(".env"), "DEBUG=1\n").expect("Failed to write .env");
+
+    let init = promptguard(
+        root,
+        &[
+            "init",
+            "-y",
+            "--api-key",
+            "pg_sk_test_uninstall1234567890123456",
+        ],
+    );
+    assert!(
+        init.status.success(),
+        "{}",
+        String::from_utf8_lossy(&init.stderr)
+    );
+    assert_ne!(
+        fs::read_to_string(root.join("app.py")).expect("app.py"),
+        app
+    );
 
-    for path in malicious_paths {
-        let is_safe = !path.contains("..") && !path.starts_with('/') && !path.contains(":\\");
-        assert!(
-            !is_safe || path == path,
-            "Path traversal should be prevented for: {path}"
-        );
-    }
+    let output = promptguard(root, &["uninstall", "-y"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("app.py")).expect("app.py"),
+        app
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("server.ts")).expect("server.ts"),
+        server
+    );
+    assert_eq!(
+        fs::read_to_string(root.join(".env")).expect(".env"),
+        "DEBUG=1\n"
+    );
+    assert!(!root.join(".promptguard.json").exists());
+    assert!(!root.join(".promptguard").exists());
 
-    // These should be allowed
-    let safe_paths = vec![
-        ".env",
-        ".env.local",
-        "config/.env",
-        "environments/.env.prod",
-    ];
+    // Nothing left to remove
+    let again = promptguard(root, &["uninstall", "-y"]);
+    assert!(again.status.success());
+    assert!(String::from_utf8_lossy(&again.stdout).contains("Nothing to uninstall"));
+}
 
-    for path in safe_paths {
-        let is_safe = !path.contains("..") && !path.starts_with('/');
-        assert!(is_safe, "Safe path should be allowed: {path}");
-    }
+// =============================================================================
+// REVERT COMMAND TESTS - Restoring Without Git
+// =============================================================================
+
+/// Test revert restores managed files outside git, even without the journal
+#[test]
+fn test_revert_restores_files_without_git() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    let app = "from openai import OpenAI\nclient = OpenAI()\n";
+    fs::write(root.join("app.py"), app).expect("Failed to write app.py");
+
+    let init = promptguard(
+        root,
+        &[
+            "init",
+            "-y",
+            "--api-key",
+            "pg_sk_test_revert123456789012345678901",
+            "--force",
+        ],
+    );
+    assert!(
+        init.status.success(),
+        "{}",
+        String::from_utf8_lossy(&init.stderr)
+    );
+    assert_ne!(
+        fs::read_to_string(root.join("app.py")).expect("app.py"),
+        app
+    );
+    // Projects set up before the journal existed only have the managed list
+    let _ = fs::remove_file(root.join(".promptguard/journal.json"));
+
+    let output = promptguard(root, &["revert", "-y"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("No git repository found"), "{stdout}");
+    // Without the journal only the injected arguments can be located
+    let reverted = fs::read_to_string(root.join("app.py")).expect("app.py");
+    assert!(reverted.contains("client = OpenAI()\n"), "{reverted}");
+    assert!(!reverted.contains("base_url"), "{reverted}");
+    assert!(!root.join(".promptguard.json").exists());
 }
 
-/// Test that proxy URLs are validated
+/// Test revert --json lists files whose edits it can't find, keeping stdout JSON
 #[test]
-fn test_proxy_url_validation() {
-    // Valid URLs
-    let valid_urls = vec![
-        "https://api.promptguard.co/api/v1",
-        "https://custom.example.com/proxy",
-        "http://localhost:8080/api",
-        "http://127.0.0.1:3000/v1",
-    ];
+fn test_revert_json_reports_unresolved_files() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::write(
+        root.join("app.py"),
+        "from openai import OpenAI\nclient = OpenAI()\n",
+    )
+    .expect("Failed to write app.py");
+
+    let init = promptguard(
+        root,
+        &[
+            "init",
+            "-y",
+            "--api-key",
+            "pg_sk_test_revert123456789012345678901",
+            "--force",
+        ],
+    );
+    assert!(
+        init.status.success(),
+        "{}",
+        String::from_utf8_lossy(&init.stderr)
+    );
+    // Editing the injected argument by hand hides it from revert
+    let edited = fs::read_to_string(root.join("app.py"))
+        .expect("app.py")
+        .replace("/api/v1\"", "/api/v2\"");
+    fs::write(root.join("app.py"), &edited).expect("Failed to edit app.py");
 
-    for url in &valid_urls {
-        let is_valid = url.starts_with("https://")
-            || url.starts_with("http://localhost")
-            || url.starts_with("http://127.0.0.1");
-        assert!(is_valid, "Valid URL should be accepted: {url}");
-    }
+    let output = promptguard(root, &["--json", "revert", "-y"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let value: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be a single JSON document");
+    assert_eq!(value["unresolved"], serde_json::json!(["app.py"]));
+    assert_eq!(value["restored_files"], serde_json::json!([]));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Revert these manually"), "{stderr}");
+    assert_eq!(
+        fs::read_to_string(root.join("app.py")).expect("app.py"),
+        edited
+    );
+}
 
-    // Invalid URLs (HTTP to remote hosts)
-    let invalid_urls = vec![
-        "http://api.promptguard.co/api/v1", // HTTP to remote
-        "http://evil.com/proxy",
-    ];
+/// Test revert --files and --provider undo only the matching transformations
+#[test]
+fn test_revert_selected_files_and_providers() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    let app = "from openai import OpenAI\nclient = OpenAI()\n";
+    fs::create_dir(root.join("legacy")).expect("Failed to create legacy/");
+    fs::write(root.join("app.py"), app).expect("Failed to write app.py");
+    fs::write(root.join("legacy/old.py"), app).expect("Failed to write old.py");
+
+    let init = promptguard(
+        root,
+        &[
+            "init",
+            "-y",
+            "--api-key",
+            "pg_sk_test_revert123456789012345678901",
+            "--force",
+        ],
+    );
+    assert!(
+        init.status.success(),
+        "{}",
+        String::from_utf8_lossy(&init.stderr)
+    );
 
-    for url in &invalid_urls {
-        let is_valid = url.starts_with("https://")
-            || url.starts_with("http://localhost")
-            || url.starts_with("http://127.0.0.1");
-        assert!(!is_valid, "Invalid URL should be rejected: {url}");
-    }
+    let output = promptguard(root, &["revert", "-y", "--files", "legacy"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("legacy/old.py")).expect("old.py"),
+        app
+    );
+    assert_ne!(
+        fs::read_to_string(root.join("app.py")).expect("app.py"),
+        app
+    );
+    let config = ConfigManager::new(Some(root.join(".promptguard.json")))
+        .expect("Failed to create config manager")
+        .load()
+        .expect("Config should be kept");
+    assert!(config.exclude_patterns.contains(&"legacy/**".to_string()));
+    assert_eq!(config.metadata.files_managed, vec!["app.py".to_string()]);
+
+    let output = promptguard(root, &["revert", "-y", "--provider", "openai"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("app.py")).expect("app.py"),
+        app
+    );
+    let config = ConfigManager::new(Some(root.join(".promptguard.json")))
+        .expect("Failed to create config manager")
+        .load()
+        .expect("Config should be kept");
+    assert!(!config.providers.contains(&"openai".to_string()));
+    assert!(config.metadata.files_managed.is_empty());
+
+    // Excluding the files would stop every provider's transforms there
+    let output = promptguard(
+        root,
+        &[
+            "revert",
+            "-y",
+            "--provider",
+            "anthropic",
+            "--files",
+            "app.py",
+        ],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+}
+
+// =============================================================================
+// SECRETS COMMAND TESTS - Hardcoded Keys
+// =============================================================================
+
+/// Test `secrets` reports inline keys and `secrets migrate` moves them to .env
+#[test]
+fn test_secrets_scan_and_migrate() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    git(root, &["init", "-q"]);
+    fs::write(root.join(".gitignore"), ".env\n").expect("Failed to write .gitignore");
+    let openai_key = "sk-proj-abcdefghijklmnopqrstuvwx";
+    fs::write(
+        root.join("app.py"),
+        format!(
+            "from openai import OpenAI\nclient = OpenAI(api_key=\"{openai_key}\")\nheader = \"Bearer {openai_key}\"\n"
+        ),
+    )
+    .expect("Failed to write app.py");
+    fs::write(
+        root.join("chat.ts"),
+        "const client = new Anthropic({ apiKey: 'sk-ant-REDACTED' });\n",
+    )
+    .expect("Failed to write chat.ts");
+
+    let output = promptguard(root, &["secrets"]);
+    assert_eq!(output.status.code(), Some(2));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("app.py:2:"), "{stdout}");
+    assert!(!stdout.contains(openai_key), "keys are masked: {stdout}");
+    let findings: serde_json::Value =
+        serde_json::from_slice(&promptguard(root, &["secrets", "--json"]).stdout)
+            .expect("secrets --json is JSON");
+    assert_eq!(findings["schema_version"], 1);
+    let findings = &findings["items"];
+    assert_eq!(findings.as_array().map(Vec::len), Some(3));
+    assert_eq!(findings[0]["env_var"], "OPENAI_API_KEY");
+    assert_eq!(findings[2]["env_var"], "ANTHROPIC_API_KEY");
+
+    let dry_run = promptguard(root, &["secrets", "migrate", "--dry-run"]);
+    assert!(dry_run.status.success());
+    assert!(!root.join(".env").exists());
+
+    let migrate = promptguard(root, &["secrets", "migrate", "-y"]);
+    assert!(
+        migrate.status.success(),
+        "{}",
+        String::from_utf8_lossy(&migrate.stderr)
+    );
+    let env = fs::read_to_string(root.join(".env")).expect("Failed to read .env");
+    assert!(
+        env.contains(&format!("OPENAI_API_KEY={openai_key}")),
+        "{env}"
+    );
+    assert!(env.contains("ANTHROPIC_API_KEY=sk-ant-api03-"), "{env}");
+    let app = fs::read_to_string(root.join("app.py")).expect("Failed to read app.py");
+    assert!(app.starts_with("import os\n"), "{app}");
+    assert!(
+        app.contains("OpenAI(api_key=os.environ[\"OPENAI_API_KEY\"])"),
+        "{app}"
+    );
+    let chat = fs::read_to_string(root.join("chat.ts")).expect("Failed to read chat.ts");
+    assert!(
+        chat.contains("apiKey: process.env.ANTHROPIC_API_KEY"),
+        "{chat}"
+    );
+
+    // The key inside a longer string is left for the user
+    let findings: serde_json::Value =
+        serde_json::from_slice(&promptguard(root, &["secrets", "--json"]).stdout)
+            .expect("secrets --json is JSON");
+    assert_eq!(findings["items"].as_array().map(Vec::len), Some(1));
+    assert_eq!(findings["items"][0]["line"], 5);
+}
+
+/// Test `secrets migrate --json` reports keys as moved only once they have been
+#[test]
+fn test_secrets_migrate_json_after_moving() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    git(root, &["init", "-q"]);
+    let app = "from openai import OpenAI\nclient = OpenAI(api_key=\"sk-proj-abcdefghijklmnopqrstuvwx\")\n";
+    fs::write(root.join("app.py"), app).expect("Failed to write app.py");
+
+    // .env isn't ignored yet, so git would commit the moved key
+    let refused = promptguard(root, &["--json", "secrets", "migrate", "-y"]);
+    assert_eq!(refused.status.code(), Some(1));
+    assert!(
+        refused.stdout.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&refused.stdout)
+    );
+    let stderr = String::from_utf8_lossy(&refused.stderr);
+    assert!(stderr.contains("PG003"), "{stderr}");
+    assert_eq!(
+        fs::read_to_string(root.join("app.py")).expect("Failed to read app.py"),
+        app
+    );
+    assert!(!root.join(".env").exists());
+
+    fs::write(root.join(".gitignore"), ".env\n").expect("Failed to write .gitignore");
+    let migrated = promptguard_json(root, &["secrets", "migrate", "-y"]);
+    assert_eq!(migrated["moved"][0]["env_var"], "OPENAI_API_KEY");
+    assert!(fs::read_to_string(root.join(".env"))
+        .expect("Failed to read .env")
+        .contains("OPENAI_API_KEY=sk-proj-"));
+}
+
+// =============================================================================
+// BACKUP COMMAND TESTS - Snapshot Store
+// =============================================================================
+
+/// Test apply snapshots files into .promptguard/backups/ and `backup` manages them
+#[test]
+fn test_backup_snapshots_from_apply() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).expect("Failed to create .git");
+    let app = "from openai import OpenAI\nclient = OpenAI()\n";
+    fs::write(root.join("app.py"), app).expect("Failed to write app.py");
+    let init = promptguard(
+        root,
+        &[
+            "init",
+            "-y",
+            "--api-key",
+            "pg_sk_test_backup123456789012345678901",
+            "--provider",
+            "openai",
+        ],
+    );
+    assert!(
+        init.status.success(),
+        "{}",
+        String::from_utf8_lossy(&init.stderr)
+    );
+
+    // A file added after init is picked up by apply
+    fs::write(root.join("worker.py"), app).expect("Failed to write worker.py");
+    let apply = promptguard(root, &["apply", "-y"]);
+    assert!(
+        apply.status.success(),
+        "{}",
+        String::from_utf8_lossy(&apply.stderr)
+    );
+    assert_ne!(
+        fs::read_to_string(root.join("worker.py")).expect("worker.py"),
+        app
+    );
+    assert!(!root.join("worker.py.bak").exists());
+
+    let list = promptguard(root, &["backup", "list", "--json"]);
+    let entries: serde_json::Value =
+        serde_json::from_slice(&list.stdout).expect("backup list --json should be JSON");
+    assert_eq!(entries["schema_version"], 1);
+    assert_eq!(entries["items"][0]["file"], "worker.py");
+    assert_eq!(entries["items"][0]["operation"], "apply");
+
+    let diff = promptguard(root, &["backup", "diff", "worker.py"]);
+    let diff = String::from_utf8_lossy(&diff.stdout);
+    assert!(diff.contains("+client = OpenAI("), "{diff}");
+
+    let restore = promptguard(root, &["backup", "restore", "worker.py", "-y"]);
+    assert!(
+        restore.status.success(),
+        "{}",
+        String::from_utf8_lossy(&restore.stderr)
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("worker.py")).expect("worker.py"),
+        app
+    );
+
+    // The restore snapshotted the transformed file; keep only that one
+    let prune = promptguard(root, &["backup", "prune", "--keep", "1", "--json"]);
+    let removed: serde_json::Value =
+        serde_json::from_slice(&prune.stdout).expect("backup prune --json should be JSON");
+    assert_eq!(removed["schema_version"], 1);
+    let removed = &removed["items"];
+    assert_eq!(removed.as_array().map(Vec::len), Some(1));
+    assert_eq!(removed[0]["operation"], "apply");
+}
+
+// =============================================================================
+// VERIFY COMMAND TESTS - Transformation Drift
+// =============================================================================
+
+/// Test verify fails once a managed call site stops pointing at the proxy
+#[test]
+fn test_verify_reports_transformation_drift() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).expect("Failed to create .git");
+    fs::write(
+        root.join("app.py"),
+        "from openai import OpenAI\nclient = OpenAI()\n",
+    )
+    .expect("Failed to write app.py");
+    let init = promptguard(
+        root,
+        &[
+            "init",
+            "-y",
+            "--api-key",
+            "pg_sk_test_verify12345678901234567890",
+        ],
+    );
+    assert!(
+        init.status.success(),
+        "{}",
+        String::from_utf8_lossy(&init.stderr)
+    );
+
+    let clean = promptguard(root, &["verify"]);
+    assert!(
+        clean.status.success(),
+        "{}",
+        String::from_utf8_lossy(&clean.stdout)
+    );
+
+    let content = fs::read_to_string(root.join("app.py")).expect("app.py");
+    fs::write(
+        root.join("app.py"),
+        content.replace(
+            "https://api.promptguard.co/api/v1",
+            "https://api.openai.com/v1",
+        ),
+    )
+    .expect("Failed to edit app.py");
+    let drifted = promptguard(root, &["verify", "--offline"]);
+    assert_eq!(drifted.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&drifted.stderr);
+    assert!(
+        stderr.contains("app.py:") && stderr.contains("points at https://api.openai.com/v1"),
+        "{stderr}"
+    );
+
+    let sarif = promptguard(root, &["verify", "--format", "sarif"]);
+    assert_eq!(sarif.status.code(), Some(1));
+    let log: serde_json::Value =
+        serde_json::from_slice(&sarif.stdout).expect("verify --format sarif should be JSON");
+    let results = log["runs"][0]["results"].as_array().expect("results");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["ruleId"], "PG002");
+    assert_eq!(
+        results[0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+        "app.py"
+    );
+}
+
+/// Test scan --format sarif reports unguarded clients and other gateways
+/// with paths relative to the git repository
+#[test]
+fn test_scan_sarif_output() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).expect("Failed to create .git");
+    fs::create_dir(root.join("svc")).expect("Failed to create svc");
+    fs::write(
+        root.join("svc/app.py"),
+        "from openai import OpenAI\nclient = OpenAI()\nother = OpenAI(base_url=\"https://gateway.example.com/v1\")\n",
+    )
+    .expect("Failed to write app.py");
+
+    let output = promptguard(&root.join("svc"), &["scan", "--format", "sarif"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let log: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("scan --format sarif should be JSON");
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "PromptGuard");
+    let results = run["results"].as_array().expect("results");
+    let summary: Vec<(String, String, u64)> = results
+        .iter()
+        .map(|r| {
+            let location = &r["locations"][0]["physicalLocation"];
+            (
+                r["ruleId"].as_str().unwrap_or_default().to_string(),
+                location["artifactLocation"]["uri"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                location["region"]["startLine"].as_u64().unwrap_or_default(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("PG001".to_string(), "svc/app.py".to_string(), 2),
+            ("PG002".to_string(), "svc/app.py".to_string(), 3),
+        ]
+    );
+}
+
+// =============================================================================
+// COVERAGE COMMAND TESTS - Guarded Call Sites
+// =============================================================================
+
+/// Test coverage counts transformed call sites and lists the rest as gaps
+#[test]
+fn test_coverage_reports_gaps() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).expect("Failed to create .git");
+    fs::write(
+        root.join("app.py"),
+        "from openai import OpenAI\nclient = OpenAI()\n",
+    )
+    .expect("Failed to write app.py");
+    let init = promptguard(
+        root,
+        &[
+            "init",
+            "-y",
+            "--api-key",
+            "pg_sk_test_coverage123456789012345678",
+        ],
+    );
+    assert!(
+        init.status.success(),
+        "{}",
+        String::from_utf8_lossy(&init.stderr)
+    );
+    // Added after init, so never transformed
+    fs::write(
+        root.join("worker.py"),
+        "from openai import OpenAI\nopts = {}\nclient = OpenAI(**opts)\n",
+    )
+    .expect("Failed to write worker.py");
+
+    let output = promptguard(root, &["coverage", "--json"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("coverage --json should be JSON");
+    assert_eq!(report["total"], 2);
+    assert_eq!(report["guarded"], 1);
+    assert_eq!(report["coverage_percent"], 50.0);
+    let sites = report["call_sites"].as_array().expect("call_sites");
+    assert_eq!(sites[0]["file"], "app.py");
+    assert_eq!(sites[0]["guard"], "transform");
+    assert_eq!(sites[1]["file"], "worker.py");
+    assert_eq!(sites[1]["line"], 3);
+    assert!(sites[1]["guard"].is_null());
+
+    let below = promptguard(root, &["coverage", "--min", "80"]);
+    assert_eq!(below.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&below.stdout).contains("worker.py:3"));
+}
+
+// =============================================================================
+// AUDIT COMMAND TESTS - LLM Usage Inventory
+// =============================================================================
+
+/// Test audit lists each client with its class, models and guard status
+#[test]
+fn test_audit_exports_inventory() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).expect("Failed to create .git");
+    fs::write(
+        root.join("app.py"),
+        "from openai import OpenAI\nMODEL = \"gpt-4o-mini\"\n\nclient = OpenAI()\nclient.chat.completions.create(model=MODEL, messages=[])\n",
+    )
+    .expect("Failed to write app.py");
+    let init = promptguard(
+        root,
+        &[
+            "init",
+            "-y",
+            "--api-key",
+            "pg_sk_test_audit12345678901234567890",
+        ],
+    );
+    assert!(
+        init.status.success(),
+        "{}",
+        String::from_utf8_lossy(&init.stderr)
+    );
+    // Added after init, so never transformed
+    fs::write(
+        root.join("chat.ts"),
+        "import Anthropic from \"@anthropic-ai/sdk\";\nconst claude = new Anthropic();\nawait claude.messages.create({ model: \"claude-3-5-haiku-latest\" });\n",
+    )
+    .expect("Failed to write chat.ts");
+
+    let output = promptguard(root, &["audit", "--format", "json"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("audit --format json should be JSON");
+    assert_eq!(report["summary"]["call_sites"], 2);
+    let sites = report["call_sites"].as_array().expect("call_sites");
+    assert_eq!(sites[0]["file"], "app.py");
+    assert_eq!(sites[0]["client"], "OpenAI");
+    assert_eq!(sites[0]["models"], serde_json::json!(["gpt-4o-mini"]));
+    assert_eq!(sites[0]["guard"], "transform");
+    assert_eq!(sites[1]["provider"], "anthropic");
+    assert_eq!(sites[1]["client"], "Anthropic");
+    assert_eq!(
+        sites[1]["models"],
+        serde_json::json!(["claude-3-5-haiku-latest"])
+    );
+    assert_eq!(sites[1]["guarded"], false);
+
+    let csv_path = root.join("bom.csv");
+    let csv = promptguard(
+        root,
+        &[
+            "audit",
+            "--format",
+            "csv",
+            "-o",
+            csv_path.to_str().expect("utf-8 path"),
+        ],
+    );
+    assert!(csv.status.success());
+    let csv = fs::read_to_string(&csv_path).expect("bom.csv");
+    let mut rows = csv.lines();
+    assert_eq!(
+        rows.next(),
+        Some("provider,file,line,column,language,client,models,guarded,guard,gap")
+    );
+    let row = rows.next().expect("app.py row");
+    assert!(row.starts_with("openai,app.py,"), "{row}");
+    assert!(
+        row.ends_with(",python,OpenAI,gpt-4o-mini,true,transform,"),
+        "{row}"
+    );
+}
+
+// =============================================================================
+// REPORT COMMAND TESTS - Security Review Documents
+// =============================================================================
+
+/// Test report writes Markdown to stdout and HTML by the output extension
+#[test]
+fn test_report_markdown_and_html() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).expect("Failed to create .git");
+    fs::write(
+        root.join("app.py"),
+        "from openai import OpenAI\nclient = OpenAI()\n",
+    )
+    .expect("Failed to write app.py");
+    let init = promptguard(
+        root,
+        &[
+            "init",
+            "-y",
+            "--api-key",
+            "pg_sk_test_report123456789012345678",
+        ],
+    );
+    assert!(
+        init.status.success(),
+        "{}",
+        String::from_utf8_lossy(&init.stderr)
+    );
+    fs::write(
+        root.join("worker.py"),
+        "from openai import OpenAI\nclient = OpenAI(base_url=\"https://<gateway>.example/v1\")\n",
+    )
+    .expect("Failed to write worker.py");
+
+    let md = promptguard(root, &["report"]);
+    assert!(
+        md.status.success(),
+        "{}",
+        String::from_utf8_lossy(&md.stderr)
+    );
+    let md = String::from_utf8_lossy(&md.stdout);
+    assert!(md.starts_with("# PromptGuard Security Report"), "{md}");
+    assert!(md.contains("- **Coverage:** 50.0% (1 of 2 call sites guarded)"));
+    assert!(md.contains("| Location | Provider | Client | Models | Routed by |"));
+    assert!(md.contains("| worker.py:2 | openai | OpenAI |  | ✗ base URL points at"));
+    assert!(md.contains("## Environment"));
+
+    let html_path = root.join("report.html");
+    let html = promptguard(
+        root,
+        &["report", "-o", html_path.to_str().expect("utf-8 path")],
+    );
+    assert!(html.status.success());
+    let html = fs::read_to_string(&html_path).expect("report.html");
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<td>worker.py:2</td>"));
+    assert!(html.contains("https://&lt;gateway&gt;.example/v1"));
+    assert!(!html.contains("<gateway>"));
+}
+
+// =============================================================================
+// LSP COMMAND TESTS - Editor Diagnostics
+// =============================================================================
+
+/// Test the language server publishes unrouted call sites and offers the transform
+#[test]
+fn test_lsp_diagnostics_and_code_action() {
+    use std::io::{Read, Write as _};
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).expect("Failed to create .git");
+    fs::write(
+        root.join("app.py"),
+        "from openai import OpenAI\nclient = OpenAI()\n",
+    )
+    .expect("Failed to write app.py");
+    let init = promptguard(
+        root,
+        &[
+            "init",
+            "-y",
+            "--api-key",
+            "pg_sk_test_lsp12345678901234567890",
+        ],
+    );
+    assert!(
+        init.status.success(),
+        "{}",
+        String::from_utf8_lossy(&init.stderr)
+    );
+    let source = "from openai import OpenAI\nclient = OpenAI()\n";
+    fs::write(root.join("worker.py"), source).expect("Failed to write worker.py");
+
+    let uri = format!("file://{}", root.join("worker.py").display());
+    let messages = [
+        serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+        serde_json::json!({"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+            "textDocument": {"uri": uri, "languageId": "python", "version": 1, "text": source}
+        }}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/codeAction", "params": {
+            "textDocument": {"uri": uri},
+            "range": {"start": {"line": 1, "character": 0}, "end": {"line": 1, "character": 0}},
+            "context": {"diagnostics": []}
+        }}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 3, "method": "shutdown"}),
+        serde_json::json!({"jsonrpc": "2.0", "method": "exit"}),
+    ];
+    let mut input = String::new();
+    for message in messages {
+        let body = message.to_string();
+        write!(input, "Content-Length: {}\r\n\r\n{body}", body.len()).expect("write");
+    }
+
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_promptguard"))
+        .arg("--air-gapped")
+        .arg("--path")
+        .arg(root)
+        .arg("lsp")
+        .env("XDG_CONFIG_HOME", root.join(".user-config"))
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to run promptguard lsp");
+    child
+        .stdin
+        .take()
+        .expect("stdin")
+        .write_all(input.as_bytes())
+        .expect("Failed to write to lsp");
+    let mut output = String::new();
+    child
+        .stdout
+        .take()
+        .expect("stdout")
+        .read_to_string(&mut output)
+        .expect("Failed to read lsp output");
+    assert!(child.wait().expect("lsp exit").success());
+
+    let replies: Vec<serde_json::Value> = output
+        .split("Content-Length: ")
+        .skip(1)
+        .map(|part| {
+            let body = part.split_once("\r\n\r\n").expect("framed message").1;
+            serde_json::from_str(body).expect("json message")
+        })
+        .collect();
+    let diagnostics = replies
+        .iter()
+        .find(|r| r["method"] == "textDocument/publishDiagnostics")
+        .expect("publishDiagnostics");
+    let diagnostic = &diagnostics["params"]["diagnostics"][0];
+    assert_eq!(diagnostic["code"], "PG001");
+    assert_eq!(diagnostic["source"], "promptguard");
+    assert_eq!(diagnostic["range"]["start"]["line"], 1);
+    assert_eq!(diagnostic["range"]["start"]["character"], 9);
+
+    let action = &replies.iter().find(|r| r["id"] == 2).expect("codeAction")["result"][0];
+    assert_eq!(action["title"], "Route through PromptGuard");
+    let new_text = action["edit"]["changes"][&uri][0]["newText"]
+        .as_str()
+        .expect("edit text");
+    assert!(new_text.contains("base_url=\"https://api.promptguard.co/api/v1\""));
+    // The buffer is edited, not the file
+    assert_eq!(
+        fs::read_to_string(root.join("worker.py")).expect("worker.py"),
+        source
+    );
+}
+
+// =============================================================================
+// COMPLETIONS COMMAND TESTS - Shell Scripts
+// =============================================================================
+
+/// Test completion scripts cover nested subcommands and flag values for every shell
+#[test]
+fn test_completions_scripts() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    let script = |shell: &str| {
+        let output = promptguard(root, &["completions", shell]);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).expect("utf-8 script")
+    };
+
+    let bash = script("bash");
+    assert!(bash.contains("complete -F _promptguard"));
+    assert!(bash.contains("promptguard__shim__inject)"));
+    assert!(bash.contains("compgen -W \"human json sarif\""));
+
+    let zsh = script("zsh");
+    assert!(zsh.starts_with("#compdef promptguard"));
+    assert!(zsh.contains("_promptguard__shim__inject() {"));
+    assert!(zsh.contains("'coverage:"));
+
+    let fish = script("fish");
+    assert!(fish.contains("complete -c promptguard -n '__fish_promptguard_path shim' -a inject"));
+    assert!(fish.contains("-l format -x -a 'human json sarif'"));
+
+    let powershell = script("powershell");
+    assert!(powershell.starts_with("Register-ArgumentCompleter -Native -CommandName 'promptguard'"));
+    assert!(powershell.contains("'promptguard;shim;inject' = @("));
+
+    assert!(!promptguard(root, &["completions", "tcsh"]).status.success());
+}
+
+// =============================================================================
+// GEN-DOCS COMMAND TESTS - Man Pages and Reference
+// =============================================================================
+
+/// Test gen-docs writes a man page per command and a Markdown reference
+#[test]
+fn test_gen_docs_writes_man_pages_and_reference() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    let out_dir = root.join("docs");
+    let output = promptguard(
+        root,
+        &[
+            "gen-docs",
+            "--out-dir",
+            out_dir.to_str().expect("utf-8 path"),
+        ],
+    );
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let root_page = fs::read_to_string(out_dir.join("man/promptguard.1")).expect("root man page");
+    assert!(root_page.starts_with(".TH PROMPTGUARD 1"));
+    assert!(root_page.contains("\\fBpromptguard\\-shim\\fR(1)"));
+    let page =
+        fs::read_to_string(out_dir.join("man/promptguard-shim-inject.1")).expect("nested man page");
+    assert!(page.contains(".SH SEE ALSO\n\\fBpromptguard\\-shim\\fR(1)"));
+    let scan = fs::read_to_string(out_dir.join("man/promptguard-scan.1")).expect("scan man page");
+    assert!(scan.contains("Possible values: human, json, sarif. Default: human"));
+    // Hidden commands are not documented
+    assert!(!out_dir.join("man/promptguard-gen-docs.1").exists());
+
+    let reference = fs::read_to_string(out_dir.join("cli-reference.md")).expect("reference");
+    assert!(reference.contains("  - [`promptguard shim`](#promptguard-shim)"));
+    assert!(reference.contains("## promptguard coverage"));
+    assert!(reference.contains("- `--path, --project-dir <DIR>`:"));
+    assert!(!reference.contains("gen-docs`]"));
+}
+
+// =============================================================================
+// HOOK COMMAND TESTS - Pre-commit Checks
+// =============================================================================
+
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .expect("Failed to run git");
+    assert!(status.success(), "git {args:?} failed");
+}
+
+/// Test `scan --staged --fail-on unguarded` fails only on staged unguarded clients
+#[test]
+fn test_scan_staged_fail_on_unguarded() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    git(root, &["init", "-q"]);
+    let unguarded = "from openai import OpenAI\nclient = OpenAI()\n";
+    fs::write(root.join("app.py"), unguarded).expect("Failed to write app.py");
+    fs::write(root.join("notes.py"), "print('hi')\n").expect("Failed to write notes.py");
+
+    git(root, &["add", "notes.py"]);
+    let output = promptguard(root, &["scan", "--staged", "--fail-on", "unguarded"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    git(root, &["add", "app.py"]);
+    let output = promptguard(
+        root,
+        &["scan", "--staged", "--fail-on", "unguarded", "--json"],
+    );
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("(app.py:2)"));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).expect("scan JSON");
+    assert_eq!(report["total_files_scanned"], 2);
+
+    // Without --fail-on findings don't change the exit code
+    assert!(promptguard(root, &["scan", "--staged"]).status.success());
+}
+
+/// Test `scan --fail-on detected` fails on any SDK usage, guarded or not
+#[test]
+fn test_scan_fail_on_detected() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).expect("Failed to create .git");
+    fs::write(root.join("notes.py"), "print('hi')\n").expect("Failed to write notes.py");
+    assert!(promptguard(root, &["scan", "--fail-on", "detected"])
+        .status
+        .success());
+
+    fs::write(
+        root.join("app.py"),
+        "from openai import OpenAI\nclient = OpenAI()\n",
+    )
+    .expect("Failed to write app.py");
+    let init = promptguard(
+        root,
+        &[
+            "init",
+            "-y",
+            "--api-key",
+            "pg_sk_test_failon1234567890123456789",
+        ],
+    );
+    assert!(init.status.success());
+
+    assert!(promptguard(root, &["scan", "--fail-on", "unguarded"])
+        .status
+        .success());
+    let output = promptguard(root, &["scan", "--fail-on", "detected"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("1 LLM SDK call site detected (OpenAI)")
+    );
+}
+
+/// Human scan output is a table row per provider and file, with call-site
+/// lines and guard status, truncated to `COLUMNS`
+#[test]
+fn test_scan_table_output() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("services/billing")).expect("Failed to create dir");
+    fs::write(
+        root.join("services/billing/invoice.py"),
+        "from openai import OpenAI\nclient = OpenAI()\n\
+         proxied = OpenAI(base_url=\"https://api.promptguard.co/api/v1\")\n",
+    )
+    .expect("Failed to write invoice.py");
+
+    let output = promptguard(root, &["scan"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("PROVIDER  FILE                         CALLS  LINES  GUARDED"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("OpenAI    services/billing/invoice.py  2      2, 3   partial (1/2)"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("Guarded: 1 of 2"), "{stdout}");
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_promptguard"))
+        .args(["--air-gapped", "--path"])
+        .arg(root)
+        .arg("scan")
+        .env("COLUMNS", "50")
+        .env("XDG_CONFIG_HOME", root.join(".user-config"))
+        .output()
+        .expect("Failed to run promptguard");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("OpenAI    …/invoice.py"), "{stdout}");
+}
+
+/// --progress json streams NDJSON events on stderr alongside the JSON result
+#[test]
+fn test_scan_progress_json_events() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    let client = "from openai import OpenAI\nclient = OpenAI()\n";
+    fs::write(root.join("a.py"), client).expect("Failed to write a.py");
+    fs::write(root.join("b.py"), client).expect("Failed to write b.py");
+
+    let output = promptguard(root, &["scan", "--json", "--progress", "json"]);
+    assert!(output.status.success());
+    let result: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be the JSON result");
+    assert_eq!(result["schema_version"], 1);
+
+    let events: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each stderr line should be an event"))
+        .collect();
+    assert_eq!(events.len(), 4, "{events:?}");
+    assert_eq!(events[0]["event"], "start");
+    assert_eq!(events[0]["total"], 2);
+    assert_eq!(events[1]["event"], "progress");
+    assert_eq!(events[1]["done"], 1);
+    assert!(events[1]["file"]
+        .as_str()
+        .is_some_and(|f| f == "a.py" || f == "b.py"));
+    assert_eq!(events[3]["event"], "finish");
+    assert_eq!(events[3]["done"], 2);
+    assert_eq!(events[3]["findings"], 2);
+}
+
+/// Test hook install/uninstall for git, pre-commit and husky keeps other hooks intact
+#[test]
+fn test_hook_install_and_uninstall() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    git(root, &["init", "-q"]);
+
+    let hook = root.join(".git/hooks/pre-commit");
+    fs::write(&hook, "#!/bin/sh\nexec make lint\n").expect("Failed to write hook");
+    assert!(promptguard(root, &["hook", "install"]).status.success());
+    let content = fs::read_to_string(&hook).expect("pre-commit hook");
+    assert!(content.starts_with("#!/bin/sh\n# >>> promptguard pre-commit hook >>>"));
+    assert!(content.contains("promptguard scan --staged --fail-on unguarded || exit $?"));
+    assert!(content.ends_with("exec make lint\n"));
+    // Installing twice doesn't duplicate the block
+    assert!(promptguard(root, &["hook", "install"]).status.success());
+    assert_eq!(fs::read_to_string(&hook).expect("pre-commit hook"), content);
+    assert!(promptguard(root, &["hook", "uninstall"]).status.success());
+    assert_eq!(
+        fs::read_to_string(&hook).expect("pre-commit hook"),
+        "#!/bin/sh\nexec make lint\n"
+    );
+
+    let config = "repos:\n-   repo: https://github.com/psf/black\n    rev: 24.1.0\n    hooks:\n    -   id: black\n\nci:\n    autofix_prs: false\n";
+    fs::write(root.join(".pre-commit-config.yaml"), config).expect("Failed to write config");
+    let output = promptguard(root, &["hook", "install", "--json"]);
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).expect("hook JSON");
+    assert_eq!(result["manager"], "pre-commit");
+    assert_eq!(result["changed"], true);
+    let updated: serde_yaml::Value = serde_yaml::from_str(
+        &fs::read_to_string(root.join(".pre-commit-config.yaml")).expect("config"),
+    )
+    .expect("valid YAML");
+    assert_eq!(updated["repos"][1]["repo"], "local");
+    assert_eq!(updated["repos"][1]["hooks"][0]["id"], "promptguard");
+    assert_eq!(updated["ci"]["autofix_prs"], false);
+    assert!(promptguard(root, &["hook", "uninstall"]).status.success());
+    assert_eq!(
+        fs::read_to_string(root.join(".pre-commit-config.yaml")).expect("config"),
+        config
+    );
+
+    let output = promptguard(root, &["hook", "install", "--manager", "husky"]);
+    assert!(output.status.success());
+    let husky = fs::read_to_string(root.join(".husky/pre-commit")).expect("husky hook");
+    assert!(!husky.starts_with("#!"));
+    assert!(
+        promptguard(root, &["hook", "uninstall", "--manager", "husky"])
+            .status
+            .success()
+    );
+    assert!(!root.join(".husky/pre-commit").exists());
+}
+
+// =============================================================================
+// CI COMMAND TESTS - Annotations and Exit Codes
+// =============================================================================
+
+/// Test `ci` annotates unguarded call sites and follows the exit-code contract
+#[test]
+fn test_ci_annotations_and_exit_codes() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join(".git")).expect("Failed to create .git");
+    let package = root.join("services/api");
+    fs::create_dir_all(&package).expect("Failed to create package");
+    let client = "from openai import OpenAI\nclient = OpenAI()\n";
+    fs::write(package.join("app.py"), client).expect("Failed to write app.py");
+
+    // No config is a configuration error
+    assert_eq!(promptguard(&package, &["ci"]).status.code(), Some(3));
+
+    let init = promptguard(
+        &package,
+        &[
+            "init",
+            "-y",
+            "--force",
+            "--api-key",
+            "pg_sk_test_ci12345678901234567890123",
+        ],
+    );
+    assert!(
+        init.status.success(),
+        "{}",
+        String::from_utf8_lossy(&init.stderr)
+    );
+    let output = promptguard(&package, &["ci"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("1/1 call sites routed"));
+
+    fs::write(package.join("worker.py"), client).expect("Failed to write worker.py");
+    let output = promptguard(&package, &["ci"]);
+    assert_eq!(output.status.code(), Some(2));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // Paths are relative to the repository root, for GitHub to place them
+    assert!(
+        stdout.contains("::error file=services/api/worker.py,line=2,col=10,title=PG001 "),
+        "{stdout}"
+    );
+
+    fs::write(package.join(".promptguard.json"), "{").expect("Failed to write config");
+    assert_eq!(promptguard(&package, &["ci"]).status.code(), Some(3));
+    assert_eq!(
+        promptguard(&package, &["--ci", "status"]).status.code(),
+        Some(3)
+    );
+}
+
+// =============================================================================
+// DOCTOR COMMAND TESTS - Key Leaks
+// =============================================================================
+
+/// Test doctor reports keys hardcoded in tracked files, by file and line
+#[test]
+fn test_doctor_reports_key_leaks() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    git(root, &["init", "-q"]);
+    fs::write(
+        root.join("app.py"),
+        "from openai import OpenAI\nclient = OpenAI(api_key=\"sk-proj-abcdefghijklmnopqrstuvwx\")\n",
+    )
+    .expect("Failed to write app.py");
+    fs::write(
+        root.join("fixture.py"),
+        "KEY = \"pg_sk_test_abcdefghijklmnop1234\"  # promptguard: ignore\n",
+    )
+    .expect("Failed to write fixture.py");
+    // Untracked files are not scanned
+    fs::write(
+        root.join("scratch.py"),
+        "KEY = \"sk-ant-REDACTED\"\n",
+    )
+    .expect("Failed to write scratch.py");
+    git(root, &["add", "app.py", "fixture.py"]);
+
+    let output = promptguard(root, &["doctor", "--json"]);
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("doctor --json output is only JSON");
+    let leaks = report["key_leaks"].as_array().expect("key_leaks");
+    assert_eq!(leaks.len(), 1, "{leaks:?}");
+    assert_eq!(leaks[0]["file"], "app.py");
+    assert_eq!(leaks[0]["line"], 2);
+    assert_eq!(leaks[0]["kind"], "openai");
+    assert_eq!(report["health"], "error");
+}
+
+// =============================================================================
+// KEY COMMAND TESTS - Per-Environment Keys
+// =============================================================================
+
+/// Test `key set` writes each profile's key to its env files and keeps prod
+/// keys out of committed files
+#[test]
+fn test_key_set_per_profile() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    git(root, &["init", "-q"]);
+    fs::write(root.join(".gitignore"), ".env.local\n").expect("Failed to write .gitignore");
+    let mut config = PromptGuardConfig::new(
+        "pg_sk_test_perenv1234567890123456789".to_string(),
+        "https://api.promptguard.co/api/v1".to_string(),
+        vec!["openai".to_string()],
+    )
+    .expect("Failed to create config");
+    config.env_file = ".env.local".to_string().into();
+    config.profiles.insert(
+        "prod".to_string(),
+        promptguard::config::ConfigProfile {
+            env_file: Some(".env.prod".to_string().into()),
+            ..Default::default()
+        },
+    );
+    ConfigManager::new(Some(root.join(".promptguard.json")))
+        .expect("Failed to create config manager")
+        .save(&config)
+        .expect("Failed to save config");
+
+    let test_key = "pg_sk_test_perenv1234567890123456789";
+    let prod_key = "pg_sk_prod_perenv1234567890123456789";
+    assert!(promptguard(root, &["key", "set", test_key])
+        .status
+        .success());
+    assert!(fs::read_to_string(root.join(".env.local"))
+        .expect(".env.local")
+        .contains(test_key));
+
+    // .env.prod isn't ignored, so git would commit the key
+    let refused = promptguard(root, &["--profile", "prod", "key", "set", prod_key]);
+    assert!(!refused.status.success());
+    assert!(String::from_utf8_lossy(&refused.stderr).contains("git would commit it"));
+    assert!(!root.join(".env.prod").exists());
+
+    fs::write(root.join(".gitignore"), ".env.local\n.env.prod\n")
+        .expect("Failed to write .gitignore");
+    assert!(
+        promptguard(root, &["--profile", "prod", "key", "set", prod_key])
+            .status
+            .success()
+    );
+    assert!(!fs::read_to_string(root.join(".env.local"))
+        .expect(".env.local")
+        .contains(prod_key));
+
+    let output = promptguard(root, &["key", "list", "--json"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).expect("key JSON");
+    assert_eq!(report["entries"][0]["file"], ".env.local");
+    assert_eq!(report["entries"][0]["key_type"], "test");
+    assert_eq!(report["entries"][1]["profiles"][0], "prod");
+    assert_eq!(report["entries"][1]["key_type"], "prod");
+
+    // A prod key that became committable is a finding
+    fs::write(root.join(".gitignore"), ".env.local\n").expect("Failed to write .gitignore");
+    let output = promptguard(root, &["key", "list"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+// =============================================================================
+// INIT COMMAND TESTS - Project Association
+// =============================================================================
+
+/// Test `init --project` links the new config to a backend project
+#[test]
+fn test_init_links_project() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).expect("Failed to create .git");
+    fs::write(
+        root.join("app.py"),
+        "from openai import OpenAI\nclient = OpenAI()\n",
+    )
+    .expect("Failed to write app.py");
+
+    let init = promptguard(
+        root,
+        &[
+            "init",
+            "-y",
+            "--api-key",
+            "pg_sk_test_projects12345678901234567",
+            "--project",
+            "proj_checkout",
+        ],
+    );
+    assert!(
+        init.status.success(),
+        "{}",
+        String::from_utf8_lossy(&init.stderr)
+    );
+    let config = ConfigManager::new(Some(root.join(".promptguard.json")))
+        .expect("Failed to create config manager")
+        .load()
+        .expect("Failed to load config");
+    assert_eq!(config.project_id.as_deref(), Some("proj_checkout"));
+}
+
+/// Test the init wizard fails without a terminal instead of taking every default
+#[test]
+fn test_init_interactive_fails_without_answers() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).expect("Failed to create .git");
+    let app = "from openai import OpenAI\nclient = OpenAI()\n";
+    fs::write(root.join("app.py"), app).expect("Failed to write app.py");
+
+    // `promptguard` runs with stdin at end of input, like `</dev/null`
+    let output = promptguard(
+        root,
+        &[
+            "init",
+            "--interactive",
+            "--offline",
+            "--api-key",
+            "pg_sk_test_wizard1234567890123456789",
+        ],
+    );
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("PG009"), "{stderr}");
+    assert!(stderr.contains("stdin is not a terminal"), "{stderr}");
+
+    assert_eq!(
+        fs::read_to_string(root.join("app.py")).expect("Failed to read app.py"),
+        app
+    );
+    assert!(!root.join(".env").exists());
+    assert!(!root.join(".promptguard.json").exists());
+}
+
+// =============================================================================
+// JSON OUTPUT TESTS - Global --json
+// =============================================================================
+
+/// Run the CLI with `--json` and parse stdout, which must be one JSON document
+fn promptguard_json(dir: &std::path::Path, args: &[&str]) -> serde_json::Value {
+    let mut args = args.to_vec();
+    args.push("--json");
+    let output = promptguard(dir, &args);
+    assert!(
+        output.status.success(),
+        "{args:?}: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let value: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be a single JSON document");
+    assert_eq!(value["schema_version"], 1, "{args:?}: {value}");
+    value
+}
+
+/// Test init, apply, disable, enable, config, key and revert print versioned JSON
+#[test]
+fn test_json_output_for_lifecycle_commands() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).expect("Failed to create .git");
+    let app = "from openai import OpenAI\nclient = OpenAI()\n";
+    fs::write(root.join("app.py"), app).expect("Failed to write app.py");
+    let init = promptguard_json(
+        root,
+        &[
+            "init",
+            "-y",
+            "--api-key",
+            "pg_sk_test_json1234567890123456789012",
+            "--provider",
+            "openai",
+        ],
+    );
+    assert_eq!(init["changed"], true);
+    assert_eq!(init["providers"], serde_json::json!(["openai"]));
+    assert_eq!(init["files_modified"], serde_json::json!(["app.py"]));
+    assert_eq!(
+        init["config_files"],
+        serde_json::json!([".promptguard.json"])
+    );
+    assert_eq!(init["env_files"], serde_json::json!([".env"]));
+
+    fs::write(root.join("worker.py"), app).expect("Failed to write worker.py");
+    let preview = promptguard_json(root, &["apply", "--dry-run"]);
+    assert_eq!(preview["dry_run"], true);
+    assert_eq!(preview["files_modified"], serde_json::json!(["worker.py"]));
+    assert!(preview["diffs"][0]["diff"]
+        .as_str()
+        .is_some_and(|d| d.contains("+client = OpenAI(")));
+    assert_eq!(
+        fs::read_to_string(root.join("worker.py")).expect("worker.py"),
+        app
+    );
+
+    // --json is not consent: confirmations still need --yes
+    let unconfirmed = promptguard(root, &["disable", "--json"]);
+    assert!(!unconfirmed.status.success());
+    let stderr = String::from_utf8_lossy(&unconfirmed.stderr);
+    assert!(stderr.contains("--json was passed"), "{stderr}");
+    assert!(unconfirmed.stdout.is_empty());
+
+    let disabled = promptguard_json(root, &["disable", "-y"]);
+    assert_eq!(disabled["enabled"], false);
+    assert_eq!(disabled["mode"], "static");
+    assert_eq!(disabled["restored_files"], serde_json::json!(["app.py"]));
+
+    let enabled = promptguard_json(root, &["enable", "-y"]);
+    assert_eq!(enabled["enabled"], true);
+    assert_eq!(
+        enabled["files_modified"],
+        serde_json::json!(["app.py", "worker.py"])
+    );
+
+    let config = promptguard_json(root, &["config", "get", "providers"]);
+    assert_eq!(config["key"], "providers");
+    assert_eq!(config["value"], serde_json::json!(["openai"]));
+    assert_eq!(
+        promptguard_json(root, &["config"])["providers"][0],
+        "openai"
+    );
+
+    let key = promptguard_json(root, &["key", "list"]);
+    assert_eq!(key["entries"][0]["key_type"], "test");
+
+    let reverted = promptguard_json(root, &["revert", "-y"]);
+    assert_eq!(reverted["config_removed"], true);
+    assert_eq!(reverted["key_removed_from"], serde_json::json!([".env"]));
+    assert!(!root.join(".promptguard.json").exists());
+
+    // Commands without a JSON result refuse the flag rather than print text
+    let refused = promptguard(root, &["--json", "uninstall", "-y"]);
+    assert!(!refused.status.success());
+    assert!(refused.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&refused.stderr);
+    assert!(stderr.contains("--json is not supported"), "{stderr}");
+}
+
+/// Without a terminal, confirmations fail fast instead of waiting on stdin
+/// unless --yes answers them
+#[test]
+fn test_prompts_fail_fast_without_a_terminal() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::write(
+        root.join("app.py"),
+        "from openai import OpenAI\nclient = OpenAI()\n",
+    )
+    .expect("Failed to write app.py");
+    let init = promptguard(
+        root,
+        &[
+            "init",
+            "--yes",
+            "--force",
+            "--api-key",
+            "pg_sk_test_noinput12345678901234567890",
+        ],
+    );
+    assert!(
+        init.status.success(),
+        "{}",
+        String::from_utf8_lossy(&init.stderr)
+    );
+
+    let disable = promptguard(root, &["disable"]);
+    assert!(!disable.status.success());
+    let stderr = String::from_utf8_lossy(&disable.stderr);
+    assert!(stderr.contains("stdin is not a terminal"), "{stderr}");
+    assert!(stderr.contains("--yes"), "{stderr}");
+
+    // --ci must not answer a destructive confirmation on the user's behalf
+    let disable = promptguard(root, &["--ci", "disable"]);
+    assert!(!disable.status.success());
+    let stderr = String::from_utf8_lossy(&disable.stderr);
+    assert!(stderr.contains("CI mode"), "{stderr}");
+    assert!(stderr.contains("--yes"), "{stderr}");
+
+    for args in [["disable", "--yes"], ["enable", "-y"]] {
+        let output = promptguard(root, &args);
+        assert!(
+            output.status.success(),
+            "{args:?}: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
+
+/// Failures carry a stable code on stderr, as a JSON object with --json
+#[test]
+fn test_errors_carry_stable_codes() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+
+    let output = promptguard(root, &["apply", "-y"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Error [PG001]: Not initialized"),
+        "{stderr}"
+    );
+
+    let output = promptguard(root, &["apply", "-y", "--json"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.stderr).expect("the error should be JSON");
+    assert_eq!(report["schema_version"], 1);
+    assert_eq!(report["error"]["code"], "PG001");
+    assert_eq!(report["error"]["exit_code"], 1);
+    assert!(report["error"]["message"]
+        .as_str()
+        .is_some_and(|m| m.starts_with("Not initialized")));
+}
+
+// =============================================================================
+// LOGGING TESTS - Verbosity Levels and Colors
+// =============================================================================
+
+/// -v explains skipped files on stderr, -vv adds query timings, and
+/// --quiet drops diagnostics even when -v is given
+#[test]
+fn test_verbose_logging_levels() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    let client = "import OpenAI from 'openai';\nconst client = new OpenAI();\n";
+    fs::write(root.join("app.ts"), client).expect("Failed to write");
+    fs::write(root.join("app.test.ts"), client).expect("Failed to write");
+
+    let stderr = |args: &[&str]| {
+        let output = promptguard(root, args);
+        assert!(output.status.success(), "{args:?}");
+        String::from_utf8_lossy(&output.stderr).to_string()
+    };
+
+    let default = stderr(&["scan"]);
+    assert!(!default.contains("[debug]"), "{default}");
+
+    let debug = stderr(&["-v", "scan"]);
+    assert!(
+        debug.contains("Skipping app.test.ts: matches exclude pattern '**/*.test.ts'"),
+        "{debug}"
+    );
+    assert!(debug.contains("Found 1 source files"), "{debug}");
+    assert!(!debug.contains("[trace]"), "{debug}");
+
+    let trace = stderr(&["-vv", "scan"]);
+    assert!(trace.contains("[trace] openai query on"), "{trace}");
+
+    let quiet = stderr(&["-q", "-v", "scan"]);
+    assert!(!quiet.contains("[debug]"), "{quiet}");
+}
+
+/// --quiet keeps commands' human-readable output off stdout
+#[test]
+fn test_quiet_prints_nothing_on_success() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).expect("Failed to create .git");
+    let app = "from openai import OpenAI\nclient = OpenAI()\n";
+    fs::write(root.join("app.py"), app).expect("Failed to write app.py");
+
+    for args in [
+        &[
+            "--quiet",
+            "init",
+            "-y",
+            "--api-key",
+            "pg_sk_test_quiet12345678901234567890",
+        ][..],
+        &["--quiet", "apply", "-y"],
+        &["--quiet", "status"],
+        &["--quiet", "doctor"],
+        &["--quiet", "scan"],
+        &["--quiet", "config"],
+    ] {
+        let output = promptguard(root, args);
+        assert!(
+            output.status.success(),
+            "{args:?}: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert!(
+            output.stdout.is_empty(),
+            "{args:?}: {}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+    }
+}
+
+/// `NO_COLOR` (when non-empty) and --no-color drop ANSI escapes and emoji
+#[test]
+fn test_no_color_drops_escapes_and_emoji() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::write(
+        root.join("app.ts"),
+        "import OpenAI from 'openai';\nconst client = new OpenAI();\n",
+    )
+    .expect("Failed to write");
+
+    let scan = |no_color: Option<&str>, args: &[&str]| {
+        let mut command = std::process::Command::new(env!("CARGO_BIN_EXE_promptguard"));
+        command
+            .args(["--air-gapped", "--path"])
+            .arg(root)
+            .args(args)
+            .arg("scan")
+            .env("XDG_CONFIG_HOME", root.join(".user-config"))
+            .env("CLICOLOR_FORCE", "1")
+            .env_remove("NO_COLOR");
+        if let Some(value) = no_color {
+            command.env("NO_COLOR", value);
+        }
+        let output = command.output().expect("Failed to run promptguard");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let styled = scan(None, &[]);
+    assert!(styled.contains("🛡"), "{styled}");
+
+    for plain in [scan(Some("1"), &[]), scan(None, &["--no-color"])] {
+        assert!(plain.contains("PromptGuard CLI v"), "{plain}");
+        assert!(!plain.contains('\u{1b}'), "{plain}");
+        assert!(!plain.contains("🛡") && !plain.contains("📊"), "{plain}");
+    }
+
+    let empty = scan(Some(""), &[]);
+    assert!(empty.contains("🛡"), "an empty NO_COLOR is ignored: {empty}");
+
+    // Commands' own lines are stripped too, not just the shared helpers'
+    let doctor = promptguard(root, &["--no-color", "doctor"]);
+    let stdout = String::from_utf8_lossy(&doctor.stdout);
+    assert!(stdout.contains("Running diagnostics"), "{stdout}");
+    assert!(
+        !stdout.contains("🩺") && !stdout.contains('\u{fe0f}'),
+        "{stdout}"
+    );
+}
+
+// =============================================================================
+// SECURITY TESTS - Path Traversal Prevention
+// =============================================================================
+
+/// Test that path traversal attempts are rejected in env file paths
+#[test]
+fn test_path_traversal_prevention() {
+    // These should be rejected
+    let malicious_paths = vec![
+        "../../../etc/passwd",
+        "..\\..\\windows\\system32",
+        "/etc/passwd",
+        "C:\\Windows\\system32",
+    ];
+
+    for path in malicious_paths {
+        let is_safe = !path.contains("..") && !path.starts_with('/') && !path.contains(":\\");
+        assert!(
+            !is_safe || path == path,
+            "Path traversal should be prevented for: {path}"
+        );
+    }
+
+    // These should be allowed
+    let safe_paths = vec![
+        ".env",
+        ".env.local",
+        "config/.env",
+        "environments/.env.prod",
+    ];
+
+    for path in safe_paths {
+        let is_safe = !path.contains("..") && !path.starts_with('/');
+        assert!(is_safe, "Safe path should be allowed: {path}");
+    }
+}
+
+/// Test `proxy_url` can be a per-provider map with a default
+#[test]
+fn test_per_provider_proxy_url_config() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config_path = temp_dir.path().join(".promptguard.json");
+    fs::write(
+        &config_path,
+        r#"{
+  "version": "1.0",
+  "api_key": "pg_sk_test_123456789012345678901234",
+  "proxy_url": {
+    "default": "https://us.promptguard.example/api/v1",
+    "anthropic": "https://eu.promptguard.example/api/v1"
+  },
+  "providers": ["openai", "anthropic"]
+}"#,
+    )
+    .expect("Failed to write");
+
+    let manager = ConfigManager::new(Some(config_path.clone())).expect("Failed to create manager");
+    let config = manager.load().expect("Per-provider config should load");
+    assert_eq!(
+        config.proxy_url.for_provider(Provider::OpenAI),
+        "https://us.promptguard.example/api/v1"
+    );
+    assert_eq!(
+        config.proxy_url.for_provider(Provider::Anthropic),
+        "https://eu.promptguard.example/api/v1"
+    );
+
+    let content = fs::read_to_string(&config_path).expect("Failed to read");
+    fs::write(&config_path, content.replace("https://eu", "http://eu")).expect("Failed to write");
+    assert!(
+        manager.load().is_err(),
+        "HTTP proxy for one provider should be rejected"
+    );
+
+    fs::write(
+        &config_path,
+        content.replace("\"anthropic\":", "\"antropic\":"),
+    )
+    .expect("Failed to write");
+    assert!(
+        manager.load().is_err(),
+        "Unknown provider keys should be rejected"
+    );
+}
+
+/// Test providers can read their API key from their own environment variable
+#[test]
+fn test_env_var_overrides_per_provider() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let mut config = PromptGuardConfig::new(
+        "pg_sk_test_123456789012345678901234".to_string(),
+        "https://api.promptguard.co/api/v1".to_string(),
+        vec!["openai".to_string(), "anthropic".to_string()],
+    )
+    .expect("Failed to create config");
+    config
+        .env_var_overrides
+        .insert("anthropic".to_string(), "ANTHROPIC_API_KEY".to_string());
+
+    let config_path = temp_dir.path().join(".promptguard.json");
+    let manager = ConfigManager::new(Some(config_path)).expect("Failed to create manager");
+    manager.save(&config).expect("Failed to save");
+    let loaded = manager.load().expect("Failed to load");
+    assert_eq!(loaded.env_var_for(Provider::OpenAI), "PROMPTGUARD_API_KEY");
+    assert_eq!(loaded.env_var_for(Provider::Anthropic), "ANTHROPIC_API_KEY");
+
+    let ts_file = temp_dir.path().join("app.ts");
+    fs::write(
+        &ts_file,
+        "import Anthropic from '@anthropic-ai/sdk';\nconst client = new Anthropic({});\n",
+    )
+    .expect("Failed to write");
+    transformer::transform_file(
+        &ts_file,
+        Provider::Anthropic,
+        loaded.proxy_url.for_provider(Provider::Anthropic),
+        loaded.env_var_for(Provider::Anthropic),
+    )
+    .expect("Transform should succeed");
+    let content = fs::read_to_string(&ts_file).expect("Failed to read");
+    assert!(content.contains("apiKey: process.env.ANTHROPIC_API_KEY"));
+}
+
+/// Test that proxy URLs are validated
+#[test]
+fn test_proxy_url_validation() {
+    // Valid URLs
+    let valid_urls = vec![
+        "https://api.promptguard.co/api/v1",
+        "https://custom.example.com/proxy",
+        "http://localhost:8080/api",
+        "http://127.0.0.1:3000/v1",
+    ];
+
+    for url in &valid_urls {
+        let is_valid = url.starts_with("https://")
+            || url.starts_with("http://localhost")
+            || url.starts_with("http://127.0.0.1");
+        assert!(is_valid, "Valid URL should be accepted: {url}");
+    }
+
+    // Invalid URLs (HTTP to remote hosts)
+    let invalid_urls = vec![
+        "http://api.promptguard.co/api/v1", // HTTP to remote
+        "http://evil.com/proxy",
+    ];
+
+    for url in &invalid_urls {
+        let is_valid = url.starts_with("https://")
+            || url.starts_with("http://localhost")
+            || url.starts_with("http://127.0.0.1");
+        assert!(!is_valid, "Invalid URL should be rejected: {url}");
+    }
+}
+
+/// Test the offline bundle's guide lists the commands air-gapped mode refuses
+#[test]
+fn test_package_offline_guide_lists_network_commands() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let bundle = temp_dir.path().join("bundle");
+    let output = promptguard(
+        temp_dir.path(),
+        &[
+            "package-offline",
+            "--output",
+            bundle.to_str().expect("UTF-8 path"),
+        ],
+    );
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let guide = fs::read_to_string(bundle.join("docs/AIR_GAPPED.md")).expect("guide");
+    let (offline, online) = guide
+        .split_once("## Disabled in air-gapped mode")
+        .expect("online section");
+    assert!(offline.contains("- `promptguard verify`"), "{offline}");
+    for command in ["stats", "key --rotate", "scan --text/--file"] {
+        assert!(
+            online.contains(&format!("- `promptguard {command}`")),
+            "{online}"
+        );
+    }
+    assert!(!online.contains("`promptguard verify`"), "{online}");
+}
+
+/// Test each demo run gets its own directory, removed unless `--keep`
+#[test]
+fn test_demo_uses_a_fresh_directory_per_run() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let demo = |args: &[&str]| {
+        let output = promptguard(temp_dir.path(), args);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let result: serde_json::Value =
+            serde_json::from_slice(&output.stdout).expect("Output should be JSON");
+        std::path::PathBuf::from(result["project_dir"].as_str().expect("project_dir"))
+    };
+
+    let kept = demo(&["demo", "--seed", "7", "--keep", "--json"]);
+    let removed = demo(&["demo", "--seed", "7", "--json"]);
+    assert_ne!(kept, removed);
+    assert!(kept.join(".promptguard.json").exists());
+    assert!(!removed.exists());
+    fs::remove_dir_all(kept).expect("Failed to clean up");
 }
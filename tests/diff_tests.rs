@@ -0,0 +1,65 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+/// Integration tests for transformation previews
+///
+/// These tests verify the unified diffs shown before a transform and the
+/// text edits the journal records to undo one.
+use std::fmt::Write;
+
+use promptguard::transformer::diff::{text_edits, unified_diff};
+
+#[test]
+fn test_identical_has_no_diff() {
+    assert_eq!(unified_diff("a\nb\n", "a\nb\n", "a/x", "b/x"), "");
+}
+
+#[test]
+fn test_single_hunk() {
+    let old = "from openai import OpenAI\n\nclient = OpenAI()\n";
+    let new = "import os\n\nfrom openai import OpenAI\n\nclient = OpenAI(\n    base_url=\"x\"\n)\n";
+    let diff = unified_diff(old, new, "a/app.py", "b/app.py");
+    assert_eq!(
+        diff,
+        "--- a/app.py\n+++ b/app.py\n@@ -1,3 +1,7 @@\n+import os\n+\n from openai import OpenAI\n \n-client = OpenAI()\n+client = OpenAI(\n+    base_url=\"x\"\n+)\n"
+    );
+}
+
+#[test]
+fn test_distant_changes_split_into_hunks() {
+    let old = (1..=20).fold(String::new(), |mut s, i| {
+        let _ = writeln!(s, "line {i}");
+        s
+    });
+    let new = old
+        .replace("line 2\n", "line two\n")
+        .replace("line 19\n", "line nineteen\n");
+    let diff = unified_diff(&old, &new, "a", "b");
+    let headers: Vec<&str> = diff.lines().filter(|l| l.starts_with("@@")).collect();
+    assert_eq!(headers, vec!["@@ -1,5 +1,5 @@", "@@ -16,5 +16,5 @@"]);
+}
+
+#[test]
+fn test_text_edits_round_trip() {
+    let old = "from openai import OpenAI\n\nclient = OpenAI()\nprint(1)\n";
+    let new = "import os\n\nfrom openai import OpenAI\n\nclient = OpenAI(\n    base_url=\"x\"\n)\nprint(1)\n";
+    let edits = text_edits(old, new);
+    assert_eq!(edits.len(), 2);
+    assert_eq!(edits[0].original, "");
+    assert_eq!(edits[0].replacement, "import os\n\n");
+    assert_eq!(edits[1].original, "client = OpenAI()\n");
+
+    let mut reverted = new.to_string();
+    for edit in edits.iter().rev() {
+        assert_eq!(&reverted[edit.start..edit.end], edit.replacement);
+        reverted.replace_range(edit.start..edit.end, &edit.original);
+    }
+    assert_eq!(reverted, old);
+}
+
+#[test]
+fn test_new_file() {
+    let diff = unified_diff("", "KEY=value\n", "/dev/null", "b/.env");
+    assert_eq!(
+        diff,
+        "--- /dev/null\n+++ b/.env\n@@ -0,0 +1 @@\n+KEY=value\n"
+    );
+}
@@ -0,0 +1,126 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+/// Integration tests for the transformation journal
+///
+/// These tests verify that recorded edits are undone exactly, that edited
+/// files are reported as conflicts, and that undoing a selection keeps the
+/// other entries usable.
+use std::fs;
+use tempfile::TempDir;
+
+use promptguard::transformer::diff::text_edits;
+use promptguard::transformer::Journal;
+use promptguard::types::Provider;
+
+#[test]
+fn test_revert_undoes_stacked_transforms() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("app.py");
+    let v0 = "from openai import OpenAI\nclient = OpenAI()\n";
+    let v1 = "import os\nfrom openai import OpenAI\nclient = OpenAI(base_url=\"x\")\n";
+    let v2 = "import os\nfrom openai import OpenAI\nclient = OpenAI(base_url=\"x\")\nother = 1\n";
+    fs::write(&file, v2).unwrap();
+
+    let mut journal = Journal::default();
+    journal.record(dir.path(), &file, Provider::OpenAI, &text_edits(v0, v1));
+    journal.record(dir.path(), &file, Provider::Anthropic, &text_edits(v1, v2));
+    journal.save(dir.path()).unwrap();
+
+    let mut journal = Journal::load(dir.path()).unwrap();
+    assert_eq!(journal.files(), vec!["app.py"]);
+    let report = journal.revert_where(dir.path(), |_| true).unwrap();
+    assert_eq!(report.reverted, vec!["app.py"]);
+    assert_eq!(fs::read_to_string(&file).unwrap(), v0);
+    assert!(journal.is_empty());
+}
+
+#[test]
+fn test_revert_reports_conflicts() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("app.py");
+    let v0 = "client = OpenAI()\n";
+    let v1 = "client = OpenAI(base_url=\"x\")\n";
+    fs::write(&file, "client = OpenAI(base_url=\"edited\")\n").unwrap();
+
+    let mut journal = Journal::default();
+    journal.record(dir.path(), &file, Provider::OpenAI, &text_edits(v0, v1));
+    let report = journal.revert_where(dir.path(), |_| true).unwrap();
+
+    assert_eq!(report.conflicts, vec!["app.py"]);
+    assert_eq!(journal.entries.len(), 1);
+}
+
+#[test]
+fn test_revert_where_keeps_other_providers_edits() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("app.py");
+    let v0 = "a = OpenAI()\nb = Anthropic()\n";
+    let v1 = "import os\na = OpenAI(base_url=\"x\")\nb = Anthropic()\n";
+    let v2 = "import os\na = OpenAI(base_url=\"x\")\nb = Anthropic(base_url=\"y\")\n";
+    fs::write(&file, v2).unwrap();
+    let mut journal = Journal::default();
+    journal.record(dir.path(), &file, Provider::OpenAI, &text_edits(v0, v1));
+    journal.record(dir.path(), &file, Provider::Anthropic, &text_edits(v1, v2));
+
+    let report = journal
+        .revert_where(dir.path(), |e| e.provider == Provider::OpenAI)
+        .unwrap();
+    assert_eq!(report.reverted, vec!["app.py"]);
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "a = OpenAI()\nb = Anthropic(base_url=\"y\")\n"
+    );
+    assert!(journal
+        .entries
+        .iter()
+        .all(|e| e.provider == Provider::Anthropic));
+
+    // The kept entries were moved, so they still undo exactly
+    let report = journal.revert_where(dir.path(), |_| true).unwrap();
+    assert_eq!(report.reverted, vec!["app.py"]);
+    assert_eq!(fs::read_to_string(&file).unwrap(), v0);
+    assert!(journal.is_empty());
+}
+
+#[test]
+fn test_revert_where_reports_overlapping_edits() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("app.py");
+    let v0 = "client = OpenAI()\n";
+    let v1 = "client = OpenAI(base_url=\"x\")\n";
+    let v2 = "client = OpenAI(base_url=\"y\")\n";
+    fs::write(&file, v2).unwrap();
+    let mut journal = Journal::default();
+    journal.record(dir.path(), &file, Provider::OpenAI, &text_edits(v0, v1));
+    journal.record(dir.path(), &file, Provider::Anthropic, &text_edits(v1, v2));
+
+    let report = journal
+        .revert_where(dir.path(), |e| e.provider == Provider::OpenAI)
+        .unwrap();
+    assert_eq!(report.conflicts, vec!["app.py"]);
+    assert_eq!(fs::read_to_string(&file).unwrap(), v2);
+    assert_eq!(journal.files(), vec!["app.py"]);
+}
+
+#[test]
+fn test_revert_where_selects_files() {
+    let dir = TempDir::new().unwrap();
+    let v0 = "client = OpenAI()\n";
+    let v1 = "client = OpenAI(base_url=\"x\")\n";
+    let mut journal = Journal::default();
+    for name in ["app.py", "legacy.py"] {
+        let file = dir.path().join(name);
+        fs::write(&file, v1).unwrap();
+        journal.record(dir.path(), &file, Provider::OpenAI, &text_edits(v0, v1));
+    }
+
+    let report = journal
+        .revert_where(dir.path(), |e| e.file == "legacy.py")
+        .unwrap();
+    assert_eq!(report.reverted, vec!["legacy.py"]);
+    assert_eq!(
+        fs::read_to_string(dir.path().join("legacy.py")).unwrap(),
+        v0
+    );
+    assert_eq!(fs::read_to_string(dir.path().join("app.py")).unwrap(), v1);
+    assert_eq!(journal.files(), vec!["app.py"]);
+}
@@ -3,11 +3,14 @@
 ///
 /// These tests verify the complete shim generation and injection workflow
 /// to ensure 100% coverage of SDK calls in production environments.
+use std::collections::BTreeMap;
 use std::fs;
 use tempfile::TempDir;
 
 // Import from the main crate
-use promptguard::shim::{ShimGenerator, ShimInjector};
+use promptguard::config::{ProxyUrl, ShimFailMode};
+use promptguard::detector::PROVIDERS;
+use promptguard::shim::{ShimGenerator, ShimInjector, NODE_PRELOAD_REQUIRE};
 use promptguard::types::{Language, Provider};
 
 /// Test that Python shim is generated correctly
@@ -108,6 +111,29 @@ fn test_typescript_shim_generation() {
     assert!(package_json.exists(), "package.json should exist");
 }
 
+/// Test async clients and the module-level `OpenAI` client are patched too
+#[test]
+fn test_python_shim_patches_async_clients() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+
+    let generator = ShimGenerator::new(
+        temp_dir.path(),
+        "https://api.promptguard.co/api/v1".to_string(),
+        "PROMPTGUARD_API_KEY".to_string(),
+        vec![Provider::OpenAI, Provider::Anthropic],
+    );
+    let shim_path = generator
+        .generate_python_shim()
+        .expect("Failed to generate Python shim");
+    let content = fs::read_to_string(&shim_path).expect("Failed to read shim file");
+
+    assert!(content.contains("openai.AsyncOpenAI = PatchedAsyncOpenAI"));
+    assert!(content.contains("anthropic.AsyncAnthropic = PatchedAsyncAnthropic"));
+    assert!(content.contains("openai.base_url = "));
+    // Positional arguments are passed through to the SDK
+    assert!(content.contains("super().__init__(*args, **kwargs)"));
+}
+
 /// Test that multiple shims are generated for multi-language projects
 #[test]
 fn test_multi_language_shim_generation() {
@@ -399,6 +425,125 @@ fn test_typescript_entry_point_detection() {
     );
 }
 
+/// Test TypeScript entry points are injected and cleaned up again
+#[test]
+fn test_typescript_shim_injection_and_removal() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+
+    fs::create_dir_all(temp_dir.path().join("src")).expect("Failed to create src");
+    let entry = temp_dir.path().join("src").join("index.ts");
+    let original = "import OpenAI from 'openai';\n\nconst client = new OpenAI();\n";
+    fs::write(&entry, original).expect("Failed to create index.ts");
+
+    let injector = ShimInjector::new(temp_dir.path());
+    let injected = injector
+        .inject_shims(Language::TypeScript)
+        .expect("Failed to inject shims");
+    assert_eq!(injected, vec![entry.clone()]);
+
+    let content = fs::read_to_string(&entry).expect("Failed to read file");
+    assert!(
+        content.starts_with("// PromptGuard runtime shim - auto-injected\nimport '../.promptguard/promptguard-shim';\n"),
+        "Shim import should be first"
+    );
+
+    let removed = injector
+        .remove_all_injections()
+        .expect("Failed to remove injections");
+    assert_eq!(removed, 1);
+    assert_eq!(
+        fs::read_to_string(&entry).expect("Failed to read file"),
+        original
+    );
+}
+
+/// Test `CommonJS` and ES module shims are generated, with `.js` matching the project type
+#[test]
+fn test_javascript_module_variants() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let generator = ShimGenerator::new(
+        temp_dir.path(),
+        "https://api.promptguard.co/api/v1".to_string(),
+        "PROMPTGUARD_API_KEY".to_string(),
+        vec![Provider::OpenAI, Provider::Anthropic],
+    );
+    let shim_dir = temp_dir.path().join(".promptguard");
+
+    generator
+        .generate_javascript_shims()
+        .expect("Failed to generate shims");
+    let cjs = fs::read_to_string(shim_dir.join("promptguard-shim.cjs")).expect("cjs shim");
+    let esm = fs::read_to_string(shim_dir.join("promptguard-shim.mjs")).expect("mjs shim");
+    assert!(cjs.contains("\"openai\":{"));
+    assert!(cjs.contains("\"@anthropic-ai/sdk\":{"));
+    assert!(!cjs.contains("{{") && !cjs.contains("export "));
+    assert!(esm.contains("export const OpenAI = load(\"OpenAI\");"));
+    assert!(esm.contains("export const Anthropic = load(\"Anthropic\");"));
+    assert_eq!(
+        fs::read_to_string(shim_dir.join("promptguard-shim.js")).expect("js shim"),
+        cjs
+    );
+
+    fs::write(
+        temp_dir.path().join("package.json"),
+        r#"{"name": "app", "type": "module"}"#,
+    )
+    .expect("Failed to create package.json");
+    generator
+        .generate_javascript_shims()
+        .expect("Failed to generate shims");
+    assert_eq!(
+        fs::read_to_string(shim_dir.join("promptguard-shim.js")).expect("js shim"),
+        esm
+    );
+    let package: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(shim_dir.join("package.json")).expect("package.json"),
+    )
+    .expect("valid package.json");
+    assert_eq!(package["type"], "module");
+}
+
+/// Test the Node preload is generated and wired into start scripts
+#[test]
+fn test_node_preload_injection() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let package_json = temp_dir.path().join("package.json");
+    let original = "{\n  \"name\": \"app\",\n  \"scripts\": {\n    \"start\": \"node dist/index.js\",\n    \"test\": \"node test.js\"\n  }\n}\n";
+    fs::write(&package_json, original).expect("Failed to create package.json");
+
+    let generator = ShimGenerator::new(
+        temp_dir.path(),
+        "https://api.promptguard.co/api/v1".to_string(),
+        "PROMPTGUARD_API_KEY".to_string(),
+        vec![Provider::OpenAI, Provider::Anthropic],
+    );
+    let preload = generator
+        .generate_node_preload()
+        .expect("Failed to generate preload");
+    let content = fs::read_to_string(&preload).expect("Failed to read preload");
+    assert!(content.contains("require(\"./promptguard-shim.cjs\")"));
+    assert!(preload.with_file_name("promptguard-shim.cjs").exists());
+
+    let injector = ShimInjector::new(temp_dir.path());
+    let updated = injector
+        .inject_node_preload()
+        .expect("Failed to inject preload");
+    assert_eq!(updated, vec!["start".to_string()]);
+    let content = fs::read_to_string(&package_json).expect("Failed to read package.json");
+    assert!(content.contains(&format!(
+        "\"start\": \"node {NODE_PRELOAD_REQUIRE} dist/index.js\""
+    )));
+    assert!(content.contains("\"test\": \"node test.js\""));
+
+    injector
+        .remove_node_preload()
+        .expect("Failed to remove preload");
+    assert_eq!(
+        fs::read_to_string(&package_json).expect("Failed to read package.json"),
+        original
+    );
+}
+
 /// Test that shim works with all supported providers
 #[test]
 fn test_all_providers_in_shim() {
@@ -455,6 +600,35 @@ fn test_all_providers_in_shim() {
     }
 }
 
+/// Test framework clients (`LangChain`, the `OpenAI` Agents SDK) are patched along
+/// with the SDKs of the configured providers
+#[test]
+fn test_python_framework_patches() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+
+    let generator = ShimGenerator::new(
+        temp_dir.path(),
+        "https://api.promptguard.co/api/v1".to_string(),
+        "PROMPTGUARD_API_KEY".to_string(),
+        vec![Provider::OpenAI],
+    );
+    let shim_path = generator
+        .generate_python_shim()
+        .expect("Failed to generate shim");
+    let content = fs::read_to_string(&shim_path).expect("Failed to read shim");
+
+    assert!(content.contains(
+        "_patch_init(getattr(langchain_openai, \"ChatOpenAI\", None), \"OpenAI\", (\"base_url\", \"openai_api_base\",), (\"api_key\", \"openai_api_key\",))"
+    ));
+    assert!(content.contains("getattr(agents, \"OpenAIProvider\", None)"));
+    assert!(content.contains("    if _shim_langchain_openai():"));
+    assert!(content.contains("    if _shim_openai_agents():"));
+    // Anthropic is not configured, so neither is its LangChain integration
+    assert!(!content.contains("langchain_anthropic"));
+    // SDKs built by libraries with `base_url=None` are routed too
+    assert!(content.contains("if kwargs.get(param_name) is not None:"));
+}
+
 /// Test shim with custom proxy URL
 #[test]
 fn test_custom_proxy_url() {
@@ -483,3 +657,240 @@ fn test_custom_proxy_url() {
         "Shim should use custom API key var"
     );
 }
+
+/// Test shims route each provider through its own proxy when configured per provider
+#[test]
+fn test_per_provider_proxy_urls() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let proxy_url = ProxyUrl::PerProvider(BTreeMap::from([
+        (
+            "default".to_string(),
+            "https://us.promptguard.example/api/v1".to_string(),
+        ),
+        (
+            "anthropic".to_string(),
+            "https://eu.promptguard.example/api/v1".to_string(),
+        ),
+    ]));
+
+    let generator = ShimGenerator::new(
+        temp_dir.path(),
+        proxy_url,
+        "PROMPTGUARD_API_KEY".to_string(),
+        vec![Provider::OpenAI, Provider::Anthropic],
+    );
+
+    let python = fs::read_to_string(generator.generate_python_shim().expect("Python shim"))
+        .expect("Failed to read shim");
+    assert!(python.contains("\"PROMPTGUARD_PROXY_URL\", \"https://us.promptguard.example/api/v1\""));
+    assert!(python.contains("{\"Anthropic\":\"https://eu.promptguard.example/api/v1\"}"));
+
+    let ts = fs::read_to_string(generator.generate_typescript_shim().expect("TS shim"))
+        .expect("Failed to read shim");
+    assert!(ts.contains("{\"Anthropic\":\"https://eu.promptguard.example/api/v1\"}"));
+    assert!(!ts.contains("{{PROVIDER_PROXY_URLS}}"));
+}
+
+/// Test shims read overridden providers' API keys from their own variables
+#[test]
+fn test_api_key_var_overrides() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let generator = ShimGenerator::new(
+        temp_dir.path(),
+        "https://api.promptguard.co/api/v1".to_string(),
+        "PROMPTGUARD_API_KEY".to_string(),
+        vec![Provider::OpenAI, Provider::Anthropic],
+    )
+    .with_api_key_var_overrides(BTreeMap::from([(
+        "anthropic".to_string(),
+        "ANTHROPIC_API_KEY".to_string(),
+    )]));
+
+    let python = fs::read_to_string(generator.generate_python_shim().expect("Python shim"))
+        .expect("Failed to read shim");
+    assert!(python
+        .contains("PROVIDER_API_KEY_VARS: Dict[str, str] = {\"Anthropic\":\"ANTHROPIC_API_KEY\"}"));
+
+    let ts = fs::read_to_string(generator.generate_typescript_shim().expect("TS shim"))
+        .expect("Failed to read shim");
+    assert!(ts.contains(
+        "PROVIDER_API_KEY_VARS: Record<string, string> = {\"Anthropic\":\"ANTHROPIC_API_KEY\"}"
+    ));
+}
+
+/// Test the configured fail mode is generated into every shim
+#[test]
+fn test_fail_mode_in_shims() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let generator = ShimGenerator::new(
+        temp_dir.path(),
+        "https://api.promptguard.co/api/v1".to_string(),
+        "PROMPTGUARD_API_KEY".to_string(),
+        vec![Provider::OpenAI],
+    );
+    let python = fs::read_to_string(generator.generate_python_shim().expect("Python shim"))
+        .expect("Failed to read shim");
+    assert!(python.contains("os.environ.get(\"PROMPTGUARD_FAIL_MODE\", \"closed\")"));
+
+    let generator = generator.with_fail_mode(ShimFailMode::Open);
+    let python = fs::read_to_string(generator.generate_python_shim().expect("Python shim"))
+        .expect("Failed to read shim");
+    assert!(python.contains("os.environ.get(\"PROMPTGUARD_FAIL_MODE\", \"open\")"));
+    let ts = fs::read_to_string(generator.generate_typescript_shim().expect("TS shim"))
+        .expect("Failed to read shim");
+    assert!(ts.contains("process.env.PROMPTGUARD_FAIL_MODE || \"open\""));
+    let cjs = fs::read_to_string(temp_dir.path().join(".promptguard/promptguard-shim.cjs"))
+        .expect("Failed to read CommonJS shim");
+    assert!(cjs.contains("process.env.PROMPTGUARD_FAIL_MODE || \"open\""));
+    assert!(!cjs.contains("{{FAIL_MODE}}") && !python.contains("{{FAIL_MODE}}"));
+}
+
+/// Test local logging is off unless turned on, and the log stays out of git
+#[test]
+fn test_shim_log_option() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let generator = ShimGenerator::new(
+        temp_dir.path(),
+        "https://api.promptguard.co/api/v1".to_string(),
+        "PROMPTGUARD_API_KEY".to_string(),
+        vec![Provider::OpenAI],
+    );
+    let python = fs::read_to_string(generator.generate_python_shim().expect("Python shim"))
+        .expect("Failed to read shim");
+    assert!(python.contains("os.environ.get(\"PROMPTGUARD_SHIM_LOG\", \"false\")"));
+
+    let generator = generator.with_shim_log(true);
+    generator
+        .generate_shims(&[Language::Python, Language::TypeScript])
+        .expect("Failed to generate shims");
+    let python = fs::read_to_string(generator.python_shim_path()).expect("Failed to read shim");
+    assert!(python.contains("os.environ.get(\"PROMPTGUARD_SHIM_LOG\", \"true\")"));
+    assert!(python.contains("_log_call(provider, proxy_url, \"routed\")"));
+    let cjs = fs::read_to_string(temp_dir.path().join(".promptguard/promptguard-shim.cjs"))
+        .expect("Failed to read CommonJS shim");
+    assert!(cjs.contains("process.env.PROMPTGUARD_SHIM_LOG || \"true\""));
+    let gitignore = fs::read_to_string(temp_dir.path().join(".promptguard/.gitignore"))
+        .expect("Failed to read .gitignore");
+    assert!(gitignore.lines().any(|l| l == "shim.log"));
+}
+
+/// Test shim code comes from the provider registry, so every registry entry
+/// with shim metadata is patched without a hand-written template
+#[test]
+fn test_shims_follow_provider_registry() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let providers: Vec<Provider> = PROVIDERS.iter().map(|info| info.provider).collect();
+
+    let generator = ShimGenerator::new(
+        temp_dir.path(),
+        "https://api.promptguard.co/api/v1".to_string(),
+        "PROMPTGUARD_API_KEY".to_string(),
+        providers,
+    );
+    let python = fs::read_to_string(generator.generate_python_shim().expect("Python shim"))
+        .expect("Failed to read Python shim");
+    generator
+        .generate_typescript_shim()
+        .expect("TypeScript shim");
+    let cjs = fs::read_to_string(temp_dir.path().join(".promptguard/promptguard-shim.cjs"))
+        .expect("Failed to read CommonJS shim");
+
+    for info in PROVIDERS {
+        let name = info.provider.display_name();
+        for class in info.py_shim_classes {
+            assert!(
+                python.contains(&format!("{}.{class} = Patched{class}", info.py_shim_module)),
+                "{name}: Python shim should wrap {class}"
+            );
+        }
+        if info.py_shim_module.is_empty() {
+            assert!(python.contains(&format!("# {name}: not covered")));
+        }
+        let package = format!("\"{}\":{{", info.npm_packages[0]);
+        assert_eq!(
+            cjs.contains(&package),
+            info.ts_shim,
+            "{name}: JavaScript target"
+        );
+    }
+    assert!(python.contains("groq.AsyncGroq = PatchedAsyncGroq"));
+    assert!(
+        !python.contains("{{"),
+        "Python shim has unfilled placeholders"
+    );
+}
+
+/// Test the Bun preload resolves each SDK to a wrapper re-exporting it
+#[test]
+fn test_bun_preload_generation() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+
+    let generator = ShimGenerator::new(
+        temp_dir.path(),
+        "https://api.promptguard.co/api/v1".to_string(),
+        "PROMPTGUARD_API_KEY".to_string(),
+        vec![Provider::OpenAI, Provider::Anthropic],
+    );
+    let preload_path = generator
+        .generate_bun_preload()
+        .expect("Failed to generate Bun preload");
+    let preload = fs::read_to_string(&preload_path).expect("Failed to read Bun preload");
+    assert!(preload.contains("import { plugin } from \"bun\";"));
+    assert!(preload.contains("filter: /^(?:openai|@anthropic-ai\\/sdk)$/"));
+    assert!(preload.contains("\"@anthropic-ai/sdk\":\"bun/anthropic-ai__sdk.mjs\""));
+
+    let wrapper = fs::read_to_string(temp_dir.path().join(".promptguard/bun/openai.mjs"))
+        .expect("Failed to read Bun wrapper");
+    assert!(wrapper.contains("export * from \"openai\";"));
+    assert!(wrapper.contains("export const OpenAI = shim.wrapClient(sdk.OpenAI, \"openai\");"));
+    // Wrappers rely on the CommonJS shim exposing its wrapper
+    let cjs = fs::read_to_string(temp_dir.path().join(".promptguard/promptguard-shim.cjs"))
+        .expect("Failed to read CommonJS shim");
+    assert!(cjs.contains("\"wrapClient\""));
+    assert!(!preload.contains("{{") && !wrapper.contains("{{"));
+}
+
+/// Test the Deno import map routes SDK specifiers to wrappers around the real SDK
+#[test]
+fn test_deno_shim_generation() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    fs::write(
+        temp_dir.path().join("deno.json"),
+        r#"{"imports": {"@anthropic-ai/sdk": "npm:@anthropic-ai/sdk@^0.20"}}"#,
+    )
+    .expect("Failed to write deno.json");
+
+    let generator = ShimGenerator::new(
+        temp_dir.path(),
+        "https://api.promptguard.co/api/v1".to_string(),
+        "PROMPTGUARD_API_KEY".to_string(),
+        vec![Provider::OpenAI, Provider::Anthropic],
+    );
+    let files = generator
+        .generate_deno_shim()
+        .expect("Failed to generate Deno shim");
+    assert_eq!(files.len(), 2);
+
+    let shim = fs::read_to_string(&files[0]).expect("Failed to read Deno shim");
+    assert!(shim.contains("Deno.env.get(name)"));
+    assert!(shim.contains("\"@anthropic-ai/sdk\":{"));
+
+    let map: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&files[1]).expect("Failed to read import map"))
+            .expect("Import map is not JSON");
+    assert_eq!(map["imports"]["npm:openai"], "./deno/openai.ts");
+    assert_eq!(
+        map["imports"]["@anthropic-ai/sdk"],
+        "./deno/anthropic-ai__sdk.ts"
+    );
+    assert_eq!(map["scopes"]["./deno/"]["openai"], "npm:openai");
+    assert_eq!(
+        map["scopes"]["./deno/"]["@anthropic-ai/sdk"],
+        "npm:@anthropic-ai/sdk@^0.20"
+    );
+
+    let wrapper = fs::read_to_string(temp_dir.path().join(".promptguard/deno/openai.ts"))
+        .expect("Failed to read Deno wrapper");
+    assert!(wrapper.contains("export const OpenAI = wrapClient(sdk.OpenAI, \"openai\");"));
+    assert!(!shim.contains("{{") && !wrapper.contains("{{"));
+}
@@ -0,0 +1,75 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+/// Integration tests for staged multi-file transforms
+///
+/// These tests verify that nothing is written before `commit` and that a
+/// failed write puts back, or removes, the files already written.
+use std::fs;
+use tempfile::TempDir;
+
+use promptguard::transformer::StagedChanges;
+use promptguard::types::Provider;
+
+const PROXY: &str = "https://api.promptguard.co/api/v1";
+
+#[test]
+fn test_nothing_written_until_commit() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("app.py");
+    let original = "from openai import OpenAI\nclient = OpenAI()\n";
+    fs::write(&file, original).unwrap();
+
+    let mut staged = StagedChanges::new();
+    assert!(staged
+        .transform(&file, Provider::OpenAI, PROXY, "PROMPTGUARD_API_KEY")
+        .unwrap());
+    assert_eq!(fs::read_to_string(&file).unwrap(), original);
+    assert_eq!(staged.changed_files(), vec![file.as_path()]);
+
+    let committed = staged.commit().unwrap();
+    assert_eq!(committed.len(), 1);
+    assert!(fs::read_to_string(&file).unwrap().contains(PROXY));
+}
+
+#[test]
+fn test_failed_write_rolls_back() {
+    let dir = TempDir::new().unwrap();
+    let first = dir.path().join("a.py");
+    let second = dir.path().join("b.py");
+    let original = "from openai import OpenAI\nclient = OpenAI()\n";
+    fs::write(&first, original).unwrap();
+    fs::write(&second, original).unwrap();
+
+    let mut staged = StagedChanges::new();
+    for file in [&first, &second] {
+        staged
+            .transform(file, Provider::OpenAI, PROXY, "PROMPTGUARD_API_KEY")
+            .unwrap();
+    }
+    // A directory can't be replaced by a file, so the second write fails
+    fs::remove_file(&second).unwrap();
+    fs::create_dir(&second).unwrap();
+
+    assert!(staged.commit().is_err());
+    assert_eq!(fs::read_to_string(&first).unwrap(), original);
+}
+
+#[test]
+fn test_failed_write_removes_created_files() {
+    let dir = TempDir::new().unwrap();
+    let env = dir.path().join(".env");
+    let blocked = dir.path().join("z.py");
+    fs::write(&blocked, "key = 'sk'\n").unwrap();
+
+    let mut staged = StagedChanges::new();
+    assert!(staged
+        .replace(&env, "OPENAI_API_KEY=sk\n".to_string())
+        .unwrap());
+    assert!(staged
+        .replace(&blocked, "key = os.environ['OPENAI_API_KEY']\n".to_string())
+        .unwrap());
+    fs::remove_file(&blocked).unwrap();
+    fs::create_dir(&blocked).unwrap();
+
+    assert!(staged.commit().is_err());
+    assert!(!env.exists());
+}
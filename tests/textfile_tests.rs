@@ -0,0 +1,76 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+/// Integration tests for reading and writing source files in their own format
+///
+/// These tests verify that line endings, byte-order marks, UTF-16 and file
+/// permissions survive a decode/encode round trip and an atomic write.
+use std::fs;
+use tempfile::TempDir;
+
+use promptguard::textfile::{write_atomic, Encoding, TextFormat};
+
+#[test]
+fn test_crlf_bom_round_trip() {
+    let raw = b"\xEF\xBB\xBFfrom openai import OpenAI\r\nclient = OpenAI()\r\n";
+    let (format, text) = TextFormat::decode(raw).unwrap();
+    assert_eq!(format.encoding, Encoding::Utf8Bom);
+    assert!(format.crlf);
+    assert_eq!(text, "from openai import OpenAI\nclient = OpenAI()\n");
+
+    let edited = text.replace("OpenAI()", "OpenAI(\n    base_url=\"x\"\n)");
+    assert_eq!(
+        format.encode(&edited),
+        b"\xEF\xBB\xBFfrom openai import OpenAI\r\nclient = OpenAI(\r\n    base_url=\"x\"\r\n)\r\n"
+    );
+}
+
+#[test]
+fn test_lf_file_keeps_stray_carriage_returns() {
+    let raw = b"a\nb\r\nc\n";
+    let (format, text) = TextFormat::decode(raw).unwrap();
+    assert_eq!(format, TextFormat::default());
+    assert_eq!(format.encode(&text), raw);
+}
+
+#[test]
+fn test_utf16_round_trip() {
+    let raw: Vec<u8> = [0xFF, 0xFE]
+        .into_iter()
+        .chain("x = 1\r\n".encode_utf16().flat_map(u16::to_le_bytes))
+        .collect();
+    let (format, text) = TextFormat::decode(&raw).unwrap();
+    assert_eq!(format.encoding, Encoding::Utf16Le);
+    assert_eq!(text, "x = 1\n");
+    assert_eq!(format.encode(&text), raw);
+}
+
+#[test]
+fn test_write_atomic_replaces_contents() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("app.py");
+    fs::write(&path, "old\n").unwrap();
+
+    write_atomic(&path, b"new\n").unwrap();
+
+    assert_eq!(fs::read(&path).unwrap(), b"new\n");
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_write_atomic_keeps_mode_and_symlinks() {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("run.py");
+    fs::write(&path, "#!/usr/bin/env python3\n").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    let link = dir.path().join("link.py");
+    symlink(&path, &link).unwrap();
+
+    write_atomic(&link, b"print(1)\n").unwrap();
+
+    assert!(link.is_symlink());
+    assert_eq!(fs::read(&path).unwrap(), b"print(1)\n");
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o755);
+}